@@ -0,0 +1,150 @@
+//! Minimal reader and writer for pcap (not pcapng) capture files, shared by the examples
+
+// not all examples use all functions
+#![allow(dead_code)]
+
+use std::io::{self, Write};
+
+/// A capture file
+pub struct PcapFile<'a> {
+    /// Raw global header, copied when writing a new capture
+    pub header: &'a [u8],
+    pub linktype: u32,
+    le: bool,
+    records: &'a [u8],
+}
+
+/// A packet of a capture file
+pub struct PcapPacket<'a> {
+    /// Number of the packet in the capture, starting from 1
+    pub number: usize,
+    /// Raw record (header and data), copied when writing a new capture
+    pub record: &'a [u8],
+    pub data: &'a [u8],
+}
+
+impl<'a> PcapFile<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Self, String> {
+        if data.len() < 24 {
+            return Err("capture file is too short".to_string());
+        }
+        let le = match data[..4] {
+            [0xd4, 0xc3, 0xb2, 0xa1] | [0x4d, 0x3c, 0xb2, 0xa1] => true,
+            [0xa1, 0xb2, 0xc3, 0xd4] | [0xa1, 0xb2, 0x3c, 0x4d] => false,
+            _ => return Err("not a pcap file (pcapng is not supported)".to_string()),
+        };
+        let linktype = read_u32(&data[20..24], le);
+        match linktype {
+            0 | 1 | 12 | 101 | 113 => (),
+            _ => return Err(format!("unsupported link type {}", linktype)),
+        }
+        Ok(PcapFile {
+            header: &data[..24],
+            linktype,
+            le,
+            records: &data[24..],
+        })
+    }
+
+    /// Iterate over packets. A truncated last packet is ignored, with a warning.
+    pub fn packets(&self) -> impl Iterator<Item = PcapPacket<'a>> {
+        let le = self.le;
+        let mut i = self.records;
+        let mut number = 0;
+        std::iter::from_fn(move || {
+            if i.len() < 16 {
+                return None;
+            }
+            let caplen = read_u32(&i[8..12], le) as usize;
+            if i.len() < 16 + caplen {
+                eprintln!("warning: truncated capture file");
+                i = &[];
+                return None;
+            }
+            let (record, rem) = i.split_at(16 + caplen);
+            i = rem;
+            number += 1;
+            Some(PcapPacket {
+                number,
+                record,
+                data: &record[16..],
+            })
+        })
+    }
+
+    /// Return the payload of a UDP packet to or from ports 161 or 162
+    pub fn snmp_payload<'b>(&self, packet: &PcapPacket<'b>) -> Option<&'b [u8]> {
+        let data = packet.data;
+        let ip = match self.linktype {
+            // Ethernet, skipping VLAN tags
+            1 => {
+                let mut offset = 12;
+                while data.len() >= offset + 2
+                    && (data[offset..offset + 2] == [0x81, 0x00]
+                        || data[offset..offset + 2] == [0x88, 0xa8])
+                {
+                    offset += 4;
+                }
+                data.get(offset + 2..)?
+            }
+            // BSD loopback
+            0 => data.get(4..)?,
+            // Linux cooked capture
+            113 => data.get(16..)?,
+            // raw IP
+            _ => data,
+        };
+        udp_payload(ip)
+    }
+}
+
+/// Write a capture file, with the global header of `file` and the given packets
+pub fn write_pcap<'a, W: Write>(
+    out: &mut W,
+    file: &PcapFile,
+    packets: impl IntoIterator<Item = &'a PcapPacket<'a>>,
+) -> io::Result<usize> {
+    out.write_all(file.header)?;
+    let mut count = 0;
+    for packet in packets {
+        out.write_all(packet.record)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn read_u32(b: &[u8], le: bool) -> u32 {
+    let b = [b[0], b[1], b[2], b[3]];
+    if le {
+        u32::from_le_bytes(b)
+    } else {
+        u32::from_be_bytes(b)
+    }
+}
+
+fn udp_payload(ip: &[u8]) -> Option<&[u8]> {
+    let udp = match ip.first()? >> 4 {
+        4 => {
+            let ihl = usize::from(ip[0] & 0x0f) * 4;
+            if *ip.get(9)? != 17 {
+                return None;
+            }
+            ip.get(ihl..)?
+        }
+        6 => {
+            if *ip.get(6)? != 17 {
+                return None;
+            }
+            ip.get(40..)?
+        }
+        _ => return None,
+    };
+    let src = u16::from_be_bytes([*udp.first()?, *udp.get(1)?]);
+    let dst = u16::from_be_bytes([*udp.get(2)?, *udp.get(3)?]);
+    let is_snmp = |port| port == 161 || port == 162;
+    if !is_snmp(src) && !is_snmp(dst) {
+        return None;
+    }
+    let len = usize::from(u16::from_be_bytes([*udp.get(4)?, *udp.get(5)?]));
+    udp.get(8..len.min(udp.len()))
+}
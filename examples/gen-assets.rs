@@ -0,0 +1,303 @@
+//! Generate the test assets from their descriptions in `assets/assets.txt`
+//!
+//! Usage: `cargo run --example gen-assets -- [--check]`
+//!
+//! Each asset is described by a section, starting with the file name in brackets, followed by
+//! `key = value` lines. Lines starting with `#` are comments. For example:
+//!
+//! ```text
+//! [snmpv1_req.bin]
+//! version = 0
+//! community = public
+//! pdu = get
+//! request_id = 38
+//! varbind = 1.3.6.1.2.1.1.2.0 null
+//! ```
+//!
+//! Keys:
+//!   - `version`: 0 (SNMPv1), 1 (SNMPv2c) or 3 (SNMPv3, using USM)
+//!   - `community` (SNMPv1 and SNMPv2c)
+//!   - `msg_id`, `msg_max_size`, `msg_flags`, `engine_id`, `engine_boots`, `engine_time`,
+//!     `user`, `auth_params`, `priv_params`, `context_engine_id`, `context_name` (SNMPv3)
+//!   - `encrypted_data` (SNMPv3): hex-encoded encrypted scoped PDU, replaces the PDU
+//!   - `pdu`: `get`, `getnext`, `response`, `set`, `trap`, `getbulk`, `inform`, `trapv2` or
+//!     `report`
+//!   - `request_id`, `error_status`, `error_index`
+//!   - `non_repeaters`, `max_repetitions` (GetBulkRequest)
+//!   - `enterprise`, `agent_addr`, `generic_trap`, `specific_trap`, `timestamp` (SNMPv1 trap)
+//!   - `varbind`: `<oid> <type> [value]`, where type is `null`, `int`, `string`, `hex` (OCTET
+//!     STRING, hex-encoded), `oid`, `ip`, `counter32`, `gauge32`, `timeticks`, `counter64`,
+//!     `opaque` (hex-encoded), `nosuchobject`, `nosuchinstance` or `endofmibview`
+//!   - `varbinds`: `<count> <oid> <type> [value]`, adds `count` variables, replacing `{i}` by the
+//!     index (starting from 1) in the OID and the value
+//!
+//! Indented lines continue the value of the previous line, and hex-encoded values can contain
+//! whitespace. With `--check`, assets are not written, but compared to the existing files.
+
+use asn1_rs::Oid;
+use snmp_parser::encode::SnmpEncode;
+use snmp_parser::*;
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::Path;
+use std::str::FromStr;
+
+struct Description {
+    name: String,
+    fields: Vec<(String, String)>,
+}
+
+impl Description {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn get_or<'a>(&'a self, key: &str, default: &'a str) -> &'a str {
+        self.get(key).unwrap_or(default)
+    }
+
+    fn number<T: FromStr>(&self, key: &str, default: T) -> Result<T, String> {
+        match self.get(key) {
+            Some(v) => v
+                .parse()
+                .map_err(|_| format!("invalid number for {}: {}", key, v)),
+            None => Ok(default),
+        }
+    }
+
+    fn bytes(&self, key: &str) -> Result<&'static [u8], String> {
+        decode_hex(self.get_or(key, ""))
+    }
+}
+
+fn parse_descriptions(text: &str) -> Result<Vec<Description>, String> {
+    let mut descriptions: Vec<Description> = Vec::new();
+    for (idx, raw_line) in text.lines().enumerate() {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // indented lines continue the previous value
+        if raw_line.starts_with(char::is_whitespace) {
+            match descriptions
+                .last_mut()
+                .and_then(|desc| desc.fields.last_mut())
+            {
+                Some((_, value)) => {
+                    value.push(' ');
+                    value.push_str(line);
+                    continue;
+                }
+                None => return Err(format!("line {}: invalid line", idx + 1)),
+            }
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            descriptions.push(Description {
+                name: name.to_string(),
+                fields: Vec::new(),
+            });
+            continue;
+        }
+        match (line.split_once('='), descriptions.last_mut()) {
+            (Some((key, value)), Some(desc)) => {
+                let field = (key.trim().to_string(), value.trim().to_string());
+                desc.fields.push(field);
+            }
+            _ => return Err(format!("line {}: invalid line", idx + 1)),
+        }
+    }
+    Ok(descriptions)
+}
+
+/// Decode a hex string. Memory is leaked, so values can be borrowed by messages until the end of
+/// the (short-lived) program.
+fn decode_hex(s: &str) -> Result<&'static [u8], String> {
+    let digits: Vec<char> = s.chars().filter(|c| !c.is_whitespace()).collect();
+    let bytes = digits
+        .chunks(2)
+        .map(|pair| {
+            let pair: String = pair.iter().collect();
+            u8::from_str_radix(&pair, 16).map_err(|_| format!("invalid hex value {}", s))
+        })
+        .collect::<Result<Vec<u8>, String>>()?;
+    Ok(Box::leak(bytes.into_boxed_slice()))
+}
+
+fn parse_oid(s: &str) -> Result<Oid<'static>, String> {
+    Oid::from_str(s).map_err(|_| format!("invalid OID {}", s))
+}
+
+fn parse_number<T: FromStr>(s: &str) -> Result<T, String> {
+    s.parse().map_err(|_| format!("invalid number {}", s))
+}
+
+fn parse_varbind(s: &str) -> Result<SnmpVariable<'static>, String> {
+    let mut parts = s.splitn(3, ' ');
+    let oid = parse_oid(parts.next().unwrap_or(""))?;
+    let ty = parts.next().unwrap_or("");
+    let value = parts.next().unwrap_or("").trim();
+    let val = match ty {
+        "null" => VarBindValue::Unspecified,
+        "nosuchobject" => VarBindValue::NoSuchObject,
+        "nosuchinstance" => VarBindValue::NoSuchInstance,
+        "endofmibview" => VarBindValue::EndOfMibView,
+        _ => VarBindValue::Value(match ty {
+            "int" => ObjectSyntax::Number(parse_number(value)?),
+            "string" => ObjectSyntax::String(Box::leak(value.as_bytes().into())),
+            "hex" => ObjectSyntax::String(decode_hex(value)?),
+            "oid" => ObjectSyntax::Object(parse_oid(value)?),
+            "ip" => ObjectSyntax::IpAddress(NetworkAddress::IPv4(parse_number(value)?)),
+            "counter32" => ObjectSyntax::Counter32(parse_number(value)?),
+            "gauge32" => ObjectSyntax::Gauge32(parse_number(value)?),
+            "timeticks" => ObjectSyntax::TimeTicks(parse_number(value)?),
+            "counter64" => ObjectSyntax::Counter64(parse_number(value)?),
+            "opaque" => ObjectSyntax::Opaque(decode_hex(value)?),
+            _ => return Err(format!("invalid varbind type {}", ty)),
+        }),
+    };
+    Ok(SnmpVariable { oid, val })
+}
+
+fn build_varbinds(desc: &Description) -> Result<Vec<SnmpVariable<'static>>, String> {
+    let mut var = Vec::new();
+    for (key, value) in &desc.fields {
+        match key.as_str() {
+            "varbind" => var.push(parse_varbind(value)?),
+            "varbinds" => {
+                let (count, template) = value.split_once(' ').unwrap_or((value, ""));
+                let count: usize = parse_number(count)?;
+                for i in 1..=count {
+                    var.push(parse_varbind(&template.replace("{i}", &i.to_string()))?);
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(var)
+}
+
+fn build_pdu(desc: &Description) -> Result<SnmpPdu<'static>, String> {
+    let pdu_type = match desc.get_or("pdu", "") {
+        "get" => PduType::GetRequest,
+        "getnext" => PduType::GetNextRequest,
+        "response" => PduType::Response,
+        "set" => PduType::SetRequest,
+        "trap" => PduType::TrapV1,
+        "getbulk" => PduType::GetBulkRequest,
+        "inform" => PduType::InformRequest,
+        "trapv2" => PduType::TrapV2,
+        "report" => PduType::Report,
+        s => return Err(format!("invalid PDU type {:?}", s)),
+    };
+    let var = build_varbinds(desc)?;
+    let pdu = match pdu_type {
+        PduType::TrapV1 => SnmpPdu::TrapV1(SnmpTrapPdu {
+            enterprise: parse_oid(desc.get_or("enterprise", "1.3.6.1.4.1"))?,
+            agent_addr: NetworkAddress::IPv4(desc.number("agent_addr", Ipv4Addr::UNSPECIFIED)?),
+            generic_trap: TrapType(desc.number("generic_trap", 0)?),
+            specific_trap: desc.number("specific_trap", 0)?,
+            timestamp: desc.number("timestamp", 0)?,
+            var,
+        }),
+        PduType::GetBulkRequest => SnmpPdu::Bulk(SnmpBulkPdu {
+            req_id: desc.number("request_id", 0)?,
+            non_repeaters: desc.number("non_repeaters", 0)?,
+            max_repetitions: desc.number("max_repetitions", 0)?,
+            var,
+        }),
+        _ => SnmpPdu::Generic(SnmpGenericPdu {
+            pdu_type,
+            req_id: desc.number("request_id", 0)?,
+            err: ErrorStatus(desc.number("error_status", 0)?),
+            err_index: desc.number("error_index", 0)?,
+            var,
+        }),
+    };
+    Ok(pdu)
+}
+
+fn build_asset(desc: &Description) -> Result<Vec<u8>, String> {
+    let version = desc.number("version", 1)?;
+    let bytes = match version {
+        0 | 1 => SnmpMessage {
+            version,
+            community: desc.get_or("community", "public").to_string(),
+            pdu: build_pdu(desc)?,
+        }
+        .encode(),
+        3 => {
+            let data = match desc.get("encrypted_data") {
+                Some(data) => ScopedPduData::Encrypted(decode_hex(data)?),
+                None => ScopedPduData::Plaintext(ScopedPdu {
+                    ctx_engine_id: desc.bytes("context_engine_id")?,
+                    ctx_engine_name: desc.get_or("context_name", "").as_bytes(),
+                    data: build_pdu(desc)?,
+                }),
+            };
+            SnmpV3Message {
+                version,
+                header_data: HeaderData {
+                    msg_id: desc.number("msg_id", 0)?,
+                    msg_max_size: desc.number("msg_max_size", 65507)?,
+                    msg_flags: desc.number("msg_flags", 4)?,
+                    msg_security_model: SecurityModel::USM,
+                },
+                security_params: SecurityParameters::USM(UsmSecurityParameters {
+                    msg_authoritative_engine_id: desc.bytes("engine_id")?,
+                    msg_authoritative_engine_boots: desc.number("engine_boots", 0)?,
+                    msg_authoritative_engine_time: desc.number("engine_time", 0)?,
+                    msg_user_name: desc.get_or("user", "").to_string(),
+                    msg_authentication_parameters: desc.bytes("auth_params")?,
+                    msg_privacy_parameters: desc.bytes("priv_params")?,
+                }),
+                security_params_raw: b"",
+                data,
+            }
+            .encode()
+        }
+        _ => return Err(format!("unsupported version {}", version)),
+    };
+    // check that the asset can be parsed
+    match parse_snmp_generic_message(&bytes) {
+        Ok(([], _)) => Ok(bytes),
+        Ok(_) => Err("trailing bytes after encoded message".to_string()),
+        Err(e) => Err(format!("parsing encoded message failed: {:?}", e)),
+    }
+}
+
+fn main() {
+    let check = std::env::args().any(|arg| arg == "--check");
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+    let text = fs::read_to_string(dir.join("assets.txt")).expect("cannot read assets.txt");
+    let descriptions = parse_descriptions(&text).unwrap_or_else(|e| {
+        eprintln!("error: assets.txt: {}", e);
+        std::process::exit(1);
+    });
+    let mut failed = false;
+    for desc in &descriptions {
+        let bytes = match build_asset(desc) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                eprintln!("error: {}: {}", desc.name, e);
+                failed = true;
+                continue;
+            }
+        };
+        let path = dir.join(&desc.name);
+        if check {
+            if fs::read(&path).ok().as_ref() != Some(&bytes) {
+                eprintln!("error: {} is not up to date", desc.name);
+                failed = true;
+            }
+        } else {
+            fs::write(&path, &bytes).expect("cannot write asset");
+            println!("{} ({} bytes)", desc.name, bytes.len());
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,363 @@
+//! Generate a seed corpus and a dictionary for the fuzz targets
+//!
+//! Usage: `cargo run --example gen-corpus -- <corpus directory> <dictionary file>`
+//!
+//! For example, from the `fuzz` directory:
+//!
+//! ```text
+//! cargo run --example gen-corpus -- corpus/fuzzer_snmp_generic snmp.dict
+//! cargo fuzz run fuzzer_snmp_generic -- -dict=snmp.dict
+//! ```
+
+use asn1_rs::{oid, Any, BitString, Class, Header, Length, Oid, Tag};
+use snmp_parser::encode::SnmpEncode;
+use snmp_parser::trap::SNMP_TRAP_OID;
+use snmp_parser::*;
+use std::borrow::Cow;
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::Path;
+
+#[rustfmt::skip]
+const SYS_DESCR: Oid<'static> = oid!(1.3.6.1.2.1.1.1.0);
+#[rustfmt::skip]
+const SYS_UPTIME: Oid<'static> = oid!(1.3.6.1.2.1.1.3.0);
+#[rustfmt::skip]
+const ENTERPRISE: Oid<'static> = oid!(1.3.6.1.4.1.4.1.2.21);
+#[rustfmt::skip]
+const MIB2: Oid<'static> = oid!(1.3.6.1.2.1);
+#[rustfmt::skip]
+const ENTERPRISES: Oid<'static> = oid!(1.3.6.1.4.1);
+
+fn values() -> Vec<(&'static str, VarBindValue<'static>)> {
+    let unknown = Header::new(Class::Application, false, Tag(9), Length::Definite(2));
+    let boolean = Header::new(Class::Universal, false, Tag::Boolean, Length::Definite(1));
+    vec![
+        ("number", VarBindValue::Value(ObjectSyntax::Number(-1))),
+        (
+            "number-max",
+            VarBindValue::Value(ObjectSyntax::Number(i32::MAX)),
+        ),
+        (
+            "number-min",
+            VarBindValue::Value(ObjectSyntax::Number(i32::MIN)),
+        ),
+        (
+            "string",
+            VarBindValue::Value(ObjectSyntax::String(b"Linux")),
+        ),
+        (
+            "object",
+            VarBindValue::Value(ObjectSyntax::Object(ENTERPRISE)),
+        ),
+        (
+            "bitstring",
+            VarBindValue::Value(ObjectSyntax::BitString(BitString {
+                unused_bits: 3,
+                data: Cow::Borrowed(&[0xa8]),
+            })),
+        ),
+        ("empty", VarBindValue::Value(ObjectSyntax::Empty)),
+        ("real", VarBindValue::Value(ObjectSyntax::Real(-2.5))),
+        (
+            "real-infinity",
+            VarBindValue::Value(ObjectSyntax::Real(f64::INFINITY)),
+        ),
+        (
+            "enumerated",
+            VarBindValue::Value(ObjectSyntax::Enumerated(3)),
+        ),
+        (
+            "ipaddress",
+            VarBindValue::Value(ObjectSyntax::IpAddress(NetworkAddress::IPv4(
+                Ipv4Addr::new(192, 168, 0, 1),
+            ))),
+        ),
+        (
+            "counter32",
+            VarBindValue::Value(ObjectSyntax::Counter32(u32::MAX)),
+        ),
+        ("gauge32", VarBindValue::Value(ObjectSyntax::Gauge32(0x80))),
+        (
+            "timeticks",
+            VarBindValue::Value(ObjectSyntax::TimeTicks(123456)),
+        ),
+        (
+            "opaque",
+            VarBindValue::Value(ObjectSyntax::Opaque(&[0x9f, 0x78, 0x04, 0, 0, 0, 0])),
+        ),
+        (
+            "nsap",
+            VarBindValue::Value(ObjectSyntax::NsapAddress(&[0x47, 0x00])),
+        ),
+        (
+            "counter64",
+            VarBindValue::Value(ObjectSyntax::Counter64(u64::MAX)),
+        ),
+        (
+            "uinteger32",
+            VarBindValue::Value(ObjectSyntax::UInteger32(42)),
+        ),
+        (
+            "unknown-application",
+            VarBindValue::Value(ObjectSyntax::UnknownApplication(Any::new(unknown, &[1, 2]))),
+        ),
+        (
+            "unknown-simple",
+            VarBindValue::Value(ObjectSyntax::UnknownSimple(Any::new(boolean, &[0xff]))),
+        ),
+        ("unspecified", VarBindValue::Unspecified),
+        ("nosuchobject", VarBindValue::NoSuchObject),
+        ("nosuchinstance", VarBindValue::NoSuchInstance),
+        ("endofmibview", VarBindValue::EndOfMibView),
+    ]
+}
+
+fn var(oid: Oid<'static>, val: VarBindValue<'static>) -> SnmpVariable<'static> {
+    SnmpVariable { oid, val }
+}
+
+fn generic_pdu(pdu_type: PduType, var: Vec<SnmpVariable<'static>>) -> SnmpPdu<'static> {
+    SnmpPdu::Generic(SnmpGenericPdu {
+        pdu_type,
+        req_id: 0x1234_5678,
+        err: ErrorStatus::NoError,
+        err_index: 0,
+        var,
+    })
+}
+
+fn message(version: u32, community: &str, pdu: SnmpPdu<'static>) -> SnmpMessage<'static> {
+    SnmpMessage {
+        version,
+        community: community.to_string(),
+        pdu,
+    }
+}
+
+fn trap_v1() -> SnmpPdu<'static> {
+    SnmpPdu::TrapV1(SnmpTrapPdu {
+        enterprise: ENTERPRISE,
+        agent_addr: NetworkAddress::IPv4(Ipv4Addr::new(127, 0, 0, 1)),
+        generic_trap: TrapType::ENTERPRISE_SPECIFIC,
+        specific_trap: 1,
+        timestamp: 100,
+        var: vec![var(
+            SYS_DESCR,
+            VarBindValue::Value(ObjectSyntax::String(b"trap")),
+        )],
+    })
+}
+
+fn v3_message(flags: u8, security_model: SecurityModel) -> SnmpV3Message<'static> {
+    let header_data = HeaderData {
+        msg_id: 1,
+        msg_max_size: 65507,
+        msg_flags: flags,
+        msg_security_model: security_model,
+    };
+    let security_params = if security_model == SecurityModel::USM {
+        SecurityParameters::USM(UsmSecurityParameters {
+            msg_authoritative_engine_id: b"\x80\x00\x1f\x88\x80\x59\xdc\x48\x61",
+            msg_authoritative_engine_boots: 1,
+            msg_authoritative_engine_time: 1000,
+            msg_user_name: "user".to_string(),
+            msg_authentication_parameters: if flags & 1 != 0 { &[0; 12] } else { b"" },
+            msg_privacy_parameters: if flags & 2 != 0 { &[0; 8] } else { b"" },
+        })
+    } else {
+        SecurityParameters::Raw(b"\x30\x00")
+    };
+    let data = if flags & 2 != 0 {
+        ScopedPduData::Encrypted(&[0x55; 48])
+    } else {
+        ScopedPduData::Plaintext(ScopedPdu {
+            ctx_engine_id: b"\x80\x00\x1f\x88\x80\x59\xdc\x48\x61",
+            ctx_engine_name: b"",
+            data: generic_pdu(
+                PduType::GetRequest,
+                vec![var(SYS_DESCR, VarBindValue::Unspecified)],
+            ),
+        })
+    };
+    SnmpV3Message {
+        version: 3,
+        header_data,
+        security_params,
+        security_params_raw: b"",
+        data,
+    }
+}
+
+fn corpus() -> Vec<(String, Vec<u8>)> {
+    let mut seeds = Vec::new();
+    // every PDU type
+    let v1_types = [
+        PduType::GetRequest,
+        PduType::GetNextRequest,
+        PduType::Response,
+        PduType::SetRequest,
+    ];
+    for pdu_type in v1_types {
+        let pdu = generic_pdu(pdu_type, vec![var(SYS_DESCR, VarBindValue::Unspecified)]);
+        let name = format!("v1-{:?}", pdu_type);
+        seeds.push((name, message(0, "public", pdu).encode()));
+    }
+    seeds.push((
+        "v1-TrapV1".to_string(),
+        message(0, "public", trap_v1()).encode(),
+    ));
+    let v2_types = [
+        PduType::GetRequest,
+        PduType::GetNextRequest,
+        PduType::Response,
+        PduType::SetRequest,
+        PduType::InformRequest,
+        PduType::TrapV2,
+        PduType::Report,
+    ];
+    for pdu_type in v2_types {
+        let vars = vec![
+            var(
+                SYS_UPTIME,
+                VarBindValue::Value(ObjectSyntax::TimeTicks(100)),
+            ),
+            var(
+                SNMP_TRAP_OID,
+                VarBindValue::Value(ObjectSyntax::Object(ENTERPRISE)),
+            ),
+        ];
+        let name = format!("v2c-{:?}", pdu_type);
+        seeds.push((
+            name,
+            message(1, "public", generic_pdu(pdu_type, vars)).encode(),
+        ));
+    }
+    let bulk = SnmpPdu::Bulk(SnmpBulkPdu {
+        req_id: 1,
+        non_repeaters: 1,
+        max_repetitions: 10,
+        var: vec![var(SYS_DESCR, VarBindValue::Unspecified)],
+    });
+    seeds.push((
+        "v2c-GetBulkRequest".to_string(),
+        message(1, "public", bulk).encode(),
+    ));
+    // every SMI type
+    for (name, val) in values() {
+        let pdu = generic_pdu(PduType::Response, vec![var(SYS_DESCR, val)]);
+        seeds.push((
+            format!("v2c-value-{}", name),
+            message(1, "public", pdu).encode(),
+        ));
+    }
+    // every v3 flag combination, with USM and an unknown security model
+    for flags in 0..8 {
+        let msg = v3_message(flags, SecurityModel::USM);
+        seeds.push((format!("v3-usm-flags{}", flags), msg.encode()));
+    }
+    let msg = v3_message(0b100, SecurityModel(99));
+    seeds.push(("v3-model99".to_string(), msg.encode()));
+    // edge lengths, around the short/long form limits
+    for len in [0, 127, 128, 255, 256, 65535] {
+        let pdu = generic_pdu(PduType::GetRequest, vec![]);
+        let msg = message(1, &"c".repeat(len), pdu);
+        seeds.push((format!("v2c-community{}", len), msg.encode()));
+    }
+    for count in [0, 1, 100] {
+        let vars = (0..count)
+            .map(|i| {
+                let oid = Oid::from(&[1, 3, 6, 1, 2, 1, 2, 2, 1, 10, i]).unwrap();
+                var(oid, VarBindValue::Value(ObjectSyntax::Counter32(i as u32)))
+            })
+            .collect();
+        let pdu = generic_pdu(PduType::Response, vars);
+        seeds.push((
+            format!("v2c-varbinds{}", count),
+            message(1, "public", pdu).encode(),
+        ));
+    }
+    let oid = Oid::from(&[1, 3, 6, 1, 4, 1, u64::from(u32::MAX), u64::MAX]).unwrap();
+    let pdu = generic_pdu(
+        PduType::GetRequest,
+        vec![var(oid, VarBindValue::Unspecified)],
+    );
+    seeds.push((
+        "v2c-large-arcs".to_string(),
+        message(1, "public", pdu).encode(),
+    ));
+    seeds
+}
+
+fn dictionary() -> Vec<(&'static str, Vec<u8>)> {
+    let mut entries = vec![
+        ("sequence", vec![0x30]),
+        ("integer", vec![0x02, 0x01]),
+        ("octetstring", vec![0x04]),
+        ("null", vec![0x05, 0x00]),
+        ("oid", vec![0x06]),
+        ("long_length1", vec![0x81]),
+        ("long_length2", vec![0x82]),
+        ("indefinite_length", vec![0x80]),
+        ("ipaddress", vec![0x40, 0x04]),
+        ("counter32", vec![0x41]),
+        ("gauge32", vec![0x42]),
+        ("timeticks", vec![0x43]),
+        ("opaque", vec![0x44]),
+        ("nsap", vec![0x45]),
+        ("counter64", vec![0x46]),
+        ("uinteger32", vec![0x47]),
+        ("nosuchobject", vec![0x80, 0x00]),
+        ("nosuchinstance", vec![0x81, 0x00]),
+        ("endofmibview", vec![0x82, 0x00]),
+        ("version1", vec![0x02, 0x01, 0x00]),
+        ("version2c", vec![0x02, 0x01, 0x01]),
+        ("version3", vec![0x02, 0x01, 0x03]),
+        ("msgflags", vec![0x04, 0x01, 0x07]),
+        ("usm", vec![0x02, 0x01, 0x03]),
+        ("public", b"public".to_vec()),
+        ("private", b"private".to_vec()),
+    ];
+    let pdu_names = [
+        "get", "getnext", "response", "set", "trap", "getbulk", "inform", "trapv2", "report",
+    ];
+    for (i, name) in pdu_names.iter().enumerate() {
+        entries.push((name, vec![0xa0 + i as u8]));
+    }
+    let oids = [
+        ("mib2", MIB2),
+        ("enterprises", ENTERPRISES),
+        ("snmpTrapOID", SNMP_TRAP_OID),
+        ("sysUpTime", SYS_UPTIME),
+    ];
+    for (name, oid) in oids {
+        entries.push((name, oid.as_bytes().to_vec()));
+    }
+    entries
+}
+
+fn main() -> std::io::Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 3 {
+        eprintln!("usage: {} <corpus directory> <dictionary file>", args[0]);
+        std::process::exit(1);
+    }
+    let dir = Path::new(&args[1]);
+    fs::create_dir_all(dir)?;
+    let seeds = corpus();
+    for (name, bytes) in &seeds {
+        // check that seeds are valid, so they reach the deepest code
+        if let Err(e) = parse_snmp_generic_message(bytes) {
+            eprintln!("warning: seed {} is invalid: {:?}", name, e);
+        }
+        fs::write(dir.join(format!("{}.bin", name)), bytes)?;
+    }
+    let mut dict = String::new();
+    for (name, bytes) in dictionary() {
+        let escaped: String = bytes.iter().map(|b| format!("\\x{:02x}", b)).collect();
+        dict.push_str(&format!("{}=\"{}\"\n", name, escaped));
+    }
+    fs::write(&args[2], dict)?;
+    println!("{} seeds written to {}", seeds.len(), dir.display());
+    Ok(())
+}
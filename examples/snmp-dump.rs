@@ -0,0 +1,516 @@
+//! Parse and print SNMP messages
+//!
+//! Usage: `cargo run --example snmp-dump -- [--json | --hexdump | --explain] <input>`
+//!
+//! The input is one of:
+//!   - `--hex <data>`: hex-encoded message (whitespace and `:` are ignored)
+//!   - `--base64 <data>`: base64-encoded message
+//!   - `--pcap <file>`: UDP packets to or from ports 161 and 162 in a capture file (requires the
+//!     `pcap` feature)
+//!   - `<file>`: raw message, or `-` to read from the standard input
+//!
+//! For example:
+//!
+//! ```text
+//! cargo run --example snmp-dump -- assets/snmpv1_trap_coldstart.bin
+//! cargo run --example snmp-dump -- --json --hex "30 26 02 01 01 04 06 70 75 62 6c 69 63 ..."
+//! cargo run --features pcap --example snmp-dump -- --pcap snmp.pcap
+//! ```
+//!
+//! With `--hexdump`, the message is also written as a hexdump annotated with the fields of the
+//! message, for ex. to report encoding issues. With `--explain`, the BER class, tag and lengths
+//! of each field are written instead of the bytes.
+//!
+//! The exit code is:
+//!   - 0 if all messages were parsed without anomalies
+//!   - 1 if a message has anomalies (trailing data, PDU type not allowed for the version, etc.)
+//!   - 2 if a message could not be parsed
+//!   - 3 if the arguments are invalid, or the input cannot be read
+
+#[cfg(feature = "pcap")]
+#[path = "common/pcap.rs"]
+mod pcap;
+
+use snmp_parser::annotate::{annotated_hexdump, explain};
+use snmp_parser::format::{format_octet_string, StringEscape};
+use snmp_parser::*;
+use std::fmt::Write as _;
+use std::io::Read;
+use std::process::exit;
+
+const EXIT_OK: i32 = 0;
+const EXIT_ANOMALY: i32 = 1;
+const EXIT_PARSE_ERROR: i32 = 2;
+const EXIT_USAGE: i32 = 3;
+
+/// Parsed data, printed as text or JSON
+enum Node {
+    Null,
+    Bool(bool),
+    Number(String),
+    Str(String),
+    List(Vec<Node>),
+    Map(Vec<(&'static str, Node)>),
+}
+
+impl Node {
+    fn number<T: ToString>(n: T) -> Node {
+        Node::Number(n.to_string())
+    }
+
+    fn debug<T: std::fmt::Debug>(t: T) -> Node {
+        Node::Str(format!("{:?}", t))
+    }
+
+    fn bytes(b: &[u8]) -> Node {
+        Node::Str(format_octet_string(b).escape(StringEscape::Raw).to_string())
+    }
+
+    fn write_json(&self, out: &mut String) {
+        match self {
+            Node::Null => out.push_str("null"),
+            Node::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            Node::Number(n) => out.push_str(n),
+            Node::Str(s) => write_json_string(out, s),
+            Node::List(items) => {
+                out.push('[');
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        out.push(',');
+                    }
+                    item.write_json(out);
+                }
+                out.push(']');
+            }
+            Node::Map(fields) => {
+                out.push('{');
+                for (idx, (key, value)) in fields.iter().enumerate() {
+                    if idx > 0 {
+                        out.push(',');
+                    }
+                    write_json_string(out, key);
+                    out.push(':');
+                    value.write_json(out);
+                }
+                out.push('}');
+            }
+        }
+    }
+
+    /// Write the value after a key, on the same line for scalar values
+    fn write_text(&self, out: &mut String, indent: usize) {
+        match self {
+            Node::Null => out.push_str(" -\n"),
+            Node::Bool(b) => {
+                let _ = writeln!(out, " {}", b);
+            }
+            Node::Number(s) | Node::Str(s) => {
+                let _ = writeln!(out, " {}", s);
+            }
+            Node::List(items) if items.is_empty() => out.push_str(" []\n"),
+            Node::List(items) => {
+                out.push('\n');
+                for item in items {
+                    let _ = write!(out, "{:indent$}-", "", indent = indent);
+                    match item {
+                        // print the first field on the same line as the dash
+                        Node::Map(fields) => write_text_fields(out, fields, indent + 2, true),
+                        _ => item.write_text(out, indent + 2),
+                    }
+                }
+            }
+            Node::Map(fields) => {
+                out.push('\n');
+                write_text_fields(out, fields, indent, false);
+            }
+        }
+    }
+}
+
+fn write_text_fields(out: &mut String, fields: &[(&str, Node)], indent: usize, inline: bool) {
+    for (idx, (key, value)) in fields.iter().enumerate() {
+        if idx == 0 && inline {
+            out.push(' ');
+        } else {
+            let _ = write!(out, "{:indent$}", "", indent = indent);
+        }
+        let _ = write!(out, "{}:", key);
+        value.write_text(out, indent + 2);
+    }
+}
+
+fn write_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn to_hex(b: &[u8]) -> String {
+    b.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>, String> {
+    let digits: Vec<u8> = s
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace() && *c != b':')
+        .collect();
+    digits
+        .chunks(2)
+        .map(|pair| {
+            if pair.len() != 2 {
+                return Err("odd number of hex digits".to_string());
+            }
+            let pair = std::str::from_utf8(pair).map_err(|e| e.to_string())?;
+            u8::from_str_radix(pair, 16).map_err(|_| format!("invalid hex digits {:?}", pair))
+        })
+        .collect()
+}
+
+fn decode_base64(s: &str) -> Result<Vec<u8>, String> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = Vec::new();
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    for c in s.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        if c == b'=' {
+            break;
+        }
+        let value = match ALPHABET.iter().position(|&a| a == c) {
+            Some(value) => value as u32,
+            None => return Err(format!("invalid base64 character {:?}", c as char)),
+        };
+        acc = (acc << 6) | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
+
+fn value_node(val: &VarBindValue) -> (&'static str, Node) {
+    let obj = match val {
+        VarBindValue::Value(obj) => obj,
+        VarBindValue::Unspecified => return ("NULL", Node::Null),
+        VarBindValue::NoSuchObject => return ("noSuchObject", Node::Null),
+        VarBindValue::NoSuchInstance => return ("noSuchInstance", Node::Null),
+        VarBindValue::EndOfMibView => return ("endOfMibView", Node::Null),
+    };
+    match obj {
+        ObjectSyntax::Number(n) => ("INTEGER", Node::number(n)),
+        ObjectSyntax::String(s) => ("OCTET STRING", Node::bytes(s)),
+        ObjectSyntax::Object(oid) => ("OBJECT IDENTIFIER", Node::Str(oid.to_id_string())),
+        ObjectSyntax::BitString(b) => ("BIT STRING", Node::Str(to_hex(&b.data))),
+        ObjectSyntax::Empty => ("empty", Node::Null),
+        ObjectSyntax::Real(n) if n.is_finite() => ("REAL", Node::number(n)),
+        ObjectSyntax::Real(n) => ("REAL", Node::Str(n.to_string())),
+        ObjectSyntax::Enumerated(n) => ("ENUMERATED", Node::number(n)),
+        ObjectSyntax::IpAddress(NetworkAddress::IPv4(addr)) => ("IpAddress", Node::number(addr)),
+        ObjectSyntax::Counter32(n) => ("Counter32", Node::number(n)),
+        ObjectSyntax::Gauge32(n) => ("Gauge32", Node::number(n)),
+        ObjectSyntax::TimeTicks(n) => ("TimeTicks", Node::number(n)),
+        ObjectSyntax::Opaque(b) => ("Opaque", Node::Str(to_hex(b))),
+        ObjectSyntax::NsapAddress(b) => ("NsapAddress", Node::Str(to_hex(b))),
+        ObjectSyntax::Counter64(n) => ("Counter64", Node::number(n)),
+        ObjectSyntax::UInteger32(n) => ("UInteger32", Node::number(n)),
+        ObjectSyntax::UnknownSimple(any) | ObjectSyntax::UnknownApplication(any) => {
+            ("unknown", Node::Str(to_hex(any.data)))
+        }
+        ObjectSyntax::Custom(value) => ("custom", Node::Str(to_hex(value.raw.data))),
+    }
+}
+
+fn pdu_node(pdu: &SnmpPdu) -> Node {
+    let mut fields = vec![("type", Node::debug(pdu.pdu_type()))];
+    match pdu {
+        SnmpPdu::Generic(pdu) => {
+            fields.push(("request_id", Node::number(pdu.req_id)));
+            fields.push(("error_status", Node::debug(pdu.err)));
+            fields.push(("error_index", Node::number(pdu.err_index)));
+        }
+        SnmpPdu::Bulk(pdu) => {
+            fields.push(("request_id", Node::number(pdu.req_id)));
+            fields.push(("non_repeaters", Node::number(pdu.non_repeaters)));
+            fields.push(("max_repetitions", Node::number(pdu.max_repetitions)));
+        }
+        SnmpPdu::TrapV1(trap) => {
+            let NetworkAddress::IPv4(addr) = trap.agent_addr;
+            fields.push(("enterprise", Node::Str(trap.enterprise.to_id_string())));
+            fields.push(("agent_addr", Node::number(addr)));
+            fields.push(("generic_trap", Node::debug(trap.generic_trap)));
+            fields.push(("specific_trap", Node::number(trap.specific_trap)));
+            fields.push(("timestamp", Node::number(trap.timestamp)));
+        }
+    }
+    let varbinds = pdu
+        .vars_iter()
+        .map(|var| {
+            let (ty, value) = value_node(&var.val);
+            Node::Map(vec![
+                ("oid", Node::Str(var.oid.to_id_string())),
+                ("type", Node::Str(ty.to_string())),
+                ("value", value),
+            ])
+        })
+        .collect();
+    fields.push(("varbinds", Node::List(varbinds)));
+    Node::Map(fields)
+}
+
+fn message_node(msg: &SnmpGenericMessage) -> Vec<(&'static str, Node)> {
+    let mut fields = vec![("version", Node::number(msg.version()))];
+    match msg {
+        SnmpGenericMessage::V1(m) | SnmpGenericMessage::V2(m) => {
+            fields.push(("community", Node::Str(m.community.clone())));
+        }
+        #[cfg(feature = "v2p")]
+        SnmpGenericMessage::V2p(m) => {
+            fields.push(("priv_dst", Node::Str(m.priv_dst.to_id_string())));
+            if let Some(auth) = &m.auth_msg {
+                fields.push(("src_party", Node::Str(auth.src_party.to_id_string())));
+                fields.push(("dst_party", Node::Str(auth.dst_party.to_id_string())));
+                fields.push(("context", Node::Str(auth.context.to_id_string())));
+            }
+        }
+        #[cfg(feature = "v2u")]
+        SnmpGenericMessage::V2u(m) => {
+            let params = &m.parameters;
+            fields.push(("user", Node::bytes(params.user_name)));
+            fields.push(("authenticated", Node::Bool(params.is_authenticated())));
+            fields.push(("encrypted", Node::Bool(params.is_encrypted())));
+            fields.push(("context", Node::bytes(params.context_selector)));
+        }
+        SnmpGenericMessage::V3(m) => {
+            let hdr = &m.header_data;
+            fields.push(("msg_id", Node::number(hdr.msg_id)));
+            fields.push(("security_model", Node::debug(hdr.msg_security_model)));
+            fields.push(("authenticated", Node::Bool(hdr.is_authenticated())));
+            fields.push(("encrypted", Node::Bool(hdr.is_encrypted())));
+            if let SecurityParameters::USM(usm) = &m.security_params {
+                fields.push(("user", Node::Str(usm.msg_user_name.clone())));
+                let engine_id = usm.msg_authoritative_engine_id;
+                fields.push(("engine_id", Node::Str(to_hex(engine_id))));
+            }
+            if let ScopedPduData::Plaintext(scoped) = &m.data {
+                let ctx_engine_id = Node::Str(to_hex(scoped.ctx_engine_id));
+                fields.push(("context_engine_id", ctx_engine_id));
+                fields.push(("context_name", Node::bytes(scoped.ctx_engine_name)));
+            }
+        }
+    }
+    match msg.pdu() {
+        Some(pdu) => fields.push(("pdu", pdu_node(pdu))),
+        None => fields.push(("pdu", Node::Str("encrypted".to_string()))),
+    }
+    fields
+}
+
+/// Return the list of anomalies of a message that was parsed successfully
+fn anomalies(msg: &SnmpGenericMessage, rem: &[u8]) -> Vec<String> {
+    let mut anomalies = Vec::new();
+    if !rem.is_empty() {
+        anomalies.push(format!("{} trailing bytes after message", rem.len()));
+    }
+    if let SnmpGenericMessage::V3(m) = msg {
+        if m.has_privacy_mismatch() {
+            anomalies.push("privacy flag does not match scoped PDU data".to_string());
+        }
+    }
+    let pdu = match msg.pdu() {
+        Some(pdu) => pdu,
+        None => return anomalies,
+    };
+    let pdu_type = pdu.pdu_type();
+    let allowed = match msg.version() {
+        0 => matches!(
+            pdu_type,
+            PduType::GetRequest
+                | PduType::GetNextRequest
+                | PduType::Response
+                | PduType::SetRequest
+                | PduType::TrapV1
+        ),
+        _ => pdu_type != PduType::TrapV1,
+    };
+    if !allowed {
+        anomalies.push(format!(
+            "PDU type {:?} is not allowed in version {}",
+            pdu_type,
+            msg.version()
+        ));
+    }
+    if let SnmpPdu::Generic(pdu) = pdu {
+        if pdu.err != ErrorStatus::NoError && pdu.err_index as usize > pdu.var.len() {
+            anomalies.push(format!(
+                "error index {} is greater than the number of variables",
+                pdu.err_index
+            ));
+        }
+    }
+    if msg.version() == 0 {
+        let has_exception = pdu
+            .vars_iter()
+            .any(|var| !matches!(var.val, VarBindValue::Value(_) | VarBindValue::Unspecified));
+        if has_exception {
+            anomalies.push("SNMPv2 exception value in SNMPv1 message".to_string());
+        }
+    }
+    anomalies
+}
+
+/// Output format
+#[derive(Clone, Copy, PartialEq)]
+enum Output {
+    Text,
+    Json,
+    /// Text, followed by the annotated hexdump
+    Hexdump,
+    /// Text, followed by the encoding of each field
+    Explain,
+}
+
+/// Parse and print a message, and return the exit code
+fn dump(data: &[u8], packet: Option<usize>, output: Output) -> i32 {
+    let mut fields = Vec::new();
+    if let Some(packet) = packet {
+        fields.push(("packet", Node::number(packet)));
+    }
+    let code = match parse_snmp_generic_message(data) {
+        Ok((rem, msg)) => {
+            fields.extend(message_node(&msg));
+            let anomalies = anomalies(&msg, rem);
+            let code = if anomalies.is_empty() {
+                EXIT_OK
+            } else {
+                EXIT_ANOMALY
+            };
+            let anomalies = anomalies.into_iter().map(Node::Str).collect();
+            fields.push(("anomalies", Node::List(anomalies)));
+            code
+        }
+        Err(e) => {
+            let msg = match e {
+                nom::Err::Incomplete(_) => "incomplete message".to_string(),
+                nom::Err::Error(e) | nom::Err::Failure(e) => e.to_string(),
+            };
+            fields.push(("error", Node::Str(msg)));
+            EXIT_PARSE_ERROR
+        }
+    };
+    let node = Node::Map(fields);
+    let mut out = String::new();
+    if output == Output::Json {
+        node.write_json(&mut out);
+        out.push('\n');
+    } else {
+        write_text_fields(&mut out, fields_of(&node), 0, false);
+        match output {
+            Output::Hexdump => {
+                let _ = write!(out, "\n{}", annotated_hexdump(data));
+            }
+            Output::Explain => {
+                let _ = write!(out, "\n{}", explain(data));
+            }
+            _ => (),
+        }
+        if packet.is_some() {
+            out.push('\n');
+        }
+    }
+    print!("{}", out);
+    code
+}
+
+fn fields_of(node: &Node) -> &[(&'static str, Node)] {
+    match node {
+        Node::Map(fields) => fields,
+        _ => &[],
+    }
+}
+
+fn usage(prog: &str) -> ! {
+    eprintln!(
+        "usage: {} [--json | --hexdump | --explain] (--hex <data> | --base64 <data> | --pcap <file> | <file>)",
+        prog
+    );
+    exit(EXIT_USAGE);
+}
+
+fn read_file(path: &str) -> Vec<u8> {
+    let res = if path == "-" {
+        let mut data = Vec::new();
+        std::io::stdin().read_to_end(&mut data).map(|_| data)
+    } else {
+        std::fs::read(path)
+    };
+    res.unwrap_or_else(|e| {
+        eprintln!("error: cannot read {}: {}", path, e);
+        exit(EXIT_USAGE);
+    })
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let mut output = Output::Text;
+    let mut input = None;
+    let mut idx = 1;
+    while idx < args.len() {
+        let arg = args[idx].as_str();
+        match arg {
+            "--json" => output = Output::Json,
+            "--hexdump" => output = Output::Hexdump,
+            "--explain" => output = Output::Explain,
+            "--hex" | "--base64" | "--pcap" if idx + 1 < args.len() => {
+                idx += 1;
+                input = Some((arg, args[idx].as_str()));
+            }
+            "-h" | "--help" => usage(&args[0]),
+            _ if arg.starts_with("--") || input.is_some() => usage(&args[0]),
+            _ => input = Some(("--file", arg)),
+        }
+        idx += 1;
+    }
+    let (kind, value) = input.unwrap_or_else(|| usage(&args[0]));
+    let decoded = match kind {
+        "--hex" => decode_hex(value),
+        "--base64" => decode_base64(value),
+        "--file" => Ok(read_file(value)),
+        #[cfg(feature = "pcap")]
+        "--pcap" => {
+            let data = read_file(value);
+            let file = pcap::PcapFile::parse(&data).unwrap_or_else(|e| {
+                eprintln!("error: {}", e);
+                exit(EXIT_USAGE);
+            });
+            let code = file
+                .packets()
+                .filter_map(|packet| {
+                    let payload = file.snmp_payload(&packet)?;
+                    Some(dump(payload, Some(packet.number), output))
+                })
+                .max();
+            exit(code.unwrap_or(EXIT_OK));
+        }
+        _ => Err("capture files require the `pcap` feature".to_string()),
+    };
+    match decoded {
+        Ok(data) => exit(dump(&data, None, output)),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            exit(EXIT_USAGE);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Copy the SNMP packets of a capture file matching some criteria to a new capture file
+//!
+//! Usage: `cargo run --features pcap --example snmp-grep -- [criteria] <input> <output>`
+//!
+//! Criteria are combined: a packet is written if it matches all of them.
+//!   - `--community <string>`: SNMPv1 or SNMPv2c community
+//!   - `--user <string>`: SNMPv3 (or SNMPv2u) user name
+//!   - `--oid <oid>`: a variable binding is in the subtree of this OID
+//!   - `--pdu-type <name>`: PDU type (`get`, `getnext`, `response`, `set`, `trap`, `getbulk`,
+//!     `inform`, `trapv2` or `report`)
+//!   - `--rule <rule>`: any rule, see the [`rules`](snmp_parser::rules) module
+//!
+//! Only UDP packets to or from ports 161 and 162 are considered, and packets that cannot be
+//! parsed are ignored. For example:
+//!
+//! ```text
+//! cargo run --features pcap --example snmp-grep -- --oid 1.3.6.1.4.1.9 --pdu-type set in.pcap out.pcap
+//! ```
+
+#[path = "common/pcap.rs"]
+mod pcap;
+
+use snmp_parser::rules::Rule;
+use snmp_parser::*;
+use std::fs;
+use std::io::BufWriter;
+use std::process::exit;
+
+fn usage(prog: &str) -> ! {
+    eprintln!(
+        "usage: {} [--community <string>] [--user <string>] [--oid <oid>] [--pdu-type <name>] \
+         [--rule <rule>] <input> <output>",
+        prog
+    );
+    exit(1);
+}
+
+/// Quote a string for the rule language
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn main() -> std::io::Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    let mut clauses = Vec::new();
+    let mut files = Vec::new();
+    let mut idx = 1;
+    while idx < args.len() {
+        let arg = args[idx].as_str();
+        if arg.starts_with("--") {
+            let value = match args.get(idx + 1) {
+                Some(value) => value,
+                None => usage(&args[0]),
+            };
+            let clause = match arg {
+                "--community" => format!("community == {}", quote(value)),
+                "--user" => format!("user == {}", quote(value)),
+                "--oid" => format!("oid startswith {}", value),
+                "--pdu-type" => format!("pdu_type == {}", value),
+                "--rule" => format!("({})", value),
+                _ => usage(&args[0]),
+            };
+            clauses.push(clause);
+            idx += 2;
+        } else {
+            files.push(arg);
+            idx += 1;
+        }
+    }
+    if files.len() != 2 || clauses.is_empty() {
+        usage(&args[0]);
+    }
+    let rule = Rule::parse(&clauses.join(" && ")).unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        exit(1);
+    });
+
+    let data = fs::read(files[0])?;
+    let file = pcap::PcapFile::parse(&data).unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        exit(1);
+    });
+    let mut total = 0;
+    let matching: Vec<_> = file
+        .packets()
+        .filter(|packet| {
+            let payload = match file.snmp_payload(packet) {
+                Some(payload) => payload,
+                None => return false,
+            };
+            total += 1;
+            match parse_snmp_generic_message(payload) {
+                Ok((_, msg)) => rule.matches(&msg),
+                Err(_) => false,
+            }
+        })
+        .collect();
+    let mut out = BufWriter::new(fs::File::create(files[1])?);
+    let count = pcap::write_pcap(&mut out, &file, &matching)?;
+    eprintln!(
+        "{} of {} SNMP packets written to {}",
+        count, total, files[1]
+    );
+    Ok(())
+}
@@ -0,0 +1,11 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate asn1_rs;
+extern crate snmp_parser;
+
+use asn1_rs::FromBer;
+use snmp_parser::SnmpGenericMessage;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = SnmpGenericMessage::from_ber(data);
+});
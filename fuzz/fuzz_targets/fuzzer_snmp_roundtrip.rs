@@ -0,0 +1,203 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate arbitrary;
+extern crate asn1_rs;
+extern crate snmp_parser;
+
+use arbitrary::Arbitrary;
+use asn1_rs::{Any, BitString, Class, Header, Oid, Tag};
+use snmp_parser::encode::SnmpEncode;
+use snmp_parser::*;
+use std::borrow::Cow;
+use std::net::Ipv4Addr;
+
+// Structure-aware description of a message. It is converted to the parser types, encoded, and
+// the encoding must then be stable: parse(encode(m)) must re-encode to the same bytes.
+
+#[derive(Arbitrary, Debug)]
+enum FuzzValue {
+    Number(i32),
+    String(Vec<u8>),
+    Object(Vec<u32>),
+    BitString(u8, Vec<u8>),
+    Empty,
+    IpAddress([u8; 4]),
+    Counter32(u32),
+    Gauge32(u32),
+    TimeTicks(u32),
+    Opaque(Vec<u8>),
+    NsapAddress(Vec<u8>),
+    Counter64(u64),
+    UInteger32(u32),
+    UnknownApplication(u8, Vec<u8>),
+    Unspecified,
+    NoSuchObject,
+    NoSuchInstance,
+    EndOfMibView,
+}
+
+#[derive(Arbitrary, Debug)]
+struct FuzzVarBind {
+    oid: Vec<u32>,
+    value: FuzzValue,
+}
+
+#[derive(Arbitrary, Debug)]
+enum FuzzPdu {
+    Generic {
+        pdu_type: u8,
+        req_id: u32,
+        err: u32,
+        err_index: u32,
+    },
+    Bulk {
+        req_id: u32,
+        non_repeaters: u32,
+        max_repetitions: u32,
+    },
+    TrapV1 {
+        enterprise: Vec<u32>,
+        agent: [u8; 4],
+        generic_trap: u8,
+        specific_trap: u32,
+        timestamp: u32,
+    },
+}
+
+#[derive(Arbitrary, Debug)]
+struct FuzzMessage {
+    v2: bool,
+    community: String,
+    pdu: FuzzPdu,
+    vars: Vec<FuzzVarBind>,
+}
+
+fn to_oid(arcs: &[u32]) -> Oid<'static> {
+    let mut arcs: Vec<u64> = arcs.iter().map(|&a| u64::from(a)).collect();
+    // the first two arcs are constrained
+    match arcs.len() {
+        0 | 1 => arcs = vec![1, 3],
+        _ => {
+            arcs[0] %= 3;
+            if arcs[0] < 2 {
+                arcs[1] %= 40;
+            }
+        }
+    }
+    Oid::from(&arcs).unwrap_or_else(|_| Oid::from(&[1, 3]).unwrap())
+}
+
+fn to_value(value: &FuzzValue) -> VarBindValue<'_> {
+    let v = match value {
+        FuzzValue::Number(n) => ObjectSyntax::Number(*n),
+        FuzzValue::String(s) => ObjectSyntax::String(s),
+        FuzzValue::Object(arcs) => ObjectSyntax::Object(to_oid(arcs)),
+        FuzzValue::BitString(unused, data) => ObjectSyntax::BitString(BitString {
+            unused_bits: unused % 8,
+            data: Cow::Borrowed(data),
+        }),
+        FuzzValue::Empty => ObjectSyntax::Empty,
+        FuzzValue::IpAddress(ip) => {
+            ObjectSyntax::IpAddress(NetworkAddress::IPv4(Ipv4Addr::from(*ip)))
+        }
+        FuzzValue::Counter32(n) => ObjectSyntax::Counter32(*n),
+        FuzzValue::Gauge32(n) => ObjectSyntax::Gauge32(*n),
+        FuzzValue::TimeTicks(n) => ObjectSyntax::TimeTicks(*n),
+        FuzzValue::Opaque(s) => ObjectSyntax::Opaque(s),
+        FuzzValue::NsapAddress(s) => ObjectSyntax::NsapAddress(s),
+        FuzzValue::Counter64(n) => ObjectSyntax::Counter64(*n),
+        FuzzValue::UInteger32(n) => ObjectSyntax::UInteger32(*n),
+        FuzzValue::UnknownApplication(tag, data) => {
+            let header = Header::new(
+                Class::Application,
+                false,
+                Tag(u32::from(*tag) + 8),
+                data.len().into(),
+            );
+            ObjectSyntax::UnknownApplication(Any::new(header, data))
+        }
+        FuzzValue::Unspecified => return VarBindValue::Unspecified,
+        FuzzValue::NoSuchObject => return VarBindValue::NoSuchObject,
+        FuzzValue::NoSuchInstance => return VarBindValue::NoSuchInstance,
+        FuzzValue::EndOfMibView => return VarBindValue::EndOfMibView,
+    };
+    VarBindValue::Value(v)
+}
+
+fn to_message(m: &FuzzMessage) -> SnmpMessage<'_> {
+    let var = m
+        .vars
+        .iter()
+        .map(|v| SnmpVariable {
+            oid: to_oid(&v.oid),
+            val: to_value(&v.value),
+        })
+        .collect();
+    let pdu = match m.pdu {
+        FuzzPdu::Generic {
+            pdu_type,
+            req_id,
+            err,
+            err_index,
+        } => {
+            // any type using the generic layout
+            let pdu_type = match pdu_type % 7 {
+                4 => PduType::Report,
+                5 => PduType::InformRequest,
+                6 => PduType::TrapV2,
+                n => PduType(u32::from(n)),
+            };
+            SnmpPdu::Generic(SnmpGenericPdu {
+                pdu_type,
+                req_id,
+                err: ErrorStatus(err),
+                err_index,
+                var,
+            })
+        }
+        FuzzPdu::Bulk {
+            req_id,
+            non_repeaters,
+            max_repetitions,
+        } => SnmpPdu::Bulk(SnmpBulkPdu {
+            req_id,
+            non_repeaters,
+            max_repetitions,
+            var,
+        }),
+        FuzzPdu::TrapV1 {
+            ref enterprise,
+            agent,
+            generic_trap,
+            specific_trap,
+            timestamp,
+        } => SnmpPdu::TrapV1(SnmpTrapPdu {
+            enterprise: to_oid(enterprise),
+            agent_addr: NetworkAddress::IPv4(Ipv4Addr::from(agent)),
+            generic_trap: TrapType(generic_trap),
+            specific_trap,
+            timestamp,
+            var,
+        }),
+    };
+    SnmpMessage {
+        version: if m.v2 { 1 } else { 0 },
+        community: m.community.clone(),
+        pdu,
+    }
+}
+
+fuzz_target!(|m: FuzzMessage| {
+    let msg = to_message(&m);
+    let bytes = msg.encode();
+    // not all messages are valid (for ex. a bulk PDU in SNMPv1), but valid ones must be stable
+    if let Ok((rem, msg1)) = parse_snmp_generic_message(&bytes) {
+        assert!(rem.is_empty());
+        let bytes1 = msg1.encode();
+        let (_, msg2) = parse_snmp_generic_message(&bytes1).expect("re-encoded message is invalid");
+        // messages can differ (`Empty` is encoded as NULL, and read back as `Unspecified`), but
+        // not their encoding
+        assert_eq!(bytes1, msg2.encode());
+    }
+});
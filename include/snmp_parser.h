@@ -0,0 +1,79 @@
+/*
+ * C API for snmp-parser
+ *
+ * Build the library using:
+ *   cargo rustc --release --features capi --crate-type cdylib
+ */
+
+#ifndef SNMP_PARSER_H
+#define SNMP_PARSER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Return codes. Positive values are the numeric SnmpErrorCode values. */
+#define SNMP_OK 0
+#define SNMP_ERR_INVALID_ARGUMENT (-1)
+#define SNMP_ERR_INCOMPLETE (-2)
+#define SNMP_ERR_INVALID_MESSAGE 1
+#define SNMP_ERR_INVALID_VERSION 2
+#define SNMP_ERR_INVALID_PDU_TYPE 3
+#define SNMP_ERR_INVALID_PDU 4
+#define SNMP_ERR_INVALID_HEADER_DATA 5
+#define SNMP_ERR_INVALID_SCOPED_PDU_DATA 6
+#define SNMP_ERR_INVALID_SECURITY_MODEL 7
+#define SNMP_ERR_NOM_ERROR 8
+#define SNMP_ERR_BER_ERROR 9
+#define SNMP_ERR_MAX_DEPTH_EXCEEDED 10
+
+typedef enum SnmpValueType {
+    SNMP_VALUE_UNSPECIFIED = 0,
+    SNMP_VALUE_NO_SUCH_OBJECT = 1,
+    SNMP_VALUE_NO_SUCH_INSTANCE = 2,
+    SNMP_VALUE_END_OF_MIB_VIEW = 3,
+    SNMP_VALUE_NUMBER = 4,
+    SNMP_VALUE_STRING = 5,
+    SNMP_VALUE_OBJECT = 6,
+    SNMP_VALUE_BIT_STRING = 7,
+    SNMP_VALUE_EMPTY = 8,
+    SNMP_VALUE_IP_ADDRESS = 9,
+    SNMP_VALUE_COUNTER32 = 10,
+    SNMP_VALUE_GAUGE32 = 11,
+    SNMP_VALUE_TIME_TICKS = 12,
+    SNMP_VALUE_OPAQUE = 13,
+    SNMP_VALUE_NSAP_ADDRESS = 14,
+    SNMP_VALUE_COUNTER64 = 15,
+    SNMP_VALUE_UINTEGER32 = 16,
+    SNMP_VALUE_REAL = 17,
+    SNMP_VALUE_ENUMERATED = 18,
+    SNMP_VALUE_UNKNOWN = 255,
+} SnmpValueType;
+
+/* Opaque parsed message */
+typedef struct SnmpCMessage SnmpCMessage;
+
+int snmp_parse(const uint8_t *buf, size_t len, SnmpCMessage **result);
+void snmp_message_free(SnmpCMessage *msg);
+
+uint32_t snmp_message_version(const SnmpCMessage *msg);
+const uint8_t *snmp_message_community(const SnmpCMessage *msg, size_t *len);
+const uint8_t *snmp_message_user(const SnmpCMessage *msg, size_t *len);
+int32_t snmp_message_pdu_type(const SnmpCMessage *msg);
+int64_t snmp_message_request_id(const SnmpCMessage *msg);
+int snmp_message_is_encrypted(const SnmpCMessage *msg);
+
+size_t snmp_message_varbind_count(const SnmpCMessage *msg);
+const char *snmp_message_varbind_oid(const SnmpCMessage *msg, size_t index);
+SnmpValueType snmp_message_varbind_type(const SnmpCMessage *msg, size_t index);
+
+const char *snmp_error_name(int code);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SNMP_PARSER_H */
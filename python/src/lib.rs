@@ -0,0 +1,164 @@
+//! Python bindings for snmp-parser
+//!
+//! The module exposes a `parse(data)` function, returning messages as Python `dict` objects.
+//! Build it using [maturin](https://github.com/PyO3/maturin):
+//!
+//! ```text
+//! cd python && maturin develop --release
+//! ```
+//!
+//! ```python
+//! import snmp_parser
+//!
+//! msg = snmp_parser.parse(data)
+//! print(msg["version"], msg["community"])
+//! for vb in msg["pdu"]["varbinds"]:
+//!     print(vb["oid"], vb["type"], vb["value"])
+//! ```
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList};
+use snmp_parser::{
+    parse_snmp_generic_message, NetworkAddress, ObjectSyntax, ScopedPduData, SecurityParameters,
+    SnmpGenericMessage, SnmpPdu, SnmpVariable, VarBindValue,
+};
+
+create_exception!(
+    snmp_parser,
+    SnmpError,
+    PyValueError,
+    "Error raised when a SNMP message cannot be parsed"
+);
+
+fn value_to_py<'py>(py: Python<'py>, val: &VarBindValue) -> (&'static str, PyObject) {
+    let none = py.None();
+    match val {
+        VarBindValue::Unspecified => ("Unspecified", none),
+        VarBindValue::NoSuchObject => ("NoSuchObject", none),
+        VarBindValue::NoSuchInstance => ("NoSuchInstance", none),
+        VarBindValue::EndOfMibView => ("EndOfMibView", none),
+        VarBindValue::Value(v) => match v {
+            ObjectSyntax::Number(n) => ("Number", n.into_py(py)),
+            ObjectSyntax::String(s) => ("String", PyBytes::new_bound(py, s).into_py(py)),
+            ObjectSyntax::Object(o) => ("Object", o.to_id_string().into_py(py)),
+            ObjectSyntax::BitString(b) => {
+                ("BitString", PyBytes::new_bound(py, &b.data).into_py(py))
+            }
+            ObjectSyntax::Empty => ("Empty", none),
+            ObjectSyntax::Real(n) => ("Real", n.into_py(py)),
+            ObjectSyntax::Enumerated(n) => ("Enumerated", n.into_py(py)),
+            ObjectSyntax::IpAddress(NetworkAddress::IPv4(ip)) => {
+                ("IpAddress", ip.to_string().into_py(py))
+            }
+            ObjectSyntax::Counter32(n) => ("Counter32", n.into_py(py)),
+            ObjectSyntax::Gauge32(n) => ("Gauge32", n.into_py(py)),
+            ObjectSyntax::TimeTicks(n) => ("TimeTicks", n.into_py(py)),
+            ObjectSyntax::Opaque(s) => ("Opaque", PyBytes::new_bound(py, s).into_py(py)),
+            ObjectSyntax::NsapAddress(s) => ("NsapAddress", PyBytes::new_bound(py, s).into_py(py)),
+            ObjectSyntax::Counter64(n) => ("Counter64", n.into_py(py)),
+            ObjectSyntax::UInteger32(n) => ("UInteger32", n.into_py(py)),
+            ObjectSyntax::UnknownSimple(a) | ObjectSyntax::UnknownApplication(a) => {
+                ("Unknown", PyBytes::new_bound(py, a.data).into_py(py))
+            }
+            ObjectSyntax::Custom(c) => ("Unknown", PyBytes::new_bound(py, c.raw.data).into_py(py)),
+        },
+    }
+}
+
+fn varbind_to_py<'py>(py: Python<'py>, var: &SnmpVariable) -> PyResult<Bound<'py, PyDict>> {
+    let d = PyDict::new_bound(py);
+    let (ty, value) = value_to_py(py, &var.val);
+    d.set_item("oid", var.oid.to_id_string())?;
+    d.set_item("type", ty)?;
+    d.set_item("value", value)?;
+    Ok(d)
+}
+
+fn pdu_to_py<'py>(py: Python<'py>, pdu: &SnmpPdu) -> PyResult<Bound<'py, PyDict>> {
+    let d = PyDict::new_bound(py);
+    d.set_item("type", format!("{:?}", pdu.pdu_type()))?;
+    match pdu {
+        SnmpPdu::Generic(p) => {
+            d.set_item("request_id", p.req_id)?;
+            d.set_item("error_status", format!("{:?}", p.err))?;
+            d.set_item("error_index", p.err_index)?;
+        }
+        SnmpPdu::Bulk(p) => {
+            d.set_item("request_id", p.req_id)?;
+            d.set_item("non_repeaters", p.non_repeaters)?;
+            d.set_item("max_repetitions", p.max_repetitions)?;
+        }
+        SnmpPdu::TrapV1(p) => {
+            let NetworkAddress::IPv4(agent) = p.agent_addr;
+            d.set_item("enterprise", p.enterprise.to_id_string())?;
+            d.set_item("agent_addr", agent.to_string())?;
+            d.set_item("generic_trap", p.generic_trap.0)?;
+            d.set_item("specific_trap", p.specific_trap)?;
+            d.set_item("timestamp", p.timestamp)?;
+        }
+    }
+    let varbinds = PyList::empty_bound(py);
+    for var in pdu.vars_iter() {
+        varbinds.append(varbind_to_py(py, var)?)?;
+    }
+    d.set_item("varbinds", varbinds)?;
+    Ok(d)
+}
+
+fn message_to_py<'py>(py: Python<'py>, msg: &SnmpGenericMessage) -> PyResult<Bound<'py, PyDict>> {
+    let d = PyDict::new_bound(py);
+    d.set_item("version", msg.version())?;
+    d.set_item("community", msg.community())?;
+    if let SnmpGenericMessage::V3(m) = msg {
+        let hdr = &m.header_data;
+        d.set_item("msg_id", hdr.msg_id)?;
+        d.set_item("msg_max_size", hdr.msg_max_size)?;
+        d.set_item("msg_flags", hdr.msg_flags)?;
+        d.set_item("security_model", hdr.msg_security_model.0)?;
+        let user = match m.security_params {
+            SecurityParameters::USM(ref usm) => Some(usm.msg_user_name.as_str()),
+            SecurityParameters::Raw(_) => None,
+        };
+        d.set_item("user", user)?;
+        if let ScopedPduData::Plaintext(ref scoped) = m.data {
+            d.set_item(
+                "context_engine_id",
+                PyBytes::new_bound(py, scoped.ctx_engine_id),
+            )?;
+            d.set_item(
+                "context_name",
+                PyBytes::new_bound(py, scoped.ctx_engine_name),
+            )?;
+        }
+        d.set_item("encrypted", msg.pdu().is_none())?;
+    }
+    match msg.pdu() {
+        Some(pdu) => d.set_item("pdu", pdu_to_py(py, pdu)?)?,
+        None => d.set_item("pdu", py.None())?,
+    }
+    Ok(d)
+}
+
+/// Parse a SNMP message (of any version), and return it as a `dict`
+///
+/// Raise `SnmpError` if the message is invalid.
+#[pyfunction]
+fn parse<'py>(py: Python<'py>, data: &[u8]) -> PyResult<Bound<'py, PyDict>> {
+    match parse_snmp_generic_message(data) {
+        Ok((_, msg)) => message_to_py(py, &msg),
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+            Err(SnmpError::new_err((e.to_string(), e.code().0, e.offset())))
+        }
+        Err(nom::Err::Incomplete(_)) => Err(SnmpError::new_err("incomplete message")),
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "snmp_parser")]
+fn snmp_parser_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("SnmpError", m.py().get_type_bound::<SnmpError>())?;
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    Ok(())
+}
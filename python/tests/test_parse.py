@@ -0,0 +1,35 @@
+import os
+
+import pytest
+import snmp_parser
+
+ASSETS = os.path.join(os.path.dirname(__file__), "..", "..", "assets")
+
+
+def read_asset(name):
+    with open(os.path.join(ASSETS, name), "rb") as f:
+        return f.read()
+
+
+def test_parse_v1_trap():
+    msg = snmp_parser.parse(read_asset("snmpv1_trap_coldstart.bin"))
+    assert msg["version"] == 0
+    assert msg["community"] == "public"
+    pdu = msg["pdu"]
+    assert pdu["type"] == "TrapV1"
+    assert pdu["agent_addr"] == "127.0.0.1"
+    assert pdu["varbinds"] == [{"oid": "1.3.6.1.2.1.2.1.0", "type": "Number", "value": 33}]
+
+
+def test_parse_v3():
+    msg = snmp_parser.parse(read_asset("snmpv3_req.bin"))
+    assert msg["version"] == 3
+    assert msg["community"] is None
+    assert msg["encrypted"] is False
+    assert msg["pdu"]["type"] == "GetRequest"
+
+
+def test_parse_error():
+    with pytest.raises(snmp_parser.SnmpError) as e:
+        snmp_parser.parse(b"\x30\x03\x02\x01\x07")
+    assert e.value.args[1] == 2
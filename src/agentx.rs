@@ -0,0 +1,719 @@
+//! AgentX Parser
+//!
+//! AgentX is defined in [RFC2741](https://tools.ietf.org/html/rfc2741): Agent Extensibility
+//! (AgentX) Protocol Version 1.
+//!
+//! AgentX is used between a master agent and subagents, usually over TCP port 705 or a UNIX
+//! socket. Unlike SNMP, it does not use BER: PDUs are made of a fixed 20-bytes header and a
+//! payload, and integers are encoded using the byte order indicated in the header.
+//!
+//! Variable bindings are returned using the same types as SNMP ([`SnmpVariable`]).
+
+use crate::error::{context, context_with, cut, resolve_offsets, SnmpError};
+use crate::snmp::{NetworkAddress, ObjectSyntax, SnmpVariable, VarBindValue};
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::vec::Vec;
+use asn1_rs::Oid;
+use core::fmt;
+#[cfg(not(feature = "std"))]
+use core::net::Ipv4Addr;
+use nom::bytes::{complete, streaming};
+use nom::multi::count;
+use nom::number::complete::{be_u8, i32, u16, u32, u64};
+use nom::number::Endianness;
+use nom::{Err, IResult};
+#[cfg(feature = "std")]
+use std::net::Ipv4Addr;
+
+/// Length of the AgentX PDU header
+pub const AGENTX_HEADER_LEN: usize = 20;
+
+/// Header flag: register a single instance
+pub const AGENTX_FLAG_INSTANCE_REGISTRATION: u8 = 0x01;
+/// Header flag: allocate a new index
+pub const AGENTX_FLAG_NEW_INDEX: u8 = 0x02;
+/// Header flag: allocate any index
+pub const AGENTX_FLAG_ANY_INDEX: u8 = 0x04;
+/// Header flag: the payload starts with a context
+pub const AGENTX_FLAG_NON_DEFAULT_CONTEXT: u8 = 0x08;
+/// Header flag: integers are encoded in network (big-endian) byte order
+pub const AGENTX_FLAG_NETWORK_BYTE_ORDER: u8 = 0x10;
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct AgentxPduType(pub u8);
+
+#[allow(non_upper_case_globals)]
+impl AgentxPduType {
+    pub const Open: AgentxPduType = AgentxPduType(1);
+    pub const Close: AgentxPduType = AgentxPduType(2);
+    pub const Register: AgentxPduType = AgentxPduType(3);
+    pub const Unregister: AgentxPduType = AgentxPduType(4);
+    pub const Get: AgentxPduType = AgentxPduType(5);
+    pub const GetNext: AgentxPduType = AgentxPduType(6);
+    pub const GetBulk: AgentxPduType = AgentxPduType(7);
+    pub const TestSet: AgentxPduType = AgentxPduType(8);
+    pub const CommitSet: AgentxPduType = AgentxPduType(9);
+    pub const UndoSet: AgentxPduType = AgentxPduType(10);
+    pub const CleanupSet: AgentxPduType = AgentxPduType(11);
+    pub const Notify: AgentxPduType = AgentxPduType(12);
+    pub const Ping: AgentxPduType = AgentxPduType(13);
+    pub const IndexAllocate: AgentxPduType = AgentxPduType(14);
+    pub const IndexDeallocate: AgentxPduType = AgentxPduType(15);
+    pub const AddAgentCaps: AgentxPduType = AgentxPduType(16);
+    pub const RemoveAgentCaps: AgentxPduType = AgentxPduType(17);
+    pub const Response: AgentxPduType = AgentxPduType(18);
+
+    /// Return true if PDUs of this type may start with a non-default context
+    pub fn has_context(self) -> bool {
+        !matches!(
+            self,
+            AgentxPduType::Open
+                | AgentxPduType::Close
+                | AgentxPduType::CommitSet
+                | AgentxPduType::UndoSet
+                | AgentxPduType::CleanupSet
+                | AgentxPduType::Response
+        )
+    }
+}
+
+impl fmt::Debug for AgentxPduType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            1 => f.write_str("Open"),
+            2 => f.write_str("Close"),
+            3 => f.write_str("Register"),
+            4 => f.write_str("Unregister"),
+            5 => f.write_str("Get"),
+            6 => f.write_str("GetNext"),
+            7 => f.write_str("GetBulk"),
+            8 => f.write_str("TestSet"),
+            9 => f.write_str("CommitSet"),
+            10 => f.write_str("UndoSet"),
+            11 => f.write_str("CleanupSet"),
+            12 => f.write_str("Notify"),
+            13 => f.write_str("Ping"),
+            14 => f.write_str("IndexAllocate"),
+            15 => f.write_str("IndexDeallocate"),
+            16 => f.write_str("AddAgentCaps"),
+            17 => f.write_str("RemoveAgentCaps"),
+            18 => f.write_str("Response"),
+            n => f.debug_tuple("AgentxPduType").field(&n).finish(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct AgentxCloseReason(pub u8);
+
+#[allow(non_upper_case_globals)]
+impl AgentxCloseReason {
+    pub const Other: AgentxCloseReason = AgentxCloseReason(1);
+    pub const ParseError: AgentxCloseReason = AgentxCloseReason(2);
+    pub const ProtocolError: AgentxCloseReason = AgentxCloseReason(3);
+    pub const Timeouts: AgentxCloseReason = AgentxCloseReason(4);
+    pub const Shutdown: AgentxCloseReason = AgentxCloseReason(5);
+    pub const ByManager: AgentxCloseReason = AgentxCloseReason(6);
+}
+
+impl fmt::Debug for AgentxCloseReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            1 => f.write_str("reasonOther"),
+            2 => f.write_str("reasonParseError"),
+            3 => f.write_str("reasonProtocolError"),
+            4 => f.write_str("reasonTimeouts"),
+            5 => f.write_str("reasonShutdown"),
+            6 => f.write_str("reasonByManager"),
+            n => f.debug_tuple("AgentxCloseReason").field(&n).finish(),
+        }
+    }
+}
+
+/// Error status of a Response PDU
+///
+/// Values 0 to 18 are the SNMP error status values, values from 256 are specific to AgentX.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct AgentxErrorStatus(pub u16);
+
+#[allow(non_upper_case_globals)]
+impl AgentxErrorStatus {
+    pub const NoAgentXError: AgentxErrorStatus = AgentxErrorStatus(0);
+    pub const OpenFailed: AgentxErrorStatus = AgentxErrorStatus(256);
+    pub const NotOpen: AgentxErrorStatus = AgentxErrorStatus(257);
+    pub const IndexWrongType: AgentxErrorStatus = AgentxErrorStatus(258);
+    pub const IndexAlreadyAllocated: AgentxErrorStatus = AgentxErrorStatus(259);
+    pub const IndexNoneAvailable: AgentxErrorStatus = AgentxErrorStatus(260);
+    pub const IndexNotAllocated: AgentxErrorStatus = AgentxErrorStatus(261);
+    pub const UnsupportedContext: AgentxErrorStatus = AgentxErrorStatus(262);
+    pub const DuplicateRegistration: AgentxErrorStatus = AgentxErrorStatus(263);
+    pub const UnknownRegistration: AgentxErrorStatus = AgentxErrorStatus(264);
+    pub const UnknownAgentCaps: AgentxErrorStatus = AgentxErrorStatus(265);
+    pub const ParseError: AgentxErrorStatus = AgentxErrorStatus(266);
+    pub const RequestDenied: AgentxErrorStatus = AgentxErrorStatus(267);
+    pub const ProcessingError: AgentxErrorStatus = AgentxErrorStatus(268);
+}
+
+impl fmt::Debug for AgentxErrorStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            0 => f.write_str("noAgentXError"),
+            256 => f.write_str("openFailed"),
+            257 => f.write_str("notOpen"),
+            258 => f.write_str("indexWrongType"),
+            259 => f.write_str("indexAlreadyAllocated"),
+            260 => f.write_str("indexNoneAvailable"),
+            261 => f.write_str("indexNotAllocated"),
+            262 => f.write_str("unsupportedContext"),
+            263 => f.write_str("duplicateRegistration"),
+            264 => f.write_str("unknownRegistration"),
+            265 => f.write_str("unknownAgentCaps"),
+            266 => f.write_str("parseError"),
+            267 => f.write_str("requestDenied"),
+            268 => f.write_str("processingError"),
+            n => f.debug_tuple("AgentxErrorStatus").field(&n).finish(),
+        }
+    }
+}
+
+/// AgentX PDU header
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AgentxHeader {
+    pub version: u8,
+    pub pdu_type: AgentxPduType,
+    pub flags: u8,
+    pub session_id: u32,
+    pub transaction_id: u32,
+    pub packet_id: u32,
+    pub payload_length: u32,
+}
+
+impl AgentxHeader {
+    /// Byte order used for integers in this PDU
+    pub fn byte_order(&self) -> Endianness {
+        if self.flags & AGENTX_FLAG_NETWORK_BYTE_ORDER != 0 {
+            Endianness::Big
+        } else {
+            Endianness::Little
+        }
+    }
+
+    /// Return true if the payload starts with a non-default context
+    pub fn has_non_default_context(&self) -> bool {
+        self.flags & AGENTX_FLAG_NON_DEFAULT_CONTEXT != 0
+    }
+}
+
+/// A range of OIDs, in Get, GetNext and GetBulk PDUs
+#[derive(Debug, PartialEq)]
+pub struct AgentxSearchRange {
+    pub start: Oid<'static>,
+    /// End of the range (excluded), or `None` if the range is unbounded
+    pub end: Option<Oid<'static>>,
+    /// If true, `start` is included in the range
+    pub include: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct AgentxOpen<'a> {
+    pub timeout: u8,
+    /// Identifier of the subagent, or `None` if not provided
+    pub id: Option<Oid<'static>>,
+    pub descr: &'a [u8],
+}
+
+/// Payload of Register and Unregister PDUs
+///
+/// For Unregister PDUs, `timeout` is a reserved field and is always 0.
+#[derive(Debug, PartialEq)]
+pub struct AgentxRegister {
+    pub timeout: u8,
+    pub priority: u8,
+    pub range_subid: u8,
+    pub subtree: Oid<'static>,
+    /// Upper bound of the sub-identifier at position `range_subid`, if `range_subid` is not 0
+    pub upper_bound: Option<u32>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct AgentxGetBulk {
+    pub non_repeaters: u16,
+    pub max_repetitions: u16,
+    pub ranges: Vec<AgentxSearchRange>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct AgentxResponse<'a> {
+    pub sys_uptime: u32,
+    pub error: AgentxErrorStatus,
+    pub index: u16,
+    pub var: Vec<SnmpVariable<'a>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AgentxPayload<'a> {
+    Open(AgentxOpen<'a>),
+    Close(AgentxCloseReason),
+    Register(AgentxRegister),
+    Unregister(AgentxRegister),
+    Get(Vec<AgentxSearchRange>),
+    GetNext(Vec<AgentxSearchRange>),
+    GetBulk(AgentxGetBulk),
+    TestSet(Vec<SnmpVariable<'a>>),
+    CommitSet,
+    UndoSet,
+    CleanupSet,
+    Notify(Vec<SnmpVariable<'a>>),
+    Ping,
+    IndexAllocate(Vec<SnmpVariable<'a>>),
+    IndexDeallocate(Vec<SnmpVariable<'a>>),
+    AddAgentCaps { id: Oid<'static>, descr: &'a [u8] },
+    RemoveAgentCaps { id: Oid<'static> },
+    Response(AgentxResponse<'a>),
+}
+
+/// An AgentX PDU
+#[derive(Debug, PartialEq)]
+pub struct AgentxPdu<'a> {
+    pub header: AgentxHeader,
+    /// Non-default context, if present
+    pub context: Option<&'a [u8]>,
+    pub payload: AgentxPayload<'a>,
+}
+
+impl<'a> AgentxPdu<'a> {
+    pub fn pdu_type(&self) -> AgentxPduType {
+        self.header.pdu_type
+    }
+
+    /// Return the variable bindings, or an empty slice if this type of PDU has none
+    pub fn varbinds(&self) -> &[SnmpVariable<'a>] {
+        match self.payload {
+            AgentxPayload::TestSet(ref var)
+            | AgentxPayload::Notify(ref var)
+            | AgentxPayload::IndexAllocate(ref var)
+            | AgentxPayload::IndexDeallocate(ref var) => var,
+            AgentxPayload::Response(ref r) => &r.var,
+            _ => &[],
+        }
+    }
+}
+
+/// Parse an AgentX PDU header
+///
+/// If the input is shorter than the header, `nom::Err::Incomplete` is returned.
+/// If the version is not 1, a recoverable error (`nom::Err::Error`) is returned.
+pub fn parse_agentx_header(i: &[u8]) -> IResult<&[u8], AgentxHeader, SnmpError> {
+    let (rem, hdr) = streaming::take(AGENTX_HEADER_LEN)(i)?;
+    let (hdr, version) = read_u8(hdr)?;
+    if version != 1 {
+        return Err(Err::Error(SnmpError::InvalidVersion {
+            found: u32::from(version),
+        }));
+    }
+    let (hdr, pdu_type) = read_u8(hdr)?;
+    let (hdr, flags) = read_u8(hdr)?;
+    let (hdr, _reserved) = read_u8(hdr)?;
+    let e = if flags & AGENTX_FLAG_NETWORK_BYTE_ORDER != 0 {
+        Endianness::Big
+    } else {
+        Endianness::Little
+    };
+    let (hdr, session_id) = read_u32(e)(hdr)?;
+    let (hdr, transaction_id) = read_u32(e)(hdr)?;
+    let (hdr, packet_id) = read_u32(e)(hdr)?;
+    let (_, payload_length) = read_u32(e)(hdr)?;
+    let header = AgentxHeader {
+        version,
+        pdu_type: AgentxPduType(pdu_type),
+        flags,
+        session_id,
+        transaction_id,
+        packet_id,
+        payload_length,
+    };
+    Ok((rem, header))
+}
+
+/// Parse an AgentX PDU
+///
+/// Since AgentX runs over streams, `nom::Err::Incomplete` is returned if the input does not
+/// contain the complete PDU, and the remaining bytes (for ex. the next PDU) are returned on
+/// success.
+///
+/// # Errors
+///
+/// If the header cannot be read or the version is not 1, a recoverable error
+/// (`nom::Err::Error`) is returned. Once the header has been read, errors are returned as
+/// `nom::Err::Failure`.
+///
+/// Example:
+///
+/// ```rust
+/// use snmp_parser::agentx::{parse_agentx_pdu, AgentxPayload, AgentxPduType};
+///
+/// static PING: &[u8] = &[
+///     0x01, 0x0d, 0x10, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02,
+///     0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00,
+/// ];
+///
+/// # fn main() {
+/// let (_, pdu) = parse_agentx_pdu(PING).expect("parsing failed");
+/// assert_eq!(pdu.pdu_type(), AgentxPduType::Ping);
+/// assert_eq!(pdu.header.session_id, 1);
+/// assert_eq!(pdu.payload, AgentxPayload::Ping);
+/// # }
+/// ```
+pub fn parse_agentx_pdu(bytes: &[u8]) -> IResult<&[u8], AgentxPdu<'_>, SnmpError> {
+    snmp_span!("parse_agentx_pdu", len = bytes.len());
+    let (rem, header) = parse_agentx_header(bytes)?;
+    snmp_event!(pdu_type = ?header.pdu_type, "parse start");
+    let (rem, payload) = streaming::take(header.payload_length)(rem)?;
+    let res = parse_agentx_payload(payload, &header)
+        .map(|(_, (context, payload))| {
+            let pdu = AgentxPdu {
+                header,
+                context,
+                payload,
+            };
+            (rem, pdu)
+        })
+        .map_err(cut);
+    resolve_offsets(res, bytes)
+}
+
+type Payload<'a> = (Option<&'a [u8]>, AgentxPayload<'a>);
+
+fn parse_agentx_payload<'a>(
+    i: &'a [u8],
+    header: &AgentxHeader,
+) -> IResult<&'a [u8], Payload<'a>, SnmpError> {
+    let e = header.byte_order();
+    let pdu_type = header.pdu_type;
+    let (i, ctx) = if header.has_non_default_context() {
+        if pdu_type.has_context() {
+            let (i, ctx) = context("context", octet_string(e))(i)?;
+            (i, Some(ctx))
+        } else {
+            snmp_anomaly!(pdu_type = ?pdu_type, "context flag set on PDU without context");
+            (i, None)
+        }
+    } else {
+        (i, None)
+    };
+    let (i, payload) = match pdu_type {
+        AgentxPduType::Open => {
+            let (i, timeout) = context("timeout", read_u8)(i)?;
+            let (i, _) = complete::take(3usize)(i)?;
+            let (i, (id, _)) = context("id", agentx_oid(e))(i)?;
+            let (i, descr) = context("descr", octet_string(e))(i)?;
+            (i, AgentxPayload::Open(AgentxOpen { timeout, id, descr }))
+        }
+        AgentxPduType::Close => {
+            let (i, reason) = context("reason", read_u8)(i)?;
+            let (i, _) = complete::take(3usize)(i)?;
+            (i, AgentxPayload::Close(AgentxCloseReason(reason)))
+        }
+        AgentxPduType::Register => {
+            let (i, r) = parse_register(i, e)?;
+            (i, AgentxPayload::Register(r))
+        }
+        AgentxPduType::Unregister => {
+            let (i, r) = parse_register(i, e)?;
+            (i, AgentxPayload::Unregister(r))
+        }
+        AgentxPduType::Get => {
+            let (i, ranges) = context("ranges", search_range_list(e))(i)?;
+            (i, AgentxPayload::Get(ranges))
+        }
+        AgentxPduType::GetNext => {
+            let (i, ranges) = context("ranges", search_range_list(e))(i)?;
+            (i, AgentxPayload::GetNext(ranges))
+        }
+        AgentxPduType::GetBulk => {
+            let (i, non_repeaters) = context("non_repeaters", read_u16(e))(i)?;
+            let (i, max_repetitions) = context("max_repetitions", read_u16(e))(i)?;
+            let (i, ranges) = context("ranges", search_range_list(e))(i)?;
+            let bulk = AgentxGetBulk {
+                non_repeaters,
+                max_repetitions,
+                ranges,
+            };
+            (i, AgentxPayload::GetBulk(bulk))
+        }
+        AgentxPduType::TestSet => {
+            let (i, var) = context("varbinds", varbind_list(e))(i)?;
+            (i, AgentxPayload::TestSet(var))
+        }
+        AgentxPduType::CommitSet => (i, AgentxPayload::CommitSet),
+        AgentxPduType::UndoSet => (i, AgentxPayload::UndoSet),
+        AgentxPduType::CleanupSet => (i, AgentxPayload::CleanupSet),
+        AgentxPduType::Notify => {
+            let (i, var) = context("varbinds", varbind_list(e))(i)?;
+            (i, AgentxPayload::Notify(var))
+        }
+        AgentxPduType::Ping => (i, AgentxPayload::Ping),
+        AgentxPduType::IndexAllocate => {
+            let (i, var) = context("varbinds", varbind_list(e))(i)?;
+            (i, AgentxPayload::IndexAllocate(var))
+        }
+        AgentxPduType::IndexDeallocate => {
+            let (i, var) = context("varbinds", varbind_list(e))(i)?;
+            (i, AgentxPayload::IndexDeallocate(var))
+        }
+        AgentxPduType::AddAgentCaps => {
+            let (i, id) = context("id", required_oid(e))(i)?;
+            let (i, descr) = context("descr", octet_string(e))(i)?;
+            (i, AgentxPayload::AddAgentCaps { id, descr })
+        }
+        AgentxPduType::RemoveAgentCaps => {
+            let (i, id) = context("id", required_oid(e))(i)?;
+            (i, AgentxPayload::RemoveAgentCaps { id })
+        }
+        AgentxPduType::Response => {
+            let (i, sys_uptime) = context("sys_uptime", read_u32(e))(i)?;
+            let (i, error) = context("error", read_u16(e))(i)?;
+            let (i, index) = context("index", read_u16(e))(i)?;
+            let (i, var) = context("varbinds", varbind_list(e))(i)?;
+            let r = AgentxResponse {
+                sys_uptime,
+                error: AgentxErrorStatus(error),
+                index,
+                var,
+            };
+            (i, AgentxPayload::Response(r))
+        }
+        _ => {
+            snmp_anomaly!(pdu_type = pdu_type.0, "invalid AgentX PDU type");
+            return Err(Err::Error(SnmpError::InvalidPduType {
+                tag: u32::from(pdu_type.0),
+            }));
+        }
+    };
+    if !i.is_empty() {
+        snmp_anomaly!(len = i.len(), "trailing bytes in AgentX payload");
+    }
+    Ok((i, (ctx, payload)))
+}
+
+fn parse_register(i: &[u8], e: Endianness) -> IResult<&[u8], AgentxRegister, SnmpError> {
+    let (i, timeout) = context("timeout", read_u8)(i)?;
+    let (i, priority) = context("priority", read_u8)(i)?;
+    let (i, range_subid) = context("range_subid", read_u8)(i)?;
+    let (i, _) = read_u8(i)?;
+    let (i, subtree) = context("subtree", required_oid(e))(i)?;
+    let (i, upper_bound) = if range_subid != 0 {
+        let (i, bound) = context("upper_bound", read_u32(e))(i)?;
+        (i, Some(bound))
+    } else {
+        (i, None)
+    };
+    let r = AgentxRegister {
+        timeout,
+        priority,
+        range_subid,
+        subtree,
+        upper_bound,
+    };
+    Ok((i, r))
+}
+
+/// Octet String: a length, followed by the data padded to a multiple of 4 bytes
+fn octet_string<'a>(
+    e: Endianness,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], &'a [u8], SnmpError> {
+    move |i| {
+        let (i, len) = read_u32(e)(i)?;
+        let (i, data) = complete::take(len)(i)?;
+        let padding = (4 - len % 4) % 4;
+        let (i, _) = complete::take(padding)(i)?;
+        Ok((i, data))
+    }
+}
+
+/// An Object Identifier, and its `include` field
+type AgentxOid = (Option<Oid<'static>>, bool);
+
+/// Object Identifier, and its `include` field
+///
+/// The null OID (no sub-identifiers and no prefix) is returned as `None`.
+fn agentx_oid<'a>(
+    e: Endianness,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], AgentxOid, SnmpError> {
+    move |i| {
+        let (i, n_subid) = read_u8(i)?;
+        let (i, prefix) = read_u8(i)?;
+        let (i, include) = read_u8(i)?;
+        let (i, _reserved) = read_u8(i)?;
+        let (i, subids) = count(read_u32(e), usize::from(n_subid))(i)?;
+        if n_subid == 0 && prefix == 0 {
+            return Ok((i, (None, include != 0)));
+        }
+        let mut arcs: Vec<u64> = Vec::with_capacity(usize::from(n_subid) + 5);
+        if prefix != 0 {
+            // internet prefix: 1.3.6.1.<prefix>
+            arcs.extend_from_slice(&[1, 3, 6, 1, u64::from(prefix)]);
+        }
+        arcs.extend(subids.iter().map(|&x| u64::from(x)));
+        let oid = Oid::from(&arcs).map_err(|_| Err::Error(SnmpError::InvalidPdu))?;
+        Ok((i, (Some(oid), include != 0)))
+    }
+}
+
+fn required_oid<'a>(
+    e: Endianness,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Oid<'static>, SnmpError> {
+    let mut f = agentx_oid(e);
+    move |i| match f(i)? {
+        (rem, (Some(oid), _)) => Ok((rem, oid)),
+        (_, (None, _)) => Err(Err::Error(SnmpError::InvalidPdu)),
+    }
+}
+
+fn search_range_list<'a>(
+    e: Endianness,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Vec<AgentxSearchRange>, SnmpError> {
+    move |mut i| {
+        let mut ranges = Vec::new();
+        while !i.is_empty() {
+            let index = ranges.len();
+            let (rem, range) =
+                context_with(|| Cow::Owned(format!("range[{}]", index)), search_range(e))(i)?;
+            ranges.push(range);
+            i = rem;
+        }
+        Ok((i, ranges))
+    }
+}
+
+fn search_range<'a>(
+    e: Endianness,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], AgentxSearchRange, SnmpError> {
+    move |i| {
+        let (i, (start, include)) = context("start", agentx_oid(e))(i)?;
+        let start = start.ok_or(Err::Error(SnmpError::InvalidPdu))?;
+        let (i, (end, _)) = context("end", agentx_oid(e))(i)?;
+        let range = AgentxSearchRange {
+            start,
+            end,
+            include,
+        };
+        Ok((i, range))
+    }
+}
+
+fn varbind_list<'a>(
+    e: Endianness,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Vec<SnmpVariable<'a>>, SnmpError> {
+    move |mut i| {
+        let mut vars = Vec::new();
+        while !i.is_empty() {
+            let index = vars.len();
+            let (rem, var) =
+                context_with(|| Cow::Owned(format!("varbind[{}]", index)), varbind(e))(i)?;
+            vars.push(var);
+            i = rem;
+        }
+        Ok((i, vars))
+    }
+}
+
+/// <pre>
+/// VarBind: type (2 bytes), reserved (2 bytes), name (OID), data
+/// </pre>
+fn varbind<'a>(
+    e: Endianness,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], SnmpVariable<'a>, SnmpError> {
+    move |i| {
+        let (i, vtype) = context("type", read_u16(e))(i)?;
+        let (i, _reserved) = read_u16(e)(i)?;
+        let (i, oid) = context("name", required_oid(e))(i)?;
+        let (i, val) = context("value", varbind_value(e, vtype))(i)?;
+        Ok((i, SnmpVariable { oid, val }))
+    }
+}
+
+fn varbind_value<'a>(
+    e: Endianness,
+    vtype: u16,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], VarBindValue<'a>, SnmpError> {
+    move |i| {
+        let (i, obj) = match vtype {
+            2 => {
+                let (i, n) = read_i32(e)(i)?;
+                (i, ObjectSyntax::Number(n))
+            }
+            4 => {
+                let (i, s) = octet_string(e)(i)?;
+                (i, ObjectSyntax::String(s))
+            }
+            5 => return Ok((i, VarBindValue::Unspecified)),
+            6 => {
+                // the null OID is equivalent to zeroDotZero
+                let (i, (oid, _)) = agentx_oid(e)(i)?;
+                let oid = match oid {
+                    Some(oid) => oid,
+                    None => Oid::from(&[0, 0]).map_err(|_| Err::Error(SnmpError::InvalidPdu))?,
+                };
+                (i, ObjectSyntax::Object(oid))
+            }
+            64 => {
+                let (i, s) = octet_string(e)(i)?;
+                if s.len() != 4 {
+                    return Err(Err::Error(SnmpError::InvalidPdu));
+                }
+                let ip = Ipv4Addr::new(s[0], s[1], s[2], s[3]);
+                (i, ObjectSyntax::IpAddress(NetworkAddress::IPv4(ip)))
+            }
+            65 => {
+                let (i, n) = read_u32(e)(i)?;
+                (i, ObjectSyntax::Counter32(n))
+            }
+            66 => {
+                let (i, n) = read_u32(e)(i)?;
+                (i, ObjectSyntax::Gauge32(n))
+            }
+            67 => {
+                let (i, n) = read_u32(e)(i)?;
+                (i, ObjectSyntax::TimeTicks(n))
+            }
+            68 => {
+                let (i, s) = octet_string(e)(i)?;
+                (i, ObjectSyntax::Opaque(s))
+            }
+            70 => {
+                let (i, n) = read_u64(e)(i)?;
+                (i, ObjectSyntax::Counter64(n))
+            }
+            128 => return Ok((i, VarBindValue::NoSuchObject)),
+            129 => return Ok((i, VarBindValue::NoSuchInstance)),
+            130 => return Ok((i, VarBindValue::EndOfMibView)),
+            _ => {
+                snmp_anomaly!(vtype, "invalid AgentX value type");
+                return Err(Err::Error(SnmpError::InvalidPdu));
+            }
+        };
+        Ok((i, VarBindValue::Value(obj)))
+    }
+}
+
+fn read_u8(i: &[u8]) -> IResult<&[u8], u8, SnmpError> {
+    be_u8(i)
+}
+
+fn read_u16<'a>(e: Endianness) -> impl Fn(&'a [u8]) -> IResult<&'a [u8], u16, SnmpError> {
+    u16(e)
+}
+
+fn read_u32<'a>(e: Endianness) -> impl Fn(&'a [u8]) -> IResult<&'a [u8], u32, SnmpError> {
+    u32(e)
+}
+
+fn read_i32<'a>(e: Endianness) -> impl Fn(&'a [u8]) -> IResult<&'a [u8], i32, SnmpError> {
+    i32(e)
+}
+
+fn read_u64<'a>(e: Endianness) -> impl Fn(&'a [u8]) -> IResult<&'a [u8], u64, SnmpError> {
+    u64(e)
+}
@@ -0,0 +1,560 @@
+//! Annotated hexdump and explanation of encoded messages
+//!
+//! [`annotated_hexdump`] writes an encoded message with the boundaries and names of its fields,
+//! like `openssl asn1parse -i`. This is useful to show exactly how an agent encoded a message,
+//! for ex. when reporting interoperability issues:
+//!
+//! ```text
+//! 0000  30 26                                            message: SEQUENCE (38)
+//! 0002  02 01 00                                           version: INTEGER (1) = 0
+//! 0005  04 06 70 75 62 6c 69 63                            community: OCTET STRING (6) = "public"
+//! 000d  a0 19                                              pdu: GetRequest (25)
+//! ...
+//! ```
+//!
+//! [`explain`] writes the same fields without the bytes, but with the details of their encoding
+//! (class, tag, form and lengths).
+//!
+//! The fields are located by [`field_spans`], using only the BER encoding and the structure of
+//! the messages, so messages rejected by the parser can also be annotated: elements which cannot
+//! be decoded are shown as raw bytes, as well as trailing data.
+//!
+//! ```rust
+//! use snmp_parser::annotate::{annotated_hexdump, field_spans};
+//!
+//! # static SNMPV1_REQ: &[u8] = include_bytes!("../assets/snmpv1_req.bin");
+//! # fn main() {
+//! let spans = field_spans(SNMPV1_REQ);
+//! assert_eq!(spans[1].field, "version");
+//! assert!(spans.iter().any(|s| s.field == "pdu.varbinds.varbind[0].name"));
+//! println!("{}", annotated_hexdump(SNMPV1_REQ));
+//! # }
+//! ```
+
+use crate::format::{format_octet_string, format_oid};
+use crate::snmp::{PduType, MAX_DEPTH};
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use asn1_rs::{Any, Class, FromBer, Header, Oid, Tag};
+use core::fmt;
+
+/// Number of bytes per line of the hexdump
+const BYTES_PER_LINE: usize = 16;
+
+/// Location of a field in an encoded message
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldSpan {
+    /// Path of the field, for ex `pdu.varbinds.varbind[1].value` (as in errors)
+    pub field: String,
+    /// Nesting level, 0 for the message
+    pub depth: usize,
+    /// Offset of the field, in bytes, from the start of the input
+    pub offset: usize,
+    /// Length of the header (tag and length)
+    pub header_len: usize,
+    /// Length of the content
+    pub len: usize,
+    /// Tag of the element, or `None` if the bytes could not be decoded as a BER element
+    pub ber: Option<BerInfo>,
+}
+
+/// Tag of a BER element
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BerInfo {
+    pub class: Class,
+    pub tag: Tag,
+    pub constructed: bool,
+}
+
+impl FieldSpan {
+    /// Return the position of the field (header and content) in the input
+    pub fn range(&self) -> core::ops::Range<usize> {
+        self.offset..self.offset + self.header_len + self.len
+    }
+
+    /// Return the last part of the path, for ex `value`
+    pub fn name(&self) -> &str {
+        self.field.rsplit('.').next().unwrap_or(&self.field)
+    }
+}
+
+/// Expected structure of an element
+#[derive(Clone, Copy)]
+enum Schema {
+    /// Primitive value, or element with an unknown structure
+    Any,
+    /// SEQUENCE with named elements
+    Fields(&'static [(&'static str, Schema)]),
+    /// SEQUENCE OF elements with the same structure
+    List(&'static str, &'static Schema),
+    /// Message, whose fields depend on the version
+    Message,
+    /// PDU, whose fields depend on the PDU type
+    Pdu,
+    /// OCTET STRING containing an encoded element (SNMPv3 security parameters)
+    Encapsulated(&'static str, &'static Schema),
+    /// SNMPv3 scoped PDU, or encrypted scoped PDU
+    ScopedPduData,
+}
+
+const VARBIND: Schema = Schema::Fields(&[("name", Schema::Any), ("value", Schema::Any)]);
+const VARBINDS: Schema = Schema::List("varbind", &VARBIND);
+
+const GENERIC_PDU: Schema = Schema::Fields(&[
+    ("request_id", Schema::Any),
+    ("error_status", Schema::Any),
+    ("error_index", Schema::Any),
+    ("varbinds", VARBINDS),
+]);
+
+const BULK_PDU: Schema = Schema::Fields(&[
+    ("request_id", Schema::Any),
+    ("non_repeaters", Schema::Any),
+    ("max_repetitions", Schema::Any),
+    ("varbinds", VARBINDS),
+]);
+
+const TRAP_PDU: Schema = Schema::Fields(&[
+    ("enterprise", Schema::Any),
+    ("agent_addr", Schema::Any),
+    ("generic_trap", Schema::Any),
+    ("specific_trap", Schema::Any),
+    ("time_stamp", Schema::Any),
+    ("varbinds", VARBINDS),
+]);
+
+const V1_MESSAGE: Schema = Schema::Fields(&[
+    ("version", Schema::Any),
+    ("community", Schema::Any),
+    ("pdu", Schema::Pdu),
+]);
+
+const USM_PARAMETERS: Schema = Schema::Fields(&[
+    ("msg_authoritative_engine_id", Schema::Any),
+    ("msg_authoritative_engine_boots", Schema::Any),
+    ("msg_authoritative_engine_time", Schema::Any),
+    ("msg_user_name", Schema::Any),
+    ("msg_authentication_parameters", Schema::Any),
+    ("msg_privacy_parameters", Schema::Any),
+]);
+
+const V3_MESSAGE: Schema = Schema::Fields(&[
+    ("version", Schema::Any),
+    (
+        "header_data",
+        Schema::Fields(&[
+            ("msg_id", Schema::Any),
+            ("msg_max_size", Schema::Any),
+            ("msg_flags", Schema::Any),
+            ("msg_security_model", Schema::Any),
+        ]),
+    ),
+    (
+        "security_parameters",
+        Schema::Encapsulated("usm", &USM_PARAMETERS),
+    ),
+    ("data", Schema::ScopedPduData),
+]);
+
+const SCOPED_PDU: Schema = Schema::Fields(&[
+    ("context_engine_id", Schema::Any),
+    ("context_name", Schema::Any),
+    ("pdu", Schema::Pdu),
+]);
+
+const UNKNOWN_MESSAGE: Schema = Schema::Fields(&[("version", Schema::Any)]);
+
+impl Schema {
+    /// Return the structure of an element with this header and content
+    fn resolve(self, header: &Header, content: &[u8]) -> Schema {
+        match self {
+            Schema::Message => match u32::from_ber(content) {
+                Ok((_, 0 | 1)) => V1_MESSAGE,
+                Ok((_, 3)) => V3_MESSAGE,
+                _ => UNKNOWN_MESSAGE,
+            },
+            Schema::Pdu if header.class() == Class::ContextSpecific => {
+                match PduType(header.tag().0) {
+                    PduType::TrapV1 => TRAP_PDU,
+                    PduType::GetBulkRequest => BULK_PDU,
+                    _ => GENERIC_PDU,
+                }
+            }
+            Schema::ScopedPduData if header.tag() == Tag::Sequence => SCOPED_PDU,
+            Schema::Pdu | Schema::ScopedPduData => Schema::Any,
+            schema => schema,
+        }
+    }
+
+    /// Return the name and structure of the child element at `index`
+    fn child(self, index: usize) -> (Cow<'static, str>, Schema) {
+        match self {
+            Schema::Fields(fields) => match fields.get(index) {
+                Some(&(name, schema)) => (Cow::Borrowed(name), schema),
+                None => (Cow::Owned(format!("element[{}]", index)), Schema::Any),
+            },
+            Schema::List(name, schema) => (Cow::Owned(format!("{}[{}]", name, index)), *schema),
+            _ => (Cow::Owned(format!("element[{}]", index)), Schema::Any),
+        }
+    }
+}
+
+struct Walker<'a> {
+    input: &'a [u8],
+    spans: Vec<FieldSpan>,
+}
+
+impl<'a> Walker<'a> {
+    fn offset(&self, data: &[u8]) -> usize {
+        data.as_ptr() as usize - self.input.as_ptr() as usize
+    }
+
+    /// Record bytes which are not a BER element
+    fn raw(&mut self, data: &[u8], field: String, depth: usize) {
+        self.spans.push(FieldSpan {
+            field,
+            depth,
+            offset: self.offset(data),
+            header_len: 0,
+            len: data.len(),
+            ber: None,
+        });
+    }
+
+    /// Record the element at the start of `data`, and its children
+    ///
+    /// Return the remaining bytes, or `None` if the element is invalid.
+    fn element(
+        &mut self,
+        data: &'a [u8],
+        field: String,
+        depth: usize,
+        schema: Schema,
+    ) -> Option<&'a [u8]> {
+        let (rem, header, content) = match Any::from_ber(data) {
+            Ok((rem, any)) => (rem, any.header, any.data),
+            Err(_) => {
+                self.raw(data, field, depth);
+                return None;
+            }
+        };
+        let header_len = self.offset(content) - self.offset(data);
+        let ber = BerInfo {
+            class: header.class(),
+            tag: header.tag(),
+            constructed: header.is_constructed(),
+        };
+        self.spans.push(FieldSpan {
+            field: field.clone(),
+            depth,
+            offset: self.offset(data),
+            header_len,
+            len: data.len() - rem.len() - header_len,
+            ber: Some(ber),
+        });
+        if depth >= MAX_DEPTH {
+            return Some(rem);
+        }
+        // children are named relative to the message
+        let prefix = if depth == 0 { "" } else { &field };
+        match schema.resolve(&header, content) {
+            Schema::Encapsulated(name, schema) if !ber.constructed => {
+                match Any::from_ber(content) {
+                    Ok((r, any)) if r.is_empty() && any.header.is_constructed() => {
+                        let path = join(prefix, name);
+                        self.element(content, path, depth + 1, *schema);
+                    }
+                    _ => (),
+                }
+            }
+            schema if ber.constructed => self.children(content, prefix, depth + 1, schema),
+            _ => (),
+        }
+        Some(rem)
+    }
+
+    fn children(&mut self, mut data: &'a [u8], prefix: &str, depth: usize, schema: Schema) {
+        let mut index = 0;
+        while !data.is_empty() {
+            let (name, child) = schema.child(index);
+            match self.element(data, join(prefix, &name), depth, child) {
+                Some(rem) => data = rem,
+                None => return,
+            }
+            index += 1;
+        }
+    }
+}
+
+fn join(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        String::from(name)
+    } else {
+        format!("{}.{}", prefix, name)
+    }
+}
+
+/// Locate the fields of the message in `input`
+///
+/// Spans are returned in order of position, each element being followed by its children. The
+/// first span is the message. Bytes after the message are returned in a last span named
+/// `trailing`.
+pub fn field_spans(input: &[u8]) -> Vec<FieldSpan> {
+    let mut walker = Walker {
+        input,
+        spans: Vec::new(),
+    };
+    if input.is_empty() {
+        return walker.spans;
+    }
+    let message = String::from("message");
+    if let Some(rem) = walker.element(input, message, 0, Schema::Message) {
+        if !rem.is_empty() {
+            walker.raw(rem, String::from("trailing"), 0);
+        }
+    }
+    walker.spans
+}
+
+/// Formatter for the annotated hexdump of a message, see [`annotated_hexdump`]
+#[derive(Clone, Debug)]
+pub struct AnnotatedHexdump<'a> {
+    input: &'a [u8],
+    spans: Vec<FieldSpan>,
+}
+
+/// Format the message in `input` as a hexdump, annotated with the fields of the message
+///
+/// Each line contains the offset of a field, its bytes (only the header for elements with
+/// children), and its name, type and length, followed by the value for simple types.
+pub fn annotated_hexdump(input: &[u8]) -> AnnotatedHexdump<'_> {
+    AnnotatedHexdump {
+        input,
+        spans: field_spans(input),
+    }
+}
+
+impl<'a> AnnotatedHexdump<'a> {
+    /// Return the fields of the message
+    pub fn spans(&self) -> &[FieldSpan] {
+        &self.spans
+    }
+}
+
+impl<'a> fmt::Display for AnnotatedHexdump<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, span) in self.spans.iter().enumerate() {
+            // the content of elements with children is written with the children
+            let has_children = self
+                .spans
+                .get(idx + 1)
+                .is_some_and(|next| next.depth > span.depth);
+            let bytes = if has_children {
+                &self.input[span.offset..span.offset + span.header_len]
+            } else {
+                &self.input[span.range()]
+            };
+            for (idx, line) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+                write!(f, "{:04x} ", span.offset + idx * BYTES_PER_LINE)?;
+                for b in line {
+                    write!(f, " {:02x}", b)?;
+                }
+                if idx > 0 {
+                    writeln!(f)?;
+                    continue;
+                }
+                let padding = 3 * (BYTES_PER_LINE - line.len()) + 2 * span.depth + 2;
+                write!(f, "{:width$}{}: ", "", span.name(), width = padding)?;
+                match span.ber {
+                    Some(ber) => {
+                        write_type(f, span, &ber)?;
+                        write!(f, " ({})", span.len)?;
+                        if !ber.constructed {
+                            let content = &self.input[span.offset + span.header_len..][..span.len];
+                            write_value(f, &ber, content)?;
+                        }
+                    }
+                    None => write!(f, "{} bytes, not decoded", span.len)?,
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Formatter for the detailed encoding of the fields of a message, see [`explain`]
+#[derive(Clone, Debug)]
+pub struct Explanation<'a> {
+    input: &'a [u8],
+    spans: Vec<FieldSpan>,
+}
+
+/// Describe the encoding of each field of the message in `input`
+///
+/// Each line contains the name and type of a field, its BER class, tag and form, its offset and
+/// the lengths of its header and content, followed by the value for simple types. Encodings
+/// which are valid BER but not DER (indefinite or long-form lengths, integers with leading
+/// bytes) are flagged, since they are frequent causes of interoperability issues:
+///
+/// ```text
+/// message: SEQUENCE [UNIVERSAL 16, constructed] at 0, header 2, length 38
+///   version: INTEGER [UNIVERSAL 2, primitive] at 2, header 2, length 1 = 0
+/// ...
+/// ```
+pub fn explain(input: &[u8]) -> Explanation<'_> {
+    Explanation {
+        input,
+        spans: field_spans(input),
+    }
+}
+
+impl<'a> Explanation<'a> {
+    /// Return the fields of the message
+    pub fn spans(&self) -> &[FieldSpan] {
+        &self.spans
+    }
+}
+
+impl<'a> fmt::Display for Explanation<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for span in &self.spans {
+            write!(f, "{:width$}{}: ", "", span.name(), width = 2 * span.depth)?;
+            let ber = match span.ber {
+                Some(ber) => ber,
+                None => {
+                    writeln!(f, "{} bytes at {}, not decoded", span.len, span.offset)?;
+                    continue;
+                }
+            };
+            write_type(f, span, &ber)?;
+            let class = match ber.class {
+                Class::Universal => "UNIVERSAL",
+                Class::Application => "APPLICATION",
+                Class::ContextSpecific => "CONTEXT-SPECIFIC",
+                Class::Private => "PRIVATE",
+            };
+            let form = if ber.constructed {
+                "constructed"
+            } else {
+                "primitive"
+            };
+            write!(
+                f,
+                " [{} {}, {}] at {}, header {}, length {}",
+                class, ber.tag.0, form, span.offset, span.header_len, span.len
+            )?;
+            let header = &self.input[span.offset..][..span.header_len];
+            if let Some(note) = length_note(header, span.len) {
+                write!(f, " ({})", note)?;
+            }
+            if !ber.constructed {
+                let content = &self.input[span.offset + span.header_len..][..span.len];
+                if is_integer(&ber) && !is_minimal_integer(content) {
+                    f.write_str(" (non-minimal integer)")?;
+                }
+                write_value(f, &ber, content)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Describe the length of an element, if it is not encoded in the shortest form
+fn length_note(header: &[u8], len: usize) -> Option<&'static str> {
+    // skip the identifier octets (high tag numbers continue while bit 8 is set)
+    let tag_len = if header[0] & 0x1f == 0x1f {
+        1 + header[1..].iter().take_while(|&&b| b & 0x80 != 0).count() + 1
+    } else {
+        1
+    };
+    let first = *header.get(tag_len)?;
+    if first == 0x80 {
+        return Some("indefinite length");
+    }
+    let num_bytes = usize::from(first & 0x7f);
+    let minimal = if len < 0x80 {
+        0
+    } else {
+        (usize::BITS - len.leading_zeros()).div_ceil(8) as usize
+    };
+    if first & 0x80 != 0 && num_bytes > minimal {
+        Some("non-minimal length")
+    } else {
+        None
+    }
+}
+
+fn is_integer(ber: &BerInfo) -> bool {
+    matches!(
+        (ber.class, ber.tag.0),
+        (Class::Universal, 2 | 10) | (Class::Application, 1 | 2 | 3 | 6)
+    )
+}
+
+/// Test if an integer has no redundant leading byte
+fn is_minimal_integer(content: &[u8]) -> bool {
+    match content {
+        [0x00, next, ..] => next & 0x80 != 0,
+        [0xff, next, ..] => next & 0x80 == 0,
+        _ => true,
+    }
+}
+
+fn write_type(f: &mut fmt::Formatter, span: &FieldSpan, ber: &BerInfo) -> fmt::Result {
+    let name = match (ber.class, ber.tag.0) {
+        (Class::Universal, 2) => "INTEGER",
+        (Class::Universal, 4) => "OCTET STRING",
+        (Class::Universal, 5) => "NULL",
+        (Class::Universal, 6) => "OBJECT IDENTIFIER",
+        (Class::Universal, 9) => "REAL",
+        (Class::Universal, 10) => "ENUMERATED",
+        (Class::Universal, 16) => "SEQUENCE",
+        (Class::Application, 0) => "IpAddress",
+        (Class::Application, 1) => "Counter32",
+        (Class::Application, 2) => "Gauge32",
+        (Class::Application, 3) => "TimeTicks",
+        (Class::Application, 4) => "Opaque",
+        (Class::Application, 5) => "NsapAddress",
+        (Class::Application, 6) => "Counter64",
+        (Class::ContextSpecific, 0) if span.name() == "value" => "noSuchObject",
+        (Class::ContextSpecific, 1) if span.name() == "value" => "noSuchInstance",
+        (Class::ContextSpecific, 2) if span.name() == "value" => "endOfMibView",
+        (Class::ContextSpecific, tag) if span.name() == "pdu" => {
+            return write!(f, "{:?}", PduType(tag));
+        }
+        (Class::Universal, tag) => return write!(f, "[UNIVERSAL {}]", tag),
+        (Class::Application, tag) => return write!(f, "[APPLICATION {}]", tag),
+        (Class::ContextSpecific, tag) => return write!(f, "[{}]", tag),
+        (Class::Private, tag) => return write!(f, "[PRIVATE {}]", tag),
+    };
+    f.write_str(name)
+}
+
+fn write_value(f: &mut fmt::Formatter, ber: &BerInfo, content: &[u8]) -> fmt::Result {
+    match (ber.class, ber.tag.0) {
+        (Class::Universal, 2 | 10) | (Class::Application, 1 | 2 | 3 | 6)
+            if !content.is_empty() && content.len() <= 9 =>
+        {
+            let sign: i128 = if content[0] & 0x80 != 0 { -1 } else { 0 };
+            let n = content
+                .iter()
+                .fold(sign, |acc, &b| (acc << 8) | i128::from(b));
+            write!(f, " = {}", n)
+        }
+        (Class::Universal, 4) => match format_octet_string(content).as_printable() {
+            Some(_) => write!(f, " = {}", format_octet_string(content)),
+            None => Ok(()),
+        },
+        (Class::Universal, 6) => write!(f, " = {}", format_oid(&Oid::new(Cow::Borrowed(content)))),
+        (Class::Application, 0) if content.len() == 4 => write!(
+            f,
+            " = {}.{}.{}.{}",
+            content[0], content[1], content[2], content[3]
+        ),
+        _ => Ok(()),
+    }
+}
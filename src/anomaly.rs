@@ -0,0 +1,246 @@
+//! Aggregation of anomalies
+//!
+//! The checks of this crate (for ex. [`validate_response`](crate::exchange::validate_response)
+//! or [`check_snmp_v3_lengths`](crate::snmpv3::check_snmp_v3_lengths)) return the anomalies
+//! found in each message. [`AnomalySummary`] counts them per kind and per source, to be
+//! reported periodically instead of logging each message.
+//!
+//! Summaries are plain values: in a multi-threaded sensor, each worker records anomalies in its
+//! own summary, and periodically sends a [`snapshot`](AnomalySummary::snapshot) to a collector,
+//! which [`merge`](AnomalySummary::merge)s them:
+//!
+//! ```rust
+//! use snmp_parser::anomaly::AnomalySummary;
+//! use snmp_parser::exchange::ResponseViolation;
+//! use std::net::{IpAddr, Ipv4Addr};
+//! use std::sync::mpsc;
+//! use std::thread;
+//!
+//! let (tx, rx) = mpsc::channel();
+//! let worker = thread::spawn(move || {
+//!     let mut summary = AnomalySummary::new();
+//!     let agent = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+//!     let violation = ResponseViolation::RequestIdMismatch { request: 1, response: 2 };
+//!     summary.record(agent, &[violation]);
+//!     tx.send(summary.snapshot()).expect("collector stopped");
+//! });
+//! worker.join().expect("worker failed");
+//!
+//! let mut total = AnomalySummary::new();
+//! for snapshot in rx {
+//!     total.merge(&snapshot);
+//! }
+//! assert_eq!(total.count("RequestIdMismatch"), 1);
+//! ```
+
+use crate::audit::{AccessViolation, SetDuplicate, TypeMismatch};
+#[cfg(feature = "v3")]
+use crate::exchange::ReportReason;
+use crate::exchange::ResponseViolation;
+use crate::snmp::CommunityViolation;
+#[cfg(feature = "v3")]
+use crate::snmpv3::LengthAnomaly;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use core::net::IpAddr;
+#[cfg(feature = "std")]
+use std::net::IpAddr;
+
+/// An anomaly which can be counted by [`AnomalySummary`]
+pub trait AnomalyKind {
+    /// Return the name of the kind of anomaly (usually the name of the variant)
+    fn anomaly_kind(&self) -> &'static str;
+}
+
+impl AnomalyKind for &'static str {
+    fn anomaly_kind(&self) -> &'static str {
+        self
+    }
+}
+
+impl AnomalyKind for ResponseViolation {
+    fn anomaly_kind(&self) -> &'static str {
+        match self {
+            ResponseViolation::NotAResponse(_) => "NotAResponse",
+            ResponseViolation::VersionMismatch { .. } => "VersionMismatch",
+            ResponseViolation::RequestIdMismatch { .. } => "RequestIdMismatch",
+            ResponseViolation::VarCountMismatch { .. } => "VarCountMismatch",
+            ResponseViolation::OidMismatch { .. } => "OidMismatch",
+            ResponseViolation::InvalidErrorIndex { .. } => "InvalidErrorIndex",
+            ResponseViolation::V2ErrorStatusInV1(_) => "V2ErrorStatusInV1",
+            ResponseViolation::ExceptionInV1 { .. } => "ExceptionInV1",
+            ResponseViolation::Counter64InV1 { .. } => "Counter64InV1",
+        }
+    }
+}
+
+#[cfg(feature = "v3")]
+impl AnomalyKind for LengthAnomaly {
+    fn anomaly_kind(&self) -> &'static str {
+        match self {
+            LengthAnomaly::Overshoot { .. } => "LengthOvershoot",
+            LengthAnomaly::TrailingData { .. } => "TrailingData",
+        }
+    }
+}
+
+/// Conflicting assignments are counted separately from repeated assignments of the same value
+impl AnomalyKind for SetDuplicate {
+    fn anomaly_kind(&self) -> &'static str {
+        if self.conflicting {
+            "SetConflict"
+        } else {
+            "SetDuplicate"
+        }
+    }
+}
+
+impl AnomalyKind for AccessViolation {
+    fn anomaly_kind(&self) -> &'static str {
+        "AccessViolation"
+    }
+}
+
+impl AnomalyKind for TypeMismatch {
+    fn anomaly_kind(&self) -> &'static str {
+        "TypeMismatch"
+    }
+}
+
+impl AnomalyKind for CommunityViolation {
+    fn anomaly_kind(&self) -> &'static str {
+        match self {
+            CommunityViolation::Empty => "EmptyCommunity",
+            CommunityViolation::TooShort { .. } => "CommunityTooShort",
+            CommunityViolation::TooLong { .. } => "CommunityTooLong",
+        }
+    }
+}
+
+/// Reports are not always anomalies (engine ID discovery and time synchronization are normal
+/// exchanges), but counting their reasons per source shows misconfigured managers
+#[cfg(feature = "v3")]
+impl AnomalyKind for ReportReason {
+    fn anomaly_kind(&self) -> &'static str {
+        match self {
+            ReportReason::UnsupportedSecLevel => "UnsupportedSecLevel",
+            ReportReason::NotInTimeWindow => "NotInTimeWindow",
+            ReportReason::UnknownUserName => "UnknownUserName",
+            ReportReason::UnknownEngineId => "UnknownEngineId",
+            ReportReason::WrongDigest => "WrongDigest",
+            ReportReason::DecryptionError => "DecryptionError",
+            ReportReason::UnknownSecurityModel => "UnknownSecurityModel",
+            ReportReason::InvalidMsg => "InvalidMsg",
+            ReportReason::UnknownPduHandler => "UnknownPduHandler",
+            ReportReason::Other => "UnknownReport",
+        }
+    }
+}
+
+/// Counts of anomalies, per kind and per source
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AnomalySummary {
+    messages: u64,
+    anomalous_messages: u64,
+    by_kind: BTreeMap<&'static str, u64>,
+    by_source: BTreeMap<IpAddr, BTreeMap<&'static str, u64>>,
+}
+
+impl AnomalySummary {
+    pub fn new() -> Self {
+        AnomalySummary::default()
+    }
+
+    /// Record the anomalies found in a message received from `source`
+    ///
+    /// The message is counted even if `anomalies` is empty.
+    pub fn record<A: AnomalyKind>(&mut self, source: IpAddr, anomalies: &[A]) {
+        self.messages += 1;
+        if anomalies.is_empty() {
+            return;
+        }
+        self.anomalous_messages += 1;
+        let by_source = self.by_source.entry(source).or_default();
+        for anomaly in anomalies {
+            let kind = anomaly.anomaly_kind();
+            *self.by_kind.entry(kind).or_default() += 1;
+            *by_source.entry(kind).or_default() += 1;
+        }
+    }
+
+    /// Add the counts of `other` to this summary
+    pub fn merge(&mut self, other: &AnomalySummary) {
+        self.messages += other.messages;
+        self.anomalous_messages += other.anomalous_messages;
+        for (kind, count) in &other.by_kind {
+            *self.by_kind.entry(kind).or_default() += count;
+        }
+        for (source, kinds) in &other.by_source {
+            let by_source = self.by_source.entry(*source).or_default();
+            for (kind, count) in kinds {
+                *by_source.entry(kind).or_default() += count;
+            }
+        }
+    }
+
+    /// Return the current counts, and reset this summary
+    pub fn snapshot(&mut self) -> AnomalySummary {
+        core::mem::take(self)
+    }
+
+    /// Number of messages recorded
+    pub fn messages(&self) -> u64 {
+        self.messages
+    }
+
+    /// Number of messages with at least one anomaly
+    pub fn anomalous_messages(&self) -> u64 {
+        self.anomalous_messages
+    }
+
+    /// Total number of anomalies
+    pub fn total(&self) -> u64 {
+        self.by_kind.values().sum()
+    }
+
+    /// Number of anomalies of `kind`
+    pub fn count(&self, kind: &str) -> u64 {
+        self.by_kind.get(kind).copied().unwrap_or(0)
+    }
+
+    /// Return the number of anomalies of each kind, sorted by kind
+    pub fn kinds(&self) -> impl Iterator<Item = (&'static str, u64)> + '_ {
+        self.by_kind.iter().map(|(kind, count)| (*kind, *count))
+    }
+
+    /// Number of anomalies in the messages of `source`
+    pub fn source_count(&self, source: IpAddr) -> u64 {
+        self.by_source
+            .get(&source)
+            .map_or(0, |kinds| kinds.values().sum())
+    }
+
+    /// Return the number of anomalies of each kind, in the messages of `source`
+    pub fn source_kinds(&self, source: IpAddr) -> impl Iterator<Item = (&'static str, u64)> + '_ {
+        self.by_source
+            .get(&source)
+            .into_iter()
+            .flat_map(|kinds| kinds.iter().map(|(kind, count)| (*kind, *count)))
+    }
+
+    /// Return the sources with anomalies, and their number of anomalies, sorted by address
+    pub fn sources(&self) -> impl Iterator<Item = (IpAddr, u64)> + '_ {
+        self.by_source
+            .iter()
+            .map(|(source, kinds)| (*source, kinds.values().sum()))
+    }
+
+    /// Return the `n` sources with the most anomalies, in decreasing order
+    pub fn top_sources(&self, n: usize) -> Vec<(IpAddr, u64)> {
+        let mut sources: Vec<_> = self.sources().collect();
+        sources.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        sources.truncate(n);
+        sources
+    }
+}
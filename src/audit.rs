@@ -0,0 +1,317 @@
+//! Auditing of configuration changes
+//!
+//! SetRequest PDUs modify the configuration of agents, and are usually logged by monitoring
+//! tools. [`SetAudit`] extracts the modified variables, with their new values formatted for
+//! humans, and optionally the names of the objects:
+//!
+//! ```rust
+//! use snmp_parser::audit::SetAudit;
+//! use snmp_parser::{oid, Oid};
+//! # use snmp_parser::*;
+//!
+//! # let pdu = SnmpPdu::Generic(SnmpGenericPdu {
+//! #     pdu_type: PduType::SetRequest,
+//! #     req_id: 1,
+//! #     err: ErrorStatus::NoError,
+//! #     err_index: 0,
+//! #     var: vec![SnmpVariable {
+//! #         oid: oid!(1.3.6.1.2.1.1.5.0),
+//! #         val: VarBindValue::Value(ObjectSyntax::String(b"router-1")),
+//! #     }],
+//! # });
+//! let names = |oid: &Oid<'_>| match oid.to_id_string().as_str() {
+//!     "1.3.6.1.2.1.1.5.0" => Some("sysName.0".to_string()),
+//!     _ => None,
+//! };
+//! for entry in SetAudit::with_names(&names).entries(&pdu) {
+//!     // sysName.0 (1.3.6.1.2.1.1.5.0) = OCTET STRING: "router-1"
+//!     println!("{}", entry);
+//! }
+//! ```
+//!
+//! [`check_set_duplicates`] detects SetRequests assigning the same variable more than once,
+//! which are invalid (see [RFC3416](https://tools.ietf.org/html/rfc3416) section 4.2.5) and
+//! may be handled differently by each agent.
+//!
+//! When object definitions are loaded in a [`MibRegistry`], [`check_set_access`] reports the
+//! variables which cannot be modified according to the MAX-ACCESS of their objects, and
+//! [`check_value_types`] reports the values of SetRequests and Responses which do not match the
+//! SYNTAX of their objects.
+
+use crate::format::{format_oid, format_varbind_value};
+use crate::generic::SnmpGenericMessage;
+use crate::mib::{MaxAccess, MibRegistry, MibSyntax};
+use crate::snmp::{ErrorStatus, PduType, SnmpPdu};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use asn1_rs::Oid;
+use core::fmt;
+
+/// Source of names for OIDs (for ex. loaded from MIB files)
+///
+/// This is implemented for closures taking an OID and returning an optional name.
+pub trait OidNames {
+    fn oid_name(&self, oid: &Oid) -> Option<String>;
+}
+
+impl<F> OidNames for F
+where
+    F: Fn(&Oid) -> Option<String>,
+{
+    fn oid_name(&self, oid: &Oid) -> Option<String> {
+        self(oid)
+    }
+}
+
+/// A variable modified by a SetRequest
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SetAuditEntry {
+    pub oid: Oid<'static>,
+    /// Name of the object, if known
+    pub name: Option<String>,
+    /// New value, formatted with [`format_varbind_value`]
+    pub value: String,
+    /// Type of the new value (see `VarBindValue::type_name`)
+    pub value_type: &'static str,
+}
+
+/// Format the entry on one line, for ex. `sysName.0 (1.3.6.1.2.1.1.5.0) = OCTET STRING: "r1"`
+impl fmt::Display for SetAuditEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name {
+            Some(ref name) => write!(f, "{} ({})", name, format_oid(&self.oid))?,
+            None => write!(f, "{}", format_oid(&self.oid))?,
+        }
+        write!(f, " = {}: {}", self.value_type, self.value)
+    }
+}
+
+/// Extraction of the variables modified by SetRequest PDUs
+#[derive(Clone, Copy, Default)]
+pub struct SetAudit<'n> {
+    names: Option<&'n dyn OidNames>,
+}
+
+impl<'n> fmt::Debug for SetAudit<'n> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SetAudit")
+            .field("names", &self.names.is_some())
+            .finish()
+    }
+}
+
+impl<'n> SetAudit<'n> {
+    /// Create an extractor without object names
+    pub fn new() -> Self {
+        SetAudit::default()
+    }
+
+    /// Create an extractor resolving object names using `names`
+    pub fn with_names(names: &'n dyn OidNames) -> Self {
+        SetAudit { names: Some(names) }
+    }
+
+    /// Return the variables modified by `pdu`, in order
+    ///
+    /// The result is empty if `pdu` is not a SetRequest.
+    pub fn entries(&self, pdu: &SnmpPdu) -> Vec<SetAuditEntry> {
+        let set = match pdu {
+            SnmpPdu::Generic(set) if set.pdu_type == PduType::SetRequest => set,
+            _ => return Vec::new(),
+        };
+        set.var
+            .iter()
+            .map(|var| SetAuditEntry {
+                oid: var.oid.to_owned(),
+                name: self.names.and_then(|n| n.oid_name(&var.oid)),
+                value: format_varbind_value(&var.val).to_string(),
+                value_type: var.val.type_name(),
+            })
+            .collect()
+    }
+
+    /// Return the variables modified by `msg`
+    ///
+    /// The result is empty if the message is not a SetRequest, or if it is encrypted.
+    pub fn message_entries(&self, msg: &SnmpGenericMessage) -> Vec<SetAuditEntry> {
+        match msg.pdu() {
+            Some(pdu) => self.entries(pdu),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// A variable assigned more than once in a SetRequest
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SetDuplicate {
+    pub oid: Oid<'static>,
+    /// Index of the first assignment (starting from 0)
+    pub first: usize,
+    /// Index of the repeated assignment
+    pub index: usize,
+    /// `true` if the values are different
+    pub conflicting: bool,
+}
+
+/// Return the variables of a SetRequest which are assigned more than once
+///
+/// Each repeated assignment is compared with the first assignment of the variable. The result
+/// is empty if `pdu` is not a SetRequest.
+pub fn check_set_duplicates(pdu: &SnmpPdu) -> Vec<SetDuplicate> {
+    let set = match pdu {
+        SnmpPdu::Generic(set) if set.pdu_type == PduType::SetRequest => set,
+        _ => return Vec::new(),
+    };
+    let mut duplicates = Vec::new();
+    for (index, var) in set.var.iter().enumerate() {
+        let first = match set.var[..index].iter().position(|v| v.oid == var.oid) {
+            Some(first) => first,
+            None => continue,
+        };
+        let conflicting = set.var[first].val != var.val;
+        snmp_anomaly!(
+            oid = %format_oid(&var.oid),
+            conflicting,
+            "variable assigned more than once in SetRequest"
+        );
+        duplicates.push(SetDuplicate {
+            oid: var.oid.to_owned(),
+            first,
+            index,
+            conflicting,
+        });
+    }
+    duplicates
+}
+
+/// A variable of a SetRequest which cannot be modified
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccessViolation {
+    /// Index of the variable (starting from 0)
+    pub index: usize,
+    pub oid: Oid<'static>,
+    /// Name of the object
+    pub name: String,
+    pub max_access: MaxAccess,
+}
+
+impl AccessViolation {
+    /// Return the error an agent should answer with (`notWritable` for read-only objects,
+    /// `noAccess` for objects which are not readable)
+    pub fn error_status(&self) -> ErrorStatus {
+        match self.max_access {
+            MaxAccess::ReadOnly => ErrorStatus::NotWritable,
+            _ => ErrorStatus::NoAccess,
+        }
+    }
+}
+
+/// Result of [`check_set_access`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SetAccessReport {
+    /// Variables of objects which are not writable
+    pub violations: Vec<AccessViolation>,
+    /// Indexes of the variables of unknown objects (not checked)
+    pub unknown: Vec<usize>,
+}
+
+impl SetAccessReport {
+    /// Test if no violation was found
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Check the variables of a SetRequest against the MAX-ACCESS of their objects
+///
+/// Writes to `read-only`, `accessible-for-notify` or `not-accessible` objects are reported as
+/// violations. The report is empty if `pdu` is not a SetRequest.
+pub fn check_set_access(registry: &MibRegistry, pdu: &SnmpPdu) -> SetAccessReport {
+    let mut report = SetAccessReport::default();
+    let set = match pdu {
+        SnmpPdu::Generic(set) if set.pdu_type == PduType::SetRequest => set,
+        _ => return report,
+    };
+    for (index, var) in set.var.iter().enumerate() {
+        let object = match registry.lookup(&var.oid) {
+            Some((object, _)) => object,
+            None => {
+                report.unknown.push(index);
+                continue;
+            }
+        };
+        if object.max_access.is_writable() {
+            continue;
+        }
+        snmp_anomaly!(
+            oid = %format_oid(&var.oid),
+            max_access = %object.max_access,
+            "SetRequest on object which is not writable"
+        );
+        report.violations.push(AccessViolation {
+            index,
+            oid: var.oid.to_owned(),
+            name: object.name.clone(),
+            max_access: object.max_access,
+        });
+    }
+    report
+}
+
+/// A variable whose value does not match the SYNTAX of its object (`wrongType`)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeMismatch {
+    /// Index of the variable (starting from 0)
+    pub index: usize,
+    pub oid: Oid<'static>,
+    /// Name of the object
+    pub name: String,
+    pub expected: MibSyntax,
+    /// Type of the value (see `VarBindValue::type_name`)
+    pub found: &'static str,
+}
+
+/// Check the types of the values of a SetRequest or Response against the SYNTAX of their objects
+///
+/// In Responses, NULL values and exceptions are not checked. In SetRequests, they are reported
+/// as mismatches, since they cannot be assigned. Variables of unknown objects are ignored, and
+/// the result is empty for other PDU types.
+pub fn check_value_types(registry: &MibRegistry, pdu: &SnmpPdu) -> Vec<TypeMismatch> {
+    let pdu = match pdu {
+        SnmpPdu::Generic(pdu)
+            if pdu.pdu_type == PduType::SetRequest || pdu.pdu_type == PduType::Response =>
+        {
+            pdu
+        }
+        _ => return Vec::new(),
+    };
+    let is_set = pdu.pdu_type == PduType::SetRequest;
+    let mut mismatches = Vec::new();
+    for (index, var) in pdu.var.iter().enumerate() {
+        let object = match registry.lookup(&var.oid) {
+            Some((object, _)) => object,
+            None => continue,
+        };
+        let matches = match var.val.value() {
+            Some(value) => object.syntax.matches(value),
+            None => !is_set,
+        };
+        if matches {
+            continue;
+        }
+        snmp_anomaly!(
+            oid = %format_oid(&var.oid),
+            expected = %object.syntax,
+            found = var.val.type_name(),
+            "value type does not match object syntax"
+        );
+        mismatches.push(TypeMismatch {
+            index,
+            oid: var.oid.to_owned(),
+            name: object.name.clone(),
+            expected: object.syntax,
+            found: var.val.type_name(),
+        });
+    }
+    mismatches
+}
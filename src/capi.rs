@@ -0,0 +1,350 @@
+//! C API
+//!
+//! This module exposes a flat C API, to use the parser from C engines (for ex. IDS). The
+//! declarations are in `include/snmp_parser.h`.
+//!
+//! To build the shared library, enable the `capi` feature and request a `cdylib`:
+//!
+//! ```text
+//! cargo rustc --release --features capi --crate-type cdylib
+//! ```
+//!
+//! A message is parsed using `snmp_parse`, which returns an opaque handle. All data returned by
+//! accessors is owned by the handle, and remains valid until `snmp_message_free` is called.
+//! Return codes are the numeric values of [`SnmpErrorCode`], `0` meaning success.
+
+#![allow(unsafe_code)]
+
+use crate::error::{SnmpError, SnmpErrorCode};
+use crate::format::format_oid;
+use crate::generic::{parse_snmp_generic_message, SnmpGenericMessage};
+use crate::snmp::{ObjectSyntax, VarBindValue};
+use crate::snmpv3::SecurityParameters;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+/// Return code for success
+pub const SNMP_OK: c_int = 0;
+/// Return code for invalid arguments (null pointers)
+pub const SNMP_ERR_INVALID_ARGUMENT: c_int = -1;
+/// Return code when data was too short (incomplete message)
+pub const SNMP_ERR_INCOMPLETE: c_int = -2;
+
+/// Value types of variable bindings, as returned by `snmp_message_varbind_type`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnmpValueType {
+    Unspecified = 0,
+    NoSuchObject = 1,
+    NoSuchInstance = 2,
+    EndOfMibView = 3,
+    Number = 4,
+    String = 5,
+    Object = 6,
+    BitString = 7,
+    Empty = 8,
+    IpAddress = 9,
+    Counter32 = 10,
+    Gauge32 = 11,
+    TimeTicks = 12,
+    Opaque = 13,
+    NsapAddress = 14,
+    Counter64 = 15,
+    UInteger32 = 16,
+    Real = 17,
+    Enumerated = 18,
+    Unknown = 255,
+}
+
+#[derive(Debug)]
+struct CVarBind {
+    oid: CString,
+    value_type: SnmpValueType,
+}
+
+/// A parsed message, as seen by C code
+///
+/// Parsed messages borrow the input buffer, so the relevant fields are copied.
+#[derive(Debug)]
+pub struct SnmpCMessage {
+    version: u32,
+    community: Option<Vec<u8>>,
+    user: Option<Vec<u8>>,
+    pdu_type: i32,
+    request_id: i64,
+    encrypted: bool,
+    varbinds: Vec<CVarBind>,
+}
+
+impl<'a> From<&VarBindValue<'a>> for SnmpValueType {
+    fn from(value: &VarBindValue<'a>) -> Self {
+        match value {
+            VarBindValue::Unspecified => SnmpValueType::Unspecified,
+            VarBindValue::NoSuchObject => SnmpValueType::NoSuchObject,
+            VarBindValue::NoSuchInstance => SnmpValueType::NoSuchInstance,
+            VarBindValue::EndOfMibView => SnmpValueType::EndOfMibView,
+            VarBindValue::Value(v) => match v {
+                ObjectSyntax::Number(_) => SnmpValueType::Number,
+                ObjectSyntax::String(_) => SnmpValueType::String,
+                ObjectSyntax::Object(_) => SnmpValueType::Object,
+                ObjectSyntax::BitString(_) => SnmpValueType::BitString,
+                ObjectSyntax::Empty => SnmpValueType::Empty,
+                ObjectSyntax::Real(_) => SnmpValueType::Real,
+                ObjectSyntax::Enumerated(_) => SnmpValueType::Enumerated,
+                ObjectSyntax::IpAddress(_) => SnmpValueType::IpAddress,
+                ObjectSyntax::Counter32(_) => SnmpValueType::Counter32,
+                ObjectSyntax::Gauge32(_) => SnmpValueType::Gauge32,
+                ObjectSyntax::TimeTicks(_) => SnmpValueType::TimeTicks,
+                ObjectSyntax::Opaque(_) => SnmpValueType::Opaque,
+                ObjectSyntax::NsapAddress(_) => SnmpValueType::NsapAddress,
+                ObjectSyntax::Counter64(_) => SnmpValueType::Counter64,
+                ObjectSyntax::UInteger32(_) => SnmpValueType::UInteger32,
+                ObjectSyntax::UnknownSimple(_)
+                | ObjectSyntax::UnknownApplication(_)
+                | ObjectSyntax::Custom(_) => SnmpValueType::Unknown,
+            },
+        }
+    }
+}
+
+impl<'a> From<&SnmpGenericMessage<'a>> for SnmpCMessage {
+    fn from(msg: &SnmpGenericMessage<'a>) -> Self {
+        let pdu = msg.pdu();
+        let user = match msg {
+            SnmpGenericMessage::V3(m) => match m.security_params {
+                SecurityParameters::USM(ref usm) => Some(usm.msg_user_name.as_bytes().to_vec()),
+                _ => None,
+            },
+            #[cfg(feature = "v2u")]
+            SnmpGenericMessage::V2u(m) => Some(m.parameters.user_name.to_vec()),
+            _ => None,
+        };
+        let varbinds = pdu
+            .map(|pdu| {
+                pdu.vars_iter()
+                    .map(|v| CVarBind {
+                        // a dotted OID never contains a nul byte
+                        oid: CString::new(format_oid(&v.oid).to_string()).unwrap_or_default(),
+                        value_type: SnmpValueType::from(&v.val),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        SnmpCMessage {
+            version: msg.version(),
+            community: msg.community().map(|c| c.as_bytes().to_vec()),
+            user,
+            pdu_type: pdu.map(|p| p.pdu_type().0 as i32).unwrap_or(-1),
+            request_id: pdu
+                .and_then(|p| p.request_id())
+                .map(i64::from)
+                .unwrap_or(-1),
+            encrypted: pdu.is_none(),
+            varbinds,
+        }
+    }
+}
+
+fn error_code(e: &SnmpError) -> c_int {
+    e.code().0 as c_int
+}
+
+/// Parse a SNMP message (of any version)
+///
+/// On success, `*result` is set to a newly allocated message (to be freed using
+/// `snmp_message_free`) and `SNMP_OK` is returned. Otherwise, `*result` is set to null and an
+/// error code is returned.
+///
+/// # Safety
+///
+/// `buf` must point to `len` readable bytes, and `result` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn snmp_parse(
+    buf: *const u8,
+    len: usize,
+    result: *mut *mut SnmpCMessage,
+) -> c_int {
+    if result.is_null() {
+        return SNMP_ERR_INVALID_ARGUMENT;
+    }
+    *result = ptr::null_mut();
+    if buf.is_null() {
+        return SNMP_ERR_INVALID_ARGUMENT;
+    }
+    let input = std::slice::from_raw_parts(buf, len);
+    match parse_snmp_generic_message(input) {
+        Ok((_, msg)) => {
+            let msg = SnmpCMessage::from(&msg);
+            *result = Box::into_raw(Box::new(msg));
+            SNMP_OK
+        }
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => error_code(&e),
+        Err(nom::Err::Incomplete(_)) => SNMP_ERR_INCOMPLETE,
+    }
+}
+
+/// Free a message returned by `snmp_parse`
+///
+/// # Safety
+///
+/// `msg` must be null, or a pointer returned by `snmp_parse` and not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn snmp_message_free(msg: *mut SnmpCMessage) {
+    if !msg.is_null() {
+        drop(Box::from_raw(msg));
+    }
+}
+
+/// Return the version (as raw-encoded: 0 for SNMPv1, 1 for SNMPv2c, 3 for SNMPv3)
+///
+/// # Safety
+///
+/// `msg` must be a valid pointer returned by `snmp_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn snmp_message_version(msg: *const SnmpCMessage) -> u32 {
+    (*msg).version
+}
+
+unsafe fn bytes_out(bytes: Option<&[u8]>, len: *mut usize) -> *const u8 {
+    match bytes {
+        Some(b) => {
+            if !len.is_null() {
+                *len = b.len();
+            }
+            b.as_ptr()
+        }
+        None => {
+            if !len.is_null() {
+                *len = 0;
+            }
+            ptr::null()
+        }
+    }
+}
+
+/// Return the community (not nul-terminated), and store its length in `*len`
+///
+/// Return null if the message is not community-based.
+///
+/// # Safety
+///
+/// `msg` must be a valid pointer returned by `snmp_parse`, and `len` must be null or valid.
+#[no_mangle]
+pub unsafe extern "C" fn snmp_message_community(
+    msg: *const SnmpCMessage,
+    len: *mut usize,
+) -> *const u8 {
+    bytes_out((*msg).community.as_deref(), len)
+}
+
+/// Return the USM or SNMPv2u user name (not nul-terminated), and store its length in `*len`
+///
+/// Return null if the message does not use the User-based Security Model.
+///
+/// # Safety
+///
+/// `msg` must be a valid pointer returned by `snmp_parse`, and `len` must be null or valid.
+#[no_mangle]
+pub unsafe extern "C" fn snmp_message_user(msg: *const SnmpCMessage, len: *mut usize) -> *const u8 {
+    bytes_out((*msg).user.as_deref(), len)
+}
+
+/// Return the PDU type, or -1 if the PDU is encrypted
+///
+/// # Safety
+///
+/// `msg` must be a valid pointer returned by `snmp_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn snmp_message_pdu_type(msg: *const SnmpCMessage) -> i32 {
+    (*msg).pdu_type
+}
+
+/// Return the request ID, or -1 if not available (SNMPv1 trap, or encrypted PDU)
+///
+/// # Safety
+///
+/// `msg` must be a valid pointer returned by `snmp_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn snmp_message_request_id(msg: *const SnmpCMessage) -> i64 {
+    (*msg).request_id
+}
+
+/// Return 1 if the PDU is encrypted, 0 otherwise
+///
+/// # Safety
+///
+/// `msg` must be a valid pointer returned by `snmp_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn snmp_message_is_encrypted(msg: *const SnmpCMessage) -> c_int {
+    c_int::from((*msg).encrypted)
+}
+
+/// Return the number of variable bindings
+///
+/// # Safety
+///
+/// `msg` must be a valid pointer returned by `snmp_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn snmp_message_varbind_count(msg: *const SnmpCMessage) -> usize {
+    (*msg).varbinds.len()
+}
+
+/// Return the OID of variable binding `index`, as a nul-terminated dotted string
+///
+/// Return null if `index` is out of range.
+///
+/// # Safety
+///
+/// `msg` must be a valid pointer returned by `snmp_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn snmp_message_varbind_oid(
+    msg: *const SnmpCMessage,
+    index: usize,
+) -> *const c_char {
+    match (&*msg).varbinds.get(index) {
+        Some(v) => v.oid.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+/// Return the value type of variable binding `index`
+///
+/// Return `Unknown` if `index` is out of range.
+///
+/// # Safety
+///
+/// `msg` must be a valid pointer returned by `snmp_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn snmp_message_varbind_type(
+    msg: *const SnmpCMessage,
+    index: usize,
+) -> SnmpValueType {
+    match (&*msg).varbinds.get(index) {
+        Some(v) => v.value_type,
+        None => SnmpValueType::Unknown,
+    }
+}
+
+/// Return the name of an error code, as a static nul-terminated string
+///
+/// Return null if the code is unknown.
+#[no_mangle]
+pub extern "C" fn snmp_error_name(code: c_int) -> *const c_char {
+    // names are static, with a nul byte appended
+    macro_rules! cstr {
+        ($s:expr) => {
+            concat!($s, "\0").as_ptr() as *const c_char
+        };
+    }
+    match code {
+        SNMP_OK => cstr!("Ok"),
+        SNMP_ERR_INVALID_ARGUMENT => cstr!("InvalidArgument"),
+        SNMP_ERR_INCOMPLETE => cstr!("Incomplete"),
+        c if c > 0 => match SnmpErrorCode(c as u32).name_with_nul() {
+            Some(name) => name.as_ptr() as *const c_char,
+            None => ptr::null(),
+        },
+        _ => ptr::null(),
+    }
+}
@@ -0,0 +1,336 @@
+//! Translation between SNMPv1 and SNMPv2c messages
+//!
+//! This module implements the translation rules from
+//! [RFC3584](https://tools.ietf.org/html/rfc3584) (Coexistence between Version 1, Version 2, and
+//! Version 3 of the Internet-standard Network Management Framework), as used by proxy
+//! forwarders, or to normalize messages before analysis:
+//!   - notification parameters (section 3)
+//!   - PDU types, error status and exceptions (section 4)
+//!
+//! Translation consumes the message, so variables are moved and not copied.
+
+use crate::snmp::{
+    ErrorStatus, NetworkAddress, ObjectSyntax, PduType, SnmpGenericPdu, SnmpMessage, SnmpPdu,
+    SnmpTrapPdu, SnmpVariable, TrapType, VarBindValue,
+};
+use crate::trap::SNMP_TRAP_OID;
+use alloc::vec::Vec;
+use asn1_rs::{oid, Oid};
+use core::convert::TryFrom;
+use core::fmt;
+#[cfg(not(feature = "std"))]
+use core::net::Ipv4Addr;
+#[cfg(feature = "std")]
+use std::net::Ipv4Addr;
+
+/// `sysUpTime.0`
+#[rustfmt::skip]
+pub const SYS_UPTIME_OID: Oid<'static> = oid!(1.3.6.1.2.1.1.3.0);
+
+/// `snmpTraps`: prefix of the generic traps (coldStart, warmStart, etc.)
+#[rustfmt::skip]
+pub const SNMP_TRAPS_OID: Oid<'static> = oid!(1.3.6.1.6.3.1.1.5);
+
+/// `snmpTrapAddress.0`: the address of the SNMPv1 agent
+#[rustfmt::skip]
+pub const SNMP_TRAP_ADDRESS_OID: Oid<'static> = oid!(1.3.6.1.6.3.18.1.3.0);
+
+/// `snmpTrapCommunity.0`: the community of the SNMPv1 trap, added by proxies
+#[rustfmt::skip]
+pub const SNMP_TRAP_COMMUNITY_OID: Oid<'static> = oid!(1.3.6.1.6.3.18.1.4.0);
+
+/// `snmpTrapEnterprise.0`: the enterprise of the SNMPv1 trap
+#[rustfmt::skip]
+pub const SNMP_TRAP_ENTERPRISE_OID: Oid<'static> = oid!(1.3.6.1.6.3.1.1.4.3.0);
+
+/// Error returned when a message cannot be translated
+#[derive(Debug, PartialEq)]
+pub enum CoexistenceError {
+    /// The message does not have the expected version
+    InvalidVersion { found: u32 },
+    /// This type of PDU has no equivalent in the target version (for ex. InformRequest)
+    UnsupportedPdu(PduType),
+    /// The variable at `index` (starting from 0) has a Counter64 value, which cannot be
+    /// represented in SNMPv1
+    Counter64 { index: usize },
+    /// The notification has no valid trap identification
+    InvalidTrap,
+}
+
+impl fmt::Display for CoexistenceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CoexistenceError::InvalidVersion { found } => {
+                write!(f, "Cannot translate message with version {}", found)
+            }
+            CoexistenceError::UnsupportedPdu(pdu_type) => {
+                write!(f, "Cannot translate PDU of type {:?}", pdu_type)
+            }
+            CoexistenceError::Counter64 { index } => {
+                write!(f, "Cannot translate Counter64 value of variable {}", index)
+            }
+            CoexistenceError::InvalidTrap => f.write_str("Invalid trap identification"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CoexistenceError {}
+
+/// Translate a SNMPv1 message to SNMPv2c
+///
+/// Traps are translated using [`trap_v1_to_v2`], with a request ID of 0. Other PDUs are kept:
+/// SNMPv1 error status values are also valid in SNMPv2c.
+pub fn v1_to_v2c(msg: SnmpMessage) -> Result<SnmpMessage, CoexistenceError> {
+    if msg.version != 0 {
+        return Err(CoexistenceError::InvalidVersion { found: msg.version });
+    }
+    let pdu = match msg.pdu {
+        SnmpPdu::TrapV1(trap) => SnmpPdu::Generic(trap_v1_to_v2(trap, 0)?),
+        SnmpPdu::Bulk(_) => {
+            return Err(CoexistenceError::UnsupportedPdu(PduType::GetBulkRequest));
+        }
+        pdu => pdu,
+    };
+    Ok(SnmpMessage {
+        version: 1,
+        community: msg.community,
+        pdu,
+    })
+}
+
+/// Translate a SNMPv2c message to SNMPv1
+///
+///   - GetBulkRequest PDUs are translated to GetNextRequest PDUs (RFC3584 section 4.1.2)
+///   - SNMPv2 traps are translated using [`trap_v2_to_v1`]
+///   - responses are translated using [`response_v2_to_v1`]
+///   - SetRequest PDUs with Counter64 values, InformRequest and Report PDUs cannot be translated
+pub fn v2c_to_v1(msg: SnmpMessage) -> Result<SnmpMessage, CoexistenceError> {
+    if msg.version != 1 {
+        return Err(CoexistenceError::InvalidVersion { found: msg.version });
+    }
+    let pdu = match msg.pdu {
+        SnmpPdu::Bulk(bulk) => SnmpPdu::Generic(SnmpGenericPdu {
+            pdu_type: PduType::GetNextRequest,
+            req_id: bulk.req_id,
+            err: ErrorStatus::NoError,
+            err_index: 0,
+            var: bulk.var,
+        }),
+        SnmpPdu::Generic(pdu) => match pdu.pdu_type {
+            PduType::GetRequest | PduType::GetNextRequest => SnmpPdu::Generic(pdu),
+            PduType::SetRequest => {
+                if let Some(index) = pdu.var.iter().position(is_counter64) {
+                    return Err(CoexistenceError::Counter64 { index });
+                }
+                SnmpPdu::Generic(pdu)
+            }
+            PduType::Response => SnmpPdu::Generic(response_v2_to_v1(pdu)),
+            PduType::TrapV2 => SnmpPdu::TrapV1(trap_v2_to_v1(pdu)?),
+            pdu_type => return Err(CoexistenceError::UnsupportedPdu(pdu_type)),
+        },
+        pdu @ SnmpPdu::TrapV1(_) => pdu,
+    };
+    Ok(SnmpMessage {
+        version: 0,
+        community: msg.community,
+        pdu,
+    })
+}
+
+/// Translate a SNMPv2 error status to the closest SNMPv1 value (RFC3584 section 4.3)
+pub fn error_status_v2_to_v1(err: ErrorStatus) -> ErrorStatus {
+    match err {
+        ErrorStatus::WrongValue
+        | ErrorStatus::WrongEncoding
+        | ErrorStatus::WrongType
+        | ErrorStatus::WrongLength
+        | ErrorStatus::InconsistentValue => ErrorStatus::BadValue,
+        ErrorStatus::NoAccess
+        | ErrorStatus::NotWritable
+        | ErrorStatus::NoCreation
+        | ErrorStatus::InconsistentName
+        | ErrorStatus::AuthorizationError => ErrorStatus::NoSuchName,
+        ErrorStatus::ResourceUnavailable | ErrorStatus::CommitFailed | ErrorStatus::UndoFailed => {
+            ErrorStatus::GenErr
+        }
+        err => err,
+    }
+}
+
+/// Translate a SNMPv2 Response PDU to SNMPv1 (RFC3584 section 4.4)
+///
+/// The error status is translated using [`error_status_v2_to_v1`]. If there is no error, the
+/// first variable with an exception or a Counter64 value is reported as `noSuchName`.
+/// Exceptions and Counter64 values are replaced by NULL values, which can be encoded in SNMPv1.
+pub fn response_v2_to_v1(mut pdu: SnmpGenericPdu) -> SnmpGenericPdu {
+    pdu.err = error_status_v2_to_v1(pdu.err);
+    for (index, var) in pdu.var.iter_mut().enumerate() {
+        let invalid = matches!(
+            var.val,
+            VarBindValue::NoSuchObject
+                | VarBindValue::NoSuchInstance
+                | VarBindValue::EndOfMibView
+                | VarBindValue::Value(ObjectSyntax::Counter64(_))
+        );
+        if invalid {
+            if pdu.err == ErrorStatus::NoError {
+                pdu.err = ErrorStatus::NoSuchName;
+                pdu.err_index = index as u32 + 1;
+            }
+            var.val = VarBindValue::Unspecified;
+        }
+    }
+    pdu
+}
+
+/// Translate a SNMPv1 trap to a SNMPv2 trap (RFC3584 section 3.1)
+///
+/// The variables are `sysUpTime.0`, `snmpTrapOID.0`, the variables of the SNMPv1 trap, then
+/// `snmpTrapAddress.0` and `snmpTrapEnterprise.0` unless already present.
+///
+/// `snmpTrapCommunity.0` is not added: the community is kept in the message.
+pub fn trap_v1_to_v2(trap: SnmpTrapPdu, req_id: u32) -> Result<SnmpGenericPdu, CoexistenceError> {
+    let trap_oid = trap_v1_oid(&trap)?;
+    let mut var = Vec::with_capacity(trap.var.len() + 4);
+    var.push(SnmpVariable {
+        oid: SYS_UPTIME_OID,
+        val: VarBindValue::Value(ObjectSyntax::TimeTicks(trap.timestamp)),
+    });
+    var.push(SnmpVariable {
+        oid: SNMP_TRAP_OID,
+        val: VarBindValue::Value(ObjectSyntax::Object(trap_oid)),
+    });
+    let has_address = trap.var.iter().any(|v| v.oid == SNMP_TRAP_ADDRESS_OID);
+    let has_enterprise = trap.var.iter().any(|v| v.oid == SNMP_TRAP_ENTERPRISE_OID);
+    var.extend(trap.var);
+    if !has_address {
+        var.push(SnmpVariable {
+            oid: SNMP_TRAP_ADDRESS_OID,
+            val: VarBindValue::Value(ObjectSyntax::IpAddress(trap.agent_addr)),
+        });
+    }
+    if !has_enterprise {
+        var.push(SnmpVariable {
+            oid: SNMP_TRAP_ENTERPRISE_OID,
+            val: VarBindValue::Value(ObjectSyntax::Object(trap.enterprise)),
+        });
+    }
+    Ok(SnmpGenericPdu {
+        pdu_type: PduType::TrapV2,
+        req_id,
+        err: ErrorStatus::NoError,
+        err_index: 0,
+        var,
+    })
+}
+
+/// Return the `snmpTrapOID.0` value equivalent to a SNMPv1 trap (RFC3584 section 3.1.2)
+///
+/// This is `enterprise.0.specific-trap` for enterprise-specific traps, and `snmpTraps.N` (where
+/// N is generic-trap + 1) for generic traps. This can be used to identify SNMPv1 and SNMPv2
+/// notifications the same way. See also `SnmpTrapPdu::notification_oid`.
+pub fn trap_v1_oid(trap: &SnmpTrapPdu) -> Result<Oid<'static>, CoexistenceError> {
+    match trap.generic_trap.0 {
+        g @ 0..=5 => append_arcs(&SNMP_TRAPS_OID, &[u64::from(g) + 1]),
+        6 => append_arcs(&trap.enterprise, &[0, u64::from(trap.specific_trap)]),
+        _ => Err(CoexistenceError::InvalidTrap),
+    }
+}
+
+/// Translate a SNMPv2 trap to a SNMPv1 trap (RFC3584 section 3.2)
+///
+/// The SNMPv1 fields are built from `sysUpTime.0`, `snmpTrapOID.0`, and if present
+/// `snmpTrapEnterprise.0` and `snmpTrapAddress.0`. These variables are removed, so translating
+/// a trap to SNMPv2 and back gives the original trap. If `snmpTrapAddress.0` is not present,
+/// the agent address is `0.0.0.0`.
+///
+/// Variables with Counter64 values are removed.
+pub fn trap_v2_to_v1(pdu: SnmpGenericPdu) -> Result<SnmpTrapPdu, CoexistenceError> {
+    let trap_oid = pdu
+        .var
+        .iter()
+        .filter(|v| v.oid == SNMP_TRAP_OID)
+        .find_map(|v| match v.val {
+            VarBindValue::Value(ObjectSyntax::Object(ref oid)) => Some(oid.to_owned()),
+            _ => None,
+        })
+        .ok_or(CoexistenceError::InvalidTrap)?;
+    let mut enterprise = None;
+    let mut agent_addr = NetworkAddress::IPv4(Ipv4Addr::new(0, 0, 0, 0));
+    let mut timestamp = 0;
+    let mut var = Vec::with_capacity(pdu.var.len());
+    for v in pdu.var {
+        if v.oid == SYS_UPTIME_OID || v.oid == SNMP_TRAP_OID {
+            if let VarBindValue::Value(ObjectSyntax::TimeTicks(t)) = v.val {
+                timestamp = t;
+            }
+            continue;
+        }
+        match v.val {
+            VarBindValue::Value(ObjectSyntax::Object(oid))
+                if v.oid == SNMP_TRAP_ENTERPRISE_OID && enterprise.is_none() =>
+            {
+                enterprise = Some(oid);
+            }
+            VarBindValue::Value(ObjectSyntax::IpAddress(addr))
+                if v.oid == SNMP_TRAP_ADDRESS_OID =>
+            {
+                agent_addr = addr;
+            }
+            VarBindValue::Value(ObjectSyntax::Counter64(_)) => (),
+            _ => var.push(v),
+        }
+    }
+    let (enterprise, generic_trap, specific_trap) = trap_oid_to_v1(&trap_oid, enterprise)?;
+    Ok(SnmpTrapPdu {
+        enterprise,
+        agent_addr,
+        generic_trap,
+        specific_trap,
+        timestamp,
+        var,
+    })
+}
+
+/// Decompose a `snmpTrapOID.0` value into the SNMPv1 enterprise, generic-trap and specific-trap
+/// fields (RFC3584 section 3.2)
+///
+/// Generic traps (`snmpTraps.1` to `snmpTraps.6`) have the enterprise `enterprise` if provided
+/// (usually the value of `snmpTrapEnterprise.0`), or else `snmpTraps`. Other traps are
+/// enterprise-specific: the last arc is the specific-trap, and the enterprise is the remaining
+/// prefix, without the `0` arc if present.
+pub fn trap_oid_to_v1<'a>(
+    trap_oid: &Oid,
+    enterprise: Option<Oid<'a>>,
+) -> Result<(Oid<'a>, TrapType, u32), CoexistenceError> {
+    let arcs: Vec<u64> = match trap_oid.iter() {
+        Some(arcs) => arcs.collect(),
+        None => return Err(CoexistenceError::InvalidTrap),
+    };
+    let is_generic =
+        trap_oid.starts_with(&SNMP_TRAPS_OID) && arcs.len() == 10 && (1..=6).contains(&arcs[9]);
+    if is_generic {
+        let enterprise = enterprise.unwrap_or(SNMP_TRAPS_OID);
+        return Ok((enterprise, TrapType(arcs[9] as u8 - 1), 0));
+    }
+    let n = arcs.len();
+    if n < 3 {
+        return Err(CoexistenceError::InvalidTrap);
+    }
+    let specific = u32::try_from(arcs[n - 1]).map_err(|_| CoexistenceError::InvalidTrap)?;
+    let prefix = if arcs[n - 2] == 0 { n - 2 } else { n - 1 };
+    let enterprise = Oid::from(&arcs[..prefix]).map_err(|_| CoexistenceError::InvalidTrap)?;
+    Ok((enterprise, TrapType::ENTERPRISE_SPECIFIC, specific))
+}
+
+fn is_counter64(var: &SnmpVariable) -> bool {
+    matches!(var.val, VarBindValue::Value(ObjectSyntax::Counter64(_)))
+}
+
+/// Build a new OID, by appending `suffix` to `oid`
+fn append_arcs(oid: &Oid, suffix: &[u64]) -> Result<Oid<'static>, CoexistenceError> {
+    let mut arcs: Vec<u64> = oid.iter().ok_or(CoexistenceError::InvalidTrap)?.collect();
+    arcs.extend_from_slice(suffix);
+    Oid::from(&arcs).map_err(|_| CoexistenceError::InvalidTrap)
+}
@@ -0,0 +1,115 @@
+//! Parser configuration
+//!
+//! [`ParserConfig`] holds [`ApplicationDecoders`] for vendor-specific APPLICATION tags. Messages
+//! parsed with [`parse_snmp_generic_message_with_config`] return values with these tags as
+//! [`ObjectSyntax::Custom`], instead of [`ObjectSyntax::UnknownApplication`]:
+//!
+//! ```rust
+//! use snmp_parser::config::{parse_snmp_generic_message_with_config, ParserConfig};
+//! use snmp_parser::{ObjectSyntax, VarBindValue};
+//!
+//! # fn main() {
+//! let mut config = ParserConfig::new();
+//! config.register_application(30, |data| Ok(data.len()));
+//!
+//! # let bytes = [
+//! #     0x30, 0x28, 0x02, 0x01, 0x01, 0x04, 0x06, 0x70, 0x75, 0x62, 0x6c, 0x69, 0x63, 0xa2, 0x1b,
+//! #     0x02, 0x01, 0x01, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00, 0x30, 0x10, 0x30, 0x0e, 0x06, 0x08,
+//! #     0x2b, 0x06, 0x01, 0x04, 0x01, 0x81, 0x7d, 0x00, 0x5e, 0x02, 0x00, 0xd7,
+//! # ];
+//! let (_, msg) = parse_snmp_generic_message_with_config(&bytes, &config).expect("parsing failed");
+//! let var = msg.pdu().and_then(|pdu| pdu.vars_iter().next()).expect("no variable");
+//! match var.val {
+//!     VarBindValue::Value(ObjectSyntax::Custom(ref value)) => {
+//!         assert_eq!(value.downcast_ref(), Some(&2usize));
+//!     }
+//!     ref val => panic!("unexpected value {:?}", val),
+//! }
+//! # }
+//! ```
+
+use crate::decoders::ApplicationDecoders;
+use crate::error::SnmpError;
+use crate::generic::{parse_snmp_generic_message_with_options, SnmpGenericMessage};
+use crate::snmp::{CustomValue, ObjectSyntax, ParseOptions, SnmpPdu, VarBindValue};
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::format;
+use core::any::Any;
+use nom::{Err, IResult};
+
+/// Configuration of the parser
+#[derive(Debug, Default)]
+pub struct ParserConfig {
+    /// Accept the invalid encodings sent by some agents, instead of returning an error
+    ///
+    /// In SNMPv3 messages, `msgFlags` values longer than 1 byte are truncated to their first
+    /// byte, and negative or non-minimal `msgMaxSize`, `msgAuthoritativeEngineBoots` and
+    /// `msgAuthoritativeEngineTime` values are converted to unsigned. An anomaly is emitted for
+    /// each accepted value. Disabled by default.
+    pub lenient: bool,
+    decoders: ApplicationDecoders<Box<dyn Any + Send + Sync>>,
+}
+
+impl ParserConfig {
+    pub fn new() -> Self {
+        ParserConfig::default()
+    }
+
+    /// Register the decoder for APPLICATION `tag`, replacing any previous decoder
+    ///
+    /// See [`ApplicationDecoders::register`]. Values with this tag are returned as
+    /// [`ObjectSyntax::Custom`], containing the value returned by `decoder`.
+    pub fn register_application<T, F>(&mut self, tag: u32, decoder: F)
+    where
+        T: Any + Send + Sync,
+        F: Fn(&[u8]) -> Result<T, SnmpError> + Send + Sync + 'static,
+    {
+        self.decoders.register(tag, move |data| {
+            Ok(Box::new(decoder(data)?) as Box<dyn Any + Send + Sync>)
+        });
+    }
+
+    /// Decode the values of `pdu` with a registered APPLICATION tag
+    fn decode_custom_values(&self, pdu: &mut SnmpPdu, input: &[u8]) -> Result<(), SnmpError> {
+        if self.decoders.is_empty() {
+            return Ok(());
+        }
+        for (index, var) in pdu.var_list_mut().iter_mut().enumerate() {
+            let custom = match var.val {
+                VarBindValue::Value(ref value @ ObjectSyntax::UnknownApplication(ref any)) => {
+                    match self.decoders.decode(value) {
+                        Some(Ok(decoded)) => CustomValue::new(any.clone(), decoded),
+                        Some(Err(e)) => {
+                            let field = Cow::Owned(format!("varbind[{}].value", index));
+                            return Err(e.at(field, any.data, input));
+                        }
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            var.val = VarBindValue::Value(ObjectSyntax::Custom(custom));
+        }
+        Ok(())
+    }
+}
+
+/// Parse a SNMP message, of any supported version, using `config`
+///
+/// See [`parse_snmp_generic_message`]. Errors returned by the registered decoders are returned
+/// as `nom::Err::Failure`, with the location of the value.
+pub fn parse_snmp_generic_message_with_config<'a>(
+    i: &'a [u8],
+    config: &ParserConfig,
+) -> IResult<&'a [u8], SnmpGenericMessage<'a>, SnmpError> {
+    let opts = ParseOptions {
+        lenient: config.lenient,
+        ..ParseOptions::DEFAULT
+    };
+    let (rem, mut msg) = parse_snmp_generic_message_with_options(i, &opts)?;
+    if let Some(pdu) = msg.pdu_mut() {
+        config.decode_custom_values(pdu, i).map_err(Err::Failure)?;
+    }
+    Ok((rem, msg))
+}
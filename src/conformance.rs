@@ -0,0 +1,246 @@
+//! Conformance test vectors
+//!
+//! The test vectors of this crate are exposed, with the results expected from a conforming
+//! decoder, so other implementations (for ex. re-implementations, or bindings using the C API)
+//! can check that they decode the same messages identically. This includes invalid messages,
+//! which must be rejected with the same error.
+//!
+//! The decoder under test is called by [`run`] for each vector, and returns an [`Outcome`]:
+//!
+//! ```rust
+//! use snmp_parser::conformance::{run, Outcome};
+//!
+//! # fn main() {
+//! // the decoder under test, here the parser of this crate
+//! let report = run(|data: &[u8]| Outcome::of(snmp_parser::SnmpGenericMessage::parse(data)));
+//! assert!(report.is_success(), "failures: {:?}", report.failures);
+//! # }
+//! ```
+
+use crate::encode::SnmpEncode;
+use crate::error::{SnmpError, SnmpErrorCode};
+use crate::format::format_oid;
+use crate::generic::SnmpGenericMessage;
+use crate::snmp::PduType;
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A message, and the expected decoding result
+#[derive(Clone, Debug)]
+pub struct TestVector {
+    pub name: &'static str,
+    pub data: Cow<'static, [u8]>,
+    /// Error expected for invalid messages, or `None` if the message is valid
+    pub error: Option<SnmpErrorCode>,
+}
+
+impl TestVector {
+    /// Return the expected result of decoding this vector
+    ///
+    /// For valid messages, this is the result of the parser of this crate.
+    pub fn expected(&self) -> Outcome {
+        match self.error {
+            Some(code) => Outcome::Rejected(code),
+            None => Outcome::of(SnmpGenericMessage::parse(&self.data)),
+        }
+    }
+}
+
+/// Summary of a decoded message, compared between implementations
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageSummary {
+    /// Version, as raw-encoded: 0 for SNMPv1, 1 for SNMPv2c, 3 for SNMPv3
+    pub version: u32,
+    /// Number of bytes read
+    pub len: usize,
+    /// PDU type, or `None` if the PDU is encrypted
+    pub pdu_type: Option<PduType>,
+    /// Request ID, or `None` for SNMPv1 traps and encrypted PDUs
+    pub request_id: Option<u32>,
+    /// Variable bindings: OID in dotted form, and BER encoding of the value (in canonical form,
+    /// see [`SnmpEncode`])
+    pub varbinds: Vec<(String, Vec<u8>)>,
+}
+
+/// Result of decoding a test vector
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Decoded(MessageSummary),
+    /// The message was rejected, with this error code
+    Rejected(SnmpErrorCode),
+}
+
+impl Outcome {
+    /// Build the outcome of [`SnmpGenericMessage::parse`]
+    ///
+    /// Only the error code is compared, not the location of the error.
+    pub fn of(res: Result<(SnmpGenericMessage<'_>, usize), SnmpError>) -> Outcome {
+        let (msg, len) = match res {
+            Ok(r) => r,
+            Err(e) => return Outcome::Rejected(e.code()),
+        };
+        let pdu = msg.pdu();
+        let varbinds = pdu
+            .map(|pdu| {
+                pdu.vars_iter()
+                    .map(|var| (format_oid(&var.oid).to_string(), var.val.encode()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Outcome::Decoded(MessageSummary {
+            version: msg.version(),
+            len,
+            pdu_type: pdu.map(|pdu| pdu.pdu_type()),
+            request_id: pdu.and_then(|pdu| pdu.request_id()),
+            varbinds,
+        })
+    }
+}
+
+/// A test vector for which the decoder returned an unexpected result
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Failure {
+    pub name: &'static str,
+    pub expected: Outcome,
+    pub found: Outcome,
+}
+
+/// Results of [`run`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// Number of vectors decoded as expected
+    pub passed: usize,
+    pub failures: Vec<Failure>,
+}
+
+impl Report {
+    /// Return `true` if all vectors were decoded as expected
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+static SNMPV1_REQ: &[u8] = include_bytes!("../assets/snmpv1_req.bin");
+static SNMPV1_TRAP_COLDSTART: &[u8] = include_bytes!("../assets/snmpv1_trap_coldstart.bin");
+static SNMPV2C_GET_RESPONSE: &[u8] = include_bytes!("../assets/snmpv2c-get-response.bin");
+static SNMPV2C_GET_RESPONSE_100: &[u8] = include_bytes!("../assets/snmpv2c-get-response-100.bin");
+static SNMPV3_REPORT: &[u8] = include_bytes!("../assets/snmpv3-report.bin");
+static SNMPV3_REQ: &[u8] = include_bytes!("../assets/snmpv3_req.bin");
+static SNMPV3_REQ_ENCRYPTED: &[u8] = include_bytes!("../assets/snmpv3_req_encrypted.bin");
+
+/// Return a copy of `data`, with the bytes at `offset` replaced by `bytes`
+fn patch(data: &[u8], offset: usize, bytes: &[u8]) -> Cow<'static, [u8]> {
+    let mut data = data.to_vec();
+    data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    Cow::Owned(data)
+}
+
+/// A value nested deeper than `MAX_DEPTH`, in a varbind
+fn nested_value() -> Cow<'static, [u8]> {
+    let depth = crate::snmp::MAX_DEPTH + 1;
+    let mut value = Vec::new();
+    for level in 0..depth {
+        value.extend_from_slice(&[0x30, (2 * (depth - level - 1)) as u8]);
+    }
+    let mut data = SNMPV1_REQ[..0x26].to_vec();
+    data.extend_from_slice(&value);
+    // adjust the lengths of the enclosing elements (the value replaces a NULL)
+    for offset in [0x01, 0x0e, 0x19, 0x1b] {
+        data[offset] += value.len() as u8 - 2;
+    }
+    Cow::Owned(data)
+}
+
+/// Return the test vectors
+pub fn vectors() -> Vec<TestVector> {
+    let valid = |name, data| TestVector {
+        name,
+        data: Cow::Borrowed(data),
+        error: None,
+    };
+    let invalid = |name, data, code| TestVector {
+        name,
+        data,
+        error: Some(code),
+    };
+    let mut indefinite = [0x30, 0x80].to_vec();
+    indefinite.extend_from_slice(&SNMPV2C_GET_RESPONSE[2..]);
+    indefinite.extend_from_slice(&[0x00, 0x00]);
+    let truncated = &SNMPV2C_GET_RESPONSE[..SNMPV2C_GET_RESPONSE.len() - 1];
+    vec![
+        valid("snmpv1_req", SNMPV1_REQ),
+        valid("snmpv1_trap_coldstart", SNMPV1_TRAP_COLDSTART),
+        valid("snmpv2c-get-response", SNMPV2C_GET_RESPONSE),
+        valid("snmpv2c-get-response-100", SNMPV2C_GET_RESPONSE_100),
+        valid("snmpv3-report", SNMPV3_REPORT),
+        valid("snmpv3_req", SNMPV3_REQ),
+        valid("snmpv3_req_encrypted", SNMPV3_REQ_ENCRYPTED),
+        TestVector {
+            name: "indefinite-length",
+            data: Cow::Owned(indefinite),
+            error: None,
+        },
+        // security parameters of unknown models are not decoded
+        TestVector {
+            name: "unknown-security-model",
+            data: patch(SNMPV3_REQ, 0x17, &[0x07]),
+            error: None,
+        },
+        invalid("empty", Cow::Borrowed(&[]), SnmpErrorCode::InvalidMessage),
+        invalid(
+            "not-a-sequence",
+            Cow::Borrowed(&[0x02, 0x01, 0x00]),
+            SnmpErrorCode::InvalidMessage,
+        ),
+        invalid(
+            "truncated",
+            Cow::Borrowed(truncated),
+            SnmpErrorCode::InvalidMessage,
+        ),
+        invalid(
+            "invalid-version",
+            patch(SNMPV1_REQ, 0x04, &[0x07]),
+            SnmpErrorCode::InvalidVersion,
+        ),
+        invalid(
+            "invalid-pdu-type",
+            patch(SNMPV1_REQ, 0x0d, &[0xaf]),
+            SnmpErrorCode::InvalidPduType,
+        ),
+        invalid(
+            "invalid-ipaddress-length",
+            patch(SNMPV1_REQ, 0x26, &[0x40, 0x00]),
+            SnmpErrorCode::BerError,
+        ),
+        invalid(
+            "invalid-usm-parameters",
+            patch(SNMPV3_REQ, 0x1a, &[0x31]),
+            SnmpErrorCode::InvalidSecurityModel,
+        ),
+        invalid("max-depth", nested_value(), SnmpErrorCode::MaxDepthExceeded),
+    ]
+}
+
+/// Decode all test vectors with `decoder`, and compare the results to the expected results
+pub fn run<F>(mut decoder: F) -> Report
+where
+    F: FnMut(&[u8]) -> Outcome,
+{
+    let mut report = Report::default();
+    for vector in vectors() {
+        let expected = vector.expected();
+        let found = decoder(&vector.data);
+        if found == expected {
+            report.passed += 1;
+        } else {
+            report.failures.push(Failure {
+                name: vector.name,
+                expected,
+                found,
+            });
+        }
+    }
+    report
+}
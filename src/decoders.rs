@@ -0,0 +1,102 @@
+//! Decoders for vendor-specific application types
+//!
+//! Values with an APPLICATION tag not defined by the SMI are returned by the parser as
+//! [`ObjectSyntax::UnknownApplication`]. Vendors use private tags for their own types:
+//! applications can register decoders for these tags in [`ApplicationDecoders`], and decode the
+//! values after parsing:
+//!
+//! ```rust
+//! use snmp_parser::decoders::ApplicationDecoders;
+//! use snmp_parser::error::SnmpError;
+//! use snmp_parser::ObjectSyntax;
+//!
+//! /// A vendor type: a temperature in tenths of degrees, in 2 bytes
+//! fn decode_temperature(data: &[u8]) -> Result<f32, SnmpError> {
+//!     match data {
+//!         &[hi, lo] => Ok(i16::from_be_bytes([hi, lo]) as f32 / 10.0),
+//!         _ => Err(SnmpError::InvalidPdu),
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let mut decoders = ApplicationDecoders::new();
+//! decoders.register(30, decode_temperature);
+//!
+//! # let bytes = [0x5e, 0x02, 0x00, 0xd7];
+//! # let (_, any) = asn1_rs::FromBer::from_ber(&bytes).unwrap();
+//! # let value = ObjectSyntax::UnknownApplication(any);
+//! // `value` was read from a variable binding
+//! assert_eq!(decoders.decode(&value), Some(Ok(21.5)));
+//! # }
+//! ```
+
+use crate::error::SnmpError;
+use crate::snmp::ObjectSyntax;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use asn1_rs::Class;
+use core::fmt;
+
+/// A decoder for the content of values with a given APPLICATION tag
+pub type ApplicationDecoder<T> = Box<dyn Fn(&[u8]) -> Result<T, SnmpError> + Send + Sync>;
+
+/// Decoders for APPLICATION tags unknown to the parser, indexed by tag number
+///
+/// All decoders return the same type `T`, usually an enum of the vendor types.
+pub struct ApplicationDecoders<T> {
+    decoders: BTreeMap<u32, ApplicationDecoder<T>>,
+}
+
+impl<T> ApplicationDecoders<T> {
+    pub fn new() -> Self {
+        ApplicationDecoders {
+            decoders: BTreeMap::new(),
+        }
+    }
+
+    /// Register the decoder for APPLICATION `tag`, replacing any previous decoder
+    ///
+    /// Decoders for tags defined by the SMI (0 to 7) are never used, since these values are
+    /// decoded by the parser.
+    pub fn register<F>(&mut self, tag: u32, decoder: F)
+    where
+        F: Fn(&[u8]) -> Result<T, SnmpError> + Send + Sync + 'static,
+    {
+        self.decoders.insert(tag, Box::new(decoder));
+    }
+
+    /// Decode `value`, if it has an unknown APPLICATION tag with a registered decoder
+    ///
+    /// Return `None` for other values.
+    pub fn decode(&self, value: &ObjectSyntax) -> Option<Result<T, SnmpError>> {
+        let info = value.unknown_tag_info()?;
+        if info.class != Class::Application {
+            return None;
+        }
+        let decoder = self.decoders.get(&info.tag.0)?;
+        Some(decoder(info.data))
+    }
+
+    /// Return the number of registered decoders
+    pub fn len(&self) -> usize {
+        self.decoders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.decoders.is_empty()
+    }
+}
+
+impl<T> Default for ApplicationDecoders<T> {
+    fn default() -> Self {
+        ApplicationDecoders::new()
+    }
+}
+
+impl<T> fmt::Debug for ApplicationDecoders<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ApplicationDecoders")
+            .field("tags", &self.decoders.keys())
+            .finish()
+    }
+}
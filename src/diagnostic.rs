@@ -0,0 +1,141 @@
+//! Integration with the `miette` diagnostic crate
+//!
+//! [`SnmpError`] implements [`Diagnostic`] (code and help text). To get reports pointing at the
+//! offending bytes, attach the parsed input using [`SnmpError::with_input`]: the input is
+//! rendered as an hexdump, and the location of the error (when known) is highlighted.
+//!
+//! ```rust,no_run
+//! use snmp_parser::parse_snmp_v1;
+//!
+//! # let bytes: &[u8] = &[];
+//! if let Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) = parse_snmp_v1(bytes) {
+//!     let report = miette::Report::new(e.with_input(bytes));
+//!     eprintln!("{:?}", report);
+//! }
+//! ```
+
+use crate::error::{SnmpError, SupportedVersions};
+use miette::{Diagnostic, LabeledSpan, SourceCode};
+use std::fmt;
+
+const BYTES_PER_LINE: usize = 16;
+
+impl Diagnostic for SnmpError {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let name = self.code().name().unwrap_or("Unknown");
+        Some(Box::new(format!("snmp_parser::{}", name)))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let help = match self.kind() {
+            SnmpError::InvalidMessage => "a SNMP message must be a BER-encoded SEQUENCE",
+            SnmpError::InvalidVersion { .. } => {
+                let help = format!("the version field must be {}", SupportedVersions);
+                return Some(Box::new(help));
+            }
+            SnmpError::InvalidPduType { .. } => "this PDU type is not allowed for this version",
+            SnmpError::InvalidSecurityModel => {
+                "the security parameters do not match the security model"
+            }
+            SnmpError::MaxDepthExceeded => "values must not use deeply nested encodings",
+            _ => return None,
+        };
+        Some(Box::new(help))
+    }
+}
+
+/// A [`SnmpError`], with the input that was parsed
+///
+/// This type implements [`Diagnostic`], with an hexdump of the input as source code.
+#[derive(Debug)]
+pub struct SnmpErrorReport {
+    error: SnmpError,
+    hexdump: String,
+    len: usize,
+}
+
+impl SnmpError {
+    /// Attach the parsed input to the error, to build a diagnostic report
+    ///
+    /// `input` must be the buffer given to the parsing function, so the error offset matches.
+    pub fn with_input(self, input: &[u8]) -> SnmpErrorReport {
+        SnmpErrorReport {
+            error: self,
+            hexdump: hexdump(input),
+            len: input.len(),
+        }
+    }
+}
+
+impl SnmpErrorReport {
+    pub fn error(&self) -> &SnmpError {
+        &self.error
+    }
+
+    pub fn into_error(self) -> SnmpError {
+        self.error
+    }
+}
+
+impl fmt::Display for SnmpErrorReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl std::error::Error for SnmpErrorReport {}
+
+impl Diagnostic for SnmpErrorReport {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Diagnostic::code(&self.error)
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Diagnostic::help(&self.error)
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.hexdump)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let offset = self.error.offset().filter(|&o| o < self.len)?;
+        let label = match self.error.field() {
+            Some(field) => format!("{}: {}", field, self.error.kind()),
+            None => self.error.kind().to_string(),
+        };
+        // highlight the first byte of the field
+        let position = hexdump_position(offset, offset_width(self.len));
+        let span = LabeledSpan::new(Some(label), position, 2);
+        Some(Box::new(std::iter::once(span)))
+    }
+}
+
+// Each line is the offset, then " xx" for each byte
+fn hexdump(input: &[u8]) -> String {
+    let width = offset_width(input.len());
+    let mut s = String::with_capacity((input.len() / BYTES_PER_LINE + 1) * (width + 50));
+    for (i, line) in input.chunks(BYTES_PER_LINE).enumerate() {
+        s.push_str(&format!("{:0width$x}:", i * BYTES_PER_LINE, width = width));
+        for b in line {
+            s.push_str(&format!(" {:02x}", b));
+        }
+        s.push('\n');
+    }
+    s
+}
+
+fn offset_width(len: usize) -> usize {
+    let mut width = 4;
+    while len.checked_shr(4 * width as u32).unwrap_or(0) != 0 {
+        width += 1;
+    }
+    width
+}
+
+/// Position, in the hexdump text, of the byte at `offset`
+fn hexdump_position(offset: usize, width: usize) -> usize {
+    let line_len = width + 1 + BYTES_PER_LINE * 3 + 1;
+    let (line, col) = (offset / BYTES_PER_LINE, offset % BYTES_PER_LINE);
+    line * line_len + width + 2 + col * 3
+}
@@ -0,0 +1,357 @@
+//! Textual dump formats
+//!
+//! Snapshots of the variables of an agent are commonly stored as text, either in the output
+//! format of the net-snmp `snmpwalk -One` command:
+//!
+//! ```text
+//! .1.3.6.1.2.1.1.5.0 = STRING: "router-1"
+//! .1.3.6.1.2.1.1.3.0 = Timeticks: (970069) 2:41:40.69
+//! ```
+//!
+//! or in the `.snmprec` format of snmpsim, with one `oid|tag|value` entry per line (the tag is
+//! the BER tag of the value, followed by `x` if the value is hex-encoded):
+//!
+//! ```text
+//! 1.3.6.1.2.1.1.5.0|4|router-1
+//! 1.3.6.1.2.1.1.3.0|67|970069
+//! ```
+//!
+//! [`parse_snmpwalk`] and [`parse_snmprec`] read these formats as a list of [`DumpEntry`],
+//! which can for ex. be loaded in a [`Simulator`](crate::simulate::Simulator):
+//!
+//! ```rust
+//! use snmp_parser::dump::{parse_snmprec, snmpwalk_line};
+//! use snmp_parser::simulate::Simulator;
+//!
+//! let entries = parse_snmprec("1.3.6.1.2.1.1.5.0|4|router-1\n").expect("invalid dump");
+//! let mut simulator = Simulator::new();
+//! for entry in &entries {
+//!     simulator.insert(&entry.oid, &entry.value());
+//! }
+//! let line = snmpwalk_line(&entries[0].oid, &entries[0].value());
+//! assert_eq!(line.as_deref(), Some(".1.3.6.1.2.1.1.5.0 = STRING: \"router-1\""));
+//! ```
+//!
+//! [`snmpwalk_line`] and [`snmprec_line`] write a variable in each format. In `snmpwalk`
+//! dumps, values of enumerated INTEGERs may be written by name, like `up(1)`, and long
+//! `Hex-STRING` values may continue on the next lines: both are accepted. Lines which are
+//! exceptions (for ex. `No Such Instance currently exists at this OID`) are ignored.
+
+use crate::format::{format_octet_string, format_oid};
+use crate::simulate::decode_value;
+use crate::snmp::{NetworkAddress, ObjectSyntax};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use asn1_rs::Oid;
+use core::fmt::{self, Write};
+#[cfg(not(feature = "std"))]
+use core::net::Ipv4Addr;
+#[cfg(feature = "std")]
+use std::net::Ipv4Addr;
+
+/// Error returned when a dump cannot be read
+#[derive(Debug, PartialEq, Eq)]
+pub struct DumpError {
+    /// Line of the error, starting from 1
+    pub line: usize,
+}
+
+impl fmt::Display for DumpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid dump entry at line {}", self.line)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DumpError {}
+
+/// A variable read from a dump
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DumpEntry {
+    pub oid: Oid<'static>,
+    /// Encoded value
+    value: Vec<u8>,
+}
+
+impl DumpEntry {
+    pub fn new(oid: &Oid, value: &ObjectSyntax) -> Self {
+        use crate::encode::SnmpEncode;
+        DumpEntry {
+            oid: oid.to_owned(),
+            value: value.encode(),
+        }
+    }
+
+    /// Return the value of the variable
+    pub fn value(&self) -> ObjectSyntax<'_> {
+        // empty strings are decoded as NULL by the parser
+        if self.value == [0x04, 0x00] {
+            return ObjectSyntax::String(b"");
+        }
+        // the value was encoded by `new`, and always decodes
+        decode_value(&self.value).unwrap_or(ObjectSyntax::Empty)
+    }
+}
+
+/// Read the output of `snmpwalk -One`
+///
+/// Empty lines are ignored.
+pub fn parse_snmpwalk(data: &str) -> Result<Vec<DumpEntry>, DumpError> {
+    let mut entries = Vec::new();
+    // (line number, text) of the current entry, with its continuation lines
+    let mut current: Option<(usize, String)> = None;
+    for (idx, line) in data.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if line.starts_with('.') && line.contains(" = ") {
+            if let Some((line, text)) = current.replace((idx + 1, String::from(line))) {
+                entries.extend(parse_snmpwalk_entry(&text).map_err(|_| DumpError { line })?);
+            }
+        } else {
+            match current {
+                Some((_, ref mut text)) => {
+                    text.push('\n');
+                    text.push_str(line);
+                }
+                None => return Err(DumpError { line: idx + 1 }),
+            }
+        }
+    }
+    if let Some((line, text)) = current {
+        entries.extend(parse_snmpwalk_entry(&text).map_err(|_| DumpError { line })?);
+    }
+    Ok(entries)
+}
+
+/// Parse an entry, returning `None` for exceptions
+fn parse_snmpwalk_entry(text: &str) -> Result<Option<DumpEntry>, ()> {
+    let (oid, value) = text.split_once(" = ").ok_or(())?;
+    let oid = parse_oid(oid.trim())?;
+    let value = value.trim();
+    if value.starts_with("No Such ") || value.starts_with("No more variables") {
+        return Ok(None);
+    }
+    if value == "NULL" {
+        return Ok(Some(DumpEntry::new(&oid, &ObjectSyntax::Empty)));
+    }
+    if value == "\"\"" {
+        return Ok(Some(DumpEntry::new(&oid, &ObjectSyntax::String(b""))));
+    }
+    let (kind, value) = value.split_once(':').ok_or(())?;
+    let value = value.trim();
+    let bytes;
+    let syntax = match kind {
+        "INTEGER" => ObjectSyntax::Number(parse_number(enum_value(value))?),
+        "STRING" => {
+            bytes = unquote(value);
+            ObjectSyntax::String(&bytes)
+        }
+        "Hex-STRING" | "BITS" => {
+            bytes = parse_hex(value.split_whitespace())?;
+            ObjectSyntax::String(&bytes)
+        }
+        "OID" => ObjectSyntax::Object(parse_oid(value)?),
+        "IpAddress" => ObjectSyntax::IpAddress(NetworkAddress::IPv4(parse_number(value)?)),
+        "Counter32" => ObjectSyntax::Counter32(parse_number(value)?),
+        "Gauge32" => ObjectSyntax::Gauge32(parse_number(value)?),
+        "UInteger32" => ObjectSyntax::UInteger32(parse_number(value)?),
+        "Counter64" => ObjectSyntax::Counter64(parse_number(value)?),
+        "Timeticks" => {
+            // (970069) 2:41:40.69
+            let ticks = value.strip_prefix('(').and_then(|s| s.split(')').next());
+            ObjectSyntax::TimeTicks(parse_number(ticks.ok_or(())?)?)
+        }
+        _ => return Err(()),
+    };
+    Ok(Some(DumpEntry::new(&oid, &syntax)))
+}
+
+/// Read a `.snmprec` file
+///
+/// Empty lines and lines starting with `#` are ignored. Values using snmpsim variation modules
+/// (tags like `4:numeric`) are not supported.
+pub fn parse_snmprec(data: &str) -> Result<Vec<DumpEntry>, DumpError> {
+    let mut entries = Vec::new();
+    for (idx, line) in data.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let entry = parse_snmprec_entry(line).map_err(|_| DumpError { line: idx + 1 })?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+fn parse_snmprec_entry(line: &str) -> Result<DumpEntry, ()> {
+    let mut fields = line.splitn(3, '|');
+    let (oid, tag, value) = match (fields.next(), fields.next(), fields.next()) {
+        (Some(oid), Some(tag), Some(value)) => (oid, tag, value),
+        _ => return Err(()),
+    };
+    let oid = parse_oid(oid)?;
+    let (tag, hex) = match tag.strip_suffix('x') {
+        Some(tag) => (tag, true),
+        None => (tag, false),
+    };
+    let bytes = if hex {
+        parse_hex(
+            value
+                .as_bytes()
+                .chunks(2)
+                .map(|c| core::str::from_utf8(c).unwrap_or("")),
+        )?
+    } else {
+        value.as_bytes().to_vec()
+    };
+    let syntax = match (tag, hex) {
+        ("4", _) => ObjectSyntax::String(&bytes),
+        ("68", _) => ObjectSyntax::Opaque(&bytes),
+        ("5", _) => ObjectSyntax::Empty,
+        ("2", false) => ObjectSyntax::Number(parse_number(value)?),
+        ("6", false) => ObjectSyntax::Object(parse_oid(value)?),
+        ("64", false) => ObjectSyntax::IpAddress(NetworkAddress::IPv4(parse_number(value)?)),
+        ("64", true) => match bytes[..] {
+            [a, b, c, d] => {
+                ObjectSyntax::IpAddress(NetworkAddress::IPv4(Ipv4Addr::new(a, b, c, d)))
+            }
+            _ => return Err(()),
+        },
+        ("65", false) => ObjectSyntax::Counter32(parse_number(value)?),
+        ("66", false) => ObjectSyntax::Gauge32(parse_number(value)?),
+        ("67", false) => ObjectSyntax::TimeTicks(parse_number(value)?),
+        ("70", false) => ObjectSyntax::Counter64(parse_number(value)?),
+        ("71", false) => ObjectSyntax::UInteger32(parse_number(value)?),
+        _ => return Err(()),
+    };
+    Ok(DumpEntry::new(&oid, &syntax))
+}
+
+/// Format a variable as a line of the output of `snmpwalk -One` (without end of line)
+///
+/// Returns `None` for values which cannot be represented (BIT STRING, NsapAddress, Opaque and
+/// unknown types).
+pub fn snmpwalk_line(oid: &Oid, value: &ObjectSyntax) -> Option<String> {
+    let mut line = format!(".{} = ", format_oid(oid));
+    // writing to a String does not fail
+    let _ = match value {
+        ObjectSyntax::Number(n) => write!(line, "INTEGER: {}", n),
+        ObjectSyntax::String([]) => line.write_str("\"\""),
+        ObjectSyntax::String(s) => {
+            let s = format_octet_string(s);
+            match s.as_printable() {
+                Some(_) => write!(line, "STRING: {}", s),
+                None => write!(line, "{}", s),
+            }
+        }
+        ObjectSyntax::Object(oid) => write!(line, "OID: .{}", format_oid(oid)),
+        ObjectSyntax::Empty => line.write_str("NULL"),
+        ObjectSyntax::IpAddress(NetworkAddress::IPv4(ip)) => write!(line, "IpAddress: {}", ip),
+        ObjectSyntax::Counter32(n) => write!(line, "Counter32: {}", n),
+        ObjectSyntax::Gauge32(n) => write!(line, "Gauge32: {}", n),
+        ObjectSyntax::UInteger32(n) => write!(line, "UInteger32: {}", n),
+        ObjectSyntax::Counter64(n) => write!(line, "Counter64: {}", n),
+        ObjectSyntax::TimeTicks(n) => write_timeticks(&mut line, *n),
+        _ => return None,
+    };
+    Some(line)
+}
+
+/// Format a variable as a line of a `.snmprec` file (without end of line)
+///
+/// Printable strings are written as text, and other strings in hex. Returns `None` for values
+/// which cannot be represented (BIT STRING, NsapAddress and unknown types).
+pub fn snmprec_line(oid: &Oid, value: &ObjectSyntax) -> Option<String> {
+    let mut line = format!("{}|", format_oid(oid));
+    let _ = match value {
+        ObjectSyntax::Number(n) => write!(line, "2|{}", n),
+        ObjectSyntax::String(s) => match format_octet_string(s).as_printable() {
+            Some(text) => write!(line, "4|{}", text),
+            None => write_hex(&mut line, "4x|", s),
+        },
+        ObjectSyntax::Empty => line.write_str("5|"),
+        ObjectSyntax::Object(oid) => write!(line, "6|{}", format_oid(oid)),
+        ObjectSyntax::IpAddress(NetworkAddress::IPv4(ip)) => write!(line, "64|{}", ip),
+        ObjectSyntax::Counter32(n) => write!(line, "65|{}", n),
+        ObjectSyntax::Gauge32(n) => write!(line, "66|{}", n),
+        ObjectSyntax::TimeTicks(n) => write!(line, "67|{}", n),
+        ObjectSyntax::Opaque(s) => write_hex(&mut line, "68x|", s),
+        ObjectSyntax::Counter64(n) => write!(line, "70|{}", n),
+        ObjectSyntax::UInteger32(n) => write!(line, "71|{}", n),
+        _ => return None,
+    };
+    Some(line)
+}
+
+fn write_hex(out: &mut String, prefix: &str, data: &[u8]) -> fmt::Result {
+    out.write_str(prefix)?;
+    data.iter().try_for_each(|b| write!(out, "{:02x}", b))
+}
+
+/// Write TimeTicks like net-snmp, for ex. `Timeticks: (970069) 2:41:40.69`
+fn write_timeticks(out: &mut String, ticks: u32) -> fmt::Result {
+    let (days, rem) = (ticks / 8_640_000, ticks % 8_640_000);
+    write!(out, "Timeticks: ({}) ", ticks)?;
+    match days {
+        0 => (),
+        1 => out.write_str("1 day, ")?,
+        n => write!(out, "{} days, ", n)?,
+    }
+    write!(
+        out,
+        "{}:{:02}:{:02}.{:02}",
+        rem / 360_000,
+        rem / 6000 % 60,
+        rem / 100 % 60,
+        rem % 100
+    )
+}
+
+fn parse_oid(s: &str) -> Result<Oid<'static>, ()> {
+    s.strip_prefix('.').unwrap_or(s).parse().map_err(|_| ())
+}
+
+fn parse_number<T: core::str::FromStr>(s: &str) -> Result<T, ()> {
+    // values may be followed by units, for ex. `INTEGER: 5 seconds`
+    let s = s.split_whitespace().next().ok_or(())?;
+    s.parse().map_err(|_| ())
+}
+
+/// Return the value of an enumerated INTEGER written as `up(1)`
+fn enum_value(s: &str) -> &str {
+    match s.split_once('(') {
+        Some((_, value)) if s.ends_with(')') => &value[..value.len() - 1],
+        _ => s,
+    }
+}
+
+fn parse_hex<'s>(digits: impl Iterator<Item = &'s str>) -> Result<Vec<u8>, ()> {
+    digits
+        .map(|d| match d.len() {
+            2 => u8::from_str_radix(d, 16).map_err(|_| ()),
+            _ => Err(()),
+        })
+        .collect()
+}
+
+/// Remove the quotes and escaping of a STRING value (values without quotes are returned as is)
+fn unquote(s: &str) -> Vec<u8> {
+    let inner = match s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(inner) => inner,
+        None => return s.as_bytes().to_vec(),
+    };
+    let mut out = Vec::with_capacity(inner.len());
+    let mut chars = inner.bytes();
+    while let Some(c) = chars.next() {
+        match c {
+            b'\\' => match chars.next() {
+                Some(c @ (b'"' | b'\\')) => out.push(c),
+                Some(c) => out.extend_from_slice(&[b'\\', c]),
+                None => out.push(b'\\'),
+            },
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,820 @@
+//! SNMP message encoder
+//!
+//! This module implements the encoding of parsed (or manually built) messages, using the
+//! [`SnmpEncode`] trait.
+//!
+//! Lengths are always encoded in definite, minimal form, and integers use the minimal number of
+//! bytes. Unknown values (`UnknownSimple` and `UnknownApplication`) are re-emitted with their
+//! original header and content. `ObjectSyntax::Empty` is encoded as a NULL, which is read back as
+//! `VarBindValue::Unspecified`.
+//!
+//! Example:
+//!
+//! ```rust
+//! # #[cfg(feature = "v1")]
+//! # fn main() {
+//! use snmp_parser::encode::SnmpEncode;
+//! use snmp_parser::parse_snmp_v1;
+//!
+//! static SNMPV1_REQ: &[u8] = include_bytes!("../assets/snmpv1_req.bin");
+//!
+//! let (_, msg) = parse_snmp_v1(SNMPV1_REQ).expect("parsing failed");
+//! let bytes = msg.encode();
+//! let (_, msg2) = parse_snmp_v1(&bytes).expect("parsing failed");
+//! assert_eq!(msg, msg2);
+//! # }
+//! # #[cfg(not(feature = "v1"))]
+//! # fn main() {}
+//! ```
+//!
+//! Exceptions (`noSuchObject`, `noSuchInstance` and `endOfMibView`) are encoded as implicit NULL
+//! values with context-specific tags 0, 1 and 2. They do not exist in SNMPv1: when building
+//! messages (for ex. to answer requests), [`SnmpMessage::encode_checked`] verifies that the
+//! content of the message can be represented in its version.
+//!
+//! Since the encoding is canonical, [`normalize`] can be used to compare messages (for ex. in
+//! signature-matching or diffing tools) regardless of how they were encoded by the sender.
+
+use alloc::vec::Vec;
+use asn1_rs::{Class, Oid};
+#[cfg(feature = "v2u")]
+use core::convert::TryFrom;
+use core::fmt;
+
+use crate::error::SnmpError;
+use crate::generic::SnmpGenericMessage;
+use crate::snmp::*;
+#[cfg(feature = "v2p")]
+use crate::snmpv2p::*;
+#[cfg(feature = "v2u")]
+use crate::snmpv2u::*;
+#[cfg(feature = "v3")]
+use crate::snmpv3::*;
+
+/// Types that can be encoded to BER
+pub trait SnmpEncode {
+    /// Append the encoding of `self` to `out`
+    fn encode_to(&self, out: &mut Vec<u8>);
+
+    /// Return the encoding of `self`
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode_to(&mut out);
+        out
+    }
+
+    /// Return the length of the encoding of `self`
+    ///
+    /// The types of this crate compute the length without encoding, so this can be used to check
+    /// the size of a message (for ex. against `msgMaxSize`) before allocating it.
+    ///
+    /// For a parsed message, this is the number of bytes consumed by the parser, unless the
+    /// message used a non-minimal encoding (see the [module documentation](self)). To read the
+    /// length of a message before parsing it, use
+    /// [`snmp_message_len`](crate::snmp_message_len).
+    fn encoded_len(&self) -> usize;
+}
+
+/// Parse a SNMP message (of any supported version), and encode it again in canonical form
+///
+/// The output is DER, except for unknown values which are copied as is (see the
+/// [module documentation](self)). Bytes after the end of the message are ignored.
+///
+/// Note that normalizing an authenticated SNMPv3 message changes the bytes covered by the
+/// authentication digest, so the digest is not valid anymore if the encoding was not canonical.
+///
+/// # Errors
+///
+/// If the message cannot be parsed, the parsing error is returned.
+pub fn normalize(bytes: &[u8]) -> Result<Vec<u8>, SnmpError> {
+    let (msg, _) = SnmpGenericMessage::parse(bytes)?;
+    Ok(msg.encode())
+}
+
+/// Error returned when an encoded message is larger than allowed
+///
+/// An agent would answer such a request with a `tooBig` error (see
+/// [RFC3416](https://tools.ietf.org/html/rfc3416) section 4.2.1).
+#[derive(Debug, PartialEq, Eq)]
+pub struct TooBigError {
+    /// Length of the encoded message
+    pub len: usize,
+    /// Maximum allowed length
+    pub limit: usize,
+}
+
+impl TooBigError {
+    /// Return the number of bytes in excess
+    pub fn overshoot(&self) -> usize {
+        self.len.saturating_sub(self.limit)
+    }
+}
+
+impl fmt::Display for TooBigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Message too big: {} bytes, limit is {} ({} bytes in excess)",
+            self.len,
+            self.limit,
+            self.overshoot()
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TooBigError {}
+
+/// Error returned when a field is longer than its encoding allows
+#[cfg(feature = "v2u")]
+#[cfg_attr(docsrs, doc(cfg(feature = "v2u")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldLengthError {
+    /// Name of the field
+    pub field: &'static str,
+    /// Length of the field
+    pub len: usize,
+    /// Maximum length of the field
+    pub max: usize,
+}
+
+#[cfg(feature = "v2u")]
+impl fmt::Display for FieldLengthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Field {} too long: {} bytes, maximum is {}",
+            self.field, self.len, self.max
+        )
+    }
+}
+
+#[cfg(all(feature = "v2u", feature = "std"))]
+impl std::error::Error for FieldLengthError {}
+
+/// Error returned when a message contains values which do not exist in its version
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VersionError {
+    /// The PDU type is not defined in this version (for ex. GetBulk in SNMPv1, or the SNMPv1
+    /// Trap in SNMPv2c)
+    PduType(PduType),
+    /// Variable `index` is an exception, which do not exist in SNMPv1
+    Exception {
+        index: usize,
+        exception: VarBindException,
+    },
+    /// Variable `index` is a Counter64 value, which do not exist in SNMPv1
+    Counter64 { index: usize },
+}
+
+impl fmt::Display for VersionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VersionError::PduType(pdu_type) => {
+                write!(f, "PDU type {:?} not allowed in this version", pdu_type)
+            }
+            VersionError::Exception { index, exception } => {
+                write!(
+                    f,
+                    "Exception {:?} in variable {} of SNMPv1 message",
+                    exception, index
+                )
+            }
+            VersionError::Counter64 { index } => {
+                write!(f, "Counter64 value in variable {} of SNMPv1 message", index)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VersionError {}
+
+impl<'a> SnmpMessage<'a> {
+    /// Check that the PDU and its variables can be represented in the version of the message
+    ///
+    /// SNMPv1 messages cannot contain GetBulk, Inform, SNMPv2 Trap or Report PDUs, exceptions or
+    /// Counter64 values (see [RFC3584](https://tools.ietf.org/html/rfc3584) section 4.2.2.1).
+    /// SNMPv2c messages cannot contain SNMPv1 Trap PDUs. `response_v2_to_v1` (in the
+    /// `coexistence` module) translates responses to SNMPv1.
+    pub fn check_version(&self) -> Result<(), VersionError> {
+        let pdu_type = self.pdu.pdu_type();
+        if self.version != 0 {
+            return match pdu_type {
+                PduType::TrapV1 => Err(VersionError::PduType(pdu_type)),
+                _ => Ok(()),
+            };
+        }
+        if !matches!(pdu_type.0, 0..=4) {
+            return Err(VersionError::PduType(pdu_type));
+        }
+        for (index, var) in self.pdu.vars_iter().enumerate() {
+            if let Some(exception) = var.val.exception_kind() {
+                return Err(VersionError::Exception { index, exception });
+            }
+            if let VarBindValue::Value(ObjectSyntax::Counter64(_)) = var.val {
+                return Err(VersionError::Counter64 { index });
+            }
+        }
+        Ok(())
+    }
+
+    /// Encode the message, after checking that its content can be represented in its version
+    /// (see [`check_version`](Self::check_version))
+    pub fn encode_checked(&self) -> Result<Vec<u8>, VersionError> {
+        self.check_version()?;
+        Ok(self.encode())
+    }
+}
+
+#[cfg(feature = "v2u")]
+impl<'a> SnmpV2uParameters<'a> {
+    /// Check that the user name and the authentication digest fit in their one-byte length
+    pub fn check_lengths(&self) -> Result<(), FieldLengthError> {
+        for (field, value) in [
+            ("userName", self.user_name),
+            ("authDigest", self.auth_digest),
+        ] {
+            if u8::try_from(value.len()).is_err() {
+                return Err(FieldLengthError {
+                    field,
+                    len: value.len(),
+                    max: u8::MAX as usize,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "v2u")]
+impl<'a> SnmpV2uMessage<'a> {
+    /// Encode the message, after checking that its parameters can be represented (see
+    /// [`SnmpV2uParameters::check_lengths`])
+    pub fn encode_checked(&self) -> Result<Vec<u8>, FieldLengthError> {
+        self.parameters.check_lengths()?;
+        Ok(self.encode())
+    }
+}
+
+#[cfg(feature = "v3")]
+impl<'a> SnmpV3Message<'a> {
+    /// Check that the encoded message is not larger than `msgMaxSize`, and than `transport_max`
+    /// if given (for ex. the path MTU, minus the IP and UDP headers)
+    ///
+    /// When building a response, `msgMaxSize` should be copied from the request: it is the
+    /// largest message the sender of the request can receive. The message is not encoded, so
+    /// this can be checked before allocating it.
+    ///
+    /// Returns the encoded length.
+    pub fn check_encoded_size(&self, transport_max: Option<usize>) -> Result<usize, TooBigError> {
+        let len = self.encoded_len();
+        let max_size = self.header_data.msg_max_size as usize;
+        let limit = transport_max.map_or(max_size, |max| max.min(max_size));
+        if len > limit {
+            return Err(TooBigError { len, limit });
+        }
+        Ok(len)
+    }
+}
+
+const TAG_INTEGER: u32 = 2;
+const TAG_BITSTRING: u32 = 3;
+const TAG_OCTETSTRING: u32 = 4;
+const TAG_NULL: u32 = 5;
+const TAG_OID: u32 = 6;
+const TAG_REAL: u32 = 9;
+const TAG_ENUMERATED: u32 = 10;
+const TAG_SEQUENCE: u32 = 16;
+
+/// Write the identifier octets and length of an object
+pub(crate) fn write_header<O: Output>(
+    out: &mut O,
+    class: Class,
+    constructed: bool,
+    tag: u32,
+    len: usize,
+) {
+    let first = ((class as u8) << 6) | if constructed { 0x20 } else { 0 };
+    if tag < 31 {
+        out.push(first | tag as u8);
+    } else {
+        // high-tag-number form: base-128, most significant group first
+        out.push(first | 0x1f);
+        write_base128(out, u64::from(tag));
+    }
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = (len as u64).to_be_bytes();
+        let skip = bytes.iter().take_while(|&&b| b == 0).count();
+        out.push(0x80 | (bytes.len() - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+}
+
+fn write_base128<O: Output>(out: &mut O, value: u64) {
+    let mut groups = [0u8; 10];
+    let mut n = 0;
+    let mut v = value;
+    loop {
+        groups[n] = (v & 0x7f) as u8;
+        n += 1;
+        v >>= 7;
+        if v == 0 {
+            break;
+        }
+    }
+    for i in (0..n).rev() {
+        out.push(if i > 0 { groups[i] | 0x80 } else { groups[i] });
+    }
+}
+
+/// Destination of the encoding: a buffer, or a counter when only the length is needed
+pub(crate) trait Output {
+    fn push(&mut self, byte: u8);
+
+    fn extend_from_slice(&mut self, bytes: &[u8]);
+
+    /// Write an object, with content produced by `f`
+    fn write_tlv<F>(&mut self, class: Class, constructed: bool, tag: u32, f: F)
+    where
+        F: FnOnce(&mut Self);
+}
+
+impl Output for Vec<u8> {
+    fn push(&mut self, byte: u8) {
+        Vec::push(self, byte)
+    }
+
+    fn extend_from_slice(&mut self, bytes: &[u8]) {
+        Vec::extend_from_slice(self, bytes)
+    }
+
+    /// The content is written in place, and the header is inserted before it once its length
+    /// is known.
+    fn write_tlv<F>(&mut self, class: Class, constructed: bool, tag: u32, f: F)
+    where
+        F: FnOnce(&mut Self),
+    {
+        let start = self.len();
+        f(self);
+        let len = self.len() - start;
+        let mut header = Vec::with_capacity(8);
+        write_header(&mut header, class, constructed, tag, len);
+        self.splice(start..start, header);
+    }
+}
+
+/// Output counting the bytes written, used to compute lengths without allocating
+struct LenCounter(usize);
+
+impl Output for LenCounter {
+    fn push(&mut self, _byte: u8) {
+        self.0 += 1;
+    }
+
+    fn extend_from_slice(&mut self, bytes: &[u8]) {
+        self.0 += bytes.len();
+    }
+
+    fn write_tlv<F>(&mut self, class: Class, constructed: bool, tag: u32, f: F)
+    where
+        F: FnOnce(&mut Self),
+    {
+        let mut content = LenCounter(0);
+        f(&mut content);
+        write_header(self, class, constructed, tag, content.0);
+        self.0 += content.0;
+    }
+}
+
+/// Encoding of the types of this crate, to any [`Output`]
+///
+/// `SnmpEncode` is implemented for all types implementing this trait.
+pub(crate) trait WriteBer {
+    fn write_ber<O: Output>(&self, out: &mut O);
+}
+
+macro_rules! impl_snmp_encode {
+    ($($ty:ident $(<$lt:lifetime>)?),* $(,)?) => {
+        $(
+            impl$(<$lt>)? SnmpEncode for $ty$(<$lt>)? {
+                fn encode_to(&self, out: &mut Vec<u8>) {
+                    self.write_ber(out)
+                }
+
+                fn encoded_len(&self) -> usize {
+                    let mut counter = LenCounter(0);
+                    self.write_ber(&mut counter);
+                    counter.0
+                }
+            }
+        )*
+    };
+}
+
+impl_snmp_encode!(
+    NetworkAddress,
+    ObjectSyntax<'a>,
+    VarBindValue<'a>,
+    SnmpVariable<'a>,
+    SnmpPdu<'a>,
+    SnmpMessage<'a>,
+    SnmpGenericMessage<'a>,
+);
+
+#[cfg(feature = "v2p")]
+impl_snmp_encode!(SnmpV2pMessage<'a>,);
+
+#[cfg(feature = "v2u")]
+impl_snmp_encode!(SnmpV2uParameters<'a>, SnmpV2uMessage<'a>,);
+
+#[cfg(feature = "v3")]
+impl_snmp_encode!(
+    HeaderData,
+    UsmSecurityParameters<'a>,
+    SecurityParameters<'a>,
+    ScopedPdu<'a>,
+    ScopedPduData<'a>,
+    SnmpV3Message<'a>,
+);
+
+fn write_tlv<O: Output, F>(out: &mut O, class: Class, constructed: bool, tag: u32, f: F)
+where
+    F: FnOnce(&mut O),
+{
+    out.write_tlv(class, constructed, tag, f)
+}
+
+fn write_primitive<O: Output>(out: &mut O, class: Class, tag: u32, content: &[u8]) {
+    write_header(out, class, false, tag, content.len());
+    out.extend_from_slice(content);
+}
+
+fn write_sequence<O: Output, F: FnOnce(&mut O)>(out: &mut O, f: F) {
+    write_tlv(out, Class::Universal, true, TAG_SEQUENCE, f)
+}
+
+fn signed_content(value: i64) -> ([u8; 8], usize) {
+    let bytes = value.to_be_bytes();
+    let mut skip = 0;
+    while skip < 7 {
+        let (b, next) = (bytes[skip], bytes[skip + 1]);
+        if (b == 0 && next & 0x80 == 0) || (b == 0xff && next & 0x80 != 0) {
+            skip += 1;
+        } else {
+            break;
+        }
+    }
+    (bytes, skip)
+}
+
+pub(crate) fn write_signed<O: Output>(out: &mut O, class: Class, tag: u32, value: i64) {
+    let (bytes, skip) = signed_content(value);
+    write_primitive(out, class, tag, &bytes[skip..]);
+}
+
+pub(crate) fn write_unsigned<O: Output>(out: &mut O, class: Class, tag: u32, value: u64) {
+    let bytes = value.to_be_bytes();
+    let skip = bytes[..7].iter().take_while(|&&b| b == 0).count();
+    if bytes[skip] & 0x80 != 0 {
+        // a leading zero is required, so the value is not read as negative
+        write_header(out, class, false, tag, 8 - skip + 1);
+        out.push(0);
+        out.extend_from_slice(&bytes[skip..]);
+    } else {
+        write_primitive(out, class, tag, &bytes[skip..]);
+    }
+}
+
+pub(crate) fn write_integer<O: Output>(out: &mut O, value: u32) {
+    write_unsigned(out, Class::Universal, TAG_INTEGER, u64::from(value))
+}
+
+pub(crate) fn write_octetstring<O: Output>(out: &mut O, value: &[u8]) {
+    write_primitive(out, Class::Universal, TAG_OCTETSTRING, value)
+}
+
+pub(crate) fn write_oid<O: Output>(out: &mut O, oid: &Oid) {
+    write_primitive(out, Class::Universal, TAG_OID, oid.as_bytes())
+}
+
+/// Write a REAL, in the form required by DER (X.690 section 11.3.1): base 2, with an odd
+/// mantissa
+fn write_real<O: Output>(out: &mut O, value: f64) {
+    // special values (X.690 section 8.5.9)
+    let special = if value.is_nan() {
+        Some(0x42)
+    } else if value.is_infinite() {
+        Some(if value > 0.0 { 0x40 } else { 0x41 })
+    } else if value == 0.0 && value.is_sign_negative() {
+        Some(0x43)
+    } else if value == 0.0 {
+        return write_primitive(out, Class::Universal, TAG_REAL, &[]);
+    } else {
+        None
+    };
+    if let Some(special) = special {
+        return write_primitive(out, Class::Universal, TAG_REAL, &[special]);
+    }
+    let bits = value.to_bits();
+    let biased_exponent = ((bits >> 52) & 0x7ff) as i32;
+    let fraction = bits & ((1 << 52) - 1);
+    let (mut mantissa, mut exponent) = match biased_exponent {
+        // subnormal number
+        0 => (fraction, -1074),
+        e => (fraction | (1 << 52), e - 1075),
+    };
+    let shift = mantissa.trailing_zeros();
+    mantissa >>= shift;
+    exponent += shift as i32;
+    let mut first = 0x80;
+    if value.is_sign_negative() {
+        first |= 0x40;
+    }
+    let exponent = exponent as i16;
+    let exponent_bytes = exponent.to_be_bytes();
+    let exponent_bytes = if (-128..=127).contains(&exponent) {
+        &exponent_bytes[1..]
+    } else {
+        first |= 0x01;
+        &exponent_bytes[..]
+    };
+    let mantissa_bytes = mantissa.to_be_bytes();
+    let skip = mantissa_bytes.iter().take_while(|&&b| b == 0).count();
+    let len = 1 + exponent_bytes.len() + 8 - skip;
+    write_header(out, Class::Universal, false, TAG_REAL, len);
+    out.push(first);
+    out.extend_from_slice(exponent_bytes);
+    out.extend_from_slice(&mantissa_bytes[skip..]);
+}
+
+fn write_null<O: Output>(out: &mut O, class: Class, tag: u32) {
+    write_header(out, class, false, tag, 0)
+}
+
+impl WriteBer for NetworkAddress {
+    fn write_ber<O: Output>(&self, out: &mut O) {
+        let NetworkAddress::IPv4(ip) = self;
+        write_primitive(out, Class::Application, 0, &ip.octets())
+    }
+}
+
+impl<'a> WriteBer for ObjectSyntax<'a> {
+    fn write_ber<O: Output>(&self, out: &mut O) {
+        match self {
+            ObjectSyntax::Number(n) => {
+                write_signed(out, Class::Universal, TAG_INTEGER, i64::from(*n))
+            }
+            ObjectSyntax::String(s) => write_octetstring(out, s),
+            ObjectSyntax::Object(oid) => write_oid(out, oid),
+            ObjectSyntax::BitString(b) => {
+                write_tlv(out, Class::Universal, false, TAG_BITSTRING, |out| {
+                    out.push(b.unused_bits);
+                    out.extend_from_slice(&b.data);
+                })
+            }
+            ObjectSyntax::Empty => write_null(out, Class::Universal, TAG_NULL),
+            ObjectSyntax::Real(f) => write_real(out, *f),
+            ObjectSyntax::Enumerated(n) => {
+                write_signed(out, Class::Universal, TAG_ENUMERATED, i64::from(*n))
+            }
+            ObjectSyntax::IpAddress(addr) => addr.write_ber(out),
+            ObjectSyntax::Counter32(n) => write_unsigned(out, Class::Application, 1, u64::from(*n)),
+            ObjectSyntax::Gauge32(n) => write_unsigned(out, Class::Application, 2, u64::from(*n)),
+            ObjectSyntax::TimeTicks(n) => write_unsigned(out, Class::Application, 3, u64::from(*n)),
+            ObjectSyntax::Opaque(s) => write_primitive(out, Class::Application, 4, s),
+            ObjectSyntax::NsapAddress(s) => write_primitive(out, Class::Application, 5, s),
+            ObjectSyntax::Counter64(n) => write_unsigned(out, Class::Application, 6, *n),
+            ObjectSyntax::UInteger32(n) => {
+                write_unsigned(out, Class::Application, 7, u64::from(*n))
+            }
+            ObjectSyntax::UnknownSimple(any)
+            | ObjectSyntax::UnknownApplication(any)
+            | ObjectSyntax::Custom(CustomValue { raw: any, .. }) => {
+                let hdr = &any.header;
+                write_header(
+                    out,
+                    hdr.class(),
+                    hdr.is_constructed(),
+                    hdr.tag().0,
+                    any.data.len(),
+                );
+                out.extend_from_slice(any.data);
+            }
+        }
+    }
+}
+
+impl<'a> WriteBer for VarBindValue<'a> {
+    fn write_ber<O: Output>(&self, out: &mut O) {
+        match self {
+            VarBindValue::Value(v) => v.write_ber(out),
+            VarBindValue::Unspecified => write_null(out, Class::Universal, TAG_NULL),
+            VarBindValue::NoSuchObject => write_null(out, Class::ContextSpecific, 0),
+            VarBindValue::NoSuchInstance => write_null(out, Class::ContextSpecific, 1),
+            VarBindValue::EndOfMibView => write_null(out, Class::ContextSpecific, 2),
+        }
+    }
+}
+
+impl<'a> WriteBer for SnmpVariable<'a> {
+    fn write_ber<O: Output>(&self, out: &mut O) {
+        write_sequence(out, |out| {
+            write_oid(out, &self.oid);
+            self.val.write_ber(out);
+        })
+    }
+}
+
+fn write_varbind_list<O: Output>(out: &mut O, vars: &[SnmpVariable]) {
+    write_sequence(out, |out| {
+        for var in vars {
+            var.write_ber(out);
+        }
+    })
+}
+
+impl<'a> WriteBer for SnmpPdu<'a> {
+    fn write_ber<O: Output>(&self, out: &mut O) {
+        let tag = self.pdu_type().0;
+        write_tlv(out, Class::ContextSpecific, true, tag, |out| match self {
+            SnmpPdu::Generic(pdu) => {
+                write_integer(out, pdu.req_id);
+                write_integer(out, pdu.err.0);
+                write_integer(out, pdu.err_index);
+                write_varbind_list(out, &pdu.var);
+            }
+            SnmpPdu::Bulk(pdu) => {
+                write_integer(out, pdu.req_id);
+                write_integer(out, pdu.non_repeaters);
+                write_integer(out, pdu.max_repetitions);
+                write_varbind_list(out, &pdu.var);
+            }
+            SnmpPdu::TrapV1(pdu) => {
+                write_oid(out, &pdu.enterprise);
+                pdu.agent_addr.write_ber(out);
+                write_integer(out, u32::from(pdu.generic_trap.0));
+                write_integer(out, pdu.specific_trap);
+                write_unsigned(out, Class::Application, 3, u64::from(pdu.timestamp));
+                write_varbind_list(out, &pdu.var);
+            }
+        })
+    }
+}
+
+impl<'a> WriteBer for SnmpMessage<'a> {
+    fn write_ber<O: Output>(&self, out: &mut O) {
+        write_sequence(out, |out| {
+            write_integer(out, self.version);
+            write_octetstring(out, self.community.as_bytes());
+            self.pdu.write_ber(out);
+        })
+    }
+}
+
+#[cfg(feature = "v3")]
+impl WriteBer for HeaderData {
+    fn write_ber<O: Output>(&self, out: &mut O) {
+        write_sequence(out, |out| {
+            write_integer(out, self.msg_id);
+            write_integer(out, self.msg_max_size);
+            write_octetstring(out, &[self.msg_flags]);
+            write_integer(out, self.msg_security_model.0);
+        })
+    }
+}
+
+#[cfg(feature = "v3")]
+impl<'a> WriteBer for UsmSecurityParameters<'a> {
+    fn write_ber<O: Output>(&self, out: &mut O) {
+        write_sequence(out, |out| {
+            write_octetstring(out, self.msg_authoritative_engine_id);
+            write_integer(out, self.msg_authoritative_engine_boots);
+            write_integer(out, self.msg_authoritative_engine_time);
+            write_octetstring(out, self.msg_user_name.as_bytes());
+            write_octetstring(out, self.msg_authentication_parameters);
+            write_octetstring(out, self.msg_privacy_parameters);
+        })
+    }
+}
+
+/// Security parameters are encoded as an OCTET STRING, wrapping the model-specific encoding
+#[cfg(feature = "v3")]
+impl<'a> WriteBer for SecurityParameters<'a> {
+    fn write_ber<O: Output>(&self, out: &mut O) {
+        match self {
+            SecurityParameters::Raw(data) => write_octetstring(out, data),
+            SecurityParameters::USM(usm) => {
+                write_tlv(out, Class::Universal, false, TAG_OCTETSTRING, |out| {
+                    usm.write_ber(out)
+                })
+            }
+        }
+    }
+}
+
+#[cfg(feature = "v3")]
+impl<'a> WriteBer for ScopedPdu<'a> {
+    fn write_ber<O: Output>(&self, out: &mut O) {
+        write_sequence(out, |out| {
+            write_octetstring(out, self.ctx_engine_id);
+            write_octetstring(out, self.ctx_engine_name);
+            self.data.write_ber(out);
+        })
+    }
+}
+
+#[cfg(feature = "v3")]
+impl<'a> WriteBer for ScopedPduData<'a> {
+    fn write_ber<O: Output>(&self, out: &mut O) {
+        match self {
+            ScopedPduData::Plaintext(pdu) => pdu.write_ber(out),
+            ScopedPduData::Encrypted(data) => write_octetstring(out, data),
+        }
+    }
+}
+
+#[cfg(feature = "v3")]
+impl<'a> WriteBer for SnmpV3Message<'a> {
+    fn write_ber<O: Output>(&self, out: &mut O) {
+        write_sequence(out, |out| {
+            write_integer(out, self.version);
+            self.header_data.write_ber(out);
+            self.security_params.write_ber(out);
+            self.data.write_ber(out);
+        })
+    }
+}
+
+/// privData is written as read, so encrypted messages can be encoded
+#[cfg(feature = "v2p")]
+impl<'a> WriteBer for SnmpV2pMessage<'a> {
+    fn write_ber<O: Output>(&self, out: &mut O) {
+        write_sequence(out, |out| {
+            write_integer(out, self.version);
+            write_tlv(out, Class::ContextSpecific, true, 1, |out| {
+                write_oid(out, &self.priv_dst);
+                write_primitive(out, Class::ContextSpecific, 1, self.priv_data);
+            })
+        })
+    }
+}
+
+/// Parameters are encoded as an OCTET STRING, containing the fields in wire format
+///
+/// The user name and the authentication digest are truncated to 255 bytes, the largest length
+/// which can be encoded: use [`SnmpV2uMessage::encode_checked`] to reject them instead.
+#[cfg(feature = "v2u")]
+impl<'a> WriteBer for SnmpV2uParameters<'a> {
+    fn write_ber<O: Output>(&self, out: &mut O) {
+        write_tlv(out, Class::Universal, false, TAG_OCTETSTRING, |out| {
+            out.push(self.model);
+            out.push(self.qos);
+            out.extend_from_slice(self.agent_id);
+            out.extend_from_slice(&self.agent_boots.to_be_bytes());
+            out.extend_from_slice(&self.agent_time.to_be_bytes());
+            out.extend_from_slice(&self.max_size.to_be_bytes());
+            write_short_string(out, self.user_name);
+            write_short_string(out, self.auth_digest);
+            out.extend_from_slice(self.context_selector);
+        })
+    }
+}
+
+/// Write a string preceded by its length on one byte, truncated to 255 bytes
+#[cfg(feature = "v2u")]
+fn write_short_string<O: Output>(out: &mut O, bytes: &[u8]) {
+    let len = u8::try_from(bytes.len()).unwrap_or(u8::MAX);
+    out.push(len);
+    out.extend_from_slice(&bytes[..len as usize]);
+}
+
+#[cfg(feature = "v2u")]
+impl<'a> WriteBer for SnmpV2uMessage<'a> {
+    fn write_ber<O: Output>(&self, out: &mut O) {
+        write_sequence(out, |out| {
+            write_integer(out, self.version);
+            self.parameters.write_ber(out);
+            match self.data {
+                SnmpV2uData::Plaintext(ref pdu) => pdu.write_ber(out),
+                SnmpV2uData::Encrypted(data) => write_octetstring(out, data),
+            }
+        })
+    }
+}
+
+impl<'a> WriteBer for SnmpGenericMessage<'a> {
+    fn write_ber<O: Output>(&self, out: &mut O) {
+        match self {
+            SnmpGenericMessage::V1(msg) | SnmpGenericMessage::V2(msg) => msg.write_ber(out),
+            #[cfg(feature = "v2p")]
+            SnmpGenericMessage::V2p(msg) => msg.write_ber(out),
+            #[cfg(feature = "v2u")]
+            SnmpGenericMessage::V2u(msg) => msg.write_ber(out),
+            #[cfg(feature = "v3")]
+            SnmpGenericMessage::V3(msg) => msg.write_ber(out),
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! Tracking of SNMPv3 engine boots and time
+//!
+//! Authoritative engines send their `snmpEngineBoots` and `snmpEngineTime` values in the USM
+//! security parameters. For a given engine, the pair only moves forward: the time increases
+//! until the engine reboots, and then the boots counter increases (see
+//! [RFC3414](https://tools.ietf.org/html/rfc3414) section 2.2).
+//!
+//! [`EngineBootsTracker`] stores the last values seen for each engine ID, and detects
+//! regressions, which indicate replayed or spoofed messages, or a misconfigured engine (for ex.
+//! two agents using the same engine ID):
+//!
+//! ```rust
+//! use snmp_parser::engine::{BootsTimeStatus, EngineBootsTime, EngineBootsTracker};
+//!
+//! let mut tracker = EngineBootsTracker::new(0);
+//! let engine_id = b"\x80\x00\x1f\x88\x80\x59\xdc\x48\x61\x45\xa2\x63\x22";
+//! let status = tracker.observe(engine_id, EngineBootsTime { boots: 8, time: 2745 });
+//! assert_eq!(status, BootsTimeStatus::New);
+//! let status = tracker.observe(engine_id, EngineBootsTime { boots: 8, time: 2700 });
+//! assert!(status.is_regression());
+//! ```
+//!
+//! Values are read from the messages, so they can be forged: a spoofed message with a large boots
+//! value would make all later messages of the engine regressions. Only authenticated messages
+//! should be recorded when possible. Otherwise, boots values increasing by more than
+//! [`DEFAULT_MAX_BOOTS_JUMP`] (see [`EngineBootsTracker::set_max_boots_jump`]) are reported,
+//! but not recorded.
+
+use crate::lru::LruMap;
+use crate::snmpv3::UsmSecurityParameters;
+use alloc::vec::Vec;
+
+/// Maximum value of `snmpEngineBoots`: an engine reaching it must be reconfigured
+pub const MAX_ENGINE_BOOTS: u32 = 2_147_483_647;
+
+/// Values of `snmpEngineBoots` and `snmpEngineTime`
+///
+/// Values are ordered by boots, then by time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EngineBootsTime {
+    pub boots: u32,
+    pub time: u32,
+}
+
+impl EngineBootsTime {
+    pub fn from_usm(usm: &UsmSecurityParameters) -> Self {
+        EngineBootsTime {
+            boots: usm.msg_authoritative_engine_boots,
+            time: usm.msg_authoritative_engine_time,
+        }
+    }
+}
+
+/// Result of [`EngineBootsTracker::observe`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BootsTimeStatus {
+    /// The engine was not known
+    New,
+    /// The time is the same or later, with the same boots value (or earlier, within the
+    /// tolerance)
+    Forward,
+    /// The boots value increased: the engine was restarted
+    Reboot { previous: EngineBootsTime },
+    /// The boots value increased by more than the maximum jump: the value was not recorded
+    BootsJump { previous: EngineBootsTime },
+    /// The boots value decreased, or the time went back by more than the tolerance
+    Regression { previous: EngineBootsTime },
+}
+
+impl BootsTimeStatus {
+    pub fn is_regression(&self) -> bool {
+        matches!(self, BootsTimeStatus::Regression { .. })
+    }
+}
+
+/// Default maximum number of engines stored by [`EngineBootsTracker`]
+pub const DEFAULT_MAX_ENGINES: usize = 10_000;
+
+/// Default maximum increase of the boots value recorded by [`EngineBootsTracker`]
+pub const DEFAULT_MAX_BOOTS_JUMP: u32 = 1000;
+
+/// Last boots and time values seen for each engine ID
+///
+/// Engine IDs are read from the messages, so at most `max_engines` engines are stored (see
+/// [`with_max_engines`](Self::with_max_engines)): when the limit is reached, the least recently
+/// seen engine is forgotten.
+#[derive(Debug)]
+pub struct EngineBootsTracker {
+    tolerance: u32,
+    max_boots_jump: Option<u32>,
+    engines: LruMap<Vec<u8>, EngineBootsTime>,
+}
+
+impl Default for EngineBootsTracker {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl EngineBootsTracker {
+    /// Create a tracker accepting times going back by at most `tolerance` seconds, and storing
+    /// at most [`DEFAULT_MAX_ENGINES`] engines
+    ///
+    /// Messages can be reordered by the network, so a small tolerance should be used for
+    /// messages of different requests (the USM time window is 150 seconds).
+    pub fn new(tolerance: u32) -> Self {
+        Self::with_max_engines(tolerance, DEFAULT_MAX_ENGINES)
+    }
+
+    /// Create a tracker storing at most `max_engines` engines
+    pub fn with_max_engines(tolerance: u32, max_engines: usize) -> Self {
+        EngineBootsTracker {
+            tolerance,
+            max_boots_jump: Some(DEFAULT_MAX_BOOTS_JUMP),
+            engines: LruMap::new(max_engines),
+        }
+    }
+
+    /// Set the maximum increase of the boots value, or `None` to record any increase
+    ///
+    /// Larger increases are reported as [`BootsTimeStatus::BootsJump`], and the previous value
+    /// is kept. If the engine really rebooted that many times, [`remove`](Self::remove) it so that
+    /// the next value is recorded.
+    pub fn set_max_boots_jump(&mut self, max_boots_jump: Option<u32>) {
+        self.max_boots_jump = max_boots_jump;
+    }
+
+    /// Record `value` for `engine_id`, and compare it to the last value seen
+    ///
+    /// The stored value only moves forward: after a regression, or an increase of the boots
+    /// value larger than the maximum jump, the previous value is kept.
+    pub fn observe(&mut self, engine_id: &[u8], value: EngineBootsTime) -> BootsTimeStatus {
+        let previous = match self.engines.get_mut(engine_id) {
+            Some(previous) => previous,
+            None => {
+                if let Some((_evicted, _)) = self.engines.insert(engine_id.to_vec(), value) {
+                    snmp_anomaly!(engine_id = ?_evicted, "too many engines, forgetting the oldest");
+                }
+                return BootsTimeStatus::New;
+            }
+        };
+        let jump = value.boots.saturating_sub(previous.boots);
+        let status = if self.max_boots_jump.is_some_and(|max| jump > max) {
+            snmp_anomaly!(
+                previous = ?*previous,
+                current = ?value,
+                "engine boots jump"
+            );
+            return BootsTimeStatus::BootsJump {
+                previous: *previous,
+            };
+        } else if value.boots > previous.boots {
+            BootsTimeStatus::Reboot {
+                previous: *previous,
+            }
+        } else if value.boots < previous.boots
+            || value.time.saturating_add(self.tolerance) < previous.time
+        {
+            snmp_anomaly!(
+                previous = ?*previous,
+                current = ?value,
+                "engine boots/time regression"
+            );
+            return BootsTimeStatus::Regression {
+                previous: *previous,
+            };
+        } else {
+            BootsTimeStatus::Forward
+        };
+        if value > *previous {
+            *previous = value;
+        }
+        status
+    }
+
+    /// Record the values of USM security parameters
+    ///
+    /// Return `None` if the engine ID is empty (discovery messages), or if the boots value is
+    /// [`MAX_ENGINE_BOOTS`] (the values are not meaningful anymore).
+    pub fn observe_usm(&mut self, usm: &UsmSecurityParameters) -> Option<BootsTimeStatus> {
+        let value = EngineBootsTime::from_usm(usm);
+        if usm.msg_authoritative_engine_id.is_empty() || value.boots >= MAX_ENGINE_BOOTS {
+            return None;
+        }
+        Some(self.observe(usm.msg_authoritative_engine_id, value))
+    }
+
+    /// Return the last value seen for `engine_id`
+    pub fn get(&self, engine_id: &[u8]) -> Option<EngineBootsTime> {
+        self.engines.get(engine_id).copied()
+    }
+
+    /// Forget `engine_id`, for ex. after it was reconfigured
+    pub fn remove(&mut self, engine_id: &[u8]) -> Option<EngineBootsTime> {
+        self.engines.remove(engine_id)
+    }
+
+    /// Return the number of engines tracked
+    pub fn len(&self) -> usize {
+        self.engines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.engines.len() == 0
+    }
+}
@@ -0,0 +1,170 @@
+//! Names of private enterprises
+//!
+//! Enterprise OIDs (`1.3.6.1.4.1.N`, used for ex. for `sysObjectID` and SNMPv1 trap
+//! enterprises) and engine IDs both start with an IANA private enterprise number, which
+//! identifies the vendor of the device. This module extracts these numbers, and maps them to
+//! names using a built-in table of common network vendors:
+//!
+//! ```rust
+//! use snmp_parser::enterprise::{enterprise_name, enterprise_of_oid};
+//! use snmp_parser::oid;
+//!
+//! let id = enterprise_of_oid(&oid!(1.3.6.1.4.1.9.1.1208)).expect("not an enterprise OID");
+//! assert_eq!(id, 9);
+//! assert_eq!(enterprise_name(id), Some("ciscoSystems"));
+//! ```
+//!
+//! The built-in table is far from complete. A complete (or local) list can be loaded from CSV
+//! data into an [`EnterpriseRegistry`]. Like the built-in table, a loaded registry is read
+//! without locking: it is `Send` and `Sync`, and can be shared behind an `Arc`.
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use asn1_rs::Oid;
+use core::convert::TryFrom;
+use core::fmt;
+
+// sorted by enterprise number
+static ENTERPRISES: &[(u32, &str)] = &[
+    (2, "IBM"),
+    (9, "ciscoSystems"),
+    (11, "Hewlett-Packard"),
+    (23, "Novell"),
+    (42, "Sun Microsystems"),
+    (43, "3Com"),
+    (94, "Nokia"),
+    (171, "D-Link"),
+    (193, "Ericsson"),
+    (232, "Compaq"),
+    (311, "Microsoft"),
+    (637, "Alcatel-Lucent"),
+    (674, "Dell"),
+    (1588, "Brocade"),
+    (1916, "Extreme Networks"),
+    (1991, "Foundry Networks"),
+    (2011, "Huawei"),
+    (2021, "UC Davis"),
+    (2620, "Check Point"),
+    (2636, "Juniper Networks"),
+    (3375, "F5 Networks"),
+    (3902, "ZTE"),
+    (4526, "Netgear"),
+    (5951, "Netscaler"),
+    (6027, "Force10 Networks"),
+    (6486, "Alcatel-Lucent Enterprise"),
+    (6876, "VMware"),
+    (8072, "net-snmp"),
+    (8741, "SonicWall"),
+    (12356, "Fortinet"),
+    (14179, "Airespace"),
+    (14823, "Aruba Networks"),
+    (14988, "MikroTik"),
+    (25461, "Palo Alto Networks"),
+    (25506, "H3C"),
+    (30065, "Arista Networks"),
+    (41112, "Ubiquiti Networks"),
+];
+
+/// Return the name of enterprise `id`, if it is in the built-in table
+pub fn enterprise_name(id: u32) -> Option<&'static str> {
+    ENTERPRISES
+        .binary_search_by_key(&id, |&(n, _)| n)
+        .ok()
+        .map(|idx| ENTERPRISES[idx].1)
+}
+
+/// Return the enterprise number of an OID in the `1.3.6.1.4.1` subtree
+pub fn enterprise_of_oid(oid: &Oid) -> Option<u32> {
+    let mut arcs = oid.iter()?;
+    for expected in [1, 3, 6, 1, 4, 1] {
+        if arcs.next()? != expected {
+            return None;
+        }
+    }
+    u32::try_from(arcs.next()?).ok()
+}
+
+/// Return the enterprise number of a SNMPv3 engine ID
+///
+/// The first 4 bytes of an engine ID are the enterprise number, with the high bit set for
+/// engine IDs using the format of [RFC3411](https://tools.ietf.org/html/rfc3411) (and unset for
+/// 12-byte engine IDs using the older format).
+pub fn enterprise_of_engine_id(engine_id: &[u8]) -> Option<u32> {
+    let prefix = engine_id.get(..4)?;
+    let n = u32::from_be_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]);
+    Some(n & 0x7fff_ffff)
+}
+
+/// Error returned when CSV data cannot be loaded
+#[derive(Debug, PartialEq, Eq)]
+pub struct CsvError {
+    /// Line of the error, starting from 1
+    pub line: usize,
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid enterprise entry at line {}", self.line)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CsvError {}
+
+/// Enterprise names, loaded by the application
+///
+/// Lookups fall back to the built-in table for enterprises which were not loaded.
+#[derive(Clone, Debug, Default)]
+pub struct EnterpriseRegistry {
+    names: BTreeMap<u32, String>,
+}
+
+impl EnterpriseRegistry {
+    pub fn new() -> Self {
+        EnterpriseRegistry::default()
+    }
+
+    /// Load entries from CSV data, with one `number,name` entry per line
+    ///
+    /// Empty lines and lines starting with `#` are ignored. The name is trimmed, and may be
+    /// quoted with `"`. Entries replace previously loaded entries with the same number.
+    pub fn load_csv(&mut self, data: &str) -> Result<(), CsvError> {
+        for (idx, line) in data.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = || CsvError { line: idx + 1 };
+            let (number, name) = line.split_once(',').ok_or_else(err)?;
+            let number = number.trim().parse::<u32>().map_err(|_| err())?;
+            let name = name.trim();
+            let name = name
+                .strip_prefix('"')
+                .and_then(|s| s.strip_suffix('"'))
+                .unwrap_or(name);
+            self.insert(number, name);
+        }
+        Ok(())
+    }
+
+    pub fn insert(&mut self, id: u32, name: &str) {
+        self.names.insert(id, name.to_string());
+    }
+
+    /// Return the name of enterprise `id`
+    pub fn name(&self, id: u32) -> Option<&str> {
+        match self.names.get(&id) {
+            Some(name) => Some(name),
+            None => enterprise_name(id),
+        }
+    }
+
+    /// Return the number of loaded entries (not counting the built-in table)
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
@@ -1,27 +1,350 @@
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::format;
 use asn1_rs::Error;
+use core::convert::From;
+use core::fmt;
 use nom::error::{ErrorKind, ParseError};
-use std::convert::From;
+use nom::IResult;
 
-#[derive(Debug, PartialEq, thiserror::Error)]
+#[derive(Debug, PartialEq)]
 pub enum SnmpError {
-    #[error("Invalid message: not a DER sequence, or unexpected number of items, etc.")]
     InvalidMessage,
-    #[error("Invalid version: not a number, or not in supported range (1, 2 or 3)")]
-    InvalidVersion,
-    #[error("Unknown or invalid PDU type")]
-    InvalidPduType,
-    #[error("Invalid PDU: content does not match type, or content cannot be decoded")]
+    InvalidVersion {
+        found: u32,
+    },
+    InvalidPduType {
+        tag: u32,
+    },
     InvalidPdu,
-    #[error("Invalid SNMPv3 header data")]
     InvalidHeaderData,
-    #[error("Invalid SNMPv3 scoped PDU")]
     InvalidScopedPduData,
-    #[error("Invalid SNMPv3 security model")]
     InvalidSecurityModel,
-    #[error("Nom error")]
     NomError(ErrorKind),
-    #[error("BER error")]
     BerError(Error),
+    /// The message is nested deeper than [`MAX_DEPTH`](crate::snmp::MAX_DEPTH)
+    MaxDepthExceeded,
+    /// An error, with the location where it happened
+    Context {
+        /// Path of the field being parsed, for ex `varbind[3].value`
+        field: Cow<'static, str>,
+        /// Offset of the field, in bytes, from the start of the message, or `None` if the field
+        /// is not in the parsed buffer
+        offset: Option<usize>,
+        error: Box<SnmpError>,
+    },
+}
+
+impl fmt::Display for SnmpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnmpError::InvalidMessage => f.write_str(
+                "Invalid message: not a DER sequence, or unexpected number of items, etc.",
+            ),
+            SnmpError::InvalidVersion { found } => {
+                write!(
+                    f,
+                    "Invalid version {}: expected {}",
+                    found, SupportedVersions
+                )
+            }
+            SnmpError::InvalidPduType { tag } => write!(f, "Unknown or invalid PDU type {}", tag),
+            SnmpError::InvalidPdu => f.write_str(
+                "Invalid PDU: content does not match type, or content cannot be decoded",
+            ),
+            SnmpError::InvalidHeaderData => f.write_str("Invalid SNMPv3 header data"),
+            SnmpError::InvalidScopedPduData => f.write_str("Invalid SNMPv3 scoped PDU"),
+            SnmpError::InvalidSecurityModel => f.write_str("Invalid SNMPv3 security model"),
+            SnmpError::NomError(_) => f.write_str("Nom error"),
+            SnmpError::BerError(_) => f.write_str("BER error"),
+            SnmpError::MaxDepthExceeded => f.write_str("Maximum nesting depth exceeded"),
+            SnmpError::Context {
+                field,
+                offset,
+                error,
+            } => match offset {
+                Some(offset) => write!(f, "{} (field `{}` at offset {})", error, field, offset),
+                None => write!(f, "{} (field `{}`)", error, field),
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SnmpError {}
+
+/// Raw values of the version field accepted by the enabled parsers, with the version names
+const SUPPORTED_VERSIONS: &[(u32, &str)] = &[
+    #[cfg(feature = "v1")]
+    (0, "SNMPv1"),
+    #[cfg(feature = "v2c")]
+    (1, "SNMPv2c"),
+    #[cfg(all(feature = "v2p", feature = "v2u"))]
+    (2, "party-based SNMPv2 or SNMPv2u"),
+    #[cfg(all(feature = "v2p", not(feature = "v2u")))]
+    (2, "party-based SNMPv2"),
+    #[cfg(all(feature = "v2u", not(feature = "v2p")))]
+    (2, "SNMPv2u"),
+    #[cfg(feature = "v3")]
+    (3, "SNMPv3"),
+];
+
+/// List of the supported versions, for ex. "0 (SNMPv1), 1 (SNMPv2c) or 3 (SNMPv3)"
+pub(crate) struct SupportedVersions;
+
+impl fmt::Display for SupportedVersions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if SUPPORTED_VERSIONS.is_empty() {
+            return f.write_str("no version (all version features are disabled)");
+        }
+        for (index, (value, name)) in SUPPORTED_VERSIONS.iter().enumerate() {
+            if index > 0 {
+                let last = index == SUPPORTED_VERSIONS.len() - 1;
+                f.write_str(if last { " or " } else { ", " })?;
+            }
+            write!(f, "{} ({})", value, name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Convert a parsing error to an I/O error, for ex. in codecs or readers
+///
+/// Truncated data is reported as `UnexpectedEof`, and other errors as `InvalidData`. The
+/// original error is kept as the inner error.
+#[cfg(feature = "std")]
+impl From<SnmpError> for std::io::Error {
+    fn from(e: SnmpError) -> std::io::Error {
+        let kind = match e.kind() {
+            SnmpError::BerError(Error::Incomplete(_))
+            | SnmpError::NomError(ErrorKind::Eof | ErrorKind::Complete) => {
+                std::io::ErrorKind::UnexpectedEof
+            }
+            _ => std::io::ErrorKind::InvalidData,
+        };
+        std::io::Error::new(kind, e)
+    }
+}
+
+/// Stable numeric code for a [`SnmpError`] variant
+///
+/// Codes are never reused or renumbered, so they can be stored or exchanged (for ex. in C
+/// bindings or event schemas) instead of error messages.
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+pub struct SnmpErrorCode(pub u32);
+
+#[allow(non_upper_case_globals)]
+impl SnmpErrorCode {
+    pub const InvalidMessage: SnmpErrorCode = SnmpErrorCode(1);
+    pub const InvalidVersion: SnmpErrorCode = SnmpErrorCode(2);
+    pub const InvalidPduType: SnmpErrorCode = SnmpErrorCode(3);
+    pub const InvalidPdu: SnmpErrorCode = SnmpErrorCode(4);
+    pub const InvalidHeaderData: SnmpErrorCode = SnmpErrorCode(5);
+    pub const InvalidScopedPduData: SnmpErrorCode = SnmpErrorCode(6);
+    pub const InvalidSecurityModel: SnmpErrorCode = SnmpErrorCode(7);
+    pub const NomError: SnmpErrorCode = SnmpErrorCode(8);
+    pub const BerError: SnmpErrorCode = SnmpErrorCode(9);
+    pub const MaxDepthExceeded: SnmpErrorCode = SnmpErrorCode(10);
+
+    // Names are nul-terminated, so they can also be returned by the C API
+    const NAMES: &'static [(SnmpErrorCode, &'static str)] = &[
+        (SnmpErrorCode::InvalidMessage, "InvalidMessage\0"),
+        (SnmpErrorCode::InvalidVersion, "InvalidVersion\0"),
+        (SnmpErrorCode::InvalidPduType, "InvalidPduType\0"),
+        (SnmpErrorCode::InvalidPdu, "InvalidPdu\0"),
+        (SnmpErrorCode::InvalidHeaderData, "InvalidHeaderData\0"),
+        (
+            SnmpErrorCode::InvalidScopedPduData,
+            "InvalidScopedPduData\0",
+        ),
+        (
+            SnmpErrorCode::InvalidSecurityModel,
+            "InvalidSecurityModel\0",
+        ),
+        (SnmpErrorCode::NomError, "NomError\0"),
+        (SnmpErrorCode::BerError, "BerError\0"),
+        (SnmpErrorCode::MaxDepthExceeded, "MaxDepthExceeded\0"),
+    ];
+
+    /// Return the name of the error variant for this code, with a nul byte appended
+    pub(crate) fn name_with_nul(self) -> Option<&'static str> {
+        Self::NAMES
+            .iter()
+            .find(|(code, _)| *code == self)
+            .map(|(_, name)| *name)
+    }
+
+    /// Return the name of the error variant for this code, if known
+    pub fn name(self) -> Option<&'static str> {
+        self.name_with_nul().map(|name| name.trim_end_matches('\0'))
+    }
+
+    /// Return the code for an error variant name, if known
+    pub fn from_name(name: &str) -> Option<SnmpErrorCode> {
+        Self::NAMES
+            .iter()
+            .find(|(_, n)| n.trim_end_matches('\0') == name)
+            .map(|(code, _)| *code)
+    }
+}
+
+impl fmt::Debug for SnmpErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => f.debug_tuple("SnmpErrorCode").field(&self.0).finish(),
+        }
+    }
+}
+
+impl SnmpError {
+    /// Return the stable numeric code of this error
+    ///
+    /// For errors with location information, this is the code of the underlying error.
+    pub fn code(&self) -> SnmpErrorCode {
+        match self.kind() {
+            SnmpError::InvalidMessage => SnmpErrorCode::InvalidMessage,
+            SnmpError::InvalidVersion { .. } => SnmpErrorCode::InvalidVersion,
+            SnmpError::InvalidPduType { .. } => SnmpErrorCode::InvalidPduType,
+            SnmpError::InvalidPdu => SnmpErrorCode::InvalidPdu,
+            SnmpError::InvalidHeaderData => SnmpErrorCode::InvalidHeaderData,
+            SnmpError::InvalidScopedPduData => SnmpErrorCode::InvalidScopedPduData,
+            SnmpError::InvalidSecurityModel => SnmpErrorCode::InvalidSecurityModel,
+            SnmpError::NomError(_) => SnmpErrorCode::NomError,
+            SnmpError::BerError(_) => SnmpErrorCode::BerError,
+            SnmpError::MaxDepthExceeded => SnmpErrorCode::MaxDepthExceeded,
+            SnmpError::Context { .. } => unreachable!("kind() never returns a context"),
+        }
+    }
+
+    /// Return the underlying error, without location information
+    pub fn kind(&self) -> &SnmpError {
+        match self {
+            SnmpError::Context { error, .. } => error.kind(),
+            e => e,
+        }
+    }
+
+    /// Return the offset (from the start of the message) of the field where the error happened,
+    /// if known
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            SnmpError::Context { offset, .. } => *offset,
+            _ => None,
+        }
+    }
+
+    /// Return the path of the field where the error happened, if known
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            SnmpError::Context { field, .. } => Some(field),
+            _ => None,
+        }
+    }
+
+    // While parsing, `offset` contains the address of the field, since the start of the message
+    // is not known. It is converted to an offset by `resolve_offset`, before returning from the
+    // public functions, or to `None` if the field is not in the input.
+    fn with_context(self, field: Cow<'static, str>, position: &[u8]) -> SnmpError {
+        match self {
+            SnmpError::Context {
+                field: inner,
+                offset,
+                error,
+            } => SnmpError::Context {
+                field: Cow::Owned(format!("{}.{}", field, inner)),
+                offset,
+                error,
+            },
+            e => SnmpError::Context {
+                field,
+                offset: Some(position.as_ptr() as usize),
+                error: Box::new(e),
+            },
+        }
+    }
+
+    /// Attach the location of `field` to the error, after parsing `input`
+    pub(crate) fn at(self, field: Cow<'static, str>, position: &[u8], input: &[u8]) -> SnmpError {
+        self.with_context(field, position).resolve_offset(input)
+    }
+
+    fn resolve_offset(self, input: &[u8]) -> SnmpError {
+        match self {
+            SnmpError::Context {
+                field,
+                offset,
+                error,
+            } => {
+                let offset = offset.and_then(|address| {
+                    address
+                        .checked_sub(input.as_ptr() as usize)
+                        .filter(|&o| o <= input.len())
+                });
+                SnmpError::Context {
+                    field,
+                    offset,
+                    error,
+                }
+            }
+            e => e,
+        }
+    }
+}
+
+/// Wrap errors from parser `f` with the name of the field being parsed
+pub(crate) fn context<'a, O, E, F>(
+    field: &'static str,
+    f: F,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], O, SnmpError>
+where
+    F: FnMut(&'a [u8]) -> IResult<&'a [u8], O, E>,
+    SnmpError: From<E>,
+{
+    context_with(move || Cow::Borrowed(field), f)
+}
+
+/// Wrap errors from parser `f` with a field name, built only when an error happens
+pub(crate) fn context_with<'a, O, E, F, N>(
+    name: N,
+    mut f: F,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], O, SnmpError>
+where
+    F: FnMut(&'a [u8]) -> IResult<&'a [u8], O, E>,
+    N: Fn() -> Cow<'static, str>,
+    SnmpError: From<E>,
+{
+    move |i: &'a [u8]| f(i).map_err(|e| e.map(|e| SnmpError::from(e).with_context(name(), i)))
+}
+
+/// Convert a recoverable error to a failure, once the message type is known
+pub(crate) fn cut(e: nom::Err<SnmpError>) -> nom::Err<SnmpError> {
+    match e {
+        nom::Err::Error(e) => nom::Err::Failure(e),
+        e => e,
+    }
+}
+
+/// Convert field addresses to offsets relative to `input`, the start of the message
+pub(crate) fn resolve_offsets<'a, T>(
+    res: IResult<&'a [u8], T, SnmpError>,
+    input: &[u8],
+) -> IResult<&'a [u8], T, SnmpError> {
+    res.map_err(|e| e.map(|e| e.resolve_offset(input)))
+}
+
+/// Convert the result of a top-level parser to a plain `Result`, with the number of bytes read
+///
+/// Messages are complete datagrams, so `Incomplete` is reported as `SnmpError::InvalidMessage`.
+pub(crate) fn finish<T>(
+    res: IResult<&[u8], T, SnmpError>,
+    input: &[u8],
+) -> Result<(T, usize), SnmpError> {
+    match res {
+        Ok((rem, t)) => Ok((t, input.len() - rem.len())),
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Err(e),
+        Err(nom::Err::Incomplete(_)) => Err(SnmpError::InvalidMessage),
+    }
 }
 
 impl<I> ParseError<I> for SnmpError {
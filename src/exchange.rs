@@ -0,0 +1,456 @@
+//! Checks on request/response exchanges
+//!
+//! [`validate_response`] checks that a response is consistent with the request it answers (see
+//! [RFC3416](https://tools.ietf.org/html/rfc3416) section 4.2, and
+//! [RFC1157](https://tools.ietf.org/html/rfc1157) section 4.1 for SNMPv1). Inconsistent
+//! responses indicate a buggy or spoofed agent, or a response matched to the wrong request.
+//!
+//! The lexicographic order of the responses to GetNext and GetBulk requests is checked
+//! separately, by [`check_response_order`](crate::walk::check_response_order).
+//!
+//! SNMPv3 replies are linked to their request by the `msgID` of the header (see
+//! [RFC3412](https://tools.ietf.org/html/rfc3412) section 7.1). This includes Report PDUs, which
+//! do not always carry the request ID of the request: [`correlate_v3`] matches a reply with a
+//! request, and [`V3RequestTracker`] matches the messages of a capture, so engine ID discovery
+//! and time synchronization exchanges are not counted as unanswered requests.
+
+use crate::generic::SnmpGenericMessage;
+#[cfg(all(feature = "v3", feature = "std"))]
+use crate::pending::PendingRequests;
+use crate::snmp::{ErrorStatus, ObjectSyntax, PduType, SnmpPdu};
+#[cfg(feature = "v3")]
+use crate::snmpv3::{ScopedPduData, SnmpV3Message};
+use alloc::vec::Vec;
+#[cfg(feature = "v3")]
+use asn1_rs::oid;
+use asn1_rs::Oid;
+#[cfg(all(feature = "v3", feature = "std"))]
+use core::time::Duration;
+#[cfg(all(feature = "v3", feature = "std"))]
+use std::net::IpAddr;
+#[cfg(all(feature = "v3", feature = "std"))]
+use std::time::Instant;
+
+/// An inconsistency between a request and its response
+#[derive(Debug, PartialEq)]
+pub enum ResponseViolation {
+    /// The response is not a Response PDU
+    NotAResponse(PduType),
+    /// The response does not have the version of the request
+    VersionMismatch { request: u32, response: u32 },
+    /// The response does not have the request ID of the request
+    RequestIdMismatch { request: u32, response: u32 },
+    /// The number of variables of the response is not the number of variables of the request
+    /// (or, for GetBulk, is larger than allowed by the request)
+    VarCountMismatch { request: usize, response: usize },
+    /// The variable at `index` (starting from 0) does not have the OID of the requested variable
+    OidMismatch {
+        index: usize,
+        oid: Oid<'static>,
+        expected: Oid<'static>,
+    },
+    /// The error index does not point to a variable of the response, or is not 0 while there is
+    /// no error
+    InvalidErrorIndex { err_index: u32, var_count: usize },
+    /// The error status is only defined in SNMPv2, but the response is a SNMPv1 message
+    V2ErrorStatusInV1(ErrorStatus),
+    /// The variable at `index` is an exception (`noSuchObject`, etc.) in a SNMPv1 response
+    ExceptionInV1 { index: usize },
+    /// The variable at `index` has a Counter64 value in a SNMPv1 response
+    Counter64InV1 { index: usize },
+}
+
+/// Check that `response` is consistent with `request`
+///
+/// The following properties are checked:
+///   - the response is a Response PDU, with the version and request ID of the request
+///   - for Get, Set and Inform requests, the variables of the response have the OIDs of the
+///     requested variables, in the same order. GetNext responses have the same number of
+///     variables, and GetBulk responses at most `non-repeaters + repeaters * max-repetitions`
+///   - the error index is 0 if there is no error, and otherwise at most the number of variables
+///   - SNMPv1 responses have no SNMPv2 error status, exceptions or Counter64 values
+///
+/// A `tooBig` response may have no variables. If the request is not a Get, GetNext, GetBulk,
+/// Set or Inform request, or if a PDU is encrypted, no check is done.
+pub fn validate_response(
+    request: &SnmpGenericMessage,
+    response: &SnmpGenericMessage,
+) -> Vec<ResponseViolation> {
+    let mut violations = Vec::new();
+    let (req, resp) = match (request.pdu(), response.pdu()) {
+        (Some(req), Some(resp)) => (req, resp),
+        _ => return violations,
+    };
+    if !matches!(
+        req.pdu_type(),
+        PduType::GetRequest
+            | PduType::GetNextRequest
+            | PduType::GetBulkRequest
+            | PduType::SetRequest
+            | PduType::InformRequest
+    ) {
+        return violations;
+    }
+    let resp = match resp {
+        SnmpPdu::Generic(pdu) if pdu.pdu_type == PduType::Response => pdu,
+        pdu => {
+            violations.push(ResponseViolation::NotAResponse(pdu.pdu_type()));
+            return violations;
+        }
+    };
+    if request.version() != response.version() {
+        violations.push(ResponseViolation::VersionMismatch {
+            request: request.version(),
+            response: response.version(),
+        });
+    }
+    if let Some(req_id) = req.request_id() {
+        if req_id != resp.req_id {
+            violations.push(ResponseViolation::RequestIdMismatch {
+                request: req_id,
+                response: resp.req_id,
+            });
+        }
+    }
+    let req_vars = req.var_list();
+    let too_big_empty = resp.err == ErrorStatus::TooBig && resp.var.is_empty();
+    match req {
+        SnmpPdu::Bulk(bulk) => {
+            let n = (bulk.non_repeaters as usize).min(req_vars.len());
+            let m = req_vars.len() - n;
+            let max = n + m * bulk.max_repetitions as usize;
+            if resp.var.len() > max {
+                violations.push(ResponseViolation::VarCountMismatch {
+                    request: max,
+                    response: resp.var.len(),
+                });
+            }
+        }
+        _ if too_big_empty => (),
+        _ if req_vars.len() != resp.var.len() => {
+            violations.push(ResponseViolation::VarCountMismatch {
+                request: req_vars.len(),
+                response: resp.var.len(),
+            });
+        }
+        _ if req.pdu_type() != PduType::GetNextRequest => {
+            for (index, (r, v)) in req_vars.iter().zip(resp.var.iter()).enumerate() {
+                if r.oid != v.oid {
+                    violations.push(ResponseViolation::OidMismatch {
+                        index,
+                        oid: v.oid.to_owned(),
+                        expected: r.oid.to_owned(),
+                    });
+                }
+            }
+        }
+        _ => (),
+    }
+    let error_index_valid = if resp.err == ErrorStatus::NoError {
+        resp.err_index == 0
+    } else {
+        resp.err_index as usize <= resp.var.len()
+    };
+    if !error_index_valid {
+        violations.push(ResponseViolation::InvalidErrorIndex {
+            err_index: resp.err_index,
+            var_count: resp.var.len(),
+        });
+    }
+    if response.version() == 0 {
+        if resp.err.0 > ErrorStatus::GenErr.0 {
+            violations.push(ResponseViolation::V2ErrorStatusInV1(resp.err));
+        }
+        for (index, var) in resp.var.iter().enumerate() {
+            if var.val.is_exception() {
+                violations.push(ResponseViolation::ExceptionInV1 { index });
+            } else if let Some(ObjectSyntax::Counter64(_)) = var.val.value() {
+                violations.push(ResponseViolation::Counter64InV1 { index });
+            }
+        }
+    }
+    for _violation in &violations {
+        snmp_anomaly!(violation = ?_violation, "response is not consistent with the request");
+    }
+    violations
+}
+
+/// Reason of a SNMPv3 Report PDU, given by the statistics counter of its first variable
+///
+/// Counters are defined in [RFC3414](https://tools.ietf.org/html/rfc3414) section 5 (USM) and
+/// [RFC3412](https://tools.ietf.org/html/rfc3412) section 5 (message processing).
+#[cfg(feature = "v3")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ReportReason {
+    /// `usmStatsUnsupportedSecLevels`
+    UnsupportedSecLevel,
+    /// `usmStatsNotInTimeWindows`: time synchronization
+    NotInTimeWindow,
+    /// `usmStatsUnknownUserNames`
+    UnknownUserName,
+    /// `usmStatsUnknownEngineIDs`: engine ID discovery
+    UnknownEngineId,
+    /// `usmStatsWrongDigests`
+    WrongDigest,
+    /// `usmStatsDecryptionErrors`
+    DecryptionError,
+    /// `snmpUnknownSecurityModels`
+    UnknownSecurityModel,
+    /// `snmpInvalidMsgs`
+    InvalidMsg,
+    /// `snmpUnknownPDUHandlers`
+    UnknownPduHandler,
+    /// No variable, or an unknown counter
+    Other,
+}
+
+#[cfg(feature = "v3")]
+const REPORT_REASONS: &[(Oid<'static>, ReportReason)] = &[
+    (
+        oid!(1.3.6 .1 .6 .3 .15 .1 .1 .1 .0),
+        ReportReason::UnsupportedSecLevel,
+    ),
+    (
+        oid!(1.3.6 .1 .6 .3 .15 .1 .1 .2 .0),
+        ReportReason::NotInTimeWindow,
+    ),
+    (
+        oid!(1.3.6 .1 .6 .3 .15 .1 .1 .3 .0),
+        ReportReason::UnknownUserName,
+    ),
+    (
+        oid!(1.3.6 .1 .6 .3 .15 .1 .1 .4 .0),
+        ReportReason::UnknownEngineId,
+    ),
+    (
+        oid!(1.3.6 .1 .6 .3 .15 .1 .1 .5 .0),
+        ReportReason::WrongDigest,
+    ),
+    (
+        oid!(1.3.6 .1 .6 .3 .15 .1 .1 .6 .0),
+        ReportReason::DecryptionError,
+    ),
+    (
+        oid!(1.3.6 .1 .6 .3 .11 .2 .1 .1 .0),
+        ReportReason::UnknownSecurityModel,
+    ),
+    (
+        oid!(1.3.6 .1 .6 .3 .11 .2 .1 .2 .0),
+        ReportReason::InvalidMsg,
+    ),
+    (
+        oid!(1.3.6 .1 .6 .3 .11 .2 .1 .3 .0),
+        ReportReason::UnknownPduHandler,
+    ),
+];
+
+#[cfg(feature = "v3")]
+impl ReportReason {
+    /// Return the reason of `pdu`, or `None` if it is not a Report PDU
+    pub fn from_pdu(pdu: &SnmpPdu) -> Option<ReportReason> {
+        if pdu.pdu_type() != PduType::Report {
+            return None;
+        }
+        let reason = pdu
+            .vars_iter()
+            .next()
+            .and_then(|var| REPORT_REASONS.iter().find(|(oid, _)| *oid == var.oid))
+            .map_or(ReportReason::Other, |(_, reason)| *reason);
+        Some(reason)
+    }
+
+    /// Return `true` for the reports of engine ID discovery and time synchronization
+    ///
+    /// These reports are a normal step of the first exchanges with an engine (see RFC3414
+    /// section 4), after which the request is sent again with a new `msgID`.
+    pub fn is_synchronization(self) -> bool {
+        matches!(
+            self,
+            ReportReason::UnknownEngineId | ReportReason::NotInTimeWindow
+        )
+    }
+}
+
+/// How a SNMPv3 reply answers a request, returned by [`correlate_v3`]
+#[cfg(feature = "v3")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum V3Correlation {
+    /// A Response PDU (or an encrypted PDU, assumed to be a response)
+    Response,
+    /// A Report PDU, sent instead of a response
+    Report(ReportReason),
+}
+
+#[cfg(feature = "v3")]
+impl V3Correlation {
+    /// Return `true` for discovery and time synchronization reports
+    pub fn is_synchronization(self) -> bool {
+        matches!(self, V3Correlation::Report(reason) if reason.is_synchronization())
+    }
+}
+
+#[cfg(feature = "v3")]
+fn v3_pdu<'a, 'b>(msg: &'b SnmpV3Message<'a>) -> Option<&'b SnmpPdu<'a>> {
+    match msg.data {
+        ScopedPduData::Plaintext(ref scoped_pdu) => Some(&scoped_pdu.data),
+        ScopedPduData::Encrypted(_) => None,
+    }
+}
+
+#[cfg(feature = "v3")]
+fn correlate_ids(msg_id: u32, req_id: Option<u32>, reply: &SnmpV3Message) -> Option<V3Correlation> {
+    if reply.header_data.msg_id != msg_id {
+        return None;
+    }
+    let pdu = match v3_pdu(reply) {
+        Some(pdu) => pdu,
+        None => return Some(V3Correlation::Response),
+    };
+    let reply_req_id = pdu.request_id();
+    match pdu.pdu_type() {
+        PduType::Response if req_id.is_none() || reply_req_id == req_id => {
+            Some(V3Correlation::Response)
+        }
+        // the request ID is 0 if the request PDU could not be decoded
+        PduType::Report
+            if req_id.is_none() || reply_req_id == req_id || reply_req_id == Some(0) =>
+        {
+            ReportReason::from_pdu(pdu).map(V3Correlation::Report)
+        }
+        _ => None,
+    }
+}
+
+/// Test if `reply` answers `request`, and how
+///
+/// The reply must have the `msgID` of the request. If both PDUs are visible, a Response PDU must
+/// also have the request ID of the request, and a Report PDU the request ID of the request or 0
+/// (RFC3412 section 7.1). An encrypted reply with the `msgID` of the request is assumed to be a
+/// response.
+///
+/// Return `None` if `reply` does not answer `request`.
+#[cfg(feature = "v3")]
+pub fn correlate_v3(request: &SnmpV3Message, reply: &SnmpV3Message) -> Option<V3Correlation> {
+    let req_id = v3_pdu(request).and_then(|pdu| pdu.request_id());
+    correlate_ids(request.header_data.msg_id, req_id, reply)
+}
+
+/// Default maximum number of requests stored by [`V3RequestTracker`]
+#[cfg(all(feature = "v3", feature = "std"))]
+pub const DEFAULT_MAX_PENDING_V3_REQUESTS: usize = 10_000;
+
+/// A SNMPv3 reply matched with a request, returned by [`V3RequestTracker::observe_reply`]
+#[cfg(all(feature = "v3", feature = "std"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct V3Reply {
+    pub correlation: V3Correlation,
+    /// Time between the request and the reply
+    pub delay: Duration,
+}
+
+/// A SNMPv3 request which was not answered before the timeout, returned by
+/// [`V3RequestTracker::expire`]
+#[cfg(all(feature = "v3", feature = "std"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnansweredV3Request {
+    pub peer: IpAddr,
+    pub msg_id: u32,
+    /// Request ID, or `None` if the request is encrypted
+    pub req_id: Option<u32>,
+}
+
+#[cfg(all(feature = "v3", feature = "std"))]
+/// Matching of SNMPv3 requests with their replies
+///
+/// Requests are keyed by the address of the agent (the destination of the request, and the
+/// source of the reply) and the `msgID`. A request is answered by a Response or a Report PDU,
+/// see [`correlate_v3`]. A request answered by a discovery or time synchronization report is
+/// sent again with a new `msgID`, so it is not reported as unanswered.
+///
+/// Requests which are not answered within `timeout` are returned by
+/// [`expire`](V3RequestTracker::expire), which should be called periodically. At most
+/// `max_pending` requests are stored (see [`with_max_pending`](Self::with_max_pending)): when
+/// the limit is reached, the least recently seen request is dropped. Like
+/// [`InformTracker`](crate::trap::InformTracker), time is provided by the caller.
+#[derive(Debug)]
+pub struct V3RequestTracker {
+    /// Request ID of the pending requests, if visible
+    pending: PendingRequests<Option<u32>>,
+}
+
+#[cfg(all(feature = "v3", feature = "std"))]
+impl V3RequestTracker {
+    /// Create a tracker storing at most [`DEFAULT_MAX_PENDING_V3_REQUESTS`] requests
+    pub fn new(timeout: Duration) -> Self {
+        Self::with_max_pending(timeout, DEFAULT_MAX_PENDING_V3_REQUESTS)
+    }
+
+    /// Create a tracker storing at most `max_pending` requests
+    pub fn with_max_pending(timeout: Duration, max_pending: usize) -> Self {
+        V3RequestTracker {
+            pending: PendingRequests::new(timeout, max_pending),
+        }
+    }
+
+    /// Record a request sent to `peer` at time `now`
+    ///
+    /// Return `false` (and ignore the message) if the PDU is visible and is not a Get, GetNext,
+    /// GetBulk, Set or Inform request.
+    pub fn observe_request(&mut self, peer: IpAddr, msg: &SnmpV3Message, now: Instant) -> bool {
+        let req_id = match v3_pdu(msg) {
+            Some(pdu) => {
+                if !matches!(
+                    pdu.pdu_type(),
+                    PduType::GetRequest
+                        | PduType::GetNextRequest
+                        | PduType::GetBulkRequest
+                        | PduType::SetRequest
+                        | PduType::InformRequest
+                ) {
+                    return false;
+                }
+                pdu.request_id()
+            }
+            None => None,
+        };
+        (self.pending).insert(peer, msg.header_data.msg_id, now, req_id);
+        true
+    }
+
+    /// Match a reply received from `peer` at time `now` with a pending request
+    ///
+    /// Return `None` if the message does not answer a pending request.
+    pub fn observe_reply(
+        &mut self,
+        peer: IpAddr,
+        msg: &SnmpV3Message,
+        now: Instant,
+    ) -> Option<V3Reply> {
+        let msg_id = msg.header_data.msg_id;
+        let pending = self.pending.get(peer, msg_id)?;
+        let correlation = correlate_ids(msg_id, pending.data, msg)?;
+        let delay = now.saturating_duration_since(pending.last_seen);
+        self.pending.remove(peer, msg_id);
+        Some(V3Reply { correlation, delay })
+    }
+
+    /// Remove and return the requests which were not answered within the timeout
+    pub fn expire(&mut self, now: Instant) -> Vec<UnansweredV3Request> {
+        (self.pending.expire(now).into_iter())
+            .map(|(peer, msg_id, p)| UnansweredV3Request {
+                peer,
+                msg_id,
+                req_id: p.data,
+            })
+            .collect()
+    }
+
+    /// Return the number of requests waiting for a reply
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.len() == 0
+    }
+}
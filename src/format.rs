@@ -0,0 +1,275 @@
+//! Formatting helpers for SNMP values
+//!
+//! OCTET STRING values are used both for text (for ex. `sysDescr`) and binary data (for ex. MAC
+//! addresses). [`format_octet_string`] renders printable values as text, and other values as hex,
+//! like the `net-snmp` tools:
+//!
+//! ```rust
+//! use snmp_parser::format::{format_octet_string, StringEscape};
+//!
+//! assert_eq!(format_octet_string(b"eth0").to_string(), "\"eth0\"");
+//! assert_eq!(
+//!     format_octet_string(b"\x00\x1b\x21").to_string(),
+//!     "Hex-STRING: 00 1B 21"
+//! );
+//! // without quotes, for ex. to be escaped by a JSON writer
+//! let s = format_octet_string(b"eth0").escape(StringEscape::Raw);
+//! assert_eq!(s.to_string(), "eth0");
+//! ```
+//!
+//! [`format_varbind_value`] formats any variable value in the same way, and [`format_oid`]
+//! writes OIDs in dotted form without allocating, unlike `Oid::to_id_string`.
+//!
+//! [`CompactMessage`] formats a message on one line, with a limited number of variables and
+//! optionally without secrets, for logs (see `SnmpMessage::fmt_compact`).
+
+use crate::snmp::{NetworkAddress, ObjectSyntax, SnmpPdu, VarBindValue};
+use asn1_rs::Oid;
+use core::fmt::{self, Write};
+
+/// Size of the stack buffer used to format OIDs (longer OIDs are written in several parts)
+const OID_BUFFER_SIZE: usize = 256;
+
+/// Escaping of printable OCTET STRING values
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StringEscape {
+    /// Surround the value with double quotes, and escape quotes and backslashes with a
+    /// backslash (default)
+    Quoted,
+    /// Write the value as is, without quotes
+    Raw,
+}
+
+/// Formatter for OCTET STRING values, see [`format_octet_string`]
+#[derive(Clone, Copy, Debug)]
+pub struct OctetStringDisplay<'a> {
+    data: &'a [u8],
+    escape: StringEscape,
+}
+
+/// Format an OCTET STRING value as text if printable, or else as hex
+///
+/// A value is printable if it is valid UTF-8 and has no control characters. Other values are
+/// written as `Hex-STRING: ` followed by the bytes as uppercase hex, separated by spaces.
+pub fn format_octet_string(data: &[u8]) -> OctetStringDisplay<'_> {
+    OctetStringDisplay {
+        data,
+        escape: StringEscape::Quoted,
+    }
+}
+
+impl<'a> OctetStringDisplay<'a> {
+    /// Set the escaping of printable values
+    pub fn escape(self, escape: StringEscape) -> Self {
+        OctetStringDisplay { escape, ..self }
+    }
+
+    /// Return the value as text, if it is printable
+    pub fn as_printable(&self) -> Option<&'a str> {
+        match core::str::from_utf8(self.data) {
+            Ok(s) if !s.chars().any(char::is_control) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> fmt::Display for OctetStringDisplay<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self.as_printable() {
+            Some(s) => s,
+            None => {
+                f.write_str("Hex-STRING:")?;
+                for b in self.data {
+                    write!(f, " {:02X}", b)?;
+                }
+                return Ok(());
+            }
+        };
+        match self.escape {
+            StringEscape::Raw => f.write_str(s),
+            StringEscape::Quoted => {
+                f.write_char('"')?;
+                for c in s.chars() {
+                    if c == '"' || c == '\\' {
+                        f.write_char('\\')?;
+                    }
+                    f.write_char(c)?;
+                }
+                f.write_char('"')
+            }
+        }
+    }
+}
+
+/// Formatter for OIDs, see [`format_oid`]
+#[derive(Clone, Copy, Debug)]
+pub struct OidDisplay<'o, 'a> {
+    oid: &'o Oid<'a>,
+}
+
+/// Format an OID in dotted form, for ex. `1.3.6.1.2.1.1.5.0`
+///
+/// The output is the same as `Oid::to_id_string`, but is written through a buffer on the stack.
+/// OIDs with arcs which do not fit in a `u64` are written by `Oid::to_id_string`.
+pub fn format_oid<'o, 'a>(oid: &'o Oid<'a>) -> OidDisplay<'o, 'a> {
+    OidDisplay { oid }
+}
+
+impl<'o, 'a> fmt::Display for OidDisplay<'o, 'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let arcs = match self.oid.iter() {
+            Some(arcs) => arcs,
+            None => return f.write_str(&self.oid.to_id_string()),
+        };
+        let mut buf = [0u8; OID_BUFFER_SIZE];
+        let mut len = 0;
+        for (i, arc) in arcs.enumerate() {
+            // room for a separator and 20 digits
+            if len + 21 > buf.len() {
+                f.write_str(core::str::from_utf8(&buf[..len]).map_err(|_| fmt::Error)?)?;
+                len = 0;
+            }
+            if i > 0 {
+                buf[len] = b'.';
+                len += 1;
+            }
+            let mut digits = [0u8; 20];
+            let mut start = digits.len();
+            let mut n = arc;
+            loop {
+                start -= 1;
+                digits[start] = b'0' + (n % 10) as u8;
+                n /= 10;
+                if n == 0 {
+                    break;
+                }
+            }
+            let digits = &digits[start..];
+            buf[len..len + digits.len()].copy_from_slice(digits);
+            len += digits.len();
+        }
+        f.write_str(core::str::from_utf8(&buf[..len]).map_err(|_| fmt::Error)?)
+    }
+}
+
+/// Compact, one-line formatter for messages
+///
+/// The output has the form `version=1 community="public" pdu=Response req_id=42 vars=3
+/// [1.3.6.1.2.1.1.3.0=1234, ... 2 more]`. At most `max_varbinds` variables are written,
+/// followed by the number of omitted variables.
+/// When secrets are redacted, the community or user name is replaced by `***`.
+///
+/// Created by the `fmt_compact` methods of the message types.
+#[derive(Clone, Copy, Debug)]
+pub struct CompactMessage<'m, 'a> {
+    version: u32,
+    principal: Option<(&'static str, &'m [u8])>,
+    pdu: Option<&'m SnmpPdu<'a>>,
+    max_varbinds: usize,
+    redact_secrets: bool,
+}
+
+impl<'m, 'a> CompactMessage<'m, 'a> {
+    /// `principal` is the name and value of the community or user field, and `pdu` is `None`
+    /// if the PDU is encrypted
+    pub(crate) fn new(
+        version: u32,
+        principal: Option<(&'static str, &'m [u8])>,
+        pdu: Option<&'m SnmpPdu<'a>>,
+        max_varbinds: usize,
+        redact_secrets: bool,
+    ) -> Self {
+        CompactMessage {
+            version,
+            principal,
+            pdu,
+            max_varbinds,
+            redact_secrets,
+        }
+    }
+}
+
+impl<'m, 'a> fmt::Display for CompactMessage<'m, 'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "version={}", self.version)?;
+        if let Some((name, value)) = self.principal {
+            if self.redact_secrets {
+                write!(f, " {}=***", name)?;
+            } else {
+                write!(f, " {}={}", name, format_octet_string(value))?;
+            }
+        }
+        let pdu = match self.pdu {
+            Some(pdu) => pdu,
+            None => return f.write_str(" pdu=encrypted"),
+        };
+        write!(f, " pdu={:?}", pdu.pdu_type())?;
+        if let Some(req_id) = pdu.request_id() {
+            write!(f, " req_id={}", req_id)?;
+        }
+        let count = pdu.vars_iter().count();
+        write!(f, " vars={}", count)?;
+        if count == 0 {
+            return Ok(());
+        }
+        f.write_str(" [")?;
+        for (i, var) in pdu.vars_iter().take(self.max_varbinds).enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(
+                f,
+                "{}={}",
+                format_oid(&var.oid),
+                format_varbind_value(&var.val)
+            )?;
+        }
+        if count > self.max_varbinds {
+            if self.max_varbinds > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "... {} more", count - self.max_varbinds)?;
+        }
+        f.write_char(']')
+    }
+}
+
+/// Formatter for variable values, see [`format_varbind_value`]
+#[derive(Clone, Copy, Debug)]
+pub struct VarBindValueDisplay<'v, 'a> {
+    val: &'v VarBindValue<'a>,
+}
+
+/// Format a variable value for humans
+///
+/// Numbers are written in decimal, OIDs in dotted form, strings using [`format_octet_string`],
+/// and exceptions by their name (for ex. `noSuchObject`).
+pub fn format_varbind_value<'v, 'a>(val: &'v VarBindValue<'a>) -> VarBindValueDisplay<'v, 'a> {
+    VarBindValueDisplay { val }
+}
+
+impl<'v, 'a> fmt::Display for VarBindValueDisplay<'v, 'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let obj = match self.val {
+            VarBindValue::Value(obj) => obj,
+            VarBindValue::Unspecified => return f.write_str("NULL"),
+            VarBindValue::NoSuchObject => return f.write_str("noSuchObject"),
+            VarBindValue::NoSuchInstance => return f.write_str("noSuchInstance"),
+            VarBindValue::EndOfMibView => return f.write_str("endOfMibView"),
+        };
+        match obj {
+            ObjectSyntax::Number(n) => write!(f, "{}", n),
+            ObjectSyntax::String(s) => write!(f, "{}", format_octet_string(s)),
+            ObjectSyntax::Object(oid) => write!(f, "{}", format_oid(oid)),
+            ObjectSyntax::IpAddress(NetworkAddress::IPv4(ip)) => write!(f, "{}", ip),
+            ObjectSyntax::Counter32(n) | ObjectSyntax::Gauge32(n) | ObjectSyntax::TimeTicks(n) => {
+                write!(f, "{}", n)
+            }
+            ObjectSyntax::Counter64(n) => write!(f, "{}", n),
+            ObjectSyntax::UInteger32(n) => write!(f, "{}", n),
+            ObjectSyntax::Real(n) => write!(f, "{}", n),
+            ObjectSyntax::Enumerated(n) => write!(f, "{}", n),
+            obj => write!(f, "{:?}", obj),
+        }
+    }
+}
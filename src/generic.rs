@@ -1,72 +1,269 @@
-use crate::error::SnmpError;
+#[cfg(any(
+    feature = "v1",
+    feature = "v2c",
+    feature = "v2p",
+    feature = "v2u",
+    feature = "v3"
+))]
+use crate::error::cut;
+use crate::error::{context, finish, resolve_offsets, SnmpError};
+use crate::format::CompactMessage;
+use crate::instrument::trace_parse_result;
 use crate::snmp::*;
+#[cfg(feature = "v2p")]
+use crate::snmpv2p::*;
+#[cfg(feature = "v2u")]
+use crate::snmpv2u::*;
+#[cfg(feature = "v3")]
 use crate::snmpv3::*;
-use asn1_rs::{Any, FromBer, Tag};
-use nom::combinator::map_res;
+use asn1_rs::{Any, FromBer, Header, Length, ParseResult, Tag, Tagged};
 use nom::{Err, IResult};
 
 #[derive(Debug, PartialEq)]
 pub enum SnmpGenericMessage<'a> {
     V1(SnmpMessage<'a>),
     V2(SnmpMessage<'a>),
+    /// Historic party-based SNMPv2 message
+    #[cfg(feature = "v2p")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "v2p")))]
+    V2p(SnmpV2pMessage<'a>),
+    /// Historic user-based SNMPv2 (SNMPv2u) message
+    #[cfg(feature = "v2u")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "v2u")))]
+    V2u(SnmpV2uMessage<'a>),
+    #[cfg(feature = "v3")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "v3")))]
     V3(SnmpV3Message<'a>),
 }
 
-fn parse_snmp_v1_pdu_content(i: &[u8]) -> IResult<&[u8], SnmpMessage, SnmpError> {
-    let (i, community) = parse_ber_octetstring_as_str(i).map_err(Err::convert)?;
-    let (i, pdu) = parse_snmp_v1_pdu(i)?;
-    let msg = SnmpMessage {
-        version: 0,
-        community: community.to_string(),
-        pdu,
-    };
-    Ok((i, msg))
+impl<'a> SnmpGenericMessage<'a> {
+    /// Parse a SNMP message of any supported version, returning the message and the number of
+    /// bytes read
+    ///
+    /// This is equivalent to [`parse_snmp_generic_message`], for callers not using `nom`.
+    pub fn parse(bytes: &'a [u8]) -> Result<(Self, usize), SnmpError> {
+        finish(parse_snmp_generic_message(bytes), bytes)
+    }
+
+    /// Version, as raw-encoded: 0 for SNMPv1, 1 for SNMPv2c, 2 for party-based SNMPv2 and
+    /// SNMPv2u, 3 for SNMPv3
+    pub fn version(&self) -> u32 {
+        match self {
+            SnmpGenericMessage::V1(msg) | SnmpGenericMessage::V2(msg) => msg.version,
+            #[cfg(feature = "v2p")]
+            SnmpGenericMessage::V2p(msg) => msg.version,
+            #[cfg(feature = "v2u")]
+            SnmpGenericMessage::V2u(msg) => msg.version,
+            #[cfg(feature = "v3")]
+            SnmpGenericMessage::V3(msg) => msg.version,
+        }
+    }
+
+    /// Return the community, if this is a community-based (SNMPv1 or SNMPv2c) message
+    pub fn community(&self) -> Option<&str> {
+        match self {
+            SnmpGenericMessage::V1(msg) | SnmpGenericMessage::V2(msg) => Some(&msg.community),
+            #[cfg(feature = "v2p")]
+            SnmpGenericMessage::V2p(_) => None,
+            #[cfg(feature = "v2u")]
+            SnmpGenericMessage::V2u(_) => None,
+            #[cfg(feature = "v3")]
+            SnmpGenericMessage::V3(_) => None,
+        }
+    }
+
+    pub(crate) fn trace_fields(&self) -> (Option<PduType>, usize) {
+        match self {
+            SnmpGenericMessage::V1(msg) | SnmpGenericMessage::V2(msg) => msg.trace_fields(),
+            #[cfg(feature = "v2p")]
+            SnmpGenericMessage::V2p(msg) => msg.trace_fields(),
+            #[cfg(feature = "v2u")]
+            SnmpGenericMessage::V2u(msg) => msg.trace_fields(),
+            #[cfg(feature = "v3")]
+            SnmpGenericMessage::V3(msg) => msg.trace_fields(),
+        }
+    }
+
+    /// Format the message on one line, with at most `max_varbinds` variables
+    ///
+    /// If `redact_secrets` is `true`, the community (SNMPv1 and SNMPv2c) or user name (SNMPv2u
+    /// and SNMPv3 with USM) is not written. See [`CompactMessage`].
+    pub fn fmt_compact(&self, max_varbinds: usize, redact_secrets: bool) -> CompactMessage<'_, 'a> {
+        match self {
+            SnmpGenericMessage::V1(msg) | SnmpGenericMessage::V2(msg) => {
+                msg.fmt_compact(max_varbinds, redact_secrets)
+            }
+            #[cfg(feature = "v2p")]
+            SnmpGenericMessage::V2p(msg) => {
+                CompactMessage::new(msg.version, None, msg.pdu(), max_varbinds, redact_secrets)
+            }
+            #[cfg(feature = "v2u")]
+            SnmpGenericMessage::V2u(msg) => CompactMessage::new(
+                msg.version,
+                Some(("user", msg.parameters.user_name)),
+                msg.pdu(),
+                max_varbinds,
+                redact_secrets,
+            ),
+            #[cfg(feature = "v3")]
+            SnmpGenericMessage::V3(msg) => msg.fmt_compact(max_varbinds, redact_secrets),
+        }
+    }
+
+    /// Return the PDU, if it is available (that is, unless this is an encrypted message)
+    pub fn pdu(&self) -> Option<&SnmpPdu<'a>> {
+        match self {
+            SnmpGenericMessage::V1(msg) | SnmpGenericMessage::V2(msg) => Some(&msg.pdu),
+            #[cfg(feature = "v2p")]
+            SnmpGenericMessage::V2p(msg) => msg.pdu(),
+            #[cfg(feature = "v2u")]
+            SnmpGenericMessage::V2u(msg) => msg.pdu(),
+            #[cfg(feature = "v3")]
+            SnmpGenericMessage::V3(msg) => match msg.data {
+                ScopedPduData::Plaintext(ref scoped) => Some(&scoped.data),
+                ScopedPduData::Encrypted(_) => None,
+            },
+        }
+    }
+
+    pub(crate) fn pdu_mut(&mut self) -> Option<&mut SnmpPdu<'a>> {
+        match self {
+            SnmpGenericMessage::V1(msg) | SnmpGenericMessage::V2(msg) => Some(&mut msg.pdu),
+            #[cfg(feature = "v2p")]
+            SnmpGenericMessage::V2p(msg) => msg.auth_msg.as_mut().map(|auth| &mut auth.pdu),
+            #[cfg(feature = "v2u")]
+            SnmpGenericMessage::V2u(msg) => match msg.data {
+                SnmpV2uData::Plaintext(ref mut pdu) => Some(pdu),
+                SnmpV2uData::Encrypted(_) => None,
+            },
+            #[cfg(feature = "v3")]
+            SnmpGenericMessage::V3(msg) => match msg.data {
+                ScopedPduData::Plaintext(ref mut scoped) => Some(&mut scoped.data),
+                ScopedPduData::Encrypted(_) => None,
+            },
+        }
+    }
 }
 
-fn parse_snmp_v2c_pdu_content(i: &[u8]) -> IResult<&[u8], SnmpMessage, SnmpError> {
-    let (i, community) = parse_ber_octetstring_as_str(i).map_err(Err::convert)?;
-    let (i, pdu) = parse_snmp_v2c_pdu(i)?;
-    let msg = SnmpMessage {
-        version: 1,
-        community: community.to_string(),
-        pdu,
-    };
-    Ok((i, msg))
+/// Parse a SNMP message, of any supported version
+///
+/// # Errors
+///
+/// If the input is not a SEQUENCE, or if the version is not supported, a recoverable error
+/// (`nom::Err::Error`) is returned. Once the version has been recognized, errors are returned
+/// as `nom::Err::Failure`.
+pub fn parse_snmp_generic_message(i: &[u8]) -> IResult<&[u8], SnmpGenericMessage<'_>, SnmpError> {
+    parse_snmp_generic_message_with_options(i, &ParseOptions::DEFAULT)
 }
 
-fn parse_snmp_v3_pdu_content(i: &[u8]) -> IResult<&[u8], SnmpV3Message, SnmpError> {
-    let (i, hdr) = parse_snmp_v3_headerdata(i)?;
-    let (i, secp) = map_res(<&[u8]>::from_ber, |x| parse_secp(x, &hdr))(i).map_err(Err::convert)?;
-    let (i, data) = parse_snmp_v3_data(i, &hdr)?;
-    let msg = SnmpV3Message {
-        version: 3,
-        header_data: hdr,
-        security_params: secp,
-        data,
-    };
-    Ok((i, msg))
+pub(crate) fn parse_snmp_generic_message_with_options<'a>(
+    i: &'a [u8],
+    opts: &ParseOptions,
+) -> IResult<&'a [u8], SnmpGenericMessage<'a>, SnmpError> {
+    snmp_span!("parse_snmp_generic_message", len = i.len());
+    let res = resolve_offsets(parse_snmp_generic_message_content(i, opts), i);
+    trace_parse_result(&res, SnmpGenericMessage::trace_fields);
+    res
 }
 
-pub fn parse_snmp_generic_message(i: &[u8]) -> IResult<&[u8], SnmpGenericMessage, SnmpError> {
-    let (rem, any) = Any::from_ber(i).or(Err(Err::Error(SnmpError::InvalidMessage)))?;
-    if any.tag() != Tag::Sequence {
+// without any version, all messages are rejected after reading the version
+#[cfg_attr(
+    not(any(
+        feature = "v1",
+        feature = "v2c",
+        feature = "v2p",
+        feature = "v2u",
+        feature = "v3"
+    )),
+    allow(unreachable_code, unused_variables)
+)]
+fn parse_snmp_generic_message_content<'a>(
+    i: &'a [u8],
+    _opts: &ParseOptions,
+) -> IResult<&'a [u8], SnmpGenericMessage<'a>, SnmpError> {
+    // read only the header, so the content is not traversed before dispatching on the version
+    let (content, hdr) = Header::from_ber(i).or(Err(Err::Error(SnmpError::InvalidMessage)))?;
+    if hdr.tag() != Tag::Sequence {
+        snmp_anomaly!(tag = ?hdr.tag(), "message is not a sequence");
         return Err(Err::Error(SnmpError::InvalidMessage));
     }
-    let (r, version) = u32::from_ber(any.data).map_err(Err::convert)?;
-    let (_, msg) = match version {
+    let (data, rem) = match hdr.length() {
+        Length::Definite(len) if len <= content.len() => content.split_at(len),
+        Length::Definite(_) => return Err(Err::Error(SnmpError::InvalidMessage)),
+        Length::Indefinite => {
+            // the end of the content is only known by reading it
+            let (rem, any) = Any::from_ber(i).or(Err(Err::Error(SnmpError::InvalidMessage)))?;
+            (any.data, rem)
+        }
+    };
+    let (r, version) = context("version", u32::from_ber)(data)?;
+    snmp_event!(version, "version dispatch");
+    let (_, msg): (&[u8], SnmpGenericMessage) = match version {
+        #[cfg(feature = "v1")]
         0 => {
-            let (rem, msg) = parse_snmp_v1_pdu_content(r)?;
+            let (rem, msg) = parse_snmp_v1_content(r, _opts).map_err(cut)?;
             (rem, SnmpGenericMessage::V1(msg))
         }
+        #[cfg(feature = "v2c")]
         1 => {
-            let (rem, msg) = parse_snmp_v2c_pdu_content(r)?;
+            let (rem, msg) = parse_snmp_v2c_content(r, _opts).map_err(cut)?;
             (rem, SnmpGenericMessage::V2(msg))
         }
+        // party-based SNMPv2 and SNMPv2u both use version 2: SNMPv2u parameters are an
+        // OCTET STRING, while party-based messages continue with a [1] tag
+        #[cfg(feature = "v2u")]
+        2 if r.first() == Some(&0x04) => {
+            let (rem, msg) = parse_snmp_v2u_content(r).map_err(cut)?;
+            (rem, SnmpGenericMessage::V2u(msg))
+        }
+        #[cfg(feature = "v2p")]
+        2 if r.first() != Some(&0x04) => {
+            let (rem, msg) = parse_snmp_v2p_content(r).map_err(cut)?;
+            (rem, SnmpGenericMessage::V2p(msg))
+        }
+        #[cfg(feature = "v3")]
         3 => {
-            let (rem, msg) = parse_snmp_v3_pdu_content(r)?;
+            let (rem, msg) = parse_snmp_v3_content(r, _opts).map_err(cut)?;
             (rem, SnmpGenericMessage::V3(msg))
         }
-        _ => return Err(Err::Error(SnmpError::InvalidVersion)),
+        _ => {
+            snmp_anomaly!(version, "unsupported version");
+            return Err(Err::Error(SnmpError::InvalidVersion { found: version }));
+        }
     };
     Ok((rem, msg))
 }
+
+/// Return the total length (header and content) of the SNMP message starting at `i`
+///
+/// Only the header of the outer SEQUENCE is read, so this can be used to split a stream (for
+/// ex. SNMP over TCP, [RFC3430](https://tools.ietf.org/html/rfc3430)) into messages before
+/// parsing them.
+///
+/// # Errors
+///
+/// If `i` is too short to contain the header, `nom::Err::Incomplete` is returned. If the header
+/// is not a SEQUENCE with a definite length, `SnmpError::InvalidMessage` is returned.
+pub fn snmp_message_len(i: &[u8]) -> Result<usize, Err<SnmpError>> {
+    let (rem, hdr) = Header::from_ber(i).map_err(|e| match e {
+        Err::Incomplete(n) => Err::Incomplete(n),
+        _ => Err::Error(SnmpError::InvalidMessage),
+    })?;
+    match (hdr.tag(), hdr.length()) {
+        (Tag::Sequence, Length::Definite(len)) => Ok(i.len() - rem.len() + len),
+        _ => Err(Err::Error(SnmpError::InvalidMessage)),
+    }
+}
+
+impl<'a> FromBer<'a, SnmpError> for SnmpGenericMessage<'a> {
+    /// Parse a SNMP message, of any supported version
+    ///
+    /// See [`parse_snmp_generic_message`].
+    fn from_ber(bytes: &'a [u8]) -> ParseResult<'a, Self, SnmpError> {
+        parse_snmp_generic_message(bytes)
+    }
+}
+
+impl<'a> Tagged for SnmpGenericMessage<'a> {
+    const TAG: Tag = Tag::Sequence;
+}
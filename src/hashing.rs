@@ -0,0 +1,166 @@
+//! Hashing of communities and user names
+//!
+//! Some deployments must not store credentials, but still need to correlate the messages using
+//! the same community or user name. [`parse_snmp_generic_message_hashed`] parses a message like
+//! [`parse_snmp_generic_message`](crate::parse_snmp_generic_message), but the community (SNMPv1
+//! and SNMPv2c) or the user name (SNMPv2u and SNMPv3 USM) is returned as a salted hash, computed
+//! while parsing: the values are never copied to the heap, and the corresponding fields of the
+//! message are left empty.
+//!
+//! ```rust
+//! use snmp_parser::hashing::{parse_snmp_generic_message_hashed, CredentialHasher};
+//!
+//! # static SNMPV2C_GET_RESPONSE: &[u8] = include_bytes!("../assets/snmpv2c-get-response.bin");
+//! # fn main() {
+//! let hasher = CredentialHasher::new(b"site salt");
+//! let (_, msg) = parse_snmp_generic_message_hashed(SNMPV2C_GET_RESPONSE, &hasher)
+//!     .expect("parsing failed");
+//! let credential = msg.credential.expect("no community");
+//! assert!(credential.matches(&hasher, b"public"));
+//! assert_eq!(credential.len, 6);
+//! # }
+//! ```
+//!
+//! Hashes are HMAC-SHA256, keyed by the salt and truncated to 128 bits. The salt should be a
+//! secret random value, shared by the sensors which need to correlate their outputs: communities
+//! are usually short, so unsalted (or known salt) hashes can be reversed by brute force.
+
+use crate::error::SnmpError;
+use crate::generic::{parse_snmp_generic_message_with_options, SnmpGenericMessage};
+use crate::snmp::{CommunityPolicy, CommunityViolation, ParseOptions};
+use alloc::string::String;
+use core::cell::Cell;
+use core::fmt;
+use hmac::{Hmac, Mac};
+use nom::IResult;
+use sha2::Sha256;
+
+/// Length of a [`CredentialHash`], in bytes
+pub const CREDENTIAL_HASH_LEN: usize = 16;
+
+/// Salted hash of a community or user name
+///
+/// Hashes are formatted in lowercase hexadecimal.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CredentialHash(pub [u8; CREDENTIAL_HASH_LEN]);
+
+impl fmt::Display for CredentialHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
+impl fmt::Debug for CredentialHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CredentialHash({})", self)
+    }
+}
+
+/// Salted hash function for communities and user names
+#[derive(Clone)]
+pub struct CredentialHasher {
+    mac: Hmac<Sha256>,
+}
+
+impl CredentialHasher {
+    pub fn new(salt: &[u8]) -> Self {
+        // HMAC accepts keys of any length
+        let mac = Hmac::new_from_slice(salt).expect("invalid HMAC key length");
+        CredentialHasher { mac }
+    }
+
+    /// Return the hash of `value`
+    pub fn hash(&self, value: &[u8]) -> CredentialHash {
+        let mut mac = self.mac.clone();
+        mac.update(value);
+        let digest = mac.finalize().into_bytes();
+        let mut hash = [0; CREDENTIAL_HASH_LEN];
+        hash.copy_from_slice(&digest[..CREDENTIAL_HASH_LEN]);
+        CredentialHash(hash)
+    }
+}
+
+impl fmt::Debug for CredentialHasher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // do not show the salt
+        f.write_str("CredentialHasher")
+    }
+}
+
+/// Salted hash of the community or user name of a message, with the length of the value
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct HashedCredential {
+    pub hash: CredentialHash,
+    /// Length of the community or user name, in bytes
+    pub len: usize,
+}
+
+impl HashedCredential {
+    /// Test if the hashed value is `expected`
+    ///
+    /// The time taken does not depend on the position of the first difference between the
+    /// hashes, see [`SnmpMessage::community_matches`](crate::SnmpMessage::community_matches).
+    pub fn matches(&self, hasher: &CredentialHasher, expected: &[u8]) -> bool {
+        let expected = hasher.hash(expected);
+        let diff = (self.hash.0.iter())
+            .zip(expected.0.iter())
+            .fold(0, |diff, (a, b)| diff | (a ^ b));
+        diff == 0
+    }
+
+    /// Check the length of the hashed community against `policy`
+    ///
+    /// See [`CommunityPolicy::check`].
+    pub fn check_community(&self, policy: &CommunityPolicy) -> Option<CommunityViolation> {
+        policy.check_len(self.len)
+    }
+}
+
+/// A SNMP message parsed by [`parse_snmp_generic_message_hashed`]
+#[derive(Debug, PartialEq)]
+pub struct HashedMessage<'a> {
+    /// The message, with an empty community or user name
+    pub message: SnmpGenericMessage<'a>,
+    /// The hashed community or user name, if the message has one
+    pub credential: Option<HashedCredential>,
+}
+
+/// Parse a SNMP message, of any supported version, storing hashes instead of credentials
+///
+/// See [`parse_snmp_generic_message`](crate::parse_snmp_generic_message). The community of
+/// SNMPv1 and SNMPv2c messages, and the user name of SNMPv2u messages and of the USM security
+/// parameters of SNMPv3 messages, are hashed into [`HashedMessage::credential`], and the
+/// corresponding fields of the message are empty.
+pub fn parse_snmp_generic_message_hashed<'a>(
+    i: &'a [u8],
+    hasher: &CredentialHasher,
+) -> IResult<&'a [u8], HashedMessage<'a>, SnmpError> {
+    let credential = Cell::new(None);
+    let hash = |value: &[u8]| HashedCredential {
+        hash: hasher.hash(value),
+        len: value.len(),
+    };
+    let store = |value: &str| {
+        credential.set(Some(hash(value.as_bytes())));
+        String::new()
+    };
+    let opts = ParseOptions {
+        store: &store,
+        lenient: false,
+    };
+    #[cfg_attr(not(feature = "v2u"), allow(unused_mut))]
+    let (rem, mut message) = parse_snmp_generic_message_with_options(i, &opts)?;
+    #[cfg(feature = "v2u")]
+    if let SnmpGenericMessage::V2u(ref mut msg) = message {
+        credential.set(Some(hash(msg.parameters.user_name)));
+        msg.parameters.user_name = &[];
+    }
+    let credential = credential.get();
+    Ok((
+        rem,
+        HashedMessage {
+            message,
+            credential,
+        },
+    ))
+}
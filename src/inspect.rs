@@ -0,0 +1,231 @@
+//! Allocation-free message inspection
+//!
+//! [`inspect_snmp_message`] reads the main fields of a message (version, community, PDU type,
+//! request ID) without using the heap. Variable bindings are not decoded, but can be iterated
+//! lazily using [`SnmpMessageHeader::varbinds`].
+//!
+//! This is useful for constrained environments, or to quickly classify messages before (or
+//! instead of) fully parsing them.
+//!
+//! Errors are returned without location information, since building it requires allocations.
+//!
+//! Only SNMPv1, SNMPv2c and SNMPv3 messages are supported. Messages using version 2 (the
+//! historic party-based SNMPv2 and SNMPv2u) are rejected with `SnmpError::InvalidVersion`: use
+//! [`parse_snmp_generic_message`](crate::parse_snmp_generic_message) to read them.
+
+use asn1_rs::{Any, FromBer, Oid, Tag};
+use core::convert::TryFrom;
+use nom::{Err, IResult};
+
+use crate::error::SnmpError;
+use crate::snmp::{check_value_depth, PduType, VarBindValue};
+#[cfg(feature = "v3")]
+use crate::snmpv3::{is_scoped_pdu_data_encrypted, HeaderData};
+
+/// Main fields of a SNMP message, read without allocating
+#[derive(Debug, PartialEq)]
+pub struct SnmpMessageHeader<'a> {
+    /// Version, as raw-encoded: 0 for SNMPv1, 1 for SNMPv2c, 3 for SNMPv3
+    pub version: u32,
+    /// Community, for SNMPv1 and SNMPv2c
+    pub community: Option<&'a [u8]>,
+    /// SNMPv3 header data
+    #[cfg(feature = "v3")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "v3")))]
+    pub header_data: Option<HeaderData>,
+    /// PDU type, or `None` if the PDU is encrypted
+    pub pdu_type: Option<PduType>,
+    /// Request ID, or `None` for SNMPv1 traps and encrypted PDUs
+    pub request_id: Option<u32>,
+    pub(crate) varbinds: &'a [u8],
+}
+
+impl<'a> SnmpMessageHeader<'a> {
+    /// Return a lazy iterator over the variable bindings
+    ///
+    /// The iterator is empty if the PDU is encrypted.
+    pub fn varbinds(&self) -> RawVarBinds<'a> {
+        RawVarBinds {
+            data: self.varbinds,
+        }
+    }
+
+    /// Count the variable bindings
+    ///
+    /// Invalid variable bindings are counted, and stop the iteration.
+    pub fn varbind_count(&self) -> usize {
+        self.varbinds().count()
+    }
+}
+
+/// A variable binding, where the value has not been decoded
+#[derive(Debug, PartialEq)]
+pub struct RawVarBind<'a> {
+    pub oid: Oid<'a>,
+    pub value: Any<'a>,
+}
+
+impl<'a> RawVarBind<'a> {
+    /// Decode the value
+    pub fn decode_value(&self) -> Result<VarBindValue<'a>, SnmpError> {
+        VarBindValue::try_from(self.value.clone()).map_err(SnmpError::from)
+    }
+}
+
+/// Lazy iterator over variable bindings
+///
+/// If a variable binding cannot be read, the error is returned and the iteration stops.
+#[derive(Clone, Debug)]
+pub struct RawVarBinds<'a> {
+    pub(crate) data: &'a [u8],
+}
+
+impl<'a> Iterator for RawVarBinds<'a> {
+    type Item = Result<RawVarBind<'a>, SnmpError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        match parse_raw_varbind(self.data) {
+            Ok((rem, var)) => {
+                self.data = rem;
+                Some(Ok(var))
+            }
+            Err(e) => {
+                self.data = &[];
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+fn parse_raw_varbind(i: &[u8]) -> Result<(&[u8], RawVarBind<'_>), SnmpError> {
+    let (rem, seq) = Any::from_ber(i).map_err(ber_error)?;
+    seq.tag().assert_eq(Tag::Sequence)?;
+    let (data, oid) = Oid::from_ber(seq.data).map_err(ber_error)?;
+    check_value_depth(data)?;
+    let (_, value) = Any::from_ber(data).map_err(ber_error)?;
+    Ok((rem, RawVarBind { oid, value }))
+}
+
+pub(crate) fn ber_error(e: Err<asn1_rs::Error>) -> SnmpError {
+    match e {
+        Err::Error(e) | Err::Failure(e) => SnmpError::BerError(e),
+        Err::Incomplete(_) => SnmpError::InvalidMessage,
+    }
+}
+
+/// Read the main fields of a SNMPv1, SNMPv2c or SNMPv3 message, without allocating
+///
+/// Messages using version 2 are rejected (see the [module documentation](self)). The PDU is not
+/// fully validated: only the fields required to read the request ID and to
+/// locate the variable bindings are checked.
+///
+/// Example:
+///
+/// ```rust
+/// use snmp_parser::inspect::inspect_snmp_message;
+/// use snmp_parser::PduType;
+///
+/// static SNMPV1_REQ: &[u8] = include_bytes!("../assets/snmpv1_req.bin");
+///
+/// # fn main() {
+/// let (_, hdr) = inspect_snmp_message(SNMPV1_REQ).expect("inspection failed");
+/// assert_eq!(hdr.version, 0);
+/// assert_eq!(hdr.community, Some(&b"public"[..]));
+/// assert_eq!(hdr.pdu_type, Some(PduType::GetRequest));
+/// assert_eq!(hdr.varbind_count(), 1);
+/// # }
+/// ```
+pub fn inspect_snmp_message(i: &[u8]) -> IResult<&[u8], SnmpMessageHeader<'_>, SnmpError> {
+    let (rem, msg) = Any::from_ber(i).map_err(Err::convert)?;
+    if msg.tag() != Tag::Sequence {
+        return Err(Err::Error(SnmpError::InvalidMessage));
+    }
+    let (i, version) = u32::from_ber(msg.data).map_err(Err::convert)?;
+    let hdr = match version {
+        0 | 1 => {
+            let (i, community) = <&[u8]>::from_ber(i).map_err(Err::convert)?;
+            let pdu = inspect_pdu(i)?;
+            SnmpMessageHeader {
+                version,
+                community: Some(community),
+                #[cfg(feature = "v3")]
+                header_data: None,
+                pdu_type: Some(pdu.pdu_type),
+                request_id: pdu.request_id,
+                varbinds: pdu.varbinds,
+            }
+        }
+        #[cfg(feature = "v3")]
+        3 => {
+            let (i, header_data) = HeaderData::from_ber(i).map_err(Err::convert)?;
+            let (i, _security_params) = <&[u8]>::from_ber(i).map_err(Err::convert)?;
+            let (pdu_type, request_id, varbinds) = if is_scoped_pdu_data_encrypted(i, &header_data)
+            {
+                (None, None, &[][..])
+            } else {
+                let (_, scoped) = Any::from_ber(i).map_err(Err::convert)?;
+                expect_sequence(&scoped)?;
+                let (i, _ctx_engine_id) = <&[u8]>::from_ber(scoped.data).map_err(Err::convert)?;
+                let (i, _ctx_name) = <&[u8]>::from_ber(i).map_err(Err::convert)?;
+                let pdu = inspect_pdu(i)?;
+                (Some(pdu.pdu_type), pdu.request_id, pdu.varbinds)
+            };
+            SnmpMessageHeader {
+                version,
+                community: None,
+                header_data: Some(header_data),
+                pdu_type,
+                request_id,
+                varbinds,
+            }
+        }
+        _ => return Err(Err::Error(SnmpError::InvalidVersion { found: version })),
+    };
+    Ok((rem, hdr))
+}
+
+pub(crate) struct PduInfo<'a> {
+    pub(crate) pdu_type: PduType,
+    pub(crate) request_id: Option<u32>,
+    pub(crate) varbinds: &'a [u8],
+}
+
+/// Read the PDU type, request ID and content of the varbind list
+pub(crate) fn inspect_pdu(i: &[u8]) -> Result<PduInfo<'_>, Err<SnmpError>> {
+    let (_, pdu) = Any::from_ber(i).map_err(Err::convert)?;
+    if !pdu.header.is_contextspecific() || pdu.tag().0 > PduType::Report.0 {
+        return Err(Err::Error(SnmpError::InvalidPduType { tag: pdu.tag().0 }));
+    }
+    let pdu_type = PduType(pdu.tag().0);
+    let (i, request_id) = if pdu_type == PduType::TrapV1 {
+        // enterprise, agent-addr, generic-trap, specific-trap, time-stamp
+        let mut i = pdu.data;
+        for _ in 0..5 {
+            let (rem, _) = Any::from_ber(i).map_err(Err::convert)?;
+            i = rem;
+        }
+        (i, None)
+    } else {
+        // request-id, then error-status and error-index (or non-repeaters and max-repetitions)
+        let (i, request_id) = u32::from_ber(pdu.data).map_err(Err::convert)?;
+        let (i, _) = Any::from_ber(i).map_err(Err::convert)?;
+        let (i, _) = Any::from_ber(i).map_err(Err::convert)?;
+        (i, Some(request_id))
+    };
+    let (_, varbinds) = Any::from_ber(i).map_err(Err::convert)?;
+    expect_sequence(&varbinds)?;
+    Ok(PduInfo {
+        pdu_type,
+        request_id,
+        varbinds: varbinds.data,
+    })
+}
+
+pub(crate) fn expect_sequence(any: &Any) -> Result<(), Err<SnmpError>> {
+    any.tag()
+        .assert_eq(Tag::Sequence)
+        .map_err(|e| Err::Error(SnmpError::from(e)))
+}
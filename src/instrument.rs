@@ -0,0 +1,70 @@
+//! Optional instrumentation, using the `tracing` crate
+//!
+//! When the `tracing` feature is not enabled, all macros expand to nothing.
+
+#[cfg(feature = "tracing")]
+macro_rules! snmp_span {
+    ($name:expr $(, $($fields:tt)*)?) => {
+        let _span = tracing::debug_span!($name $(, $($fields)*)?).entered();
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! snmp_span {
+    ($($t:tt)*) => {};
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! snmp_event {
+    ($($t:tt)*) => {
+        tracing::debug!($($t)*);
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! snmp_event {
+    ($($t:tt)*) => {};
+}
+
+/// Anomalies are unexpected (but not necessarily invalid) constructions found while parsing
+#[cfg(feature = "tracing")]
+macro_rules! snmp_anomaly {
+    ($($t:tt)*) => {
+        tracing::warn!(anomaly = true, $($t)*);
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! snmp_anomaly {
+    ($($t:tt)*) => {};
+}
+
+/// Emit the end-of-parse event for a top-level message parser
+#[cfg(feature = "tracing")]
+pub(crate) fn trace_parse_result<T, F>(
+    res: &nom::IResult<&[u8], T, crate::error::SnmpError>,
+    fields: F,
+) where
+    F: FnOnce(&T) -> (Option<crate::snmp::PduType>, usize),
+{
+    match res {
+        Ok((rem, msg)) => {
+            let (pdu_type, varbinds) = fields(msg);
+            tracing::debug!(
+                pdu_type = ?pdu_type,
+                varbinds,
+                remaining = rem.len(),
+                "parse end"
+            );
+        }
+        Err(e) => tracing::debug!(error = ?e, "parse failed"),
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+#[inline(always)]
+pub(crate) fn trace_parse_result<T, F>(
+    _res: &nom::IResult<&[u8], T, crate::error::SnmpError>,
+    _fields: F,
+) {
+}
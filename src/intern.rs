@@ -0,0 +1,124 @@
+//! OID interning
+//!
+//! Parsed OIDs borrow from the input buffer. Applications retaining OIDs for a long time (for ex.
+//! per-OID statistics over millions of captured messages) have to copy them, and the same OIDs
+//! are usually seen again and again. [`OidInterner`] stores each distinct OID once, and returns
+//! cheap reference-counted handles:
+//!
+//! ```rust
+//! use snmp_parser::intern::OidInterner;
+//! use snmp_parser::oid;
+//!
+//! let mut interner = OidInterner::new();
+//! let a = interner.intern(&oid!(1.3.6.1.2.1.1.3.0));
+//! let b = interner.intern(&oid!(1.3.6.1.2.1.1.3.0));
+//! assert_eq!(a, b);
+//! assert_eq!(interner.len(), 1);
+//! assert_eq!(a.as_oid().to_id_string(), "1.3.6.1.2.1.1.3.0");
+//! ```
+//!
+//! Interners and handles are `Send` and `Sync`. An interner filled once (for ex. with the OIDs
+//! of the objects of a MIB) can be shared by the workers of a pipeline behind an `Arc`:
+//! [`get`](OidInterner::get) only borrows the interner, so lookups do not take a lock. OIDs which
+//! are not found can be interned in a local interner of each worker.
+
+use crate::format::format_oid;
+use crate::snmp::SnmpPdu;
+use alloc::borrow::Cow;
+use alloc::collections::BTreeSet;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use asn1_rs::Oid;
+use core::fmt;
+
+/// Handle to an OID stored in an [`OidInterner`]
+///
+/// Cloning a handle does not copy the OID. The storage is released when all handles and the
+/// interner are dropped (or the OID is purged from the interner).
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InternedOid(Arc<[u8]>);
+
+impl InternedOid {
+    /// Return the OID, borrowing the shared storage
+    pub fn as_oid(&self) -> Oid<'_> {
+        Oid::new(Cow::Borrowed(&self.0))
+    }
+
+    /// Return the DER-encoded content of the OID
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<'a> PartialEq<Oid<'a>> for InternedOid {
+    fn eq(&self, other: &Oid<'a>) -> bool {
+        *self.0 == *other.as_bytes()
+    }
+}
+
+impl fmt::Debug for InternedOid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("InternedOid")
+            .field(&self.as_oid().to_id_string())
+            .finish()
+    }
+}
+
+impl fmt::Display for InternedOid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", format_oid(&self.as_oid()))
+    }
+}
+
+/// Deduplicating storage for OIDs
+#[derive(Debug, Default)]
+pub struct OidInterner {
+    oids: BTreeSet<Arc<[u8]>>,
+}
+
+impl OidInterner {
+    pub fn new() -> Self {
+        OidInterner::default()
+    }
+
+    /// Return a handle to `oid`, storing it if this is the first time it is seen
+    pub fn intern(&mut self, oid: &Oid) -> InternedOid {
+        let bytes = oid.as_bytes();
+        if let Some(stored) = self.oids.get(bytes) {
+            return InternedOid(stored.clone());
+        }
+        let stored: Arc<[u8]> = Arc::from(bytes);
+        self.oids.insert(stored.clone());
+        InternedOid(stored)
+    }
+
+    /// Return a handle to `oid`, if it is stored
+    ///
+    /// Unlike [`intern`](OidInterner::intern), this does not modify the interner, so it can be
+    /// used on a shared interner.
+    pub fn get(&self, oid: &Oid) -> Option<InternedOid> {
+        self.oids.get(oid.as_bytes()).cloned().map(InternedOid)
+    }
+
+    /// Intern the OIDs of the variables of `pdu`, in order
+    pub fn intern_vars(&mut self, pdu: &SnmpPdu) -> Vec<InternedOid> {
+        pdu.var_list().iter().map(|v| self.intern(&v.oid)).collect()
+    }
+
+    /// Return the number of distinct OIDs stored
+    pub fn len(&self) -> usize {
+        self.oids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.oids.is_empty()
+    }
+
+    /// Remove the OIDs which have no handle outside of the interner
+    ///
+    /// Interned OIDs are never removed otherwise, so this should be called periodically when
+    /// retained handles expire (for ex. at the end of each aggregation window).
+    pub fn purge(&mut self) {
+        self.oids.retain(|oid| Arc::strong_count(oid) > 1);
+    }
+}
@@ -0,0 +1,578 @@
+//! Conversions between the messages of this crate and the [rasn-snmp](https://docs.rs/rasn-snmp)
+//! types
+//!
+//! Projects using rasn to build and encode messages can use this crate as the decoder: messages
+//! are parsed without copies, and converted to the rasn-snmp model only when needed (for ex. to
+//! modify and encode them again).
+//!
+//! Both directions are implemented with `TryFrom`:
+//!   - from a reference to a parsed message (or PDU, or variable binding) to the rasn-snmp type,
+//!     copying the strings
+//!   - from a reference to a rasn-snmp value to the types of this crate, borrowing the strings of
+//!     the rasn-snmp value
+//!
+//! Conversions fail if a value has no equivalent in the target model, for ex. the REAL values
+//! sent by some agents, or the `Counter64` values in SNMPv1.
+//!
+//! ```rust
+//! use snmp_parser::parse_snmp_v2c;
+//! use std::convert::TryFrom;
+//!
+//! # static SNMPV2C_GET_RESPONSE: &[u8] = include_bytes!("../assets/snmpv2c-get-response.bin");
+//! # fn main() {
+//! let (_, msg) = parse_snmp_v2c(SNMPV2C_GET_RESPONSE).expect("parsing failed");
+//! let rasn_msg = rasn_snmp::v2c::Message::<rasn_snmp::v2::Pdus>::try_from(&msg)
+//!     .expect("conversion failed");
+//! assert_eq!(rasn_msg.community, "public".as_bytes());
+//! # }
+//! ```
+
+use crate::snmp::{
+    ErrorStatus, NetworkAddress, ObjectSyntax, PduType, SnmpBulkPdu, SnmpGenericPdu, SnmpMessage,
+    SnmpPdu, SnmpTrapPdu, SnmpVariable, TrapType, VarBindValue,
+};
+#[cfg(feature = "v3")]
+use crate::snmpv3::{
+    HeaderData, ScopedPdu, ScopedPduData, SecurityModel, SecurityParameters, SnmpV3Message,
+};
+#[cfg(feature = "v3")]
+use crate::{encode::SnmpEncode, parse_usm_security_parameters};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use asn1_rs::Oid;
+use core::convert::TryFrom;
+use core::fmt;
+#[cfg(not(feature = "std"))]
+use core::net::Ipv4Addr;
+use rasn::types::{Integer, ObjectIdentifier, OctetString};
+use rasn_smi::{v1 as smi1, v2 as smi2, v2::ToOpaque};
+use rasn_snmp::{v1, v2, v2c};
+#[cfg(feature = "std")]
+use std::net::Ipv4Addr;
+
+/// Error returned when a value cannot be converted
+#[derive(Debug, PartialEq)]
+pub enum ConversionError {
+    /// The message does not have the expected version
+    InvalidVersion { found: u32 },
+    /// This type of PDU has no equivalent in the target model (for ex. GetBulkRequest in
+    /// SNMPv1)
+    UnsupportedPdu(PduType),
+    /// This type of value has no equivalent in the target model (for ex. a REAL value)
+    UnsupportedValue(&'static str),
+    /// The field has a value which does not fit in the target type, or is invalid
+    InvalidValue(&'static str),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConversionError::InvalidVersion { found } => {
+                write!(f, "Cannot convert message with version {}", found)
+            }
+            ConversionError::UnsupportedPdu(pdu_type) => {
+                write!(f, "Cannot convert PDU of type {:?}", pdu_type)
+            }
+            ConversionError::UnsupportedValue(type_name) => {
+                write!(f, "Cannot convert value of type {}", type_name)
+            }
+            ConversionError::InvalidValue(field) => write!(f, "Invalid value for `{}`", field),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConversionError {}
+
+fn to_rasn_oid(oid: &Oid) -> Result<ObjectIdentifier, ConversionError> {
+    let arcs = oid
+        .iter()
+        .and_then(|arcs| {
+            arcs.map(|arc| u32::try_from(arc).ok())
+                .collect::<Option<Vec<_>>>()
+        })
+        .ok_or(ConversionError::InvalidValue("oid"))?;
+    ObjectIdentifier::new(arcs).ok_or(ConversionError::InvalidValue("oid"))
+}
+
+fn from_rasn_oid(oid: &ObjectIdentifier) -> Result<Oid<'static>, ConversionError> {
+    let arcs = oid.iter().map(|&arc| u64::from(arc)).collect::<Vec<_>>();
+    Oid::from(&arcs).map_err(|_| ConversionError::InvalidValue("oid"))
+}
+
+fn from_rasn_integer<T>(value: &Integer, field: &'static str) -> Result<T, ConversionError>
+where
+    for<'i> T: TryFrom<&'i Integer>,
+{
+    T::try_from(value).map_err(|_| ConversionError::InvalidValue(field))
+}
+
+fn to_octets(data: &[u8]) -> OctetString {
+    OctetString::from(data.to_vec())
+}
+
+fn to_opaque(data: &[u8]) -> smi2::Opaque {
+    // the content of an Opaque value is copied as is
+    rasn::types::Any::new(data.to_vec())
+        .to_opaque()
+        .expect("encoding raw data cannot fail")
+}
+
+fn to_ip_address(addr: &NetworkAddress) -> smi1::IpAddress {
+    match addr {
+        NetworkAddress::IPv4(ip) => smi1::IpAddress(ip.octets().into()),
+    }
+}
+
+fn from_ip_address(addr: &smi1::IpAddress) -> NetworkAddress {
+    NetworkAddress::IPv4(Ipv4Addr::from(*addr.0))
+}
+
+fn to_v2_pdu(pdu: &SnmpGenericPdu) -> Result<v2::Pdu, ConversionError> {
+    Ok(v2::Pdu {
+        request_id: i32::try_from(pdu.req_id)
+            .map_err(|_| ConversionError::InvalidValue("request_id"))?,
+        error_status: pdu.err.0,
+        error_index: pdu.err_index,
+        variable_bindings: to_var_list(&pdu.var)?,
+    })
+}
+
+fn to_var_list<'v, T>(vars: &'v [SnmpVariable]) -> Result<Vec<T>, ConversionError>
+where
+    T: TryFrom<&'v SnmpVariable<'v>, Error = ConversionError>,
+{
+    vars.iter().map(T::try_from).collect()
+}
+
+fn from_var_list<'a, T>(vars: &'a [T]) -> Result<Vec<SnmpVariable<'a>>, ConversionError>
+where
+    SnmpVariable<'a>: TryFrom<&'a T, Error = ConversionError>,
+{
+    vars.iter().map(SnmpVariable::try_from).collect()
+}
+
+impl<'a> TryFrom<&SnmpVariable<'a>> for v1::VarBind {
+    type Error = ConversionError;
+
+    fn try_from(var: &SnmpVariable<'a>) -> Result<Self, Self::Error> {
+        let value = match &var.val {
+            VarBindValue::Unspecified | VarBindValue::Value(ObjectSyntax::Empty) => {
+                smi1::SimpleSyntax::Empty.into()
+            }
+            VarBindValue::Value(value) => match value {
+                ObjectSyntax::Number(n) => smi1::SimpleSyntax::Number((*n).into()).into(),
+                ObjectSyntax::String(s) => smi1::SimpleSyntax::String(to_octets(s)).into(),
+                ObjectSyntax::Object(oid) => smi1::SimpleSyntax::Object(to_rasn_oid(oid)?).into(),
+                ObjectSyntax::IpAddress(addr) => {
+                    smi1::NetworkAddress::Internet(to_ip_address(addr)).into()
+                }
+                ObjectSyntax::Counter32(n) => smi1::Counter(*n).into(),
+                ObjectSyntax::Gauge32(n) => smi1::Gauge(*n).into(),
+                ObjectSyntax::TimeTicks(n) => smi1::TimeTicks(*n).into(),
+                ObjectSyntax::Opaque(data) => to_opaque(data).into(),
+                value => return Err(ConversionError::UnsupportedValue(value.type_name())),
+            },
+            value => return Err(ConversionError::UnsupportedValue(value.type_name())),
+        };
+        Ok(v1::VarBind {
+            name: to_rasn_oid(&var.oid)?,
+            value,
+        })
+    }
+}
+
+impl<'a> TryFrom<&SnmpVariable<'a>> for v2::VarBind {
+    type Error = ConversionError;
+
+    fn try_from(var: &SnmpVariable<'a>) -> Result<Self, Self::Error> {
+        let value = match &var.val {
+            VarBindValue::Unspecified | VarBindValue::Value(ObjectSyntax::Empty) => {
+                v2::VarBindValue::Unspecified
+            }
+            VarBindValue::NoSuchObject => v2::VarBindValue::NoSuchObject,
+            VarBindValue::NoSuchInstance => v2::VarBindValue::NoSuchInstance,
+            VarBindValue::EndOfMibView => v2::VarBindValue::EndOfMibView,
+            VarBindValue::Value(value) => v2::VarBindValue::Value(match value {
+                ObjectSyntax::Number(n) => smi2::SimpleSyntax::Integer((*n).into()).into(),
+                ObjectSyntax::String(s) => smi2::SimpleSyntax::String(to_octets(s)).into(),
+                ObjectSyntax::Object(oid) => smi2::SimpleSyntax::ObjectId(to_rasn_oid(oid)?).into(),
+                ObjectSyntax::IpAddress(addr) => to_ip_address(addr).into(),
+                ObjectSyntax::Counter32(n) => smi1::Counter(*n).into(),
+                ObjectSyntax::Gauge32(n) => smi1::Gauge(*n).into(),
+                ObjectSyntax::TimeTicks(n) => smi1::TimeTicks(*n).into(),
+                ObjectSyntax::Opaque(data) => to_opaque(data).into(),
+                ObjectSyntax::Counter64(n) => smi2::Counter64(*n).into(),
+                value => return Err(ConversionError::UnsupportedValue(value.type_name())),
+            }),
+        };
+        Ok(v2::VarBind {
+            name: to_rasn_oid(&var.oid)?,
+            value,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a v1::VarBind> for SnmpVariable<'a> {
+    type Error = ConversionError;
+
+    fn try_from(var: &'a v1::VarBind) -> Result<Self, Self::Error> {
+        let value = match &var.value {
+            smi1::ObjectSyntax::Simple(value) => match value {
+                smi1::SimpleSyntax::Number(n) => {
+                    ObjectSyntax::Number(from_rasn_integer(n, "value")?)
+                }
+                smi1::SimpleSyntax::String(s) => ObjectSyntax::String(s.as_ref()),
+                smi1::SimpleSyntax::Object(oid) => ObjectSyntax::Object(from_rasn_oid(oid)?),
+                smi1::SimpleSyntax::Empty => {
+                    return Ok(SnmpVariable {
+                        oid: from_rasn_oid(&var.name)?,
+                        val: VarBindValue::Unspecified,
+                    })
+                }
+            },
+            smi1::ObjectSyntax::ApplicationWide(value) => match value {
+                smi1::ApplicationSyntax::Address(smi1::NetworkAddress::Internet(addr)) => {
+                    ObjectSyntax::IpAddress(from_ip_address(addr))
+                }
+                smi1::ApplicationSyntax::Counter(n) => ObjectSyntax::Counter32(n.0),
+                smi1::ApplicationSyntax::Gauge(n) => ObjectSyntax::Gauge32(n.0),
+                smi1::ApplicationSyntax::Ticks(n) => ObjectSyntax::TimeTicks(n.0),
+                smi1::ApplicationSyntax::Arbitrary(data) => ObjectSyntax::Opaque(data.as_ref()),
+            },
+        };
+        Ok(SnmpVariable {
+            oid: from_rasn_oid(&var.name)?,
+            val: VarBindValue::Value(value),
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a v2::VarBind> for SnmpVariable<'a> {
+    type Error = ConversionError;
+
+    fn try_from(var: &'a v2::VarBind) -> Result<Self, Self::Error> {
+        let val = match &var.value {
+            v2::VarBindValue::Unspecified => VarBindValue::Unspecified,
+            v2::VarBindValue::NoSuchObject => VarBindValue::NoSuchObject,
+            v2::VarBindValue::NoSuchInstance => VarBindValue::NoSuchInstance,
+            v2::VarBindValue::EndOfMibView => VarBindValue::EndOfMibView,
+            v2::VarBindValue::Value(smi2::ObjectSyntax::Simple(value)) => {
+                VarBindValue::Value(match value {
+                    smi2::SimpleSyntax::Integer(n) => {
+                        ObjectSyntax::Number(from_rasn_integer(n, "value")?)
+                    }
+                    smi2::SimpleSyntax::String(s) => ObjectSyntax::String(s.as_ref()),
+                    smi2::SimpleSyntax::ObjectId(oid) => ObjectSyntax::Object(from_rasn_oid(oid)?),
+                })
+            }
+            v2::VarBindValue::Value(smi2::ObjectSyntax::ApplicationWide(value)) => {
+                VarBindValue::Value(match value {
+                    smi2::ApplicationSyntax::Address(addr) => {
+                        ObjectSyntax::IpAddress(from_ip_address(addr))
+                    }
+                    smi2::ApplicationSyntax::Counter(n) => ObjectSyntax::Counter32(n.0),
+                    smi2::ApplicationSyntax::Ticks(n) => ObjectSyntax::TimeTicks(n.0),
+                    smi2::ApplicationSyntax::Arbitrary(data) => ObjectSyntax::Opaque(data.as_ref()),
+                    smi2::ApplicationSyntax::BigCounter(n) => ObjectSyntax::Counter64(n.0),
+                    smi2::ApplicationSyntax::Unsigned(n) => ObjectSyntax::Gauge32(n.0),
+                })
+            }
+        };
+        Ok(SnmpVariable {
+            oid: from_rasn_oid(&var.name)?,
+            val,
+        })
+    }
+}
+
+impl<'a> TryFrom<&SnmpPdu<'a>> for v1::Pdus {
+    type Error = ConversionError;
+
+    fn try_from(pdu: &SnmpPdu<'a>) -> Result<Self, Self::Error> {
+        let pdu = match pdu {
+            SnmpPdu::Generic(pdu) => pdu,
+            SnmpPdu::TrapV1(trap) => {
+                return Ok(v1::Pdus::Trap(v1::Trap {
+                    enterprise: to_rasn_oid(&trap.enterprise)?,
+                    agent_addr: smi1::NetworkAddress::Internet(to_ip_address(&trap.agent_addr)),
+                    generic_trap: trap.generic_trap.0.into(),
+                    specific_trap: trap.specific_trap.into(),
+                    time_stamp: smi1::TimeTicks(trap.timestamp),
+                    variable_bindings: to_var_list(&trap.var)?,
+                }))
+            }
+            SnmpPdu::Bulk(_) => {
+                return Err(ConversionError::UnsupportedPdu(PduType::GetBulkRequest))
+            }
+        };
+        let v1_pdu = v1::Pdu {
+            request_id: pdu.req_id.into(),
+            error_status: pdu.err.0.into(),
+            error_index: pdu.err_index.into(),
+            variable_bindings: to_var_list(&pdu.var)?,
+        };
+        match pdu.pdu_type {
+            PduType::GetRequest => Ok(v1::Pdus::GetRequest(v1::GetRequest(v1_pdu))),
+            PduType::GetNextRequest => Ok(v1::Pdus::GetNextRequest(v1::GetNextRequest(v1_pdu))),
+            PduType::Response => Ok(v1::Pdus::GetResponse(v1::GetResponse(v1_pdu))),
+            PduType::SetRequest => Ok(v1::Pdus::SetRequest(v1::SetRequest(v1_pdu))),
+            pdu_type => Err(ConversionError::UnsupportedPdu(pdu_type)),
+        }
+    }
+}
+
+impl<'a> TryFrom<&SnmpPdu<'a>> for v2::Pdus {
+    type Error = ConversionError;
+
+    fn try_from(pdu: &SnmpPdu<'a>) -> Result<Self, Self::Error> {
+        let pdu = match pdu {
+            SnmpPdu::Generic(pdu) => pdu,
+            SnmpPdu::Bulk(bulk) => {
+                return Ok(v2::Pdus::GetBulkRequest(v2::GetBulkRequest(v2::BulkPdu {
+                    request_id: i32::try_from(bulk.req_id)
+                        .map_err(|_| ConversionError::InvalidValue("request_id"))?,
+                    non_repeaters: bulk.non_repeaters,
+                    max_repetitions: bulk.max_repetitions,
+                    variable_bindings: to_var_list(&bulk.var)?,
+                })))
+            }
+            SnmpPdu::TrapV1(_) => return Err(ConversionError::UnsupportedPdu(PduType::TrapV1)),
+        };
+        let v2_pdu = to_v2_pdu(pdu)?;
+        match pdu.pdu_type {
+            PduType::GetRequest => Ok(v2::Pdus::GetRequest(v2::GetRequest(v2_pdu))),
+            PduType::GetNextRequest => Ok(v2::Pdus::GetNextRequest(v2::GetNextRequest(v2_pdu))),
+            PduType::Response => Ok(v2::Pdus::Response(v2::Response(v2_pdu))),
+            PduType::SetRequest => Ok(v2::Pdus::SetRequest(v2::SetRequest(v2_pdu))),
+            PduType::InformRequest => Ok(v2::Pdus::InformRequest(v2::InformRequest(v2_pdu))),
+            PduType::TrapV2 => Ok(v2::Pdus::Trap(v2::Trap(v2_pdu))),
+            PduType::Report => Ok(v2::Pdus::Report(v2::Report(v2_pdu))),
+            pdu_type => Err(ConversionError::UnsupportedPdu(pdu_type)),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a v1::Pdus> for SnmpPdu<'a> {
+    type Error = ConversionError;
+
+    fn try_from(pdus: &'a v1::Pdus) -> Result<Self, Self::Error> {
+        let (pdu_type, pdu) = match pdus {
+            v1::Pdus::GetRequest(pdu) => (PduType::GetRequest, &pdu.0),
+            v1::Pdus::GetNextRequest(pdu) => (PduType::GetNextRequest, &pdu.0),
+            v1::Pdus::GetResponse(pdu) => (PduType::Response, &pdu.0),
+            v1::Pdus::SetRequest(pdu) => (PduType::SetRequest, &pdu.0),
+            v1::Pdus::Trap(trap) => {
+                let smi1::NetworkAddress::Internet(agent_addr) = &trap.agent_addr;
+                return Ok(SnmpPdu::TrapV1(SnmpTrapPdu {
+                    enterprise: from_rasn_oid(&trap.enterprise)?,
+                    agent_addr: from_ip_address(agent_addr),
+                    generic_trap: TrapType(from_rasn_integer(&trap.generic_trap, "generic_trap")?),
+                    specific_trap: from_rasn_integer(&trap.specific_trap, "specific_trap")?,
+                    timestamp: trap.time_stamp.0,
+                    var: from_var_list(&trap.variable_bindings)?,
+                }));
+            }
+        };
+        Ok(SnmpPdu::Generic(SnmpGenericPdu {
+            pdu_type,
+            req_id: from_rasn_integer(&pdu.request_id, "request_id")?,
+            err: ErrorStatus(from_rasn_integer(&pdu.error_status, "error_status")?),
+            err_index: from_rasn_integer(&pdu.error_index, "error_index")?,
+            var: from_var_list(&pdu.variable_bindings)?,
+        }))
+    }
+}
+
+impl<'a> TryFrom<&'a v2::Pdus> for SnmpPdu<'a> {
+    type Error = ConversionError;
+
+    fn try_from(pdus: &'a v2::Pdus) -> Result<Self, Self::Error> {
+        let (pdu_type, pdu) = match pdus {
+            v2::Pdus::GetRequest(pdu) => (PduType::GetRequest, &pdu.0),
+            v2::Pdus::GetNextRequest(pdu) => (PduType::GetNextRequest, &pdu.0),
+            v2::Pdus::Response(pdu) => (PduType::Response, &pdu.0),
+            v2::Pdus::SetRequest(pdu) => (PduType::SetRequest, &pdu.0),
+            v2::Pdus::InformRequest(pdu) => (PduType::InformRequest, &pdu.0),
+            v2::Pdus::Trap(pdu) => (PduType::TrapV2, &pdu.0),
+            v2::Pdus::Report(pdu) => (PduType::Report, &pdu.0),
+            v2::Pdus::GetBulkRequest(bulk) => {
+                return Ok(SnmpPdu::Bulk(SnmpBulkPdu {
+                    req_id: u32::try_from(bulk.0.request_id)
+                        .map_err(|_| ConversionError::InvalidValue("request_id"))?,
+                    non_repeaters: bulk.0.non_repeaters,
+                    max_repetitions: bulk.0.max_repetitions,
+                    var: from_var_list(&bulk.0.variable_bindings)?,
+                }));
+            }
+        };
+        Ok(SnmpPdu::Generic(SnmpGenericPdu {
+            pdu_type,
+            req_id: u32::try_from(pdu.request_id)
+                .map_err(|_| ConversionError::InvalidValue("request_id"))?,
+            err: ErrorStatus(pdu.error_status),
+            err_index: pdu.error_index,
+            var: from_var_list(&pdu.variable_bindings)?,
+        }))
+    }
+}
+
+impl<'a> TryFrom<&SnmpMessage<'a>> for v1::Message<v1::Pdus> {
+    type Error = ConversionError;
+
+    fn try_from(msg: &SnmpMessage<'a>) -> Result<Self, Self::Error> {
+        if msg.version != 0 {
+            return Err(ConversionError::InvalidVersion { found: msg.version });
+        }
+        Ok(v1::Message {
+            version: msg.version.into(),
+            community: to_octets(msg.community.as_bytes()),
+            data: v1::Pdus::try_from(&msg.pdu)?,
+        })
+    }
+}
+
+impl<'a> TryFrom<&SnmpMessage<'a>> for v2c::Message<v2::Pdus> {
+    type Error = ConversionError;
+
+    fn try_from(msg: &SnmpMessage<'a>) -> Result<Self, Self::Error> {
+        if msg.version != 1 {
+            return Err(ConversionError::InvalidVersion { found: msg.version });
+        }
+        Ok(v2c::Message {
+            version: msg.version.into(),
+            community: to_octets(msg.community.as_bytes()),
+            data: v2::Pdus::try_from(&msg.pdu)?,
+        })
+    }
+}
+
+fn from_rasn_community(community: &OctetString) -> Result<String, ConversionError> {
+    core::str::from_utf8(community)
+        .map(ToString::to_string)
+        .map_err(|_| ConversionError::InvalidValue("community"))
+}
+
+impl<'a> TryFrom<&'a v1::Message<v1::Pdus>> for SnmpMessage<'a> {
+    type Error = ConversionError;
+
+    fn try_from(msg: &'a v1::Message<v1::Pdus>) -> Result<Self, Self::Error> {
+        let version = from_rasn_integer(&msg.version, "version")?;
+        if version != 0 {
+            return Err(ConversionError::InvalidVersion { found: version });
+        }
+        Ok(SnmpMessage {
+            version,
+            community: from_rasn_community(&msg.community)?,
+            pdu: SnmpPdu::try_from(&msg.data)?,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a v2c::Message<v2::Pdus>> for SnmpMessage<'a> {
+    type Error = ConversionError;
+
+    fn try_from(msg: &'a v2c::Message<v2::Pdus>) -> Result<Self, Self::Error> {
+        let version = from_rasn_integer(&msg.version, "version")?;
+        if version != 1 {
+            return Err(ConversionError::InvalidVersion { found: version });
+        }
+        Ok(SnmpMessage {
+            version,
+            community: from_rasn_community(&msg.community)?,
+            pdu: SnmpPdu::try_from(&msg.data)?,
+        })
+    }
+}
+
+#[cfg(feature = "v3")]
+impl<'a> TryFrom<&SnmpV3Message<'a>> for rasn_snmp::v3::Message {
+    type Error = ConversionError;
+
+    fn try_from(msg: &SnmpV3Message<'a>) -> Result<Self, Self::Error> {
+        use rasn_snmp::v3;
+        if msg.version != 3 {
+            return Err(ConversionError::InvalidVersion { found: msg.version });
+        }
+        let hdr = &msg.header_data;
+        let global_data = v3::HeaderData {
+            message_id: hdr.msg_id.into(),
+            max_size: hdr.msg_max_size.into(),
+            flags: to_octets(&[hdr.msg_flags]),
+            security_model: hdr.msg_security_model.0.into(),
+        };
+        // the content of the OCTET STRING, not the encoding of the OCTET STRING itself. The
+        // original encoding is kept when available, since the authentication digest covers it
+        let security_parameters = match &msg.security_params {
+            _ if !msg.security_params_raw.is_empty() => to_octets(msg.security_params_raw),
+            SecurityParameters::Raw(data) => to_octets(data),
+            SecurityParameters::USM(usm) => OctetString::from(usm.encode()),
+        };
+        let scoped_data = match &msg.data {
+            ScopedPduData::Plaintext(scoped_pdu) => {
+                v3::ScopedPduData::CleartextPdu(v3::ScopedPdu {
+                    engine_id: to_octets(scoped_pdu.ctx_engine_id),
+                    name: to_octets(scoped_pdu.ctx_engine_name),
+                    data: v2::Pdus::try_from(&scoped_pdu.data)?,
+                })
+            }
+            ScopedPduData::Encrypted(data) => v3::ScopedPduData::EncryptedPdu(to_octets(data)),
+        };
+        Ok(v3::Message {
+            version: msg.version.into(),
+            global_data,
+            security_parameters,
+            scoped_data,
+        })
+    }
+}
+
+#[cfg(feature = "v3")]
+impl<'a> TryFrom<&'a rasn_snmp::v3::Message> for SnmpV3Message<'a> {
+    type Error = ConversionError;
+
+    fn try_from(msg: &'a rasn_snmp::v3::Message) -> Result<Self, Self::Error> {
+        use rasn_snmp::v3;
+        let version = from_rasn_integer(&msg.version, "version")?;
+        if version != 3 {
+            return Err(ConversionError::InvalidVersion { found: version });
+        }
+        let hdr = &msg.global_data;
+        let msg_flags = match hdr.flags.as_ref() {
+            &[flags] => flags,
+            _ => return Err(ConversionError::InvalidValue("msg_flags")),
+        };
+        let header_data = HeaderData {
+            msg_id: from_rasn_integer(&hdr.message_id, "msg_id")?,
+            msg_max_size: from_rasn_integer(&hdr.max_size, "msg_max_size")?,
+            msg_flags,
+            msg_security_model: SecurityModel(from_rasn_integer(
+                &hdr.security_model,
+                "msg_security_model",
+            )?),
+        };
+        let security_params_raw: &[u8] = &msg.security_parameters;
+        let security_params = if header_data.msg_security_model == SecurityModel::USM {
+            let (_, usm) = parse_usm_security_parameters(security_params_raw)
+                .map_err(|_| ConversionError::InvalidValue("security_parameters"))?;
+            SecurityParameters::USM(usm)
+        } else {
+            SecurityParameters::Raw(security_params_raw)
+        };
+        let data = match &msg.scoped_data {
+            v3::ScopedPduData::CleartextPdu(scoped_pdu) => ScopedPduData::Plaintext(ScopedPdu {
+                ctx_engine_id: &scoped_pdu.engine_id,
+                ctx_engine_name: &scoped_pdu.name,
+                data: SnmpPdu::try_from(&scoped_pdu.data)?,
+            }),
+            v3::ScopedPduData::EncryptedPdu(data) => ScopedPduData::Encrypted(data),
+        };
+        Ok(SnmpV3Message {
+            version,
+            header_data,
+            security_params,
+            security_params_raw,
+            data,
+        })
+    }
+}
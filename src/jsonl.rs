@@ -0,0 +1,280 @@
+//! Export of messages as JSON lines
+//!
+//! [`JsonLinesWriter`] writes one JSON object per message and per line, for ex. to be read by
+//! `jq` or by a log collector:
+//!
+//! ```rust
+//! # #[cfg(feature = "v2c")]
+//! # fn main() {
+//! use snmp_parser::jsonl::JsonLinesWriter;
+//! use snmp_parser::parse_snmp_generic_message;
+//!
+//! # let bytes = include_bytes!("../assets/snmpv2c-get-response.bin");
+//! let (_, msg) = parse_snmp_generic_message(bytes).expect("parsing failed");
+//! let mut writer = JsonLinesWriter::new(Vec::new());
+//! writer.write_message(&msg).expect("write failed");
+//! let output = writer.into_inner().expect("flush failed");
+//! assert!(output.starts_with(br#"{"version":1,"community":"public","pdu":"Response""#));
+//! # }
+//! # #[cfg(not(feature = "v2c"))]
+//! # fn main() {}
+//! ```
+//!
+//! Output is buffered, and flushed every `flush_every` messages or `flush_interval` (see
+//! [`JsonLinesConfig`]), so that readers of the output are not delayed indefinitely when messages
+//! are rare.
+//!
+//! Objects have the following fields, when applicable: `version` (raw-encoded), `community`,
+//! `user`, `msg_id`, `security_model`, `pdu` (the PDU type, or `"encrypted"`), `req_id`,
+//! `error_status`, `error_index`, `non_repeaters`, `max_repetitions`, `enterprise`,
+//! `agent_addr`, `generic_trap`, `specific_trap`, `timestamp`, and `vars`, a list of objects
+//! with the `oid`, `type` and `value` fields. Integer values are written as numbers, and other
+//! values as strings (formatted with [`format_varbind_value`](crate::format::format_varbind_value),
+//! without quotes). Exceptions have a `null` value.
+
+use crate::format::{format_octet_string, format_oid, format_varbind_value, StringEscape};
+use crate::generic::SnmpGenericMessage;
+use crate::snmp::{NetworkAddress, ObjectSyntax, SnmpPdu, SnmpVariable, VarBindValue};
+#[cfg(feature = "v3")]
+use crate::snmpv3::SecurityParameters;
+use core::fmt::{self, Write as _};
+use std::io::{self, BufWriter, Write};
+use std::time::{Duration, Instant};
+
+/// Configuration of a [`JsonLinesWriter`]
+#[derive(Clone, Debug)]
+pub struct JsonLinesConfig {
+    /// Size of the output buffer, in bytes
+    pub buffer_size: usize,
+    /// Flush the output after this number of messages (0 to disable)
+    pub flush_every: usize,
+    /// Flush the output when writing a message, if the last flush is older than this
+    pub flush_interval: Option<Duration>,
+    /// Maximum number of variables written for each message
+    pub max_varbinds: usize,
+    /// Omit communities and user names
+    pub redact_secrets: bool,
+}
+
+impl Default for JsonLinesConfig {
+    fn default() -> Self {
+        JsonLinesConfig {
+            buffer_size: 64 * 1024,
+            flush_every: 0,
+            flush_interval: Some(Duration::from_secs(1)),
+            max_varbinds: usize::MAX,
+            redact_secrets: false,
+        }
+    }
+}
+
+/// Writer of messages as JSON lines
+///
+/// The output is flushed when the writer is dropped, ignoring errors: use
+/// [`into_inner`](Self::into_inner) or [`flush`](Self::flush) to check them.
+#[derive(Debug)]
+pub struct JsonLinesWriter<W: Write> {
+    out: BufWriter<W>,
+    config: JsonLinesConfig,
+    line: String,
+    pending: usize,
+    last_flush: Instant,
+}
+
+impl<W: Write> JsonLinesWriter<W> {
+    /// Create a writer with the default configuration
+    pub fn new(out: W) -> Self {
+        JsonLinesWriter::with_config(out, JsonLinesConfig::default())
+    }
+
+    pub fn with_config(out: W, config: JsonLinesConfig) -> Self {
+        JsonLinesWriter {
+            out: BufWriter::with_capacity(config.buffer_size, out),
+            config,
+            line: String::new(),
+            pending: 0,
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// Write `msg` as one line, and flush the output if required by the configuration
+    pub fn write_message(&mut self, msg: &SnmpGenericMessage) -> io::Result<()> {
+        self.line.clear();
+        // writing to a String does not fail
+        let _ = write_json_message(&mut self.line, msg, &self.config);
+        self.line.push('\n');
+        self.out.write_all(self.line.as_bytes())?;
+        self.pending += 1;
+        let flush_count = self.config.flush_every > 0 && self.pending >= self.config.flush_every;
+        let flush_time = matches!(
+            self.config.flush_interval,
+            Some(interval) if self.last_flush.elapsed() >= interval
+        );
+        if flush_count || flush_time {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Flush the buffered lines to the output
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()?;
+        self.pending = 0;
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+
+    /// Return a reference to the output
+    pub fn get_ref(&self) -> &W {
+        self.out.get_ref()
+    }
+
+    /// Flush the buffered lines, and return the output
+    pub fn into_inner(self) -> io::Result<W> {
+        self.out.into_inner().map_err(|e| e.into_error())
+    }
+}
+
+/// JSON string, with escaping
+struct JsonStr<T: fmt::Display>(T);
+
+impl<T: fmt::Display> fmt::Display for JsonStr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        struct Escaper<'f, 'g>(&'f mut fmt::Formatter<'g>);
+        impl<'f, 'g> fmt::Write for Escaper<'f, 'g> {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                for c in s.chars() {
+                    match c {
+                        '"' => self.0.write_str("\\\"")?,
+                        '\\' => self.0.write_str("\\\\")?,
+                        '\n' => self.0.write_str("\\n")?,
+                        '\r' => self.0.write_str("\\r")?,
+                        '\t' => self.0.write_str("\\t")?,
+                        c if (c as u32) < 0x20 => write!(self.0, "\\u{:04x}", c as u32)?,
+                        c => self.0.write_char(c)?,
+                    }
+                }
+                Ok(())
+            }
+        }
+        f.write_char('"')?;
+        write!(Escaper(f), "{}", self.0)?;
+        f.write_char('"')
+    }
+}
+
+fn write_json_message(
+    out: &mut String,
+    msg: &SnmpGenericMessage,
+    config: &JsonLinesConfig,
+) -> fmt::Result {
+    write!(out, "{{\"version\":{}", msg.version())?;
+    let principal = match msg {
+        SnmpGenericMessage::V1(msg) | SnmpGenericMessage::V2(msg) => {
+            Some(("community", msg.community.as_bytes()))
+        }
+        #[cfg(feature = "v2p")]
+        SnmpGenericMessage::V2p(_) => None,
+        #[cfg(feature = "v2u")]
+        SnmpGenericMessage::V2u(msg) => Some(("user", msg.parameters.user_name)),
+        #[cfg(feature = "v3")]
+        SnmpGenericMessage::V3(msg) => {
+            write!(
+                out,
+                ",\"msg_id\":{},\"security_model\":{}",
+                msg.header_data.msg_id,
+                JsonStr(msg.header_data.msg_security_model)
+            )?;
+            match msg.security_params {
+                SecurityParameters::USM(ref usm) => Some(("user", usm.msg_user_name.as_bytes())),
+                SecurityParameters::Raw(_) => None,
+            }
+        }
+    };
+    if let Some((name, value)) = principal {
+        if !config.redact_secrets {
+            let value = format_octet_string(value).escape(StringEscape::Raw);
+            write!(out, ",\"{}\":{}", name, JsonStr(value))?;
+        }
+    }
+    let pdu = match msg.pdu() {
+        Some(pdu) => pdu,
+        None => return out.write_str(",\"pdu\":\"encrypted\"}"),
+    };
+    write!(
+        out,
+        ",\"pdu\":{}",
+        JsonStr(format_args!("{:?}", pdu.pdu_type()))
+    )?;
+    let vars = match pdu {
+        SnmpPdu::Generic(pdu) => {
+            write!(
+                out,
+                ",\"req_id\":{},\"error_status\":{},\"error_index\":{}",
+                pdu.req_id, pdu.err.0, pdu.err_index
+            )?;
+            &pdu.var
+        }
+        SnmpPdu::Bulk(pdu) => {
+            write!(
+                out,
+                ",\"req_id\":{},\"non_repeaters\":{},\"max_repetitions\":{}",
+                pdu.req_id, pdu.non_repeaters, pdu.max_repetitions
+            )?;
+            &pdu.var
+        }
+        SnmpPdu::TrapV1(pdu) => {
+            let NetworkAddress::IPv4(agent_addr) = pdu.agent_addr;
+            write!(
+                out,
+                ",\"enterprise\":\"{}\",\"agent_addr\":\"{}\",\"generic_trap\":{},\
+                 \"specific_trap\":{},\"timestamp\":{}",
+                format_oid(&pdu.enterprise),
+                agent_addr,
+                pdu.generic_trap.0,
+                pdu.specific_trap,
+                pdu.timestamp
+            )?;
+            &pdu.var
+        }
+    };
+    out.write_str(",\"vars\":[")?;
+    for (i, var) in vars.iter().take(config.max_varbinds).enumerate() {
+        if i > 0 {
+            out.write_char(',')?;
+        }
+        write_json_var(out, var)?;
+    }
+    out.write_str("]}")
+}
+
+fn write_json_var(out: &mut String, var: &SnmpVariable) -> fmt::Result {
+    write!(
+        out,
+        "{{\"oid\":\"{}\",\"type\":\"{}\",\"value\":",
+        format_oid(&var.oid),
+        var.val.type_name()
+    )?;
+    match var.val {
+        VarBindValue::Value(ObjectSyntax::Number(n) | ObjectSyntax::Enumerated(n)) => {
+            write!(out, "{}", n)?
+        }
+        // infinite and NaN values are written as strings
+        VarBindValue::Value(ObjectSyntax::Real(n)) if n.is_finite() => write!(out, "{}", n)?,
+        VarBindValue::Value(
+            ObjectSyntax::Counter32(n) | ObjectSyntax::Gauge32(n) | ObjectSyntax::TimeTicks(n),
+        ) => write!(out, "{}", n)?,
+        VarBindValue::Value(ObjectSyntax::UInteger32(n)) => write!(out, "{}", n)?,
+        VarBindValue::Value(ObjectSyntax::Counter64(n)) => write!(out, "{}", n)?,
+        VarBindValue::Value(ObjectSyntax::String(s)) => {
+            let s = format_octet_string(s).escape(StringEscape::Raw);
+            write!(out, "{}", JsonStr(s))?
+        }
+        VarBindValue::Value(_) => write!(out, "{}", JsonStr(format_varbind_value(&var.val)))?,
+        VarBindValue::Unspecified
+        | VarBindValue::NoSuchObject
+        | VarBindValue::NoSuchInstance
+        | VarBindValue::EndOfMibView => out.write_str("null")?,
+    }
+    out.write_char('}')
+}
@@ -30,21 +30,126 @@
     rust_2018_idioms,*/
     unreachable_pub
 )]
-#![forbid(unsafe_code)]
+#![cfg_attr(
+    not(any(feature = "capi", feature = "alloc-stats")),
+    forbid(unsafe_code)
+)]
 #![deny(broken_intra_doc_links)]
 #![doc(test(
     no_crate_inject,
     attr(deny(warnings, rust_2018_idioms), allow(dead_code, unused_variables))
 ))]
 #![cfg_attr(docsrs, feature(doc_cfg))]
+// without the `std` feature, the crate itself only uses `core` and `alloc`, but its dependencies
+// still require `std` (see Cargo.toml)
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[macro_use]
+mod instrument;
 
 mod generic;
+#[cfg(any(feature = "std", feature = "v3"))]
+mod lru;
+#[cfg(feature = "std")]
+mod pending;
+#[cfg(feature = "v3")]
 mod usm;
 
+#[cfg(feature = "capi")]
+#[cfg_attr(docsrs, doc(cfg(feature = "capi")))]
+pub mod capi;
+#[cfg(feature = "miette")]
+#[cfg_attr(docsrs, doc(cfg(feature = "miette")))]
+pub mod diagnostic;
+
+pub mod agentx;
+pub mod annotate;
+pub mod anomaly;
+pub mod audit;
+pub mod coexistence;
+pub mod config;
+#[cfg(feature = "conformance")]
+#[cfg_attr(docsrs, doc(cfg(feature = "conformance")))]
+pub mod conformance;
+pub mod decoders;
+pub mod dump;
+pub mod encode;
+#[cfg(feature = "v3")]
+#[cfg_attr(docsrs, doc(cfg(feature = "v3")))]
+pub mod engine;
+#[cfg(feature = "enterprises")]
+#[cfg_attr(docsrs, doc(cfg(feature = "enterprises")))]
+pub mod enterprise;
 pub mod error;
+pub mod exchange;
+pub mod format;
+#[cfg(feature = "hash-credentials")]
+#[cfg_attr(docsrs, doc(cfg(feature = "hash-credentials")))]
+pub mod hashing;
+pub mod inspect;
+pub mod intern;
+#[cfg(feature = "rasn")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rasn")))]
+pub mod interop;
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub mod jsonl;
+pub mod metrics;
+pub mod mib;
+pub mod mib2;
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub mod profile;
+pub mod raw;
+pub mod rules;
+pub mod scrub;
+#[cfg(feature = "zeroize")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+pub mod secrets;
+#[cfg(feature = "bytes")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bytes")))]
+pub mod shared;
+pub mod simulate;
+pub mod smux;
 pub mod snmp;
+#[cfg(feature = "v2p")]
+#[cfg_attr(docsrs, doc(cfg(feature = "v2p")))]
+pub mod snmpv2p;
+#[cfg(feature = "v2u")]
+#[cfg_attr(docsrs, doc(cfg(feature = "v2u")))]
+pub mod snmpv2u;
+#[cfg(feature = "v3")]
+#[cfg_attr(docsrs, doc(cfg(feature = "v3")))]
 pub mod snmpv3;
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub mod stream;
+pub mod topology;
+pub mod trap;
+pub mod visit;
+pub mod walk;
+
+/// Build an [`Oid`] from a dotted literal, at compile time
+///
+/// This is a re-export of the [`asn1_rs::oid`] macro, so OID constants can be declared without
+/// depending on `asn1-rs`:
+///
+/// ```rust
+/// use snmp_parser::{oid, Oid};
+///
+/// const SYS_NAME: Oid<'static> = oid!(1.3.6.1.2.1.1.5.0);
+/// assert_eq!(SYS_NAME.to_id_string(), "1.3.6.1.2.1.1.5.0");
+/// ```
+pub use asn1_rs::oid;
+pub use asn1_rs::Oid;
 
 pub use generic::*;
 pub use snmp::*;
+#[cfg(feature = "v2p")]
+pub use snmpv2p::*;
+#[cfg(feature = "v2u")]
+pub use snmpv2u::*;
+#[cfg(feature = "v3")]
 pub use snmpv3::*;
@@ -0,0 +1,141 @@
+//! Map with a maximum number of entries, evicting the least recently updated entry
+//!
+//! This is used by the trackers storing state for keys read from the network (engine IDs, trap
+//! keys, request IDs), so that spoofed messages cannot make them grow without limit.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+
+#[derive(Debug)]
+pub(crate) struct LruMap<K, V> {
+    capacity: usize,
+    next_seq: u64,
+    entries: BTreeMap<K, (u64, V)>,
+    /// Keys, ordered by last update
+    order: BTreeMap<u64, K>,
+}
+
+impl<K: Ord + Clone, V> LruMap<K, V> {
+    pub(crate) fn new(capacity: usize) -> Self {
+        LruMap {
+            capacity,
+            next_seq: 0,
+            entries: BTreeMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    fn touch(&mut self, seq: u64) -> u64 {
+        let new_seq = self.next_seq;
+        self.next_seq += 1;
+        if let Some(key) = self.order.remove(&seq) {
+            self.order.insert(new_seq, key);
+        }
+        new_seq
+    }
+
+    pub(crate) fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.entries.get(key).map(|(_, value)| value)
+    }
+
+    /// Return the value of `key`, marking it as the most recently updated entry
+    pub(crate) fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let seq = self.entries.get(key)?.0;
+        let new_seq = self.touch(seq);
+        let entry = self.entries.get_mut(key)?;
+        entry.0 = new_seq;
+        Some(&mut entry.1)
+    }
+
+    /// Insert or replace the value of `key`, returning the entry evicted to make room for it
+    pub(crate) fn insert(&mut self, key: K, value: V) -> Option<(K, V)> {
+        if let Some(entry) = self.get_mut(&key) {
+            *entry = value;
+            return None;
+        }
+        if self.capacity == 0 {
+            return Some((key, value));
+        }
+        let evicted = if self.entries.len() >= self.capacity {
+            self.order.pop_first().and_then(|(_, oldest)| {
+                let (_, value) = self.entries.remove(&oldest)?;
+                Some((oldest, value))
+            })
+        } else {
+            None
+        };
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.order.insert(seq, key.clone());
+        self.entries.insert(key, (seq, value));
+        evicted
+    }
+
+    pub(crate) fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (seq, value) = self.entries.remove(key)?;
+        self.order.remove(&seq);
+        Some(value)
+    }
+
+    /// Remove the entries for which `f` returns `false`
+    // only used by the trap storm detector
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub(crate) fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        let entries = &mut self.entries;
+        self.order.retain(|_, key| {
+            let keep = match entries.get_mut(key) {
+                Some((_, value)) => f(key, value),
+                None => false,
+            };
+            if !keep {
+                entries.remove(key);
+            }
+            keep
+        });
+    }
+
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(key, (_, value))| (key, value))
+    }
+
+    /// Remove and return the entries for which `f` returns `true`, in the order of their last
+    /// update
+    // only used by the request trackers
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub(crate) fn extract_if<F>(&mut self, mut f: F) -> Vec<(K, V)>
+    where
+        F: FnMut(&K, &V) -> bool,
+    {
+        let keys: Vec<K> = (self.order.values())
+            .filter(|key| matches!(self.entries.get(*key), Some((_, value)) if f(key, value)))
+            .cloned()
+            .collect();
+        (keys.into_iter())
+            .filter_map(|key| {
+                let value = self.remove(&key)?;
+                Some((key, value))
+            })
+            .collect()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
@@ -0,0 +1,161 @@
+//! Conversion of responses to metric samples
+//!
+//! Counters and gauges read by a manager can be observed passively, and exported to a
+//! monitoring system. [`MetricsAdapter`] converts the `Counter32`, `Counter64`, `Gauge32` and
+//! `TimeTicks` values of Response PDUs into samples, formatted like the Prometheus text format:
+//!
+//! ```rust
+//! use snmp_parser::metrics::MetricsAdapter;
+//! use snmp_parser::mib::{MaxAccess, MibRegistry, MibSyntax};
+//! use snmp_parser::oid;
+//! # use snmp_parser::*;
+//!
+//! let mut registry = MibRegistry::new();
+//! let (ro, syntax) = (MaxAccess::ReadOnly, MibSyntax::Counter64);
+//! registry.insert(&oid!(1.3.6.1.2.1.31.1.1.1.6), "ifHCInOctets", ro, syntax);
+//!
+//! # let pdu = SnmpPdu::Generic(SnmpGenericPdu {
+//! #     pdu_type: PduType::Response,
+//! #     req_id: 1,
+//! #     err: ErrorStatus::NoError,
+//! #     err_index: 0,
+//! #     var: vec![SnmpVariable {
+//! #         oid: oid!(1.3.6.1.2.1.31.1.1.1.6.3),
+//! #         val: VarBindValue::Value(ObjectSyntax::Counter64(1234)),
+//! #     }],
+//! # });
+//! let samples = MetricsAdapter::with_registry(&registry).samples(&pdu);
+//! assert_eq!(samples[0].to_string(), "ifHCInOctets{index=\"3\"} 1234");
+//! ```
+//!
+//! Without a registry, metrics are named after the OID of the variable (for ex.
+//! `snmp_1_3_6_1_2_1_1_3_0`), and have no labels.
+
+use crate::format::format_oid;
+use crate::mib::MibRegistry;
+use crate::snmp::{ObjectSyntax, PduType, SnmpPdu, VarBindValue};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+/// Type of a metric
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetricKind {
+    /// Monotonic value (`Counter32` and `Counter64`)
+    Counter,
+    /// Value which can go up and down (`Gauge32` and `TimeTicks`)
+    Gauge,
+}
+
+/// A value read from a response
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MetricSample {
+    pub name: String,
+    /// Labels, for ex. `index` for the instance of a table column
+    pub labels: Vec<(String, String)>,
+    pub kind: MetricKind,
+    pub value: u64,
+}
+
+/// Format the sample as a line of the Prometheus text format, for ex. `ifInOctets{index="3"} 12`
+impl fmt::Display for MetricSample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if !self.labels.is_empty() {
+            f.write_char('{')?;
+            for (i, (name, value)) in self.labels.iter().enumerate() {
+                if i > 0 {
+                    f.write_char(',')?;
+                }
+                write!(f, "{}=\"", name)?;
+                for c in value.chars() {
+                    match c {
+                        '\\' => f.write_str("\\\\")?,
+                        '"' => f.write_str("\\\"")?,
+                        '\n' => f.write_str("\\n")?,
+                        c => f.write_char(c)?,
+                    }
+                }
+                f.write_char('"')?;
+            }
+            f.write_char('}')?;
+        }
+        write!(f, " {}", self.value)
+    }
+}
+
+/// Conversion of Response PDUs to metric samples
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MetricsAdapter<'r> {
+    registry: Option<&'r MibRegistry>,
+}
+
+impl<'r> MetricsAdapter<'r> {
+    /// Create an adapter naming metrics after OIDs
+    pub fn new() -> Self {
+        MetricsAdapter::default()
+    }
+
+    /// Create an adapter naming metrics after the objects registered in `registry`
+    ///
+    /// The instance part of the OIDs (for ex. the index of a table row) is written in the
+    /// `index` label, unless it is `0` (scalars). Variables of unknown objects are named after
+    /// their OID.
+    pub fn with_registry(registry: &'r MibRegistry) -> Self {
+        MetricsAdapter {
+            registry: Some(registry),
+        }
+    }
+
+    /// Return the samples of the counter and gauge values of `pdu`
+    ///
+    /// The result is empty if `pdu` is not a Response.
+    pub fn samples(&self, pdu: &SnmpPdu) -> Vec<MetricSample> {
+        let response = match pdu {
+            SnmpPdu::Generic(response) if response.pdu_type == PduType::Response => response,
+            _ => return Vec::new(),
+        };
+        let mut samples = Vec::new();
+        for var in &response.var {
+            let (kind, value) = match var.val {
+                VarBindValue::Value(ObjectSyntax::Counter32(n)) => (MetricKind::Counter, n as u64),
+                VarBindValue::Value(ObjectSyntax::Counter64(n)) => (MetricKind::Counter, n),
+                VarBindValue::Value(ObjectSyntax::Gauge32(n) | ObjectSyntax::TimeTicks(n)) => {
+                    (MetricKind::Gauge, n as u64)
+                }
+                _ => continue,
+            };
+            let mut labels = Vec::new();
+            let name = match self.registry.and_then(|r| r.lookup(&var.oid)) {
+                Some((object, instance)) => {
+                    if instance != [0] && !instance.is_empty() {
+                        let index: Vec<_> = instance.iter().map(|arc| arc.to_string()).collect();
+                        labels.push(("index".to_string(), index.join(".")));
+                    }
+                    sanitize_name(&object.name)
+                }
+                None => sanitize_name(&format!("snmp_{}", format_oid(&var.oid))),
+            };
+            samples.push(MetricSample {
+                name,
+                labels,
+                kind,
+                value,
+            });
+        }
+        samples
+    }
+}
+
+/// Replace characters which are not valid in metric names by `_`
+fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .enumerate()
+        .map(|(i, c)| match c {
+            'a'..='z' | 'A'..='Z' | '_' | ':' => c,
+            '0'..='9' if i > 0 => c,
+            _ => '_',
+        })
+        .collect()
+}
@@ -0,0 +1,210 @@
+//! Definitions of MIB objects
+//!
+//! This crate does not read MIB files. Applications register the definitions they need (for
+//! ex. compiled from MIB modules by another tool) in a [`MibRegistry`], which is used to name
+//! variables and to check requests against the definitions of the objects:
+//!
+//! ```rust
+//! use snmp_parser::audit::OidNames;
+//! use snmp_parser::mib::{MaxAccess, MibRegistry, MibSyntax};
+//! use snmp_parser::oid;
+//!
+//! let mut registry = MibRegistry::new();
+//! let (rw, ro) = (MaxAccess::ReadWrite, MaxAccess::ReadOnly);
+//! registry.insert(&oid!(1.3.6.1.2.1.1.5), "sysName", rw, MibSyntax::OctetString);
+//! registry.insert(&oid!(1.3.6.1.2.1.2.2.1.2), "ifDescr", ro, MibSyntax::OctetString);
+//!
+//! let (object, _) = registry.lookup(&oid!(1.3.6.1.2.1.2.2.1.2.3)).expect("unknown object");
+//! assert_eq!(object.max_access, MaxAccess::ReadOnly);
+//! let name = registry.oid_name(&oid!(1.3.6.1.2.1.2.2.1.2.3));
+//! assert_eq!(name.as_deref(), Some("ifDescr.3"));
+//! ```
+//!
+//! Lookups only borrow the registry, which is `Send` and `Sync`: a registry loaded once can be
+//! shared by all the workers of a pipeline behind an `Arc`, without locking.
+
+use crate::audit::OidNames;
+use crate::snmp::ObjectSyntax;
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use asn1_rs::Oid;
+use core::fmt;
+use core::fmt::Write;
+
+/// MAX-ACCESS clause of an OBJECT-TYPE definition
+///
+/// SMIv1 `ACCESS` clauses map to the same values (`write-only` should be mapped to
+/// `ReadWrite`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MaxAccess {
+    NotAccessible,
+    AccessibleForNotify,
+    ReadOnly,
+    ReadWrite,
+    ReadCreate,
+}
+
+impl MaxAccess {
+    /// Test if the object can be modified by a SetRequest
+    pub fn is_writable(self) -> bool {
+        matches!(self, MaxAccess::ReadWrite | MaxAccess::ReadCreate)
+    }
+}
+
+/// Format the value as in MIB definitions, for ex. `read-only`
+impl fmt::Display for MaxAccess {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            MaxAccess::NotAccessible => "not-accessible",
+            MaxAccess::AccessibleForNotify => "accessible-for-notify",
+            MaxAccess::ReadOnly => "read-only",
+            MaxAccess::ReadWrite => "read-write",
+            MaxAccess::ReadCreate => "read-create",
+        };
+        f.write_str(s)
+    }
+}
+
+/// SYNTAX clause of an OBJECT-TYPE definition, reduced to the base type
+///
+/// Textual conventions must be mapped to their base type (for ex. `DisplayString` to
+/// `OctetString`), and enumerations to `Integer`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MibSyntax {
+    /// `INTEGER` or `Integer32`
+    Integer,
+    OctetString,
+    ObjectIdentifier,
+    /// `BITS`, encoded as an OCTET STRING
+    Bits,
+    IpAddress,
+    Counter32,
+    /// `Gauge32` or `Unsigned32`
+    Gauge32,
+    TimeTicks,
+    Opaque,
+    NsapAddress,
+    Counter64,
+    /// `UInteger32` (obsolete)
+    UInteger32,
+}
+
+impl MibSyntax {
+    /// Test if `value` has the type of this syntax
+    pub fn matches(self, value: &ObjectSyntax) -> bool {
+        matches!(
+            (self, value),
+            (MibSyntax::Integer, ObjectSyntax::Number(_))
+                | (MibSyntax::OctetString, ObjectSyntax::String(_))
+                | (MibSyntax::ObjectIdentifier, ObjectSyntax::Object(_))
+                | (MibSyntax::Bits, ObjectSyntax::String(_))
+                | (MibSyntax::Bits, ObjectSyntax::BitString(_))
+                | (MibSyntax::IpAddress, ObjectSyntax::IpAddress(_))
+                | (MibSyntax::Counter32, ObjectSyntax::Counter32(_))
+                | (MibSyntax::Gauge32, ObjectSyntax::Gauge32(_))
+                | (MibSyntax::TimeTicks, ObjectSyntax::TimeTicks(_))
+                | (MibSyntax::Opaque, ObjectSyntax::Opaque(_))
+                | (MibSyntax::NsapAddress, ObjectSyntax::NsapAddress(_))
+                | (MibSyntax::Counter64, ObjectSyntax::Counter64(_))
+                | (MibSyntax::UInteger32, ObjectSyntax::UInteger32(_))
+        )
+    }
+}
+
+/// Format the type as in MIB definitions, for ex. `OCTET STRING`
+impl fmt::Display for MibSyntax {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            MibSyntax::Integer => "INTEGER",
+            MibSyntax::OctetString => "OCTET STRING",
+            MibSyntax::ObjectIdentifier => "OBJECT IDENTIFIER",
+            MibSyntax::Bits => "BITS",
+            MibSyntax::IpAddress => "IpAddress",
+            MibSyntax::Counter32 => "Counter32",
+            MibSyntax::Gauge32 => "Gauge32",
+            MibSyntax::TimeTicks => "TimeTicks",
+            MibSyntax::Opaque => "Opaque",
+            MibSyntax::NsapAddress => "NsapAddress",
+            MibSyntax::Counter64 => "Counter64",
+            MibSyntax::UInteger32 => "UInteger32",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Definition of an object (OBJECT-TYPE)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MibObject {
+    pub name: String,
+    pub max_access: MaxAccess,
+    pub syntax: MibSyntax,
+}
+
+/// Object definitions, loaded by the application
+///
+/// Objects are indexed by their OID. Variables (instances of objects) are found by looking up the
+/// longest registered prefix of their OID.
+#[derive(Clone, Debug, Default)]
+pub struct MibRegistry {
+    objects: BTreeMap<Vec<u8>, MibObject>,
+}
+
+impl MibRegistry {
+    pub fn new() -> Self {
+        MibRegistry::default()
+    }
+
+    /// Register object `name`, replacing any previous definition with the same OID
+    pub fn insert(&mut self, oid: &Oid, name: &str, max_access: MaxAccess, syntax: MibSyntax) {
+        let object = MibObject {
+            name: name.to_string(),
+            max_access,
+            syntax,
+        };
+        self.objects.insert(oid.as_bytes().to_vec(), object);
+    }
+
+    /// Return the object registered with this exact OID
+    pub fn get(&self, oid: &Oid) -> Option<&MibObject> {
+        self.objects.get(oid.as_bytes())
+    }
+
+    /// Return the object defining variable `oid`, and the instance part of the OID (the arcs
+    /// following the OID of the object, for ex. `[0]` for a scalar)
+    ///
+    /// Return `None` if no registered object is a prefix of `oid`.
+    pub fn lookup(&self, oid: &Oid) -> Option<(&MibObject, Vec<u64>)> {
+        let bytes = oid.as_bytes();
+        // arcs end on bytes without the continuation bit
+        let (len, object) = (1..=bytes.len())
+            .rev()
+            .filter(|&len| bytes[len - 1] & 0x80 == 0)
+            .find_map(|len| self.objects.get(&bytes[..len]).map(|obj| (len, obj)))?;
+        let prefix_arcs = Oid::new(Cow::Borrowed(&bytes[..len])).iter()?.count();
+        let instance = oid.iter()?.skip(prefix_arcs).collect();
+        Some((object, instance))
+    }
+
+    /// Return the number of registered objects
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+}
+
+/// Name variables as `object.instance`, for ex. `ifDescr.3`
+impl OidNames for MibRegistry {
+    fn oid_name(&self, oid: &Oid) -> Option<String> {
+        let (object, instance) = self.lookup(oid)?;
+        let mut name = object.name.clone();
+        for arc in instance {
+            let _ = write!(name, ".{}", arc);
+        }
+        Some(name)
+    }
+}
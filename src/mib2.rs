@@ -0,0 +1,577 @@
+//! Decoding of common MIB-2 objects
+//!
+//! Most monitoring tools poll the same few tables. This module extracts typed values from the
+//! variables of Response PDUs, grouped by row like with [`TableWalk`](crate::walk::TableWalk):
+//!
+//! - [`InterfaceTable`]: interface statistics, from `ifTable` (IF-MIB) and `ifXTable`
+//! - [`HostResources`]: storage usage and processor load, from `hrStorageTable` and
+//!   `hrProcessorTable` (HOST-RESOURCES-MIB)
+//! - [`EntityInventory`]: physical inventory, from `entPhysicalTable` (ENTITY-MIB)
+//! - [`EngineCounters`]: statistics of the SNMP engine of the agent (SNMPv2-MIB and
+//!   SNMP-MPD-MIB), which can corroborate attacks observed passively (for ex. community
+//!   guessing increases `snmpInBadCommunityNames`)
+//!
+//! ```rust
+//! use snmp_parser::mib2::InterfaceTable;
+//! # use snmp_parser::SnmpPdu;
+//!
+//! # fn print_interfaces(responses: &[SnmpPdu<'_>]) {
+//! let mut table = InterfaceTable::new();
+//! for response in responses {
+//!     table.push_pdu(response);
+//! }
+//! for interface in table.samples() {
+//!     println!("{:?}: {:?} octets in", interface.descr, interface.in_octets_64());
+//! }
+//! # }
+//! ```
+
+use crate::snmp::{ObjectSyntax, PduType, SnmpPdu, SnmpVariable, VarBindValue};
+use crate::walk::split_table_instance;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use asn1_rs::{oid, Oid};
+use core::convert::TryFrom;
+
+/// `ifEntry` (IF-MIB), indexed by `ifIndex`
+#[rustfmt::skip]
+pub const IF_ENTRY_OID: Oid<'static> = oid!(1.3.6.1.2.1.2.2.1);
+
+/// `ifXEntry` (IF-MIB), indexed by `ifIndex`
+#[rustfmt::skip]
+pub const IF_X_ENTRY_OID: Oid<'static> = oid!(1.3.6.1.2.1.31.1.1.1);
+
+/// `hrStorageEntry` (HOST-RESOURCES-MIB), indexed by `hrStorageIndex`
+#[rustfmt::skip]
+pub const HR_STORAGE_ENTRY_OID: Oid<'static> = oid!(1.3.6.1.2.1.25.2.3.1);
+
+/// `hrProcessorEntry` (HOST-RESOURCES-MIB), indexed by `hrDeviceIndex`
+#[rustfmt::skip]
+pub const HR_PROCESSOR_ENTRY_OID: Oid<'static> = oid!(1.3.6.1.2.1.25.3.3.1);
+
+/// `entPhysicalEntry` (ENTITY-MIB), indexed by `entPhysicalIndex`
+#[rustfmt::skip]
+pub const ENT_PHYSICAL_ENTRY_OID: Oid<'static> = oid!(1.3.6.1.2.1.47.1.1.1.1);
+
+/// `snmp` group (SNMPv2-MIB)
+#[rustfmt::skip]
+pub const SNMP_GROUP_OID: Oid<'static> = oid!(1.3.6.1.2.1.11);
+
+/// `snmpMPDStats` group (SNMP-MPD-MIB)
+#[rustfmt::skip]
+pub const SNMP_MPD_STATS_OID: Oid<'static> = oid!(1.3.6.1.6.3.11.2.1);
+
+/// Statistics of an interface
+///
+/// Fields are `None` if the corresponding column was not seen (or had an unexpected type).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InterfaceSample {
+    pub if_index: u32,
+    pub descr: Option<String>,
+    pub if_type: Option<i32>,
+    pub mtu: Option<i32>,
+    /// `ifSpeed`, in bits per second
+    pub speed: Option<u32>,
+    pub phys_address: Option<Vec<u8>>,
+    /// `ifAdminStatus`: 1 (up), 2 (down) or 3 (testing)
+    pub admin_status: Option<i32>,
+    /// `ifOperStatus`: 1 (up), 2 (down), 3 (testing), 4 (unknown), 5 (dormant), 6 (notPresent)
+    /// or 7 (lowerLayerDown)
+    pub oper_status: Option<i32>,
+    pub in_octets: Option<u32>,
+    pub in_ucast_pkts: Option<u32>,
+    pub in_discards: Option<u32>,
+    pub in_errors: Option<u32>,
+    pub out_octets: Option<u32>,
+    pub out_ucast_pkts: Option<u32>,
+    pub out_discards: Option<u32>,
+    pub out_errors: Option<u32>,
+    /// `ifName` (ifXTable)
+    pub name: Option<String>,
+    /// `ifHCInOctets` (ifXTable)
+    pub hc_in_octets: Option<u64>,
+    /// `ifHCOutOctets` (ifXTable)
+    pub hc_out_octets: Option<u64>,
+    /// `ifHighSpeed` (ifXTable), in millions of bits per second
+    pub high_speed: Option<u32>,
+    /// `ifAlias` (ifXTable)
+    pub alias: Option<String>,
+}
+
+impl InterfaceSample {
+    /// Test if the operational status is `up`
+    pub fn is_up(&self) -> bool {
+        self.oper_status == Some(1)
+    }
+
+    /// Return the number of octets received, using the 64-bit counter if available
+    pub fn in_octets_64(&self) -> Option<u64> {
+        self.hc_in_octets.or_else(|| self.in_octets.map(u64::from))
+    }
+
+    /// Return the number of octets sent, using the 64-bit counter if available
+    pub fn out_octets_64(&self) -> Option<u64> {
+        self.hc_out_octets
+            .or_else(|| self.out_octets.map(u64::from))
+    }
+
+    fn set_if_column(&mut self, column: u64, value: &ObjectSyntax) {
+        match column {
+            2 => self.descr = as_string(value),
+            3 => self.if_type = as_i32(value),
+            4 => self.mtu = as_i32(value),
+            5 => self.speed = as_u32(value),
+            6 => self.phys_address = as_bytes(value),
+            7 => self.admin_status = as_i32(value),
+            8 => self.oper_status = as_i32(value),
+            10 => self.in_octets = as_u32(value),
+            11 => self.in_ucast_pkts = as_u32(value),
+            13 => self.in_discards = as_u32(value),
+            14 => self.in_errors = as_u32(value),
+            16 => self.out_octets = as_u32(value),
+            17 => self.out_ucast_pkts = as_u32(value),
+            19 => self.out_discards = as_u32(value),
+            20 => self.out_errors = as_u32(value),
+            _ => (),
+        }
+    }
+
+    fn set_if_x_column(&mut self, column: u64, value: &ObjectSyntax) {
+        match column {
+            1 => self.name = as_string(value),
+            6 => self.hc_in_octets = as_u64(value),
+            10 => self.hc_out_octets = as_u64(value),
+            15 => self.high_speed = as_u32(value),
+            18 => self.alias = as_string(value),
+            _ => (),
+        }
+    }
+}
+
+/// Accumulator of the variables of `ifTable` and `ifXTable`, grouped by `ifIndex`
+#[derive(Clone, Debug, Default)]
+pub struct InterfaceTable {
+    interfaces: BTreeMap<u32, InterfaceSample>,
+}
+
+impl InterfaceTable {
+    pub fn new() -> Self {
+        InterfaceTable::default()
+    }
+
+    /// Add the variables of a Response PDU (other PDUs are ignored)
+    pub fn push_pdu(&mut self, pdu: &SnmpPdu) {
+        response_vars(pdu).iter().for_each(|var| self.push_var(var));
+    }
+
+    /// Add a variable, if it is a column of `ifTable` or `ifXTable`
+    pub fn push_var(&mut self, var: &SnmpVariable) {
+        let value = match var.val {
+            VarBindValue::Value(ref value) => value,
+            _ => return,
+        };
+        if let Some((column, if_index)) = table_instance(&IF_ENTRY_OID, &var.oid) {
+            self.sample(if_index).set_if_column(column, value);
+        } else if let Some((column, if_index)) = table_instance(&IF_X_ENTRY_OID, &var.oid) {
+            self.sample(if_index).set_if_x_column(column, value);
+        }
+    }
+
+    fn sample(&mut self, if_index: u32) -> &mut InterfaceSample {
+        self.interfaces
+            .entry(if_index)
+            .or_insert_with(|| InterfaceSample {
+                if_index,
+                ..InterfaceSample::default()
+            })
+    }
+
+    /// Return the interface `if_index`, if any of its columns was seen
+    pub fn get(&self, if_index: u32) -> Option<&InterfaceSample> {
+        self.interfaces.get(&if_index)
+    }
+
+    /// Iterate over the interfaces, ordered by `ifIndex`
+    pub fn samples(&self) -> impl Iterator<Item = &InterfaceSample> {
+        self.interfaces.values()
+    }
+
+    /// Return the number of interfaces
+    pub fn len(&self) -> usize {
+        self.interfaces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.interfaces.is_empty()
+    }
+}
+
+/// Storage area of a host (`hrStorageEntry`)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StorageSample {
+    pub index: u32,
+    /// `hrStorageType`, for ex. `hrStorageFixedDisk` (1.3.6.1.2.1.25.2.1.4)
+    pub storage_type: Option<Oid<'static>>,
+    pub descr: Option<String>,
+    /// Size of the allocation units, in bytes
+    pub allocation_units: Option<i32>,
+    /// Size, in allocation units
+    pub size: Option<i32>,
+    /// Used space, in allocation units
+    pub used: Option<i32>,
+}
+
+impl StorageSample {
+    /// Return the size in bytes
+    pub fn size_bytes(&self) -> Option<u64> {
+        units_to_bytes(self.size?, self.allocation_units?)
+    }
+
+    /// Return the used space in bytes
+    pub fn used_bytes(&self) -> Option<u64> {
+        units_to_bytes(self.used?, self.allocation_units?)
+    }
+}
+
+fn units_to_bytes(count: i32, unit: i32) -> Option<u64> {
+    u64::try_from(count)
+        .ok()?
+        .checked_mul(u64::try_from(unit).ok()?)
+}
+
+/// Accumulator of the variables of `hrStorageTable` and `hrProcessorTable`
+#[derive(Clone, Debug, Default)]
+pub struct HostResources {
+    storage: BTreeMap<u32, StorageSample>,
+    processors: BTreeMap<u32, i32>,
+}
+
+impl HostResources {
+    pub fn new() -> Self {
+        HostResources::default()
+    }
+
+    /// Add the variables of a Response PDU (other PDUs are ignored)
+    pub fn push_pdu(&mut self, pdu: &SnmpPdu) {
+        response_vars(pdu).iter().for_each(|var| self.push_var(var));
+    }
+
+    /// Add a variable, if it is a column of `hrStorageTable`, or `hrProcessorLoad`
+    pub fn push_var(&mut self, var: &SnmpVariable) {
+        let value = match var.val {
+            VarBindValue::Value(ref value) => value,
+            _ => return,
+        };
+        if let Some((column, index)) = table_instance(&HR_STORAGE_ENTRY_OID, &var.oid) {
+            let storage = self.storage.entry(index).or_insert_with(|| StorageSample {
+                index,
+                ..StorageSample::default()
+            });
+            match column {
+                2 => storage.storage_type = as_oid(value),
+                3 => storage.descr = as_string(value),
+                4 => storage.allocation_units = as_i32(value),
+                5 => storage.size = as_i32(value),
+                6 => storage.used = as_i32(value),
+                _ => (),
+            }
+        } else if let Some((2, index)) = table_instance(&HR_PROCESSOR_ENTRY_OID, &var.oid) {
+            if let Some(load) = as_i32(value) {
+                self.processors.insert(index, load);
+            }
+        }
+    }
+
+    /// Iterate over the storage areas, ordered by index
+    pub fn storage(&self) -> impl Iterator<Item = &StorageSample> {
+        self.storage.values()
+    }
+
+    /// Iterate over the processors, as `(hrDeviceIndex, hrProcessorLoad)`
+    ///
+    /// The load is the average percentage of time the processor was not idle over the last
+    /// minute.
+    pub fn processor_loads(&self) -> impl Iterator<Item = (u32, i32)> + '_ {
+        self.processors.iter().map(|(&index, &load)| (index, load))
+    }
+
+    /// Return the average load of all processors
+    pub fn average_processor_load(&self) -> Option<i32> {
+        if self.processors.is_empty() {
+            return None;
+        }
+        let total: i64 = self.processors.values().map(|&load| i64::from(load)).sum();
+        i32::try_from(total / self.processors.len() as i64).ok()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.storage.is_empty() && self.processors.is_empty()
+    }
+}
+
+/// Physical component of a device (`entPhysicalEntry`)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PhysicalEntity {
+    pub index: u32,
+    pub descr: Option<String>,
+    /// `entPhysicalVendorType`
+    pub vendor_type: Option<Oid<'static>>,
+    /// Index of the containing entity (0 if this entity is not contained in another)
+    pub contained_in: Option<i32>,
+    /// `entPhysicalClass`, for ex. 3 (chassis), 6 (powerSupply), 9 (module) or 10 (port)
+    pub class: Option<i32>,
+    pub name: Option<String>,
+    pub hardware_rev: Option<String>,
+    pub firmware_rev: Option<String>,
+    pub software_rev: Option<String>,
+    pub serial_num: Option<String>,
+    pub mfg_name: Option<String>,
+    pub model_name: Option<String>,
+}
+
+/// Accumulator of the variables of `entPhysicalTable`
+#[derive(Clone, Debug, Default)]
+pub struct EntityInventory {
+    entities: BTreeMap<u32, PhysicalEntity>,
+}
+
+impl EntityInventory {
+    pub fn new() -> Self {
+        EntityInventory::default()
+    }
+
+    /// Add the variables of a Response PDU (other PDUs are ignored)
+    pub fn push_pdu(&mut self, pdu: &SnmpPdu) {
+        response_vars(pdu).iter().for_each(|var| self.push_var(var));
+    }
+
+    /// Add a variable, if it is a column of `entPhysicalTable`
+    pub fn push_var(&mut self, var: &SnmpVariable) {
+        let value = match var.val {
+            VarBindValue::Value(ref value) => value,
+            _ => return,
+        };
+        let (column, index) = match table_instance(&ENT_PHYSICAL_ENTRY_OID, &var.oid) {
+            Some(instance) => instance,
+            None => return,
+        };
+        let entity = self
+            .entities
+            .entry(index)
+            .or_insert_with(|| PhysicalEntity {
+                index,
+                ..PhysicalEntity::default()
+            });
+        match column {
+            2 => entity.descr = as_string(value),
+            3 => entity.vendor_type = as_oid(value),
+            4 => entity.contained_in = as_i32(value),
+            5 => entity.class = as_i32(value),
+            7 => entity.name = as_string(value),
+            8 => entity.hardware_rev = as_string(value),
+            9 => entity.firmware_rev = as_string(value),
+            10 => entity.software_rev = as_string(value),
+            11 => entity.serial_num = as_string(value),
+            12 => entity.mfg_name = as_string(value),
+            13 => entity.model_name = as_string(value),
+            _ => (),
+        }
+    }
+
+    /// Return the entity `index`
+    pub fn get(&self, index: u32) -> Option<&PhysicalEntity> {
+        self.entities.get(&index)
+    }
+
+    /// Iterate over the entities, ordered by index
+    pub fn entities(&self) -> impl Iterator<Item = &PhysicalEntity> {
+        self.entities.values()
+    }
+
+    /// Iterate over the entities contained in entity `index`
+    pub fn children(&self, index: u32) -> impl Iterator<Item = &PhysicalEntity> {
+        let index = i64::from(index);
+        self.entities
+            .values()
+            .filter(move |e| e.contained_in.map(i64::from) == Some(index))
+    }
+
+    /// Return the number of entities
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+/// Counters of the SNMP engine of an agent
+///
+/// Fields are `None` if the counter was not seen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EngineCounters {
+    /// `snmpInPkts`
+    pub in_pkts: Option<u32>,
+    /// `snmpOutPkts`
+    pub out_pkts: Option<u32>,
+    /// `snmpInBadVersions`
+    pub in_bad_versions: Option<u32>,
+    /// `snmpInBadCommunityNames`
+    pub in_bad_community_names: Option<u32>,
+    /// `snmpInBadCommunityUses`
+    pub in_bad_community_uses: Option<u32>,
+    /// `snmpInASNParseErrs`
+    pub in_asn_parse_errs: Option<u32>,
+    /// `snmpSilentDrops`
+    pub silent_drops: Option<u32>,
+    /// `snmpProxyDrops`
+    pub proxy_drops: Option<u32>,
+    /// `snmpUnknownSecurityModels`
+    pub unknown_security_models: Option<u32>,
+    /// `snmpInvalidMsgs`
+    pub invalid_msgs: Option<u32>,
+    /// `snmpUnknownPDUHandlers`
+    pub unknown_pdu_handlers: Option<u32>,
+}
+
+impl EngineCounters {
+    /// Return the counters found in a Response or Report PDU
+    pub fn from_pdu(pdu: &SnmpPdu) -> Self {
+        let mut counters = EngineCounters::default();
+        counters.update(pdu);
+        counters
+    }
+
+    /// Update the counters found in a Response or Report PDU (other PDUs are ignored)
+    pub fn update(&mut self, pdu: &SnmpPdu) {
+        let vars = match pdu {
+            SnmpPdu::Generic(pdu)
+                if pdu.pdu_type == PduType::Response || pdu.pdu_type == PduType::Report =>
+            {
+                &pdu.var
+            }
+            _ => return,
+        };
+        for var in vars {
+            let value = match var.val {
+                VarBindValue::Value(ObjectSyntax::Counter32(n)) => Some(n),
+                _ => continue,
+            };
+            if let Some((n, 0)) = table_instance(&SNMP_GROUP_OID, &var.oid) {
+                match n {
+                    1 => self.in_pkts = value,
+                    2 => self.out_pkts = value,
+                    3 => self.in_bad_versions = value,
+                    4 => self.in_bad_community_names = value,
+                    5 => self.in_bad_community_uses = value,
+                    6 => self.in_asn_parse_errs = value,
+                    31 => self.silent_drops = value,
+                    32 => self.proxy_drops = value,
+                    _ => (),
+                }
+            } else if let Some((n, 0)) = table_instance(&SNMP_MPD_STATS_OID, &var.oid) {
+                match n {
+                    1 => self.unknown_security_models = value,
+                    2 => self.invalid_msgs = value,
+                    3 => self.unknown_pdu_handlers = value,
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    /// Return the increase of each counter since `previous` (an older snapshot of the same
+    /// agent)
+    ///
+    /// Counters wrap around at 2^32. Counters missing from either snapshot are `None`.
+    pub fn increase_since(&self, previous: &EngineCounters) -> EngineCounters {
+        let diff = |new: Option<u32>, old: Option<u32>| Some(new?.wrapping_sub(old?));
+        EngineCounters {
+            in_pkts: diff(self.in_pkts, previous.in_pkts),
+            out_pkts: diff(self.out_pkts, previous.out_pkts),
+            in_bad_versions: diff(self.in_bad_versions, previous.in_bad_versions),
+            in_bad_community_names: diff(
+                self.in_bad_community_names,
+                previous.in_bad_community_names,
+            ),
+            in_bad_community_uses: diff(self.in_bad_community_uses, previous.in_bad_community_uses),
+            in_asn_parse_errs: diff(self.in_asn_parse_errs, previous.in_asn_parse_errs),
+            silent_drops: diff(self.silent_drops, previous.silent_drops),
+            proxy_drops: diff(self.proxy_drops, previous.proxy_drops),
+            unknown_security_models: diff(
+                self.unknown_security_models,
+                previous.unknown_security_models,
+            ),
+            invalid_msgs: diff(self.invalid_msgs, previous.invalid_msgs),
+            unknown_pdu_handlers: diff(self.unknown_pdu_handlers, previous.unknown_pdu_handlers),
+        }
+    }
+
+    /// Test if no counter was found
+    pub fn is_empty(&self) -> bool {
+        *self == EngineCounters::default()
+    }
+}
+
+/// Return the variables of a Response PDU, or an empty slice for other PDUs
+fn response_vars<'p, 'a>(pdu: &'p SnmpPdu<'a>) -> &'p [SnmpVariable<'a>] {
+    match pdu {
+        SnmpPdu::Generic(response) if response.pdu_type == PduType::Response => &response.var,
+        _ => &[],
+    }
+}
+
+/// Split the OID of a variable of a table indexed by a single integer
+fn table_instance(entry: &Oid, oid: &Oid) -> Option<(u64, u32)> {
+    match split_table_instance(entry.as_bytes(), oid)? {
+        (column, index) if index.len() == 1 => Some((column, u32::try_from(index[0]).ok()?)),
+        _ => None,
+    }
+}
+
+fn as_i32(value: &ObjectSyntax) -> Option<i32> {
+    match *value {
+        ObjectSyntax::Number(n) => Some(n),
+        _ => None,
+    }
+}
+
+fn as_u32(value: &ObjectSyntax) -> Option<u32> {
+    match *value {
+        ObjectSyntax::Counter32(n)
+        | ObjectSyntax::Gauge32(n)
+        | ObjectSyntax::TimeTicks(n)
+        | ObjectSyntax::UInteger32(n) => Some(n),
+        _ => None,
+    }
+}
+
+fn as_u64(value: &ObjectSyntax) -> Option<u64> {
+    match *value {
+        ObjectSyntax::Counter64(n) => Some(n),
+        _ => as_u32(value).map(u64::from),
+    }
+}
+
+fn as_oid(value: &ObjectSyntax) -> Option<Oid<'static>> {
+    match value {
+        ObjectSyntax::Object(oid) => Some(oid.to_owned()),
+        _ => None,
+    }
+}
+
+fn as_bytes(value: &ObjectSyntax) -> Option<Vec<u8>> {
+    match *value {
+        ObjectSyntax::String(s) => Some(s.to_vec()),
+        _ => None,
+    }
+}
+
+fn as_string(value: &ObjectSyntax) -> Option<String> {
+    match *value {
+        ObjectSyntax::String(s) => Some(String::from_utf8_lossy(s).into_owned()),
+        _ => None,
+    }
+}
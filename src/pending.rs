@@ -0,0 +1,103 @@
+//! Requests waiting for an answer, shared by the request trackers
+//!
+//! Requests are keyed by the address of the peer and an identifier (the request ID of informs,
+//! or the `msgID` of SNMPv3 messages). Time is provided by the caller. The number of pending
+//! requests is limited: when the limit is reached, the least recently seen request is dropped.
+
+use crate::lru::LruMap;
+use alloc::vec::Vec;
+use core::time::Duration;
+use std::net::IpAddr;
+use std::time::Instant;
+
+/// A request waiting for an answer, with the data stored by the tracker
+#[derive(Debug)]
+pub(crate) struct PendingRequest<T> {
+    pub(crate) first_seen: Instant,
+    pub(crate) last_seen: Instant,
+    /// Number of requests seen again with the same key
+    pub(crate) retransmissions: u32,
+    pub(crate) data: T,
+}
+
+#[derive(Debug)]
+pub(crate) struct PendingRequests<T> {
+    timeout: Duration,
+    pending: LruMap<(IpAddr, u32), PendingRequest<T>>,
+}
+
+impl<T> PendingRequests<T> {
+    pub(crate) fn new(timeout: Duration, max_pending: usize) -> Self {
+        PendingRequests {
+            timeout,
+            pending: LruMap::new(max_pending),
+        }
+    }
+
+    fn insert_new(&mut self, peer: IpAddr, id: u32, request: PendingRequest<T>) {
+        if let Some(((_peer, _id), _)) = self.pending.insert((peer, id), request) {
+            snmp_anomaly!(
+                peer = %_peer,
+                id = _id,
+                "too many pending requests, dropping the oldest"
+            );
+        }
+    }
+
+    /// Record a request seen at time `now`, as a retransmission if the key is already pending
+    pub(crate) fn observe<F>(&mut self, peer: IpAddr, id: u32, now: Instant, data: F)
+    where
+        F: FnOnce() -> T,
+    {
+        if let Some(p) = self.pending.get_mut(&(peer, id)) {
+            p.retransmissions += 1;
+            p.last_seen = now;
+            return;
+        }
+        let request = PendingRequest {
+            first_seen: now,
+            last_seen: now,
+            retransmissions: 0,
+            data: data(),
+        };
+        self.insert_new(peer, id, request);
+    }
+
+    /// Record a request seen at time `now`, replacing the pending request with the same key
+    // only used by the SNMPv3 request tracker
+    #[cfg_attr(not(feature = "v3"), allow(dead_code))]
+    pub(crate) fn insert(&mut self, peer: IpAddr, id: u32, now: Instant, data: T) {
+        let request = PendingRequest {
+            first_seen: now,
+            last_seen: now,
+            retransmissions: 0,
+            data,
+        };
+        self.pending.remove(&(peer, id));
+        self.insert_new(peer, id, request);
+    }
+
+    #[cfg_attr(not(feature = "v3"), allow(dead_code))]
+    pub(crate) fn get(&self, peer: IpAddr, id: u32) -> Option<&PendingRequest<T>> {
+        self.pending.get(&(peer, id))
+    }
+
+    pub(crate) fn remove(&mut self, peer: IpAddr, id: u32) -> Option<PendingRequest<T>> {
+        self.pending.remove(&(peer, id))
+    }
+
+    /// Remove and return the requests not answered within `timeout` after their last
+    /// retransmission
+    pub(crate) fn expire(&mut self, now: Instant) -> Vec<(IpAddr, u32, PendingRequest<T>)> {
+        let timeout = self.timeout;
+        (self.pending)
+            .extract_if(|_, p| now.saturating_duration_since(p.last_seen) >= timeout)
+            .into_iter()
+            .map(|((peer, id), p)| (peer, id, p))
+            .collect()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.pending.len()
+    }
+}
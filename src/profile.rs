@@ -0,0 +1,203 @@
+//! Parse cost instrumentation
+//!
+//! [`parse_with_metrics`] parses a message like [`parse_snmp_generic_message`], and reports the
+//! cost of the parsing (input size, duration, number of variable bindings) to a
+//! [`ParseMetricsSink`]. This can be used to monitor performance regressions, or messages
+//! crafted to slow down sensors:
+//!
+//! ```rust
+//! use snmp_parser::profile::{parse_with_metrics, ParseMetrics};
+//!
+//! # static SNMPV1_REQ: &[u8] = include_bytes!("../assets/snmpv1_req.bin");
+//! # fn main() {
+//! let mut slowest = None;
+//! let mut sink = |metrics: &ParseMetrics| {
+//!     if slowest.map_or(true, |d| metrics.duration > d) {
+//!         slowest = Some(metrics.duration);
+//!     }
+//! };
+//! let (_, msg) = parse_with_metrics(SNMPV1_REQ, &mut sink).expect("parsing failed");
+//! assert!(slowest.is_some());
+//! # }
+//! ```
+//!
+//! With the `alloc-stats` feature, installing [`CountingAllocator`] as the global allocator also
+//! reports the number of allocations made while parsing each message.
+
+use crate::error::SnmpError;
+use crate::generic::{parse_snmp_generic_message, SnmpGenericMessage};
+use nom::IResult;
+use std::time::{Duration, Instant};
+
+/// Cost of parsing a message
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseMetrics {
+    /// Number of bytes read (or the input length, if parsing failed)
+    pub bytes: usize,
+    pub duration: Duration,
+    /// Number of variable bindings (0 if parsing failed, or if the PDU is encrypted)
+    pub varbinds: usize,
+    pub success: bool,
+    /// Allocations made while parsing, if [`CountingAllocator`] is the global allocator
+    pub allocations: Option<AllocStats>,
+}
+
+/// Number and total size of allocations
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AllocStats {
+    pub count: u64,
+    pub bytes: u64,
+}
+
+/// Receiver of the metrics of [`parse_with_metrics`]
+///
+/// This is implemented for closures taking a [`ParseMetrics`].
+pub trait ParseMetricsSink {
+    fn record(&mut self, metrics: &ParseMetrics);
+}
+
+impl<F> ParseMetricsSink for F
+where
+    F: FnMut(&ParseMetrics),
+{
+    fn record(&mut self, metrics: &ParseMetrics) {
+        self(metrics)
+    }
+}
+
+/// Parse a SNMP message, of any supported version, and report the cost of parsing to `sink`
+///
+/// See [`parse_snmp_generic_message`]. Metrics are reported for both successes and failures.
+pub fn parse_with_metrics<'a, S>(
+    i: &'a [u8],
+    sink: &mut S,
+) -> IResult<&'a [u8], SnmpGenericMessage<'a>, SnmpError>
+where
+    S: ParseMetricsSink + ?Sized,
+{
+    let allocs_before = thread_alloc_stats();
+    let start = Instant::now();
+    let res = parse_snmp_generic_message(i);
+    let duration = start.elapsed();
+    let allocations = match (allocs_before, thread_alloc_stats()) {
+        (Some(before), Some(after)) => Some(AllocStats {
+            count: after.count - before.count,
+            bytes: after.bytes - before.bytes,
+        }),
+        _ => None,
+    };
+    let (bytes, varbinds) = match res {
+        Ok((rem, ref msg)) => (
+            i.len() - rem.len(),
+            msg.pdu().map_or(0, |pdu| pdu.vars_iter().count()),
+        ),
+        Err(_) => (i.len(), 0),
+    };
+    snmp_event!(bytes, ?duration, varbinds, "parse metrics");
+    sink.record(&ParseMetrics {
+        bytes,
+        duration,
+        varbinds,
+        success: res.is_ok(),
+        allocations,
+    });
+    res
+}
+
+#[cfg(not(feature = "alloc-stats"))]
+fn thread_alloc_stats() -> Option<AllocStats> {
+    None
+}
+
+#[cfg(feature = "alloc-stats")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc-stats")))]
+pub use self::counting::*;
+
+#[cfg(feature = "alloc-stats")]
+mod counting {
+    use super::AllocStats;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    thread_local! {
+        static THREAD_STATS: Cell<AllocStats> = const {
+            Cell::new(AllocStats { count: 0, bytes: 0 })
+        };
+    }
+
+    /// Set when `CountingAllocator` is used
+    static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+    /// A global allocator counting the allocations of each thread
+    ///
+    /// Allocations are delegated to the wrapped allocator (the system allocator by default):
+    ///
+    /// ```rust
+    /// use snmp_parser::profile::CountingAllocator;
+    ///
+    /// #[global_allocator]
+    /// static ALLOCATOR: CountingAllocator = CountingAllocator::new();
+    /// # fn main() {}
+    /// ```
+    #[derive(Debug, Default)]
+    pub struct CountingAllocator<A = System> {
+        inner: A,
+    }
+
+    impl CountingAllocator<System> {
+        pub const fn new() -> Self {
+            CountingAllocator { inner: System }
+        }
+    }
+
+    impl<A> CountingAllocator<A> {
+        pub const fn with_allocator(inner: A) -> Self {
+            CountingAllocator { inner }
+        }
+    }
+
+    fn count(size: usize) {
+        if !INSTALLED.load(Ordering::Relaxed) {
+            INSTALLED.store(true, Ordering::Relaxed);
+        }
+        // the counters cannot be updated while the thread is being destroyed
+        let _ = THREAD_STATS.try_with(|stats| {
+            let s = stats.get();
+            stats.set(AllocStats {
+                count: s.count + 1,
+                bytes: s.bytes + size as u64,
+            });
+        });
+    }
+
+    #[allow(unsafe_code)]
+    unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            count(layout.size());
+            self.inner.alloc(layout)
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            count(layout.size());
+            self.inner.alloc_zeroed(layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            count(new_size);
+            self.inner.realloc(ptr, layout, new_size)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            self.inner.dealloc(ptr, layout)
+        }
+    }
+
+    /// Return the allocations made by the current thread, if `CountingAllocator` is used
+    pub(super) fn thread_alloc_stats() -> Option<AllocStats> {
+        if !INSTALLED.load(Ordering::Relaxed) {
+            return None;
+        }
+        THREAD_STATS.try_with(Cell::get).ok()
+    }
+}
@@ -0,0 +1,150 @@
+//! Stored messages, parsed on demand
+//!
+//! Parsed messages borrow the input buffer, which makes it difficult to store them, for ex. in
+//! a table of pending requests: the buffer and the message would have to be stored together,
+//! in a self-referential structure.
+//!
+//! [`RawSnmpMessage`] owns the encoded message instead (in a `Vec<u8>`, or any other buffer
+//! type, for ex. `bytes::Bytes` or `Arc<[u8]>`). The main fields are read when it is created,
+//! and stored as values or offsets in the buffer. Other fields are read when required, from
+//! views borrowing the stored message:
+//!
+//! ```rust
+//! # #[cfg(feature = "v1")]
+//! # fn main() {
+//! use snmp_parser::raw::RawSnmpMessage;
+//! use snmp_parser::PduType;
+//!
+//! # let datagram = include_bytes!("../assets/snmpv1_req.bin");
+//! let stored = RawSnmpMessage::new(datagram.to_vec()).expect("invalid message");
+//! assert_eq!(stored.pdu_type(), Some(PduType::GetRequest));
+//! assert_eq!(stored.community(), Some(&b"public"[..]));
+//! // later, when the content of the message is required
+//! let msg = stored.parse().expect("parsing failed");
+//! # }
+//! # #[cfg(not(feature = "v1"))]
+//! # fn main() {}
+//! ```
+
+use crate::error::{finish, SnmpError};
+use crate::generic::SnmpGenericMessage;
+use crate::inspect::{inspect_snmp_message, RawVarBinds, SnmpMessageHeader};
+use crate::snmp::PduType;
+#[cfg(feature = "v3")]
+use crate::snmpv3::HeaderData;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// An encoded SNMP message, with its main fields
+///
+/// The header is read using [`inspect_snmp_message`], so messages of all the versions supported
+/// by [`inspect_snmp_message`] can be stored, even if the corresponding parsers are disabled.
+/// This excludes messages using version 2 (party-based SNMPv2 and SNMPv2u), which are rejected.
+#[derive(Clone, Debug)]
+pub struct RawSnmpMessage<B: AsRef<[u8]> = Vec<u8>> {
+    data: B,
+    /// Length of the message (the buffer may contain trailing bytes)
+    len: usize,
+    version: u32,
+    community: Option<Range<usize>>,
+    #[cfg(feature = "v3")]
+    header_data: Option<HeaderData>,
+    pdu_type: Option<PduType>,
+    request_id: Option<u32>,
+    varbinds: Range<usize>,
+}
+
+impl<B: AsRef<[u8]>> RawSnmpMessage<B> {
+    /// Read the header of the message in `data`, and store both
+    ///
+    /// Bytes after the end of the message are ignored.
+    pub fn new(data: B) -> Result<Self, SnmpError> {
+        let buf = data.as_ref();
+        let (hdr, len) = finish(inspect_snmp_message(buf), buf)?;
+        let community = hdr.community.map(|c| offsets(buf, c));
+        let varbinds = offsets(buf, hdr.varbinds);
+        Ok(RawSnmpMessage {
+            len,
+            version: hdr.version,
+            community,
+            #[cfg(feature = "v3")]
+            header_data: hdr.header_data,
+            pdu_type: hdr.pdu_type,
+            request_id: hdr.request_id,
+            varbinds,
+            data,
+        })
+    }
+
+    /// Return the encoded message
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data.as_ref()[..self.len]
+    }
+
+    /// Return the buffer
+    pub fn into_inner(self) -> B {
+        self.data
+    }
+
+    /// Version, as raw-encoded: 0 for SNMPv1, 1 for SNMPv2c, 3 for SNMPv3
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Community, for SNMPv1 and SNMPv2c
+    pub fn community(&self) -> Option<&[u8]> {
+        let range = self.community.clone()?;
+        Some(&self.data.as_ref()[range])
+    }
+
+    /// SNMPv3 header data
+    #[cfg(feature = "v3")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "v3")))]
+    pub fn header_data(&self) -> Option<&HeaderData> {
+        self.header_data.as_ref()
+    }
+
+    /// PDU type, or `None` if the PDU is encrypted
+    pub fn pdu_type(&self) -> Option<PduType> {
+        self.pdu_type
+    }
+
+    /// Request ID, or `None` for SNMPv1 traps and encrypted PDUs
+    pub fn request_id(&self) -> Option<u32> {
+        self.request_id
+    }
+
+    /// Return the header, as returned by [`inspect_snmp_message`]
+    pub fn header(&self) -> SnmpMessageHeader<'_> {
+        SnmpMessageHeader {
+            version: self.version,
+            community: self.community(),
+            #[cfg(feature = "v3")]
+            header_data: self.header_data,
+            pdu_type: self.pdu_type,
+            request_id: self.request_id,
+            varbinds: &self.data.as_ref()[self.varbinds.clone()],
+        }
+    }
+
+    /// Return a lazy iterator over the variable bindings
+    ///
+    /// The iterator is empty if the PDU is encrypted.
+    pub fn varbinds(&self) -> RawVarBinds<'_> {
+        self.header().varbinds()
+    }
+
+    /// Parse the complete message
+    ///
+    /// The message is parsed again at each call.
+    pub fn parse(&self) -> Result<SnmpGenericMessage<'_>, SnmpError> {
+        SnmpGenericMessage::parse(self.as_bytes()).map(|(msg, _)| msg)
+    }
+}
+
+/// Return the position of `slice` in `buf`
+fn offsets(buf: &[u8], slice: &[u8]) -> Range<usize> {
+    // the slices returned by the inspection are always parts of the buffer
+    let start = (slice.as_ptr() as usize).saturating_sub(buf.as_ptr() as usize);
+    start..start + slice.len()
+}
@@ -0,0 +1,519 @@
+//! Rule matching over parsed messages
+//!
+//! This module implements a small rule language, to express policies on SNMP messages without
+//! writing code. Rules are compiled once using [`Rule::parse`], and evaluated against
+//! [`SnmpGenericMessage`] values.
+//!
+//! A rule is a boolean expression made of comparisons, combined with `&&`, `||`, `!` and
+//! parentheses. A comparison has the form `field operator value`.
+//!
+//! | Field          | Value                                  | Operators                     |
+//! |----------------|----------------------------------------|-------------------------------|
+//! | `version`      | number (raw-encoded: 0, 1, 2 or 3)     | `==` `!=` `<` `<=` `>` `>=`   |
+//! | `community`    | string                                 | `==` `!=` `startswith` `contains` |
+//! | `user`         | string (USM or SNMPv2u user name)      | `==` `!=` `startswith` `contains` |
+//! | `pdu_type`     | PDU type name or number                | `==` `!=`                     |
+//! | `request_id`   | number                                 | `==` `!=` `<` `<=` `>` `>=`   |
+//! | `error_status` | number                                 | `==` `!=` `<` `<=` `>` `>=`   |
+//! | `varbinds`     | number (count of variable bindings)    | `==` `!=` `<` `<=` `>` `>=`   |
+//! | `oid`          | dotted OID                             | `==` `!=` `startswith`        |
+//!
+//! PDU type names are `get`, `getnext`, `response`, `set`, `trap`, `getbulk`, `inform`,
+//! `trapv2` and `report`. Strings can be bare words or quoted with `"` (use `\"` and `\\` to
+//! escape).
+//!
+//! A comparison on a field that is not present in the message (for example, `community` for a
+//! SNMPv3 message, or `request_id` in an encrypted message) is always false, whatever the
+//! operator. Comparisons on `oid` are true if they are true for any variable binding, except
+//! `!=` which is true if no variable has this OID.
+//!
+//! Example:
+//!
+//! ```rust
+//! use snmp_parser::rules::{Rule, RuleSet};
+//!
+//! let rule = Rule::parse(r#"pdu_type==set && oid startswith 1.3.6.1.4.1.9 && community!="expected""#)
+//!     .expect("invalid rule");
+//! let mut rules = RuleSet::new();
+//! rules.add(1, rule);
+//! ```
+
+use crate::generic::SnmpGenericMessage;
+use crate::snmp::{PduType, SnmpPdu};
+#[cfg(feature = "v3")]
+use crate::snmpv3::SecurityParameters;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use asn1_rs::Oid;
+use core::fmt;
+use core::str::FromStr;
+
+/// Maximum nesting of `!` operators and parentheses in a rule
+///
+/// Rules are compiled recursively, so deeper rules are rejected to protect the stack. The operands
+/// of chains of `&&` and `||` operators are stored flat, so long chains are not nested.
+pub const MAX_RULE_DEPTH: usize = 64;
+
+/// An error encountered while compiling a rule
+#[derive(Debug, PartialEq, Eq)]
+pub struct RuleError {
+    /// Offset of the error in the rule text, in bytes
+    pub offset: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid rule at offset {}: {}", self.offset, self.reason)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RuleError {}
+
+impl RuleError {
+    fn new(offset: usize, reason: impl Into<String>) -> Self {
+        RuleError {
+            offset,
+            reason: reason.into(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Version,
+    Community,
+    User,
+    PduType,
+    RequestId,
+    ErrorStatus,
+    VarBinds,
+    Oid,
+}
+
+impl Field {
+    fn from_name(s: &str) -> Option<Field> {
+        let f = match s {
+            "version" => Field::Version,
+            "community" => Field::Community,
+            "user" => Field::User,
+            "pdu_type" => Field::PduType,
+            "request_id" => Field::RequestId,
+            "error_status" => Field::ErrorStatus,
+            "varbinds" => Field::VarBinds,
+            "oid" => Field::Oid,
+            _ => return None,
+        };
+        Some(f)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    StartsWith,
+    Contains,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Number(u64),
+    Str(String),
+    Oid(Oid<'static>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    /// Operands of a chain of `&&` operators
+    And(Vec<Expr>),
+    /// Operands of a chain of `||` operators
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Cmp(Field, Op, Value),
+}
+
+/// A compiled rule
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    expr: Expr,
+}
+
+impl Rule {
+    /// Compile a rule from its textual representation
+    pub fn parse(s: &str) -> Result<Rule, RuleError> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            pos: 0,
+            end: s.len(),
+            depth: 0,
+        };
+        let expr = parser.parse_or()?;
+        if let Some((offset, _)) = parser.peek() {
+            return Err(RuleError::new(offset, "unexpected trailing input"));
+        }
+        Ok(Rule { expr })
+    }
+
+    /// Evaluate the rule against a message
+    pub fn matches(&self, msg: &SnmpGenericMessage) -> bool {
+        eval(&self.expr, msg)
+    }
+}
+
+impl FromStr for Rule {
+    type Err = RuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Rule::parse(s)
+    }
+}
+
+/// A list of rules, each associated with an identifier
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RuleSet {
+    rules: Vec<(u32, Rule)>,
+}
+
+impl RuleSet {
+    pub fn new() -> Self {
+        RuleSet::default()
+    }
+
+    pub fn add(&mut self, id: u32, rule: Rule) {
+        self.rules.push((id, rule));
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Evaluate all rules against a message, and return the identifiers of the matching rules,
+    /// in insertion order
+    pub fn matches(&self, msg: &SnmpGenericMessage) -> Vec<u32> {
+        self.rules
+            .iter()
+            .filter(|(_, rule)| rule.matches(msg))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+    Op(Op),
+    Word(String),
+    Quoted(String),
+}
+
+fn tokenize(s: &str) -> Result<Vec<(usize, Token)>, RuleError> {
+    let bytes = s.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        let start = i;
+        let two = if i + 1 < bytes.len() {
+            &bytes[i..i + 2]
+        } else {
+            &bytes[i..]
+        };
+        let tok = match (c, two) {
+            (c, _) if c.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            (_, b"&&") => Token::And,
+            (_, b"||") => Token::Or,
+            (_, b"==") => Token::Op(Op::Eq),
+            (_, b"!=") => Token::Op(Op::Ne),
+            (_, b"<=") => Token::Op(Op::Le),
+            (_, b">=") => Token::Op(Op::Ge),
+            (b'<', _) => Token::Op(Op::Lt),
+            (b'>', _) => Token::Op(Op::Gt),
+            (b'!', _) => Token::Not,
+            (b'(', _) => Token::LParen,
+            (b')', _) => Token::RParen,
+            (b'"', _) => {
+                let mut value = String::new();
+                let mut chars = s[i + 1..].char_indices();
+                loop {
+                    match chars.next() {
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, c @ ('"' | '\\'))) => value.push(c),
+                            _ => return Err(RuleError::new(start, "invalid escape in string")),
+                        },
+                        Some((n, '"')) => {
+                            i += n + 2;
+                            break;
+                        }
+                        Some((_, c)) => value.push(c),
+                        None => return Err(RuleError::new(start, "unterminated string")),
+                    }
+                }
+                tokens.push((start, Token::Quoted(value)));
+                continue;
+            }
+            (c, _) if c.is_ascii_alphanumeric() || c == b'_' || c == b'.' || c == b'-' => {
+                let len = bytes[i..]
+                    .iter()
+                    .take_while(|&&c| {
+                        c.is_ascii_alphanumeric() || c == b'_' || c == b'.' || c == b'-'
+                    })
+                    .count();
+                i += len;
+                let word = &s[start..i];
+                let tok = match word {
+                    "startswith" => Token::Op(Op::StartsWith),
+                    "contains" => Token::Op(Op::Contains),
+                    _ => Token::Word(word.to_string()),
+                };
+                tokens.push((start, tok));
+                continue;
+            }
+            _ => return Err(RuleError::new(start, "unexpected character")),
+        };
+        i += match tok {
+            Token::And | Token::Or | Token::Op(Op::Eq | Op::Ne | Op::Le | Op::Ge) => 2,
+            _ => 1,
+        };
+        tokens.push((start, tok));
+    }
+    Ok(tokens)
+}
+
+struct Parser<'t> {
+    tokens: &'t [(usize, Token)],
+    pos: usize,
+    end: usize,
+    /// Current nesting of `!` operators and parentheses
+    depth: usize,
+}
+
+impl<'t> Parser<'t> {
+    fn peek(&self) -> Option<(usize, &'t Token)> {
+        self.tokens.get(self.pos).map(|(o, t)| (*o, t))
+    }
+
+    fn next(&mut self) -> Result<(usize, &'t Token), RuleError> {
+        let t = self
+            .peek()
+            .ok_or_else(|| RuleError::new(self.end, "unexpected end of rule"))?;
+        self.pos += 1;
+        Ok(t)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, RuleError> {
+        let mut operands = vec![self.parse_and()?];
+        while let Some((_, Token::Or)) = self.peek() {
+            self.pos += 1;
+            operands.push(self.parse_and()?);
+        }
+        Ok(flatten(operands, Expr::Or))
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, RuleError> {
+        let mut operands = vec![self.parse_unary()?];
+        while let Some((_, Token::And)) = self.peek() {
+            self.pos += 1;
+            operands.push(self.parse_unary()?);
+        }
+        Ok(flatten(operands, Expr::And))
+    }
+
+    /// Parse a nested expression with `f`, checking the nesting depth
+    fn nested<T>(
+        &mut self,
+        offset: usize,
+        f: impl FnOnce(&mut Self) -> Result<T, RuleError>,
+    ) -> Result<T, RuleError> {
+        if self.depth >= MAX_RULE_DEPTH {
+            return Err(RuleError::new(offset, "rule nested too deeply"));
+        }
+        self.depth += 1;
+        let res = f(self);
+        self.depth -= 1;
+        res
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, RuleError> {
+        match self.next()? {
+            (offset, Token::Not) => {
+                let expr = self.nested(offset, Self::parse_unary)?;
+                Ok(Expr::Not(Box::new(expr)))
+            }
+            (offset, Token::LParen) => {
+                let expr = self.nested(offset, Self::parse_or)?;
+                match self.next() {
+                    Ok((_, Token::RParen)) => Ok(expr),
+                    _ => Err(RuleError::new(offset, "unbalanced parenthesis")),
+                }
+            }
+            (offset, Token::Word(name)) => {
+                let field = Field::from_name(name)
+                    .ok_or_else(|| RuleError::new(offset, format!("unknown field '{}'", name)))?;
+                let op = match self.next()? {
+                    (_, Token::Op(op)) => *op,
+                    (offset, _) => return Err(RuleError::new(offset, "expected operator")),
+                };
+                let (offset, value) = self.next()?;
+                let value = parse_value(field, op, value)
+                    .map_err(|reason| RuleError::new(offset, reason))?;
+                Ok(Expr::Cmp(field, op, value))
+            }
+            (offset, _) => Err(RuleError::new(offset, "expected field name")),
+        }
+    }
+}
+
+/// Return the single operand, or build the chain of operators with `f`
+fn flatten(mut operands: Vec<Expr>, f: fn(Vec<Expr>) -> Expr) -> Expr {
+    if operands.len() == 1 {
+        operands.remove(0)
+    } else {
+        f(operands)
+    }
+}
+
+fn parse_value(field: Field, op: Op, token: &Token) -> Result<Value, String> {
+    let s = match token {
+        Token::Word(s) | Token::Quoted(s) => s,
+        _ => return Err("expected value".to_string()),
+    };
+    let allowed = match field {
+        Field::Community | Field::User => {
+            matches!(op, Op::Eq | Op::Ne | Op::StartsWith | Op::Contains)
+        }
+        Field::PduType => matches!(op, Op::Eq | Op::Ne),
+        Field::Oid => matches!(op, Op::Eq | Op::Ne | Op::StartsWith),
+        _ => !matches!(op, Op::StartsWith | Op::Contains),
+    };
+    if !allowed {
+        return Err("operator not supported for this field".to_string());
+    }
+    match field {
+        Field::Community | Field::User => Ok(Value::Str(s.clone())),
+        Field::Oid => Oid::from_str(s)
+            .map(Value::Oid)
+            .map_err(|_| format!("invalid OID '{}'", s)),
+        Field::PduType => {
+            let pdu_type = match s.as_str() {
+                "get" => PduType::GetRequest,
+                "getnext" => PduType::GetNextRequest,
+                "response" => PduType::Response,
+                "set" => PduType::SetRequest,
+                "trap" => PduType::TrapV1,
+                "getbulk" => PduType::GetBulkRequest,
+                "inform" => PduType::InformRequest,
+                "trapv2" => PduType::TrapV2,
+                "report" => PduType::Report,
+                _ => s
+                    .parse::<u32>()
+                    .map(PduType)
+                    .map_err(|_| format!("unknown PDU type '{}'", s))?,
+            };
+            Ok(Value::Number(u64::from(pdu_type.0)))
+        }
+        _ => s
+            .parse::<u64>()
+            .map(Value::Number)
+            .map_err(|_| format!("invalid number '{}'", s)),
+    }
+}
+
+fn eval(expr: &Expr, msg: &SnmpGenericMessage) -> bool {
+    match expr {
+        Expr::And(operands) => operands.iter().all(|e| eval(e, msg)),
+        Expr::Or(operands) => operands.iter().any(|e| eval(e, msg)),
+        Expr::Not(e) => !eval(e, msg),
+        Expr::Cmp(field, op, value) => eval_cmp(*field, *op, value, msg),
+    }
+}
+
+fn eval_cmp(field: Field, op: Op, value: &Value, msg: &SnmpGenericMessage) -> bool {
+    let pdu = msg.pdu();
+    match (field, value) {
+        (Field::Community, Value::Str(expected)) => msg
+            .community()
+            .map(|c| cmp_str(op, c, expected))
+            .unwrap_or(false),
+        (Field::User, Value::Str(_expected)) => match msg {
+            #[cfg(feature = "v3")]
+            SnmpGenericMessage::V3(m) => match m.security_params {
+                SecurityParameters::USM(ref usm) => cmp_str(op, &usm.msg_user_name, _expected),
+                _ => false,
+            },
+            #[cfg(feature = "v2u")]
+            SnmpGenericMessage::V2u(m) => core::str::from_utf8(m.parameters.user_name)
+                .map(|user| cmp_str(op, user, _expected))
+                .unwrap_or(false),
+            _ => false,
+        },
+        (Field::Oid, Value::Oid(expected)) => match pdu {
+            Some(pdu) => match op {
+                Op::Eq => pdu.vars_iter().any(|v| &v.oid == expected),
+                Op::Ne => pdu.vars_iter().all(|v| &v.oid != expected),
+                Op::StartsWith => pdu.vars_iter().any(|v| v.oid.starts_with(expected)),
+                _ => false,
+            },
+            None => false,
+        },
+        (_, Value::Number(expected)) => {
+            let actual = match field {
+                Field::Version => Some(msg.version()),
+                Field::PduType => pdu.map(|p| p.pdu_type().0),
+                Field::RequestId => pdu.and_then(|p| p.request_id()),
+                Field::ErrorStatus => match pdu {
+                    Some(SnmpPdu::Generic(p)) => Some(p.err.0),
+                    _ => None,
+                },
+                Field::VarBinds => pdu.map(|p| p.vars_iter().count() as u32),
+                _ => None,
+            };
+            actual
+                .map(|actual| cmp_num(op, u64::from(actual), *expected))
+                .unwrap_or(false)
+        }
+        _ => false,
+    }
+}
+
+fn cmp_str(op: Op, actual: &str, expected: &str) -> bool {
+    match op {
+        Op::Eq => actual == expected,
+        Op::Ne => actual != expected,
+        Op::StartsWith => actual.starts_with(expected),
+        Op::Contains => actual.contains(expected),
+        _ => false,
+    }
+}
+
+fn cmp_num(op: Op, actual: u64, expected: u64) -> bool {
+    match op {
+        Op::Eq => actual == expected,
+        Op::Ne => actual != expected,
+        Op::Lt => actual < expected,
+        Op::Le => actual <= expected,
+        Op::Gt => actual > expected,
+        Op::Ge => actual >= expected,
+        _ => false,
+    }
+}
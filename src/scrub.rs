@@ -0,0 +1,193 @@
+//! Message anonymization
+//!
+//! [`scrub_message`] rewrites a message to remove credentials and identifying information, so
+//! captures can be shared (for ex. in bug reports):
+//!   - community strings (SNMPv1 and SNMPv2c)
+//!   - user names, engine IDs and authentication parameters (SNMPv3 with USM, and SNMPv2u)
+//!   - context engine IDs and context names (SNMPv3), and context selectors (SNMPv2u)
+//!   - agent addresses of SNMPv1 traps, replaced by `0.0.0.0`
+//!   - the values of selected variables (by default `sysContact`, `sysName` and `sysLocation`)
+//!
+//! Replaced strings keep their length, so the message length does not change, unless the
+//! original message used a non-canonical encoding (it is re-encoded, see
+//! [`encode`](crate::encode)).
+//!
+//! The following fields are not modified:
+//!   - encrypted data (SNMPv3 and SNMPv2u)
+//!   - party-based SNMPv2 messages: the party OIDs, the context and the PDU (which can be
+//!     encrypted) are copied as read
+//!   - OIDs, including the enterprise OID of SNMPv1 traps
+//!
+//! ```rust
+//! # #[cfg(feature = "v2c")]
+//! # fn main() {
+//! use snmp_parser::scrub::{scrub_message, ScrubConfig};
+//! use snmp_parser::parse_snmp_v2c;
+//!
+//! static SNMPV2_GET: &[u8] = include_bytes!("../assets/snmpv2c-get-response.bin");
+//!
+//! let bytes = scrub_message(SNMPV2_GET, &ScrubConfig::default()).expect("parsing failed");
+//! let (_, msg) = parse_snmp_v2c(&bytes).expect("parsing failed");
+//! assert_eq!(msg.community, "xxxxxx");
+//! # }
+//! # #[cfg(not(feature = "v2c"))]
+//! # fn main() {}
+//! ```
+
+use crate::encode::SnmpEncode;
+use crate::error::SnmpError;
+use crate::generic::SnmpGenericMessage;
+use crate::snmp::{NetworkAddress, ObjectSyntax, SnmpPdu, VarBindValue};
+#[cfg(feature = "v2u")]
+use crate::snmpv2u::SnmpV2uData;
+#[cfg(feature = "v3")]
+use crate::snmpv3::{ScopedPduData, SecurityParameters};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use asn1_rs::{oid, Oid};
+#[cfg(not(feature = "std"))]
+use core::net::Ipv4Addr;
+#[cfg(feature = "std")]
+use std::net::Ipv4Addr;
+
+/// `sysContact.0`
+#[rustfmt::skip]
+pub const SYS_CONTACT_OID: Oid<'static> = oid!(1.3.6.1.2.1.1.4.0);
+
+/// `sysName.0`
+#[rustfmt::skip]
+pub const SYS_NAME_OID: Oid<'static> = oid!(1.3.6.1.2.1.1.5.0);
+
+/// `sysLocation.0`
+#[rustfmt::skip]
+pub const SYS_LOCATION_OID: Oid<'static> = oid!(1.3.6.1.2.1.1.6.0);
+
+/// Configuration of [`scrub_message`]
+#[derive(Clone, Debug)]
+pub struct ScrubConfig {
+    /// Variables to scrub: all variables in the subtree of one of these OIDs (including the OID
+    /// itself) are scrubbed
+    pub oids: Vec<Oid<'static>>,
+    /// Character used to replace strings
+    pub placeholder: Placeholder,
+}
+
+impl Default for ScrubConfig {
+    fn default() -> Self {
+        ScrubConfig {
+            oids: vec![SYS_CONTACT_OID, SYS_NAME_OID, SYS_LOCATION_OID],
+            placeholder: Placeholder::default(),
+        }
+    }
+}
+
+/// ASCII character used to replace strings (`x` by default)
+///
+/// Other characters are rejected, since their UTF-8 encoding would change the length of the
+/// strings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Placeholder(u8);
+
+impl Placeholder {
+    /// Return a placeholder, or `None` if `byte` is not an ASCII character
+    pub const fn new(byte: u8) -> Option<Self> {
+        if byte.is_ascii() {
+            Some(Placeholder(byte))
+        } else {
+            None
+        }
+    }
+
+    pub const fn get(self) -> u8 {
+        self.0
+    }
+}
+
+impl Default for Placeholder {
+    fn default() -> Self {
+        Placeholder(b'x')
+    }
+}
+
+/// Parse a SNMP message (of any supported version), scrub it and encode it again
+///
+/// String values (OCTET STRING and Opaque) of the scrubbed variables are replaced by
+/// placeholders of the same length. Other values are replaced by NULL. Authentication
+/// parameters are replaced by zeros, since they could be used to recover the password.
+///
+/// # Errors
+///
+/// If the message cannot be parsed, the parsing error is returned.
+pub fn scrub_message(bytes: &[u8], config: &ScrubConfig) -> Result<Vec<u8>, SnmpError> {
+    let placeholder = config.placeholder.get();
+    // replacement strings borrow from these buffers, which are longer than any field
+    let fill = vec![placeholder; bytes.len()];
+    #[cfg(any(feature = "v2u", feature = "v3"))]
+    let zeros = vec![0u8; bytes.len()];
+    let (mut msg, _) = SnmpGenericMessage::parse(bytes)?;
+    match msg {
+        SnmpGenericMessage::V1(ref mut msg) | SnmpGenericMessage::V2(ref mut msg) => {
+            scrub_string(&mut msg.community, placeholder);
+            scrub_pdu(&mut msg.pdu, &fill, config);
+        }
+        // privData is encoded from its raw content
+        #[cfg(feature = "v2p")]
+        SnmpGenericMessage::V2p(_) => (),
+        #[cfg(feature = "v2u")]
+        SnmpGenericMessage::V2u(ref mut msg) => {
+            let params = &mut msg.parameters;
+            params.agent_id = &fill[..params.agent_id.len()];
+            params.user_name = &fill[..params.user_name.len()];
+            params.auth_digest = &zeros[..params.auth_digest.len()];
+            params.context_selector = &fill[..params.context_selector.len()];
+            if let SnmpV2uData::Plaintext(ref mut pdu) = msg.data {
+                scrub_pdu(pdu, &fill, config);
+            }
+        }
+        #[cfg(feature = "v3")]
+        SnmpGenericMessage::V3(ref mut msg) => {
+            match msg.security_params {
+                SecurityParameters::USM(ref mut usm) => {
+                    usm.msg_authoritative_engine_id =
+                        &fill[..usm.msg_authoritative_engine_id.len()];
+                    scrub_string(&mut usm.msg_user_name, placeholder);
+                    usm.msg_authentication_parameters =
+                        &zeros[..usm.msg_authentication_parameters.len()];
+                }
+                SecurityParameters::Raw(ref mut raw) => *raw = &fill[..raw.len()],
+            }
+            if let ScopedPduData::Plaintext(ref mut scoped_pdu) = msg.data {
+                scoped_pdu.ctx_engine_id = &fill[..scoped_pdu.ctx_engine_id.len()];
+                scoped_pdu.ctx_engine_name = &fill[..scoped_pdu.ctx_engine_name.len()];
+                scrub_pdu(&mut scoped_pdu.data, &fill, config);
+            }
+        }
+    }
+    Ok(msg.encode())
+}
+
+fn scrub_string(s: &mut String, placeholder: u8) {
+    *s = (0..s.len()).map(|_| char::from(placeholder)).collect();
+}
+
+fn scrub_pdu<'a>(pdu: &mut SnmpPdu<'a>, fill: &'a [u8], config: &ScrubConfig) {
+    if let SnmpPdu::TrapV1(ref mut trap) = pdu {
+        trap.agent_addr = NetworkAddress::IPv4(Ipv4Addr::UNSPECIFIED);
+    }
+    for var in pdu.var_list_mut() {
+        if !config.oids.iter().any(|prefix| var.oid.starts_with(prefix)) {
+            continue;
+        }
+        match var.val {
+            VarBindValue::Value(ObjectSyntax::String(s)) => {
+                var.val = VarBindValue::Value(ObjectSyntax::String(&fill[..s.len()]))
+            }
+            VarBindValue::Value(ObjectSyntax::Opaque(s)) => {
+                var.val = VarBindValue::Value(ObjectSyntax::Opaque(&fill[..s.len()]))
+            }
+            VarBindValue::Value(_) => var.val = VarBindValue::Unspecified,
+            _ => (),
+        }
+    }
+}
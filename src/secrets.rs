@@ -0,0 +1,75 @@
+//! Erasing secrets from memory
+//!
+//! Most fields of parsed messages borrow from the input buffer, which is owned (and should be
+//! cleared) by the application. Communities and USM user names are copied when parsing: this
+//! module implements [`Zeroize`] for the messages, so these copies can be erased once the message
+//! has been processed:
+//!
+//! ```rust
+//! # #[cfg(feature = "v2c")]
+//! # fn main() {
+//! use snmp_parser::parse_snmp_v2c;
+//! use zeroize::Zeroize;
+//!
+//! # let bytes = include_bytes!("../assets/snmpv2c-get-response.bin");
+//! let (_, mut msg) = parse_snmp_v2c(bytes).expect("parsing failed");
+//! // ... process message
+//! msg.zeroize();
+//! assert!(msg.community.is_empty());
+//! # }
+//! # #[cfg(not(feature = "v2c"))]
+//! # fn main() {}
+//! ```
+//!
+//! No type erases itself on drop, since this would prevent moving fields out of messages. Wrap
+//! messages in [`Zeroizing`](zeroize::Zeroizing) to erase them when they are dropped.
+
+use crate::generic::SnmpGenericMessage;
+use crate::snmp::SnmpMessage;
+#[cfg(feature = "v3")]
+use crate::snmpv3::{SecurityParameters, SnmpV3Message, UsmSecurityParameters};
+use zeroize::Zeroize;
+
+/// Erase the community (the PDU is not modified)
+impl<'a> Zeroize for SnmpMessage<'a> {
+    fn zeroize(&mut self) {
+        self.community.zeroize();
+    }
+}
+
+/// Erase the user name
+///
+/// Other fields borrow from the input buffer, and are not modified.
+#[cfg(feature = "v3")]
+impl<'a> Zeroize for UsmSecurityParameters<'a> {
+    fn zeroize(&mut self) {
+        self.msg_user_name.zeroize();
+    }
+}
+
+/// Erase the USM user name, if any
+#[cfg(feature = "v3")]
+impl<'a> Zeroize for SnmpV3Message<'a> {
+    fn zeroize(&mut self) {
+        if let SecurityParameters::USM(ref mut usm) = self.security_params {
+            usm.zeroize();
+        }
+    }
+}
+
+/// Erase the community (SNMPv1 and SNMPv2c) or the USM user name (SNMPv3)
+///
+/// Party-based and user-based SNMPv2 messages only contain borrowed data, and are not modified.
+impl<'a> Zeroize for SnmpGenericMessage<'a> {
+    fn zeroize(&mut self) {
+        match self {
+            SnmpGenericMessage::V1(msg) | SnmpGenericMessage::V2(msg) => msg.zeroize(),
+            #[cfg(feature = "v2p")]
+            SnmpGenericMessage::V2p(_) => (),
+            #[cfg(feature = "v2u")]
+            SnmpGenericMessage::V2u(_) => (),
+            #[cfg(feature = "v3")]
+            SnmpGenericMessage::V3(msg) => msg.zeroize(),
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Messages backed by reference-counted buffers
+//!
+//! Parsed messages borrow the input buffer, so they cannot outlive it. Servers receiving
+//! datagrams in [`Bytes`] buffers (for ex. with tokio) often need to keep messages longer, for
+//! ex. in a table of pending requests, or to send them to another task.
+//!
+//! [`SharedMessageHeader`] has the same fields as
+//! [`SnmpMessageHeader`](crate::inspect::SnmpMessageHeader), but the community and the variable
+//! bindings are slices of the input [`Bytes`] buffer: they are not copied, and keep the buffer
+//! alive. Values are decoded on demand:
+//!
+//! ```rust
+//! use bytes::Bytes;
+//! use snmp_parser::shared::SharedMessageHeader;
+//!
+//! # let datagram = Bytes::from_static(include_bytes!("../assets/snmpv1_req.bin"));
+//! let (hdr, _) = SharedMessageHeader::parse(&datagram).expect("parsing failed");
+//! drop(datagram);
+//! for var in hdr.varbinds() {
+//!     let var = var.expect("invalid varbind");
+//!     println!("{} = {:?}", var.oid(), var.decode_value());
+//! }
+//! ```
+
+use crate::error::{finish, SnmpError};
+use crate::inspect::{ber_error, inspect_snmp_message};
+use crate::snmp::{check_value_depth, PduType, VarBindValue};
+#[cfg(feature = "v3")]
+use crate::snmpv3::HeaderData;
+use alloc::borrow::Cow;
+use asn1_rs::{Any, FromBer, Oid, Tag};
+use bytes::Bytes;
+use core::convert::TryFrom;
+
+/// Main fields of a SNMP message, backed by the input buffer
+#[derive(Clone, Debug, PartialEq)]
+pub struct SharedMessageHeader {
+    /// Version, as raw-encoded: 0 for SNMPv1, 1 for SNMPv2c, 3 for SNMPv3
+    pub version: u32,
+    /// Community, for SNMPv1 and SNMPv2c
+    pub community: Option<Bytes>,
+    /// SNMPv3 header data
+    #[cfg(feature = "v3")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "v3")))]
+    pub header_data: Option<HeaderData>,
+    /// PDU type, or `None` if the PDU is encrypted
+    pub pdu_type: Option<PduType>,
+    /// Request ID, or `None` for SNMPv1 traps and encrypted PDUs
+    pub request_id: Option<u32>,
+    varbinds: Bytes,
+    message: Bytes,
+}
+
+impl SharedMessageHeader {
+    /// Read the main fields of a SNMPv1, SNMPv2c or SNMPv3 message, returning the header and the
+    /// number of bytes read
+    ///
+    /// The message is checked like [`inspect_snmp_message`] does: messages using version 2
+    /// (party-based SNMPv2 and SNMPv2u) are rejected.
+    pub fn parse(buf: &Bytes) -> Result<(Self, usize), SnmpError> {
+        let (hdr, len) = finish(inspect_snmp_message(buf), buf)?;
+        let header = SharedMessageHeader {
+            version: hdr.version,
+            community: hdr.community.map(|c| subslice(buf, c)),
+            #[cfg(feature = "v3")]
+            header_data: hdr.header_data,
+            pdu_type: hdr.pdu_type,
+            request_id: hdr.request_id,
+            varbinds: subslice(buf, hdr.varbinds),
+            message: buf.slice(..len),
+        };
+        Ok((header, len))
+    }
+
+    /// Return the encoded message
+    pub fn as_bytes(&self) -> &Bytes {
+        &self.message
+    }
+
+    /// Return a lazy iterator over the variable bindings
+    ///
+    /// The iterator is empty if the PDU is encrypted.
+    pub fn varbinds(&self) -> SharedVarBinds {
+        SharedVarBinds {
+            data: self.varbinds.clone(),
+        }
+    }
+
+    /// Count the variable bindings
+    ///
+    /// Invalid variable bindings are counted, and stop the iteration.
+    pub fn varbind_count(&self) -> usize {
+        self.varbinds().count()
+    }
+}
+
+/// A variable binding backed by the input buffer, where the value has not been decoded
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SharedVarBind {
+    oid: Bytes,
+    value: Bytes,
+}
+
+impl SharedVarBind {
+    /// Return the name of the variable
+    pub fn oid(&self) -> Oid<'_> {
+        Oid::new(Cow::Borrowed(&self.oid))
+    }
+
+    /// Return the encoded value (tag, length and content)
+    pub fn raw_value(&self) -> &Bytes {
+        &self.value
+    }
+
+    /// Decode the value
+    pub fn decode_value(&self) -> Result<VarBindValue<'_>, SnmpError> {
+        let (_, any) = Any::from_ber(&self.value).map_err(ber_error)?;
+        VarBindValue::try_from(any).map_err(SnmpError::from)
+    }
+}
+
+/// Lazy iterator over variable bindings backed by the input buffer
+///
+/// If a variable binding cannot be read, the error is returned and the iteration stops.
+#[derive(Clone, Debug)]
+pub struct SharedVarBinds {
+    data: Bytes,
+}
+
+impl Iterator for SharedVarBinds {
+    type Item = Result<SharedVarBind, SnmpError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        match parse_shared_varbind(&self.data) {
+            Ok((len, var)) => {
+                let _ = self.data.split_to(len);
+                Some(Ok(var))
+            }
+            Err(e) => {
+                self.data.clear();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Read a variable binding, returning its length
+fn parse_shared_varbind(buf: &Bytes) -> Result<(usize, SharedVarBind), SnmpError> {
+    let (rem, seq) = Any::from_ber(buf).map_err(ber_error)?;
+    seq.tag().assert_eq(Tag::Sequence)?;
+    let (data, oid) = Oid::from_ber(seq.data).map_err(ber_error)?;
+    check_value_depth(data)?;
+    let (value_rem, _) = Any::from_ber(data).map_err(ber_error)?;
+    let var = SharedVarBind {
+        oid: subslice(buf, oid.as_bytes()),
+        value: subslice(buf, &data[..data.len() - value_rem.len()]),
+    };
+    Ok((buf.len() - rem.len(), var))
+}
+
+/// Return the part of `buf` which is `slice`, without copying
+///
+/// Slices which are not part of `buf` are copied.
+fn subslice(buf: &Bytes, slice: &[u8]) -> Bytes {
+    let start = (slice.as_ptr() as usize).wrapping_sub(buf.as_ptr() as usize);
+    match start.checked_add(slice.len()) {
+        Some(end) if end <= buf.len() => buf.slice(start..end),
+        _ => Bytes::copy_from_slice(slice),
+    }
+}
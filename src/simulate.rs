@@ -0,0 +1,294 @@
+//! Agent simulation
+//!
+//! [`Simulator`] stores a snapshot of the variables of an agent (for ex. recorded while
+//! observing a table walk), and answers Get, GetNext and GetBulk requests like the agent would,
+//! with Response messages which can be encoded using [`SnmpEncode`](crate::encode::SnmpEncode):
+//!
+//! ```rust
+//! use snmp_parser::encode::SnmpEncode;
+//! use snmp_parser::simulate::Simulator;
+//! use snmp_parser::{oid, ObjectSyntax};
+//! # use snmp_parser::*;
+//!
+//! let mut simulator = Simulator::new();
+//! simulator.insert(&oid!(1.3.6.1.2.1.1.5.0), &ObjectSyntax::String(b"router-1"));
+//!
+//! # let request = SnmpMessage {
+//! #     version: 1,
+//! #     community: "public".to_string(),
+//! #     pdu: SnmpPdu::Generic(SnmpGenericPdu {
+//! #         pdu_type: PduType::GetNextRequest,
+//! #         req_id: 1,
+//! #         err: ErrorStatus::NoError,
+//! #         err_index: 0,
+//! #         var: vec![SnmpVariable {
+//! #             oid: oid!(1.3.6.1.2.1.1),
+//! #             val: VarBindValue::Unspecified,
+//! #         }],
+//! #     }),
+//! # };
+//! let response = simulator.respond(&request).expect("not a request");
+//! let bytes = response.encode();
+//! ```
+//!
+//! SetRequests are rejected with a `notWritable` error (`noSuchName` in SNMPv1), since the
+//! snapshot is read-only. The community is not checked.
+
+use crate::coexistence::error_status_v2_to_v1;
+use crate::encode::SnmpEncode;
+use crate::snmp::*;
+use crate::walk::cmp_oid;
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use asn1_rs::{Any, FromBer, Oid};
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::ops::Bound;
+
+/// Encoded OID, ordered by arcs
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct OidKey(Vec<u8>);
+
+impl OidKey {
+    fn as_oid(&self) -> Oid<'_> {
+        Oid::new(Cow::Borrowed(&self.0))
+    }
+}
+
+impl Ord for OidKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_oid(&self.as_oid(), &other.as_oid())
+    }
+}
+
+impl PartialOrd for OidKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Simulated agent, answering requests from a snapshot of variables
+#[derive(Clone, Debug)]
+pub struct Simulator {
+    /// Encoded values
+    values: BTreeMap<OidKey, Vec<u8>>,
+    max_varbinds: usize,
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Simulator {
+            values: BTreeMap::new(),
+            max_varbinds: 1000,
+        }
+    }
+}
+
+impl Simulator {
+    pub fn new() -> Self {
+        Simulator::default()
+    }
+
+    /// Set the maximum number of variables in responses to GetBulk requests (default: 1000)
+    pub fn set_max_varbinds(&mut self, max_varbinds: usize) {
+        self.max_varbinds = max_varbinds;
+    }
+
+    /// Add a variable to the snapshot, replacing any previous value
+    pub fn insert(&mut self, oid: &Oid, value: &ObjectSyntax) {
+        self.values
+            .insert(OidKey(oid.as_bytes().to_vec()), value.encode());
+    }
+
+    /// Add the values of the variables of a PDU (for ex. a response observed during a walk)
+    ///
+    /// NULL values and exceptions are ignored.
+    pub fn insert_vars(&mut self, pdu: &SnmpPdu) {
+        for var in pdu.vars_iter() {
+            if let VarBindValue::Value(ref value) = var.val {
+                if *value != ObjectSyntax::Empty {
+                    self.insert(&var.oid, value);
+                }
+            }
+        }
+    }
+
+    /// Return the value of variable `oid`
+    pub fn get(&self, oid: &Oid) -> Option<ObjectSyntax<'_>> {
+        let bytes = self.values.get(&OidKey(oid.as_bytes().to_vec()))?;
+        decode_value(bytes)
+    }
+
+    /// Iterate over the variables, ordered by OID
+    pub fn iter(&self) -> impl Iterator<Item = (Oid<'_>, ObjectSyntax<'_>)> {
+        self.values
+            .iter()
+            .filter_map(|(k, v)| Some((k.as_oid(), decode_value(v)?)))
+    }
+
+    /// Return the number of variables
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Answer a request message
+    ///
+    /// The response has the version, community and request ID of the request. Returns `None` if
+    /// the message is not a request, or if its PDU is not valid in its version (GetBulk in
+    /// SNMPv1).
+    pub fn respond(&self, request: &SnmpMessage) -> Option<SnmpMessage<'_>> {
+        let v1 = request.version == 0;
+        let pdu = match request.pdu {
+            SnmpPdu::Generic(ref pdu) => self.respond_generic(pdu, v1)?,
+            SnmpPdu::Bulk(ref pdu) if !v1 => self.respond_bulk(pdu),
+            _ => return None,
+        };
+        Some(SnmpMessage {
+            version: request.version,
+            community: request.community.clone(),
+            pdu: SnmpPdu::Generic(pdu),
+        })
+    }
+
+    fn respond_generic(&self, request: &SnmpGenericPdu, v1: bool) -> Option<SnmpGenericPdu<'_>> {
+        let mut response = SnmpGenericPdu {
+            pdu_type: PduType::Response,
+            req_id: request.req_id,
+            err: ErrorStatus::NoError,
+            err_index: 0,
+            var: Vec::with_capacity(request.var.len()),
+        };
+        let (next, err) = match request.pdu_type {
+            PduType::GetRequest => (false, ErrorStatus::NoSuchName),
+            PduType::GetNextRequest => (true, ErrorStatus::NoSuchName),
+            PduType::SetRequest => {
+                // the snapshot is read-only
+                let err = ErrorStatus::NotWritable;
+                response.err = if v1 { error_status_v2_to_v1(err) } else { err };
+                response.err_index = u32::from(!request.var.is_empty());
+                response.var = copy_vars(&request.var);
+                return Some(response);
+            }
+            _ => return None,
+        };
+        for (index, var) in request.var.iter().enumerate() {
+            let result = if next {
+                self.get_next(&var.oid)
+            } else {
+                self.get_exact(&var.oid)
+            };
+            let result = match result {
+                Ok(var) => var,
+                Err(_) if v1 => {
+                    // SNMPv1 has no exceptions: the whole request fails
+                    response.err = err;
+                    response.err_index = u32::try_from(index + 1).unwrap_or(u32::MAX);
+                    response.var = copy_vars(&request.var);
+                    return Some(response);
+                }
+                Err(exception) => SnmpVariable {
+                    oid: var.oid.to_owned(),
+                    val: exception,
+                },
+            };
+            response.var.push(result);
+        }
+        Some(response)
+    }
+
+    fn respond_bulk(&self, request: &SnmpBulkPdu) -> SnmpGenericPdu<'_> {
+        let non_repeaters = (request.non_repeaters as usize).min(request.var.len());
+        let (first, repeaters) = request.var.split_at(non_repeaters);
+        let mut vars: Vec<SnmpVariable> = first.iter().map(|v| self.next_or_end(&v.oid)).collect();
+        let mut last: Vec<Oid> = repeaters.iter().map(|v| v.oid.to_owned()).collect();
+        for _ in 0..request.max_repetitions {
+            if last.is_empty() || vars.len() + last.len() > self.max_varbinds {
+                break;
+            }
+            let mut all_ended = true;
+            for oid in last.iter_mut() {
+                let var = self.next_or_end(oid);
+                if var.val != VarBindValue::EndOfMibView {
+                    all_ended = false;
+                    *oid = var.oid.to_owned();
+                }
+                vars.push(var);
+            }
+            if all_ended {
+                break;
+            }
+        }
+        SnmpGenericPdu {
+            pdu_type: PduType::Response,
+            req_id: request.req_id,
+            err: ErrorStatus::NoError,
+            err_index: 0,
+            var: vars,
+        }
+    }
+
+    fn get_exact(&self, oid: &Oid) -> Result<SnmpVariable<'_>, VarBindValue<'static>> {
+        let key = OidKey(oid.as_bytes().to_vec());
+        if let Some(val) = self.values.get(&key).and_then(|v| decode_value(v)) {
+            return Ok(SnmpVariable {
+                oid: oid.to_owned(),
+                val: VarBindValue::Value(val),
+            });
+        }
+        // the object exists if there is another instance with the same parent
+        let bytes = oid.as_bytes();
+        let parent_len = bytes[..bytes.len().saturating_sub(1)]
+            .iter()
+            .rposition(|b| b & 0x80 == 0)
+            .map_or(0, |pos| pos + 1);
+        if parent_len > 0 {
+            let parent = OidKey(bytes[..parent_len].to_vec());
+            let sibling = self.values.range(parent.clone()..).next();
+            if matches!(sibling, Some((k, _)) if k.0.starts_with(&parent.0)) {
+                return Err(VarBindValue::NoSuchInstance);
+            }
+        }
+        Err(VarBindValue::NoSuchObject)
+    }
+
+    fn get_next(&self, oid: &Oid) -> Result<SnmpVariable<'_>, VarBindValue<'static>> {
+        let key = OidKey(oid.as_bytes().to_vec());
+        self.values
+            .range((Bound::Excluded(key), Bound::Unbounded))
+            .find_map(|(k, v)| {
+                Some(SnmpVariable {
+                    oid: k.as_oid(),
+                    val: VarBindValue::Value(decode_value(v)?),
+                })
+            })
+            .ok_or(VarBindValue::EndOfMibView)
+    }
+
+    fn next_or_end(&self, oid: &Oid) -> SnmpVariable<'_> {
+        self.get_next(oid).unwrap_or_else(|val| SnmpVariable {
+            oid: oid.to_owned(),
+            val,
+        })
+    }
+}
+
+pub(crate) fn decode_value(bytes: &[u8]) -> Option<ObjectSyntax<'_>> {
+    let (_, any) = Any::from_ber(bytes).ok()?;
+    ObjectSyntax::try_from(any).ok()
+}
+
+/// Copy the names of the variables of a request, for error responses (values are replaced by
+/// NULL)
+fn copy_vars<'a>(vars: &[SnmpVariable]) -> Vec<SnmpVariable<'a>> {
+    vars.iter()
+        .map(|var| SnmpVariable {
+            oid: var.oid.to_owned(),
+            val: VarBindValue::Unspecified,
+        })
+        .collect()
+}
@@ -0,0 +1,256 @@
+//! SMUX Parser
+//!
+//! SMUX is defined in [RFC1227](https://tools.ietf.org/html/rfc1227): SNMP MUX Protocol and MIB.
+//!
+//! SMUX is a historic protocol, used between a SNMP agent and peers (for ex. routing daemons)
+//! over TCP port 199. SMUX PDUs are BER-encoded, and SNMPv1 PDUs are exchanged directly between
+//! the agent and the peer.
+//!
+//! <pre>
+//! SMUX-PDUs ::=
+//!     CHOICE {
+//!         open                -- SMUX peer uses
+//!             OpenPDU,        -- immediately after TCP open
+//!
+//!         close               -- either uses immediately before TCP close
+//!             ClosePDU,
+//!
+//!         registerRequest     -- SMUX peer uses
+//!             RReqPDU,
+//!
+//!         registerResponse    -- SNMP agent uses
+//!             RRspPDU,
+//!
+//!             PDUs,           -- note that roles are reversed:
+//!                             -- SNMP agent does get/get-next/set
+//!                             -- SMUX peer does get-response/trap
+//!
+//!         commitOrRollback    -- SNMP agent uses
+//!             SOutPDU
+//!     }
+//! </pre>
+
+use crate::error::{context, cut, resolve_offsets, SnmpError};
+use crate::snmp::{parse_snmp_v1_pdu, SnmpPdu};
+use asn1_rs::{Any, Class, FromBer, Header, Integer, Oid, Tag};
+use core::fmt;
+use nom::{Err, IResult};
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct SmuxCloseReason(pub u32);
+
+#[allow(non_upper_case_globals)]
+impl SmuxCloseReason {
+    pub const GoingDown: SmuxCloseReason = SmuxCloseReason(0);
+    pub const UnsupportedVersion: SmuxCloseReason = SmuxCloseReason(1);
+    pub const PacketFormat: SmuxCloseReason = SmuxCloseReason(2);
+    pub const ProtocolError: SmuxCloseReason = SmuxCloseReason(3);
+    pub const InternalError: SmuxCloseReason = SmuxCloseReason(4);
+    pub const AuthenticationFailure: SmuxCloseReason = SmuxCloseReason(5);
+}
+
+impl fmt::Debug for SmuxCloseReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            0 => f.write_str("goingDown"),
+            1 => f.write_str("unsupportedVersion"),
+            2 => f.write_str("packetFormat"),
+            3 => f.write_str("protocolError"),
+            4 => f.write_str("internalError"),
+            5 => f.write_str("authenticationFailure"),
+            n => f.debug_tuple("SmuxCloseReason").field(&n).finish(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct SmuxOperation(pub u32);
+
+#[allow(non_upper_case_globals)]
+impl SmuxOperation {
+    pub const Delete: SmuxOperation = SmuxOperation(0);
+    pub const ReadOnly: SmuxOperation = SmuxOperation(1);
+    pub const ReadWrite: SmuxOperation = SmuxOperation(2);
+}
+
+impl fmt::Debug for SmuxOperation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            0 => f.write_str("delete"),
+            1 => f.write_str("readOnly"),
+            2 => f.write_str("readWrite"),
+            n => f.debug_tuple("SmuxOperation").field(&n).finish(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct SmuxCommitOrRollback(pub u32);
+
+#[allow(non_upper_case_globals)]
+impl SmuxCommitOrRollback {
+    pub const Commit: SmuxCommitOrRollback = SmuxCommitOrRollback(0);
+    pub const Rollback: SmuxCommitOrRollback = SmuxCommitOrRollback(1);
+}
+
+impl fmt::Debug for SmuxCommitOrRollback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            0 => f.write_str("commit"),
+            1 => f.write_str("rollback"),
+            n => f.debug_tuple("SmuxCommitOrRollback").field(&n).finish(),
+        }
+    }
+}
+
+/// <pre>
+/// SimpleOpen ::=
+///     [APPLICATION 0] IMPLICIT
+///         SEQUENCE {
+///             version
+///                 INTEGER {
+///                     version-1(0)
+///                 },
+///             identity
+///                 OBJECT IDENTIFIER,
+///             description
+///                 DisplayString,
+///             password
+///                 OCTET STRING
+///         }
+/// </pre>
+#[derive(Debug, PartialEq)]
+pub struct SmuxOpen<'a> {
+    pub version: u32,
+    pub identity: Oid<'a>,
+    pub description: &'a [u8],
+    pub password: &'a [u8],
+}
+
+/// <pre>
+/// RReqPDU ::=
+///     [APPLICATION 2] IMPLICIT
+///         SEQUENCE {
+///             subtree
+///                 ObjectName,
+///             priority
+///                 INTEGER (-1..2147483647),
+///             operation
+///                 INTEGER {
+///                     delete(0),
+///                     readOnly(1),
+///                     readWrite(2)
+///                 }
+///         }
+/// </pre>
+#[derive(Debug, PartialEq)]
+pub struct SmuxRegisterRequest<'a> {
+    pub subtree: Oid<'a>,
+    /// Requested priority, or -1 to let the agent choose
+    pub priority: i32,
+    pub operation: SmuxOperation,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SmuxPdu<'a> {
+    Open(SmuxOpen<'a>),
+    Close(SmuxCloseReason),
+    RegisterRequest(SmuxRegisterRequest<'a>),
+    /// Priority granted by the agent, or -1 on failure
+    RegisterResponse(i32),
+    /// SNMPv1 PDU
+    Snmp(SnmpPdu<'a>),
+    CommitOrRollback(SmuxCommitOrRollback),
+}
+
+/// Parse a SMUX PDU
+///
+/// The remaining bytes (for ex. the next PDU in the stream) are returned on success.
+///
+/// # Errors
+///
+/// If the input does not start with a SMUX or SNMP PDU tag, a recoverable error
+/// (`nom::Err::Error`) is returned. Once the PDU type has been recognized, errors are returned
+/// as `nom::Err::Failure`.
+///
+/// Example:
+///
+/// ```rust
+/// use snmp_parser::smux::{parse_smux_pdu, SmuxCommitOrRollback, SmuxPdu};
+///
+/// # fn main() {
+/// let (_, pdu) = parse_smux_pdu(&[0x44, 0x01, 0x00]).expect("parsing failed");
+/// assert_eq!(pdu, SmuxPdu::CommitOrRollback(SmuxCommitOrRollback::Commit));
+/// # }
+/// ```
+pub fn parse_smux_pdu(bytes: &[u8]) -> IResult<&[u8], SmuxPdu<'_>, SnmpError> {
+    snmp_span!("parse_smux_pdu", len = bytes.len());
+    let (_, hdr) = Header::from_ber(bytes).map_err(Err::convert)?;
+    snmp_event!(class = ?hdr.class(), tag = hdr.tag().0, "parse start");
+    let res = match hdr.class() {
+        Class::Application => parse_smux_application_pdu(bytes, hdr.tag()),
+        Class::ContextSpecific => parse_snmp_v1_pdu(bytes)
+            .map(|(rem, pdu)| (rem, SmuxPdu::Snmp(pdu)))
+            .map_err(cut),
+        _ => Err(Err::Error(SnmpError::InvalidPduType { tag: hdr.tag().0 })),
+    };
+    resolve_offsets(res, bytes)
+}
+
+fn parse_smux_application_pdu(i: &[u8], tag: Tag) -> IResult<&[u8], SmuxPdu<'_>, SnmpError> {
+    let (rem, any) = Any::from_ber(i).map_err(Err::convert)?;
+    let pdu = match tag.0 {
+        0 => parse_smux_open(any.data).map(SmuxPdu::Open),
+        1 => smux_unsigned(&any).map(|n| SmuxPdu::Close(SmuxCloseReason(n))),
+        2 => parse_smux_rreq(any.data).map(SmuxPdu::RegisterRequest),
+        3 => smux_integer(&any).map(SmuxPdu::RegisterResponse),
+        4 => smux_unsigned(&any).map(|n| SmuxPdu::CommitOrRollback(SmuxCommitOrRollback(n))),
+        _ => {
+            snmp_anomaly!(tag = tag.0, "invalid SMUX PDU type");
+            return Err(Err::Error(SnmpError::InvalidPduType { tag: tag.0 }));
+        }
+    };
+    pdu.map(|pdu| (rem, pdu)).map_err(|e| match e {
+        // the PDU is complete, so missing data means its content is invalid
+        Err::Incomplete(_) => Err::Failure(SnmpError::InvalidPdu),
+        e => cut(e),
+    })
+}
+
+/// Read the value of an IMPLICIT INTEGER
+fn smux_integer(any: &Any) -> Result<i32, Err<SnmpError>> {
+    Integer::new(any.data)
+        .as_i32()
+        .map_err(|e| Err::Error(SnmpError::from(e)))
+}
+
+/// Read the value of an IMPLICIT INTEGER, which must not be negative
+fn smux_unsigned(any: &Any) -> Result<u32, Err<SnmpError>> {
+    Integer::new(any.data)
+        .as_u32()
+        .map_err(|e| Err::Error(SnmpError::from(e)))
+}
+
+fn parse_smux_open(i: &[u8]) -> Result<SmuxOpen<'_>, Err<SnmpError>> {
+    let (i, version) = context("version", u32::from_ber)(i)?;
+    let (i, identity) = context("identity", Oid::from_ber)(i)?;
+    let (i, description) = context("description", <&[u8]>::from_ber)(i)?;
+    let (_, password) = context("password", <&[u8]>::from_ber)(i)?;
+    Ok(SmuxOpen {
+        version,
+        identity,
+        description,
+        password,
+    })
+}
+
+fn parse_smux_rreq(i: &[u8]) -> Result<SmuxRegisterRequest<'_>, Err<SnmpError>> {
+    let (i, subtree) = context("subtree", Oid::from_ber)(i)?;
+    let (i, priority) = context("priority", i32::from_ber)(i)?;
+    let (_, operation) = context("operation", u32::from_ber)(i)?;
+    Ok(SmuxRegisterRequest {
+        subtree,
+        priority,
+        operation: SmuxOperation(operation),
+    })
+}
@@ -8,17 +8,34 @@
 //!   - [RFC3416](https://tools.ietf.org/html/rfc3416): SNMP v2
 //!   - [RFC2570](https://tools.ietf.org/html/rfc2570): Introduction to SNMP v3
 
-use crate::error::SnmpError;
+use crate::coexistence::{trap_v1_oid, SYS_UPTIME_OID};
+#[cfg(any(feature = "v1", feature = "v2c"))]
+use crate::error::cut;
+use crate::error::{context, context_with, resolve_offsets, SnmpError};
+use crate::format::CompactMessage;
+use crate::generic::SnmpGenericMessage;
+#[cfg(any(feature = "v1", feature = "v2c"))]
+use crate::instrument::trace_parse_result;
+use crate::walk::cmp_oid;
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use asn1_rs::{
-    Any, BitString, Class, Error, FromBer, Header, Implicit, Integer, Oid, Sequence, Tag,
+    Any, BitString, CheckDerConstraints, Class, DerAutoDerive, DynTagged, Error, FromBer, FromDer,
+    Header, Implicit, Integer, Length, Null, Oid, ParseResult, Real, Sequence, Tag, Tagged,
     TaggedValue,
 };
+use core::convert::TryFrom;
+use core::fmt;
+#[cfg(not(feature = "std"))]
+use core::net::Ipv4Addr;
+use core::slice::Iter;
 use nom::combinator::map;
 use nom::{Err, IResult};
-use std::convert::TryFrom;
+#[cfg(feature = "std")]
 use std::net::Ipv4Addr;
-use std::slice::Iter;
-use std::{fmt, str};
 
 // This will be merged in next release of asn1-rs
 type Application<T, E, TagKind, const TAG: u32> = TaggedValue<T, E, TagKind, 0b01, TAG>;
@@ -37,6 +54,13 @@ impl PduType {
     pub const InformRequest: PduType = PduType(6);
     pub const TrapV2: PduType = PduType(7);
     pub const Report: PduType = PduType(8);
+
+    /// Return `true` for PDUs of the Confirmed Class (see
+    /// [RFC3411](https://tools.ietf.org/html/rfc3411) section 2.8): Get, GetNext, GetBulk, Set
+    /// and Inform requests, which expect a response
+    pub fn is_confirmed_class(self) -> bool {
+        matches!(self.0, 0 | 1 | 3 | 5 | 6)
+    }
 }
 
 impl fmt::Debug for PduType {
@@ -122,6 +146,68 @@ impl ErrorStatus {
     pub const BadValue: ErrorStatus = ErrorStatus(3);
     pub const ReadOnly: ErrorStatus = ErrorStatus(4);
     pub const GenErr: ErrorStatus = ErrorStatus(5);
+    // SNMPv2 (RFC3416)
+    pub const NoAccess: ErrorStatus = ErrorStatus(6);
+    pub const WrongType: ErrorStatus = ErrorStatus(7);
+    pub const WrongLength: ErrorStatus = ErrorStatus(8);
+    pub const WrongEncoding: ErrorStatus = ErrorStatus(9);
+    pub const WrongValue: ErrorStatus = ErrorStatus(10);
+    pub const NoCreation: ErrorStatus = ErrorStatus(11);
+    pub const InconsistentValue: ErrorStatus = ErrorStatus(12);
+    pub const ResourceUnavailable: ErrorStatus = ErrorStatus(13);
+    pub const CommitFailed: ErrorStatus = ErrorStatus(14);
+    pub const UndoFailed: ErrorStatus = ErrorStatus(15);
+    pub const AuthorizationError: ErrorStatus = ErrorStatus(16);
+    pub const NotWritable: ErrorStatus = ErrorStatus(17);
+    pub const InconsistentName: ErrorStatus = ErrorStatus(18);
+
+    /// Test if this is an error (not `noError`)
+    pub fn is_error(self) -> bool {
+        self != ErrorStatus::NoError
+    }
+
+    /// Test if this error is only used by SNMPv1 agents (`noSuchName`, `badValue` and
+    /// `readOnly`)
+    ///
+    /// SNMPv2 agents use more specific errors (or exceptions) instead, see
+    /// [RFC3416](https://tools.ietf.org/html/rfc3416) section 4.2. These values can still be
+    /// found in SNMPv2 messages translated from SNMPv1 by proxies.
+    pub fn is_v1_only(self) -> bool {
+        matches!(self.0, 2..=4)
+    }
+
+    /// Test if this error is only defined in SNMPv2 (`noAccess` to `inconsistentName`)
+    pub fn is_v2_only(self) -> bool {
+        matches!(self.0, 6..=18)
+    }
+
+    /// Return a coarse classification of this error
+    pub fn severity(self) -> ErrorSeverity {
+        match self.0 {
+            0 => ErrorSeverity::None,
+            1 | 13 => ErrorSeverity::Transient,
+            2..=4 | 6..=12 | 16..=18 => ErrorSeverity::Request,
+            5 | 14 | 15 => ErrorSeverity::Agent,
+            _ => ErrorSeverity::Unknown,
+        }
+    }
+}
+
+/// Coarse classification of error status values, see [`ErrorStatus::severity`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ErrorSeverity {
+    /// No error
+    None,
+    /// The request may succeed if sent differently (`tooBig`: with fewer variables) or later
+    /// (`resourceUnavailable`)
+    Transient,
+    /// The request is not valid for the agent: unknown variable, wrong value, access denied, etc.
+    Request,
+    /// The agent failed to process the request (`genErr`, `commitFailed` and `undoFailed`).
+    /// After `undoFailed`, the agent may be left in an inconsistent state.
+    Agent,
+    /// The error status is not defined
+    Unknown,
 }
 
 impl fmt::Debug for ErrorStatus {
@@ -133,6 +219,19 @@ impl fmt::Debug for ErrorStatus {
             3 => f.write_str("BadValue"),
             4 => f.write_str("ReadOnly"),
             5 => f.write_str("GenErr"),
+            6 => f.write_str("NoAccess"),
+            7 => f.write_str("WrongType"),
+            8 => f.write_str("WrongLength"),
+            9 => f.write_str("WrongEncoding"),
+            10 => f.write_str("WrongValue"),
+            11 => f.write_str("NoCreation"),
+            12 => f.write_str("InconsistentValue"),
+            13 => f.write_str("ResourceUnavailable"),
+            14 => f.write_str("CommitFailed"),
+            15 => f.write_str("UndoFailed"),
+            16 => f.write_str("AuthorizationError"),
+            17 => f.write_str("NotWritable"),
+            18 => f.write_str("InconsistentName"),
             n => f.debug_tuple("ErrorStatus").field(&n).finish(),
         }
     }
@@ -181,16 +280,93 @@ pub struct SnmpMessage<'a> {
     pub pdu: SnmpPdu<'a>,
 }
 
+/// Lookup and ordering methods on the variable list of a PDU, accessed through the `var_list` and
+/// `var_list_mut` methods of the PDU type
+macro_rules! impl_var_list {
+    ($ty:ident) => {
+        impl<'a> $ty<'a> {
+            /// Return the first variable with this OID
+            pub fn find_var(&self, oid: &Oid) -> Option<&SnmpVariable<'a>> {
+                self.var_list().iter().find(|v| v.oid == *oid)
+            }
+
+            /// Iterate over the variables in the subtree of `prefix` (including `prefix`)
+            pub fn find_under<'s>(
+                &'s self,
+                prefix: &'s Oid,
+            ) -> impl Iterator<Item = &'s SnmpVariable<'a>> + 's {
+                self.var_list()
+                    .iter()
+                    .filter(move |v| v.oid.starts_with(prefix))
+            }
+
+            /// Return `true` if at least two variables have the same OID
+            pub fn has_duplicate_oids(&self) -> bool {
+                let mut oids: Vec<&Oid> = self.var_list().iter().map(|v| &v.oid).collect();
+                oids.sort_by(|a, b| cmp_oid(a, b));
+                oids.windows(2).any(|w| w[0] == w[1])
+            }
+
+            /// Sort variables in lexicographic order of their OIDs
+            ///
+            /// The sort is stable: variables with the same OID keep their relative order.
+            pub fn sort_by_oid(&mut self) {
+                self.var_list_mut().sort_by(|a, b| cmp_oid(&a.oid, &b.oid));
+            }
+        }
+    };
+}
+
+impl_var_list!(SnmpGenericPdu);
+impl_var_list!(SnmpBulkPdu);
+impl_var_list!(SnmpTrapPdu);
+impl_var_list!(SnmpPdu);
+
 impl<'a> SnmpGenericPdu<'a> {
-    pub fn vars_iter(&'a self) -> Iter<SnmpVariable> {
+    pub fn vars_iter(&'a self) -> Iter<'a, SnmpVariable<'a>> {
         self.var.iter()
     }
+
+    fn var_list(&self) -> &[SnmpVariable<'a>] {
+        &self.var
+    }
+
+    fn var_list_mut(&mut self) -> &mut [SnmpVariable<'a>] {
+        &mut self.var
+    }
+}
+
+impl<'a> SnmpBulkPdu<'a> {
+    fn var_list(&self) -> &[SnmpVariable<'a>] {
+        &self.var
+    }
+
+    fn var_list_mut(&mut self) -> &mut [SnmpVariable<'a>] {
+        &mut self.var
+    }
 }
 
 impl<'a> SnmpTrapPdu<'a> {
-    pub fn vars_iter(&'a self) -> Iter<SnmpVariable> {
+    pub fn vars_iter(&'a self) -> Iter<'a, SnmpVariable<'a>> {
         self.var.iter()
     }
+
+    /// Return the notification OID of the trap, as it would be sent in `snmpTrapOID.0` by a
+    /// SNMPv2 agent (`enterprise.0.specific-trap` for enterprise-specific traps)
+    ///
+    /// Return `None` if the generic-trap value is not valid. See
+    /// [`trap_v1_oid`](crate::coexistence::trap_v1_oid).
+    pub fn notification_oid(&self) -> Option<Oid<'static>> {
+        trap_v1_oid(self).ok()
+    }
+
+    fn var_list(&self) -> &[SnmpVariable<'a>] {
+        &self.var
+    }
+
+    fn var_list_mut(&mut self) -> &mut [SnmpVariable<'a>] {
+        &mut self.var
+    }
 }
 
 impl<'a> SnmpPdu<'a> {
@@ -202,23 +378,380 @@ impl<'a> SnmpPdu<'a> {
         }
     }
 
-    pub fn vars_iter(&'a self) -> Iter<SnmpVariable> {
+    /// Return the request ID, or `None` for SNMPv1 traps
+    pub fn request_id(&self) -> Option<u32> {
+        match *self {
+            SnmpPdu::Generic(ref pdu) => Some(pdu.req_id),
+            SnmpPdu::Bulk(ref pdu) => Some(pdu.req_id),
+            SnmpPdu::TrapV1(_) => None,
+        }
+    }
+
+    pub fn vars_iter(&'a self) -> Iter<'a, SnmpVariable<'a>> {
         match *self {
             SnmpPdu::Generic(ref pdu) => pdu.var.iter(),
             SnmpPdu::Bulk(ref pdu) => pdu.var.iter(),
             SnmpPdu::TrapV1(ref pdu) => pdu.var.iter(),
         }
     }
+
+    /// Check the OIDs of the variables against `limits`, returning the index of the first
+    /// variable not allowed and the violation
+    ///
+    /// See [`OidLimits::check`].
+    pub fn check_oid_limits(&self, limits: &OidLimits) -> Option<(usize, OidViolation)> {
+        self.var_list()
+            .iter()
+            .enumerate()
+            .find_map(|(index, var)| Some((index, limits.check(&var.oid)?)))
+    }
+
+    pub(crate) fn var_list(&self) -> &[SnmpVariable<'a>] {
+        match *self {
+            SnmpPdu::Generic(ref pdu) => &pdu.var,
+            SnmpPdu::Bulk(ref pdu) => &pdu.var,
+            SnmpPdu::TrapV1(ref pdu) => &pdu.var,
+        }
+    }
+
+    pub(crate) fn var_list_mut(&mut self) -> &mut [SnmpVariable<'a>] {
+        self.var_vec_mut()
+    }
+
+    fn var_vec_mut(&mut self) -> &mut Vec<SnmpVariable<'a>> {
+        match *self {
+            SnmpPdu::Generic(ref mut pdu) => &mut pdu.var,
+            SnmpPdu::Bulk(ref mut pdu) => &mut pdu.var,
+            SnmpPdu::TrapV1(ref mut pdu) => &mut pdu.var,
+        }
+    }
+
+    /// Append a variable
+    ///
+    /// In GetBulkRequest PDUs, appended variables are repeaters.
+    pub fn push_var(&mut self, var: SnmpVariable<'a>) {
+        self.var_vec_mut().push(var);
+    }
+
+    /// Keep only the variables for which `f` returns `true`
+    ///
+    /// Fields referring to positions in the variable list are updated: the error index is
+    /// adjusted (or reset to 0 if the variable it designates is removed), and for GetBulkRequest
+    /// PDUs, `non_repeaters` becomes the number of remaining non-repeaters.
+    pub fn retain_vars<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&SnmpVariable<'a>) -> bool,
+    {
+        let keep: Vec<bool> = self.var_list().iter().map(&mut f).collect();
+        match *self {
+            SnmpPdu::Generic(ref mut pdu) => {
+                // the error index starts from 1, 0 meaning no variable
+                let index = pdu.err_index as usize;
+                if index > 0 && index <= keep.len() {
+                    let kept_before = keep[..index - 1].iter().filter(|&&k| k).count();
+                    pdu.err_index = if keep[index - 1] {
+                        kept_before as u32 + 1
+                    } else {
+                        0
+                    };
+                }
+            }
+            SnmpPdu::Bulk(ref mut pdu) => {
+                let non_repeaters = keep.iter().take(pdu.non_repeaters as usize);
+                pdu.non_repeaters = non_repeaters.filter(|&&k| k).count() as u32;
+            }
+            SnmpPdu::TrapV1(_) => (),
+        }
+        let mut keep = keep.into_iter();
+        self.var_vec_mut().retain(|_| keep.next().unwrap_or(false));
+    }
+
+    /// Replace the value of the first variable with this OID, returning the previous value
+    ///
+    /// Return `None` (and leave the PDU unchanged) if no variable has this OID.
+    pub fn set_value(&mut self, oid: &Oid, value: VarBindValue<'a>) -> Option<VarBindValue<'a>> {
+        let var = self.var_list_mut().iter_mut().find(|v| v.oid == *oid)?;
+        Some(core::mem::replace(&mut var.val, value))
+    }
 }
 
 impl<'a> SnmpMessage<'a> {
+    /// Parse a SNMPv1 or SNMPv2c message, returning the message and the number of bytes read
+    ///
+    /// This is equivalent to `parse_snmp_v1` or `parse_snmp_v2c` (depending on the version
+    /// of the message), for callers not using `nom`. Messages of other versions are rejected
+    /// with `SnmpError::InvalidVersion`.
+    pub fn parse(bytes: &'a [u8]) -> Result<(Self, usize), SnmpError> {
+        match SnmpGenericMessage::parse(bytes)? {
+            (SnmpGenericMessage::V1(msg), len) | (SnmpGenericMessage::V2(msg), len) => {
+                Ok((msg, len))
+            }
+            #[allow(unreachable_patterns)]
+            (msg, _) => Err(SnmpError::InvalidVersion {
+                found: msg.version(),
+            }),
+        }
+    }
+
     pub fn pdu_type(&self) -> PduType {
         self.pdu.pdu_type()
     }
 
-    pub fn vars_iter(&'a self) -> Iter<SnmpVariable> {
+    pub fn vars_iter(&'a self) -> Iter<'a, SnmpVariable<'a>> {
         self.pdu.vars_iter()
     }
+
+    pub(crate) fn trace_fields(&self) -> (Option<PduType>, usize) {
+        (Some(self.pdu_type()), self.pdu.vars_iter().count())
+    }
+
+    /// Compare messages, ignoring the request ID
+    ///
+    /// Retransmitted requests usually have the same request ID, but some managers use a new
+    /// request ID for each retry. See also
+    /// [`semantic_eq_ignore_uptime`](Self::semantic_eq_ignore_uptime).
+    pub fn semantic_eq(&self, other: &SnmpMessage) -> bool {
+        self.semantic_eq_impl(other, false)
+    }
+
+    /// Compare messages, ignoring the request ID and the uptime of the agent
+    ///
+    /// The values of the `sysUpTime.0` variables and the timestamps of SNMPv1 traps are not
+    /// compared. This is used to detect duplicate notifications, which are sent at different
+    /// times.
+    pub fn semantic_eq_ignore_uptime(&self, other: &SnmpMessage) -> bool {
+        self.semantic_eq_impl(other, true)
+    }
+
+    fn semantic_eq_impl(&self, other: &SnmpMessage, ignore_uptime: bool) -> bool {
+        let vars_eq = |a: &[SnmpVariable], b: &[SnmpVariable]| {
+            a.len() == b.len()
+                && a.iter().zip(b.iter()).all(|(a, b)| {
+                    a.oid == b.oid && (a.val == b.val || (ignore_uptime && a.oid == SYS_UPTIME_OID))
+                })
+        };
+        if self.version != other.version || self.community != other.community {
+            return false;
+        }
+        match (&self.pdu, &other.pdu) {
+            (SnmpPdu::Generic(a), SnmpPdu::Generic(b)) => {
+                a.pdu_type == b.pdu_type
+                    && a.err == b.err
+                    && a.err_index == b.err_index
+                    && vars_eq(&a.var, &b.var)
+            }
+            (SnmpPdu::Bulk(a), SnmpPdu::Bulk(b)) => {
+                a.non_repeaters == b.non_repeaters
+                    && a.max_repetitions == b.max_repetitions
+                    && vars_eq(&a.var, &b.var)
+            }
+            (SnmpPdu::TrapV1(a), SnmpPdu::TrapV1(b)) => {
+                a.enterprise == b.enterprise
+                    && a.agent_addr == b.agent_addr
+                    && a.generic_trap == b.generic_trap
+                    && a.specific_trap == b.specific_trap
+                    && (ignore_uptime || a.timestamp == b.timestamp)
+                    && vars_eq(&a.var, &b.var)
+            }
+            _ => false,
+        }
+    }
+
+    /// Format the message on one line, with at most `max_varbinds` variables
+    ///
+    /// If `redact_secrets` is `true`, the community is not written. See [`CompactMessage`].
+    pub fn fmt_compact(&self, max_varbinds: usize, redact_secrets: bool) -> CompactMessage<'_, 'a> {
+        CompactMessage::new(
+            self.version,
+            Some(("community", self.community.as_bytes())),
+            Some(&self.pdu),
+            max_varbinds,
+            redact_secrets,
+        )
+    }
+
+    /// Compare the community with `expected`, in constant time
+    ///
+    /// The time taken depends on the length of `expected`, but not on the content of the
+    /// communities, nor on the position of the first difference. This should be used instead of
+    /// `==` to authenticate messages, to avoid leaking the expected community through timing.
+    pub fn community_matches(&self, expected: &[u8], case: CommunityCase) -> bool {
+        let community = self.community.as_bytes();
+        let fold = |b: u8| match case {
+            CommunityCase::Sensitive => b,
+            CommunityCase::AsciiInsensitive => b | (((b.wrapping_sub(b'A') < 26) as u8) << 5),
+        };
+        let mut diff = (community.len() != expected.len()) as u8;
+        for (i, &e) in expected.iter().enumerate() {
+            let c = community.get(i).copied().unwrap_or(0);
+            diff |= fold(c) ^ fold(e);
+        }
+        diff == 0
+    }
+
+    /// Check the length of the community against `policy`
+    ///
+    /// See [`CommunityPolicy::check`].
+    pub fn check_community(&self, policy: &CommunityPolicy) -> Option<CommunityViolation> {
+        policy.check(self.community.as_bytes())
+    }
+}
+
+/// Case sensitivity of [`SnmpMessage::community_matches`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CommunityCase {
+    /// Communities must be identical (as required by the standards)
+    Sensitive,
+    /// ASCII letters are compared ignoring case, for devices which do not preserve the case of
+    /// configured communities
+    AsciiInsensitive,
+}
+
+/// Limits on the length of community strings
+///
+/// The standards do not restrict communities, but empty or very long communities are rarely
+/// configured on real devices, and are strong indicators of scanning or fuzzing tools. The
+/// default limits are 1 to 32 bytes (the usual maximum length of `snmpCommunityName` in
+/// device configurations).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CommunityPolicy {
+    pub min_len: usize,
+    pub max_len: usize,
+}
+
+impl Default for CommunityPolicy {
+    fn default() -> Self {
+        CommunityPolicy {
+            min_len: 1,
+            max_len: 32,
+        }
+    }
+}
+
+/// A community string not allowed by a [`CommunityPolicy`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommunityViolation {
+    /// The community is empty (and `min_len` is not 0)
+    Empty,
+    /// The community is shorter than `min_len`
+    TooShort { len: usize },
+    /// The community is longer than `max_len`
+    TooLong { len: usize },
+}
+
+impl CommunityPolicy {
+    /// Check the length of `community`, returning `None` if it is allowed
+    ///
+    /// The community is passed as bytes, so this can also be used on the raw community of
+    /// [`SnmpMessageHeader`](crate::inspect::SnmpMessageHeader).
+    pub fn check(&self, community: &[u8]) -> Option<CommunityViolation> {
+        self.check_len(community.len())
+    }
+
+    pub(crate) fn check_len(&self, len: usize) -> Option<CommunityViolation> {
+        let violation = if len < self.min_len {
+            if len == 0 {
+                CommunityViolation::Empty
+            } else {
+                CommunityViolation::TooShort { len }
+            }
+        } else if len > self.max_len {
+            CommunityViolation::TooLong { len }
+        } else {
+            return None;
+        };
+        match violation {
+            CommunityViolation::Empty => {
+                snmp_anomaly!("empty community");
+            }
+            CommunityViolation::TooShort { .. } => {
+                snmp_anomaly!(len, min_len = self.min_len, "community too short");
+            }
+            CommunityViolation::TooLong { .. } => {
+                snmp_anomaly!(len, max_len = self.max_len, "community too long");
+            }
+        }
+        Some(violation)
+    }
+}
+
+/// Limits on the OIDs of variable bindings
+///
+/// The SMI restricts OIDs to 128 arcs of at most 2^32-1 (RFC 2578 section 7.1.3), but the BER
+/// encoding allows any number of arcs of any size. OIDs with thousands of arcs or huge arcs are
+/// not used by real agents, and are sent by fuzzing tools to stress parsers. The default limits
+/// are the SMI limits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OidLimits {
+    pub max_arcs: usize,
+    pub max_arc: u64,
+}
+
+impl Default for OidLimits {
+    fn default() -> Self {
+        OidLimits {
+            max_arcs: 128,
+            max_arc: u64::from(u32::MAX),
+        }
+    }
+}
+
+/// An OID not allowed by [`OidLimits`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OidViolation {
+    /// The OID has more than `max_arcs` arcs
+    TooManyArcs { arcs: usize },
+    /// The arc at position `index` (starting from 0) is greater than `max_arc`
+    ArcTooLarge { index: usize },
+}
+
+impl OidLimits {
+    /// Check the number and values of the arcs of `oid`, returning `None` if it is allowed
+    ///
+    /// The arcs are read from the encoded OID, so arcs which do not fit in a `u64` are reported
+    /// as [`OidViolation::ArcTooLarge`].
+    pub fn check(&self, oid: &Oid) -> Option<OidViolation> {
+        let mut arcs = 0;
+        let mut too_large = None;
+        let mut value: Option<u64> = Some(0);
+        for &b in oid.as_bytes() {
+            value = value
+                .and_then(|v| v.checked_mul(128))
+                .map(|v| v | u64::from(b & 0x7f));
+            if b & 0x80 != 0 {
+                continue;
+            }
+            let allowed = |v: Option<u64>| matches!(v, Some(v) if v <= self.max_arc);
+            if arcs == 0 {
+                // the first subidentifier encodes the first two arcs, as X * 40 + Y
+                let (first, second) = match value {
+                    Some(v) => ((v / 40).min(2), Some(v - (v / 40).min(2) * 40)),
+                    None => (2, None),
+                };
+                if !allowed(Some(first)) {
+                    too_large = Some(0);
+                } else if !allowed(second) {
+                    too_large = Some(1);
+                }
+                arcs = 2;
+            } else {
+                if too_large.is_none() && !allowed(value) {
+                    too_large = Some(arcs);
+                }
+                arcs += 1;
+            }
+            value = Some(0);
+        }
+        let violation = if arcs > self.max_arcs {
+            snmp_anomaly!(arcs, max_arcs = self.max_arcs, "OID has too many arcs");
+            OidViolation::TooManyArcs { arcs }
+        } else {
+            let index = too_large?;
+            snmp_anomaly!(index, max_arc = self.max_arc, "OID arc too large");
+            OidViolation::ArcTooLarge { index }
+        };
+        Some(violation)
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -236,6 +769,52 @@ pub enum VarBindValue<'a> {
     EndOfMibView,
 }
 
+/// An exception in a response variable binding (see [`VarBindValue::exception_kind`])
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum VarBindException {
+    NoSuchObject,
+    NoSuchInstance,
+    EndOfMibView,
+}
+
+impl<'a> VarBindValue<'a> {
+    /// Return the value, or `None` for NULL values and exceptions
+    pub fn value(&self) -> Option<&ObjectSyntax<'a>> {
+        match self {
+            VarBindValue::Value(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Return `true` if this is an exception (`noSuchObject`, `noSuchInstance` or
+    /// `endOfMibView`)
+    pub fn is_exception(&self) -> bool {
+        self.exception_kind().is_some()
+    }
+
+    /// Return the name of the type of the value, as used in MIB definitions (for ex.
+    /// `Counter32`), or the name of the exception
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            VarBindValue::Value(obj) => obj.type_name(),
+            VarBindValue::Unspecified => "NULL",
+            VarBindValue::NoSuchObject => "noSuchObject",
+            VarBindValue::NoSuchInstance => "noSuchInstance",
+            VarBindValue::EndOfMibView => "endOfMibView",
+        }
+    }
+
+    /// Return the exception, or `None` if this is a value or NULL
+    pub fn exception_kind(&self) -> Option<VarBindException> {
+        match self {
+            VarBindValue::NoSuchObject => Some(VarBindException::NoSuchObject),
+            VarBindValue::NoSuchInstance => Some(VarBindException::NoSuchInstance),
+            VarBindValue::EndOfMibView => Some(VarBindException::EndOfMibView),
+            VarBindValue::Value(_) | VarBindValue::Unspecified => None,
+        }
+    }
+}
+
 /// <pre>
 /// VarBind ::= SEQUENCE {
 ///     name ObjectName,
@@ -257,6 +836,16 @@ impl<'a> TryFrom<Any<'a>> for SnmpVariable<'a> {
     fn try_from(any: Any<'a>) -> Result<SnmpVariable<'a>, Self::Error> {
         let (rem, oid) = Oid::from_ber(any.data)?;
         let (_, choice) = Any::from_ber(rem)?;
+        let val = VarBindValue::try_from(choice)?;
+        let var_bind = SnmpVariable { oid, val };
+        Ok(var_bind)
+    }
+}
+
+impl<'a> TryFrom<Any<'a>> for VarBindValue<'a> {
+    type Error = Error;
+
+    fn try_from(choice: Any<'a>) -> Result<VarBindValue<'a>, Self::Error> {
         let val = if choice.header.is_contextspecific() {
             match choice.tag().0 {
                 0 => VarBindValue::NoSuchObject,
@@ -274,8 +863,7 @@ impl<'a> TryFrom<Any<'a>> for SnmpVariable<'a> {
         } else {
             VarBindValue::Value(ObjectSyntax::try_from(choice)?)
         };
-        let var_bind = SnmpVariable { oid, val };
-        Ok(var_bind)
+        Ok(val)
     }
 }
 
@@ -286,6 +874,11 @@ pub enum ObjectSyntax<'a> {
     Object(Oid<'a>),
     BitString(BitString<'a>),
     Empty,
+    /// REAL value, which is not part of the SMI but is sent by some agents
+    Real(f64),
+    /// ENUMERATED value, which is not part of the SMI (enumerations are INTEGER values) but is
+    /// sent by some agents
+    Enumerated(i32),
     UnknownSimple(Any<'a>),
     IpAddress(NetworkAddress),
     Counter32(Counter),
@@ -296,6 +889,97 @@ pub enum ObjectSyntax<'a> {
     Counter64(u64),
     UInteger32(u32),
     UnknownApplication(Any<'a>),
+    /// A value with a vendor-specific APPLICATION tag, decoded by a decoder registered in a
+    /// [`ParserConfig`](crate::config::ParserConfig)
+    Custom(CustomValue<'a>),
+}
+
+impl<'a> ObjectSyntax<'a> {
+    /// Return the name of the type of the value, as used in MIB definitions (for ex.
+    /// `Counter32`)
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ObjectSyntax::Number(_) => "INTEGER",
+            ObjectSyntax::String(_) => "OCTET STRING",
+            ObjectSyntax::Object(_) => "OBJECT IDENTIFIER",
+            ObjectSyntax::BitString(_) => "BITS",
+            ObjectSyntax::Empty => "NULL",
+            ObjectSyntax::Real(_) => "REAL",
+            ObjectSyntax::Enumerated(_) => "ENUMERATED",
+            ObjectSyntax::UnknownSimple(_) | ObjectSyntax::UnknownApplication(_) => "unknown",
+            ObjectSyntax::IpAddress(_) => "IpAddress",
+            ObjectSyntax::Counter32(_) => "Counter32",
+            ObjectSyntax::Gauge32(_) => "Gauge32",
+            ObjectSyntax::TimeTicks(_) => "TimeTicks",
+            ObjectSyntax::Opaque(_) => "Opaque",
+            ObjectSyntax::NsapAddress(_) => "NsapAddress",
+            ObjectSyntax::Counter64(_) => "Counter64",
+            ObjectSyntax::UInteger32(_) => "UInteger32",
+            ObjectSyntax::Custom(_) => "custom",
+        }
+    }
+
+    /// Return the class, tag and content of values with an unknown type
+    ///
+    /// Return `None` if the type of the value is known (even if it is not the expected type).
+    pub fn unknown_tag_info(&self) -> Option<UnknownTagInfo<'a>> {
+        match self {
+            ObjectSyntax::UnknownSimple(any) | ObjectSyntax::UnknownApplication(any) => {
+                Some(UnknownTagInfo {
+                    class: any.class(),
+                    tag: any.tag(),
+                    constructed: any.header.is_constructed(),
+                    data: any.data,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A value decoded by a user-defined decoder, see [`ObjectSyntax::Custom`]
+pub struct CustomValue<'a> {
+    /// The value, as read
+    pub raw: Any<'a>,
+    value: Box<dyn core::any::Any + Send + Sync>,
+}
+
+impl<'a> CustomValue<'a> {
+    pub(crate) fn new(raw: Any<'a>, value: Box<dyn core::any::Any + Send + Sync>) -> Self {
+        CustomValue { raw, value }
+    }
+
+    /// Return the decoded value, if it has type `T`
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        self.value.downcast_ref()
+    }
+}
+
+impl<'a> fmt::Debug for CustomValue<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CustomValue")
+            .field("tag", &self.raw.tag())
+            .field("data", &self.raw.data)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Values are compared using their encoding, since they are decoded from it
+impl<'a> PartialEq for CustomValue<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+/// Encoding of a value with an unknown type, see [`ObjectSyntax::unknown_tag_info`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnknownTagInfo<'a> {
+    pub class: Class,
+    pub tag: Tag,
+    /// `true` if the value uses a constructed encoding
+    pub constructed: bool,
+    /// Raw content of the value, without the identifier and length
+    pub data: &'a [u8],
 }
 
 /// <pre>
@@ -383,6 +1067,13 @@ impl<'a> TryFrom<Any<'a>> for ObjectSyntax<'a> {
             // Some implementations do not send NULL, but empty objects
             // Treat 0-length objects as ObjectSyntax::Empty
             if any.data.is_empty() {
+                // zero is encoded as an empty REAL
+                if any.tag() == Tag::RealType {
+                    return Ok(ObjectSyntax::Real(0.0));
+                }
+                if any.tag() != Tag::Null {
+                    snmp_anomaly!(tag = ?any.tag(), "empty value instead of NULL");
+                }
                 return Ok(ObjectSyntax::Empty);
             }
             let obj = match any.header.tag() {
@@ -394,6 +1085,29 @@ impl<'a> TryFrom<Any<'a>> for ObjectSyntax<'a> {
                 Tag::Null => ObjectSyntax::Empty,
                 Tag::Oid => ObjectSyntax::Object(any.oid()?),
                 Tag::OctetString => ObjectSyntax::String(any.data),
+                Tag::RealType => match any.data {
+                    // special values not decoded by asn1-rs
+                    [0x42] => ObjectSyntax::Real(f64::NAN),
+                    [0x43] => ObjectSyntax::Real(-0.0),
+                    [first, ..] if first & 0x80 != 0 => match binary_real_to_f64(any.data) {
+                        Some(value) => ObjectSyntax::Real(value),
+                        None => {
+                            snmp_anomaly!("invalid binary REAL value");
+                            ObjectSyntax::UnknownSimple(any)
+                        }
+                    },
+                    _ => match Real::try_from(&any) {
+                        Ok(real) => ObjectSyntax::Real(real.f64()),
+                        Err(_e) => {
+                            snmp_anomaly!(error = ?_e, "invalid REAL value");
+                            ObjectSyntax::UnknownSimple(any)
+                        }
+                    },
+                },
+                Tag::Enumerated => {
+                    let number = Integer::new(any.data).as_i32()?;
+                    ObjectSyntax::Enumerated(number)
+                }
                 _ => ObjectSyntax::UnknownSimple(any),
             };
             Ok(obj)
@@ -401,6 +1115,92 @@ impl<'a> TryFrom<Any<'a>> for ObjectSyntax<'a> {
     }
 }
 
+/// Largest binary exponent of a REAL value which is not rounded to infinity or zero
+///
+/// Mantissas have at most 64 bits, so any exponent beyond this bound gives the same result.
+const MAX_REAL_EXPONENT: i64 = 2200;
+
+/// Decode the content of a REAL with the binary encoding (X.690 section 8.5.7)
+///
+/// The value is computed without overflow for any exponent: asn1-rs scales the exponent of base
+/// 8 and 16 values without checking it, and `Real::f64` computes the power of the base
+/// separately, which loses precision (or underflows) for small values.
+///
+/// Return `None` if the encoding is invalid, or if the exponent or the mantissa do not fit in 32
+/// and 64 bits.
+fn binary_real_to_f64(data: &[u8]) -> Option<f64> {
+    let (&first, rem) = data.split_first()?;
+    let (exponent_len, rem) = match first & 0x03 {
+        3 => {
+            let (&len, rem) = rem.split_first()?;
+            (usize::from(len), rem)
+        }
+        n => (usize::from(n) + 1, rem),
+    };
+    if exponent_len == 0 || exponent_len > 4 || exponent_len >= rem.len() {
+        return None;
+    }
+    let (exponent, mantissa) = rem.split_at(exponent_len);
+    if mantissa.len() > 8 {
+        return None;
+    }
+    let sign_extension = if exponent[0] & 0x80 != 0 { -1 } else { 0 };
+    let exponent = (exponent.iter()).fold(sign_extension, |e: i64, &b| (e << 8) | i64::from(b));
+    let bits_per_digit = match (first >> 4) & 0x03 {
+        0 => 1,
+        1 => 3,
+        2 => 4,
+        _ => return None,
+    };
+    let scale = i64::from((first >> 2) & 0x03);
+    let mantissa = mantissa
+        .iter()
+        .fold(0, |m: u64, &b| (m << 8) | u64::from(b)) as f64;
+    let mantissa = if first & 0x40 != 0 {
+        -mantissa
+    } else {
+        mantissa
+    };
+    if mantissa == 0.0 {
+        return Some(mantissa);
+    }
+    // |exponent| < 2^31, so this cannot overflow
+    let exponent = (exponent * bits_per_digit + scale).clamp(-MAX_REAL_EXPONENT, MAX_REAL_EXPONENT);
+    // 2^exponent may not be representable, but its halves are (or saturate the same way)
+    let half = (exponent / 2) as i32;
+    let other_half = exponent as i32 - half;
+    Some(mantissa * 2f64.powi(half) * 2f64.powi(other_half))
+}
+
+/// Options of the message parsers, set by the public parsing functions
+// the options are only read by the parsers of SNMPv1, SNMPv2c and SNMPv3 messages
+#[cfg_attr(
+    not(any(feature = "v1", feature = "v2c", feature = "v3")),
+    allow(dead_code)
+)]
+#[derive(Clone, Copy)]
+pub(crate) struct ParseOptions<'f> {
+    /// Conversion of the communities and user names read by the parsers to the stored strings
+    ///
+    /// Parsers usually store a copy (`&str::to_string`), see
+    /// [`parse_snmp_generic_message_hashed`](crate::hashing::parse_snmp_generic_message_hashed).
+    pub(crate) store: &'f dyn Fn(&str) -> String,
+    /// Accept the invalid encodings sent by some agents, see
+    /// [`ParserConfig::lenient`](crate::config::ParserConfig::lenient)
+    // only SNMPv3 messages have lenient encodings
+    #[cfg_attr(not(feature = "v3"), allow(dead_code))]
+    pub(crate) lenient: bool,
+}
+
+impl ParseOptions<'static> {
+    /// Options of the default parsers: credentials are copied, and encodings are strict
+    pub(crate) const DEFAULT: ParseOptions<'static> = ParseOptions {
+        store: &<str as ToString>::to_string,
+        lenient: false,
+    };
+}
+
+#[cfg(any(feature = "v1", feature = "v2c", feature = "v3"))]
 #[inline]
 pub(crate) fn parse_ber_octetstring_as_str(i: &[u8]) -> IResult<&[u8], &str, Error> {
     let (rem, b) = <&[u8]>::from_ber(i)?;
@@ -408,9 +1208,112 @@ pub(crate) fn parse_ber_octetstring_as_str(i: &[u8]) -> IResult<&[u8], &str, Err
     Ok((rem, s))
 }
 
-fn parse_varbind_list(i: &[u8]) -> IResult<&[u8], Vec<SnmpVariable>, Error> {
-    // parse_ber_sequence_of_v(parse_varbind)(i)
-    <Vec<SnmpVariable>>::from_ber(i)
+/// Maximum nesting depth of BER encodings in a message
+///
+/// The depth is counted from the message sequence: values of variable bindings are at depth 5
+/// (6 in SNMPv3 scoped PDUs). Values nested deeper, using constructed encodings or BER-encoded
+/// content in Opaque values, are rejected with [`SnmpError::MaxDepthExceeded`].
+pub const MAX_DEPTH: usize = 16;
+
+/// Depth of variable binding values, in the deepest message structure (SNMPv3)
+const VALUE_DEPTH: usize = 6;
+
+/// Marker for the end of indefinite-length encodings, in `check_value_depth`
+const INDEFINITE: usize = usize::MAX;
+
+/// Check that the variable binding value in `i` does not nest deeper than [`MAX_DEPTH`]
+///
+/// The encoding is scanned without recursion. Malformed encodings are not reported here, but
+/// by the parsers reading the value.
+pub(crate) fn check_value_depth(i: &[u8]) -> Result<(), SnmpError> {
+    let max_depth = MAX_DEPTH - VALUE_DEPTH + 1;
+    let mut ends = [0usize; MAX_DEPTH];
+    let mut depth = 0;
+    let mut pos = 0;
+    loop {
+        // leave the encodings ending at the current position
+        while depth > 0 {
+            let end = ends[depth - 1];
+            if end == INDEFINITE && i[pos..].starts_with(&[0, 0]) {
+                pos += 2;
+            } else if end != INDEFINITE && pos >= end {
+                pos = end;
+            } else {
+                break;
+            }
+            depth -= 1;
+        }
+        if depth == 0 && pos > 0 {
+            return Ok(());
+        }
+        let (rem, header) = match Header::from_ber(&i[pos..]) {
+            Ok(res) => res,
+            // skip the rest of the enclosing encoding (for ex. an Opaque value which is not BER)
+            Err(_) => match depth.checked_sub(1).map(|d| ends[d]) {
+                Some(end) if end != INDEFINITE => {
+                    pos = end;
+                    continue;
+                }
+                _ => return Ok(()),
+            },
+        };
+        if depth >= max_depth {
+            snmp_anomaly!(max_depth = MAX_DEPTH, "value nested too deeply");
+            return Err(SnmpError::MaxDepthExceeded);
+        }
+        let content = i.len() - rem.len();
+        // Opaque (APPLICATION 4) usually wraps another BER-encoded value
+        let nested = header.is_constructed() || (header.is_application() && header.tag().0 == 4);
+        match header.length() {
+            Length::Definite(len) => {
+                let end = match content.checked_add(len) {
+                    Some(end) if end <= i.len() => end,
+                    _ => return Ok(()),
+                };
+                if nested && len > 0 {
+                    ends[depth] = end;
+                    depth += 1;
+                    pos = content;
+                } else {
+                    pos = end;
+                }
+            }
+            Length::Indefinite => {
+                ends[depth] = INDEFINITE;
+                depth += 1;
+                pos = content;
+            }
+        }
+    }
+}
+
+fn parse_varbind_value(i: &[u8]) -> IResult<&[u8], VarBindValue<'_>, SnmpError> {
+    check_value_depth(i)?;
+    let (rem, any) = Any::from_ber(i).map_err(Err::convert)?;
+    let val = VarBindValue::try_from(any).map_err(|e| Err::Error(e.into()))?;
+    Ok((rem, val))
+}
+
+fn parse_varbind(i: &[u8]) -> IResult<&[u8], SnmpVariable<'_>, SnmpError> {
+    Sequence::from_ber_and_then(i, |i| {
+        let (i, oid) = context("name", Oid::from_ber)(i)?;
+        let (i, val) = context("value", parse_varbind_value)(i)?;
+        Ok((i, SnmpVariable { oid, val }))
+    })
+}
+
+fn parse_varbind_list(i: &[u8]) -> IResult<&[u8], Vec<SnmpVariable<'_>>, SnmpError> {
+    Sequence::from_ber_and_then(i, |mut i| {
+        let mut vars = Vec::new();
+        while !i.is_empty() {
+            let index = vars.len();
+            let (rem, var) =
+                context_with(|| Cow::Owned(format!("varbind[{}]", index)), parse_varbind)(i)?;
+            vars.push(var);
+            i = rem;
+        }
+        Ok((i, vars))
+    })
 }
 
 /// <pre>
@@ -440,6 +1343,139 @@ impl<'a> TryFrom<Any<'a>> for NetworkAddress {
     }
 }
 
+// Integration with asn1-rs: tags, DER constraints and DER parsing
+//
+// `Tagged` only carries the tag number: PDU tags are context-specific, and application types
+// (IpAddress, Counter32, etc.) use the application class.
+
+impl<'a> Tagged for SnmpMessage<'a> {
+    const TAG: Tag = Tag::Sequence;
+}
+
+impl<'a> DynTagged for SnmpGenericPdu<'a> {
+    fn tag(&self) -> Tag {
+        Tag(self.pdu_type.0)
+    }
+}
+
+impl<'a> Tagged for SnmpBulkPdu<'a> {
+    const TAG: Tag = Tag(PduType::GetBulkRequest.0);
+}
+
+impl<'a> Tagged for SnmpTrapPdu<'a> {
+    const TAG: Tag = Tag(PduType::TrapV1.0);
+}
+
+impl<'a> DynTagged for SnmpPdu<'a> {
+    fn tag(&self) -> Tag {
+        Tag(self.pdu_type().0)
+    }
+}
+
+impl<'a> Tagged for SnmpVariable<'a> {
+    const TAG: Tag = Tag::Sequence;
+}
+
+impl<'a> CheckDerConstraints for SnmpVariable<'a> {
+    fn check_constraints(any: &Any) -> asn1_rs::Result<()> {
+        any.header.assert_constructed()?;
+        any.header.length().assert_definite()?;
+        let (rem, oid) = Any::from_der(any.data)?;
+        Oid::check_constraints(&oid)?;
+        let (_, val) = Any::from_der(rem)?;
+        VarBindValue::check_constraints(&val)
+    }
+}
+
+impl<'a> DerAutoDerive for SnmpVariable<'a> {}
+
+impl<'a> DynTagged for VarBindValue<'a> {
+    fn tag(&self) -> Tag {
+        match self {
+            VarBindValue::Value(obj) => obj.tag(),
+            VarBindValue::Unspecified => Tag::Null,
+            VarBindValue::NoSuchObject => Tag(0),
+            VarBindValue::NoSuchInstance => Tag(1),
+            VarBindValue::EndOfMibView => Tag(2),
+        }
+    }
+}
+
+impl<'a> CheckDerConstraints for VarBindValue<'a> {
+    fn check_constraints(any: &Any) -> asn1_rs::Result<()> {
+        if any.header.is_contextspecific() {
+            // exceptions are IMPLICIT NULL
+            Null::check_constraints(any)
+        } else {
+            ObjectSyntax::check_constraints(any)
+        }
+    }
+}
+
+impl<'a> DerAutoDerive for VarBindValue<'a> {}
+
+impl<'a> DynTagged for ObjectSyntax<'a> {
+    fn tag(&self) -> Tag {
+        match self {
+            ObjectSyntax::Number(_) => Tag::Integer,
+            ObjectSyntax::String(_) => Tag::OctetString,
+            ObjectSyntax::Object(_) => Tag::Oid,
+            ObjectSyntax::BitString(_) => Tag::BitString,
+            ObjectSyntax::Empty => Tag::Null,
+            ObjectSyntax::Real(_) => Tag::RealType,
+            ObjectSyntax::Enumerated(_) => Tag::Enumerated,
+            ObjectSyntax::UnknownSimple(any) | ObjectSyntax::UnknownApplication(any) => any.tag(),
+            ObjectSyntax::IpAddress(_) => Tag(0),
+            ObjectSyntax::Counter32(_) => Tag(1),
+            ObjectSyntax::Gauge32(_) => Tag(2),
+            ObjectSyntax::TimeTicks(_) => Tag(3),
+            ObjectSyntax::Opaque(_) => Tag(4),
+            ObjectSyntax::NsapAddress(_) => Tag(5),
+            ObjectSyntax::Counter64(_) => Tag(6),
+            ObjectSyntax::UInteger32(_) => Tag(7),
+            ObjectSyntax::Custom(value) => value.raw.tag(),
+        }
+    }
+}
+
+impl<'a> CheckDerConstraints for ObjectSyntax<'a> {
+    fn check_constraints(any: &Any) -> asn1_rs::Result<()> {
+        if any.header.is_application() {
+            match any.tag().0 {
+                0 => NetworkAddress::check_constraints(any),
+                1..=3 | 7 => u32::check_constraints(any),
+                4 | 5 => <&[u8]>::check_constraints(any),
+                6 => u64::check_constraints(any),
+                _ => any.header.length().assert_definite(),
+            }
+        } else {
+            match any.tag() {
+                Tag::Integer => i32::check_constraints(any),
+                Tag::OctetString => <&[u8]>::check_constraints(any),
+                Tag::Oid => Oid::check_constraints(any),
+                Tag::BitString => BitString::check_constraints(any),
+                Tag::Null => Null::check_constraints(any),
+                _ => any.header.length().assert_definite(),
+            }
+        }
+    }
+}
+
+impl<'a> DerAutoDerive for ObjectSyntax<'a> {}
+
+impl Tagged for NetworkAddress {
+    const TAG: Tag = Tag(0);
+}
+
+impl CheckDerConstraints for NetworkAddress {
+    fn check_constraints(any: &Any) -> asn1_rs::Result<()> {
+        any.header.assert_primitive()?;
+        any.header.length().assert_definite()
+    }
+}
+
+impl DerAutoDerive for NetworkAddress {}
+
 /// <pre>
 /// TimeTicks ::=
 ///     [APPLICATION 3]
@@ -450,11 +1486,11 @@ fn parse_timeticks(i: &[u8]) -> IResult<&[u8], TimeTicks, Error> {
     Ok((rem, tagged.into_inner()))
 }
 
-fn parse_snmp_v1_generic_pdu(pdu: &[u8], tag: PduType) -> IResult<&[u8], SnmpPdu, SnmpError> {
-    let (i, req_id) = u32::from_ber(pdu).map_err(Err::convert)?;
-    let (i, err) = map(u32::from_ber, ErrorStatus)(i).map_err(Err::convert)?;
-    let (i, err_index) = u32::from_ber(i).map_err(Err::convert)?;
-    let (i, var) = parse_varbind_list(i).map_err(Err::convert)?;
+fn parse_snmp_v1_generic_pdu(pdu: &[u8], tag: PduType) -> IResult<&[u8], SnmpPdu<'_>, SnmpError> {
+    let (i, req_id) = context("request_id", u32::from_ber)(pdu)?;
+    let (i, err) = context("error_status", map(u32::from_ber, ErrorStatus))(i)?;
+    let (i, err_index) = context("error_index", u32::from_ber)(i)?;
+    let (i, var) = context("varbinds", parse_varbind_list)(i)?;
     let pdu = SnmpPdu::Generic(SnmpGenericPdu {
         pdu_type: tag,
         req_id,
@@ -465,11 +1501,11 @@ fn parse_snmp_v1_generic_pdu(pdu: &[u8], tag: PduType) -> IResult<&[u8], SnmpPdu
     Ok((i, pdu))
 }
 
-fn parse_snmp_v1_bulk_pdu(i: &[u8]) -> IResult<&[u8], SnmpPdu, SnmpError> {
-    let (i, req_id) = u32::from_ber(i).map_err(Err::convert)?;
-    let (i, non_repeaters) = u32::from_ber(i).map_err(Err::convert)?;
-    let (i, max_repetitions) = u32::from_ber(i).map_err(Err::convert)?;
-    let (i, var) = parse_varbind_list(i).map_err(Err::convert)?;
+fn parse_snmp_v1_bulk_pdu(i: &[u8]) -> IResult<&[u8], SnmpPdu<'_>, SnmpError> {
+    let (i, req_id) = context("request_id", u32::from_ber)(i)?;
+    let (i, non_repeaters) = context("non_repeaters", u32::from_ber)(i)?;
+    let (i, max_repetitions) = context("max_repetitions", u32::from_ber)(i)?;
+    let (i, var) = context("varbinds", parse_varbind_list)(i)?;
     let pdu = SnmpBulkPdu {
         req_id,
         non_repeaters,
@@ -479,13 +1515,13 @@ fn parse_snmp_v1_bulk_pdu(i: &[u8]) -> IResult<&[u8], SnmpPdu, SnmpError> {
     Ok((i, SnmpPdu::Bulk(pdu)))
 }
 
-fn parse_snmp_v1_trap_pdu(i: &[u8]) -> IResult<&[u8], SnmpPdu, SnmpError> {
-    let (i, enterprise) = Oid::from_ber(i).map_err(Err::convert)?;
-    let (i, agent_addr) = NetworkAddress::from_ber(i).map_err(Err::convert)?;
-    let (i, generic_trap) = u32::from_ber(i).map_err(Err::convert)?;
-    let (i, specific_trap) = u32::from_ber(i).map_err(Err::convert)?;
-    let (i, timestamp) = parse_timeticks(i).map_err(Err::convert)?;
-    let (i, var) = parse_varbind_list(i).map_err(Err::convert)?;
+fn parse_snmp_v1_trap_pdu(i: &[u8]) -> IResult<&[u8], SnmpPdu<'_>, SnmpError> {
+    let (i, enterprise) = context("enterprise", Oid::from_ber)(i)?;
+    let (i, agent_addr) = context("agent_addr", NetworkAddress::from_ber)(i)?;
+    let (i, generic_trap) = context("generic_trap", u32::from_ber)(i)?;
+    let (i, specific_trap) = context("specific_trap", u32::from_ber)(i)?;
+    let (i, timestamp) = context("time_stamp", parse_timeticks)(i)?;
+    let (i, var) = context("varbinds", parse_varbind_list)(i)?;
     let pdu = SnmpTrapPdu {
         enterprise,
         agent_addr,
@@ -497,6 +1533,8 @@ fn parse_snmp_v1_trap_pdu(i: &[u8]) -> IResult<&[u8], SnmpPdu, SnmpError> {
     Ok((i, SnmpPdu::TrapV1(pdu)))
 }
 
+#[cfg(feature = "v1")]
+#[cfg_attr(docsrs, doc(cfg(feature = "v1")))]
 /// Parse a SNMP v1 message.
 ///
 /// Top-level message
@@ -517,6 +1555,12 @@ fn parse_snmp_v1_trap_pdu(i: &[u8]) -> IResult<&[u8], SnmpPdu, SnmpError> {
 ///         }
 /// </pre>
 ///
+/// # Errors
+///
+/// If the input is not a SEQUENCE, or if the version is not 0 (SNMPv1), a recoverable error
+/// (`nom::Err::Error`) is returned, so other parsers can be tried (for ex. using `alt`).
+/// Once the version has been recognized, errors are returned as `nom::Err::Failure`.
+///
 /// Example:
 ///
 /// ```rust
@@ -535,41 +1579,73 @@ fn parse_snmp_v1_trap_pdu(i: &[u8]) -> IResult<&[u8], SnmpPdu, SnmpError> {
 /// }
 /// # }
 /// ```
-pub fn parse_snmp_v1(bytes: &[u8]) -> IResult<&[u8], SnmpMessage, SnmpError> {
-    Sequence::from_der_and_then(bytes, |i| {
-        let (i, version) = u32::from_ber(i).map_err(Err::convert)?;
+pub fn parse_snmp_v1(bytes: &[u8]) -> IResult<&[u8], SnmpMessage<'_>, SnmpError> {
+    snmp_span!("parse_snmp_v1", len = bytes.len());
+    let res = Sequence::from_der_and_then(bytes, |i| {
+        let (i, version) = context("version", u32::from_ber)(i)?;
+        snmp_event!(version, "parse start");
         if version != 0 {
-            return Err(Err::Error(SnmpError::InvalidVersion));
-        }
-        let (i, community) = parse_ber_octetstring_as_str(i).map_err(Err::convert)?;
-        let (i, pdu) = parse_snmp_v1_pdu(i)?;
-        let msg = SnmpMessage {
-            version,
-            community: community.to_string(),
-            pdu,
-        };
-        Ok((i, msg))
-    })
-    //.map_err(Err::convert)
+            return Err(Err::Error(SnmpError::InvalidVersion { found: version }));
+        }
+        parse_snmp_v1_content(i, &ParseOptions::DEFAULT).map_err(cut)
+    });
+    let res = resolve_offsets(res, bytes);
+    trace_parse_result(&res, SnmpMessage::trace_fields);
+    res
 }
 
-pub(crate) fn parse_snmp_v1_pdu(i: &[u8]) -> IResult<&[u8], SnmpPdu, SnmpError> {
+#[cfg(feature = "v1")]
+/// Parse the content of a SNMPv1 message, after the version
+pub(crate) fn parse_snmp_v1_content<'a>(
+    i: &'a [u8],
+    opts: &ParseOptions,
+) -> IResult<&'a [u8], SnmpMessage<'a>, SnmpError> {
+    let (i, community) = context("community", parse_ber_octetstring_as_str)(i)?;
+    let (i, pdu) = context("pdu", parse_snmp_v1_pdu)(i)?;
+    let msg = SnmpMessage {
+        version: 0,
+        community: (opts.store)(community),
+        pdu,
+    };
+    Ok((i, msg))
+}
+
+pub(crate) fn parse_snmp_v1_pdu(i: &[u8]) -> IResult<&[u8], SnmpPdu<'_>, SnmpError> {
     match Header::from_ber(i) {
         Ok((rem, hdr)) => {
+            snmp_event!(pdu_type = ?PduType(hdr.tag().0), "PDU");
             match PduType(hdr.tag().0) {
                 PduType::GetRequest
                 | PduType::GetNextRequest
                 | PduType::Response
                 | PduType::SetRequest => parse_snmp_v1_generic_pdu(rem, PduType(hdr.tag().0)),
                 PduType::TrapV1 => parse_snmp_v1_trap_pdu(rem),
-                _ => Err(Err::Error(SnmpError::InvalidPduType)),
-                // _                       => { return IResult::Error(error_code!(ErrorKind::Custom(SnmpError::InvalidPdu))); },
+                _ => {
+                    snmp_anomaly!(tag = hdr.tag().0, "invalid PDU type");
+                    Err(Err::Error(SnmpError::InvalidPduType { tag: hdr.tag().0 }))
+                }
             }
         }
         Err(e) => Err(Err::convert(e)),
     }
 }
 
+impl<'a> FromBer<'a, SnmpError> for SnmpPdu<'a> {
+    /// Parse a PDU of any type (SNMPv1 or SNMPv2), without the enclosing message
+    ///
+    /// This can be used to parse a PDU after decrypting a SNMPv3 message, or when received using
+    /// another transport. Error offsets are relative to the start of the PDU.
+    fn from_ber(bytes: &'a [u8]) -> ParseResult<'a, Self, SnmpError> {
+        snmp_span!("parse_snmp_pdu", len = bytes.len());
+        let (rem, _) = Any::from_ber(bytes).map_err(Err::convert)?;
+        let pdu_bytes = &bytes[..bytes.len() - rem.len()];
+        let res = parse_snmp_v2c_pdu(pdu_bytes).map(|(_, pdu)| (rem, pdu));
+        resolve_offsets(res, bytes)
+    }
+}
+
+#[cfg(feature = "v2c")]
+#[cfg_attr(docsrs, doc(cfg(feature = "v2c")))]
 /// Parse a SNMP v2c message.
 ///
 /// Top-level message
@@ -589,26 +1665,47 @@ pub(crate) fn parse_snmp_v1_pdu(i: &[u8]) -> IResult<&[u8], SnmpPdu, SnmpError>
 ///                 ANY
 ///         }
 /// </pre>
-pub fn parse_snmp_v2c(bytes: &[u8]) -> IResult<&[u8], SnmpMessage, SnmpError> {
-    Sequence::from_der_and_then(bytes, |i| {
-        let (i, version) = u32::from_ber(i).map_err(Err::convert)?;
+///
+/// # Errors
+///
+/// If the input is not a SEQUENCE, or if the version is not 1 (SNMPv2c), a recoverable error
+/// (`nom::Err::Error`) is returned, so other parsers can be tried (for ex. using `alt`).
+/// Once the version has been recognized, errors are returned as `nom::Err::Failure`.
+pub fn parse_snmp_v2c(bytes: &[u8]) -> IResult<&[u8], SnmpMessage<'_>, SnmpError> {
+    snmp_span!("parse_snmp_v2c", len = bytes.len());
+    let res = Sequence::from_der_and_then(bytes, |i| {
+        let (i, version) = context("version", u32::from_ber)(i)?;
+        snmp_event!(version, "parse start");
         if version != 1 {
-            return Err(Err::Error(SnmpError::InvalidVersion));
-        }
-        let (i, community) = parse_ber_octetstring_as_str(i).map_err(Err::convert)?;
-        let (i, pdu) = parse_snmp_v2c_pdu(i)?;
-        let msg = SnmpMessage {
-            version,
-            community: community.to_string(),
-            pdu,
-        };
-        Ok((i, msg))
-    })
+            return Err(Err::Error(SnmpError::InvalidVersion { found: version }));
+        }
+        parse_snmp_v2c_content(i, &ParseOptions::DEFAULT).map_err(cut)
+    });
+    let res = resolve_offsets(res, bytes);
+    trace_parse_result(&res, SnmpMessage::trace_fields);
+    res
 }
 
-pub(crate) fn parse_snmp_v2c_pdu(i: &[u8]) -> IResult<&[u8], SnmpPdu, SnmpError> {
+#[cfg(feature = "v2c")]
+/// Parse the content of a SNMPv2c message, after the version
+pub(crate) fn parse_snmp_v2c_content<'a>(
+    i: &'a [u8],
+    opts: &ParseOptions,
+) -> IResult<&'a [u8], SnmpMessage<'a>, SnmpError> {
+    let (i, community) = context("community", parse_ber_octetstring_as_str)(i)?;
+    let (i, pdu) = context("pdu", parse_snmp_v2c_pdu)(i)?;
+    let msg = SnmpMessage {
+        version: 1,
+        community: (opts.store)(community),
+        pdu,
+    };
+    Ok((i, msg))
+}
+
+pub(crate) fn parse_snmp_v2c_pdu(i: &[u8]) -> IResult<&[u8], SnmpPdu<'_>, SnmpError> {
     match Header::from_ber(i) {
         Ok((rem, hdr)) => {
+            snmp_event!(pdu_type = ?PduType(hdr.tag().0), "PDU");
             match PduType(hdr.tag().0) {
                 PduType::GetRequest
                 | PduType::GetNextRequest
@@ -619,8 +1716,10 @@ pub(crate) fn parse_snmp_v2c_pdu(i: &[u8]) -> IResult<&[u8], SnmpPdu, SnmpError>
                 | PduType::Report => parse_snmp_v1_generic_pdu(rem, PduType(hdr.tag().0)),
                 PduType::GetBulkRequest => parse_snmp_v1_bulk_pdu(rem),
                 PduType::TrapV1 => parse_snmp_v1_trap_pdu(rem),
-                _ => Err(Err::Error(SnmpError::InvalidPduType)),
-                // _                       => { return IResult::Error(error_code!(ErrorKind::Custom(SnmpError::InvalidPdu))); },
+                _ => {
+                    snmp_anomaly!(tag = hdr.tag().0, "invalid PDU type");
+                    Err(Err::Error(SnmpError::InvalidPduType { tag: hdr.tag().0 }))
+                }
             }
         }
         Err(e) => Err(Err::convert(e)),
@@ -0,0 +1,192 @@
+//! SNMPv2 party-based (historic) Parser
+//!
+//! Party-based SNMPv2 is defined in the following RFCs:
+//!   - [RFC1445](https://tools.ietf.org/html/rfc1445): Administrative Model for version 2 of the
+//!     Simple Network Management Protocol (SNMPv2)
+//!   - [RFC1446](https://tools.ietf.org/html/rfc1446): Security Protocols for version 2 of the
+//!     Simple Network Management Protocol (SNMPv2)
+//!
+//! These RFCs are historic, and were replaced by SNMPv2c and SNMPv3. Such messages can still be
+//! found in old captures, where they are sent with version 2.
+
+use asn1_rs::{Any, Class, Error, FromBer, Oid, Sequence, Tag, Tagged};
+use nom::{Err, IResult};
+
+use crate::error::{context, cut, finish, resolve_offsets, SnmpError};
+use crate::instrument::trace_parse_result;
+use crate::snmp::{parse_snmp_v2c_pdu, PduType, SnmpPdu};
+
+/// A party-based SNMPv2 message
+///
+/// <pre>
+/// SnmpPrivMsg ::= [1] IMPLICIT SEQUENCE {
+///     privDst
+///         OBJECT IDENTIFIER,
+///     privData
+///         [1] IMPLICIT OCTET STRING
+/// }
+/// </pre>
+#[derive(Debug, PartialEq)]
+pub struct SnmpV2pMessage<'a> {
+    /// Version, as raw-encoded: 2 for party-based SNMPv2
+    pub version: u32,
+    pub priv_dst: Oid<'a>,
+    /// Raw content of privData
+    pub priv_data: &'a [u8],
+    /// Decoded content of privData, or `None` if it is encrypted
+    pub auth_msg: Option<SnmpV2pAuthMsg<'a>>,
+}
+
+/// <pre>
+/// SnmpAuthMsg ::= [1] IMPLICIT SEQUENCE {
+///     authInfo
+///         ANY, -- defined by authentication protocol
+///     authData
+///         SnmpMgmtCom
+/// }
+///
+/// SnmpMgmtCom ::= [2] IMPLICIT SEQUENCE {
+///     dstParty
+///         OBJECT IDENTIFIER,
+///     srcParty
+///         OBJECT IDENTIFIER,
+///     context
+///         OBJECT IDENTIFIER,
+///     pdu
+///         PDUs
+/// }
+/// </pre>
+#[derive(Debug, PartialEq)]
+pub struct SnmpV2pAuthMsg<'a> {
+    pub auth_info: Any<'a>,
+    pub dst_party: Oid<'a>,
+    pub src_party: Oid<'a>,
+    pub context: Oid<'a>,
+    pub pdu: SnmpPdu<'a>,
+}
+
+impl<'a> SnmpV2pMessage<'a> {
+    /// Parse a party-based SNMPv2 message, returning the message and the number of bytes read
+    ///
+    /// This is equivalent to [`parse_snmp_v2p`], for callers not using `nom`.
+    pub fn parse(bytes: &'a [u8]) -> Result<(Self, usize), SnmpError> {
+        finish(parse_snmp_v2p(bytes), bytes)
+    }
+
+    /// Return the PDU, unless the message is encrypted
+    pub fn pdu(&self) -> Option<&SnmpPdu<'a>> {
+        self.auth_msg.as_ref().map(|auth| &auth.pdu)
+    }
+
+    pub(crate) fn trace_fields(&self) -> (Option<PduType>, usize) {
+        match self.pdu() {
+            Some(pdu) => (Some(pdu.pdu_type()), pdu.vars_iter().count()),
+            None => (None, 0),
+        }
+    }
+}
+
+/// Parse a party-based SNMPv2 message
+///
+/// <pre>
+/// Message ::=
+///         SEQUENCE {
+///             version
+///                 INTEGER {
+///                     version(2)
+///                 },
+///
+///             data
+///                 SnmpPrivMsg
+///         }
+/// </pre>
+///
+/// The privacy protocol is not indicated in the message. If privData does not start with a
+/// SnmpAuthMsg, it is considered encrypted and is not decoded.
+///
+/// # Errors
+///
+/// If the input is not a SEQUENCE, or if the version is not 2, a recoverable error
+/// (`nom::Err::Error`) is returned, so other parsers can be tried (for ex. using `alt`).
+/// Once the version has been recognized, errors are returned as `nom::Err::Failure`.
+pub fn parse_snmp_v2p(bytes: &[u8]) -> IResult<&[u8], SnmpV2pMessage<'_>, SnmpError> {
+    snmp_span!("parse_snmp_v2p", len = bytes.len());
+    let res = Sequence::from_der_and_then(bytes, |i| {
+        let (i, version) = context("version", u32::from_ber)(i)?;
+        snmp_event!(version, "parse start");
+        if version != 2 {
+            return Err(Err::Error(SnmpError::InvalidVersion { found: version }));
+        }
+        parse_snmp_v2p_content(i).map_err(cut)
+    });
+    let res = resolve_offsets(res, bytes);
+    trace_parse_result(&res, SnmpV2pMessage::trace_fields);
+    res
+}
+
+/// Parse the content of a party-based SNMPv2 message, after the version
+pub(crate) fn parse_snmp_v2p_content(i: &[u8]) -> IResult<&[u8], SnmpV2pMessage<'_>, SnmpError> {
+    let (rem, priv_msg) = context("priv_msg", implicit_tagged(1, true))(i)?;
+    let (i, priv_dst) = context("priv_msg.priv_dst", Oid::from_ber)(priv_msg.data)?;
+    let (_, priv_data) = context("priv_msg.priv_data", implicit_tagged(1, false))(i)?;
+    let auth_msg = match Any::from_ber(priv_data.data) {
+        Ok((_, any))
+            if any.class() == Class::ContextSpecific
+                && any.tag() == Tag(1)
+                && any.header.is_constructed() =>
+        {
+            let (_, auth) = context("priv_msg.priv_data", parse_snmp_v2p_auth_msg)(any.data)?;
+            Some(auth)
+        }
+        _ => {
+            snmp_event!("privData is not a SnmpAuthMsg, assuming it is encrypted");
+            None
+        }
+    };
+    let msg = SnmpV2pMessage {
+        version: 2,
+        priv_dst,
+        priv_data: priv_data.data,
+        auth_msg,
+    };
+    Ok((rem, msg))
+}
+
+/// Parse the content of a SnmpAuthMsg
+fn parse_snmp_v2p_auth_msg(i: &[u8]) -> IResult<&[u8], SnmpV2pAuthMsg<'_>, SnmpError> {
+    let (i, auth_info) = context("auth_info", Any::from_ber)(i)?;
+    let (rem, mgmt) = context("auth_data", implicit_tagged(2, true))(i)?;
+    let (i, dst_party) = context("auth_data.dst_party", Oid::from_ber)(mgmt.data)?;
+    let (i, src_party) = context("auth_data.src_party", Oid::from_ber)(i)?;
+    let (i, ctx) = context("auth_data.context", Oid::from_ber)(i)?;
+    let (_, pdu) = context("auth_data.pdu", parse_snmp_v2c_pdu)(i)?;
+    let auth = SnmpV2pAuthMsg {
+        auth_info,
+        dst_party,
+        src_party,
+        context: ctx,
+        pdu,
+    };
+    Ok((rem, auth))
+}
+
+/// Read an object with a context-specific IMPLICIT tag
+fn implicit_tagged<'a>(
+    tag: u32,
+    constructed: bool,
+) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], Any<'a>, Error> {
+    move |i| {
+        let (rem, any) = Any::from_ber(i)?;
+        any.class().assert_eq(Class::ContextSpecific)?;
+        any.tag().assert_eq(Tag(tag))?;
+        if any.header.is_constructed() != constructed {
+            return Err(Err::Error(Error::ConstructUnexpected));
+        }
+        Ok((rem, any))
+    }
+}
+
+/// `SnmpPrivMsg` has a context-specific tag
+impl<'a> Tagged for SnmpV2pMessage<'a> {
+    const TAG: Tag = Tag(1);
+}
@@ -0,0 +1,190 @@
+//! SNMPv2u (historic) Parser
+//!
+//! SNMPv2u is defined in [RFC1910](https://tools.ietf.org/html/rfc1910): User-based Security
+//! Model for SNMPv2.
+//!
+//! SNMPv2u messages use version 2, like party-based SNMPv2 messages (see
+//! [`snmpv2p`](crate::snmpv2p)), but the community is replaced by an OCTET STRING containing the
+//! user-based security parameters.
+
+use asn1_rs::{FromBer, Sequence, Tag, Tagged};
+use nom::bytes::complete::take;
+use nom::combinator::{map, rest};
+use nom::number::complete::{be_u16, be_u32, be_u8};
+use nom::{Err, IResult};
+
+use crate::error::{context, cut, finish, resolve_offsets, SnmpError};
+use crate::instrument::trace_parse_result;
+use crate::snmp::{parse_snmp_v2c_pdu, PduType, SnmpPdu};
+
+/// An SNMPv2u message
+///
+/// <pre>
+/// Message ::=
+///     SEQUENCE {
+///          version
+///             INTEGER { v2 (2) },
+///
+///          parameters
+///             OCTET STRING,
+///          -- <model=1>
+///          --     <qoS><agentID><agentBoots><agentTime><maxSize>
+///          --     <userLen><userName><authLen><authDigest>
+///          --     <contextSelector>
+///
+///          data
+///             ANY -- encrypted data
+///     }
+/// </pre>
+#[derive(Debug, PartialEq)]
+pub struct SnmpV2uMessage<'a> {
+    /// Version, as raw-encoded: 2 for SNMPv2u
+    pub version: u32,
+    pub parameters: SnmpV2uParameters<'a>,
+    pub data: SnmpV2uData<'a>,
+}
+
+/// User-based security parameters of a SNMPv2u message
+#[derive(Debug, PartialEq)]
+pub struct SnmpV2uParameters<'a> {
+    pub model: u8,
+    pub qos: u8,
+    pub agent_id: &'a [u8],
+    pub agent_boots: u32,
+    pub agent_time: u32,
+    pub max_size: u16,
+    pub user_name: &'a [u8],
+    pub auth_digest: &'a [u8],
+    pub context_selector: &'a [u8],
+}
+
+impl<'a> SnmpV2uParameters<'a> {
+    pub fn is_authenticated(&self) -> bool {
+        self.qos & 0b001 != 0
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.qos & 0b010 != 0
+    }
+
+    pub fn is_reportable(&self) -> bool {
+        self.qos & 0b100 != 0
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SnmpV2uData<'a> {
+    Plaintext(SnmpPdu<'a>),
+    Encrypted(&'a [u8]),
+}
+
+impl<'a> SnmpV2uMessage<'a> {
+    /// Parse a SNMPv2u message, returning the message and the number of bytes read
+    ///
+    /// This is equivalent to [`parse_snmp_v2u`], for callers not using `nom`.
+    pub fn parse(bytes: &'a [u8]) -> Result<(Self, usize), SnmpError> {
+        finish(parse_snmp_v2u(bytes), bytes)
+    }
+
+    /// Return the PDU, unless the message is encrypted
+    pub fn pdu(&self) -> Option<&SnmpPdu<'a>> {
+        match self.data {
+            SnmpV2uData::Plaintext(ref pdu) => Some(pdu),
+            SnmpV2uData::Encrypted(_) => None,
+        }
+    }
+
+    pub(crate) fn trace_fields(&self) -> (Option<PduType>, usize) {
+        match self.pdu() {
+            Some(pdu) => (Some(pdu.pdu_type()), pdu.vars_iter().count()),
+            None => (None, 0),
+        }
+    }
+}
+
+/// Parse a SNMPv2u message
+///
+/// # Errors
+///
+/// If the input is not a SEQUENCE, or if the version is not 2, a recoverable error
+/// (`nom::Err::Error`) is returned, so other parsers can be tried (for ex. using `alt`).
+/// Once the version has been recognized, errors are returned as `nom::Err::Failure`.
+///
+/// Party-based SNMPv2 messages also use version 2, and are rejected with a failure.
+pub fn parse_snmp_v2u(bytes: &[u8]) -> IResult<&[u8], SnmpV2uMessage<'_>, SnmpError> {
+    snmp_span!("parse_snmp_v2u", len = bytes.len());
+    let res = Sequence::from_der_and_then(bytes, |i| {
+        let (i, version) = context("version", u32::from_ber)(i)?;
+        snmp_event!(version, "parse start");
+        if version != 2 {
+            return Err(Err::Error(SnmpError::InvalidVersion { found: version }));
+        }
+        parse_snmp_v2u_content(i).map_err(cut)
+    });
+    let res = resolve_offsets(res, bytes);
+    trace_parse_result(&res, SnmpV2uMessage::trace_fields);
+    res
+}
+
+/// Parse the content of a SNMPv2u message, after the version
+pub(crate) fn parse_snmp_v2u_content(i: &[u8]) -> IResult<&[u8], SnmpV2uMessage<'_>, SnmpError> {
+    let (i, parameters) = context("parameters", parse_snmp_v2u_parameters)(i)?;
+    snmp_event!(
+        qos = parameters.qos,
+        encrypted = parameters.is_encrypted(),
+        "SNMPv2u parameters"
+    );
+    let (i, data) = if parameters.is_encrypted() {
+        context("data", map(<&[u8]>::from_ber, SnmpV2uData::Encrypted))(i)?
+    } else {
+        context("data", map(parse_snmp_v2c_pdu, SnmpV2uData::Plaintext))(i)?
+    };
+    let msg = SnmpV2uMessage {
+        version: 2,
+        parameters,
+        data,
+    };
+    Ok((i, msg))
+}
+
+/// Parse the parameters OCTET STRING
+fn parse_snmp_v2u_parameters(i: &[u8]) -> IResult<&[u8], SnmpV2uParameters<'_>, SnmpError> {
+    let (rem, params) = <&[u8]>::from_ber(i).map_err(Err::convert)?;
+    match parse_snmp_v2u_parameters_content(params) {
+        Ok((_, params)) if params.model == 1 => Ok((rem, params)),
+        _ => {
+            snmp_anomaly!("invalid SNMPv2u parameters");
+            Err(Err::Error(SnmpError::InvalidSecurityModel))
+        }
+    }
+}
+
+fn parse_snmp_v2u_parameters_content(i: &[u8]) -> IResult<&[u8], SnmpV2uParameters<'_>, SnmpError> {
+    let (i, model) = be_u8(i)?;
+    let (i, qos) = be_u8(i)?;
+    let (i, agent_id) = take(12usize)(i)?;
+    let (i, agent_boots) = be_u32(i)?;
+    let (i, agent_time) = be_u32(i)?;
+    let (i, max_size) = be_u16(i)?;
+    let (i, user_len) = be_u8(i)?;
+    let (i, user_name) = take(user_len)(i)?;
+    let (i, auth_len) = be_u8(i)?;
+    let (i, auth_digest) = take(auth_len)(i)?;
+    let (i, context_selector) = rest(i)?;
+    let params = SnmpV2uParameters {
+        model,
+        qos,
+        agent_id,
+        agent_boots,
+        agent_time,
+        max_size,
+        user_name,
+        auth_digest,
+        context_selector,
+    };
+    Ok((i, params))
+}
+
+impl<'a> Tagged for SnmpV2uMessage<'a> {
+    const TAG: Tag = Tag::Sequence;
+}
@@ -8,38 +8,85 @@
 //! See also:
 //!   - [RFC2578](https://tools.ietf.org/html/rfc2578): Structure of Management Information Version 2 (SMIv2)
 
-use asn1_rs::{Error, FromBer, Sequence};
-use nom::combinator::{map, map_res};
+use asn1_rs::{DynTagged, Error, FromBer, Header, Length, ParseResult, Sequence, Tag, Tagged};
+use core::fmt;
+use nom::combinator::map;
 use nom::{Err, IResult};
-use std::fmt;
 
-use crate::error::SnmpError;
-use crate::snmp::{parse_snmp_v2c_pdu, SnmpPdu};
+use crate::error::{context, cut, finish, resolve_offsets, SnmpError};
+use crate::format::CompactMessage;
+use crate::instrument::trace_parse_result;
+use crate::snmp::{
+    parse_snmp_v2c_pdu, ErrorStatus, ParseOptions, PduType, SnmpGenericPdu, SnmpPdu, SnmpVariable,
+};
+use crate::usm::{parse_ber_u32, parse_usm_security_parameters_with};
 pub use crate::usm::{parse_usm_security_parameters, UsmSecurityParameters};
+use alloc::vec::Vec;
 
+/// Security model of a SNMPv3 message
+///
+/// Values are assigned as defined in [RFC3411](https://tools.ietf.org/html/rfc3411) section
+/// 5 (`SnmpSecurityModel`): 0 is reserved for "any", values up to 255 are reserved for
+/// standards-track models, and larger values are enterprise-specific (enterprise ID * 256 +
+/// model number).
 #[derive(Clone, Copy, Eq, PartialEq)]
 pub struct SecurityModel(pub u32);
 
 #[allow(non_upper_case_globals)]
 impl SecurityModel {
+    pub const Any: SecurityModel = SecurityModel(0);
     pub const SnmpV1: SecurityModel = SecurityModel(1);
     pub const SnmpV2c: SecurityModel = SecurityModel(2);
     pub const USM: SecurityModel = SecurityModel(3);
+    /// Transport Security Model ([RFC5591](https://tools.ietf.org/html/rfc5591))
+    pub const TSM: SecurityModel = SecurityModel(4);
+
+    /// Return `true` if the value is in the range of standards-track models (1 to 255)
+    pub fn is_standard(self) -> bool {
+        (1..=255).contains(&self.0)
+    }
+
+    /// Return the enterprise ID and model number of enterprise-specific models
+    pub fn enterprise(self) -> Option<(u32, u8)> {
+        if self.0 > 255 {
+            Some((self.0 >> 8, self.0 as u8))
+        } else {
+            None
+        }
+    }
 }
 
 impl fmt::Debug for SecurityModel {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.0 {
+            0 => f.write_str("Any"),
             1 => f.write_str("SnmpV1"),
             2 => f.write_str("SnmpV2c"),
             3 => f.write_str("USM"),
+            4 => f.write_str("TSM"),
             n => f.debug_tuple("SecurityModel").field(&n).finish(),
         }
     }
 }
 
+/// Format the model name, for ex. `USM`, or `reserved(n)` for unknown standards-track values and
+/// `enterprise(id:model)` for enterprise-specific values
+impl fmt::Display for SecurityModel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            0 => f.write_str("any"),
+            1 => f.write_str("SNMPv1"),
+            2 => f.write_str("SNMPv2c"),
+            3 => f.write_str("USM"),
+            4 => f.write_str("TSM"),
+            n if self.is_standard() => write!(f, "reserved({})", n),
+            n => write!(f, "enterprise({}:{})", n >> 8, n & 0xff),
+        }
+    }
+}
+
 impl<'a> FromBer<'a> for SecurityModel {
-    fn from_ber(bytes: &'a [u8]) -> asn1_rs::ParseResult<'a, Self> {
+    fn from_ber(bytes: &'a [u8]) -> ParseResult<'a, Self> {
         map(u32::from_ber, SecurityModel)(bytes)
     }
 }
@@ -51,6 +98,27 @@ pub enum SecurityParameters<'a> {
     USM(UsmSecurityParameters<'a>),
 }
 
+/// The `localEngineID` value of the context engine ID, used when the engine ID of the agent is
+/// not known (see [RFC5343](https://tools.ietf.org/html/rfc5343) section 3.1)
+pub const LOCAL_ENGINE_ID: &[u8] = &[0x80, 0x00, 0x00, 0x00, 0x06];
+
+/// Relation between the context engine ID and the authoritative engine ID of a message, see
+/// [`SnmpV3Message::context_engine_id_status`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContextEngineIdStatus {
+    /// The context engine ID is the authoritative engine ID
+    Match,
+    /// The context engine ID is `localEngineID` (RFC5343)
+    Local,
+    /// The authoritative engine ID is empty (engine ID discovery, RFC3414 section 4)
+    Discovery,
+    /// The context engine ID is not the authoritative engine ID
+    Mismatch,
+    /// The engine IDs cannot be compared: the scoped PDU is encrypted, or the security model
+    /// is not USM
+    Unknown,
+}
+
 /// An SNMPv3 message
 #[derive(Debug, PartialEq)]
 pub struct SnmpV3Message<'a> {
@@ -58,9 +126,203 @@ pub struct SnmpV3Message<'a> {
     pub version: u32,
     pub header_data: HeaderData,
     pub security_params: SecurityParameters<'a>,
+    /// Raw content of msgSecurityParameters, as read
+    ///
+    /// This is the exact original encoding of the security parameters (for ex. to verify the
+    /// authentication digest, or for forensic storage). It is not used by the encoder, and is
+    /// empty for messages built manually.
+    pub security_params_raw: &'a [u8],
     pub data: ScopedPduData<'a>,
 }
 
+impl<'a> SnmpV3Message<'a> {
+    /// Parse a SNMPv3 message, returning the message and the number of bytes read
+    ///
+    /// This is equivalent to [`parse_snmp_v3`], for callers not using `nom`.
+    pub fn parse(bytes: &'a [u8]) -> Result<(Self, usize), SnmpError> {
+        finish(parse_snmp_v3(bytes), bytes)
+    }
+
+    /// Test if the privacy flag of the header does not match the scoped PDU data
+    ///
+    /// The scoped PDU data is parsed according to its tag, not the flags, so an encrypted
+    /// message can have plaintext data (or the opposite). Such messages are not valid, and can be
+    /// crafted to confuse monitoring tools.
+    pub fn has_privacy_mismatch(&self) -> bool {
+        let encrypted = self.data.wire_form() == ScopedPduDataForm::OctetString;
+        encrypted != self.header_data.is_encrypted()
+    }
+
+    /// Compare the context engine ID of the scoped PDU to the authoritative engine ID
+    ///
+    /// Both are usually the engine ID of the agent. A mismatch can be legitimate (for ex. when
+    /// a proxy forwards requests to another context engine), but is also a sign of spoofed
+    /// messages, so it is reported as an anomaly.
+    pub fn context_engine_id_status(&self) -> ContextEngineIdStatus {
+        let (usm, scoped_pdu) = match (&self.security_params, &self.data) {
+            (SecurityParameters::USM(usm), ScopedPduData::Plaintext(scoped_pdu)) => {
+                (usm, scoped_pdu)
+            }
+            _ => return ContextEngineIdStatus::Unknown,
+        };
+        if scoped_pdu.ctx_engine_id == LOCAL_ENGINE_ID {
+            ContextEngineIdStatus::Local
+        } else if usm.msg_authoritative_engine_id.is_empty() {
+            ContextEngineIdStatus::Discovery
+        } else if scoped_pdu.ctx_engine_id == usm.msg_authoritative_engine_id {
+            ContextEngineIdStatus::Match
+        } else {
+            snmp_anomaly!(
+                msg_id = self.header_data.msg_id,
+                "context engine ID does not match the authoritative engine ID"
+            );
+            ContextEngineIdStatus::Mismatch
+        }
+    }
+
+    /// Return the security model and raw security parameters, if the security model is not
+    /// decoded by this crate
+    ///
+    /// This can be used to decode security models unknown to the parser.
+    pub fn unknown_security_params(&self) -> Option<(SecurityModel, &'a [u8])> {
+        match self.security_params {
+            SecurityParameters::Raw(raw) => Some((self.header_data.msg_security_model, raw)),
+            SecurityParameters::USM(_) => None,
+        }
+    }
+
+    /// Format the message on one line, with at most `max_varbinds` variables
+    ///
+    /// If `redact_secrets` is `true`, the USM user name is not written. See
+    /// [`CompactMessage`].
+    pub fn fmt_compact(&self, max_varbinds: usize, redact_secrets: bool) -> CompactMessage<'_, 'a> {
+        let principal = match self.security_params {
+            SecurityParameters::USM(ref usm) => Some(("user", usm.msg_user_name.as_bytes())),
+            SecurityParameters::Raw(_) => None,
+        };
+        let pdu = match self.data {
+            ScopedPduData::Plaintext(ref scoped_pdu) => Some(&scoped_pdu.data),
+            ScopedPduData::Encrypted(_) => None,
+        };
+        CompactMessage::new(self.version, principal, pdu, max_varbinds, redact_secrets)
+    }
+
+    /// Test if a Report PDU should be sent when processing of this message fails
+    ///
+    /// Following [RFC3412](https://tools.ietf.org/html/rfc3412) section 7.1, a report is sent
+    /// only if the reportable flag is set and the PDU is of the Confirmed Class. If the PDU is
+    /// encrypted, its class is not known and only the flag is used.
+    pub fn expects_report(&self) -> bool {
+        if !self.header_data.is_reportable() {
+            return false;
+        }
+        match self.data {
+            ScopedPduData::Plaintext(ref scoped_pdu) => {
+                scoped_pdu.data.pdu_type().is_confirmed_class()
+            }
+            ScopedPduData::Encrypted(_) => true,
+        }
+    }
+
+    /// Build the Report message answering this message, with the variables `var` (usually
+    /// the USM or MPD error counter)
+    ///
+    /// The report has the message ID, maximum size and security model of this message, no
+    /// security flags, and the request ID of this message (or 0 if the PDU is encrypted). The
+    /// security parameters are copied without authentication and privacy parameters: the caller
+    /// should set the local engine ID, boots and time before encoding.
+    ///
+    /// Return `None` if this message does not expect a report (see
+    /// [`expects_report`](Self::expects_report)).
+    pub fn report_skeleton(&self, var: Vec<SnmpVariable<'a>>) -> Option<SnmpV3Message<'a>> {
+        if !self.expects_report() {
+            return None;
+        }
+        let security_params = match self.security_params {
+            SecurityParameters::USM(ref usm) => SecurityParameters::USM(UsmSecurityParameters {
+                msg_authoritative_engine_id: usm.msg_authoritative_engine_id,
+                msg_authoritative_engine_boots: usm.msg_authoritative_engine_boots,
+                msg_authoritative_engine_time: usm.msg_authoritative_engine_time,
+                msg_user_name: usm.msg_user_name.clone(),
+                msg_authentication_parameters: &[],
+                msg_privacy_parameters: &[],
+            }),
+            SecurityParameters::Raw(raw) => SecurityParameters::Raw(raw),
+        };
+        let (ctx_engine_id, ctx_engine_name, req_id) = match self.data {
+            ScopedPduData::Plaintext(ref scoped_pdu) => (
+                scoped_pdu.ctx_engine_id,
+                scoped_pdu.ctx_engine_name,
+                scoped_pdu.data.request_id().unwrap_or(0),
+            ),
+            ScopedPduData::Encrypted(_) => (&[][..], &[][..], 0),
+        };
+        Some(SnmpV3Message {
+            version: self.version,
+            header_data: HeaderData {
+                msg_flags: 0,
+                ..self.header_data
+            },
+            security_params,
+            security_params_raw: &[],
+            data: ScopedPduData::Plaintext(ScopedPdu {
+                ctx_engine_id,
+                ctx_engine_name,
+                data: SnmpPdu::Generic(SnmpGenericPdu {
+                    pdu_type: PduType::Report,
+                    req_id,
+                    err: ErrorStatus::NoError,
+                    err_index: 0,
+                    var,
+                }),
+            }),
+        })
+    }
+
+    /// Parse the scoped PDU of an encrypted message, from the decrypted data
+    ///
+    /// Decryption is not done by this crate: `plaintext` must be the result of decrypting the
+    /// encrypted data (see [`ScopedPduData::Encrypted`]) with the privacy protocol of the user.
+    /// Padding bytes after the scoped PDU are ignored.
+    ///
+    /// The result keeps the original encrypted data, so it can be stored as evidence while
+    /// working on the decoded PDU.
+    ///
+    /// # Errors
+    ///
+    /// If the message is not encrypted, `SnmpError::InvalidScopedPduData` is returned. If the
+    /// plaintext cannot be parsed, the parsing error is returned.
+    pub fn parse_decrypted<'b>(
+        &self,
+        plaintext: &'b [u8],
+    ) -> Result<DecryptedScopedPdu<'a, 'b>, SnmpError> {
+        let ciphertext = match self.data {
+            ScopedPduData::Encrypted(ciphertext) => ciphertext,
+            ScopedPduData::Plaintext(_) => return Err(SnmpError::InvalidScopedPduData),
+        };
+        let res = ScopedPdu::from_ber(plaintext).map_err(Err::convert);
+        let (scoped_pdu, _) = finish(res, plaintext)?;
+        Ok(DecryptedScopedPdu {
+            ciphertext,
+            scoped_pdu,
+        })
+    }
+
+    pub(crate) fn trace_fields(&self) -> (Option<PduType>, usize) {
+        match self.data {
+            ScopedPduData::Plaintext(ref pdu) => {
+                (Some(pdu.data.pdu_type()), pdu.data.vars_iter().count())
+            }
+            ScopedPduData::Encrypted(_) => (None, 0),
+        }
+    }
+}
+
+/// The header of a SNMPv3 message
+///
+/// In [lenient](crate::config::ParserConfig::lenient) mode, `msgFlags` values longer than 1
+/// byte are accepted (only the first byte is kept), and negative or non-minimal `msgMaxSize`
+/// values are converted to unsigned, so the rest of the message can be inspected.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct HeaderData {
     pub msg_id: u32,
@@ -81,20 +343,68 @@ impl HeaderData {
     pub fn is_reportable(&self) -> bool {
         self.msg_flags & 0b100 != 0
     }
+
+    /// Return the bits of `msgFlags` which are not defined (bits 3 to 7), or 0
+    ///
+    /// These bits must be zero, but are ignored by most agents, so they can be used to
+    /// fingerprint SNMP stacks or as a covert channel. An anomaly is emitted when parsing a
+    /// header where they are set.
+    pub fn unknown_flag_bits(&self) -> u8 {
+        self.msg_flags & !0b111
+    }
 }
 
-impl<'a> FromBer<'a> for HeaderData {
-    fn from_ber(bytes: &'a [u8]) -> asn1_rs::ParseResult<'a, Self> {
+/// Format the header on one line, for ex.
+/// `msg_id=821490645 max_size=65507 flags=auth|priv|reportable model=USM`
+impl fmt::Display for HeaderData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "msg_id={} max_size={} flags=",
+            self.msg_id, self.msg_max_size
+        )?;
+        let names = [(0b001, "auth"), (0b010, "priv"), (0b100, "reportable")];
+        let mut sep = "";
+        for (bit, name) in names.iter() {
+            if self.msg_flags & bit != 0 {
+                write!(f, "{}{}", sep, name)?;
+                sep = "|";
+            }
+        }
+        if self.unknown_flag_bits() != 0 {
+            write!(f, "{}{:#04x}", sep, self.unknown_flag_bits())?;
+        } else if self.msg_flags == 0 {
+            f.write_str("none")?;
+        }
+        write!(f, " model={:?}", self.msg_security_model)
+    }
+}
+
+impl HeaderData {
+    /// Parse the header, accepting the invalid encodings of `msgMaxSize` and `msgFlags` sent by
+    /// some agents if `lenient` is set
+    pub(crate) fn parse_ber(bytes: &[u8], lenient: bool) -> ParseResult<'_, Self> {
         Sequence::from_ber_and_then(bytes, |i| {
             let (i, msg_id) = u32::from_ber(i)?;
-            let (i, msg_max_size) = u32::from_ber(i)?;
+            let (i, msg_max_size) = parse_ber_u32(i, "msgMaxSize", lenient)?;
             let (i, b) = <&[u8]>::from_ber(i)?;
-            let msg_flags = if b.len() == 1 {
-                b[0]
-            } else {
-                return Err(Err::Error(Error::BerValueError));
+            // msgFlags is a 1-byte string, but some stacks add padding: in lenient mode, only the
+            // first byte is used
+            let msg_flags = match b.len() {
+                1 => b[0],
+                _len if lenient && _len > 1 => {
+                    snmp_anomaly!(len = _len, "msgFlags is longer than 1 byte");
+                    b[0]
+                }
+                _ => return Err(Err::Error(Error::BerValueError)),
             };
+            if msg_flags & !0b111 != 0 {
+                snmp_anomaly!(msg_flags, "undefined msgFlags bits are set");
+            }
             let (i, msg_security_model) = map(u32::from_ber, SecurityModel)(i)?;
+            if !i.is_empty() {
+                snmp_anomaly!(len = i.len(), "trailing data in msgGlobalData");
+            }
             let hdr = HeaderData {
                 msg_id,
                 msg_max_size,
@@ -106,12 +416,42 @@ impl<'a> FromBer<'a> for HeaderData {
     }
 }
 
+impl<'a> FromBer<'a> for HeaderData {
+    fn from_ber(bytes: &'a [u8]) -> ParseResult<'a, Self> {
+        HeaderData::parse_ber(bytes, false)
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum ScopedPduData<'a> {
     Plaintext(ScopedPdu<'a>),
     Encrypted(&'a [u8]),
 }
 
+/// Encoding of the `msgData` CHOICE, as found on the wire
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ScopedPduDataForm {
+    /// `plaintext`: a ScopedPDU SEQUENCE
+    Sequence,
+    /// `encryptedPDU`: an OCTET STRING
+    OctetString,
+}
+
+impl<'a> ScopedPduData<'a> {
+    /// Return the encoding of the data on the wire
+    ///
+    /// When parsing, the variant is chosen from the tag of the data, not from the privacy flag
+    /// of the header, so this is the form actually used by the sender. Compare it with
+    /// [`HeaderData::is_encrypted`] (or use [`SnmpV3Message::has_privacy_mismatch`]) to detect
+    /// messages crafted to be decoded differently by different tools.
+    pub fn wire_form(&self) -> ScopedPduDataForm {
+        match self {
+            ScopedPduData::Plaintext(_) => ScopedPduDataForm::Sequence,
+            ScopedPduData::Encrypted(_) => ScopedPduDataForm::OctetString,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct ScopedPdu<'a> {
     pub ctx_engine_id: &'a [u8],
@@ -120,32 +460,101 @@ pub struct ScopedPdu<'a> {
     pub data: SnmpPdu<'a>,
 }
 
+/// A scoped PDU parsed from decrypted data, with the original encrypted data
+///
+/// See [`SnmpV3Message::parse_decrypted`].
+#[derive(Debug, PartialEq)]
+pub struct DecryptedScopedPdu<'a, 'b> {
+    /// Encrypted data, as found in the message
+    pub ciphertext: &'a [u8],
+    pub scoped_pdu: ScopedPdu<'b>,
+}
+
 pub(crate) fn parse_snmp_v3_data<'a>(
     i: &'a [u8],
     hdr: &HeaderData,
 ) -> IResult<&'a [u8], ScopedPduData<'a>, SnmpError> {
-    if hdr.is_encrypted() {
+    snmp_event!(
+        msg_id = hdr.msg_id,
+        msg_flags = hdr.msg_flags,
+        encrypted = hdr.is_encrypted(),
+        "scoped PDU data"
+    );
+    if is_scoped_pdu_data_encrypted(i, hdr) {
         map(<&[u8]>::from_ber, ScopedPduData::Encrypted)(i).map_err(Err::convert)
     } else {
         parse_snmp_v3_plaintext_pdu(i)
     }
 }
 
+/// Test if the scoped PDU data is encrypted, using its tag (OCTET STRING if encrypted, or
+/// SEQUENCE), or the header flags if the tag is neither
+pub(crate) fn is_scoped_pdu_data_encrypted(i: &[u8], hdr: &HeaderData) -> bool {
+    let encrypted = match i.first() {
+        Some(0x04) => true,
+        Some(0x30) => false,
+        _ => return hdr.is_encrypted(),
+    };
+    if encrypted != hdr.is_encrypted() {
+        snmp_anomaly!(
+            msg_flags = hdr.msg_flags,
+            encrypted,
+            "privacy flag does not match scoped PDU data"
+        );
+    }
+    encrypted
+}
+
+/// Parse the security parameters, returning them with their raw content
+pub(crate) fn parse_snmp_v3_security_parameters<'a>(
+    i: &'a [u8],
+    hdr: &HeaderData,
+    opts: &ParseOptions,
+) -> IResult<&'a [u8], (SecurityParameters<'a>, &'a [u8]), SnmpError> {
+    let (rem, data) = <&[u8]>::from_ber(i).map_err(Err::convert)?;
+    let secp = parse_secp(data, hdr, opts).map_err(Err::Error)?;
+    Ok((rem, (secp, data)))
+}
+
 pub(crate) fn parse_secp<'a>(
     i: &'a [u8],
     hdr: &HeaderData,
+    opts: &ParseOptions,
 ) -> Result<SecurityParameters<'a>, SnmpError> {
+    snmp_span!("security_parameters", model = ?hdr.msg_security_model, len = i.len());
     match hdr.msg_security_model {
-        SecurityModel::USM => match parse_usm_security_parameters(i) {
-            Ok((_, usm)) => Ok(SecurityParameters::USM(usm)),
-            _ => Err(SnmpError::InvalidSecurityModel),
+        SecurityModel::USM => match parse_usm_security_parameters_with(i, opts) {
+            Ok((_rem, usm)) => {
+                if !_rem.is_empty() {
+                    snmp_anomaly!(len = _rem.len(), "trailing data in msgSecurityParameters");
+                }
+                snmp_event!(
+                    engine_boots = usm.msg_authoritative_engine_boots,
+                    engine_time = usm.msg_authoritative_engine_time,
+                    "USM security parameters"
+                );
+                Ok(SecurityParameters::USM(usm))
+            }
+            Err(_e) => {
+                snmp_anomaly!(error = ?_e, "invalid USM security parameters");
+                Err(SnmpError::InvalidSecurityModel)
+            }
         },
-        _ => Ok(SecurityParameters::Raw(i)),
+        _ => {
+            snmp_event!("unknown security model, keeping raw parameters");
+            Ok(SecurityParameters::Raw(i))
+        }
     }
 }
 
 /// Parse an SNMPv3 top-level message
 ///
+/// # Errors
+///
+/// If the input is not a SEQUENCE, or if the version is not 3, a recoverable error
+/// (`nom::Err::Error`) is returned, so other parsers can be tried (for ex. using `alt`).
+/// Once the version has been recognized, errors are returned as `nom::Err::Failure`.
+///
 /// Example:
 ///
 /// ```rust
@@ -167,38 +576,248 @@ pub(crate) fn parse_secp<'a>(
 /// }
 /// # }
 /// ```
-pub fn parse_snmp_v3(bytes: &[u8]) -> IResult<&[u8], SnmpV3Message, SnmpError> {
-    Sequence::from_der_and_then(bytes, |i| {
-        let (i, version) = u32::from_ber(i).map_err(Err::convert)?;
-        let (i, header_data) = parse_snmp_v3_headerdata(i)?;
-        let (i, secp) =
-            map_res(<&[u8]>::from_ber, |x| parse_secp(x, &header_data))(i).map_err(Err::convert)?;
-        let (i, data) = parse_snmp_v3_data(i, &header_data)?;
-        let msg = SnmpV3Message {
-            version,
-            header_data,
-            security_params: secp,
-            data,
+pub fn parse_snmp_v3(bytes: &[u8]) -> IResult<&[u8], SnmpV3Message<'_>, SnmpError> {
+    snmp_span!("parse_snmp_v3", len = bytes.len());
+    let res = Sequence::from_der_and_then(bytes, |i| {
+        let (i, version) = context("version", u32::from_ber)(i)?;
+        snmp_event!(version, "parse start");
+        if version != 3 {
+            return Err(Err::Error(SnmpError::InvalidVersion { found: version }));
+        }
+        parse_snmp_v3_content(i, &ParseOptions::DEFAULT).map_err(cut)
+    });
+    let res = resolve_offsets(res, bytes);
+    trace_parse_result(&res, SnmpV3Message::trace_fields);
+    res
+}
+
+/// Parse the content of a SNMPv3 message, after the version
+pub(crate) fn parse_snmp_v3_content<'a>(
+    i: &'a [u8],
+    opts: &ParseOptions,
+) -> IResult<&'a [u8], SnmpV3Message<'a>, SnmpError> {
+    let (i, header_data) = context("header_data", |i| HeaderData::parse_ber(i, opts.lenient))(i)?;
+    let (i, (secp, secp_raw)) = context("security_parameters", |i| {
+        parse_snmp_v3_security_parameters(i, &header_data, opts)
+    })(i)?;
+    let (i, data) = context("data", |i| parse_snmp_v3_data(i, &header_data))(i)?;
+    if !i.is_empty() {
+        snmp_anomaly!(len = i.len(), "trailing data after msgData");
+    }
+    let msg = SnmpV3Message {
+        version: 3,
+        header_data,
+        security_params: secp,
+        security_params_raw: secp_raw,
+        data,
+    };
+    Ok((i, msg))
+}
+
+/// An inconsistency between the lengths of the elements of a SNMPv3 message
+///
+/// Offsets are relative to the start of the message. See [`check_snmp_v3_lengths`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LengthAnomaly {
+    /// The length of an element exceeds the content of `field`, which contains it (or the
+    /// input, for the message itself)
+    Overshoot {
+        field: &'static str,
+        /// Offset of the element
+        offset: usize,
+        /// Length of the element (header and content), as encoded
+        declared: usize,
+        /// Bytes remaining in `field`, starting from the element
+        available: usize,
+    },
+    /// Bytes remain in `field` after its last element
+    TrailingData {
+        field: &'static str,
+        /// Offset of the first unused byte
+        offset: usize,
+        len: usize,
+    },
+}
+
+/// Check that the lengths of the elements of a SNMPv3 message are consistent
+///
+/// The lengths of the header data, security parameters (if encoded as a SEQUENCE) and scoped
+/// PDU are compared with the content of the elements enclosing them. Parsers usually ignore
+/// trailing data and differ on overshooting lengths, so messages crafted with inconsistent
+/// lengths can be decoded differently by an IDS and by the agent.
+///
+/// Only the headers of the elements are read: the message is not validated. Elements with an
+/// indefinite length are not checked.
+///
+/// # Errors
+///
+/// If the message is not a SEQUENCE, or if a header cannot be read, `SnmpError::InvalidMessage`
+/// is returned.
+pub fn check_snmp_v3_lengths(bytes: &[u8]) -> Result<Vec<LengthAnomaly>, SnmpError> {
+    let mut anomalies = Vec::new();
+    let (rem, hdr) = Header::from_ber(bytes).map_err(|_| SnmpError::InvalidMessage)?;
+    if hdr.tag() != Tag::Sequence {
+        return Err(SnmpError::InvalidMessage);
+    }
+    let start = bytes.len() - rem.len();
+    let content = match hdr.length() {
+        Length::Definite(len) if len <= rem.len() => &rem[..len],
+        Length::Definite(len) => {
+            snmp_anomaly!(len, "message length overshoots the input");
+            anomalies.push(LengthAnomaly::Overshoot {
+                field: "message",
+                offset: 0,
+                declared: start.saturating_add(len),
+                available: bytes.len(),
+            });
+            rem
+        }
+        Length::Indefinite => return Ok(anomalies),
+    };
+    let elements = read_elements(content, start, "message", Some(4), &mut anomalies)?;
+    if let Some(header_data) = elements.get(1) {
+        header_data.check_content("header_data", Some(4), &mut anomalies)?;
+    }
+    // security parameters are an OCTET STRING, usually containing a SEQUENCE
+    if let Some(params) = elements.get(2) {
+        if params.content.first() == Some(&0x30) {
+            let inner = params.check_content("security_parameters", Some(1), &mut anomalies)?;
+            if let Some(seq) = inner.first() {
+                seq.check_content("security_parameters", None, &mut anomalies)?;
+            }
+        }
+    }
+    if let Some(data) = elements.get(3).filter(|data| data.tag_byte == 0x30) {
+        data.check_content("scoped_pdu", Some(3), &mut anomalies)?;
+    }
+    Ok(anomalies)
+}
+
+/// An element read by [`check_snmp_v3_lengths`]
+struct Element<'a> {
+    /// First byte of the identifier
+    tag_byte: u8,
+    /// Offset of the content, from the start of the message
+    offset: usize,
+    content: &'a [u8],
+}
+
+impl<'a> Element<'a> {
+    fn check_content(
+        &self,
+        field: &'static str,
+        count: Option<usize>,
+        anomalies: &mut Vec<LengthAnomaly>,
+    ) -> Result<Vec<Element<'a>>, SnmpError> {
+        read_elements(self.content, self.offset, field, count, anomalies)
+    }
+}
+
+/// Read the headers of the elements in `content` of `field` (starting at offset `start`)
+///
+/// If `count` is set, at most `count` elements are read, and remaining bytes are reported.
+fn read_elements<'a>(
+    content: &'a [u8],
+    start: usize,
+    field: &'static str,
+    count: Option<usize>,
+    anomalies: &mut Vec<LengthAnomaly>,
+) -> Result<Vec<Element<'a>>, SnmpError> {
+    let mut elements = Vec::new();
+    let mut i = content;
+    while !i.is_empty() && !matches!(count, Some(count) if elements.len() >= count) {
+        let offset = start + content.len() - i.len();
+        let (rem, hdr) = Header::from_ber(i).map_err(|_| SnmpError::InvalidMessage)?;
+        let header_len = i.len() - rem.len();
+        let len = match hdr.length() {
+            Length::Definite(len) => len,
+            Length::Indefinite => return Ok(elements),
         };
-        Ok((i, msg))
-    })
+        if len > rem.len() {
+            snmp_anomaly!(field, offset, "element length overshoots its container");
+            anomalies.push(LengthAnomaly::Overshoot {
+                field,
+                offset,
+                declared: header_len.saturating_add(len),
+                available: i.len(),
+            });
+            return Ok(elements);
+        }
+        elements.push(Element {
+            tag_byte: i[0],
+            offset: offset + header_len,
+            content: &rem[..len],
+        });
+        i = &rem[len..];
+    }
+    if count.is_some() && !i.is_empty() {
+        let offset = start + content.len() - i.len();
+        snmp_anomaly!(field, offset, len = i.len(), "trailing data in element");
+        anomalies.push(LengthAnomaly::TrailingData {
+            field,
+            offset,
+            len: i.len(),
+        });
+    }
+    Ok(elements)
 }
 
-#[inline]
-pub(crate) fn parse_snmp_v3_headerdata(i: &[u8]) -> IResult<&[u8], HeaderData, SnmpError> {
-    HeaderData::from_ber(i).map_err(Err::convert)
+fn parse_snmp_v3_plaintext_pdu(bytes: &[u8]) -> IResult<&[u8], ScopedPduData<'_>, SnmpError> {
+    map(parse_scoped_pdu, ScopedPduData::Plaintext)(bytes)
 }
 
-fn parse_snmp_v3_plaintext_pdu(bytes: &[u8]) -> IResult<&[u8], ScopedPduData, SnmpError> {
+fn parse_scoped_pdu(bytes: &[u8]) -> IResult<&[u8], ScopedPdu<'_>, SnmpError> {
     Sequence::from_der_and_then(bytes, |i| {
-        let (i, ctx_engine_id) = <&[u8]>::from_ber(i).map_err(Err::convert)?;
-        let (i, ctx_engine_name) = <&[u8]>::from_ber(i).map_err(Err::convert)?;
-        let (i, data) = parse_snmp_v2c_pdu(i)?;
+        let (i, ctx_engine_id) = context("context_engine_id", <&[u8]>::from_ber)(i)?;
+        let (i, ctx_engine_name) = context("context_name", <&[u8]>::from_ber)(i)?;
+        let (i, data) = context("pdu", parse_snmp_v2c_pdu)(i)?;
+        if !i.is_empty() {
+            snmp_anomaly!(len = i.len(), "trailing data in scoped PDU");
+        }
         let pdu = ScopedPdu {
             ctx_engine_id,
             ctx_engine_name,
             data,
         };
-        Ok((i, ScopedPduData::Plaintext(pdu)))
+        Ok((i, pdu))
     })
 }
+
+impl<'a> FromBer<'a, SnmpError> for ScopedPdu<'a> {
+    /// Parse a scoped PDU, without the enclosing message
+    ///
+    /// This can be used to parse the plaintext of an encrypted message (see
+    /// [`ScopedPduData::Encrypted`]), after decrypting it. Error offsets are relative to the start
+    /// of the scoped PDU.
+    fn from_ber(bytes: &'a [u8]) -> ParseResult<'a, Self, SnmpError> {
+        snmp_span!("parse_scoped_pdu", len = bytes.len());
+        let res = parse_scoped_pdu(bytes);
+        resolve_offsets(res, bytes)
+    }
+}
+
+impl<'a> Tagged for SnmpV3Message<'a> {
+    const TAG: Tag = Tag::Sequence;
+}
+
+impl Tagged for HeaderData {
+    const TAG: Tag = Tag::Sequence;
+}
+
+/// Security parameters are always encoded in an OCTET STRING
+impl<'a> Tagged for SecurityParameters<'a> {
+    const TAG: Tag = Tag::OctetString;
+}
+
+impl<'a> DynTagged for ScopedPduData<'a> {
+    fn tag(&self) -> Tag {
+        match self {
+            ScopedPduData::Plaintext(_) => Tag::Sequence,
+            ScopedPduData::Encrypted(_) => Tag::OctetString,
+        }
+    }
+}
+
+impl<'a> Tagged for ScopedPdu<'a> {
+    const TAG: Tag = Tag::Sequence;
+}
@@ -0,0 +1,101 @@
+//! Reading messages from asynchronous streams
+//!
+//! When SNMP runs over a stream transport (TCP, [RFC3430](https://tools.ietf.org/html/rfc3430),
+//! or TLS, [RFC6353](https://tools.ietf.org/html/rfc6353)), messages are sent one after the
+//! other, without framing: the length of each message is read from its BER header.
+//! [`read_snmp_message`] reads exactly one message from a [`tokio::io::AsyncRead`] stream.
+//!
+//! The header is read byte by byte, so the stream should be buffered (for ex. with
+//! [`tokio::io::BufReader`]).
+
+use crate::error::SnmpError;
+use crate::generic::{snmp_message_len, SnmpGenericMessage};
+use alloc::vec::Vec;
+use core::fmt;
+use nom::Err;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Error returned when a message cannot be read from a stream
+#[derive(Debug)]
+pub enum ReadError {
+    /// The stream returned an error, or was closed in the middle of a message
+    Io(std::io::Error),
+    /// The message length is larger than the limit
+    TooLarge { len: usize },
+    /// The message could not be parsed
+    Snmp(SnmpError),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::Io(e) => write!(f, "I/O error: {}", e),
+            ReadError::TooLarge { len } => write!(f, "Message too large ({} bytes)", len),
+            ReadError::Snmp(e) => write!(f, "Invalid message: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadError::Io(e) => Some(e),
+            ReadError::TooLarge { .. } => None,
+            ReadError::Snmp(e) => Some(e),
+        }
+    }
+}
+
+impl From<std::io::Error> for ReadError {
+    fn from(e: std::io::Error) -> ReadError {
+        ReadError::Io(e)
+    }
+}
+
+/// Read and parse one SNMP message from `reader`
+///
+/// The message is read into `buf` (previous content is discarded), and the returned message
+/// borrows from it. Messages longer than `max_len` bytes (header included) are rejected before
+/// reading their content.
+///
+/// Returns `Ok(None)` if the stream is closed before the first byte of a message.
+///
+/// # Errors
+///
+/// If the stream is closed in the middle of a message, `ReadError::Io` is returned, with kind
+/// `UnexpectedEof`. After an error, the stream is not at a message boundary anymore, and should
+/// be closed.
+pub async fn read_snmp_message<'b, R>(
+    reader: &mut R,
+    buf: &'b mut Vec<u8>,
+    max_len: usize,
+) -> Result<Option<SnmpGenericMessage<'b>>, ReadError>
+where
+    R: AsyncRead + Unpin,
+{
+    buf.clear();
+    let len = loop {
+        match snmp_message_len(buf) {
+            Ok(len) => break len,
+            Err(Err::Incomplete(_)) => (),
+            Err(Err::Error(e)) | Err(Err::Failure(e)) => return Err(ReadError::Snmp(e)),
+        }
+        let mut byte = [0u8];
+        if reader.read(&mut byte).await? == 0 {
+            if buf.is_empty() {
+                return Ok(None);
+            }
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        buf.push(byte[0]);
+    };
+    if len > max_len {
+        snmp_anomaly!(len, max_len, "message too large");
+        return Err(ReadError::TooLarge { len });
+    }
+    let header_len = buf.len();
+    buf.resize(len, 0);
+    reader.read_exact(&mut buf[header_len..]).await?;
+    let (msg, _) = SnmpGenericMessage::parse(buf).map_err(ReadError::Snmp)?;
+    Ok(Some(msg))
+}
@@ -0,0 +1,265 @@
+//! Extraction of topology hints from responses
+//!
+//! Switches and routers publish their neighbors in the LLDP-MIB (IEEE 802.1AB) and CISCO-CDP-MIB
+//! tables, and the MAC addresses learned on each port in the BRIDGE-MIB forwarding database.
+//! Observing the responses to managers walking these tables is enough to discover the topology
+//! of a network passively.
+//!
+//! [`TopologyHints`] accumulates the variables of these tables, from any number of responses:
+//!
+//! ```rust
+//! use snmp_parser::topology::TopologyHints;
+//! # use snmp_parser::SnmpPdu;
+//!
+//! # fn print_neighbors(responses: &[SnmpPdu<'_>]) {
+//! let mut hints = TopologyHints::new();
+//! for response in responses {
+//!     hints.push_pdu(response);
+//! }
+//! for neighbor in hints.neighbors() {
+//!     println!(
+//!         "port {} -> {:?} port {:?}",
+//!         neighbor.local_port, neighbor.system_name, neighbor.port_id
+//!     );
+//! }
+//! # }
+//! ```
+
+use crate::snmp::{ObjectSyntax, PduType, SnmpPdu, SnmpVariable, VarBindValue};
+use crate::walk::split_table_instance;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use asn1_rs::{oid, Oid};
+use core::convert::TryFrom;
+#[cfg(not(feature = "std"))]
+use core::net::Ipv4Addr;
+#[cfg(feature = "std")]
+use std::net::Ipv4Addr;
+
+/// `lldpRemEntry` (LLDP-MIB), indexed by time mark, local port and remote index
+#[rustfmt::skip]
+pub const LLDP_REM_ENTRY_OID: Oid<'static> = oid!(1.0.8802.1.1.2.1.4.1.1);
+
+/// `cdpCacheEntry` (CISCO-CDP-MIB), indexed by interface and device index
+#[rustfmt::skip]
+pub const CDP_CACHE_ENTRY_OID: Oid<'static> = oid!(1.3.6.1.4.1.9.9.23.1.2.1.1);
+
+/// `dot1dTpFdbEntry` (BRIDGE-MIB), indexed by MAC address
+#[rustfmt::skip]
+pub const DOT1D_TP_FDB_ENTRY_OID: Oid<'static> = oid!(1.3.6.1.2.1.17.4.3.1);
+
+/// `dot1dBasePortEntry` (BRIDGE-MIB), indexed by bridge port
+#[rustfmt::skip]
+pub const DOT1D_BASE_PORT_ENTRY_OID: Oid<'static> = oid!(1.3.6.1.2.1.17.1.4.1);
+
+/// Discovery protocol of a neighbor
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NeighborProtocol {
+    Lldp,
+    Cdp,
+}
+
+/// A neighbor of the agent, as seen by a discovery protocol
+///
+/// Fields are `None` until the corresponding column is seen.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Neighbor {
+    pub protocol: NeighborProtocol,
+    /// Local port: `lldpLocPortNum` for LLDP, `ifIndex` for CDP
+    pub local_port: u32,
+    /// Chassis ID (`lldpRemChassisId`, or `cdpCacheDeviceId` for CDP)
+    pub chassis_id: Option<Vec<u8>>,
+    /// Port of the neighbor (`lldpRemPortId` or `cdpCacheDevicePort`)
+    pub port_id: Option<Vec<u8>>,
+    /// `lldpRemPortDesc`
+    pub port_description: Option<String>,
+    /// `lldpRemSysName`, or `cdpCacheDeviceId` for CDP
+    pub system_name: Option<String>,
+    /// `lldpRemSysDesc`, or `cdpCachePlatform` for CDP
+    pub system_description: Option<String>,
+    /// `cdpCacheAddress`, if this is an IPv4 address
+    pub address: Option<Ipv4Addr>,
+}
+
+/// An entry of the forwarding database of a bridge
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FdbEntry {
+    pub mac: [u8; 6],
+    /// Bridge port (see [`TopologyHints::port_if_index`])
+    pub port: u32,
+}
+
+/// Accumulator of topology information found in responses
+#[derive(Clone, Debug, Default)]
+pub struct TopologyHints {
+    neighbors: BTreeMap<(NeighborProtocol, u32, u32), Neighbor>,
+    fdb: BTreeMap<[u8; 6], u32>,
+    ports: BTreeMap<u32, u32>,
+}
+
+impl TopologyHints {
+    pub fn new() -> Self {
+        TopologyHints::default()
+    }
+
+    /// Add the variables of a Response PDU (other PDUs are ignored)
+    pub fn push_pdu(&mut self, pdu: &SnmpPdu) {
+        if let SnmpPdu::Generic(response) = pdu {
+            if response.pdu_type == PduType::Response {
+                response.var.iter().for_each(|var| self.push_var(var));
+            }
+        }
+    }
+
+    /// Add a variable, if it belongs to one of the known tables
+    pub fn push_var(&mut self, var: &SnmpVariable) {
+        let value = match var.val {
+            VarBindValue::Value(ref value) => value,
+            _ => return,
+        };
+        if let Some((column, index)) = split_table_instance(LLDP_REM_ENTRY_OID.as_bytes(), &var.oid)
+        {
+            // index is lldpRemTimeMark, lldpRemLocalPortNum, lldpRemIndex
+            if let [_, port, remote] = index[..] {
+                self.push_lldp(column, port, remote, value);
+            }
+        } else if let Some((column, index)) =
+            split_table_instance(CDP_CACHE_ENTRY_OID.as_bytes(), &var.oid)
+        {
+            if let [if_index, device] = index[..] {
+                self.push_cdp(column, if_index, device, value);
+            }
+        } else if let Some((column, index)) =
+            split_table_instance(DOT1D_TP_FDB_ENTRY_OID.as_bytes(), &var.oid)
+        {
+            // dot1dTpFdbPort
+            if let (2, Some(mac), Some(port)) = (column, mac_index(&index), as_u32(value)) {
+                self.fdb.insert(mac, port);
+            }
+        } else if let Some((column, index)) =
+            split_table_instance(DOT1D_BASE_PORT_ENTRY_OID.as_bytes(), &var.oid)
+        {
+            // dot1dBasePortIfIndex
+            if let (2, [port], Some(if_index)) = (column, &index[..], as_u32(value)) {
+                if let Ok(port) = u32::try_from(*port) {
+                    self.ports.insert(port, if_index);
+                }
+            }
+        }
+    }
+
+    fn neighbor(
+        &mut self,
+        protocol: NeighborProtocol,
+        port: u64,
+        idx: u64,
+    ) -> Option<&mut Neighbor> {
+        let port = u32::try_from(port).ok()?;
+        let idx = u32::try_from(idx).ok()?;
+        let neighbor = self
+            .neighbors
+            .entry((protocol, port, idx))
+            .or_insert_with(|| Neighbor {
+                protocol,
+                local_port: port,
+                chassis_id: None,
+                port_id: None,
+                port_description: None,
+                system_name: None,
+                system_description: None,
+                address: None,
+            });
+        Some(neighbor)
+    }
+
+    fn push_lldp(&mut self, column: u64, port: u64, remote: u64, value: &ObjectSyntax) {
+        let s = match value {
+            ObjectSyntax::String(s) => *s,
+            _ => return,
+        };
+        if !matches!(column, 5 | 7..=10) {
+            return;
+        }
+        let neighbor = match self.neighbor(NeighborProtocol::Lldp, port, remote) {
+            Some(neighbor) => neighbor,
+            None => return,
+        };
+        match column {
+            5 => neighbor.chassis_id = Some(s.to_vec()),
+            7 => neighbor.port_id = Some(s.to_vec()),
+            8 => neighbor.port_description = Some(String::from_utf8_lossy(s).into_owned()),
+            9 => neighbor.system_name = Some(String::from_utf8_lossy(s).into_owned()),
+            _ => neighbor.system_description = Some(String::from_utf8_lossy(s).into_owned()),
+        }
+    }
+
+    fn push_cdp(&mut self, column: u64, if_index: u64, device: u64, value: &ObjectSyntax) {
+        let s = match value {
+            ObjectSyntax::String(s) => *s,
+            _ => return,
+        };
+        if !matches!(column, 4 | 6..=8) {
+            return;
+        }
+        let neighbor = match self.neighbor(NeighborProtocol::Cdp, if_index, device) {
+            Some(neighbor) => neighbor,
+            None => return,
+        };
+        match column {
+            4 => {
+                if let [a, b, c, d] = *s {
+                    neighbor.address = Some(Ipv4Addr::new(a, b, c, d));
+                }
+            }
+            6 => {
+                neighbor.chassis_id = Some(s.to_vec());
+                neighbor.system_name = Some(String::from_utf8_lossy(s).into_owned());
+            }
+            7 => neighbor.port_id = Some(s.to_vec()),
+            _ => neighbor.system_description = Some(String::from_utf8_lossy(s).into_owned()),
+        }
+    }
+
+    /// Iterate over the neighbors, ordered by protocol and local port
+    pub fn neighbors(&self) -> impl Iterator<Item = &Neighbor> {
+        self.neighbors.values()
+    }
+
+    /// Return the forwarding database entries, ordered by MAC address
+    pub fn fdb(&self) -> Vec<FdbEntry> {
+        self.fdb
+            .iter()
+            .map(|(&mac, &port)| FdbEntry { mac, port })
+            .collect()
+    }
+
+    /// Return the `ifIndex` of bridge port `port` (`dot1dBasePortIfIndex`)
+    pub fn port_if_index(&self, port: u32) -> Option<u32> {
+        self.ports.get(&port).copied()
+    }
+
+    /// Test if no topology information was found
+    pub fn is_empty(&self) -> bool {
+        self.neighbors.is_empty() && self.fdb.is_empty() && self.ports.is_empty()
+    }
+}
+
+fn mac_index(index: &[u64]) -> Option<[u8; 6]> {
+    let mut mac = [0; 6];
+    if index.len() != mac.len() {
+        return None;
+    }
+    for (b, &arc) in mac.iter_mut().zip(index) {
+        *b = u8::try_from(arc).ok()?;
+    }
+    Some(mac)
+}
+
+fn as_u32(value: &ObjectSyntax) -> Option<u32> {
+    match *value {
+        ObjectSyntax::Number(n) => u32::try_from(n).ok(),
+        ObjectSyntax::Gauge32(n) | ObjectSyntax::UInteger32(n) => Some(n),
+        _ => None,
+    }
+}
@@ -0,0 +1,505 @@
+//! Trap helpers
+//!
+//! This module contains helpers to classify notifications (SNMPv1 traps, SNMPv2 traps and
+//! informs), a sliding-window rate counter to detect trap storms, and a tracker of inform
+//! acknowledgments (both require the `std` feature).
+//!
+//! Notifications forwarded by proxies carry the address and community of the original SNMPv1
+//! trap in the `snmpTrapAddress.0` and `snmpTrapCommunity.0` variables, returned by
+//! [`SnmpPdu::snmp_trap_address`] and [`SnmpPdu::snmp_trap_community`].
+//!
+//! See also:
+//!   - [RFC3416](https://tools.ietf.org/html/rfc3416): Version 2 of the Protocol Operations for the
+//!     Simple Network Management Protocol (SNMP)
+//!   - [RFC3584](https://tools.ietf.org/html/rfc3584): Coexistence between Version 1, Version 2, and
+//!     Version 3 of the Internet-standard Network Management Framework
+
+pub use crate::coexistence::{SNMP_TRAP_ADDRESS_OID, SNMP_TRAP_COMMUNITY_OID};
+#[cfg(feature = "std")]
+use crate::lru::LruMap;
+#[cfg(feature = "std")]
+use crate::pending::PendingRequests;
+use crate::snmp::{NetworkAddress, ObjectSyntax, PduType, SnmpMessage, SnmpPdu, VarBindValue};
+use asn1_rs::{oid, Oid};
+#[cfg(feature = "std")]
+use core::cmp::Ordering;
+#[cfg(not(feature = "std"))]
+use core::net::IpAddr;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+#[cfg(feature = "std")]
+use std::net::IpAddr;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+/// `snmpTrapOID.0`: the authoritative identification of the notification currently being sent
+#[rustfmt::skip]
+pub const SNMP_TRAP_OID: Oid<'static> = oid!(1.3.6.1.6.3.1.1.4.1.0);
+
+impl<'a> SnmpPdu<'a> {
+    /// Test if this PDU is a notification (SNMPv1 trap, SNMPv2 trap or inform)
+    pub fn is_notification(&self) -> bool {
+        matches!(
+            self.pdu_type(),
+            PduType::TrapV1 | PduType::TrapV2 | PduType::InformRequest
+        )
+    }
+
+    /// Return the value of the `snmpTrapOID.0` variable, if present
+    ///
+    /// This only applies to SNMPv2 traps and informs, for which it is the second variable of the
+    /// list. For robustness, all variables are searched.
+    pub fn snmp_trap_oid(&self) -> Option<&Oid<'a>> {
+        let vars = match self {
+            SnmpPdu::Generic(pdu) => &pdu.var,
+            _ => return None,
+        };
+        vars.iter()
+            .filter(|v| v.oid == SNMP_TRAP_OID)
+            .find_map(|v| match v.val {
+                VarBindValue::Value(ObjectSyntax::Object(ref oid)) => Some(oid),
+                _ => None,
+            })
+    }
+
+    /// Return the value of the `snmpTrapAddress.0` variable, if present
+    ///
+    /// When a SNMPv1 trap is forwarded as a SNMPv2 trap by a proxy (RFC3584 section 3.1), this
+    /// is the address of the agent which sent the original trap.
+    pub fn snmp_trap_address(&self) -> Option<IpAddr> {
+        self.notification_var(&SNMP_TRAP_ADDRESS_OID)
+            .find_map(|val| match val {
+                ObjectSyntax::IpAddress(NetworkAddress::IPv4(ip)) => Some(IpAddr::V4(*ip)),
+                _ => None,
+            })
+    }
+
+    /// Return the value of the `snmpTrapCommunity.0` variable, if present
+    ///
+    /// This is the community of the original SNMPv1 trap, when forwarded by a proxy.
+    pub fn snmp_trap_community(&self) -> Option<&'a [u8]> {
+        self.notification_var(&SNMP_TRAP_COMMUNITY_OID)
+            .find_map(|val| match *val {
+                ObjectSyntax::String(s) => Some(s),
+                _ => None,
+            })
+    }
+
+    /// Return the values of the variables of a SNMPv2 notification with this OID
+    fn notification_var<'p>(
+        &'p self,
+        oid: &'p Oid,
+    ) -> impl Iterator<Item = &'p ObjectSyntax<'a>> + 'p {
+        let vars = match self {
+            SnmpPdu::Generic(pdu) => &pdu.var[..],
+            _ => &[],
+        };
+        vars.iter()
+            .filter(move |v| v.oid == *oid)
+            .filter_map(|v| match v.val {
+                VarBindValue::Value(ref val) => Some(val),
+                _ => None,
+            })
+    }
+}
+
+/// Key used to count traps: the agent address, and the trap OID
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TrapKey {
+    pub agent: IpAddr,
+    pub trap_oid: Oid<'static>,
+}
+
+impl TrapKey {
+    /// Build the key for a notification message
+    ///
+    /// `source` is the transport address the message was received from. It is used as the agent
+    /// address, except for SNMPv1 traps which carry their own `agent-addr` field, and for
+    /// SNMPv2 notifications forwarded by a proxy, which carry the original address in
+    /// `snmpTrapAddress.0`.
+    ///
+    /// For SNMPv1 traps, the trap OID is the notification OID defined by RFC3584 (see
+    /// [`SnmpTrapPdu::notification_oid`](crate::SnmpTrapPdu::notification_oid)), so that
+    /// different traps of the same enterprise are counted separately, and SNMPv1 and SNMPv2
+    /// notifications have the same key. For SNMPv2 notifications, it is the value of
+    /// `snmpTrapOID.0`.
+    ///
+    /// Return `None` if the message is not a notification, or if no trap OID could be found.
+    pub fn from_message(source: IpAddr, msg: &SnmpMessage) -> Option<TrapKey> {
+        match msg.pdu {
+            SnmpPdu::TrapV1(ref trap) => {
+                let NetworkAddress::IPv4(ip) = trap.agent_addr;
+                trap.notification_oid().map(|trap_oid| TrapKey {
+                    agent: IpAddr::V4(ip),
+                    trap_oid,
+                })
+            }
+            ref pdu if pdu.is_notification() => pdu.snmp_trap_oid().map(|oid| TrapKey {
+                agent: pdu.snmp_trap_address().unwrap_or(source),
+                trap_oid: oid.to_owned(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Thresholds for the trap storm detector
+///
+/// A storm starts when at least `start_threshold` traps with the same key are seen during
+/// `window`, and stops when at most `stop_threshold` traps are counted during the window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TrapStormConfig {
+    pub window: Duration,
+    pub start_threshold: usize,
+    pub stop_threshold: usize,
+}
+
+impl Default for TrapStormConfig {
+    fn default() -> Self {
+        TrapStormConfig {
+            window: Duration::from_secs(10),
+            start_threshold: 100,
+            stop_threshold: 10,
+        }
+    }
+}
+
+/// Callbacks invoked by the [`TrapStormDetector`] when storms start or stop
+///
+/// All methods have an empty default implementation.
+pub trait TrapStormListener {
+    /// Called when the rate for `key` reaches the start threshold
+    fn storm_started(&mut self, _key: &TrapKey, _count: usize) {}
+
+    /// Called when the rate for `key` goes down to the stop threshold
+    fn storm_stopped(&mut self, _key: &TrapKey) {}
+}
+
+impl TrapStormListener for () {}
+
+/// Default maximum number of keys stored by [`TrapStormDetector`]
+#[cfg(feature = "std")]
+pub const DEFAULT_MAX_KEYS: usize = 10_000;
+
+/// [`TrapKey`] ordered by agent address, then by the encoding of the trap OID (which is not
+/// `Ord`), to be stored in a `LruMap`
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct OrdTrapKey(TrapKey);
+
+#[cfg(feature = "std")]
+impl PartialOrd for OrdTrapKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(feature = "std")]
+impl Ord for OrdTrapKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.0.agent, self.0.trap_oid.as_bytes())
+            .cmp(&(other.0.agent, other.0.trap_oid.as_bytes()))
+    }
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct KeyState {
+    // timestamps of the most recent traps, at most `start_threshold` entries
+    seen: VecDeque<Instant>,
+    in_storm: bool,
+}
+
+#[cfg(feature = "std")]
+/// Sliding-window rate counter for traps, keyed by [`TrapKey`]
+///
+/// The application feeds traps using [`observe`](TrapStormDetector::observe), and should call
+/// [`expire`](TrapStormDetector::expire) periodically so storms are closed (and memory
+/// is reclaimed) even when no more traps are received.
+///
+/// Keys are read from the traps, so at most `max_keys` keys are stored (see
+/// [`with_max_keys`](Self::with_max_keys)): when the limit is reached, the least recently seen
+/// key is forgotten (ending its storm, if any).
+///
+/// Time is provided by the caller, so the detector can also be used to replay captures.
+#[derive(Debug)]
+pub struct TrapStormDetector<L: TrapStormListener = ()> {
+    config: TrapStormConfig,
+    listener: L,
+    keys: LruMap<OrdTrapKey, KeyState>,
+}
+
+#[cfg(feature = "std")]
+impl TrapStormDetector<()> {
+    pub fn new(config: TrapStormConfig) -> Self {
+        Self::with_listener(config, ())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<L: TrapStormListener> TrapStormDetector<L> {
+    /// Create a detector storing at most [`DEFAULT_MAX_KEYS`] keys
+    pub fn with_listener(config: TrapStormConfig, listener: L) -> Self {
+        Self::with_max_keys(config, listener, DEFAULT_MAX_KEYS)
+    }
+
+    /// Create a detector storing at most `max_keys` keys
+    pub fn with_max_keys(config: TrapStormConfig, listener: L, max_keys: usize) -> Self {
+        // a storm must be able to stop, and must not start with a single trap
+        let start_threshold = config.start_threshold.max(1);
+        let config = TrapStormConfig {
+            start_threshold,
+            stop_threshold: config.stop_threshold.min(start_threshold - 1),
+            ..config
+        };
+        TrapStormDetector {
+            config,
+            listener,
+            keys: LruMap::new(max_keys),
+        }
+    }
+
+    pub fn config(&self) -> &TrapStormConfig {
+        &self.config
+    }
+
+    pub fn listener(&self) -> &L {
+        &self.listener
+    }
+
+    pub fn listener_mut(&mut self) -> &mut L {
+        &mut self.listener
+    }
+
+    /// Count one trap for `key`, received at time `now`
+    ///
+    /// Return `true` if `key` is in a storm after counting this trap.
+    pub fn observe(&mut self, key: TrapKey, now: Instant) -> bool {
+        let window = self.config.window;
+        let start_threshold = self.config.start_threshold;
+        let key = OrdTrapKey(key);
+        if self.keys.get(&key).is_none() {
+            let state = KeyState {
+                seen: VecDeque::with_capacity(start_threshold.min(64)),
+                in_storm: false,
+            };
+            if let Some((evicted, state)) = self.keys.insert(key.clone(), state) {
+                snmp_anomaly!(key = ?evicted.0, "too many trap keys, forgetting the oldest");
+                if state.in_storm {
+                    self.listener.storm_stopped(&evicted.0);
+                }
+            }
+        }
+        let state = match self.keys.get_mut(&key) {
+            Some(state) => state,
+            // the detector cannot store any key
+            None => return false,
+        };
+        expire_state(state, window, now);
+        if state.in_storm && state.seen.len() <= self.config.stop_threshold {
+            state.in_storm = false;
+            self.listener.storm_stopped(&key.0);
+        }
+        // older entries are not needed to know that the threshold is reached
+        if state.seen.len() == start_threshold {
+            state.seen.pop_front();
+        }
+        state.seen.push_back(now);
+        if !state.in_storm && state.seen.len() >= start_threshold {
+            state.in_storm = true;
+            let count = state.seen.len();
+            self.listener.storm_started(&key.0, count);
+        }
+        state.in_storm
+    }
+
+    /// Count one notification message, received from `source` at time `now`
+    ///
+    /// See [`TrapKey::from_message`] for the key definition. Messages that are not notifications
+    /// are ignored.
+    pub fn observe_message(&mut self, source: IpAddr, msg: &SnmpMessage, now: Instant) -> bool {
+        match TrapKey::from_message(source, msg) {
+            Some(key) => self.observe(key, now),
+            None => false,
+        }
+    }
+
+    /// Test if `key` is currently in a storm
+    pub fn is_storm(&self, key: &TrapKey) -> bool {
+        (self.keys.get(&OrdTrapKey(key.clone())))
+            .map(|s| s.in_storm)
+            .unwrap_or(false)
+    }
+
+    /// Return an iterator over the keys currently in a storm
+    pub fn storms(&self) -> impl Iterator<Item = &TrapKey> {
+        self.keys
+            .iter()
+            .filter(|(_, s)| s.in_storm)
+            .map(|(key, _)| &key.0)
+    }
+
+    /// Remove traps older than the window, ending storms and forgetting idle keys
+    pub fn expire(&mut self, now: Instant) {
+        let window = self.config.window;
+        let stop_threshold = self.config.stop_threshold;
+        let listener = &mut self.listener;
+        self.keys.retain(|key, state| {
+            expire_state(state, window, now);
+            if state.in_storm && state.seen.len() <= stop_threshold {
+                state.in_storm = false;
+                listener.storm_stopped(&key.0);
+            }
+            !state.seen.is_empty()
+        });
+    }
+}
+
+#[cfg(feature = "std")]
+fn expire_state(state: &mut KeyState, window: Duration, now: Instant) {
+    while let Some(&t) = state.seen.front() {
+        if now.saturating_duration_since(t) < window {
+            break;
+        }
+        state.seen.pop_front();
+    }
+}
+
+/// Default maximum number of informs stored by [`InformTracker`]
+#[cfg(feature = "std")]
+pub const DEFAULT_MAX_PENDING_INFORMS: usize = 10_000;
+
+/// An inform which was acknowledged, returned by [`InformTracker::observe_response`]
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InformAck {
+    /// Time between the first transmission of the inform and the acknowledgment
+    pub delay: Duration,
+    /// Number of retransmissions of the inform (with the same request ID)
+    pub retransmissions: u32,
+    /// `true` if the response has the variables of the inform, in the same order (only OIDs are
+    /// compared)
+    pub vars_match: bool,
+}
+
+/// An inform which was not acknowledged before the timeout, returned by
+/// [`InformTracker::expire`]
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnacknowledgedInform {
+    pub peer: IpAddr,
+    pub req_id: u32,
+    /// Value of `snmpTrapOID.0`, if present
+    pub trap_oid: Option<Oid<'static>>,
+    pub retransmissions: u32,
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct PendingInform {
+    oids: Vec<Oid<'static>>,
+    trap_oid: Option<Oid<'static>>,
+}
+
+#[cfg(feature = "std")]
+/// Matching of InformRequest PDUs with their acknowledgments
+///
+/// Informs are keyed by the address of the manager (the destination of the inform, and the
+/// source of the acknowledgment) and the request ID. An inform is acknowledged by a Response PDU
+/// with the same request ID. Informs which are not acknowledged within `timeout` after their last
+/// retransmission are returned by [`expire`](InformTracker::expire), which should be called
+/// periodically.
+///
+/// At most `max_pending` informs are stored (see [`with_max_pending`](Self::with_max_pending)):
+/// when the limit is reached, the least recently seen inform is dropped, so spoofed informs
+/// cannot exhaust memory.
+///
+/// Like [`TrapStormDetector`], time is provided by the caller.
+#[derive(Debug)]
+pub struct InformTracker {
+    pending: PendingRequests<PendingInform>,
+}
+
+#[cfg(feature = "std")]
+impl InformTracker {
+    /// Create a tracker storing at most [`DEFAULT_MAX_PENDING_INFORMS`] informs
+    pub fn new(timeout: Duration) -> Self {
+        Self::with_max_pending(timeout, DEFAULT_MAX_PENDING_INFORMS)
+    }
+
+    /// Create a tracker storing at most `max_pending` informs
+    pub fn with_max_pending(timeout: Duration, max_pending: usize) -> Self {
+        InformTracker {
+            pending: PendingRequests::new(timeout, max_pending),
+        }
+    }
+
+    /// Record an inform sent to `peer` at time `now`
+    ///
+    /// Return `false` (and ignore the PDU) if it is not an InformRequest. An inform with the
+    /// request ID of a pending inform is counted as a retransmission.
+    pub fn observe_inform(&mut self, peer: IpAddr, pdu: &SnmpPdu, now: Instant) -> bool {
+        let inform = match pdu {
+            SnmpPdu::Generic(inform) if inform.pdu_type == PduType::InformRequest => inform,
+            _ => return false,
+        };
+        self.pending
+            .observe(peer, inform.req_id, now, || PendingInform {
+                oids: inform.var.iter().map(|v| v.oid.to_owned()).collect(),
+                trap_oid: pdu.snmp_trap_oid().map(|oid| oid.to_owned()),
+            });
+        true
+    }
+
+    /// Match a response received from `peer` at time `now` with a pending inform
+    ///
+    /// Return `None` if the PDU is not a Response, or does not acknowledge a pending inform.
+    pub fn observe_response(
+        &mut self,
+        peer: IpAddr,
+        pdu: &SnmpPdu,
+        now: Instant,
+    ) -> Option<InformAck> {
+        let resp = match pdu {
+            SnmpPdu::Generic(resp) if resp.pdu_type == PduType::Response => resp,
+            _ => return None,
+        };
+        let pending = self.pending.remove(peer, resp.req_id)?;
+        let oids = &pending.data.oids;
+        let vars_match =
+            oids.len() == resp.var.len() && oids.iter().zip(&resp.var).all(|(o, v)| *o == v.oid);
+        if !vars_match {
+            snmp_anomaly!(
+                req_id = resp.req_id,
+                "inform acknowledgment does not have the variables of the inform"
+            );
+        }
+        Some(InformAck {
+            delay: now.saturating_duration_since(pending.first_seen),
+            retransmissions: pending.retransmissions,
+            vars_match,
+        })
+    }
+
+    /// Remove and return the informs which were not acknowledged within the timeout
+    pub fn expire(&mut self, now: Instant) -> Vec<UnacknowledgedInform> {
+        (self.pending.expire(now).into_iter())
+            .map(|(peer, req_id, p)| UnacknowledgedInform {
+                peer,
+                req_id,
+                trap_oid: p.data.trap_oid,
+                retransmissions: p.retransmissions,
+            })
+            .collect()
+    }
+
+    /// Return the number of informs waiting for an acknowledgment
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.len() == 0
+    }
+}
@@ -1,7 +1,9 @@
 //! RFC2274 - User-based Security Model (USM) for version 3 of the Simple Network Management Protocol (SNMPv3)
 
-use crate::parse_ber_octetstring_as_str;
-use asn1_rs::{Error, FromBer, Sequence};
+use crate::snmp::{parse_ber_octetstring_as_str, ParseOptions};
+use alloc::string::String;
+use asn1_rs::{Any, Error, FromBer, Sequence, Tag, Tagged};
+use core::fmt;
 use nom::IResult;
 
 #[derive(Debug, PartialEq)]
@@ -14,11 +16,100 @@ pub struct UsmSecurityParameters<'a> {
     pub msg_privacy_parameters: &'a [u8],
 }
 
-pub fn parse_usm_security_parameters(bytes: &[u8]) -> IResult<&[u8], UsmSecurityParameters, Error> {
+/// Format the parameters on one line, for ex.
+/// `engine_id=80001f888059dc486145a26322 boots=8 time=2745 user="pippo"`
+///
+/// Authentication and privacy parameters are not included.
+impl<'a> fmt::Display for UsmSecurityParameters<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("engine_id=")?;
+        for b in self.msg_authoritative_engine_id {
+            write!(f, "{:02x}", b)?;
+        }
+        write!(
+            f,
+            " boots={} time={} user={:?}",
+            self.msg_authoritative_engine_boots,
+            self.msg_authoritative_engine_time,
+            self.msg_user_name
+        )
+    }
+}
+
+/// Parse an INTEGER with values in `0..2^32`, accepting invalid encodings
+///
+/// Some agents encode unsigned values on 4 bytes without the leading zero byte (so large values
+/// are read as negative), or add redundant leading bytes. Negative values are converted to
+/// unsigned (modulo 2^32), redundant bytes are skipped, and an anomaly is emitted for `field`.
+/// Values which do not fit in 32 bits are still rejected.
+pub(crate) fn parse_ber_u32_lenient<'a>(
+    i: &'a [u8],
+    _field: &'static str,
+) -> IResult<&'a [u8], u32, Error> {
+    let (rem, any) = Any::from_ber(i)?;
+    any.tag().assert_eq(Tag::Integer)?;
+    let mut data = any.data;
+    if data.is_empty() {
+        return Err(nom::Err::Error(Error::InvalidLength));
+    }
+    // skip redundant sign bytes
+    let mut non_minimal = false;
+    while data.len() > 1
+        && ((data[0] == 0 && data[1] & 0x80 == 0) || (data[0] == 0xff && data[1] & 0x80 != 0))
+    {
+        data = &data[1..];
+        non_minimal = true;
+    }
+    if non_minimal {
+        snmp_anomaly!(field = _field, "non-minimal INTEGER encoding");
+    }
+    let negative = data[0] & 0x80 != 0;
+    if data.len() == 5 && data[0] == 0 {
+        data = &data[1..];
+    } else if data.len() > 4 {
+        return Err(nom::Err::Error(Error::IntegerTooLarge));
+    }
+    // sign-extend negative values
+    let init = if negative {
+        snmp_anomaly!(field = _field, "negative INTEGER converted to unsigned");
+        u32::MAX
+    } else {
+        0
+    };
+    let value = data.iter().fold(init, |acc, &b| (acc << 8) | u32::from(b));
+    Ok((rem, value))
+}
+
+/// Parse an INTEGER with values in `0..2^32`, accepting invalid encodings only if `lenient` is
+/// set (see [`parse_ber_u32_lenient`])
+pub(crate) fn parse_ber_u32<'a>(
+    i: &'a [u8],
+    field: &'static str,
+    lenient: bool,
+) -> IResult<&'a [u8], u32, Error> {
+    if lenient {
+        parse_ber_u32_lenient(i, field)
+    } else {
+        u32::from_ber(i)
+    }
+}
+
+pub fn parse_usm_security_parameters(
+    bytes: &[u8],
+) -> IResult<&[u8], UsmSecurityParameters<'_>, Error> {
+    parse_usm_security_parameters_with(bytes, &ParseOptions::DEFAULT)
+}
+
+pub(crate) fn parse_usm_security_parameters_with<'a>(
+    bytes: &'a [u8],
+    opts: &ParseOptions,
+) -> IResult<&'a [u8], UsmSecurityParameters<'a>, Error> {
     Sequence::from_der_and_then(bytes, |i| {
         let (i, msg_authoritative_engine_id) = <&[u8]>::from_ber(i)?;
-        let (i, msg_authoritative_engine_boots) = u32::from_ber(i)?;
-        let (i, msg_authoritative_engine_time) = u32::from_ber(i)?;
+        let (i, msg_authoritative_engine_boots) =
+            parse_ber_u32(i, "msgAuthoritativeEngineBoots", opts.lenient)?;
+        let (i, msg_authoritative_engine_time) =
+            parse_ber_u32(i, "msgAuthoritativeEngineTime", opts.lenient)?;
         let (i, msg_user_name) = parse_ber_octetstring_as_str(i)?;
         let (i, msg_authentication_parameters) = <&[u8]>::from_ber(i)?;
         let (i, msg_privacy_parameters) = <&[u8]>::from_ber(i)?;
@@ -26,10 +117,14 @@ pub fn parse_usm_security_parameters(bytes: &[u8]) -> IResult<&[u8], UsmSecurity
             msg_authoritative_engine_id,
             msg_authoritative_engine_boots,
             msg_authoritative_engine_time,
-            msg_user_name: msg_user_name.to_string(),
+            msg_user_name: (opts.store)(msg_user_name),
             msg_authentication_parameters,
             msg_privacy_parameters,
         };
         Ok((i, usm))
     })
 }
+
+impl<'a> Tagged for UsmSecurityParameters<'a> {
+    const TAG: Tag = Tag::Sequence;
+}
@@ -0,0 +1,145 @@
+//! Visitor-style message parsing
+//!
+//! [`parse_with_visitor`] walks a message and calls the methods of a [`SnmpVisitor`] for each
+//! field, in order: version, community (or SNMPv3 header data), PDU type and request ID, and
+//! each variable binding. No message structure is built, and the heap is not used.
+//!
+//! Each method can stop the walk by returning [`ControlFlow::Break`], so filters reading only
+//! one or two fields do not pay for the rest of the message:
+//!
+//! ```rust
+//! use core::ops::ControlFlow;
+//! use snmp_parser::visit::{parse_with_visitor, SnmpVisitor};
+//!
+//! # static SNMPV1_REQ: &[u8] = include_bytes!("../assets/snmpv1_req.bin");
+//! #[derive(Default)]
+//! struct CommunityFilter<'a> {
+//!     community: Option<&'a [u8]>,
+//! }
+//!
+//! impl<'a> SnmpVisitor<'a> for CommunityFilter<'a> {
+//!     fn visit_community(&mut self, community: &'a [u8]) -> ControlFlow<()> {
+//!         self.community = Some(community);
+//!         ControlFlow::Break(())
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let mut filter = CommunityFilter::default();
+//! parse_with_visitor(SNMPV1_REQ, &mut filter).expect("parsing failed");
+//! assert_eq!(filter.community, Some(&b"public"[..]));
+//! # }
+//! ```
+
+use asn1_rs::{Any, FromBer, Tag};
+use core::ops::ControlFlow;
+use nom::{Err, IResult};
+
+use crate::error::SnmpError;
+#[cfg(feature = "v3")]
+use crate::inspect::expect_sequence;
+use crate::inspect::{inspect_pdu, RawVarBind, RawVarBinds};
+use crate::snmp::PduType;
+#[cfg(feature = "v3")]
+use crate::snmpv3::{is_scoped_pdu_data_encrypted, HeaderData};
+
+/// Callbacks invoked by [`parse_with_visitor`]
+///
+/// All methods have a default implementation, which continues the walk.
+pub trait SnmpVisitor<'a> {
+    /// Called with the raw-encoded version: 0 for SNMPv1, 1 for SNMPv2c, 3 for SNMPv3
+    fn visit_version(&mut self, _version: u32) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called with the community, for SNMPv1 and SNMPv2c
+    fn visit_community(&mut self, _community: &'a [u8]) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called with the header data, for SNMPv3
+    #[cfg(feature = "v3")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "v3")))]
+    fn visit_header_data(&mut self, _header_data: &HeaderData) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called with the PDU type and request ID (`None` for SNMPv1 traps)
+    ///
+    /// This is not called if the PDU is encrypted.
+    fn visit_pdu(&mut self, _pdu_type: PduType, _request_id: Option<u32>) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called for each variable binding, in order
+    fn visit_varbind(&mut self, _var: &RawVarBind<'a>) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+/// Walk a SNMPv1, SNMPv2c or SNMPv3 message, calling `visitor` for each field
+///
+/// Like [`inspect_snmp_message`](crate::inspect::inspect_snmp_message), the PDU is not fully
+/// validated, variable binding values are not decoded, and messages using version 2
+/// (party-based SNMPv2 and SNMPv2u) are rejected after visiting the version. If the visitor
+/// stops the walk, the remaining fields are not read, and the function returns successfully.
+pub fn parse_with_visitor<'a, V>(i: &'a [u8], visitor: &mut V) -> IResult<&'a [u8], (), SnmpError>
+where
+    V: SnmpVisitor<'a> + ?Sized,
+{
+    let (rem, msg) = Any::from_ber(i).map_err(Err::convert)?;
+    if msg.tag() != Tag::Sequence {
+        return Err(Err::Error(SnmpError::InvalidMessage));
+    }
+    // the walk result is not needed: stopping early is not an error
+    let _ = walk_message(msg.data, visitor)?;
+    Ok((rem, ()))
+}
+
+fn walk_message<'a, V>(i: &'a [u8], visitor: &mut V) -> Result<ControlFlow<()>, Err<SnmpError>>
+where
+    V: SnmpVisitor<'a> + ?Sized,
+{
+    let (i, version) = u32::from_ber(i).map_err(Err::convert)?;
+    if visitor.visit_version(version).is_break() {
+        return Ok(ControlFlow::Break(()));
+    }
+    let i = match version {
+        0 | 1 => {
+            let (i, community) = <&[u8]>::from_ber(i).map_err(Err::convert)?;
+            if visitor.visit_community(community).is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
+            i
+        }
+        #[cfg(feature = "v3")]
+        3 => {
+            let (i, header_data) = HeaderData::from_ber(i).map_err(Err::convert)?;
+            if visitor.visit_header_data(&header_data).is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
+            let (i, _security_params) = <&[u8]>::from_ber(i).map_err(Err::convert)?;
+            if is_scoped_pdu_data_encrypted(i, &header_data) {
+                return Ok(ControlFlow::Continue(()));
+            }
+            let (_, scoped) = Any::from_ber(i).map_err(Err::convert)?;
+            expect_sequence(&scoped)?;
+            let (i, _ctx_engine_id) = <&[u8]>::from_ber(scoped.data).map_err(Err::convert)?;
+            let (i, _ctx_name) = <&[u8]>::from_ber(i).map_err(Err::convert)?;
+            i
+        }
+        _ => return Err(Err::Error(SnmpError::InvalidVersion { found: version })),
+    };
+    let pdu = inspect_pdu(i)?;
+    if visitor.visit_pdu(pdu.pdu_type, pdu.request_id).is_break() {
+        return Ok(ControlFlow::Break(()));
+    }
+    let vars = RawVarBinds { data: pdu.varbinds };
+    for var in vars {
+        let var = var.map_err(Err::Error)?;
+        if visitor.visit_varbind(&var).is_break() {
+            return Ok(ControlFlow::Break(()));
+        }
+    }
+    Ok(ControlFlow::Continue(()))
+}
@@ -0,0 +1,284 @@
+//! Helpers for GetNext and GetBulk exchanges
+//!
+//! When walking a MIB, an agent must return, for each requested variable, the next variable in
+//! lexicographic order (see [RFC3416](https://tools.ietf.org/html/rfc3416) section 4.2.2 and
+//! 4.2.3). Responses which do not follow this order indicate a buggy or spoofed agent.
+//!
+//! The response to a GetBulk request contains the results of the non-repeaters, followed by the
+//! rows of results of the repeaters. [`partition_bulk_response`] splits the response into these
+//! parts.
+//!
+//! [`TableWalk`] groups the variables returned while walking a table into conceptual rows.
+
+use crate::snmp::{PduType, SnmpBulkPdu, SnmpGenericPdu, SnmpPdu, SnmpVariable, VarBindValue};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use asn1_rs::Oid;
+use core::cmp::Ordering;
+
+/// Compare two OIDs in lexicographic order of their arcs
+///
+/// Arcs of any size are supported. Comparing the encoded bytes is not enough, since arcs have a
+/// variable length.
+pub fn cmp_oid(a: &Oid, b: &Oid) -> Ordering {
+    let mut a = a.as_bytes();
+    let mut b = b.as_bytes();
+    loop {
+        match (next_arc(&mut a), next_arc(&mut b)) {
+            (Some(x), Some(y)) => {
+                // arcs are compared by length first, since leading zeroes have been removed
+                let ord = x.len().cmp(&y.len()).then_with(|| cmp_arc_bytes(x, y));
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+        }
+    }
+}
+
+/// Return the bytes of the next arc (without leading `0x80` bytes), and advance `i`
+///
+/// The first arc of a BER-encoded OID combines the first two arcs, in an order-preserving way.
+fn next_arc<'a>(i: &mut &'a [u8]) -> Option<&'a [u8]> {
+    if i.is_empty() {
+        return None;
+    }
+    let end = i
+        .iter()
+        .position(|b| b & 0x80 == 0)
+        .map_or(i.len(), |pos| pos + 1);
+    let (arc, rem) = i.split_at(end);
+    *i = rem;
+    let start = arc.iter().position(|&b| b != 0x80).unwrap_or(arc.len());
+    Some(&arc[start..])
+}
+
+fn cmp_arc_bytes(x: &[u8], y: &[u8]) -> Ordering {
+    x.iter().map(|b| b & 0x7f).cmp(y.iter().map(|b| b & 0x7f))
+}
+
+/// A variable of a response which does not follow the variable it was requested for
+#[derive(Debug, PartialEq)]
+pub struct OrderViolation {
+    /// Index of the variable in the response (starting from 0)
+    pub index: usize,
+    /// OID of the variable in the response
+    pub oid: Oid<'static>,
+    /// OID the variable should follow: the requested OID, or for GetBulk repetitions the OID
+    /// returned in the previous repetition
+    pub previous: Oid<'static>,
+}
+
+/// Check that the variables of a response to a GetNext or GetBulk request are in lexicographic
+/// order
+///
+/// Each variable must follow the corresponding variable of the request (for GetBulk
+/// repetitions, the variable of the previous repetition), except `endOfMibView` exceptions.
+///
+/// For other types of requests, or if `response` is not a Response PDU, no check is done.
+pub fn check_response_order(request: &SnmpPdu, response: &SnmpPdu) -> Vec<OrderViolation> {
+    let resp = match response {
+        SnmpPdu::Generic(pdu) if pdu.pdu_type == PduType::Response => &pdu.var,
+        _ => return Vec::new(),
+    };
+    let mut violations = Vec::new();
+    match request {
+        SnmpPdu::Generic(req) if req.pdu_type == PduType::GetNextRequest => {
+            for (index, (prev, var)) in req.var.iter().zip(resp.iter()).enumerate() {
+                check_order(&mut violations, index, prev, var);
+            }
+        }
+        SnmpPdu::Bulk(req) => {
+            let n = (req.non_repeaters as usize).min(req.var.len());
+            let m = req.var.len() - n;
+            let parts = partition_bulk(req, resp);
+            for (index, var) in parts.non_repeaters.iter().enumerate() {
+                check_order(&mut violations, index, &req.var[index], var);
+            }
+            let mut prev_row = &req.var[n..];
+            for (row_index, row) in parts.rows.iter().enumerate() {
+                for (col, var) in row.iter().enumerate() {
+                    let index = n + row_index * m + col;
+                    check_order(&mut violations, index, &prev_row[col], var);
+                }
+                prev_row = row;
+            }
+        }
+        _ => (),
+    }
+    violations
+}
+
+fn check_order(
+    violations: &mut Vec<OrderViolation>,
+    index: usize,
+    prev: &SnmpVariable,
+    var: &SnmpVariable,
+) {
+    if var.val == VarBindValue::EndOfMibView || prev.val == VarBindValue::EndOfMibView {
+        return;
+    }
+    if cmp_oid(&var.oid, &prev.oid) != Ordering::Greater {
+        snmp_anomaly!(index, "response variable is not in lexicographic order");
+        violations.push(OrderViolation {
+            index,
+            oid: var.oid.to_owned(),
+            previous: prev.oid.to_owned(),
+        });
+    }
+}
+
+/// Variables of a response to a GetBulk request, split into the results of the non-repeaters and
+/// of the repetitions
+#[derive(Debug, PartialEq)]
+pub struct BulkResponse<'r, 'a> {
+    /// Results of the non-repeaters
+    pub non_repeaters: &'r [SnmpVariable<'a>],
+    /// Results of the repeaters: one row per repetition, with one variable per repeater. The last
+    /// row is incomplete if the response was truncated.
+    pub rows: Vec<&'r [SnmpVariable<'a>]>,
+}
+
+/// Split the variables of a response into the results of the non-repeaters, and the rows of
+/// results of the repeaters of a GetBulk request
+///
+/// `non-repeaters` is bounded by the number of variables of the request. If the request has no
+/// repeaters, the variables following the non-repeaters are ignored.
+pub fn partition_bulk_response<'r, 'a>(
+    request: &SnmpBulkPdu,
+    response: &'r SnmpGenericPdu<'a>,
+) -> BulkResponse<'r, 'a> {
+    partition_bulk(request, &response.var)
+}
+
+fn partition_bulk<'r, 'a>(
+    request: &SnmpBulkPdu,
+    var: &'r [SnmpVariable<'a>],
+) -> BulkResponse<'r, 'a> {
+    let n = (request.non_repeaters as usize).min(request.var.len());
+    let m = request.var.len() - n;
+    let (non_repeaters, repeaters) = var.split_at(n.min(var.len()));
+    let rows = if m > 0 {
+        repeaters.chunks(m).collect()
+    } else {
+        Vec::new()
+    };
+    BulkResponse {
+        non_repeaters,
+        rows,
+    }
+}
+
+/// A conceptual row of a table
+#[derive(Debug, PartialEq)]
+pub struct TableRow<'r, 'a> {
+    /// Instance suffix (the index) of the row
+    pub index: Vec<u64>,
+    /// Variables of the row, with their column number, in the order they were received
+    pub columns: Vec<(u64, &'r SnmpVariable<'a>)>,
+}
+
+/// Accumulator grouping the variables returned while walking a table into conceptual rows
+///
+/// The variables of a table entry `E` have OIDs `E.column.index`. Variables are fed from one or
+/// more Response PDUs, and grouped by index. A row is returned as soon as it has a variable for
+/// each of the expected columns; the remaining rows are returned by [`TableWalk::finish`].
+///
+/// Variables outside of the table, exceptions and duplicate columns of a pending row are ignored.
+///
+/// ```rust
+/// # use snmp_parser::walk::TableWalk;
+/// # use snmp_parser::SnmpGenericPdu;
+/// # fn walk<'r, 'a>(responses: &'r [SnmpGenericPdu<'a>]) {
+/// // ifEntry, with columns ifIndex and ifDescr
+/// let entry = "1.3.6.1.2.1.2.2.1".parse().unwrap();
+/// let mut table = TableWalk::new(&entry, 2);
+/// for response in responses {
+///     for row in table.push_response(response) {
+///         println!("{:?}: {:?}", row.index, row.columns);
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TableWalk<'r, 'a> {
+    entry: Vec<u8>,
+    columns: usize,
+    pending: BTreeMap<Vec<u64>, Vec<(u64, &'r SnmpVariable<'a>)>>,
+}
+
+impl<'r, 'a> TableWalk<'r, 'a> {
+    /// Create an accumulator for the table entry `entry`, with `columns` expected columns
+    ///
+    /// If `columns` is 0, rows are only returned by [`TableWalk::finish`].
+    pub fn new(entry: &Oid, columns: usize) -> Self {
+        TableWalk {
+            entry: entry.as_bytes().to_vec(),
+            columns,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Add a variable, and return its row if it is complete
+    pub fn push(&mut self, var: &'r SnmpVariable<'a>) -> Option<TableRow<'r, 'a>> {
+        if !matches!(var.val, VarBindValue::Value(_)) {
+            return None;
+        }
+        let (column, index) = split_table_instance(&self.entry, &var.oid)?;
+        let columns = self.pending.entry(index.clone()).or_default();
+        if columns.iter().any(|&(c, _)| c == column) {
+            return None;
+        }
+        columns.push((column, var));
+        if columns.len() != self.columns {
+            return None;
+        }
+        self.pending
+            .remove_entry(&index)
+            .map(|(index, columns)| TableRow { index, columns })
+    }
+
+    /// Add the variables of a response, and return the completed rows
+    pub fn push_response(&mut self, response: &'r SnmpGenericPdu<'a>) -> Vec<TableRow<'r, 'a>> {
+        response
+            .var
+            .iter()
+            .filter_map(|var| self.push(var))
+            .collect()
+    }
+
+    /// Return the rows which are not complete, ordered by index
+    pub fn finish(self) -> Vec<TableRow<'r, 'a>> {
+        self.pending
+            .into_iter()
+            .map(|(index, columns)| TableRow { index, columns })
+            .collect()
+    }
+}
+
+/// Split the OID of a variable of table entry `entry` (encoded) into column number and index
+pub(crate) fn split_table_instance(entry: &[u8], oid: &Oid) -> Option<(u64, Vec<u64>)> {
+    let mut i = oid.as_bytes().strip_prefix(entry)?;
+    let column = arc_value(next_arc(&mut i)?)?;
+    let mut index = Vec::new();
+    while let Some(arc) = next_arc(&mut i) {
+        index.push(arc_value(arc)?);
+    }
+    if index.is_empty() {
+        return None;
+    }
+    Some((column, index))
+}
+
+/// Return the value of an arc (other than the first one), if it fits in 64 bits
+fn arc_value(arc: &[u8]) -> Option<u64> {
+    arc.iter().try_fold(0u64, |acc, &b| {
+        if acc.leading_zeros() < 7 {
+            return None;
+        }
+        Some((acc << 7) | u64::from(b & 0x7f))
+    })
+}
@@ -0,0 +1,121 @@
+#[macro_use]
+extern crate hex_literal;
+extern crate snmp_parser;
+
+use nom::Err;
+use snmp_parser::agentx::*;
+use snmp_parser::error::SnmpError;
+use snmp_parser::*;
+
+static AGENTX_OPEN: &[u8] = &hex!(
+    "01 01 10 00 00 00 00 00 00 00 00 00 00 00 00 01 00 00 00 20
+     05 00 00 00
+     03 04 00 00 00 00 00 01 00 00 1f 88 00 00 00 01
+     00 00 00 05 61 67 65 6e 74 00 00 00"
+);
+
+// little-endian, with a context
+static AGENTX_GET: &[u8] = &hex!(
+    "01 05 08 00 01 00 00 00 02 00 00 00 03 00 00 00 20 00 00 00
+     03 00 00 00 63 74 78 00
+     04 02 01 00 01 00 00 00 01 00 00 00 01 00 00 00 00 00 00 00
+     00 00 00 00"
+);
+
+static AGENTX_RESPONSE: &[u8] = &hex!(
+    "01 12 10 00 00 00 00 01 00 00 00 02 00 00 00 03 00 00 00 58
+     00 00 00 64 00 00 00 00
+     00 02 00 00 04 02 00 00 00 00 00 01 00 00 00 01 00 00 00 05 00 00 00 00
+     00 00 00 2a
+     00 04 00 00 03 02 00 00 00 00 00 01 00 00 00 01 00 00 00 01
+     00 00 00 02 68 69 00 00
+     00 82 00 00 04 02 00 00 00 00 00 01 00 00 00 01 00 00 00 01 00 00 00 00"
+);
+
+static AGENTX_PING: &[u8] = &hex!("01 0d 10 00 00 00 00 01 00 00 00 02 00 00 00 03 00 00 00 00");
+
+#[test]
+fn test_agentx_open() {
+    let (rem, pdu) = parse_agentx_pdu(AGENTX_OPEN).expect("parsing failed");
+    assert!(rem.is_empty());
+    assert_eq!(pdu.pdu_type(), AgentxPduType::Open);
+    assert_eq!(pdu.header.packet_id, 1);
+    assert_eq!(pdu.context, None);
+    match pdu.payload {
+        AgentxPayload::Open(ref open) => {
+            assert_eq!(open.timeout, 5);
+            let id = open.id.as_ref().expect("no id");
+            assert_eq!(id.to_id_string(), "1.3.6.1.4.1.8072.1");
+            assert_eq!(open.descr, b"agent");
+        }
+        _ => panic!("not an Open PDU"),
+    }
+}
+
+#[test]
+fn test_agentx_get_little_endian() {
+    let (rem, pdu) = parse_agentx_pdu(AGENTX_GET).expect("parsing failed");
+    assert!(rem.is_empty());
+    assert_eq!(pdu.header.session_id, 1);
+    assert_eq!(pdu.header.transaction_id, 2);
+    assert_eq!(pdu.context, Some(&b"ctx"[..]));
+    match pdu.payload {
+        AgentxPayload::Get(ref ranges) => {
+            assert_eq!(ranges.len(), 1);
+            assert_eq!(ranges[0].start.to_id_string(), "1.3.6.1.2.1.1.1.0");
+            assert!(ranges[0].include);
+            assert_eq!(ranges[0].end, None);
+        }
+        _ => panic!("not a Get PDU"),
+    }
+}
+
+#[test]
+fn test_agentx_response() {
+    let (rem, pdu) = parse_agentx_pdu(AGENTX_RESPONSE).expect("parsing failed");
+    assert!(rem.is_empty());
+    match pdu.payload {
+        AgentxPayload::Response(ref r) => {
+            assert_eq!(r.sys_uptime, 100);
+            assert_eq!(r.error, AgentxErrorStatus::NoAgentXError);
+        }
+        _ => panic!("not a Response PDU"),
+    }
+    let vars = pdu.varbinds();
+    assert_eq!(vars.len(), 3);
+    assert_eq!(vars[0].oid.to_id_string(), "1.3.6.1.2.1.1.5.0");
+    assert_eq!(vars[0].val, VarBindValue::Value(ObjectSyntax::Number(42)));
+    assert_eq!(
+        vars[1].val,
+        VarBindValue::Value(ObjectSyntax::String(b"hi"))
+    );
+    assert_eq!(vars[2].val, VarBindValue::EndOfMibView);
+}
+
+#[test]
+fn test_agentx_stream() {
+    let mut data = AGENTX_PING.to_vec();
+    data.extend_from_slice(AGENTX_OPEN);
+    let (rem, pdu) = parse_agentx_pdu(&data).expect("parsing failed");
+    assert_eq!(pdu.payload, AgentxPayload::Ping);
+    assert_eq!(rem, AGENTX_OPEN);
+    // truncated PDU
+    let res = parse_agentx_pdu(&AGENTX_OPEN[..30]);
+    assert!(matches!(res, Err(Err::Incomplete(_))));
+}
+
+#[test]
+fn test_agentx_errors() {
+    let mut data = AGENTX_PING.to_vec();
+    data[0] = 2;
+    assert_eq!(
+        parse_agentx_pdu(&data),
+        Err(Err::Error(SnmpError::InvalidVersion { found: 2 }))
+    );
+    data[0] = 1;
+    data[1] = 42;
+    assert_eq!(
+        parse_agentx_pdu(&data),
+        Err(Err::Failure(SnmpError::InvalidPduType { tag: 42 }))
+    );
+}
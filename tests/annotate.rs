@@ -0,0 +1,123 @@
+extern crate snmp_parser;
+
+use asn1_rs::{Class, Tag};
+use snmp_parser::annotate::*;
+
+static SNMPV1_REQ: &[u8] = include_bytes!("../assets/snmpv1_req.bin");
+static SNMPV3_REQ: &[u8] = include_bytes!("../assets/snmpv3_req.bin");
+
+#[test]
+fn test_field_spans_v1() {
+    let spans = field_spans(SNMPV1_REQ);
+    let fields: Vec<_> = spans.iter().map(|s| s.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        [
+            "message",
+            "version",
+            "community",
+            "pdu",
+            "pdu.request_id",
+            "pdu.error_status",
+            "pdu.error_index",
+            "pdu.varbinds",
+            "pdu.varbinds.varbind[0]",
+            "pdu.varbinds.varbind[0].name",
+            "pdu.varbinds.varbind[0].value",
+        ]
+    );
+    let community = &spans[2];
+    assert_eq!(community.depth, 1);
+    assert_eq!(community.range(), 5..13);
+    assert_eq!(
+        &SNMPV1_REQ[community.offset + community.header_len..][..community.len],
+        b"public"
+    );
+    assert_eq!(
+        spans[3].ber,
+        Some(BerInfo {
+            class: Class::ContextSpecific,
+            tag: Tag(0),
+            constructed: true
+        })
+    );
+    assert_eq!(spans[0].range(), 0..SNMPV1_REQ.len());
+}
+
+#[test]
+fn test_field_spans_v3() {
+    let spans = field_spans(SNMPV3_REQ);
+    let user = spans
+        .iter()
+        .find(|s| s.name() == "msg_user_name")
+        .expect("user name not found");
+    assert_eq!(user.field, "security_parameters.usm.msg_user_name");
+    assert_eq!(user.depth, 3);
+    assert!(spans.iter().any(|s| s.field == "data.pdu.request_id"));
+}
+
+#[test]
+fn test_annotated_hexdump() {
+    let dump = annotated_hexdump(SNMPV1_REQ).to_string();
+    let lines: Vec<_> = dump.lines().collect();
+    assert_eq!(lines.len(), 11);
+    assert!(lines[0].starts_with("0000  30 26 "));
+    assert!(lines[0].ends_with("message: SEQUENCE (38)"));
+    assert!(lines[2].ends_with("  community: OCTET STRING (6) = \"public\""));
+    assert!(lines[3].ends_with("  pdu: GetRequest (25)"));
+    assert!(lines[9].ends_with("  name: OBJECT IDENTIFIER (8) = 1.3.6.1.2.1.1.2.0"));
+    // fields are aligned, and indented by depth
+    let column = |line: &str, name: &str| line.find(name).unwrap();
+    assert_eq!(column(lines[1], "version"), column(lines[0], "message") + 2);
+    assert_eq!(
+        column(lines[4], "request_id"),
+        column(lines[0], "message") + 4
+    );
+}
+
+#[test]
+fn test_annotated_hexdump_invalid() {
+    // truncated varbind, followed by trailing data after the message
+    let mut data = SNMPV1_REQ.to_vec();
+    data[0x1b] = 0x20;
+    data.extend_from_slice(&[0xde, 0xad]);
+    let spans = field_spans(&data);
+    let last = &spans[spans.len() - 2];
+    assert_eq!(last.field, "pdu.varbinds.varbind[0]");
+    assert_eq!(last.ber, None);
+    assert_eq!(last.range(), 0x1a..SNMPV1_REQ.len());
+    let trailing = &spans[spans.len() - 1];
+    assert_eq!(trailing.field, "trailing");
+    assert_eq!(trailing.range(), SNMPV1_REQ.len()..data.len());
+    let dump = annotated_hexdump(&data).to_string();
+    assert!(dump.ends_with("trailing: 2 bytes, not decoded\n"));
+    assert!(field_spans(&[]).is_empty());
+}
+
+#[test]
+fn test_explain() {
+    let text = explain(SNMPV1_REQ).to_string();
+    let lines: Vec<_> = text.lines().collect();
+    assert_eq!(lines.len(), 11);
+    assert_eq!(
+        lines[0],
+        "message: SEQUENCE [UNIVERSAL 16, constructed] at 0, header 2, length 38"
+    );
+    assert_eq!(
+        lines[3],
+        "  pdu: GetRequest [CONTEXT-SPECIFIC 0, constructed] at 13, header 2, length 25"
+    );
+    // long-form length, and integer with a leading zero
+    let mut data = vec![0x30, 0x81, 0x27, 0x02, 0x02, 0x00, 0x00];
+    data.extend_from_slice(&SNMPV1_REQ[5..]);
+    let text = explain(&data).to_string();
+    let lines: Vec<_> = text.lines().collect();
+    assert!(lines[0].ends_with("header 3, length 39 (non-minimal length)"));
+    assert_eq!(
+        lines[1],
+        "  version: INTEGER [UNIVERSAL 2, primitive] at 3, header 2, length 2 (non-minimal integer) = 0"
+    );
+    assert!(explain(&[0x04])
+        .to_string()
+        .ends_with("1 bytes at 0, not decoded\n"));
+}
@@ -0,0 +1,77 @@
+extern crate snmp_parser;
+
+use snmp_parser::anomaly::*;
+use snmp_parser::audit::SetDuplicate;
+use snmp_parser::exchange::ResponseViolation;
+use snmp_parser::{oid, CommunityPolicy};
+use std::net::{IpAddr, Ipv4Addr};
+
+fn addr(last: u8) -> IpAddr {
+    IpAddr::V4(Ipv4Addr::new(192, 0, 2, last))
+}
+
+#[test]
+fn test_anomaly_summary() {
+    let mut summary = AnomalySummary::new();
+    let mismatch = ResponseViolation::RequestIdMismatch {
+        request: 1,
+        response: 2,
+    };
+    let counter64 = ResponseViolation::Counter64InV1 { index: 0 };
+    summary.record(addr(1), &[mismatch, counter64]);
+    summary.record::<ResponseViolation>(addr(1), &[]);
+    summary.record(addr(2), &["MaxDepthExceeded"]);
+    assert_eq!(summary.messages(), 3);
+    assert_eq!(summary.anomalous_messages(), 2);
+    assert_eq!(summary.total(), 3);
+    assert_eq!(summary.count("RequestIdMismatch"), 1);
+    assert_eq!(summary.count("OidMismatch"), 0);
+    assert_eq!(summary.source_count(addr(1)), 2);
+    assert_eq!(summary.source_count(addr(3)), 0);
+    assert_eq!(
+        summary.source_kinds(addr(1)).collect::<Vec<_>>(),
+        vec![("Counter64InV1", 1), ("RequestIdMismatch", 1)]
+    );
+    assert_eq!(summary.top_sources(1), vec![(addr(1), 2)]);
+}
+
+#[test]
+fn test_anomaly_summary_merge() {
+    let mut worker1 = AnomalySummary::new();
+    let mut worker2 = AnomalySummary::new();
+    worker1.record(addr(1), &["TrailingData"]);
+    worker2.record(addr(1), &["TrailingData", "LengthOvershoot"]);
+    worker2.record(addr(2), &["TrailingData"]);
+    let mut total = AnomalySummary::new();
+    total.merge(&worker1.snapshot());
+    total.merge(&worker2.snapshot());
+    assert_eq!(worker1, AnomalySummary::new());
+    assert_eq!(worker2.messages(), 0);
+    assert_eq!(total.messages(), 3);
+    assert_eq!(
+        total.kinds().collect::<Vec<_>>(),
+        vec![("LengthOvershoot", 1), ("TrailingData", 3)]
+    );
+    assert_eq!(
+        total.sources().collect::<Vec<_>>(),
+        vec![(addr(1), 3), (addr(2), 1)]
+    );
+}
+
+#[test]
+fn test_anomaly_kinds() {
+    let mut summary = AnomalySummary::new();
+    let violation = CommunityPolicy::default().check(b"").expect("no violation");
+    summary.record(addr(1), &[violation]);
+    let duplicate = SetDuplicate {
+        oid: oid!(1.3.6 .1 .2 .1 .1 .5 .0),
+        first: 0,
+        index: 1,
+        conflicting: true,
+    };
+    summary.record(addr(1), &[duplicate]);
+    assert_eq!(
+        summary.kinds().collect::<Vec<_>>(),
+        vec![("EmptyCommunity", 1), ("SetConflict", 1)]
+    );
+}
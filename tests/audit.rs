@@ -0,0 +1,157 @@
+extern crate snmp_parser;
+
+use snmp_parser::audit::*;
+use snmp_parser::mib::*;
+use snmp_parser::*;
+
+fn set_request(pdu_type: PduType) -> SnmpPdu<'static> {
+    SnmpPdu::Generic(SnmpGenericPdu {
+        pdu_type,
+        req_id: 1,
+        err: ErrorStatus::NoError,
+        err_index: 0,
+        var: vec![
+            SnmpVariable {
+                oid: oid!(1.3.6 .1 .2 .1 .1 .5 .0),
+                val: VarBindValue::Value(ObjectSyntax::String(b"router-1")),
+            },
+            SnmpVariable {
+                oid: oid!(1.3.6 .1 .2 .1 .2 .2 .1 .7 .3),
+                val: VarBindValue::Value(ObjectSyntax::Number(2)),
+            },
+        ],
+    })
+}
+
+#[test]
+fn test_set_audit() {
+    let pdu = set_request(PduType::SetRequest);
+    let entries = SetAudit::new().entries(&pdu);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].name, None);
+    assert_eq!(entries[0].value, "\"router-1\"");
+    assert_eq!(entries[0].value_type, "OCTET STRING");
+    assert_eq!(entries[1].to_string(), "1.3.6.1.2.1.2.2.1.7.3 = INTEGER: 2");
+    let names = |oid: &Oid| {
+        if oid.starts_with(&oid!(1.3.6 .1 .2 .1 .2 .2 .1 .7)) {
+            Some("ifAdminStatus".to_string())
+        } else {
+            None
+        }
+    };
+    let entries = SetAudit::with_names(&names).entries(&pdu);
+    assert_eq!(entries[0].name, None);
+    assert_eq!(
+        entries[1].to_string(),
+        "ifAdminStatus (1.3.6.1.2.1.2.2.1.7.3) = INTEGER: 2"
+    );
+    // not a set request
+    let pdu = set_request(PduType::GetRequest);
+    assert!(SetAudit::new().entries(&pdu).is_empty());
+}
+
+#[test]
+fn test_set_duplicates() {
+    let mut pdu = set_request(PduType::SetRequest);
+    assert!(check_set_duplicates(&pdu).is_empty());
+    if let SnmpPdu::Generic(ref mut set) = pdu {
+        set.var.push(SnmpVariable {
+            oid: oid!(1.3.6 .1 .2 .1 .1 .5 .0),
+            val: VarBindValue::Value(ObjectSyntax::String(b"router-1")),
+        });
+        set.var.push(SnmpVariable {
+            oid: oid!(1.3.6 .1 .2 .1 .2 .2 .1 .7 .3),
+            val: VarBindValue::Value(ObjectSyntax::Number(1)),
+        });
+    }
+    assert_eq!(
+        check_set_duplicates(&pdu),
+        vec![
+            SetDuplicate {
+                oid: oid!(1.3.6 .1 .2 .1 .1 .5 .0),
+                first: 0,
+                index: 2,
+                conflicting: false,
+            },
+            SetDuplicate {
+                oid: oid!(1.3.6 .1 .2 .1 .2 .2 .1 .7 .3),
+                first: 1,
+                index: 3,
+                conflicting: true,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_set_access() {
+    let mut registry = MibRegistry::new();
+    let sys_name = oid!(1.3.6 .1 .2 .1 .1 .5);
+    registry.insert(
+        &sys_name,
+        "sysName",
+        MaxAccess::ReadOnly,
+        MibSyntax::OctetString,
+    );
+    let pdu = set_request(PduType::SetRequest);
+    let report = check_set_access(&registry, &pdu);
+    assert!(!report.is_ok());
+    assert_eq!(report.unknown, vec![1]);
+    assert_eq!(report.violations.len(), 1);
+    let violation = &report.violations[0];
+    assert_eq!(violation.index, 0);
+    assert_eq!(violation.name, "sysName");
+    assert_eq!(violation.error_status(), ErrorStatus::NotWritable);
+    // writable objects
+    registry.insert(
+        &sys_name,
+        "sysName",
+        MaxAccess::ReadWrite,
+        MibSyntax::OctetString,
+    );
+    registry.insert(
+        &oid!(1.3.6 .1 .2 .1 .2 .2 .1 .7),
+        "ifAdminStatus",
+        MaxAccess::ReadWrite,
+        MibSyntax::Integer,
+    );
+    let report = check_set_access(&registry, &pdu);
+    assert!(report.is_ok());
+    assert!(report.unknown.is_empty());
+    let entries = SetAudit::with_names(&registry).entries(&pdu);
+    assert_eq!(entries[0].name.as_deref(), Some("sysName.0"));
+    assert_eq!(entries[1].name.as_deref(), Some("ifAdminStatus.3"));
+}
+
+#[test]
+fn test_value_types() {
+    let mut registry = MibRegistry::new();
+    let (rw, syntax) = (MaxAccess::ReadWrite, MibSyntax::Integer);
+    registry.insert(&oid!(1.3.6 .1 .2 .1 .1 .5), "sysName", rw, syntax);
+    let pdu = set_request(PduType::SetRequest);
+    let mismatches = check_value_types(&registry, &pdu);
+    assert_eq!(
+        mismatches,
+        vec![TypeMismatch {
+            index: 0,
+            oid: oid!(1.3.6 .1 .2 .1 .1 .5 .0),
+            name: "sysName".to_string(),
+            expected: MibSyntax::Integer,
+            found: "OCTET STRING",
+        }]
+    );
+    let mut pdu = set_request(PduType::Response);
+    assert_eq!(check_value_types(&registry, &pdu).len(), 1);
+    // exceptions are only checked in SetRequests
+    if let SnmpPdu::Generic(ref mut pdu) = pdu {
+        pdu.var[0].val = VarBindValue::NoSuchObject;
+    }
+    assert!(check_value_types(&registry, &pdu).is_empty());
+    if let SnmpPdu::Generic(ref mut pdu) = pdu {
+        pdu.pdu_type = PduType::SetRequest;
+    }
+    assert_eq!(check_value_types(&registry, &pdu)[0].found, "noSuchObject");
+    // not checked
+    let pdu = set_request(PduType::GetRequest);
+    assert!(check_value_types(&registry, &pdu).is_empty());
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "capi")]
+
+use snmp_parser::capi::*;
+use std::ffi::CStr;
+use std::ptr;
+
+static SNMPV1_REQ: &[u8] = include_bytes!("../assets/snmpv1_req.bin");
+
+#[test]
+fn test_capi_parse() {
+    let mut msg = ptr::null_mut();
+    unsafe {
+        let rc = snmp_parse(SNMPV1_REQ.as_ptr(), SNMPV1_REQ.len(), &mut msg);
+        assert_eq!(rc, SNMP_OK);
+        assert!(!msg.is_null());
+        assert_eq!(snmp_message_version(msg), 0);
+        let mut len = 0;
+        let community = snmp_message_community(msg, &mut len);
+        assert_eq!(std::slice::from_raw_parts(community, len), b"public");
+        assert!(snmp_message_user(msg, &mut len).is_null());
+        assert_eq!(snmp_message_pdu_type(msg), 0);
+        assert_eq!(snmp_message_is_encrypted(msg), 0);
+        assert_eq!(snmp_message_varbind_count(msg), 1);
+        let oid = CStr::from_ptr(snmp_message_varbind_oid(msg, 0));
+        assert_eq!(oid.to_str(), Ok("1.3.6.1.2.1.1.2.0"));
+        assert_eq!(
+            snmp_message_varbind_type(msg, 0),
+            SnmpValueType::Unspecified
+        );
+        assert!(snmp_message_varbind_oid(msg, 1).is_null());
+        snmp_message_free(msg);
+    }
+}
+
+#[test]
+fn test_capi_errors() {
+    let mut msg = ptr::null_mut();
+    let mut bytes = SNMPV1_REQ.to_vec();
+    bytes[4] = 7; // version
+    unsafe {
+        let rc = snmp_parse(bytes.as_ptr(), bytes.len(), &mut msg);
+        assert_eq!(rc, 2);
+        assert!(msg.is_null());
+        let name = CStr::from_ptr(snmp_error_name(rc));
+        assert_eq!(name.to_str(), Ok("InvalidVersion"));
+        let rc = snmp_parse(ptr::null(), 0, &mut msg);
+        assert_eq!(rc, SNMP_ERR_INVALID_ARGUMENT);
+        let rc = snmp_parse(bytes.as_ptr(), 3, &mut msg);
+        assert!(rc > 0);
+        assert!(msg.is_null());
+    }
+}
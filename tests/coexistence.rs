@@ -0,0 +1,197 @@
+#![cfg(feature = "v1")]
+
+extern crate snmp_parser;
+
+use asn1_rs::Oid;
+use snmp_parser::coexistence::*;
+use snmp_parser::trap::SNMP_TRAP_OID;
+use snmp_parser::*;
+
+static SNMPV1_TRAP_COLDSTART: &[u8] = include_bytes!("../assets/snmpv1_trap_coldstart.bin");
+static SNMPV1_REQ: &[u8] = include_bytes!("../assets/snmpv1_req.bin");
+
+fn response(err: ErrorStatus, vals: Vec<VarBindValue<'static>>) -> SnmpMessage<'static> {
+    let var = vals
+        .into_iter()
+        .enumerate()
+        .map(|(i, val)| SnmpVariable {
+            oid: Oid::from(&[1, 3, 6, 1, 2, 1, 1, i as u64 + 1, 0]).unwrap(),
+            val,
+        })
+        .collect();
+    SnmpMessage {
+        version: 1,
+        community: "public".to_string(),
+        pdu: SnmpPdu::Generic(SnmpGenericPdu {
+            pdu_type: PduType::Response,
+            req_id: 1,
+            err,
+            err_index: 0,
+            var,
+        }),
+    }
+}
+
+#[test]
+fn test_coexistence_trap_v1_to_v2c() {
+    let (_, msg) = parse_snmp_v1(SNMPV1_TRAP_COLDSTART).expect("parsing failed");
+    let msg = v1_to_v2c(msg).expect("translation failed");
+    assert_eq!(msg.version, 1);
+    assert_eq!(msg.pdu_type(), PduType::TrapV2);
+    let vars: Vec<_> = msg.vars_iter().collect();
+    assert_eq!(vars.len(), 5);
+    assert_eq!(vars[0].oid, SYS_UPTIME_OID);
+    assert_eq!(vars[1].oid, SNMP_TRAP_OID);
+    assert_eq!(
+        vars[1].val,
+        VarBindValue::Value(ObjectSyntax::Object(
+            Oid::from(&[1, 3, 6, 1, 6, 3, 1, 1, 5, 1]).unwrap()
+        ))
+    );
+    assert_eq!(vars[3].oid, SNMP_TRAP_ADDRESS_OID);
+    assert_eq!(vars[4].oid, SNMP_TRAP_ENTERPRISE_OID);
+    assert_eq!(
+        vars[4].val,
+        VarBindValue::Value(ObjectSyntax::Object(
+            Oid::from(&[1, 3, 6, 1, 4, 1, 4, 1, 2, 21]).unwrap()
+        ))
+    );
+}
+
+#[test]
+fn test_coexistence_trap_roundtrip() {
+    let (_, msg) = parse_snmp_v1(SNMPV1_TRAP_COLDSTART).expect("parsing failed");
+    let msg = v1_to_v2c(msg)
+        .and_then(v2c_to_v1)
+        .expect("translation failed");
+    let (_, orig) = parse_snmp_v1(SNMPV1_TRAP_COLDSTART).expect("parsing failed");
+    assert_eq!(msg, orig);
+}
+
+#[test]
+fn test_coexistence_trap_enterprise_specific() {
+    let (_, mut msg) = parse_snmp_v1(SNMPV1_TRAP_COLDSTART).expect("parsing failed");
+    if let SnmpPdu::TrapV1(ref mut trap) = msg.pdu {
+        trap.generic_trap = TrapType::ENTERPRISE_SPECIFIC;
+        trap.specific_trap = 42;
+    }
+    let msg = v1_to_v2c(msg).expect("translation failed");
+    assert_eq!(
+        msg.pdu.snmp_trap_oid().map(|oid| oid.to_id_string()),
+        Some("1.3.6.1.4.1.4.1.2.21.0.42".to_string())
+    );
+    let msg = v2c_to_v1(msg).expect("translation failed");
+    match msg.pdu {
+        SnmpPdu::TrapV1(trap) => {
+            assert_eq!(trap.generic_trap, TrapType::ENTERPRISE_SPECIFIC);
+            assert_eq!(trap.specific_trap, 42);
+            assert_eq!(trap.enterprise.to_id_string(), "1.3.6.1.4.1.4.1.2.21");
+        }
+        pdu => panic!("unexpected PDU {:?}", pdu),
+    }
+}
+
+#[test]
+fn test_trap_v1_notification_oid() {
+    let (_, mut msg) = parse_snmp_v1(SNMPV1_TRAP_COLDSTART).expect("parsing failed");
+    let trap = match msg.pdu {
+        SnmpPdu::TrapV1(ref mut trap) => trap,
+        ref pdu => panic!("unexpected PDU {:?}", pdu),
+    };
+    // coldStart
+    assert_eq!(
+        trap.notification_oid().map(|oid| oid.to_id_string()),
+        Some("1.3.6.1.6.3.1.1.5.1".to_string())
+    );
+    trap.generic_trap = TrapType::ENTERPRISE_SPECIFIC;
+    trap.specific_trap = 42;
+    assert_eq!(
+        trap_v1_oid(trap).map(|oid| oid.to_id_string()),
+        Ok("1.3.6.1.4.1.4.1.2.21.0.42".to_string())
+    );
+    trap.generic_trap = TrapType(7);
+    assert_eq!(trap.notification_oid(), None);
+    assert_eq!(trap_v1_oid(trap), Err(CoexistenceError::InvalidTrap));
+}
+
+#[test]
+fn test_coexistence_response_exception() {
+    let msg = response(
+        ErrorStatus::NoError,
+        vec![VarBindValue::Unspecified, VarBindValue::NoSuchInstance],
+    );
+    let msg = v2c_to_v1(msg).expect("translation failed");
+    assert_eq!(msg.version, 0);
+    match msg.pdu {
+        SnmpPdu::Generic(pdu) => {
+            assert_eq!(pdu.err, ErrorStatus::NoSuchName);
+            assert_eq!(pdu.err_index, 2);
+            assert_eq!(pdu.var[1].val, VarBindValue::Unspecified);
+        }
+        pdu => panic!("unexpected PDU {:?}", pdu),
+    }
+}
+
+#[test]
+fn test_coexistence_error_status() {
+    let msg = response(ErrorStatus::WrongValue, vec![VarBindValue::Unspecified]);
+    let msg = v2c_to_v1(msg).expect("translation failed");
+    match msg.pdu {
+        SnmpPdu::Generic(pdu) => assert_eq!(pdu.err, ErrorStatus::BadValue),
+        pdu => panic!("unexpected PDU {:?}", pdu),
+    }
+    assert_eq!(
+        error_status_v2_to_v1(ErrorStatus::NotWritable),
+        ErrorStatus::NoSuchName
+    );
+    assert_eq!(
+        error_status_v2_to_v1(ErrorStatus::CommitFailed),
+        ErrorStatus::GenErr
+    );
+}
+
+#[test]
+fn test_coexistence_unsupported() {
+    let mut msg = response(ErrorStatus::NoError, vec![]);
+    if let SnmpPdu::Generic(ref mut pdu) = msg.pdu {
+        pdu.pdu_type = PduType::InformRequest;
+    }
+    assert_eq!(
+        v2c_to_v1(msg),
+        Err(CoexistenceError::UnsupportedPdu(PduType::InformRequest))
+    );
+    let (_, msg) = parse_snmp_v1(SNMPV1_REQ).expect("parsing failed");
+    assert_eq!(
+        v2c_to_v1(msg),
+        Err(CoexistenceError::InvalidVersion { found: 0 })
+    );
+}
+
+#[test]
+fn test_coexistence_trap_oid_to_v1() {
+    let oid = |s: &str| -> Oid<'static> { s.parse().expect("invalid OID") };
+    // linkDown
+    let (enterprise, generic, specific) =
+        trap_oid_to_v1(&oid("1.3.6.1.6.3.1.1.5.3"), None).expect("invalid trap");
+    assert_eq!(enterprise, SNMP_TRAPS_OID);
+    assert_eq!(generic, TrapType::LINK_DOWN);
+    assert_eq!(specific, 0);
+    let (enterprise, _, _) =
+        trap_oid_to_v1(&oid("1.3.6.1.6.3.1.1.5.3"), Some(oid("1.3.6.1.4.1.9")))
+            .expect("invalid trap");
+    assert_eq!(enterprise, oid("1.3.6.1.4.1.9"));
+    // enterprise-specific, with and without the 0 arc
+    let (enterprise, generic, specific) =
+        trap_oid_to_v1(&oid("1.3.6.1.4.1.9.0.7"), None).expect("invalid trap");
+    assert_eq!(enterprise, oid("1.3.6.1.4.1.9"));
+    assert_eq!(generic, TrapType::ENTERPRISE_SPECIFIC);
+    assert_eq!(specific, 7);
+    let (enterprise, _, specific) =
+        trap_oid_to_v1(&oid("1.3.6.1.4.1.9.7"), None).expect("invalid trap");
+    assert_eq!(enterprise, oid("1.3.6.1.4.1.9"));
+    assert_eq!(specific, 7);
+    assert_eq!(
+        trap_oid_to_v1(&oid("1.3"), None),
+        Err(CoexistenceError::InvalidTrap)
+    );
+}
@@ -0,0 +1,36 @@
+//! Helpers shared by the integration tests
+
+// each test crate only uses some of the helpers
+#![allow(dead_code)]
+
+use snmp_parser::*;
+
+pub fn oid(s: &str) -> Oid<'static> {
+    s.parse().expect("invalid OID")
+}
+
+pub fn var(oid: Oid<'static>, val: ObjectSyntax<'static>) -> SnmpVariable<'static> {
+    SnmpVariable {
+        oid,
+        val: VarBindValue::Value(val),
+    }
+}
+
+pub fn response(var: Vec<SnmpVariable<'static>>) -> SnmpPdu<'static> {
+    SnmpPdu::Generic(SnmpGenericPdu {
+        pdu_type: PduType::Response,
+        req_id: 1,
+        err: ErrorStatus::NoError,
+        err_index: 0,
+        var,
+    })
+}
+
+/// Variable with the OID `s`, and any value (including exceptions)
+pub fn varbind(s: &str, val: VarBindValue<'static>) -> SnmpVariable<'static> {
+    SnmpVariable { oid: oid(s), val }
+}
+
+pub fn null(s: &str) -> SnmpVariable<'static> {
+    varbind(s, VarBindValue::Unspecified)
+}
@@ -0,0 +1,57 @@
+#![cfg(feature = "conformance")]
+
+extern crate snmp_parser;
+
+use snmp_parser::conformance::*;
+use snmp_parser::error::SnmpErrorCode;
+use snmp_parser::*;
+
+#[test]
+fn test_conformance_reference() {
+    let report = run(|data: &[u8]| Outcome::of(SnmpGenericMessage::parse(data)));
+    assert!(report.is_success(), "failures: {:?}", report.failures);
+    assert_eq!(report.passed, vectors().len());
+}
+
+#[test]
+fn test_conformance_vectors() {
+    let vectors = vectors();
+    let mut names: Vec<_> = vectors.iter().map(|v| v.name).collect();
+    names.sort_unstable();
+    names.dedup();
+    assert_eq!(names.len(), vectors.len());
+    let v1 = vectors.iter().find(|v| v.name == "snmpv1_req").unwrap();
+    match v1.expected() {
+        Outcome::Decoded(summary) => {
+            assert_eq!(summary.version, 0);
+            assert_eq!(summary.len, v1.data.len());
+            assert_eq!(summary.pdu_type, Some(PduType::GetRequest));
+            assert_eq!(summary.request_id, Some(38));
+            assert_eq!(
+                summary.varbinds,
+                [(String::from("1.3.6.1.2.1.1.2.0"), vec![0x05, 0x00])]
+            );
+        }
+        outcome => panic!("unexpected outcome {:?}", outcome),
+    }
+    assert!(vectors
+        .iter()
+        .any(|v| v.error == Some(SnmpErrorCode::MaxDepthExceeded)));
+}
+
+#[test]
+fn test_conformance_failures() {
+    // a decoder returning the wrong error for invalid versions
+    let report = run(|data: &[u8]| match data.get(4) {
+        Some(7) => Outcome::Rejected(SnmpErrorCode::InvalidPdu),
+        _ => Outcome::of(SnmpGenericMessage::parse(data)),
+    });
+    assert_eq!(report.failures.len(), 1);
+    let failure = &report.failures[0];
+    assert_eq!(failure.name, "invalid-version");
+    assert_eq!(
+        failure.expected,
+        Outcome::Rejected(SnmpErrorCode::InvalidVersion)
+    );
+    assert_eq!(failure.found, Outcome::Rejected(SnmpErrorCode::InvalidPdu));
+}
@@ -0,0 +1,45 @@
+#![cfg(all(feature = "miette", feature = "v1"))]
+
+use miette::Diagnostic;
+use snmp_parser::*;
+
+static SNMPV1_REQ: &[u8] = include_bytes!("../assets/snmpv1_req.bin");
+
+#[test]
+fn test_diagnostic_report() {
+    let mut bytes = SNMPV1_REQ.to_vec();
+    // replace the PDU tag with an invalid one
+    let pos = bytes.iter().position(|&b| b == 0xa0).expect("no PDU");
+    bytes[pos] = 0xaf;
+    let err = match parse_snmp_v1(&bytes) {
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => e,
+        e => panic!("unexpected result: {:?}", e),
+    };
+    assert_eq!(err.offset(), Some(pos));
+    let report = err.with_input(&bytes);
+    assert_eq!(
+        report.code().map(|c| c.to_string()),
+        Some("snmp_parser::InvalidPduType".to_string())
+    );
+    let labels: Vec<_> = report.labels().expect("no labels").collect();
+    assert_eq!(labels.len(), 1);
+    let source = report.source_code().expect("no source");
+    let contents = source
+        .read_span(labels[0].inner(), 0, 0)
+        .expect("read_span");
+    assert_eq!(contents.data(), b"af");
+}
+
+#[test]
+#[cfg(all(feature = "v2c", feature = "v2p", feature = "v2u", feature = "v3"))]
+fn test_diagnostic_version_help() {
+    let err = snmp_parser::error::SnmpError::InvalidVersion { found: 5 };
+    assert_eq!(
+        err.help().map(|h| h.to_string()),
+        Some(
+            "the version field must be 0 (SNMPv1), 1 (SNMPv2c), \
+             2 (party-based SNMPv2 or SNMPv2u) or 3 (SNMPv3)"
+                .to_string()
+        )
+    );
+}
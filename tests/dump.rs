@@ -0,0 +1,112 @@
+extern crate snmp_parser;
+
+use snmp_parser::dump::*;
+use snmp_parser::simulate::Simulator;
+use snmp_parser::*;
+use std::net::Ipv4Addr;
+
+static SNMPWALK: &str = r#".1.3.6.1.2.1.1.1.0 = STRING: "Linux router-1 5.10 \"lts\""
+.1.3.6.1.2.1.1.2.0 = OID: .1.3.6.1.4.1.8072.3.2.10
+.1.3.6.1.2.1.1.3.0 = Timeticks: (970069) 2:41:40.69
+.1.3.6.1.2.1.1.4.0 = ""
+.1.3.6.1.2.1.2.2.1.6.2 = Hex-STRING: 00 1B 21 3A 4B 5C 00 1B 21 3A 4B 5C 00 1B 21 3A
+4B 5C
+.1.3.6.1.2.1.2.2.1.8.2 = INTEGER: up(1)
+.1.3.6.1.2.1.2.2.1.10.2 = Counter32: 1234
+.1.3.6.1.2.1.4.20.1.1.10.0.0.1 = IpAddress: 10.0.0.1
+.1.3.6.1.2.1.31.1.1.1.6.2 = Counter64: 12345678901
+.1.3.6.1.2.1.99.1.0 = No Such Instance currently exists at this OID
+"#;
+
+#[test]
+fn test_parse_snmpwalk() {
+    let entries = parse_snmpwalk(SNMPWALK).expect("parsing failed");
+    assert_eq!(entries.len(), 9);
+    assert_eq!(entries[0].oid, oid!(1.3.6 .1 .2 .1 .1 .1 .0));
+    assert_eq!(
+        entries[0].value(),
+        ObjectSyntax::String(b"Linux router-1 5.10 \"lts\"")
+    );
+    assert_eq!(
+        entries[1].value(),
+        ObjectSyntax::Object(oid!(1.3.6 .1 .4 .1 .8072 .3 .2 .10))
+    );
+    assert_eq!(entries[2].value(), ObjectSyntax::TimeTicks(970069));
+    assert_eq!(entries[3].value(), ObjectSyntax::String(b""));
+    match entries[4].value() {
+        ObjectSyntax::String(s) => assert_eq!(s.len(), 18),
+        v => panic!("unexpected value {:?}", v),
+    }
+    assert_eq!(entries[5].value(), ObjectSyntax::Number(1));
+    assert_eq!(entries[6].value(), ObjectSyntax::Counter32(1234));
+    assert_eq!(
+        entries[7].value(),
+        ObjectSyntax::IpAddress(NetworkAddress::IPv4(Ipv4Addr::new(10, 0, 0, 1)))
+    );
+    assert_eq!(entries[8].value(), ObjectSyntax::Counter64(12345678901));
+    // lines are written back identically
+    let lines: Vec<_> = entries
+        .iter()
+        .filter_map(|e| snmpwalk_line(&e.oid, &e.value()))
+        .collect();
+    assert_eq!(lines[0], SNMPWALK.lines().next().unwrap());
+    assert_eq!(
+        lines[2],
+        ".1.3.6.1.2.1.1.3.0 = Timeticks: (970069) 2:41:40.69"
+    );
+    assert_eq!(lines[5], ".1.3.6.1.2.1.2.2.1.8.2 = INTEGER: 1");
+}
+
+#[test]
+fn test_parse_snmpwalk_error() {
+    let data = ".1.3.6.1.2.1.1.5.0 = STRING: \"ok\"\n.1.3.6.1.2.1.1.6.0 = Float: 1.5\n";
+    assert_eq!(parse_snmpwalk(data), Err(DumpError { line: 2 }));
+    assert_eq!(parse_snmpwalk("garbage\n"), Err(DumpError { line: 1 }));
+}
+
+#[test]
+fn test_snmprec_roundtrip() {
+    let data = "\
+# comment
+1.3.6.1.2.1.1.1.0|4|Linux router-1
+1.3.6.1.2.1.1.2.0|6|1.3.6.1.4.1.8072.3.2.10
+1.3.6.1.2.1.1.3.0|67|970069
+1.3.6.1.2.1.2.2.1.6.2|4x|001b213a4b5c
+1.3.6.1.2.1.2.2.1.8.2|2|1
+1.3.6.1.2.1.4.20.1.1.10.0.0.1|64|10.0.0.1
+1.3.6.1.2.1.31.1.1.1.6.2|70|12345678901
+";
+    let entries = parse_snmprec(data).expect("parsing failed");
+    assert_eq!(entries.len(), 7);
+    assert_eq!(
+        entries[3].value(),
+        ObjectSyntax::String(b"\x00\x1b\x21\x3a\x4b\x5c")
+    );
+    let lines: Vec<_> = entries
+        .iter()
+        .filter_map(|e| snmprec_line(&e.oid, &e.value()))
+        .collect();
+    let expected: Vec<_> = data.lines().skip(1).collect();
+    assert_eq!(lines, expected);
+    assert_eq!(
+        parse_snmprec("1.3.6.1.2.1.1.5.0|4:numeric|1\n"),
+        Err(DumpError { line: 1 })
+    );
+}
+
+#[test]
+fn test_snmprec_simulator() {
+    let data = "1.3.6.1.2.1.1.5.0|4|router-1\n1.3.6.1.2.1.1.3.0|67|42\n";
+    let mut simulator = Simulator::new();
+    for entry in parse_snmprec(data).expect("parsing failed") {
+        simulator.insert(&entry.oid, &entry.value());
+    }
+    let lines: Vec<_> = simulator
+        .iter()
+        .filter_map(|(oid, value)| snmprec_line(&oid, &value))
+        .collect();
+    assert_eq!(
+        lines,
+        vec!["1.3.6.1.2.1.1.3.0|67|42", "1.3.6.1.2.1.1.5.0|4|router-1"]
+    );
+}
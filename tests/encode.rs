@@ -0,0 +1,310 @@
+#![cfg(all(feature = "v1", feature = "v2c", feature = "v3"))]
+
+extern crate snmp_parser;
+
+use asn1_rs::{FromBer, Oid};
+use snmp_parser::coexistence::v2c_to_v1;
+use snmp_parser::encode::{SnmpEncode, TooBigError, VersionError};
+use snmp_parser::*;
+use std::net::Ipv4Addr;
+
+static SNMPV2_GET: &[u8] = include_bytes!("../assets/snmpv2c-get-response.bin");
+
+static ASSETS: &[&[u8]] = &[
+    include_bytes!("../assets/snmpv1_req.bin"),
+    include_bytes!("../assets/snmpv1_trap_coldstart.bin"),
+    include_bytes!("../assets/snmpv2c-get-response.bin"),
+    include_bytes!("../assets/snmpv2c-get-response-100.bin"),
+    include_bytes!("../assets/snmpv3-report.bin"),
+    include_bytes!("../assets/snmpv3_req.bin"),
+    include_bytes!("../assets/snmpv3_req_encrypted.bin"),
+];
+
+#[test]
+fn test_encode_roundtrip_assets() {
+    for asset in ASSETS {
+        let (_, msg) = parse_snmp_generic_message(asset).expect("parsing failed");
+        let bytes = msg.encode();
+        let (rem, msg2) = parse_snmp_generic_message(&bytes).expect("parsing encoded failed");
+        assert!(rem.is_empty());
+        assert_eq!(msg, msg2);
+        assert_eq!(bytes, msg2.encode());
+    }
+}
+
+#[test]
+fn test_encode_values() {
+    let vars = vec![
+        (ObjectSyntax::Number(-1), &[0x02, 0x01, 0xff][..]),
+        (ObjectSyntax::Number(128), &[0x02, 0x02, 0x00, 0x80]),
+        (ObjectSyntax::Number(-129), &[0x02, 0x02, 0xff, 0x7f]),
+        (ObjectSyntax::Counter32(0), &[0x41, 0x01, 0x00]),
+        (
+            ObjectSyntax::Gauge32(u32::MAX),
+            &[0x42, 0x05, 0x00, 0xff, 0xff, 0xff, 0xff],
+        ),
+        (
+            ObjectSyntax::IpAddress(NetworkAddress::IPv4(Ipv4Addr::new(10, 0, 0, 1))),
+            &[0x40, 0x04, 0x0a, 0x00, 0x00, 0x01],
+        ),
+        (ObjectSyntax::Empty, &[0x05, 0x00]),
+    ];
+    for (value, expected) in vars {
+        assert_eq!(value.encode(), expected, "encoding of {:?}", value);
+    }
+    assert_eq!(VarBindValue::EndOfMibView.encode(), &[0x82, 0x00]);
+}
+
+#[test]
+fn test_encode_message() {
+    let msg = SnmpMessage {
+        version: 1,
+        community: String::from("public"),
+        pdu: SnmpPdu::Generic(SnmpGenericPdu {
+            pdu_type: PduType::GetRequest,
+            req_id: 1,
+            err: ErrorStatus::NoError,
+            err_index: 0,
+            var: vec![SnmpVariable {
+                oid: Oid::from(&[1, 3, 6, 1, 2, 1, 1, 1, 0]).unwrap(),
+                val: VarBindValue::Unspecified,
+            }],
+        }),
+    };
+    let bytes = msg.encode();
+    let (_, msg2) = parse_snmp_v2c(&bytes).expect("parsing failed");
+    assert_eq!(msg, msg2);
+    // a long string requires a long-form length
+    let long = SnmpMessage {
+        community: "x".repeat(300),
+        ..msg
+    };
+    let bytes = long.encode();
+    assert_eq!(&bytes[..4], &[0x30, 0x82, 0x01, 0x4e]);
+    let (_, msg2) = parse_snmp_v2c(&bytes).expect("parsing failed");
+    assert_eq!(long, msg2);
+}
+
+#[test]
+fn test_encoded_len() {
+    for asset in ASSETS {
+        assert_eq!(snmp_message_len(asset), Ok(asset.len()));
+        let (_, msg) = parse_snmp_generic_message(asset).expect("parsing failed");
+        assert_eq!(msg.encoded_len(), asset.len());
+    }
+    let asset = include_bytes!("../assets/snmpv1_req.bin");
+    let (_, msg) = parse_snmp_v1(asset).expect("parsing failed");
+    // the PDU is the last field of the message
+    let pdu_len = msg.pdu.encoded_len();
+    let (rem, pdu) = SnmpPdu::from_ber(&asset[asset.len() - pdu_len..]).expect("parsing failed");
+    assert!(rem.is_empty());
+    assert_eq!(pdu, msg.pdu);
+    // long-form lengths are counted without encoding
+    let value = vec![b'a'; 300];
+    let msg = SnmpMessage {
+        version: 1,
+        community: String::from("public"),
+        pdu: SnmpPdu::Generic(SnmpGenericPdu {
+            pdu_type: PduType::SetRequest,
+            req_id: 1,
+            err: ErrorStatus::NoError,
+            err_index: 0,
+            var: vec![SnmpVariable {
+                oid: oid!(1.3.6 .1 .2 .1 .1 .6 .0),
+                val: VarBindValue::Value(ObjectSyntax::String(&value)),
+            }],
+        }),
+    };
+    assert_eq!(msg.encoded_len(), msg.encode().len());
+    // stream of two messages, with a truncated header
+    let mut stream = asset.to_vec();
+    stream.extend_from_slice(asset);
+    assert_eq!(snmp_message_len(&stream), Ok(asset.len()));
+    assert_eq!(
+        snmp_message_len(&[0x30]),
+        Err(nom::Err::Incomplete(nom::Needed::new(1)))
+    );
+    assert_eq!(
+        snmp_message_len(&[0x04, 0x00]),
+        Err(nom::Err::Error(
+            snmp_parser::error::SnmpError::InvalidMessage
+        ))
+    );
+}
+
+#[test]
+fn test_normalize() {
+    let canonical = ASSETS[0];
+    // same message, with a long-form length and a non-minimal version
+    let mut content = vec![0x02, 0x02, 0x00, 0x00];
+    content.extend_from_slice(&canonical[5..]);
+    let mut sloppy = vec![0x30, 0x82, 0x00, content.len() as u8];
+    sloppy.extend_from_slice(&content);
+    assert_ne!(sloppy, canonical);
+    assert_eq!(
+        encode::normalize(&sloppy).expect("normalize failed"),
+        canonical
+    );
+    for asset in ASSETS {
+        assert_eq!(encode::normalize(asset).expect("normalize failed"), *asset);
+    }
+    assert!(encode::normalize(&[0x04, 0x00]).is_err());
+}
+
+#[test]
+fn test_check_encoded_size() {
+    let asset = include_bytes!("../assets/snmpv3_req.bin");
+    let (_, mut msg) = parse_snmp_v3(asset).expect("parsing failed");
+    assert_eq!(msg.check_encoded_size(None), Ok(asset.len()));
+    assert_eq!(msg.check_encoded_size(Some(asset.len())), Ok(asset.len()));
+    let err = msg
+        .check_encoded_size(Some(asset.len() - 4))
+        .expect_err("size not checked");
+    assert_eq!(err.limit, asset.len() - 4);
+    assert_eq!(err.overshoot(), 4);
+    // a smaller msgMaxSize is also encoded on fewer bytes
+    msg.header_data.msg_max_size = 64;
+    let err = msg
+        .check_encoded_size(Some(1500))
+        .expect_err("size not checked");
+    assert_eq!(err.len, msg.encoded_len());
+    assert_eq!(err.limit, 64);
+}
+
+#[test]
+fn test_too_big_error_inconsistent() {
+    // the fields are public, so the length can be smaller than the limit
+    let err = TooBigError { len: 10, limit: 20 };
+    assert_eq!(err.overshoot(), 0);
+    assert_eq!(
+        err.to_string(),
+        "Message too big: 10 bytes, limit is 20 (0 bytes in excess)"
+    );
+}
+
+#[test]
+fn test_encode_exceptions_version() {
+    let var = |n: u64, val| SnmpVariable {
+        oid: Oid::from(&[1, 3, 6, 1, 2, 1, 1, n, 0]).unwrap(),
+        val,
+    };
+    let mut msg = SnmpMessage {
+        version: 1,
+        community: String::from("public"),
+        pdu: SnmpPdu::Generic(SnmpGenericPdu {
+            pdu_type: PduType::Response,
+            req_id: 1,
+            err: ErrorStatus::NoError,
+            err_index: 0,
+            var: vec![
+                var(1, VarBindValue::Value(ObjectSyntax::Counter64(1))),
+                var(2, VarBindValue::NoSuchObject),
+                var(3, VarBindValue::NoSuchInstance),
+                var(4, VarBindValue::EndOfMibView),
+            ],
+        }),
+    };
+    let bytes = msg.encode_checked().expect("encoding failed");
+    let (_, msg2) = parse_snmp_v2c(&bytes).expect("parsing failed");
+    assert_eq!(msg, msg2);
+    // SNMPv1
+    msg.version = 0;
+    assert_eq!(
+        msg.encode_checked(),
+        Err(VersionError::Counter64 { index: 0 })
+    );
+    if let SnmpPdu::Generic(ref mut pdu) = msg.pdu {
+        pdu.var.remove(0);
+    }
+    assert_eq!(
+        msg.check_version(),
+        Err(VersionError::Exception {
+            index: 0,
+            exception: VarBindException::NoSuchObject
+        })
+    );
+    let msg = v2c_to_v1(SnmpMessage { version: 1, ..msg }).expect("translation failed");
+    assert!(msg.encode_checked().is_ok());
+    // PDU types
+    let msg = SnmpMessage {
+        version: 0,
+        community: String::from("public"),
+        pdu: SnmpPdu::Bulk(SnmpBulkPdu {
+            req_id: 1,
+            non_repeaters: 0,
+            max_repetitions: 10,
+            var: vec![],
+        }),
+    };
+    assert_eq!(
+        msg.check_version(),
+        Err(VersionError::PduType(PduType::GetBulkRequest))
+    );
+}
+
+#[test]
+fn test_edit_and_encode() {
+    let oid = |s: &str| s.parse::<Oid>().unwrap();
+    let (_, mut msg) = parse_snmp_v2c(SNMPV2_GET).expect("parsing failed");
+    if let SnmpPdu::Generic(ref mut pdu) = msg.pdu {
+        pdu.err = ErrorStatus::NoSuchName;
+        pdu.err_index = 3;
+    }
+    // remove the first variable: the error index follows the third one
+    let removed = oid("1.3.6.1.2.1.25.1.1.0");
+    msg.pdu.retain_vars(|v| v.oid != removed);
+    let old = msg.pdu.set_value(
+        &oid("1.3.6.1.2.1.25.1.5.0"),
+        VarBindValue::Value(ObjectSyntax::Gauge32(42)),
+    );
+    assert_eq!(old, Some(VarBindValue::Value(ObjectSyntax::Gauge32(3))));
+    assert_eq!(msg.pdu.set_value(&removed, VarBindValue::Unspecified), None);
+    msg.pdu.push_var(SnmpVariable {
+        oid: oid("1.3.6.1.2.1.1.3.0"),
+        val: VarBindValue::Value(ObjectSyntax::TimeTicks(5)),
+    });
+    let bytes = msg.encode();
+    let (_, msg2) = parse_snmp_v2c(&bytes).expect("parsing encoded failed");
+    assert_eq!(msg, msg2);
+    let vars: Vec<_> = msg2.vars_iter().collect();
+    assert_eq!(vars.len(), 3);
+    assert_eq!(vars[0].val, VarBindValue::Value(ObjectSyntax::Gauge32(42)));
+    match msg2.pdu {
+        SnmpPdu::Generic(ref pdu) => assert_eq!(pdu.err_index, 2),
+        _ => panic!("unexpected PDU type"),
+    }
+    // removing the variable designated by the error index resets it
+    msg.pdu
+        .retain_vars(|v| v.oid != oid("1.3.6.1.2.1.25.1.5.1"));
+    match msg.pdu {
+        SnmpPdu::Generic(ref pdu) => assert_eq!(pdu.err_index, 0),
+        _ => panic!("unexpected PDU type"),
+    }
+}
+
+#[test]
+fn test_edit_bulk_non_repeaters() {
+    let var = |s: &str| SnmpVariable {
+        oid: s.parse::<Oid>().unwrap(),
+        val: VarBindValue::Unspecified,
+    };
+    let mut pdu = SnmpPdu::Bulk(SnmpBulkPdu {
+        req_id: 1,
+        non_repeaters: 2,
+        max_repetitions: 10,
+        var: vec![
+            var("1.3.6.1.2.1.1.3"),
+            var("1.3.6.1.2.1.1.5"),
+            var("1.3.6.1.2.1.2.2.1.2"),
+        ],
+    });
+    pdu.retain_vars(|v| v.oid != "1.3.6.1.2.1.1.3".parse::<Oid>().unwrap());
+    pdu.push_var(var("1.3.6.1.2.1.2.2.1.10"));
+    match pdu {
+        SnmpPdu::Bulk(ref pdu) => {
+            assert_eq!(pdu.non_repeaters, 1);
+            assert_eq!(pdu.var.len(), 3);
+        }
+        _ => panic!("unexpected PDU type"),
+    }
+}
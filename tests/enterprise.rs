@@ -0,0 +1,37 @@
+#![cfg(feature = "enterprises")]
+
+extern crate snmp_parser;
+
+use snmp_parser::enterprise::*;
+use snmp_parser::oid;
+
+#[test]
+fn test_enterprise_lookup() {
+    assert_eq!(enterprise_name(2636), Some("Juniper Networks"));
+    assert_eq!(enterprise_name(1), None);
+    assert_eq!(
+        enterprise_of_oid(&oid!(1.3.6 .1 .4 .1 .8072 .3 .2 .10)),
+        Some(8072)
+    );
+    assert_eq!(enterprise_of_oid(&oid!(1.3.6 .1 .4 .1)), None);
+    assert_eq!(enterprise_of_oid(&oid!(1.3.6 .1 .2 .1 .1 .2 .0)), None);
+    let engine_id = b"\x80\x00\x1f\x88\x80\x59\xdc\x48\x61\x45\xa2\x63\x22";
+    assert_eq!(enterprise_of_engine_id(engine_id), Some(8072));
+    assert_eq!(enterprise_of_engine_id(b"\x80\x00"), None);
+}
+
+#[test]
+fn test_enterprise_registry() {
+    let mut registry = EnterpriseRegistry::new();
+    let csv = "# number,name\n\n99999,\"Example Corp\"\n9, Cisco\n";
+    registry.load_csv(csv).expect("loading failed");
+    assert_eq!(registry.len(), 2);
+    assert_eq!(registry.name(99999), Some("Example Corp"));
+    assert_eq!(registry.name(9), Some("Cisco"));
+    // fallback to the built-in table
+    assert_eq!(registry.name(30065), Some("Arista Networks"));
+    assert_eq!(
+        registry.load_csv("1,ok\nnot a number,name\n"),
+        Err(CsvError { line: 2 })
+    );
+}
@@ -0,0 +1,257 @@
+extern crate snmp_parser;
+
+mod common;
+
+use common::*;
+use snmp_parser::exchange::*;
+use snmp_parser::*;
+
+fn message(
+    version: u32,
+    pdu_type: PduType,
+    err: ErrorStatus,
+    err_index: u32,
+    var: Vec<SnmpVariable<'static>>,
+) -> SnmpGenericMessage<'static> {
+    let msg = SnmpMessage {
+        version,
+        community: "public".to_string(),
+        pdu: SnmpPdu::Generic(SnmpGenericPdu {
+            pdu_type,
+            req_id: 42,
+            err,
+            err_index,
+            var,
+        }),
+    };
+    if version == 0 {
+        SnmpGenericMessage::V1(msg)
+    } else {
+        SnmpGenericMessage::V2(msg)
+    }
+}
+
+#[test]
+fn test_validate_response_get() {
+    let request = message(
+        1,
+        PduType::GetRequest,
+        ErrorStatus::NoError,
+        0,
+        vec![null("1.3.6.1.2.1.1.1.0"), null("1.3.6.1.2.1.1.3.0")],
+    );
+    let response = message(
+        1,
+        PduType::Response,
+        ErrorStatus::NoError,
+        0,
+        vec![
+            varbind("1.3.6.1.2.1.1.1.0", VarBindValue::NoSuchObject),
+            varbind(
+                "1.3.6.1.2.1.1.3.0",
+                VarBindValue::Value(ObjectSyntax::TimeTicks(1234)),
+            ),
+        ],
+    );
+    assert_eq!(validate_response(&request, &response), vec![]);
+    // OIDs must be echoed, and the error index must be 0 without error
+    let response = message(
+        1,
+        PduType::Response,
+        ErrorStatus::NoError,
+        1,
+        vec![null("1.3.6.1.2.1.1.1.0"), null("1.3.6.1.2.1.1.4.0")],
+    );
+    assert_eq!(
+        validate_response(&request, &response),
+        vec![
+            ResponseViolation::OidMismatch {
+                index: 1,
+                oid: oid("1.3.6.1.2.1.1.4.0"),
+                expected: oid("1.3.6.1.2.1.1.3.0"),
+            },
+            ResponseViolation::InvalidErrorIndex {
+                err_index: 1,
+                var_count: 2
+            },
+        ]
+    );
+    // tooBig responses may have no variables
+    let response = message(1, PduType::Response, ErrorStatus::TooBig, 0, vec![]);
+    assert_eq!(validate_response(&request, &response), vec![]);
+    let response = message(1, PduType::Response, ErrorStatus::GenErr, 3, vec![]);
+    assert_eq!(
+        validate_response(&request, &response),
+        vec![
+            ResponseViolation::VarCountMismatch {
+                request: 2,
+                response: 0
+            },
+            ResponseViolation::InvalidErrorIndex {
+                err_index: 3,
+                var_count: 0
+            },
+        ]
+    );
+    // a request is not a response
+    assert_eq!(
+        validate_response(&request, &request),
+        vec![ResponseViolation::NotAResponse(PduType::GetRequest)]
+    );
+}
+
+#[test]
+fn test_validate_response_v1() {
+    let request = message(
+        0,
+        PduType::GetNextRequest,
+        ErrorStatus::NoError,
+        0,
+        vec![null("1.3.6.1.2.1.2.2.1.10"), null("1.3.6.1.2.1.31.1.1.1.6")],
+    );
+    let response = message(
+        0,
+        PduType::Response,
+        ErrorStatus::NoAccess,
+        2,
+        vec![
+            varbind("1.3.6.1.2.1.2.2.1.10.1", VarBindValue::EndOfMibView),
+            varbind(
+                "1.3.6.1.2.1.31.1.1.1.6.1",
+                VarBindValue::Value(ObjectSyntax::Counter64(1)),
+            ),
+        ],
+    );
+    assert_eq!(
+        validate_response(&request, &response),
+        vec![
+            ResponseViolation::V2ErrorStatusInV1(ErrorStatus::NoAccess),
+            ResponseViolation::ExceptionInV1 { index: 0 },
+            ResponseViolation::Counter64InV1 { index: 1 },
+        ]
+    );
+    let response = message(1, PduType::Response, ErrorStatus::NoError, 0, vec![]);
+    assert_eq!(
+        validate_response(&request, &response),
+        vec![
+            ResponseViolation::VersionMismatch {
+                request: 0,
+                response: 1
+            },
+            ResponseViolation::VarCountMismatch {
+                request: 2,
+                response: 0
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_validate_response_bulk() {
+    let request = SnmpGenericMessage::V2(SnmpMessage {
+        version: 1,
+        community: "public".to_string(),
+        pdu: SnmpPdu::Bulk(SnmpBulkPdu {
+            req_id: 42,
+            non_repeaters: 1,
+            max_repetitions: 2,
+            var: vec![null("1.3.6.1.2.1.1.3"), null("1.3.6.1.2.1.2.2.1.2")],
+        }),
+    });
+    let vars = || {
+        vec![
+            null("1.3.6.1.2.1.1.3.0"),
+            null("1.3.6.1.2.1.2.2.1.2.1"),
+            null("1.3.6.1.2.1.2.2.1.2.2"),
+        ]
+    };
+    let response = message(1, PduType::Response, ErrorStatus::NoError, 0, vars());
+    assert_eq!(validate_response(&request, &response), vec![]);
+    let mut vars = vars();
+    vars.push(null("1.3.6.1.2.1.2.2.1.2.3"));
+    let response = message(1, PduType::Response, ErrorStatus::NoError, 0, vars);
+    assert_eq!(
+        validate_response(&request, &response),
+        vec![ResponseViolation::VarCountMismatch {
+            request: 3,
+            response: 4
+        }]
+    );
+}
+
+#[cfg(feature = "v3")]
+static SNMPV3_REQ: &[u8] = include_bytes!("../assets/snmpv3_req.bin");
+#[cfg(feature = "v3")]
+static SNMPV3_REPORT: &[u8] = include_bytes!("../assets/snmpv3-report.bin");
+
+#[cfg(feature = "v3")]
+#[test]
+fn test_correlate_v3_report() {
+    let (mut request, _) = SnmpV3Message::parse(SNMPV3_REQ).expect("parsing failed");
+    let (report, _) = SnmpV3Message::parse(SNMPV3_REPORT).expect("parsing failed");
+    // the request of the capture is not the request of the report
+    assert_eq!(correlate_v3(&request, &report), None);
+    request.header_data.msg_id = report.header_data.msg_id;
+    if let ScopedPduData::Plaintext(ref mut scoped_pdu) = request.data {
+        if let SnmpPdu::Generic(ref mut pdu) = scoped_pdu.data {
+            pdu.req_id = 1622806774;
+        }
+    }
+    let correlation = correlate_v3(&request, &report);
+    assert_eq!(
+        correlation,
+        Some(V3Correlation::Report(ReportReason::UnknownEngineId))
+    );
+    assert!(correlation.unwrap().is_synchronization());
+    // a response must have the request ID of the request
+    if let ScopedPduData::Plaintext(ref mut scoped_pdu) = request.data {
+        if let SnmpPdu::Generic(ref mut pdu) = scoped_pdu.data {
+            pdu.req_id = 1;
+        }
+    }
+    assert_eq!(correlate_v3(&request, &report), None);
+}
+
+#[cfg(all(feature = "v3", feature = "std"))]
+#[test]
+fn test_v3_request_tracker() {
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::{Duration, Instant};
+
+    let agent = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+    let (mut request, _) = SnmpV3Message::parse(SNMPV3_REQ).expect("parsing failed");
+    let (report, _) = SnmpV3Message::parse(SNMPV3_REPORT).expect("parsing failed");
+    request.header_data.msg_id = report.header_data.msg_id;
+    let mut tracker = V3RequestTracker::new(Duration::from_secs(5));
+    let t0 = Instant::now();
+    assert!(tracker.observe_request(agent, &request, t0));
+    // the report does not have the request ID of the request, and is not 0
+    assert_eq!(
+        tracker.observe_reply(agent, &report, t0 + Duration::from_millis(10)),
+        None
+    );
+    if let ScopedPduData::Plaintext(ref mut scoped_pdu) = request.data {
+        if let SnmpPdu::Generic(ref mut pdu) = scoped_pdu.data {
+            pdu.req_id = 1622806774;
+        }
+    }
+    assert!(tracker.observe_request(agent, &request, t0));
+    let reply = tracker
+        .observe_reply(agent, &report, t0 + Duration::from_millis(10))
+        .expect("report not matched");
+    assert!(reply.correlation.is_synchronization());
+    assert_eq!(reply.delay, Duration::from_millis(10));
+    assert!(tracker.is_empty());
+    // the request sent again after discovery is not answered
+    request.header_data.msg_id += 1;
+    assert!(tracker.observe_request(agent, &request, t0));
+    assert_eq!(tracker.expire(t0 + Duration::from_secs(1)), vec![]);
+    assert_eq!(
+        tracker.expire(t0 + Duration::from_secs(5)),
+        vec![UnansweredV3Request {
+            peer: agent,
+            msg_id: report.header_data.msg_id + 1,
+            req_id: Some(1622806774),
+        }]
+    );
+}
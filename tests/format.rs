@@ -0,0 +1,49 @@
+extern crate snmp_parser;
+
+use snmp_parser::format::*;
+use snmp_parser::{oid, Oid};
+
+#[test]
+fn test_format_octet_string() {
+    assert_eq!(format_octet_string(b"").to_string(), "\"\"");
+    assert_eq!(
+        format_octet_string(b"Linux \"router\" 5.10").to_string(),
+        "\"Linux \\\"router\\\" 5.10\""
+    );
+    assert_eq!(
+        format_octet_string(b"C:\\temp")
+            .escape(StringEscape::Raw)
+            .to_string(),
+        "C:\\temp"
+    );
+    // MAC address
+    let mac = format_octet_string(&[0x00, 0x1b, 0x21, 0x3a, 0xfe, 0x10]);
+    assert_eq!(mac.as_printable(), None);
+    assert_eq!(mac.to_string(), "Hex-STRING: 00 1B 21 3A FE 10");
+    // control characters and invalid UTF-8 are not printable
+    assert_eq!(
+        format_octet_string(b"line\n").to_string(),
+        "Hex-STRING: 6C 69 6E 65 0A"
+    );
+    assert_eq!(
+        format_octet_string(b"\xe9t\xe9")
+            .escape(StringEscape::Raw)
+            .to_string(),
+        "Hex-STRING: E9 74 E9"
+    );
+}
+
+#[test]
+fn test_format_oid() {
+    let oid = oid!(1.3.6 .1 .2 .1 .1 .5 .0);
+    assert_eq!(format_oid(&oid).to_string(), "1.3.6.1.2.1.1.5.0");
+    // first arcs are combined in the encoding
+    let oid = oid!(2.39.3);
+    assert_eq!(format_oid(&oid).to_string(), oid.to_id_string());
+    // longer than the buffer
+    let arcs: Vec<u64> = (0..100).map(|i| u64::MAX - i).collect();
+    let mut arcs_with_root = vec![1, 3];
+    arcs_with_root.extend(arcs);
+    let oid = Oid::from(&arcs_with_root).expect("invalid OID");
+    assert_eq!(format_oid(&oid).to_string(), oid.to_id_string());
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "hash-credentials")]
+
+#[cfg_attr(feature = "v2u", macro_use)]
+extern crate hex_literal;
+extern crate snmp_parser;
+
+use snmp_parser::hashing::*;
+use snmp_parser::*;
+
+static SNMPV1_REQ: &[u8] = include_bytes!("../assets/snmpv1_req.bin");
+static SNMPV3_REQ: &[u8] = include_bytes!("../assets/snmpv3_req.bin");
+
+// SNMPv2u GetRequest for sysDescr.0, with user "user"
+#[cfg(feature = "v2u")]
+static SNMPV2U_GET: &[u8] = &hex!(
+    "30 3e 02 01 02
+     04 1e 01 04 00 01 02 03 04 05 06 07 08 09 0a 0b 00 00 00 01 00 00 00 64 05 dc
+     04 75 73 65 72 00
+     a0 19 02 01 01 02 01 00 02 01 00 30 0e 30 0c 06 08 2b 06 01 02 01 01 01 00 05 00"
+);
+
+#[test]
+fn test_credential_hasher() {
+    let hasher = CredentialHasher::new(b"salt");
+    let hash = hasher.hash(b"public");
+    assert_eq!(hash, hasher.hash(b"public"));
+    assert_ne!(hash, hasher.hash(b"private"));
+    assert_ne!(hash, CredentialHasher::new(b"other salt").hash(b"public"));
+    assert_eq!(hash.to_string().len(), 2 * CREDENTIAL_HASH_LEN);
+    assert_eq!(format!("{:?}", hasher), "CredentialHasher");
+}
+
+#[test]
+fn test_parse_hashed() {
+    let hasher = CredentialHasher::new(b"salt");
+    let (rem, msg) =
+        parse_snmp_generic_message_hashed(SNMPV1_REQ, &hasher).expect("parsing failed");
+    assert!(rem.is_empty());
+    let credential = msg.credential.expect("no credential");
+    assert_eq!(credential.hash, hasher.hash(b"public"));
+    assert!(credential.matches(&hasher, b"public"));
+    assert!(!credential.matches(&hasher, b"private"));
+    assert_eq!(
+        credential.check_community(&CommunityPolicy::default()),
+        None
+    );
+    match msg.message {
+        SnmpGenericMessage::V1(ref msg) => {
+            assert!(msg.community.is_empty());
+            assert_eq!(msg.pdu.vars_iter().count(), 1);
+        }
+        _ => panic!("unexpected message {:?}", msg),
+    }
+    let (_, msg) = parse_snmp_generic_message_hashed(SNMPV3_REQ, &hasher).expect("parsing failed");
+    let credential = msg.credential.expect("no credential");
+    assert!(credential.matches(&hasher, b""));
+    assert_eq!(credential.len, 0);
+    match msg.message {
+        SnmpGenericMessage::V3(ref msg) => match msg.security_params {
+            SecurityParameters::USM(ref usm) => assert!(usm.msg_user_name.is_empty()),
+            _ => panic!("unexpected security parameters"),
+        },
+        _ => panic!("unexpected message {:?}", msg),
+    }
+    #[cfg(feature = "v2u")]
+    {
+        let (_, msg) =
+            parse_snmp_generic_message_hashed(SNMPV2U_GET, &hasher).expect("parsing failed");
+        let credential = msg.credential.expect("no credential");
+        assert!(credential.matches(&hasher, b"user"));
+        assert_eq!(credential.len, 4);
+        match msg.message {
+            SnmpGenericMessage::V2u(ref msg) => assert!(msg.parameters.user_name.is_empty()),
+            _ => panic!("unexpected message {:?}", msg),
+        }
+    }
+    // errors have the same location as with the regular parser
+    let truncated = &SNMPV1_REQ[..SNMPV1_REQ.len() - 1];
+    let err = parse_snmp_generic_message_hashed(truncated, &hasher).unwrap_err();
+    assert_eq!(err, parse_snmp_generic_message(truncated).unwrap_err());
+}
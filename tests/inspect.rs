@@ -0,0 +1,91 @@
+#![cfg(all(feature = "v2c", feature = "v3"))]
+
+extern crate snmp_parser;
+
+use snmp_parser::error::SnmpError;
+use snmp_parser::inspect::*;
+use snmp_parser::*;
+
+static SNMPV1_TRAP_COLDSTART: &[u8] = include_bytes!("../assets/snmpv1_trap_coldstart.bin");
+static SNMPV2_GET_RESPONSE: &[u8] = include_bytes!("../assets/snmpv2c-get-response.bin");
+static SNMPV3_REQ: &[u8] = include_bytes!("../assets/snmpv3_req.bin");
+static SNMPV3_REQ_ENCRYPTED: &[u8] = include_bytes!("../assets/snmpv3_req_encrypted.bin");
+
+#[test]
+fn test_inspect_v1_trap() {
+    let (rem, hdr) = inspect_snmp_message(SNMPV1_TRAP_COLDSTART).expect("inspection failed");
+    assert!(rem.is_empty());
+    assert_eq!(hdr.version, 0);
+    assert_eq!(hdr.community, Some(&b"public"[..]));
+    assert_eq!(hdr.pdu_type, Some(PduType::TrapV1));
+    assert_eq!(hdr.request_id, None);
+    let vars = hdr
+        .varbinds()
+        .collect::<Result<Vec<_>, _>>()
+        .expect("invalid varbinds");
+    assert_eq!(vars.len(), 1);
+    assert_eq!(vars[0].oid.to_id_string(), "1.3.6.1.2.1.2.1.0");
+    assert_eq!(
+        vars[0].decode_value(),
+        Ok(VarBindValue::Value(ObjectSyntax::Number(33)))
+    );
+}
+
+#[test]
+fn test_inspect_matches_parser() {
+    let (_, hdr) = inspect_snmp_message(SNMPV2_GET_RESPONSE).expect("inspection failed");
+    let (_, msg) = parse_snmp_v2c(SNMPV2_GET_RESPONSE).expect("parsing failed");
+    assert_eq!(hdr.version, msg.version);
+    assert_eq!(hdr.community, Some(msg.community.as_bytes()));
+    assert_eq!(hdr.pdu_type, Some(msg.pdu_type()));
+    assert_eq!(hdr.request_id, msg.pdu.request_id());
+    assert_eq!(hdr.varbind_count(), msg.vars_iter().count());
+    for (raw, var) in hdr.varbinds().zip(msg.vars_iter()) {
+        let raw = raw.expect("invalid varbind");
+        assert_eq!(raw.oid, var.oid);
+        assert_eq!(raw.decode_value().as_ref(), Ok(&var.val));
+    }
+}
+
+#[test]
+fn test_inspect_v3() {
+    let (_, hdr) = inspect_snmp_message(SNMPV3_REQ).expect("inspection failed");
+    assert_eq!(hdr.version, 3);
+    assert_eq!(hdr.community, None);
+    assert_eq!(hdr.pdu_type, Some(PduType::GetRequest));
+    assert_eq!(hdr.request_id, Some(2098071598));
+    let (_, hdr) = inspect_snmp_message(SNMPV3_REQ_ENCRYPTED).expect("inspection failed");
+    assert!(hdr.header_data.map(|h| h.is_encrypted()).unwrap_or(false));
+    assert_eq!(hdr.pdu_type, None);
+    assert_eq!(hdr.varbind_count(), 0);
+}
+
+struct NoopVisitor;
+
+impl<'a> snmp_parser::visit::SnmpVisitor<'a> for NoopVisitor {}
+
+#[test]
+fn test_inspect_errors() {
+    let mut bytes = SNMPV1_TRAP_COLDSTART.to_vec();
+    bytes[4] = 2;
+    assert_eq!(
+        inspect_snmp_message(&bytes),
+        Err(nom::Err::Error(SnmpError::InvalidVersion { found: 2 }))
+    );
+    // the other allocation-free readers have the same limitation
+    assert_eq!(
+        snmp_parser::raw::RawSnmpMessage::new(bytes.clone()).unwrap_err(),
+        SnmpError::InvalidVersion { found: 2 }
+    );
+    assert_eq!(
+        snmp_parser::visit::parse_with_visitor(&bytes, &mut NoopVisitor),
+        Err(nom::Err::Error(SnmpError::InvalidVersion { found: 2 }))
+    );
+    let pos = bytes.iter().position(|&b| b == 0xa4).expect("no PDU");
+    bytes[4] = 0;
+    bytes[pos] = 0xaf;
+    assert_eq!(
+        inspect_snmp_message(&bytes),
+        Err(nom::Err::Error(SnmpError::InvalidPduType { tag: 15 }))
+    );
+}
@@ -0,0 +1,70 @@
+extern crate snmp_parser;
+
+use snmp_parser::intern::*;
+use snmp_parser::*;
+
+#[test]
+fn test_oid_interner() {
+    let mut interner = OidInterner::new();
+    let pdu = SnmpPdu::Generic(SnmpGenericPdu {
+        pdu_type: PduType::Response,
+        req_id: 1,
+        err: ErrorStatus::NoError,
+        err_index: 0,
+        var: vec![
+            SnmpVariable {
+                oid: oid!(1.3.6 .1 .2 .1 .1 .3 .0),
+                val: VarBindValue::Unspecified,
+            },
+            SnmpVariable {
+                oid: oid!(1.3.6 .1 .2 .1 .1 .5 .0),
+                val: VarBindValue::Unspecified,
+            },
+        ],
+    });
+    let first = interner.intern_vars(&pdu);
+    let second = interner.intern_vars(&pdu);
+    assert_eq!(first, second);
+    assert_eq!(interner.len(), 2);
+    assert_eq!(first[1], oid!(1.3.6 .1 .2 .1 .1 .5 .0));
+    assert_eq!(first[1].to_string(), "1.3.6.1.2.1.1.5.0");
+    assert_eq!(
+        format!("{:?}", first[0]),
+        "InternedOid(\"1.3.6.1.2.1.1.3.0\")"
+    );
+    // OIDs are purged only when there is no handle left
+    drop(second);
+    let uptime = first[0].clone();
+    drop(first);
+    interner.purge();
+    assert_eq!(interner.len(), 1);
+    drop(uptime);
+    interner.purge();
+    assert!(interner.is_empty());
+}
+
+#[test]
+fn test_shared_interner() {
+    use std::sync::Arc;
+    use std::thread;
+
+    let mut interner = OidInterner::new();
+    let uptime = interner.intern(&oid!(1.3.6 .1 .2 .1 .1 .3 .0));
+    let interner = Arc::new(interner);
+    let workers: Vec<_> = (0..4)
+        .map(|_| {
+            let interner = Arc::clone(&interner);
+            thread::spawn(move || {
+                let found = interner.get(&oid!(1.3.6 .1 .2 .1 .1 .3 .0));
+                let missing = interner.get(&oid!(1.3.6 .1 .2 .1 .1 .5 .0));
+                (found, missing)
+            })
+        })
+        .collect();
+    for worker in workers {
+        let (found, missing) = worker.join().expect("worker failed");
+        assert_eq!(found.as_ref(), Some(&uptime));
+        assert_eq!(missing, None);
+    }
+    assert_eq!(interner.len(), 1);
+}
@@ -0,0 +1,86 @@
+#![cfg(all(feature = "rasn", feature = "v1", feature = "v2c", feature = "v3"))]
+
+extern crate snmp_parser;
+
+use asn1_rs::Oid;
+use rasn_snmp::{v1, v2, v2c, v3};
+use snmp_parser::interop::*;
+use snmp_parser::*;
+use std::convert::TryFrom;
+
+static SNMPV1_REQ: &[u8] = include_bytes!("../assets/snmpv1_req.bin");
+static SNMPV1_TRAP_COLDSTART: &[u8] = include_bytes!("../assets/snmpv1_trap_coldstart.bin");
+static SNMPV2C_GET_RESPONSE: &[u8] = include_bytes!("../assets/snmpv2c-get-response.bin");
+static SNMPV3_REQ: &[u8] = include_bytes!("../assets/snmpv3_req.bin");
+static SNMPV3_REQ_ENCRYPTED: &[u8] = include_bytes!("../assets/snmpv3_req_encrypted.bin");
+
+#[test]
+fn test_interop_v1() {
+    for bytes in [SNMPV1_REQ, SNMPV1_TRAP_COLDSTART] {
+        let (_, msg) = parse_snmp_v1(bytes).expect("parsing failed");
+        let rasn_msg = v1::Message::<v1::Pdus>::try_from(&msg).expect("conversion failed");
+        let decoded: v1::Message<v1::Pdus> = rasn::ber::decode(bytes).expect("decoding failed");
+        assert_eq!(decoded, rasn_msg);
+        assert_eq!(SnmpMessage::try_from(&rasn_msg), Ok(msg));
+    }
+}
+
+#[test]
+fn test_interop_v2c() {
+    let (_, msg) = parse_snmp_v2c(SNMPV2C_GET_RESPONSE).expect("parsing failed");
+    let rasn_msg = v2c::Message::<v2::Pdus>::try_from(&msg).expect("conversion failed");
+    let decoded: v2c::Message<v2::Pdus> =
+        rasn::ber::decode(SNMPV2C_GET_RESPONSE).expect("decoding failed");
+    assert_eq!(decoded, rasn_msg);
+    assert_eq!(SnmpMessage::try_from(&rasn_msg), Ok(msg));
+    // the version must match the target model
+    let (_, msg) = parse_snmp_v1(SNMPV1_REQ).expect("parsing failed");
+    assert_eq!(
+        v2c::Message::<v2::Pdus>::try_from(&msg),
+        Err(ConversionError::InvalidVersion { found: 0 })
+    );
+}
+
+#[test]
+fn test_interop_v3() {
+    for bytes in [SNMPV3_REQ, SNMPV3_REQ_ENCRYPTED] {
+        let (_, msg) = parse_snmp_v3(bytes).expect("parsing failed");
+        let rasn_msg = v3::Message::try_from(&msg).expect("conversion failed");
+        let decoded: v3::Message = rasn::ber::decode(bytes).expect("decoding failed");
+        assert_eq!(decoded, rasn_msg);
+        assert_eq!(SnmpV3Message::try_from(&rasn_msg), Ok(msg));
+    }
+    // the original encoding of the security parameters is kept (it is covered by the digest)
+    let (_, mut msg) = parse_snmp_v3(SNMPV3_REQ).expect("parsing failed");
+    let raw = msg.security_params_raw;
+    let mut long_form = vec![0x30, 0x81, raw[1]];
+    long_form.extend_from_slice(&raw[2..]);
+    msg.security_params_raw = &long_form;
+    let rasn_msg = v3::Message::try_from(&msg).expect("conversion failed");
+    assert_eq!(rasn_msg.security_parameters.as_ref(), &long_form[..]);
+    // messages built manually have their security parameters encoded
+    msg.security_params_raw = &[];
+    let rasn_msg = v3::Message::try_from(&msg).expect("conversion failed");
+    assert_eq!(rasn_msg.security_parameters.as_ref(), raw);
+}
+
+#[test]
+fn test_interop_unsupported() {
+    let var = SnmpVariable {
+        oid: Oid::from(&[1, 3, 6, 1, 2, 1, 1, 1, 0]).unwrap(),
+        val: VarBindValue::Value(ObjectSyntax::Counter64(1)),
+    };
+    assert_eq!(
+        v1::VarBind::try_from(&var),
+        Err(ConversionError::UnsupportedValue("Counter64"))
+    );
+    assert!(v2::VarBind::try_from(&var).is_ok());
+    let var = SnmpVariable {
+        val: VarBindValue::Value(ObjectSyntax::Real(1.0)),
+        ..var
+    };
+    assert_eq!(
+        v2::VarBind::try_from(&var),
+        Err(ConversionError::UnsupportedValue("REAL"))
+    );
+}
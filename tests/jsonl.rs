@@ -0,0 +1,109 @@
+#![cfg(all(feature = "std", feature = "v1", feature = "v2c"))]
+
+extern crate snmp_parser;
+
+use snmp_parser::jsonl::*;
+use snmp_parser::*;
+use std::io;
+
+static SNMPV1_TRAP_COLDSTART: &[u8] = include_bytes!("../assets/snmpv1_trap_coldstart.bin");
+static SNMPV2C_GET_RESPONSE: &[u8] = include_bytes!("../assets/snmpv2c-get-response.bin");
+
+#[test]
+fn test_jsonl_messages() {
+    let mut writer = JsonLinesWriter::new(Vec::new());
+    for bytes in [SNMPV2C_GET_RESPONSE, SNMPV1_TRAP_COLDSTART] {
+        let (_, msg) = parse_snmp_generic_message(bytes).expect("parsing failed");
+        writer.write_message(&msg).expect("write failed");
+    }
+    let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    let lines: Vec<_> = output.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(
+        lines[0],
+        r#"{"version":1,"community":"public","pdu":"Response","req_id":97083662,"#.to_owned()
+            + r#""error_status":0,"error_index":0,"vars":["#
+            + r#"{"oid":"1.3.6.1.2.1.25.1.1.0","type":"TimeTicks","value":970069},"#
+            + r#"{"oid":"1.3.6.1.2.1.25.1.5.0","type":"Gauge32","value":3},"#
+            + r#"{"oid":"1.3.6.1.2.1.25.1.5.1","type":"noSuchInstance","value":null}]}"#
+    );
+    assert_eq!(
+        lines[1],
+        r#"{"version":0,"community":"public","pdu":"TrapV1","#.to_owned()
+            + r#""enterprise":"1.3.6.1.4.1.4.1.2.21","agent_addr":"127.0.0.1","#
+            + r#""generic_trap":0,"specific_trap":0,"timestamp":0,"vars":["#
+            + r#"{"oid":"1.3.6.1.2.1.2.1.0","type":"INTEGER","value":33}]}"#
+    );
+}
+
+#[test]
+fn test_jsonl_escape() {
+    let msg = SnmpGenericMessage::V2(SnmpMessage {
+        version: 1,
+        community: "a\"b\\".to_string(),
+        pdu: SnmpPdu::Generic(SnmpGenericPdu {
+            pdu_type: PduType::SetRequest,
+            req_id: 1,
+            err: ErrorStatus::NoError,
+            err_index: 0,
+            var: vec![SnmpVariable {
+                oid: oid!(1.3.6 .1 .2 .1 .1 .5 .0),
+                val: VarBindValue::Value(ObjectSyntax::String(b"\x00\x1b")),
+            }],
+        }),
+    });
+    let mut writer = JsonLinesWriter::new(Vec::new());
+    writer.write_message(&msg).unwrap();
+    let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    assert!(output.starts_with(r#"{"version":1,"community":"a\"b\\","#));
+    assert!(output.ends_with("\"value\":\"Hex-STRING: 00 1B\"}]}\n"));
+    // redacted
+    let config = JsonLinesConfig {
+        redact_secrets: true,
+        max_varbinds: 0,
+        ..Default::default()
+    };
+    let mut writer = JsonLinesWriter::with_config(Vec::new(), config);
+    writer.write_message(&msg).unwrap();
+    let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    assert!(!output.contains("community"));
+    assert!(output.ends_with("\"vars\":[]}\n"));
+}
+
+#[derive(Default)]
+struct Output {
+    data: Vec<u8>,
+    flushes: usize,
+}
+
+impl io::Write for &mut Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.data.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flushes += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_jsonl_flush() {
+    let (_, msg) = parse_snmp_generic_message(SNMPV2C_GET_RESPONSE).expect("parsing failed");
+    let mut output = Output::default();
+    let config = JsonLinesConfig {
+        flush_every: 2,
+        flush_interval: None,
+        ..Default::default()
+    };
+    let mut writer = JsonLinesWriter::with_config(&mut output, config);
+    writer.write_message(&msg).unwrap();
+    assert!(writer.get_ref().data.is_empty());
+    writer.write_message(&msg).unwrap();
+    assert_eq!(writer.get_ref().flushes, 1);
+    writer.write_message(&msg).unwrap();
+    assert_eq!(writer.get_ref().flushes, 1);
+    drop(writer);
+    assert_eq!(output.data.iter().filter(|&&b| b == b'\n').count(), 3);
+}
@@ -0,0 +1,70 @@
+extern crate snmp_parser;
+
+use snmp_parser::metrics::*;
+use snmp_parser::mib::*;
+use snmp_parser::*;
+
+fn response(pdu_type: PduType) -> SnmpPdu<'static> {
+    SnmpPdu::Generic(SnmpGenericPdu {
+        pdu_type,
+        req_id: 1,
+        err: ErrorStatus::NoError,
+        err_index: 0,
+        var: vec![
+            SnmpVariable {
+                oid: oid!(1.3.6 .1 .2 .1 .1 .3 .0),
+                val: VarBindValue::Value(ObjectSyntax::TimeTicks(970069)),
+            },
+            SnmpVariable {
+                oid: oid!(1.3.6 .1 .2 .1 .2 .2 .1 .2 .3),
+                val: VarBindValue::Value(ObjectSyntax::String(b"eth0")),
+            },
+            SnmpVariable {
+                oid: oid!(1.3.6 .1 .2 .1 .2 .2 .1 .10 .3),
+                val: VarBindValue::Value(ObjectSyntax::Counter32(1234)),
+            },
+            SnmpVariable {
+                oid: oid!(1.3.6 .1 .2 .1 .2 .2 .1 .16 .3),
+                val: VarBindValue::NoSuchInstance,
+            },
+        ],
+    })
+}
+
+#[test]
+fn test_metrics_oid_names() {
+    let samples = MetricsAdapter::new().samples(&response(PduType::Response));
+    assert_eq!(samples.len(), 2);
+    assert_eq!(samples[0].kind, MetricKind::Gauge);
+    assert_eq!(samples[0].to_string(), "snmp_1_3_6_1_2_1_1_3_0 970069");
+    assert_eq!(samples[1].kind, MetricKind::Counter);
+    assert_eq!(samples[1].value, 1234);
+    // not a response
+    let samples = MetricsAdapter::new().samples(&response(PduType::SetRequest));
+    assert!(samples.is_empty());
+}
+
+#[test]
+fn test_metrics_registry() {
+    let mut registry = MibRegistry::new();
+    let ro = MaxAccess::ReadOnly;
+    registry.insert(
+        &oid!(1.3.6 .1 .2 .1 .1 .3),
+        "sysUpTime",
+        ro,
+        MibSyntax::TimeTicks,
+    );
+    registry.insert(
+        &oid!(1.3.6 .1 .2 .1 .2 .2 .1 .10),
+        "ifInOctets",
+        ro,
+        MibSyntax::Counter32,
+    );
+    let samples = MetricsAdapter::with_registry(&registry).samples(&response(PduType::Response));
+    assert_eq!(samples[0].to_string(), "sysUpTime 970069");
+    assert_eq!(
+        samples[1].labels,
+        vec![("index".to_string(), "3".to_string())]
+    );
+    assert_eq!(samples[1].to_string(), "ifInOctets{index=\"3\"} 1234");
+}
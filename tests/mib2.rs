@@ -0,0 +1,176 @@
+extern crate snmp_parser;
+
+mod common;
+
+use common::*;
+use snmp_parser::mib2::*;
+use snmp_parser::*;
+
+#[test]
+fn test_interface_table() {
+    let mut table = InterfaceTable::new();
+    // GetBulk response, with rows of ifDescr, ifOperStatus and ifInOctets
+    table.push_pdu(&response(vec![
+        var(
+            oid!(1.3.6 .1 .2 .1 .2 .2 .1 .2 .1),
+            ObjectSyntax::String(b"lo"),
+        ),
+        var(oid!(1.3.6 .1 .2 .1 .2 .2 .1 .8 .1), ObjectSyntax::Number(1)),
+        var(
+            oid!(1.3.6 .1 .2 .1 .2 .2 .1 .10 .1),
+            ObjectSyntax::Counter32(100),
+        ),
+        var(
+            oid!(1.3.6 .1 .2 .1 .2 .2 .1 .2 .2),
+            ObjectSyntax::String(b"eth0"),
+        ),
+        var(oid!(1.3.6 .1 .2 .1 .2 .2 .1 .8 .2), ObjectSyntax::Number(2)),
+        var(
+            oid!(1.3.6 .1 .2 .1 .2 .2 .1 .10 .2),
+            ObjectSyntax::Counter32(200),
+        ),
+    ]));
+    // ifName and ifHCInOctets
+    table.push_pdu(&response(vec![
+        var(
+            oid!(1.3.6 .1 .2 .1 .31 .1 .1 .1 .1 .2),
+            ObjectSyntax::String(b"eth0"),
+        ),
+        var(
+            oid!(1.3.6 .1 .2 .1 .31 .1 .1 .1 .6 .2),
+            ObjectSyntax::Counter64(1 << 40),
+        ),
+        // not an interface column
+        var(oid!(1.3.6 .1 .2 .1 .1 .3 .0), ObjectSyntax::TimeTicks(100)),
+    ]));
+    assert_eq!(table.len(), 2);
+    let lo = table.get(1).expect("missing interface");
+    assert_eq!(lo.descr.as_deref(), Some("lo"));
+    assert!(lo.is_up());
+    assert_eq!(lo.in_octets_64(), Some(100));
+    let eth0 = table.get(2).expect("missing interface");
+    assert!(!eth0.is_up());
+    assert_eq!(eth0.name.as_deref(), Some("eth0"));
+    assert_eq!(eth0.in_octets, Some(200));
+    assert_eq!(eth0.in_octets_64(), Some(1 << 40));
+    assert_eq!(eth0.out_octets_64(), None);
+    let indexes: Vec<_> = table.samples().map(|s| s.if_index).collect();
+    assert_eq!(indexes, vec![1, 2]);
+}
+
+#[test]
+fn test_host_resources() {
+    let mut host = HostResources::new();
+    host.push_pdu(&response(vec![
+        var(
+            oid!(1.3.6 .1 .2 .1 .25 .2 .3 .1 .2 .31),
+            ObjectSyntax::Object(oid!(1.3.6 .1 .2 .1 .25 .2 .1 .4)),
+        ),
+        var(
+            oid!(1.3.6 .1 .2 .1 .25 .2 .3 .1 .3 .31),
+            ObjectSyntax::String(b"/"),
+        ),
+        var(
+            oid!(1.3.6 .1 .2 .1 .25 .2 .3 .1 .4 .31),
+            ObjectSyntax::Number(4096),
+        ),
+        var(
+            oid!(1.3.6 .1 .2 .1 .25 .2 .3 .1 .5 .31),
+            ObjectSyntax::Number(1000),
+        ),
+        var(
+            oid!(1.3.6 .1 .2 .1 .25 .2 .3 .1 .6 .31),
+            ObjectSyntax::Number(250),
+        ),
+        var(
+            oid!(1.3.6 .1 .2 .1 .25 .3 .3 .1 .2 .196608),
+            ObjectSyntax::Number(10),
+        ),
+        var(
+            oid!(1.3.6 .1 .2 .1 .25 .3 .3 .1 .2 .196609),
+            ObjectSyntax::Number(31),
+        ),
+    ]));
+    let storage: Vec<_> = host.storage().collect();
+    assert_eq!(storage.len(), 1);
+    assert_eq!(storage[0].index, 31);
+    assert_eq!(storage[0].descr.as_deref(), Some("/"));
+    assert_eq!(
+        storage[0].storage_type,
+        Some(oid!(1.3.6 .1 .2 .1 .25 .2 .1 .4))
+    );
+    assert_eq!(storage[0].size_bytes(), Some(4_096_000));
+    assert_eq!(storage[0].used_bytes(), Some(1_024_000));
+    let loads: Vec<_> = host.processor_loads().collect();
+    assert_eq!(loads, vec![(196608, 10), (196609, 31)]);
+    assert_eq!(host.average_processor_load(), Some(20));
+}
+
+#[test]
+fn test_entity_inventory() {
+    let mut inventory = EntityInventory::new();
+    inventory.push_pdu(&response(vec![
+        var(
+            oid!(1.3.6 .1 .2 .1 .47 .1 .1 .1 .1 .4 .1),
+            ObjectSyntax::Number(0),
+        ),
+        var(
+            oid!(1.3.6 .1 .2 .1 .47 .1 .1 .1 .1 .5 .1),
+            ObjectSyntax::Number(3),
+        ),
+        var(
+            oid!(1.3.6 .1 .2 .1 .47 .1 .1 .1 .1 .11 .1),
+            ObjectSyntax::String(b"FOC1234X0AB"),
+        ),
+        var(
+            oid!(1.3.6 .1 .2 .1 .47 .1 .1 .1 .1 .4 .1001),
+            ObjectSyntax::Number(1),
+        ),
+        var(
+            oid!(1.3.6 .1 .2 .1 .47 .1 .1 .1 .1 .7 .1001),
+            ObjectSyntax::String(b"PS-1"),
+        ),
+    ]));
+    assert_eq!(inventory.len(), 2);
+    let chassis = inventory.get(1).expect("missing entity");
+    assert_eq!(chassis.class, Some(3));
+    assert_eq!(chassis.serial_num.as_deref(), Some("FOC1234X0AB"));
+    let children: Vec<_> = inventory.children(1).map(|e| e.index).collect();
+    assert_eq!(children, vec![1001]);
+    assert_eq!(inventory.get(1001).unwrap().name.as_deref(), Some("PS-1"));
+}
+
+#[test]
+fn test_engine_counters() {
+    let mut pdu = response(vec![
+        var(
+            oid!(1.3.6 .1 .2 .1 .11 .1 .0),
+            ObjectSyntax::Counter32(1000),
+        ),
+        var(oid!(1.3.6 .1 .2 .1 .11 .4 .0), ObjectSyntax::Counter32(12)),
+        var(
+            oid!(1.3.6 .1 .6 .3 .11 .2 .1 .1 .0),
+            ObjectSyntax::Counter32(3),
+        ),
+        var(oid!(1.3.6 .1 .2 .1 .1 .3 .0), ObjectSyntax::TimeTicks(100)),
+    ]);
+    let previous = EngineCounters::from_pdu(&pdu);
+    assert_eq!(previous.in_pkts, Some(1000));
+    assert_eq!(previous.in_bad_community_names, Some(12));
+    assert_eq!(previous.unknown_security_models, Some(3));
+    assert_eq!(previous.out_pkts, None);
+    if let SnmpPdu::Generic(ref mut pdu) = pdu {
+        pdu.var[0].val = VarBindValue::Value(ObjectSyntax::Counter32(5));
+        pdu.var[1].val = VarBindValue::Value(ObjectSyntax::Counter32(512));
+    }
+    let current = EngineCounters::from_pdu(&pdu);
+    let increase = current.increase_since(&previous);
+    assert_eq!(increase.in_pkts, Some(5u32.wrapping_sub(1000)));
+    assert_eq!(increase.in_bad_community_names, Some(500));
+    assert_eq!(increase.unknown_security_models, Some(0));
+    // requests are ignored
+    if let SnmpPdu::Generic(ref mut pdu) = pdu {
+        pdu.pdu_type = PduType::GetRequest;
+    }
+    assert!(EngineCounters::from_pdu(&pdu).is_empty());
+}
@@ -0,0 +1,222 @@
+//! Differential tests against net-snmp
+//!
+//! Messages are sent to a local `snmptrapd` instance, and the fields it decodes are compared
+//! with the result of this parser.
+//!
+//! These tests require net-snmp tools in `PATH`, and only run if the `SNMP_PARSER_NETSNMP`
+//! environment variable is set.
+
+#![cfg(feature = "v1")]
+
+use asn1_rs::Oid;
+use snmp_parser::encode::SnmpEncode;
+use snmp_parser::trap::SNMP_TRAP_OID;
+use snmp_parser::*;
+use std::io::{BufRead, BufReader};
+use std::net::UdpSocket;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+static SNMPV1_TRAP_COLDSTART: &[u8] = include_bytes!("../assets/snmpv1_trap_coldstart.bin");
+
+const FORMAT: &str = "enterprise=%N|generic=%w|specific=%q|agent=%a|vars=%v\n";
+
+fn enabled() -> bool {
+    std::env::var_os("SNMP_PARSER_NETSNMP").is_some()
+}
+
+/// Fields decoded by snmptrapd
+#[derive(Debug, Default)]
+struct Decoded {
+    fields: Vec<(String, String)>,
+    vars: Vec<(String, String)>,
+}
+
+impl Decoded {
+    fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+fn parse_line(line: &str) -> Decoded {
+    let mut decoded = Decoded::default();
+    for part in line.split('|') {
+        let (name, value) = match part.split_once('=') {
+            Some(nv) => nv,
+            None => continue,
+        };
+        if name == "vars" {
+            // each varbind is "OID VALUE", separated by tabs
+            for var in value.split('\t').map(str::trim).filter(|v| !v.is_empty()) {
+                let (oid, val) = var.split_once(' ').unwrap_or((var, ""));
+                decoded.vars.push((oid.to_string(), val.trim().to_string()));
+            }
+        } else {
+            decoded
+                .fields
+                .push((name.to_string(), value.trim().to_string()));
+        }
+    }
+    decoded
+}
+
+struct TrapReceiver {
+    child: Child,
+    lines: mpsc::Receiver<String>,
+    port: u16,
+}
+
+impl TrapReceiver {
+    fn start() -> TrapReceiver {
+        // find a free port
+        let port = UdpSocket::bind("127.0.0.1:0")
+            .and_then(|s| s.local_addr())
+            .map(|a| a.port())
+            .expect("no free port");
+        let mut child = Command::new("snmptrapd")
+            .args(["-f", "-C", "-Lo", "-On", "-Oq", "-F", FORMAT])
+            .arg("--disableAuthorization=yes")
+            .arg(format!("udp:127.0.0.1:{}", port))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("could not run snmptrapd");
+        let stdout = child.stdout.take().expect("no stdout");
+        let (tx, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        // give snmptrapd time to bind
+        thread::sleep(Duration::from_millis(500));
+        TrapReceiver { child, lines, port }
+    }
+
+    fn decode(&self, message: &[u8]) -> Decoded {
+        let socket = UdpSocket::bind("127.0.0.1:0").expect("bind failed");
+        socket
+            .send_to(message, ("127.0.0.1", self.port))
+            .expect("send failed");
+        loop {
+            let line = self
+                .lines
+                .recv_timeout(Duration::from_secs(5))
+                .expect("snmptrapd did not decode the message");
+            if line.contains("enterprise=") {
+                return parse_line(&line);
+            }
+        }
+    }
+}
+
+impl Drop for TrapReceiver {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn numeric_oid(oid: &Oid) -> String {
+    format!(".{}", oid.to_id_string())
+}
+
+/// Value as printed by net-snmp with `-Oq`, for types where the output is not ambiguous
+fn netsnmp_value(val: &VarBindValue) -> Option<String> {
+    match val {
+        VarBindValue::Value(ObjectSyntax::Number(n)) => Some(n.to_string()),
+        VarBindValue::Value(ObjectSyntax::Counter32(n))
+        | VarBindValue::Value(ObjectSyntax::Gauge32(n))
+        | VarBindValue::Value(ObjectSyntax::UInteger32(n)) => Some(n.to_string()),
+        VarBindValue::Value(ObjectSyntax::Counter64(n)) => Some(n.to_string()),
+        VarBindValue::Value(ObjectSyntax::Object(oid)) => Some(numeric_oid(oid)),
+        VarBindValue::Value(ObjectSyntax::IpAddress(NetworkAddress::IPv4(ip))) => {
+            Some(ip.to_string())
+        }
+        _ => None,
+    }
+}
+
+fn compare_vars(decoded: &Decoded, pdu: &SnmpPdu) {
+    let vars: Vec<_> = pdu.vars_iter().collect();
+    assert_eq!(decoded.vars.len(), vars.len(), "varbind count differs");
+    for ((oid, value), var) in decoded.vars.iter().zip(vars) {
+        assert_eq!(oid, &numeric_oid(&var.oid), "varbind OID differs");
+        if let Some(expected) = netsnmp_value(&var.val) {
+            assert_eq!(value, &expected, "value of {} differs", oid);
+        }
+    }
+}
+
+#[test]
+fn test_netsnmp_trap_v1() {
+    if !enabled() {
+        return;
+    }
+    let receiver = TrapReceiver::start();
+    let decoded = receiver.decode(SNMPV1_TRAP_COLDSTART);
+    let (_, msg) = parse_snmp_v1(SNMPV1_TRAP_COLDSTART).expect("parsing failed");
+    let trap = match msg.pdu {
+        SnmpPdu::TrapV1(ref trap) => trap,
+        _ => panic!("not a trap"),
+    };
+    let NetworkAddress::IPv4(agent) = trap.agent_addr;
+    assert_eq!(
+        decoded.field("enterprise"),
+        Some(numeric_oid(&trap.enterprise).as_str())
+    );
+    assert_eq!(
+        decoded.field("generic"),
+        Some(trap.generic_trap.0.to_string().as_str())
+    );
+    assert_eq!(
+        decoded.field("specific"),
+        Some(trap.specific_trap.to_string().as_str())
+    );
+    assert_eq!(decoded.field("agent"), Some(agent.to_string().as_str()));
+    compare_vars(&decoded, &msg.pdu);
+}
+
+#[test]
+fn test_netsnmp_trap_v2c() {
+    if !enabled() {
+        return;
+    }
+    let sys_uptime = oid!(1.3.6 .1 .2 .1 .1 .3 .0);
+    let link_down = oid!(1.3.6 .1 .6 .3 .1 .1 .5 .3);
+    let if_index = oid!(1.3.6 .1 .2 .1 .2 .2 .1 .1 .2);
+    let msg = SnmpMessage {
+        version: 1,
+        community: String::from("public"),
+        pdu: SnmpPdu::Generic(SnmpGenericPdu {
+            pdu_type: PduType::TrapV2,
+            req_id: 1234,
+            err: ErrorStatus::NoError,
+            err_index: 0,
+            var: vec![
+                SnmpVariable {
+                    oid: sys_uptime,
+                    val: VarBindValue::Value(ObjectSyntax::TimeTicks(4200)),
+                },
+                SnmpVariable {
+                    oid: SNMP_TRAP_OID,
+                    val: VarBindValue::Value(ObjectSyntax::Object(link_down)),
+                },
+                SnmpVariable {
+                    oid: if_index,
+                    val: VarBindValue::Value(ObjectSyntax::Number(2)),
+                },
+            ],
+        }),
+    };
+    let receiver = TrapReceiver::start();
+    let decoded = receiver.decode(&msg.encode());
+    compare_vars(&decoded, &msg.pdu);
+}
@@ -0,0 +1,39 @@
+#![cfg(all(feature = "std", feature = "v2c", feature = "v3"))]
+
+extern crate snmp_parser;
+
+use snmp_parser::profile::*;
+
+static SNMPV2_GET_RESPONSE: &[u8] = include_bytes!("../assets/snmpv2c-get-response.bin");
+static SNMPV3_REQ_ENCRYPTED: &[u8] = include_bytes!("../assets/snmpv3_req_encrypted.bin");
+
+#[cfg(feature = "alloc-stats")]
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator::new();
+
+#[test]
+fn test_parse_metrics() {
+    let mut metrics = Vec::new();
+    let mut sink = |m: &ParseMetrics| metrics.push(*m);
+    let mut bytes = SNMPV2_GET_RESPONSE.to_vec();
+    bytes.push(0);
+    let (rem, _) = parse_with_metrics(&bytes, &mut sink).expect("parsing failed");
+    assert_eq!(rem.len(), 1);
+    parse_with_metrics(SNMPV3_REQ_ENCRYPTED, &mut sink).expect("parsing failed");
+    assert!(parse_with_metrics(&SNMPV2_GET_RESPONSE[..10], &mut sink).is_err());
+    assert_eq!(metrics.len(), 3);
+    assert!(metrics[0].success);
+    assert_eq!(metrics[0].bytes, SNMPV2_GET_RESPONSE.len());
+    let (_, msg) = snmp_parser::parse_snmp_v2c(SNMPV2_GET_RESPONSE).expect("parsing failed");
+    assert_eq!(metrics[0].varbinds, msg.vars_iter().count());
+    assert_eq!(metrics[1].varbinds, 0);
+    assert!(!metrics[2].success);
+    assert_eq!(metrics[2].bytes, 10);
+    #[cfg(feature = "alloc-stats")]
+    {
+        let allocations = metrics[0].allocations.expect("allocations not counted");
+        assert!(allocations.count > 0);
+    }
+    #[cfg(not(feature = "alloc-stats"))]
+    assert_eq!(metrics[0].allocations, None);
+}
@@ -0,0 +1,42 @@
+#![cfg(all(feature = "v2c", feature = "v3"))]
+
+extern crate snmp_parser;
+
+use snmp_parser::raw::RawSnmpMessage;
+use snmp_parser::*;
+use std::sync::Arc;
+
+static SNMPV2_GET_RESPONSE: &[u8] = include_bytes!("../assets/snmpv2c-get-response.bin");
+static SNMPV3_REQ: &[u8] = include_bytes!("../assets/snmpv3_req.bin");
+
+#[test]
+fn test_raw_message() {
+    let mut data = SNMPV2_GET_RESPONSE.to_vec();
+    // trailing bytes are ignored
+    data.extend_from_slice(b"\x00\x00");
+    let stored = RawSnmpMessage::new(data).expect("invalid message");
+    assert_eq!(stored.as_bytes(), SNMPV2_GET_RESPONSE);
+    assert_eq!(stored.version(), 1);
+    assert_eq!(stored.community(), Some(&b"public"[..]));
+    assert_eq!(stored.pdu_type(), Some(PduType::Response));
+    let (_, expected) = parse_snmp_v2c(SNMPV2_GET_RESPONSE).expect("parsing failed");
+    assert_eq!(stored.request_id(), expected.pdu.request_id());
+    assert_eq!(stored.varbinds().count(), expected.vars_iter().count());
+    match stored.parse().expect("parsing failed") {
+        SnmpGenericMessage::V2(msg) => assert_eq!(msg, expected),
+        msg => panic!("unexpected message {:?}", msg),
+    }
+    assert_eq!(stored.into_inner().len(), SNMPV2_GET_RESPONSE.len() + 2);
+}
+
+#[test]
+fn test_raw_message_v3() {
+    let data: Arc<[u8]> = Arc::from(SNMPV3_REQ);
+    let stored = RawSnmpMessage::new(data).expect("invalid message");
+    assert_eq!(stored.version(), 3);
+    assert_eq!(stored.community(), None);
+    assert!(stored.header_data().is_some());
+    assert_eq!(stored.header().request_id, Some(2098071598));
+    assert!(matches!(stored.parse(), Ok(SnmpGenericMessage::V3(_))));
+    assert!(RawSnmpMessage::new(&SNMPV3_REQ[..10]).is_err());
+}
@@ -0,0 +1,76 @@
+#![cfg(all(feature = "v1", feature = "v3"))]
+
+extern crate snmp_parser;
+
+use snmp_parser::rules::{Rule, RuleSet, MAX_RULE_DEPTH};
+use snmp_parser::*;
+
+static SNMPV1_REQ: &[u8] = include_bytes!("../assets/snmpv1_req.bin");
+static SNMPV3_REQ: &[u8] = include_bytes!("../assets/snmpv3_req.bin");
+
+fn check(rule: &str, msg: &SnmpGenericMessage) -> bool {
+    Rule::parse(rule).expect("invalid rule").matches(msg)
+}
+
+#[test]
+fn test_rules_v1() {
+    let (_, msg) = parse_snmp_generic_message(SNMPV1_REQ).expect("parsing failed");
+    assert!(check("version == 0", &msg));
+    assert!(check("pdu_type==get && community==public", &msg));
+    assert!(check(
+        r#"community != "private" && oid startswith 1.3.6.1.2.1.1"#,
+        &msg
+    ));
+    assert!(check("oid == 1.3.6.1.2.1.1.2.0 && varbinds == 1", &msg));
+    assert!(check("!(pdu_type == set) || community contains priv", &msg));
+    assert!(check("request_id > 10 && request_id <= 38", &msg));
+    assert!(!check("oid != 1.3.6.1.2.1.1.2.0", &msg));
+    assert!(!check("user == foo || user != foo", &msg));
+}
+
+#[test]
+fn test_rules_v3() {
+    let (_, msg) = parse_snmp_generic_message(SNMPV3_REQ).expect("parsing failed");
+    assert!(check("version == 3 && user == \"\"", &msg));
+    assert!(!check("community == public || community != public", &msg));
+    let mut rules = RuleSet::new();
+    rules.add(1, Rule::parse("pdu_type == set").unwrap());
+    rules.add(2, Rule::parse("pdu_type == get").unwrap());
+    rules.add(3, Rule::parse("version >= 1").unwrap());
+    assert_eq!(rules.matches(&msg), vec![2, 3]);
+}
+
+#[test]
+fn test_rules_errors() {
+    assert_eq!(Rule::parse("foo == 1").unwrap_err().offset, 0);
+    assert_eq!(Rule::parse("version == 1 &&").unwrap_err().offset, 15);
+    assert_eq!(Rule::parse("(version == 1").unwrap_err().offset, 0);
+    assert!(Rule::parse("pdu_type startswith get").is_err());
+    assert!(Rule::parse("oid == 1.x").is_err());
+    assert!(Rule::parse("community == \"abc").is_err());
+}
+
+#[test]
+fn test_rules_max_depth() {
+    let nested = |n: usize| format!("{}version == 1", "!".repeat(n));
+    assert!(Rule::parse(&nested(MAX_RULE_DEPTH)).is_ok());
+    let err = Rule::parse(&nested(MAX_RULE_DEPTH + 1)).unwrap_err();
+    assert_eq!(err.offset, MAX_RULE_DEPTH);
+    // deep enough to overflow the stack without the limit
+    assert!(Rule::parse(&nested(100_000)).is_err());
+    let parens = format!("{}version == 1{}", "(".repeat(10_000), ")".repeat(10_000));
+    assert!(Rule::parse(&parens).is_err());
+}
+
+#[test]
+fn test_rules_long_chains() {
+    let (_, msg) = parse_snmp_generic_message(SNMPV1_REQ).expect("parsing failed");
+    // long chains are not nested, so they neither count toward the depth nor overflow the stack
+    let chain = |op: &str| vec!["version == 0"; 100_000].join(op);
+    assert!(check(&chain(" && "), &msg));
+    assert!(check(&chain(" || "), &msg));
+    let rule = format!("{} && version == 1", chain(" && "));
+    assert!(!check(&rule, &msg));
+    let rule = format!("{} || version == 1", chain(" || "));
+    assert!(check(&rule, &msg));
+}
@@ -0,0 +1,123 @@
+#![cfg(all(feature = "v2c", feature = "v3"))]
+
+extern crate snmp_parser;
+
+use snmp_parser::encode::SnmpEncode;
+use snmp_parser::scrub::*;
+use snmp_parser::*;
+
+static SNMPV2_GET: &[u8] = include_bytes!("../assets/snmpv2c-get-response.bin");
+static SNMPV3_REQ: &[u8] = include_bytes!("../assets/snmpv3_req.bin");
+
+#[test]
+fn test_scrub_v2c() {
+    let var = |oid, val| SnmpVariable {
+        oid,
+        val: VarBindValue::Value(val),
+    };
+    let msg = SnmpMessage {
+        version: 1,
+        community: String::from("secret"),
+        pdu: SnmpPdu::Generic(SnmpGenericPdu {
+            pdu_type: PduType::Response,
+            req_id: 1,
+            err: ErrorStatus::NoError,
+            err_index: 0,
+            var: vec![
+                var(oid!(1.3.6 .1 .2 .1 .1 .3 .0), ObjectSyntax::TimeTicks(42)),
+                var(SYS_NAME_OID, ObjectSyntax::String(b"router1")),
+                var(SYS_LOCATION_OID, ObjectSyntax::Counter32(1)),
+            ],
+        }),
+    };
+    let bytes = scrub_message(&msg.encode(), &ScrubConfig::default()).expect("scrubbing failed");
+    let (_, scrubbed) = parse_snmp_v2c(&bytes).expect("parsing failed");
+    assert_eq!(scrubbed.community, "xxxxxx");
+    let vals: Vec<_> = scrubbed.vars_iter().map(|v| &v.val).collect();
+    assert_eq!(
+        vals,
+        [
+            &VarBindValue::Value(ObjectSyntax::TimeTicks(42)),
+            &VarBindValue::Value(ObjectSyntax::String(b"xxxxxxx")),
+            &VarBindValue::Unspecified,
+        ]
+    );
+    // variables are selected by prefix
+    let config = ScrubConfig {
+        oids: vec![oid!(1.3.6 .1 .2 .1 .25 .1 .5)],
+        placeholder: Placeholder::new(b'#').expect("invalid placeholder"),
+    };
+    let bytes = scrub_message(SNMPV2_GET, &config).expect("scrubbing failed");
+    let (_, scrubbed) = parse_snmp_v2c(&bytes).expect("parsing failed");
+    assert_eq!(scrubbed.community, "######");
+    let vals: Vec<_> = scrubbed.vars_iter().map(|v| &v.val).collect();
+    assert_eq!(
+        vals,
+        [
+            &VarBindValue::Value(ObjectSyntax::TimeTicks(970069)),
+            &VarBindValue::Unspecified,
+            &VarBindValue::NoSuchInstance,
+        ]
+    );
+}
+
+#[test]
+fn test_scrub_v3() {
+    let bytes = scrub_message(SNMPV3_REQ, &ScrubConfig::default()).expect("scrubbing failed");
+    assert_eq!(bytes.len(), SNMPV3_REQ.len());
+    let (_, msg) = parse_snmp_v3(&bytes).expect("parsing failed");
+    let (_, orig) = parse_snmp_v3(SNMPV3_REQ).expect("parsing failed");
+    match (&msg.security_params, &orig.security_params) {
+        (SecurityParameters::USM(usm), SecurityParameters::USM(orig)) => {
+            assert_eq!(usm.msg_user_name.len(), orig.msg_user_name.len());
+            assert!(usm.msg_user_name.bytes().all(|b| b == b'x'));
+            assert!(usm.msg_authoritative_engine_id.iter().all(|&b| b == b'x'));
+            assert!(usm.msg_authentication_parameters.iter().all(|&b| b == 0));
+        }
+        _ => panic!("unexpected security parameters"),
+    }
+    assert!(scrub_message(&[0x30, 0x00], &ScrubConfig::default()).is_err());
+}
+
+#[test]
+fn test_scrub_placeholder_not_ascii() {
+    assert_eq!(Placeholder::new(0xe9), None);
+    assert_eq!(Placeholder::default().get(), b'x');
+}
+
+#[test]
+fn test_scrub_v3_context() {
+    let (_, mut msg) = parse_snmp_v3(SNMPV3_REQ).expect("parsing failed");
+    match msg.data {
+        ScopedPduData::Plaintext(ref mut scoped_pdu) => {
+            scoped_pdu.ctx_engine_id = b"engine";
+            scoped_pdu.ctx_engine_name = b"tenant";
+        }
+        _ => panic!("unexpected encrypted data"),
+    }
+    let bytes = scrub_message(&msg.encode(), &ScrubConfig::default()).expect("scrubbing failed");
+    let (_, msg) = parse_snmp_v3(&bytes).expect("parsing failed");
+    match msg.data {
+        ScopedPduData::Plaintext(ref scoped_pdu) => {
+            assert_eq!(scoped_pdu.ctx_engine_id, b"xxxxxx");
+            assert_eq!(scoped_pdu.ctx_engine_name, b"xxxxxx");
+        }
+        _ => panic!("unexpected encrypted data"),
+    }
+}
+
+#[test]
+#[cfg(feature = "v1")]
+fn test_scrub_v1_trap() {
+    static SNMPV1_TRAP: &[u8] = include_bytes!("../assets/snmpv1_trap_coldstart.bin");
+    let bytes = scrub_message(SNMPV1_TRAP, &ScrubConfig::default()).expect("scrubbing failed");
+    assert_eq!(bytes.len(), SNMPV1_TRAP.len());
+    let (_, msg) = parse_snmp_v1(&bytes).expect("parsing failed");
+    match msg.pdu {
+        SnmpPdu::TrapV1(ref trap) => assert_eq!(
+            trap.agent_addr,
+            NetworkAddress::IPv4(std::net::Ipv4Addr::UNSPECIFIED)
+        ),
+        _ => panic!("unexpected PDU"),
+    }
+}
@@ -0,0 +1,45 @@
+#![cfg(all(feature = "zeroize", feature = "v2c", feature = "v3"))]
+
+extern crate snmp_parser;
+
+use snmp_parser::*;
+use zeroize::{Zeroize, Zeroizing};
+
+static SNMPV2_GET: &[u8] = include_bytes!("../assets/snmpv2c-get-response.bin");
+static SNMPV3_REPORT: &[u8] = include_bytes!("../assets/snmpv3-report.bin");
+
+#[test]
+fn test_zeroize_community() {
+    let (_, mut msg) = parse_snmp_generic_message(SNMPV2_GET).expect("parsing failed");
+    msg.zeroize();
+    match msg {
+        SnmpGenericMessage::V2(ref msg) => {
+            assert!(msg.community.is_empty());
+            assert_eq!(msg.pdu_type(), PduType::Response);
+        }
+        _ => panic!("unexpected message version"),
+    }
+}
+
+#[test]
+fn test_zeroize_usm_user_name() {
+    let (_, mut msg) = parse_snmp_v3(SNMPV3_REPORT).expect("parsing failed");
+    msg.zeroize();
+    match msg.security_params {
+        SecurityParameters::USM(ref usm) => assert!(usm.msg_user_name.is_empty()),
+        _ => panic!("unexpected security parameters"),
+    }
+}
+
+#[test]
+fn test_zeroize_move_out() {
+    // fields can be moved out of messages, unless they are wrapped in `Zeroizing`
+    let (_, msg) = parse_snmp_v3(SNMPV3_REPORT).expect("parsing failed");
+    let _user_name = match msg.security_params {
+        SecurityParameters::USM(usm) => usm.msg_user_name,
+        _ => panic!("unexpected security parameters"),
+    };
+    let (_, msg) = parse_snmp_v3(SNMPV3_REPORT).expect("parsing failed");
+    let msg = Zeroizing::new(msg);
+    assert_eq!(msg.version, 3);
+}
@@ -0,0 +1,19 @@
+//! Types meant to be shared between the threads of a sensor
+
+extern crate snmp_parser;
+
+use snmp_parser::config::ParserConfig;
+use snmp_parser::intern::{InternedOid, OidInterner};
+use snmp_parser::mib::MibRegistry;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_send_sync() {
+    assert_send_sync::<MibRegistry>();
+    assert_send_sync::<OidInterner>();
+    assert_send_sync::<InternedOid>();
+    assert_send_sync::<ParserConfig>();
+    #[cfg(feature = "enterprises")]
+    assert_send_sync::<snmp_parser::enterprise::EnterpriseRegistry>();
+}
@@ -0,0 +1,46 @@
+#![cfg(all(feature = "bytes", feature = "v2c"))]
+
+extern crate snmp_parser;
+
+use bytes::Bytes;
+use snmp_parser::inspect::inspect_snmp_message;
+use snmp_parser::shared::*;
+use snmp_parser::*;
+
+static SNMPV2_GET_RESPONSE: &[u8] = include_bytes!("../assets/snmpv2c-get-response.bin");
+
+#[test]
+fn test_shared_header() {
+    let buf = Bytes::copy_from_slice(SNMPV2_GET_RESPONSE);
+    let (hdr, len) = SharedMessageHeader::parse(&buf).expect("parsing failed");
+    assert_eq!(len, SNMPV2_GET_RESPONSE.len());
+    let vars: Vec<SharedVarBind> = hdr
+        .varbinds()
+        .collect::<Result<_, _>>()
+        .expect("invalid varbinds");
+    // slices are not copied
+    let community = hdr.community.clone().expect("no community");
+    assert_eq!(community, &b"public"[..]);
+    let range = buf.as_ptr() as usize..buf.as_ptr() as usize + buf.len();
+    assert!(range.contains(&(community.as_ptr() as usize)));
+    assert!(range.contains(&(vars[0].raw_value().as_ptr() as usize)));
+    // the views outlive the original buffer
+    drop(buf);
+    let (_, expected) = inspect_snmp_message(SNMPV2_GET_RESPONSE).expect("inspection failed");
+    assert_eq!(hdr.version, expected.version);
+    assert_eq!(hdr.pdu_type, Some(PduType::Response));
+    assert_eq!(hdr.request_id, expected.request_id);
+    assert_eq!(vars.len(), expected.varbind_count());
+    for (var, raw) in vars.iter().zip(expected.varbinds()) {
+        let raw = raw.expect("invalid varbind");
+        assert_eq!(var.oid(), raw.oid);
+        assert_eq!(var.decode_value(), raw.decode_value());
+    }
+    assert_eq!(hdr.as_bytes(), SNMPV2_GET_RESPONSE);
+}
+
+#[test]
+fn test_shared_header_invalid() {
+    let buf = Bytes::from_static(&SNMPV2_GET_RESPONSE[..20]);
+    assert!(SharedMessageHeader::parse(&buf).is_err());
+}
@@ -0,0 +1,189 @@
+extern crate snmp_parser;
+
+use snmp_parser::simulate::*;
+use snmp_parser::*;
+
+fn simulator() -> Simulator {
+    let mut simulator = Simulator::new();
+    simulator.insert(
+        &oid!(1.3.6 .1 .2 .1 .1 .5 .0),
+        &ObjectSyntax::String(b"router-1"),
+    );
+    simulator.insert(
+        &oid!(1.3.6 .1 .2 .1 .2 .2 .1 .2 .1),
+        &ObjectSyntax::String(b"lo"),
+    );
+    simulator.insert(
+        &oid!(1.3.6 .1 .2 .1 .2 .2 .1 .2 .2),
+        &ObjectSyntax::String(b"eth0"),
+    );
+    simulator.insert(
+        &oid!(1.3.6 .1 .2 .1 .2 .2 .1 .10 .1),
+        &ObjectSyntax::Counter32(1),
+    );
+    simulator.insert(
+        &oid!(1.3.6 .1 .2 .1 .2 .2 .1 .10 .2),
+        &ObjectSyntax::Counter32(2),
+    );
+    simulator
+}
+
+fn request(version: u32, pdu_type: PduType, oids: &[Oid<'static>]) -> SnmpMessage<'static> {
+    SnmpMessage {
+        version,
+        community: "public".to_string(),
+        pdu: SnmpPdu::Generic(SnmpGenericPdu {
+            pdu_type,
+            req_id: 42,
+            err: ErrorStatus::NoError,
+            err_index: 0,
+            var: oids
+                .iter()
+                .map(|oid| SnmpVariable {
+                    oid: oid.clone(),
+                    val: VarBindValue::Unspecified,
+                })
+                .collect(),
+        }),
+    }
+}
+
+fn response_vars<'a>(msg: &'a SnmpMessage) -> Vec<(String, &'a VarBindValue<'a>)> {
+    msg.pdu
+        .vars_iter()
+        .map(|v| (v.oid.to_id_string(), &v.val))
+        .collect()
+}
+
+#[test]
+fn test_simulate_get() {
+    let simulator = simulator();
+    assert_eq!(simulator.len(), 5);
+    let req = request(
+        1,
+        PduType::GetRequest,
+        &[
+            oid!(1.3.6 .1 .2 .1 .1 .5 .0),
+            oid!(1.3.6 .1 .2 .1 .2 .2 .1 .2 .3),
+            oid!(1.3.6 .1 .2 .1 .1 .6 .0),
+        ],
+    );
+    let resp = simulator.respond(&req).expect("no response");
+    assert_eq!(resp.pdu.pdu_type(), PduType::Response);
+    assert_eq!(resp.pdu.request_id(), Some(42));
+    let vars = response_vars(&resp);
+    assert_eq!(
+        vars[0].1,
+        &VarBindValue::Value(ObjectSyntax::String(b"router-1"))
+    );
+    assert_eq!(vars[1].1, &VarBindValue::NoSuchInstance);
+    assert_eq!(vars[2].1, &VarBindValue::NoSuchObject);
+    // SNMPv1: error on the second variable
+    let req = request(
+        0,
+        PduType::GetRequest,
+        &[oid!(1.3.6 .1 .2 .1 .1 .5 .0), oid!(1.3.6 .1 .9)],
+    );
+    let resp = simulator.respond(&req).expect("no response");
+    match resp.pdu {
+        SnmpPdu::Generic(ref pdu) => {
+            assert_eq!(pdu.err, ErrorStatus::NoSuchName);
+            assert_eq!(pdu.err_index, 2);
+            assert_eq!(pdu.var.len(), 2);
+        }
+        _ => panic!("unexpected PDU"),
+    }
+    // set requests are rejected
+    let req = request(1, PduType::SetRequest, &[oid!(1.3.6 .1 .2 .1 .1 .5 .0)]);
+    let resp = simulator.respond(&req).expect("no response");
+    match resp.pdu {
+        SnmpPdu::Generic(ref pdu) => assert_eq!(pdu.err, ErrorStatus::NotWritable),
+        _ => panic!("unexpected PDU"),
+    }
+    // not a request
+    let req = request(1, PduType::Response, &[]);
+    assert!(simulator.respond(&req).is_none());
+}
+
+#[test]
+fn test_simulate_walk() {
+    let simulator = simulator();
+    let mut oid = oid!(1.3.6 .1 .2 .1 .2);
+    let mut walked = Vec::new();
+    loop {
+        let req = request(1, PduType::GetNextRequest, &[oid.clone()]);
+        let resp = simulator.respond(&req).expect("no response");
+        let var = resp.pdu.vars_iter().next().expect("no variable");
+        if var.val == VarBindValue::EndOfMibView {
+            break;
+        }
+        walked.push(var.oid.to_id_string());
+        oid = var.oid.to_owned();
+    }
+    assert_eq!(
+        walked,
+        vec![
+            "1.3.6.1.2.1.2.2.1.2.1",
+            "1.3.6.1.2.1.2.2.1.2.2",
+            "1.3.6.1.2.1.2.2.1.10.1",
+            "1.3.6.1.2.1.2.2.1.10.2",
+        ]
+    );
+}
+
+#[test]
+fn test_simulate_bulk() {
+    let simulator = simulator();
+    let req = SnmpMessage {
+        version: 1,
+        community: "public".to_string(),
+        pdu: SnmpPdu::Bulk(SnmpBulkPdu {
+            req_id: 7,
+            non_repeaters: 1,
+            max_repetitions: 5,
+            var: vec![
+                SnmpVariable {
+                    oid: oid!(1.3.6 .1 .2 .1 .1 .4),
+                    val: VarBindValue::Unspecified,
+                },
+                SnmpVariable {
+                    oid: oid!(1.3.6 .1 .2 .1 .2 .2 .1 .2),
+                    val: VarBindValue::Unspecified,
+                },
+                SnmpVariable {
+                    oid: oid!(1.3.6 .1 .2 .1 .2 .2 .1 .10),
+                    val: VarBindValue::Unspecified,
+                },
+            ],
+        }),
+    };
+    let resp = simulator.respond(&req).expect("no response");
+    let oids: Vec<_> = response_vars(&resp)
+        .into_iter()
+        .map(|(oid, _)| oid)
+        .collect();
+    assert_eq!(
+        oids,
+        vec![
+            "1.3.6.1.2.1.1.5.0",
+            "1.3.6.1.2.1.2.2.1.2.1",
+            "1.3.6.1.2.1.2.2.1.10.1",
+            "1.3.6.1.2.1.2.2.1.2.2",
+            "1.3.6.1.2.1.2.2.1.10.2",
+            // end of the MIB for the second repeater
+            "1.3.6.1.2.1.2.2.1.10.1",
+            "1.3.6.1.2.1.2.2.1.10.2",
+            "1.3.6.1.2.1.2.2.1.10.2",
+            "1.3.6.1.2.1.2.2.1.10.2",
+            "1.3.6.1.2.1.2.2.1.10.2",
+            "1.3.6.1.2.1.2.2.1.10.2",
+        ]
+    );
+    assert_eq!(
+        resp.pdu.vars_iter().last().unwrap().val,
+        VarBindValue::EndOfMibView
+    );
+    // not valid in SNMPv1
+    let req = SnmpMessage { version: 0, ..req };
+    assert!(simulator.respond(&req).is_none());
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "v1")]
+
+#[macro_use]
+extern crate hex_literal;
+extern crate snmp_parser;
+
+use nom::Err;
+use snmp_parser::encode::SnmpEncode;
+use snmp_parser::error::SnmpError;
+use snmp_parser::smux::*;
+use snmp_parser::*;
+
+static SNMPV1_REQ: &[u8] = include_bytes!("../assets/snmpv1_req.bin");
+
+static SMUX_OPEN: &[u8] = &hex!(
+    "60 1d 02 01 00 06 09 2b 06 01 04 01 04 03 01 04
+     04 05 67 61 74 65 64 04 06 73 65 63 72 65 74"
+);
+
+static SMUX_RREQ: &[u8] = &hex!("62 0f 06 07 2b 06 01 02 01 04 15 02 01 ff 02 01 02");
+
+#[test]
+fn test_smux_open() {
+    let (rem, pdu) = parse_smux_pdu(SMUX_OPEN).expect("parsing failed");
+    assert!(rem.is_empty());
+    match pdu {
+        SmuxPdu::Open(open) => {
+            assert_eq!(open.version, 0);
+            assert_eq!(open.identity.to_id_string(), "1.3.6.1.4.1.4.3.1.4");
+            assert_eq!(open.description, b"gated");
+            assert_eq!(open.password, b"secret");
+        }
+        _ => panic!("not an Open PDU"),
+    }
+}
+
+#[test]
+fn test_smux_register() {
+    let (_, pdu) = parse_smux_pdu(SMUX_RREQ).expect("parsing failed");
+    match pdu {
+        SmuxPdu::RegisterRequest(rreq) => {
+            assert_eq!(rreq.subtree.to_id_string(), "1.3.6.1.2.1.4.21");
+            assert_eq!(rreq.priority, -1);
+            assert_eq!(rreq.operation, SmuxOperation::ReadWrite);
+        }
+        _ => panic!("not a RReq PDU"),
+    }
+    let (_, pdu) = parse_smux_pdu(&hex!("43 01 ff")).expect("parsing failed");
+    assert_eq!(pdu, SmuxPdu::RegisterResponse(-1));
+}
+
+#[test]
+fn test_smux_stream() {
+    let mut data = hex!("41 01 00").to_vec();
+    data.extend_from_slice(&hex!("44 01 01"));
+    let (rem, pdu) = parse_smux_pdu(&data).expect("parsing failed");
+    assert_eq!(pdu, SmuxPdu::Close(SmuxCloseReason::GoingDown));
+    let (rem, pdu) = parse_smux_pdu(rem).expect("parsing failed");
+    assert!(rem.is_empty());
+    assert_eq!(
+        pdu,
+        SmuxPdu::CommitOrRollback(SmuxCommitOrRollback::Rollback)
+    );
+}
+
+#[test]
+fn test_smux_snmp_pdu() {
+    let (_, msg) = parse_snmp_v1(SNMPV1_REQ).expect("parsing failed");
+    let data = msg.pdu.encode();
+    let (rem, pdu) = parse_smux_pdu(&data).expect("parsing failed");
+    assert!(rem.is_empty());
+    assert_eq!(pdu, SmuxPdu::Snmp(msg.pdu));
+}
+
+#[test]
+fn test_smux_errors() {
+    // a SNMP message is not a SMUX PDU
+    assert!(matches!(
+        parse_smux_pdu(SNMPV1_REQ),
+        Err(Err::Error(SnmpError::InvalidPduType { .. }))
+    ));
+    // once the PDU type is known, errors are failures
+    let res = parse_smux_pdu(&hex!("62 03 02 01 00"));
+    assert!(matches!(res, Err(Err::Failure(_))));
+}
@@ -0,0 +1,56 @@
+#![cfg(all(feature = "tokio", feature = "v2c"))]
+
+extern crate snmp_parser;
+
+use snmp_parser::stream::*;
+
+static SNMPV2_GET: &[u8] = include_bytes!("../assets/snmpv2c-get-response.bin");
+
+fn block_on<F: std::future::Future>(f: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("runtime")
+        .block_on(f)
+}
+
+#[test]
+fn test_read_snmp_message() {
+    let mut input = SNMPV2_GET.to_vec();
+    input.extend_from_slice(SNMPV2_GET);
+    block_on(async {
+        let mut reader = &input[..];
+        let mut buf = Vec::new();
+        for _ in 0..2 {
+            let msg = read_snmp_message(&mut reader, &mut buf, 1500)
+                .await
+                .expect("read failed")
+                .expect("no message");
+            assert_eq!(msg.version(), 1);
+            assert_eq!(msg.community(), Some("public"));
+        }
+        let res = read_snmp_message(&mut reader, &mut buf, 1500).await;
+        assert!(matches!(res, Ok(None)));
+    });
+}
+
+#[test]
+fn test_read_snmp_message_errors() {
+    block_on(async {
+        let mut buf = Vec::new();
+        // closed in the middle of the message
+        let mut reader = &SNMPV2_GET[..SNMPV2_GET.len() - 1];
+        match read_snmp_message(&mut reader, &mut buf, 1500).await {
+            Err(ReadError::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof),
+            e => panic!("unexpected result: {:?}", e.map(|_| ())),
+        }
+        let mut reader = SNMPV2_GET;
+        match read_snmp_message(&mut reader, &mut buf, 16).await {
+            Err(ReadError::TooLarge { len }) => assert_eq!(len, SNMPV2_GET.len()),
+            e => panic!("unexpected result: {:?}", e.map(|_| ())),
+        }
+        // not a SEQUENCE
+        let mut reader = &[0x04, 0x01, 0x00][..];
+        let res = read_snmp_message(&mut reader, &mut buf, 1500).await;
+        assert!(matches!(res, Err(ReadError::Snmp(_))));
+    });
+}
@@ -0,0 +1,83 @@
+extern crate snmp_parser;
+
+mod common;
+
+use common::*;
+use snmp_parser::topology::*;
+use snmp_parser::*;
+use std::net::Ipv4Addr;
+
+#[test]
+fn test_topology_neighbors() {
+    let mut hints = TopologyHints::new();
+    assert!(hints.is_empty());
+    // lldpRemPortId and lldpRemSysName, for local port 3 and remote index 1
+    hints.push_pdu(&response(vec![
+        var(
+            oid!(1.0.8802 .1 .1 .2 .1 .4 .1 .1 .7 .0 .3 .1),
+            ObjectSyntax::String(b"ge-0/0/1"),
+        ),
+        var(oid!(1.3.6 .1 .2 .1 .1 .3 .0), ObjectSyntax::TimeTicks(100)),
+    ]));
+    hints.push_pdu(&response(vec![var(
+        oid!(1.0.8802 .1 .1 .2 .1 .4 .1 .1 .9 .0 .3 .1),
+        ObjectSyntax::String(b"core-1"),
+    )]));
+    // cdpCacheAddress and cdpCacheDeviceId, for ifIndex 10 and device 2
+    hints.push_pdu(&response(vec![
+        var(
+            oid!(1.3.6 .1 .4 .1 .9 .9 .23 .1 .2 .1 .1 .4 .10 .2),
+            ObjectSyntax::String(b"\x0a\x00\x00\x01"),
+        ),
+        var(
+            oid!(1.3.6 .1 .4 .1 .9 .9 .23 .1 .2 .1 .1 .6 .10 .2),
+            ObjectSyntax::String(b"switch-2"),
+        ),
+    ]));
+    let neighbors: Vec<_> = hints.neighbors().collect();
+    assert_eq!(neighbors.len(), 2);
+    assert_eq!(neighbors[0].protocol, NeighborProtocol::Lldp);
+    assert_eq!(neighbors[0].local_port, 3);
+    assert_eq!(neighbors[0].port_id.as_deref(), Some(&b"ge-0/0/1"[..]));
+    assert_eq!(neighbors[0].system_name.as_deref(), Some("core-1"));
+    assert_eq!(neighbors[1].protocol, NeighborProtocol::Cdp);
+    assert_eq!(neighbors[1].local_port, 10);
+    assert_eq!(neighbors[1].address, Some(Ipv4Addr::new(10, 0, 0, 1)));
+    assert_eq!(neighbors[1].system_name.as_deref(), Some("switch-2"));
+}
+
+#[test]
+fn test_topology_fdb() {
+    let mut hints = TopologyHints::new();
+    hints.push_pdu(&response(vec![
+        // dot1dTpFdbPort
+        var(
+            oid!(1.3.6 .1 .2 .1 .17 .4 .3 .1 .2 .0 .27 .33 .1 .2 .3),
+            ObjectSyntax::Number(5),
+        ),
+        // dot1dBasePortIfIndex
+        var(
+            oid!(1.3.6 .1 .2 .1 .17 .1 .4 .1 .2 .5),
+            ObjectSyntax::Number(10105),
+        ),
+    ]));
+    assert_eq!(
+        hints.fdb(),
+        vec![FdbEntry {
+            mac: [0, 27, 33, 1, 2, 3],
+            port: 5
+        }]
+    );
+    assert_eq!(hints.port_if_index(5), Some(10105));
+    // other PDU types are ignored
+    let mut hints = TopologyHints::new();
+    let mut pdu = response(vec![var(
+        oid!(1.3.6 .1 .2 .1 .17 .1 .4 .1 .2 .5),
+        ObjectSyntax::Number(10105),
+    )]);
+    if let SnmpPdu::Generic(ref mut pdu) = pdu {
+        pdu.pdu_type = PduType::SetRequest;
+    }
+    hints.push_pdu(&pdu);
+    assert!(hints.is_empty());
+}
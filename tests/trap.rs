@@ -0,0 +1,209 @@
+// The storm detector requires the standard library
+#![cfg(all(feature = "std", feature = "v1"))]
+
+extern crate snmp_parser;
+
+use snmp_parser::trap::*;
+use snmp_parser::*;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::{Duration, Instant};
+
+static SNMPV1_TRAP_COLDSTART: &[u8] = include_bytes!("../assets/snmpv1_trap_coldstart.bin");
+
+#[derive(Default)]
+struct Events {
+    started: usize,
+    stopped: usize,
+}
+
+impl TrapStormListener for Events {
+    fn storm_started(&mut self, _key: &TrapKey, _count: usize) {
+        self.started += 1;
+    }
+
+    fn storm_stopped(&mut self, _key: &TrapKey) {
+        self.stopped += 1;
+    }
+}
+
+#[test]
+fn test_trap_key_v1() {
+    let (_, msg) = parse_snmp_v1(SNMPV1_TRAP_COLDSTART).expect("parsing failed");
+    let source = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let key = TrapKey::from_message(source, &msg).expect("no key");
+    assert_eq!(key.agent, IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)));
+    // coldStart, as sent by SNMPv2 agents
+    assert_eq!(key.trap_oid, oid!(1.3.6 .1 .6 .3 .1 .1 .5 .1));
+}
+
+#[test]
+fn test_trap_proxy_variables() {
+    use snmp_parser::coexistence::trap_v1_to_v2;
+    let (_, msg) = parse_snmp_v1(SNMPV1_TRAP_COLDSTART).expect("parsing failed");
+    let trap = match msg.pdu {
+        SnmpPdu::TrapV1(trap) => trap,
+        _ => panic!("unexpected PDU type"),
+    };
+    // a proxy forwarding the trap as SNMPv2 (RFC3584 section 3.1)
+    let mut pdu = SnmpPdu::Generic(trap_v1_to_v2(trap, 1).expect("translation failed"));
+    assert_eq!(pdu.snmp_trap_community(), None);
+    pdu.push_var(SnmpVariable {
+        oid: SNMP_TRAP_COMMUNITY_OID,
+        val: VarBindValue::Value(ObjectSyntax::String(b"public")),
+    });
+    assert_eq!(
+        pdu.snmp_trap_address(),
+        Some(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)))
+    );
+    assert_eq!(pdu.snmp_trap_community(), Some(&b"public"[..]));
+    let msg = SnmpMessage {
+        version: 1,
+        community: "proxy".to_string(),
+        pdu,
+    };
+    let proxy = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let key = TrapKey::from_message(proxy, &msg).expect("no key");
+    assert_eq!(key.agent, IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)));
+    assert_eq!(key.trap_oid, oid!(1.3.6 .1 .6 .3 .1 .1 .5 .1));
+    // without the proxy variables, the transport address is used
+    let pdu = inform_pdu(PduType::TrapV2, 1);
+    assert_eq!(pdu.snmp_trap_address(), None);
+    let msg = SnmpMessage { pdu, ..msg };
+    let key = TrapKey::from_message(proxy, &msg).expect("no key");
+    assert_eq!(key.agent, proxy);
+}
+
+#[test]
+fn test_trap_storm() {
+    let (_, msg) = parse_snmp_v1(SNMPV1_TRAP_COLDSTART).expect("parsing failed");
+    let source = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let config = TrapStormConfig {
+        window: Duration::from_secs(10),
+        start_threshold: 5,
+        stop_threshold: 1,
+    };
+    let mut detector = TrapStormDetector::with_listener(config, Events::default());
+    let t0 = Instant::now();
+    for i in 0..4 {
+        assert!(!detector.observe_message(source, &msg, t0 + Duration::from_millis(i * 100)));
+    }
+    assert!(detector.observe_message(source, &msg, t0 + Duration::from_millis(500)));
+    assert_eq!(detector.listener().started, 1);
+    assert_eq!(detector.storms().count(), 1);
+    // still in storm
+    detector.expire(t0 + Duration::from_secs(5));
+    assert_eq!(detector.listener().stopped, 0);
+    detector.expire(t0 + Duration::from_secs(20));
+    assert_eq!(detector.listener().stopped, 1);
+    assert_eq!(detector.storms().count(), 0);
+}
+
+#[test]
+fn test_trap_storm_max_keys() {
+    let config = TrapStormConfig {
+        window: Duration::from_secs(10),
+        start_threshold: 2,
+        stop_threshold: 0,
+    };
+    let mut detector = TrapStormDetector::with_max_keys(config, Events::default(), 2);
+    let key = |last: u8| TrapKey {
+        agent: IpAddr::V4(Ipv4Addr::new(10, 0, 0, last)),
+        trap_oid: oid!(1.3.6 .1 .6 .3 .1 .1 .5 .1),
+    };
+    let t0 = Instant::now();
+    detector.observe(key(1), t0);
+    assert!(detector.observe(key(1), t0));
+    detector.observe(key(2), t0);
+    // the least recently seen key is forgotten, and its storm ends
+    detector.observe(key(3), t0);
+    assert!(!detector.is_storm(&key(1)));
+    assert_eq!(detector.listener().stopped, 1);
+    assert!(!detector.observe(key(1), t0));
+}
+
+#[test]
+fn test_trap_semantic_eq() {
+    let (_, msg) = parse_snmp_v1(SNMPV1_TRAP_COLDSTART).expect("parsing failed");
+    let (_, mut dup) = parse_snmp_v1(SNMPV1_TRAP_COLDSTART).expect("parsing failed");
+    assert!(msg.semantic_eq(&dup));
+    if let SnmpPdu::TrapV1(ref mut pdu) = dup.pdu {
+        pdu.timestamp += 100;
+    }
+    assert!(!msg.semantic_eq(&dup));
+    assert!(msg.semantic_eq_ignore_uptime(&dup));
+    dup.community = "private".to_string();
+    assert!(!msg.semantic_eq_ignore_uptime(&dup));
+}
+
+fn inform_pdu(pdu_type: PduType, req_id: u32) -> SnmpPdu<'static> {
+    SnmpPdu::Generic(SnmpGenericPdu {
+        pdu_type,
+        req_id,
+        err: ErrorStatus::NoError,
+        err_index: 0,
+        var: vec![
+            SnmpVariable {
+                oid: oid!(1.3.6 .1 .2 .1 .1 .3 .0),
+                val: VarBindValue::Value(ObjectSyntax::TimeTicks(1234)),
+            },
+            SnmpVariable {
+                oid: SNMP_TRAP_OID,
+                val: VarBindValue::Value(ObjectSyntax::Object(oid!(1.3.6 .1 .6 .3 .1 .1 .5 .1))),
+            },
+        ],
+    })
+}
+
+#[test]
+fn test_inform_tracker() {
+    let manager = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let mut tracker = InformTracker::new(Duration::from_secs(5));
+    let t0 = Instant::now();
+    assert!(!tracker.observe_inform(manager, &inform_pdu(PduType::TrapV2, 1), t0));
+    assert!(tracker.observe_inform(manager, &inform_pdu(PduType::InformRequest, 1), t0));
+    assert!(tracker.observe_inform(manager, &inform_pdu(PduType::InformRequest, 2), t0));
+    let t1 = t0 + Duration::from_secs(3);
+    assert!(tracker.observe_inform(manager, &inform_pdu(PduType::InformRequest, 1), t1));
+    assert_eq!(tracker.len(), 2);
+    // a response from another address does not acknowledge the inform
+    let other = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+    let resp = inform_pdu(PduType::Response, 1);
+    assert_eq!(tracker.observe_response(other, &resp, t1), None);
+    let t2 = t0 + Duration::from_secs(6);
+    assert_eq!(
+        tracker.expire(t2),
+        vec![UnacknowledgedInform {
+            peer: manager,
+            req_id: 2,
+            trap_oid: Some(oid!(1.3.6 .1 .6 .3 .1 .1 .5 .1)),
+            retransmissions: 0,
+        }]
+    );
+    assert_eq!(
+        tracker.observe_response(manager, &resp, t2),
+        Some(InformAck {
+            delay: Duration::from_secs(6),
+            retransmissions: 1,
+            vars_match: true,
+        })
+    );
+    assert!(tracker.is_empty());
+}
+
+#[test]
+fn test_inform_tracker_limit() {
+    let manager = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let mut tracker = InformTracker::with_max_pending(Duration::from_secs(5), 2);
+    let t0 = Instant::now();
+    for req_id in 1..=3 {
+        let t = t0 + Duration::from_secs(u64::from(req_id));
+        assert!(tracker.observe_inform(manager, &inform_pdu(PduType::InformRequest, req_id), t));
+    }
+    // the oldest inform was dropped
+    assert_eq!(tracker.len(), 2);
+    let resp = inform_pdu(PduType::Response, 1);
+    assert_eq!(tracker.observe_response(manager, &resp, t0), None);
+    let expired = tracker.expire(t0 + Duration::from_secs(10));
+    let req_ids: Vec<_> = expired.iter().map(|inform| inform.req_id).collect();
+    assert_eq!(req_ids, vec![2, 3]);
+}
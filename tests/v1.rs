@@ -1,3 +1,5 @@
+#![cfg(feature = "v1")]
+
 #[macro_use]
 extern crate hex_literal;
 extern crate nom;
@@ -68,6 +70,20 @@ fn test_snmp_v1_req() {
     assert_eq!(r, expected);
 }
 
+#[test]
+fn test_snmp_v1_pdu_from_ber() {
+    use asn1_rs::FromBer;
+    // skip version and community, and add trailing data
+    let mut bytes = SNMPV1_REQ[13..].to_vec();
+    bytes.extend_from_slice(&[0xff, 0xff]);
+    let (rem, pdu) = SnmpPdu::from_ber(&bytes).expect("parsing failed");
+    assert_eq!(rem, &[0xff, 0xff]);
+    assert_eq!(pdu.pdu_type(), PduType::GetRequest);
+    assert_eq!(pdu.request_id(), Some(38));
+    let (_, msg) = parse_snmp_v1(SNMPV1_REQ).expect("parsing failed");
+    assert_eq!(pdu, msg.pdu);
+}
+
 static SNMPV1_TRAP_COLDSTART: &[u8] = include_bytes!("../assets/snmpv1_trap_coldstart.bin");
 
 #[test]
@@ -93,3 +109,28 @@ fn test_snmp_v1_trap_coldstart() {
         _ => panic!("unexpected pdu type"),
     }
 }
+
+#[test]
+fn test_snmp_v1_error_context() {
+    let mut bytes = SNMPV1_RESPONSE.to_vec();
+    // change the tag of the first value to IpAddress, with an invalid length
+    assert_eq!(bytes[50], 0x02);
+    bytes[50] = 0x40;
+    let err = match parse_snmp_v1(&bytes) {
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => e,
+        e => panic!("unexpected result: {:?}", e),
+    };
+    assert_eq!(err.field(), Some("pdu.varbinds.varbind[0].value"));
+    assert_eq!(err.offset(), Some(50));
+    assert!(matches!(
+        err.kind(),
+        snmp_parser::error::SnmpError::BerError(_)
+    ));
+    // same location with the generic parser
+    let err = match parse_snmp_generic_message(&bytes) {
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => e,
+        e => panic!("unexpected result: {:?}", e),
+    };
+    assert_eq!(err.field(), Some("pdu.varbinds.varbind[0].value"));
+    assert_eq!(err.offset(), Some(50));
+}
@@ -1,12 +1,17 @@
+#![cfg(all(feature = "v1", feature = "v2c"))]
+
 #[macro_use]
 extern crate pretty_assertions;
 extern crate nom;
 extern crate snmp_parser;
 
 use asn1_rs::Oid;
+use snmp_parser::error::SnmpError;
 use snmp_parser::*;
+use std::borrow::Cow;
 
 static SNMPV2_GET: &[u8] = include_bytes!("../assets/snmpv2c-get-response.bin");
+static SNMPV2_GET_100: &[u8] = include_bytes!("../assets/snmpv2c-get-response-100.bin");
 
 #[test]
 fn test_snmp_v2_get() {
@@ -51,3 +56,576 @@ fn test_snmp_v2_get() {
     assert!(rem.is_empty());
     assert_eq!(r, expected);
 }
+
+#[test]
+fn test_snmp_v2_get_100() {
+    let (rem, msg) = parse_snmp_v2c(SNMPV2_GET_100).expect("parsing failed");
+    assert!(rem.is_empty());
+    assert_eq!(msg.vars_iter().count(), 100);
+    let last = msg.vars_iter().last().expect("no varbind");
+    assert_eq!(last.oid.to_id_string(), "1.3.6.1.2.1.2.2.1.10.100");
+    assert_eq!(
+        last.val,
+        VarBindValue::Value(ObjectSyntax::Counter32(100000))
+    );
+}
+
+#[test]
+fn test_snmp_v2c_invalid_version() {
+    let bytes = include_bytes!("../assets/snmpv1_req.bin");
+    let err = match parse_snmp_v2c(bytes) {
+        Err(nom::Err::Error(e)) => e,
+        e => panic!("unexpected result: {:?}", e),
+    };
+    assert_eq!(
+        err,
+        snmp_parser::error::SnmpError::InvalidVersion { found: 0 }
+    );
+}
+
+#[test]
+fn test_snmp_error_code() {
+    use snmp_parser::error::{SnmpError, SnmpErrorCode};
+    let err = SnmpError::InvalidVersion { found: 0 };
+    assert_eq!(err.code(), SnmpErrorCode::InvalidVersion);
+    assert_eq!(err.code().0, 2);
+    assert_eq!(SnmpErrorCode(2).name(), Some("InvalidVersion"));
+    assert_eq!(
+        SnmpErrorCode::from_name("BerError"),
+        Some(SnmpErrorCode::BerError)
+    );
+    assert_eq!(SnmpErrorCode(1000).name(), None);
+}
+
+#[test]
+#[cfg(all(feature = "v2p", feature = "v2u", feature = "v3"))]
+fn test_snmp_error_version_display() {
+    let err = SnmpError::InvalidVersion { found: 5 };
+    assert_eq!(
+        err.to_string(),
+        "Invalid version 5: expected 0 (SNMPv1), 1 (SNMPv2c), \
+         2 (party-based SNMPv2 or SNMPv2u) or 3 (SNMPv3)"
+    );
+}
+
+#[test]
+fn test_snmp_error_io() {
+    use std::io;
+    let err = SnmpError::BerError(asn1_rs::Error::Incomplete(nom::Needed::new(4)));
+    let io_err = io::Error::from(err);
+    assert_eq!(io_err.kind(), io::ErrorKind::UnexpectedEof);
+    let io_err = io::Error::from(SnmpError::InvalidVersion { found: 5 });
+    assert_eq!(io_err.kind(), io::ErrorKind::InvalidData);
+    let inner = io_err.into_inner().expect("no inner error");
+    assert_eq!(
+        inner.downcast_ref::<SnmpError>(),
+        Some(&SnmpError::InvalidVersion { found: 5 })
+    );
+}
+
+#[test]
+fn test_generic_message_lengths() {
+    let (rem, msg) = parse_snmp_generic_message(SNMPV2_GET).expect("parsing failed");
+    assert!(rem.is_empty());
+    // indefinite length
+    let mut bytes = vec![0x30, 0x80];
+    bytes.extend_from_slice(&SNMPV2_GET[2..]);
+    bytes.extend_from_slice(&[0x00, 0x00, 0xff]);
+    let (rem, msg2) = parse_snmp_generic_message(&bytes).expect("parsing failed");
+    assert_eq!(rem, &[0xff]);
+    assert_eq!(msg2, msg);
+    // truncated message
+    let err = parse_snmp_generic_message(&SNMPV2_GET[..SNMPV2_GET.len() - 1]);
+    assert_eq!(err, Err(nom::Err::Error(SnmpError::InvalidMessage)));
+}
+
+#[test]
+fn test_snmp_alt() {
+    use nom::branch::alt;
+    // v1 parser returns a recoverable error, so v2c parser is tried
+    let (rem, msg) = alt((parse_snmp_v1, parse_snmp_v2c))(SNMPV2_GET).expect("parsing failed");
+    assert!(rem.is_empty());
+    assert_eq!(msg.version, 1);
+    // once the version is recognized, errors are failures
+    let mut bytes = SNMPV2_GET.to_vec();
+    bytes[5] = 0x30; // community tag
+    let res = alt((parse_snmp_v2c, parse_snmp_v1))(&bytes);
+    match res {
+        Err(nom::Err::Failure(e)) => assert_eq!(e.field(), Some("community")),
+        e => panic!("unexpected result: {:?}", e),
+    }
+}
+
+#[test]
+fn test_snmp_v2_var_list() {
+    let (_, mut msg) = parse_snmp_v2c(SNMPV2_GET).expect("parsing failed");
+    let var = msg
+        .pdu
+        .find_var(&oid!(1.3.6 .1 .2 .1 .25 .1 .5 .0))
+        .expect("variable not found");
+    assert_eq!(var.val, VarBindValue::Value(ObjectSyntax::Gauge32(3)));
+    assert!(msg.pdu.find_var(&oid!(1.3.6 .1 .2 .1 .25 .1 .5)).is_none());
+    let prefix = oid!(1.3.6 .1 .2 .1 .25 .1 .5);
+    assert_eq!(msg.pdu.find_under(&prefix).count(), 2);
+    assert!(!msg.pdu.has_duplicate_oids());
+    // add a duplicate, then sort
+    if let SnmpPdu::Generic(ref mut pdu) = msg.pdu {
+        pdu.var.push(SnmpVariable {
+            oid: oid!(1.3.6 .1 .2 .1 .25 .1 .5 .0),
+            val: VarBindValue::Unspecified,
+        });
+        pdu.var.swap(0, 2);
+    }
+    assert!(msg.pdu.has_duplicate_oids());
+    msg.pdu.sort_by_oid();
+    let oids: Vec<String> = msg.vars_iter().map(|v| v.oid.to_id_string()).collect();
+    assert_eq!(
+        oids,
+        [
+            "1.3.6.1.2.1.25.1.1.0",
+            "1.3.6.1.2.1.25.1.5.0",
+            "1.3.6.1.2.1.25.1.5.0",
+            "1.3.6.1.2.1.25.1.5.1"
+        ]
+    );
+    // stable sort: the original variable is first
+    let vars: Vec<_> = msg.vars_iter().collect();
+    assert_eq!(vars[1].val, VarBindValue::Value(ObjectSyntax::Gauge32(3)));
+}
+
+#[test]
+fn test_snmp_v2_asn1_traits() {
+    use asn1_rs::{DynTagged, FromBer, FromDer, Tag, Tagged};
+    let (_, msg) = parse_snmp_v2c(SNMPV2_GET).expect("parsing failed");
+    assert_eq!(SnmpMessage::TAG, Tag::Sequence);
+    assert_eq!(msg.pdu.tag(), Tag(2));
+    let vars: Vec<_> = msg.vars_iter().map(|v| v.val.tag()).collect();
+    assert_eq!(vars, [Tag(3), Tag(2), Tag(1)]);
+    // sysUpTime.0 = 5
+    let varbind = [
+        0x30, 0x0d, 0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x03, 0x00, 0x43, 0x01, 0x05,
+    ];
+    let (rem, var) = SnmpVariable::from_der(&varbind).expect("parsing failed");
+    assert!(rem.is_empty());
+    assert_eq!(var.val, VarBindValue::Value(ObjectSyntax::TimeTicks(5)));
+    // the value is not minimally encoded: valid BER, but not DER
+    let varbind = [
+        0x30, 0x0e, 0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x03, 0x00, 0x43, 0x02, 0x00,
+        0x05,
+    ];
+    assert!(SnmpVariable::from_ber(&varbind).is_ok());
+    assert!(SnmpVariable::from_der(&varbind).is_err());
+}
+
+#[test]
+fn test_snmp_v2_semantic_eq() {
+    let (_, msg) = parse_snmp_v2c(SNMPV2_GET).expect("parsing failed");
+    let (_, mut retry) = parse_snmp_v2c(SNMPV2_GET).expect("parsing failed");
+    if let SnmpPdu::Generic(ref mut pdu) = retry.pdu {
+        pdu.req_id += 1;
+    }
+    assert_ne!(msg, retry);
+    assert!(msg.semantic_eq(&retry));
+    if let SnmpPdu::Generic(ref mut pdu) = retry.pdu {
+        pdu.var.push(SnmpVariable {
+            oid: oid!(1.3.6 .1 .2 .1 .1 .3 .0),
+            val: VarBindValue::Value(ObjectSyntax::TimeTicks(5)),
+        });
+    }
+    assert!(!msg.semantic_eq(&retry));
+}
+
+#[test]
+fn test_snmp_v2_parse_result() {
+    let mut bytes = SNMPV2_GET.to_vec();
+    bytes.extend_from_slice(&[0, 0]);
+    let (msg, len) = SnmpMessage::parse(&bytes).expect("parsing failed");
+    assert_eq!(len, SNMPV2_GET.len());
+    assert_eq!(msg.version, 1);
+    let (msg, len) = SnmpGenericMessage::parse(SNMPV2_GET).expect("parsing failed");
+    assert_eq!(len, SNMPV2_GET.len());
+    assert_eq!(msg.version(), 1);
+    // truncated message
+    let res = SnmpMessage::parse(&SNMPV2_GET[..SNMPV2_GET.len() - 1]);
+    assert_eq!(
+        res.map(|_| ()).unwrap_err().kind(),
+        &SnmpError::InvalidMessage
+    );
+    // SNMPv3 is not a community-based version
+    let v3 = include_bytes!("../assets/snmpv3_req.bin");
+    match SnmpMessage::parse(v3) {
+        Err(SnmpError::InvalidVersion { found: 3 }) => (),
+        e => panic!("unexpected result: {:?}", e),
+    }
+}
+
+#[test]
+fn test_error_status_severity() {
+    assert!(!ErrorStatus::NoError.is_error());
+    assert!(ErrorStatus::TooBig.is_error());
+    assert!(ErrorStatus::NoSuchName.is_v1_only());
+    assert!(!ErrorStatus::GenErr.is_v1_only());
+    assert!(!ErrorStatus::GenErr.is_v2_only());
+    assert!(ErrorStatus::InconsistentName.is_v2_only());
+    assert!(!ErrorStatus(19).is_v2_only());
+    let severities: Vec<_> = [0, 1, 2, 5, 6, 13, 15, 18, 19]
+        .iter()
+        .map(|&n| ErrorStatus(n).severity())
+        .collect();
+    assert_eq!(
+        severities,
+        [
+            ErrorSeverity::None,
+            ErrorSeverity::Transient,
+            ErrorSeverity::Request,
+            ErrorSeverity::Agent,
+            ErrorSeverity::Request,
+            ErrorSeverity::Transient,
+            ErrorSeverity::Agent,
+            ErrorSeverity::Request,
+            ErrorSeverity::Unknown,
+        ]
+    );
+}
+
+#[test]
+fn test_community_policy() {
+    let (_, msg) = parse_snmp_v2c(SNMPV2_GET).expect("parsing failed");
+    let policy = CommunityPolicy::default();
+    assert_eq!(msg.check_community(&policy), None);
+    assert_eq!(policy.check(b""), Some(CommunityViolation::Empty));
+    assert_eq!(
+        policy.check(&[b'a'; 33]),
+        Some(CommunityViolation::TooLong { len: 33 })
+    );
+    let policy = CommunityPolicy {
+        min_len: 8,
+        max_len: 16,
+    };
+    assert_eq!(
+        msg.check_community(&policy),
+        Some(CommunityViolation::TooShort { len: 6 })
+    );
+    let policy = CommunityPolicy {
+        min_len: 0,
+        ..policy
+    };
+    assert_eq!(policy.check(b""), None);
+}
+
+#[test]
+fn test_varbind_value_exceptions() {
+    let val = VarBindValue::Value(ObjectSyntax::TimeTicks(1234));
+    assert_eq!(val.value(), Some(&ObjectSyntax::TimeTicks(1234)));
+    assert!(!val.is_exception());
+    assert_eq!(val.exception_kind(), None);
+    assert_eq!(VarBindValue::Unspecified.value(), None);
+    assert!(!VarBindValue::Unspecified.is_exception());
+    let val = VarBindValue::EndOfMibView;
+    assert_eq!(val.value(), None);
+    assert!(val.is_exception());
+    assert_eq!(val.exception_kind(), Some(VarBindException::EndOfMibView));
+    assert_eq!(
+        VarBindValue::NoSuchInstance.exception_kind(),
+        Some(VarBindException::NoSuchInstance)
+    );
+}
+
+#[test]
+fn test_community_matches() {
+    let (_, msg) = parse_snmp_v2c(SNMPV2_GET).expect("parsing failed");
+    assert!(msg.community_matches(b"public", CommunityCase::Sensitive));
+    assert!(!msg.community_matches(b"Public", CommunityCase::Sensitive));
+    assert!(msg.community_matches(b"PuBLiC", CommunityCase::AsciiInsensitive));
+    assert!(!msg.community_matches(b"publi", CommunityCase::Sensitive));
+    assert!(!msg.community_matches(b"public\0", CommunityCase::Sensitive));
+    assert!(!msg.community_matches(b"", CommunityCase::AsciiInsensitive));
+    // only letters are folded
+    assert!(!msg.community_matches(b"p\x15blic", CommunityCase::AsciiInsensitive));
+}
+
+#[test]
+fn test_fmt_compact() {
+    let (_, msg) = parse_snmp_v2c(SNMPV2_GET).expect("parsing failed");
+    assert_eq!(
+        msg.fmt_compact(2, false).to_string(),
+        "version=1 community=\"public\" pdu=Response req_id=97083662 vars=3 \
+         [1.3.6.1.2.1.25.1.1.0=970069, 1.3.6.1.2.1.25.1.5.0=3, ... 1 more]"
+    );
+    let (_, msg) = parse_snmp_v2c(SNMPV2_GET_100).expect("parsing failed");
+    assert_eq!(
+        msg.fmt_compact(0, true).to_string(),
+        "version=1 community=*** pdu=Response req_id=1 vars=100 [... 100 more]"
+    );
+    let msg = SnmpGenericMessage::V2(msg);
+    assert!(msg
+        .fmt_compact(1, true)
+        .to_string()
+        .ends_with("[1.3.6.1.2.1.2.2.1.10.1=1000, ... 99 more]"));
+}
+
+#[test]
+fn test_oid_limits() {
+    let (_, msg) = parse_snmp_v2c(SNMPV2_GET).expect("parsing failed");
+    let limits = OidLimits::default();
+    assert_eq!(msg.pdu.check_oid_limits(&limits), None);
+    assert_eq!(limits.check(&oid!(1.3.6 .1 .4 .1 .4294967295)), None);
+    assert_eq!(
+        limits.check(&oid!(1.3.6 .1 .4 .1 .4294967296)),
+        Some(OidViolation::ArcTooLarge { index: 6 })
+    );
+    // arc which does not fit in a u64
+    let oid = Oid::new(Cow::Borrowed(&[
+        0x2b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f,
+    ]));
+    assert_eq!(
+        limits.check(&oid),
+        Some(OidViolation::ArcTooLarge { index: 2 })
+    );
+    let arcs: Vec<u64> = (0..200).collect();
+    let oid = Oid::from(&arcs).expect("invalid OID");
+    assert_eq!(
+        limits.check(&oid),
+        Some(OidViolation::TooManyArcs { arcs: 200 })
+    );
+    let limits = OidLimits {
+        max_arcs: 4,
+        ..limits
+    };
+    assert_eq!(
+        msg.pdu.check_oid_limits(&limits),
+        Some((0, OidViolation::TooManyArcs { arcs: 10 }))
+    );
+}
+
+/// Encode a BER TLV, with a definite length
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut v = vec![tag];
+    if content.len() < 0x80 {
+        v.push(content.len() as u8);
+    } else {
+        v.extend_from_slice(&[0x82, (content.len() >> 8) as u8, content.len() as u8]);
+    }
+    v.extend_from_slice(content);
+    v
+}
+
+/// Build a SNMPv2c response with a single variable binding
+fn v2c_response_with_value(value: &[u8]) -> Vec<u8> {
+    let mut var = tlv(0x06, &[0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x05, 0x00]);
+    var.extend_from_slice(value);
+    let mut pdu = vec![0x02, 0x01, 0x01, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00];
+    pdu.extend(tlv(0x30, &tlv(0x30, &var)));
+    let mut msg = vec![0x02, 0x01, 0x01];
+    msg.extend(tlv(0x04, b"public"));
+    msg.extend(tlv(0xa2, &pdu));
+    tlv(0x30, &msg)
+}
+
+#[test]
+fn test_max_depth() {
+    fn nested(depth: usize) -> Vec<u8> {
+        (0..depth).fold(vec![0x05, 0x00], |v, _| tlv(0x30, &v))
+    }
+    let bytes = v2c_response_with_value(&nested(4));
+    let (_, msg) = parse_snmp_v2c(&bytes).expect("parsing failed");
+    assert!(matches!(
+        msg.vars_iter().next().map(|v| &v.val),
+        Some(VarBindValue::Value(ObjectSyntax::UnknownSimple(_)))
+    ));
+    let bytes = v2c_response_with_value(&nested(40));
+    let err = parse_snmp_v2c(&bytes).expect_err("parsing succeeded");
+    assert!(matches!(err, nom::Err::Failure(ref e) if e.kind() == &SnmpError::MaxDepthExceeded));
+    // indefinite lengths
+    let mut value = [0x30, 0x80].repeat(40);
+    value.extend_from_slice(&[0x00, 0x00].repeat(40));
+    let bytes = v2c_response_with_value(&value);
+    let err = parse_snmp_v2c(&bytes).expect_err("parsing succeeded");
+    assert!(matches!(err, nom::Err::Failure(ref e) if e.kind() == &SnmpError::MaxDepthExceeded));
+    // BER-encoded content of Opaque values
+    let bytes = v2c_response_with_value(&(0..40).fold(vec![0x05, 0x00], |v, _| tlv(0x44, &v)));
+    let err = parse_snmp_v2c(&bytes).expect_err("parsing succeeded");
+    assert!(matches!(err, nom::Err::Failure(ref e) if e.kind() == &SnmpError::MaxDepthExceeded));
+    // Opaque values which are not BER-encoded are accepted
+    let bytes = v2c_response_with_value(&tlv(0x44, &[0xff; 8]));
+    parse_snmp_v2c(&bytes).expect("parsing failed");
+}
+
+#[test]
+fn test_unknown_application_tag() {
+    use snmp_parser::decoders::ApplicationDecoders;
+    let bytes = v2c_response_with_value(&tlv(0x5e, &[0x00, 0xd7]));
+    let (_, msg) = parse_snmp_v2c(&bytes).expect("parsing failed");
+    let value = match msg.vars_iter().next().map(|v| &v.val) {
+        Some(VarBindValue::Value(value)) => value,
+        v => panic!("unexpected value {:?}", v),
+    };
+    let info = value.unknown_tag_info().expect("not an unknown value");
+    assert_eq!(info.class, asn1_rs::Class::Application);
+    assert_eq!(info.tag, asn1_rs::Tag(30));
+    assert!(!info.constructed);
+    assert_eq!(info.data, &[0x00, 0xd7]);
+    assert_eq!(ObjectSyntax::Counter32(3).unknown_tag_info(), None);
+
+    let mut decoders = ApplicationDecoders::new();
+    assert!(decoders.is_empty());
+    decoders.register(30, |data| Ok(u16::from_be_bytes([data[0], data[1]])));
+    assert_eq!(decoders.decode(value), Some(Ok(215)));
+    assert_eq!(decoders.decode(&ObjectSyntax::Counter32(3)), None);
+    let bytes = v2c_response_with_value(&tlv(0x5d, &[0x00]));
+    let (_, msg) = parse_snmp_v2c(&bytes).expect("parsing failed");
+    match msg.vars_iter().next().map(|v| &v.val) {
+        Some(VarBindValue::Value(value)) => assert_eq!(decoders.decode(value), None),
+        v => panic!("unexpected value {:?}", v),
+    }
+}
+
+#[test]
+fn test_parser_config_custom_value() {
+    use snmp_parser::config::*;
+    use snmp_parser::encode::SnmpEncode;
+    fn decode_u16(data: &[u8]) -> Result<u16, SnmpError> {
+        match data {
+            &[hi, lo] => Ok(u16::from_be_bytes([hi, lo])),
+            _ => Err(SnmpError::InvalidPdu),
+        }
+    }
+    let mut config = ParserConfig::new();
+    config.register_application(30, decode_u16);
+    let bytes = v2c_response_with_value(&tlv(0x5e, &[0x00, 0xd7]));
+    let (_, msg) = parse_snmp_generic_message_with_config(&bytes, &config).expect("parsing failed");
+    let var = msg
+        .pdu()
+        .and_then(|pdu| pdu.vars_iter().next())
+        .expect("no variable");
+    match var.val {
+        VarBindValue::Value(ObjectSyntax::Custom(ref value)) => {
+            assert_eq!(value.downcast_ref::<u16>(), Some(&215));
+            assert_eq!(value.downcast_ref::<u32>(), None);
+            assert_eq!(value.raw.data, &[0x00, 0xd7]);
+        }
+        ref val => panic!("unexpected value {:?}", val),
+    }
+    assert_eq!(msg.encode(), bytes);
+    // other tags are not modified
+    let (_, msg) = parse_snmp_generic_message(&bytes).expect("parsing failed");
+    assert!(matches!(
+        msg.pdu()
+            .and_then(|pdu| pdu.vars_iter().next())
+            .map(|v| &v.val),
+        Some(VarBindValue::Value(ObjectSyntax::UnknownApplication(_)))
+    ));
+    // decoding errors are located
+    let bytes = v2c_response_with_value(&tlv(0x5e, &[0x00]));
+    let err = match parse_snmp_generic_message_with_config(&bytes, &config) {
+        Err(nom::Err::Failure(e)) => e,
+        res => panic!("unexpected result {:?}", res),
+    };
+    assert_eq!(err.kind(), &SnmpError::InvalidPdu);
+    assert_eq!(err.field(), Some("varbind[0].value"));
+    assert_eq!(err.offset(), Some(bytes.len() - 1));
+}
+
+#[test]
+fn test_real_enumerated_values() {
+    use snmp_parser::encode::SnmpEncode;
+    let value_of = |bytes: &[u8]| -> ObjectSyntax<'static> {
+        let bytes = v2c_response_with_value(bytes);
+        let (_, msg) = parse_snmp_v2c(&bytes).expect("parsing failed");
+        match msg.vars_iter().next().map(|v| &v.val) {
+            Some(VarBindValue::Value(ObjectSyntax::Real(f))) => ObjectSyntax::Real(*f),
+            Some(VarBindValue::Value(ObjectSyntax::Enumerated(n))) => ObjectSyntax::Enumerated(*n),
+            Some(VarBindValue::Value(ObjectSyntax::UnknownSimple(_))) => ObjectSyntax::Empty,
+            v => panic!("unexpected value {:?}", v),
+        }
+    };
+    assert_eq!(value_of(&tlv(0x0a, &[0x03])), ObjectSyntax::Enumerated(3));
+    assert_eq!(value_of(&tlv(0x0a, &[0xff])), ObjectSyntax::Enumerated(-1));
+    // decimal encoding (NR3)
+    assert_eq!(value_of(&tlv(0x09, b"\x0315E-1")), ObjectSyntax::Real(1.5));
+    assert_eq!(value_of(&tlv(0x09, &[])), ObjectSyntax::Real(0.0));
+    assert_eq!(
+        value_of(&tlv(0x09, &[0x41])),
+        ObjectSyntax::Real(f64::NEG_INFINITY)
+    );
+    match value_of(&tlv(0x09, &[0x42])) {
+        ObjectSyntax::Real(f) => assert!(f.is_nan()),
+        v => panic!("unexpected value {:?}", v),
+    }
+    // invalid REAL values are kept as unknown values
+    assert_eq!(value_of(&tlv(0x09, &[0x80])), ObjectSyntax::Empty);
+    // binary encoding
+    for f in [
+        1.0,
+        -2.5,
+        0.1,
+        1e300,
+        -1e-300,
+        -5e-324,
+        f64::MAX,
+        -0.0,
+        f64::INFINITY,
+    ] {
+        let bytes = ObjectSyntax::Real(f).encode();
+        match value_of(&bytes) {
+            ObjectSyntax::Real(g) => assert_eq!(g.to_bits(), f.to_bits(), "value {}", f),
+            v => panic!("unexpected value {:?}", v),
+        }
+    }
+    // base 2, odd mantissa
+    assert_eq!(
+        ObjectSyntax::Real(-2.5).encode(),
+        &[0x09, 0x03, 0xc0, 0xff, 0x05]
+    );
+    assert_eq!(ObjectSyntax::Enumerated(3).encode(), &[0x0a, 0x01, 0x03]);
+}
+
+#[test]
+fn test_real_extreme_exponents() {
+    let value_of = |content: &[u8]| -> Option<f64> {
+        let bytes = v2c_response_with_value(&tlv(0x09, content));
+        let (_, msg) = parse_snmp_v2c(&bytes).expect("parsing failed");
+        match msg.vars_iter().next().map(|v| &v.val) {
+            Some(VarBindValue::Value(ObjectSyntax::Real(f))) => Some(*f),
+            Some(VarBindValue::Value(ObjectSyntax::UnknownSimple(_))) => None,
+            v => panic!("unexpected value {:?}", v),
+        }
+    };
+    // 4-byte exponents, in bases 2, 8 and 16
+    for base in [0x00, 0x10, 0x20] {
+        let first = 0x83 | base;
+        assert_eq!(
+            value_of(&[first, 0x04, 0x7f, 0xff, 0xff, 0xff, 0x01]),
+            Some(f64::INFINITY)
+        );
+        assert_eq!(
+            value_of(&[first | 0x40, 0x04, 0x7f, 0xff, 0xff, 0xff, 0x01]),
+            Some(f64::NEG_INFINITY)
+        );
+        assert_eq!(
+            value_of(&[first, 0x04, 0x80, 0x00, 0x00, 0x00, 0x01]),
+            Some(0.0)
+        );
+    }
+    // 3-byte exponents (encoded length), one past the largest finite power of 2 in base 16
+    assert_eq!(
+        value_of(&[0xa2, 0x00, 0x01, 0x00, 0x01]),
+        Some(f64::INFINITY)
+    );
+    // 2^-1024 is subnormal
+    assert_eq!(
+        value_of(&[0xa2, 0xff, 0xff, 0x00, 0x01]),
+        Some(f64::from_bits(1 << 50))
+    );
+    // largest mantissa, with the sign bit set
+    assert_eq!(
+        value_of(&[0xc0, 0x00, 0x80, 0, 0, 0, 0, 0, 0, 0]),
+        Some(-(2f64.powi(63)))
+    );
+    // exponents larger than 32 bits, and invalid bases
+    assert_eq!(
+        value_of(&[0x83, 0x05, 0x7f, 0xff, 0xff, 0xff, 0xff, 0x01]),
+        None
+    );
+    assert_eq!(value_of(&[0xb0, 0x00, 0x01]), None);
+}
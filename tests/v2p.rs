@@ -0,0 +1,72 @@
+#![cfg(feature = "v2p")]
+
+#[macro_use]
+extern crate hex_literal;
+extern crate snmp_parser;
+
+use snmp_parser::encode::SnmpEncode;
+use snmp_parser::error::SnmpError;
+use snmp_parser::*;
+
+// GetRequest for sysDescr.0, using noAuth and noPriv
+static SNMPV2P_GET: &[u8] = &hex!(
+    "30 64 02 01 02 a1 5f 06 0d 2b 06 01 06 03 03 01 03 7f 00 00 01 01 81 4e
+     a1 4c 04 00 a2 48
+     06 0d 2b 06 01 06 03 03 01 03 7f 00 00 01 01
+     06 0d 2b 06 01 06 03 03 01 03 7f 00 00 01 02
+     06 0d 2b 06 01 06 03 03 01 04 7f 00 00 01 01
+     a0 19 02 01 01 02 01 00 02 01 00 30 0e 30 0c 06 08 2b 06 01 02 01 01 01 00 05 00"
+);
+
+static SNMPV2P_ENCRYPTED: &[u8] = &hex!(
+    "30 26 02 01 02 a1 21 06 0d 2b 06 01 06 03 03 01 03 7f 00 00 01 01
+     81 10 10 11 12 13 14 15 16 17 18 19 1a 1b 1c 1d 1e 1f"
+);
+
+#[test]
+fn test_snmp_v2p_get() {
+    let (rem, msg) = parse_snmp_v2p(SNMPV2P_GET).expect("parsing failed");
+    assert!(rem.is_empty());
+    assert_eq!(msg.version, 2);
+    assert_eq!(msg.priv_dst.to_id_string(), "1.3.6.1.6.3.3.1.3.127.0.0.1.1");
+    let auth = msg.auth_msg.as_ref().expect("no auth msg");
+    assert_eq!(auth.auth_info.data, b"");
+    assert_eq!(auth.dst_party, msg.priv_dst);
+    assert_eq!(
+        auth.src_party.to_id_string(),
+        "1.3.6.1.6.3.3.1.3.127.0.0.1.2"
+    );
+    assert_eq!(auth.context.to_id_string(), "1.3.6.1.6.3.3.1.4.127.0.0.1.1");
+    assert_eq!(auth.pdu.pdu_type(), PduType::GetRequest);
+    let var = auth.pdu.vars_iter().next().expect("no varbind");
+    assert_eq!(var.oid.to_id_string(), "1.3.6.1.2.1.1.1.0");
+    assert_eq!(msg.encode(), SNMPV2P_GET);
+}
+
+#[test]
+fn test_snmp_v2p_encrypted() {
+    let (_, msg) = parse_snmp_v2p(SNMPV2P_ENCRYPTED).expect("parsing failed");
+    assert_eq!(msg.auth_msg, None);
+    assert_eq!(msg.priv_data.len(), 16);
+    assert_eq!(msg.encode(), SNMPV2P_ENCRYPTED);
+}
+
+#[test]
+fn test_snmp_v2p_generic() {
+    let (_, msg) = parse_snmp_generic_message(SNMPV2P_GET).expect("parsing failed");
+    assert!(matches!(msg, SnmpGenericMessage::V2p(_)));
+    assert_eq!(msg.version(), 2);
+    assert_eq!(msg.community(), None);
+    assert_eq!(msg.pdu().map(|pdu| pdu.request_id()), Some(Some(1)));
+    let (_, msg) = parse_snmp_generic_message(SNMPV2P_ENCRYPTED).expect("parsing failed");
+    assert!(msg.pdu().is_none());
+}
+
+#[test]
+fn test_snmp_v2p_wrong_version() {
+    static SNMPV1_REQ: &[u8] = include_bytes!("../assets/snmpv1_req.bin");
+    assert_eq!(
+        parse_snmp_v2p(SNMPV1_REQ),
+        Err(nom::Err::Error(SnmpError::InvalidVersion { found: 0 }))
+    );
+}
@@ -0,0 +1,106 @@
+#![cfg(feature = "v2u")]
+
+#[macro_use]
+extern crate hex_literal;
+extern crate snmp_parser;
+
+use snmp_parser::encode::SnmpEncode;
+use snmp_parser::error::SnmpError;
+use snmp_parser::*;
+
+// GetRequest for sysDescr.0, with user "user" and no authentication
+static SNMPV2U_GET: &[u8] = &hex!(
+    "30 3e 02 01 02
+     04 1e 01 04 00 01 02 03 04 05 06 07 08 09 0a 0b 00 00 00 01 00 00 00 64 05 dc
+     04 75 73 65 72 00
+     a0 19 02 01 01 02 01 00 02 01 00 30 0e 30 0c 06 08 2b 06 01 02 01 01 01 00 05 00"
+);
+
+// authenticated and encrypted, with context "ctx"
+static SNMPV2U_ENCRYPTED: &[u8] = &hex!(
+    "30 40 02 01 02
+     04 31 01 03 00 01 02 03 04 05 06 07 08 09 0a 0b 00 00 00 01 00 00 00 64 05 dc
+     04 75 73 65 72 10 aa aa aa aa aa aa aa aa aa aa aa aa aa aa aa aa 63 74 78
+     04 08 00 01 02 03 04 05 06 07"
+);
+
+#[test]
+fn test_snmp_v2u_get() {
+    let (rem, msg) = parse_snmp_v2u(SNMPV2U_GET).expect("parsing failed");
+    assert!(rem.is_empty());
+    assert_eq!(msg.version, 2);
+    let params = &msg.parameters;
+    assert_eq!(params.model, 1);
+    assert!(params.is_reportable());
+    assert!(!params.is_authenticated());
+    assert_eq!(params.agent_boots, 1);
+    assert_eq!(params.agent_time, 100);
+    assert_eq!(params.max_size, 1500);
+    assert_eq!(params.user_name, b"user");
+    assert_eq!(params.context_selector, b"");
+    let pdu = msg.pdu().expect("no PDU");
+    assert_eq!(pdu.pdu_type(), PduType::GetRequest);
+    assert_eq!(msg.encode(), SNMPV2U_GET);
+}
+
+#[test]
+fn test_snmp_v2u_encrypted() {
+    let (_, msg) = parse_snmp_v2u(SNMPV2U_ENCRYPTED).expect("parsing failed");
+    assert!(msg.parameters.is_encrypted());
+    assert_eq!(msg.parameters.auth_digest, &[0xaa; 16]);
+    assert_eq!(msg.parameters.context_selector, b"ctx");
+    assert_eq!(
+        msg.data,
+        SnmpV2uData::Encrypted(&hex!("00 01 02 03 04 05 06 07"))
+    );
+    assert_eq!(msg.encode(), SNMPV2U_ENCRYPTED);
+}
+
+#[test]
+fn test_snmp_v2u_encode_long_fields() {
+    let (_, mut msg) = parse_snmp_v2u(SNMPV2U_GET).expect("parsing failed");
+    let long = [b'u'; 256];
+    msg.parameters.user_name = &long;
+    let err = msg.encode_checked().unwrap_err();
+    assert_eq!(err.field, "userName");
+    assert_eq!(err.len, 256);
+    assert_eq!(err.max, 255);
+    // the unchecked encoding truncates the field, so that it can be read back
+    let bytes = msg.encode();
+    assert_eq!(bytes.len(), msg.encoded_len());
+    let (_, msg2) = parse_snmp_v2u(&bytes).expect("parsing failed");
+    assert_eq!(msg2.parameters.user_name, &long[..255]);
+    msg.parameters.user_name = b"user";
+    msg.parameters.auth_digest = &long;
+    assert_eq!(msg.encode_checked().unwrap_err().field, "authDigest");
+    msg.parameters.auth_digest = &[];
+    assert_eq!(msg.encode_checked().expect("encoding failed"), SNMPV2U_GET);
+}
+
+#[test]
+fn test_snmp_v2u_generic() {
+    let (_, msg) = parse_snmp_generic_message(SNMPV2U_GET).expect("parsing failed");
+    assert!(matches!(msg, SnmpGenericMessage::V2u(_)));
+    assert_eq!(msg.version(), 2);
+    assert_eq!(msg.pdu().and_then(|pdu| pdu.request_id()), Some(1));
+}
+
+#[test]
+fn test_snmp_v2u_invalid_model() {
+    let mut data = SNMPV2U_GET.to_vec();
+    data[7] = 2;
+    let err = match parse_snmp_v2u(&data) {
+        Err(nom::Err::Failure(e)) => e,
+        r => panic!("unexpected result {:?}", r),
+    };
+    assert_eq!(err.kind(), &SnmpError::InvalidSecurityModel);
+    assert_eq!(err.field(), Some("parameters"));
+}
+
+#[test]
+fn test_snmp_v2u_rules() {
+    use snmp_parser::rules::Rule;
+    let (_, msg) = parse_snmp_generic_message(SNMPV2U_GET).expect("parsing failed");
+    let rule = Rule::parse("version == 2 && user == user").expect("invalid rule");
+    assert!(rule.matches(&msg));
+}
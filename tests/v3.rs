@@ -1,9 +1,12 @@
+#![cfg(feature = "v3")]
+
 #[macro_use]
 extern crate pretty_assertions;
 
 extern crate nom;
 extern crate snmp_parser;
 
+use snmp_parser::error::SnmpError;
 use snmp_parser::*;
 
 static SNMPV3_REQ: &[u8] = include_bytes!("../assets/snmpv3_req.bin");
@@ -38,6 +41,10 @@ fn test_snmp_v3_req() {
             msg_security_model: SecurityModel::USM,
         },
         security_params: sp,
+        security_params_raw: &[
+            0x30, 0x0e, 0x04, 0x00, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00, 0x04, 0x00, 0x04, 0x00,
+            0x04, 0x00,
+        ],
         data: ScopedPduData::Plaintext(ScopedPdu {
             ctx_engine_id: &cei,
             ctx_engine_name: b"",
@@ -50,6 +57,27 @@ fn test_snmp_v3_req() {
     assert_eq!(res, expected);
 }
 
+#[test]
+fn test_snmp_v3_scoped_pdu_from_ber() {
+    use asn1_rs::FromBer;
+    // the scoped PDU is the last field of the message
+    let bytes = &SNMPV3_REQ[SNMPV3_REQ.len() - 35..];
+    let (rem, scoped) = ScopedPdu::from_ber(bytes).expect("parsing failed");
+    assert!(rem.is_empty());
+    assert_eq!(scoped.ctx_engine_name, b"");
+    assert_eq!(scoped.data.request_id(), Some(2098071598));
+    // errors are relative to the start of the scoped PDU
+    let mut bytes = bytes.to_vec();
+    bytes[19] = 0xaf;
+    let err = match ScopedPdu::from_ber(&bytes) {
+        Err(nom::Err::Error(e)) => e,
+        r => panic!("unexpected result {:?}", r),
+    };
+    assert_eq!(err.kind(), &SnmpError::InvalidPduType { tag: 15 });
+    assert_eq!(err.field(), Some("pdu"));
+    assert_eq!(err.offset(), Some(19));
+}
+
 #[test]
 fn test_snmp_v3_req_encrypted() {
     let bytes = include_bytes!("../assets/snmpv3_req_encrypted.bin");
@@ -84,3 +112,413 @@ fn test_snmp_v3_generic() {
         panic!("unexpected PDU type");
     }
 }
+
+#[test]
+fn test_snmp_v3_privacy_mismatch() {
+    let (_, msg) = parse_snmp_v3(SNMPV3_REQ).expect("parsing failed");
+    assert!(!msg.has_privacy_mismatch());
+    // plaintext data, with the privacy flag set
+    let mut bytes = SNMPV3_REQ.to_vec();
+    assert_eq!(bytes[20], 0x04);
+    bytes[20] = 0x06;
+    let (_, msg) = parse_snmp_v3(&bytes).expect("parsing failed");
+    assert_eq!(msg.data.wire_form(), ScopedPduDataForm::Sequence);
+    assert!(msg.header_data.is_encrypted());
+    assert!(msg.has_privacy_mismatch());
+    // encrypted data, without the privacy flag
+    let mut bytes = include_bytes!("../assets/snmpv3_req_encrypted.bin").to_vec();
+    assert_eq!(bytes[21], 0x07);
+    bytes[21] = 0x05;
+    let (_, msg) = parse_snmp_v3(&bytes).expect("parsing failed");
+    assert_eq!(msg.data.wire_form(), ScopedPduDataForm::OctetString);
+    assert!(!msg.header_data.is_encrypted());
+    assert!(msg.has_privacy_mismatch());
+}
+
+#[test]
+fn test_snmp_v3_parse_decrypted() {
+    let bytes = include_bytes!("../assets/snmpv3_req_encrypted.bin");
+    let (_, msg) = parse_snmp_v3(bytes).expect("parsing failed");
+    // use the scoped PDU of another message as plaintext, with padding
+    let mut plaintext = SNMPV3_REQ[SNMPV3_REQ.len() - 35..].to_vec();
+    plaintext.extend_from_slice(&[0, 0, 0, 0, 0]);
+    let decrypted = msg.parse_decrypted(&plaintext).expect("parsing failed");
+    assert_eq!(decrypted.ciphertext.len(), 96);
+    assert_eq!(decrypted.ciphertext, &bytes[bytes.len() - 96..]);
+    assert_eq!(decrypted.scoped_pdu.data.request_id(), Some(2098071598));
+    // not an encrypted message
+    let (_, msg) = parse_snmp_v3(SNMPV3_REQ).expect("parsing failed");
+    assert_eq!(
+        msg.parse_decrypted(&plaintext),
+        Err(SnmpError::InvalidScopedPduData)
+    );
+    // truncated plaintext
+    let (_, msg) = parse_snmp_v3(bytes).expect("parsing failed");
+    assert_eq!(
+        msg.parse_decrypted(&plaintext[..10]),
+        Err(SnmpError::InvalidMessage)
+    );
+}
+
+#[test]
+fn test_snmp_v3_display() {
+    let bytes = include_bytes!("../assets/snmpv3_req_encrypted.bin");
+    let (_, msg) = parse_snmp_v3(bytes).expect("parsing failed");
+    assert_eq!(
+        msg.header_data.to_string(),
+        "msg_id=821490645 max_size=65507 flags=auth|priv|reportable model=USM"
+    );
+    match msg.security_params {
+        SecurityParameters::USM(ref usm) => assert_eq!(
+            usm.to_string(),
+            "engine_id=80001f888059dc486145a26322 boots=8 time=2745 user=\"pippo\""
+        ),
+        ref p => panic!("unexpected security parameters {:?}", p),
+    }
+    let hdr = HeaderData {
+        msg_id: 1,
+        msg_max_size: 484,
+        msg_flags: 0,
+        msg_security_model: SecurityModel(5),
+    };
+    assert_eq!(
+        hdr.to_string(),
+        "msg_id=1 max_size=484 flags=none model=SecurityModel(5)"
+    );
+}
+
+#[test]
+fn test_security_model() {
+    assert_eq!(SecurityModel::TSM.to_string(), "TSM");
+    assert_eq!(format!("{:?}", SecurityModel(4)), "TSM");
+    assert!(SecurityModel::USM.is_standard());
+    assert!(!SecurityModel::Any.is_standard());
+    assert_eq!(SecurityModel(42).to_string(), "reserved(42)");
+    assert_eq!(SecurityModel::USM.enterprise(), None);
+    // enterprise 9, model 1
+    let model = SecurityModel(9 * 256 + 1);
+    assert!(!model.is_standard());
+    assert_eq!(model.enterprise(), Some((9, 1)));
+    assert_eq!(model.to_string(), "enterprise(9:1)");
+}
+
+#[test]
+fn test_snmp_v3_security_params_raw() {
+    use snmp_parser::encode::SnmpEncode;
+    let bytes = include_bytes!("../assets/snmpv3_req_encrypted.bin");
+    let (_, msg) = parse_snmp_v3(bytes).expect("parsing failed");
+    // the raw parameters are the original bytes, and contain the authentication digest
+    let raw = msg.security_params_raw;
+    assert!(bytes.windows(raw.len()).any(|w| w == raw));
+    match msg.security_params {
+        SecurityParameters::USM(ref usm) => {
+            assert_eq!(usm.encode(), raw);
+            assert!(raw
+                .windows(usm.msg_authentication_parameters.len())
+                .any(|w| w == usm.msg_authentication_parameters));
+        }
+        ref p => panic!("unexpected security parameters {:?}", p),
+    }
+    assert_eq!(msg.unknown_security_params(), None);
+    let mut msg = msg;
+    msg.header_data.msg_security_model = SecurityModel::TSM;
+    msg.security_params = SecurityParameters::Raw(raw);
+    assert_eq!(
+        msg.unknown_security_params(),
+        Some((SecurityModel::TSM, raw))
+    );
+}
+
+#[test]
+fn test_snmp_v3_context_engine_id_status() {
+    let (_, msg) = parse_snmp_v3(SNMPV3_REQ).expect("parsing failed");
+    assert_eq!(
+        msg.context_engine_id_status(),
+        ContextEngineIdStatus::Discovery
+    );
+    let bytes = include_bytes!("../assets/snmpv3_req_encrypted.bin");
+    let (_, msg) = parse_snmp_v3(bytes).expect("parsing failed");
+    assert_eq!(
+        msg.context_engine_id_status(),
+        ContextEngineIdStatus::Unknown
+    );
+    let bytes = include_bytes!("../assets/snmpv3-report.bin");
+    let (_, mut msg) = parse_snmp_v3(bytes).expect("parsing failed");
+    assert_eq!(msg.context_engine_id_status(), ContextEngineIdStatus::Match);
+    if let ScopedPduData::Plaintext(ref mut scoped_pdu) = msg.data {
+        scoped_pdu.ctx_engine_id = LOCAL_ENGINE_ID;
+    }
+    assert_eq!(msg.context_engine_id_status(), ContextEngineIdStatus::Local);
+    if let ScopedPduData::Plaintext(ref mut scoped_pdu) = msg.data {
+        scoped_pdu.ctx_engine_id = b"\x80\x00\x1f\x88\x04spoofed";
+    }
+    assert_eq!(
+        msg.context_engine_id_status(),
+        ContextEngineIdStatus::Mismatch
+    );
+}
+
+fn parse_snmp_v3_lenient(bytes: &[u8]) -> nom::IResult<&[u8], SnmpV3Message<'_>, SnmpError> {
+    use snmp_parser::config::*;
+    let mut config = ParserConfig::new();
+    config.lenient = true;
+    let (rem, msg) = parse_snmp_generic_message_with_config(bytes, &config)?;
+    match msg {
+        SnmpGenericMessage::V3(msg) => Ok((rem, msg)),
+        msg => panic!("unexpected message {:?}", msg),
+    }
+}
+
+#[test]
+fn test_snmp_v3_padded_msg_flags() {
+    // msgFlags is 2 bytes: 0x04 0x00
+    let mut bytes = SNMPV3_REQ.to_vec();
+    assert_eq!(&bytes[0x12..0x15], &[0x04, 0x01, 0x04]);
+    bytes[0x13] = 2;
+    bytes.insert(0x15, 0x00);
+    bytes[1] += 1;
+    bytes[6] += 1;
+    assert!(parse_snmp_v3(&bytes).is_err());
+    let (rem, msg) = parse_snmp_v3_lenient(&bytes).expect("parsing failed");
+    assert!(rem.is_empty());
+    assert_eq!(msg.header_data.msg_flags, 0x04);
+    // an empty msgFlags is still invalid
+    let mut bytes = SNMPV3_REQ.to_vec();
+    bytes[0x13] = 0;
+    bytes.remove(0x14);
+    bytes[1] -= 1;
+    bytes[6] -= 1;
+    assert!(parse_snmp_v3_lenient(&bytes).is_err());
+}
+
+#[test]
+fn test_snmp_v3_lenient_integers() {
+    let mut bytes = SNMPV3_REQ.to_vec();
+    // msgMaxSize: non-minimal encoding of 227
+    assert_eq!(&bytes[0x0d..0x12], &[0x02, 0x03, 0x00, 0xff, 0xe3]);
+    bytes[0x10] = 0x00;
+    // msgAuthoritativeEngineTime: -1
+    assert_eq!(&bytes[0x21..0x24], &[0x02, 0x01, 0x00]);
+    bytes[0x23] = 0xff;
+    assert!(parse_snmp_v3(&bytes).is_err());
+    let (rem, msg) = parse_snmp_v3_lenient(&bytes).expect("parsing failed");
+    assert!(rem.is_empty());
+    assert_eq!(msg.header_data.msg_max_size, 227);
+    match msg.security_params {
+        SecurityParameters::USM(ref usm) => {
+            assert_eq!(usm.msg_authoritative_engine_boots, 0);
+            assert_eq!(usm.msg_authoritative_engine_time, u32::MAX);
+        }
+        _ => panic!("unexpected security parameters"),
+    }
+    // values larger than 32 bits are still rejected
+    let mut bytes = SNMPV3_REQ.to_vec();
+    bytes[0x0e] = 0x05;
+    bytes[0x0f..0x12].copy_from_slice(&[0x01, 0x00, 0x00]);
+    bytes.splice(0x12..0x12, [0x00, 0x00]);
+    bytes[1] += 2;
+    bytes[6] += 2;
+    assert!(parse_snmp_v3_lenient(&bytes).is_err());
+}
+
+#[test]
+fn test_snmp_v3_expects_report() {
+    let (_, msg) = parse_snmp_v3(SNMPV3_REQ).expect("parsing failed");
+    assert!(msg.expects_report());
+    let bytes = include_bytes!("../assets/snmpv3-report.bin");
+    let (_, report) = parse_snmp_v3(bytes).expect("parsing failed");
+    assert!(!report.expects_report());
+    assert!(report.report_skeleton(Vec::new()).is_none());
+    // usmStatsUnknownEngineIDs.0
+    let var = SnmpVariable {
+        oid: "1.3.6.1.6.3.15.1.1.4.0".parse().expect("invalid OID"),
+        val: VarBindValue::Value(ObjectSyntax::Counter32(1)),
+    };
+    let skeleton = msg.report_skeleton(vec![var]).expect("no report");
+    assert_eq!(skeleton.header_data.msg_id, msg.header_data.msg_id);
+    assert_eq!(skeleton.header_data.msg_flags, 0);
+    assert!(!skeleton.expects_report());
+    match skeleton.data {
+        ScopedPduData::Plaintext(ref scoped_pdu) => {
+            assert_eq!(scoped_pdu.data.pdu_type(), PduType::Report);
+            assert_eq!(scoped_pdu.data.request_id(), Some(0x7d0e082e));
+            assert_eq!(scoped_pdu.data.vars_iter().count(), 1);
+        }
+        ScopedPduData::Encrypted(_) => panic!("unexpected encrypted data"),
+    }
+}
+
+#[test]
+fn test_snmp_v3_unknown_flag_bits() {
+    let (_, msg) = parse_snmp_v3(SNMPV3_REQ).expect("parsing failed");
+    assert_eq!(msg.header_data.unknown_flag_bits(), 0);
+    let mut bytes = SNMPV3_REQ.to_vec();
+    assert_eq!(bytes[0x14], 0x04);
+    bytes[0x14] = 0x84;
+    let (_, msg) = parse_snmp_v3(&bytes).expect("parsing failed");
+    assert_eq!(msg.header_data.unknown_flag_bits(), 0x80);
+    assert!(msg.header_data.is_reportable());
+    assert!(msg
+        .header_data
+        .to_string()
+        .contains("flags=reportable|0x80"));
+}
+
+#[test]
+fn test_engine_boots_tracker() {
+    use snmp_parser::engine::*;
+
+    let bytes = include_bytes!("../assets/snmpv3-report.bin");
+    let (_, msg) = parse_snmp_v3(bytes).expect("parsing failed");
+    let usm = match msg.security_params {
+        SecurityParameters::USM(ref usm) => usm,
+        _ => panic!("unexpected security parameters"),
+    };
+    let mut tracker = EngineBootsTracker::new(10);
+    assert_eq!(tracker.observe_usm(usm), Some(BootsTimeStatus::New));
+    assert_eq!(tracker.observe_usm(usm), Some(BootsTimeStatus::Forward));
+    let engine_id = usm.msg_authoritative_engine_id;
+    let previous = EngineBootsTime {
+        boots: 3,
+        time: 68125,
+    };
+    assert_eq!(tracker.get(engine_id), Some(previous));
+    // reordered message, within the tolerance
+    let status = tracker.observe(
+        engine_id,
+        EngineBootsTime {
+            boots: 3,
+            time: 68120,
+        },
+    );
+    assert_eq!(status, BootsTimeStatus::Forward);
+    let status = tracker.observe(
+        engine_id,
+        EngineBootsTime {
+            boots: 3,
+            time: 68000,
+        },
+    );
+    assert_eq!(status, BootsTimeStatus::Regression { previous });
+    let status = tracker.observe(
+        engine_id,
+        EngineBootsTime {
+            boots: 2,
+            time: 99999,
+        },
+    );
+    assert_eq!(status, BootsTimeStatus::Regression { previous });
+    assert_eq!(tracker.get(engine_id), Some(previous));
+    let status = tracker.observe(engine_id, EngineBootsTime { boots: 4, time: 1 });
+    assert_eq!(status, BootsTimeStatus::Reboot { previous });
+    assert_eq!(tracker.len(), 1);
+    // discovery messages are ignored
+    let (_, msg) = parse_snmp_v3(SNMPV3_REQ).expect("parsing failed");
+    match msg.security_params {
+        SecurityParameters::USM(ref usm) => assert_eq!(tracker.observe_usm(usm), None),
+        _ => panic!("unexpected security parameters"),
+    }
+}
+
+#[test]
+fn test_engine_boots_tracker_limit() {
+    use snmp_parser::engine::*;
+
+    let value = EngineBootsTime { boots: 1, time: 1 };
+    let mut tracker = EngineBootsTracker::with_max_engines(0, 2);
+    tracker.observe(b"engine1", value);
+    tracker.observe(b"engine2", value);
+    // engine1 is now the most recently seen engine
+    tracker.observe(b"engine1", value);
+    tracker.observe(b"engine3", value);
+    assert_eq!(tracker.len(), 2);
+    assert_eq!(tracker.get(b"engine1"), Some(value));
+    assert_eq!(tracker.get(b"engine2"), None);
+    assert_eq!(tracker.get(b"engine3"), Some(value));
+}
+
+#[test]
+fn test_engine_boots_tracker_jump() {
+    use snmp_parser::engine::*;
+
+    let previous = EngineBootsTime { boots: 3, time: 10 };
+    let mut tracker = EngineBootsTracker::new(0);
+    tracker.observe(b"engine", previous);
+    // a single spoofed message must not make the next messages regressions
+    let spoofed = EngineBootsTime {
+        boots: 3 + DEFAULT_MAX_BOOTS_JUMP + 1,
+        time: 0,
+    };
+    let status = tracker.observe(b"engine", spoofed);
+    assert_eq!(status, BootsTimeStatus::BootsJump { previous });
+    assert!(!status.is_regression());
+    assert_eq!(tracker.get(b"engine"), Some(previous));
+    let status = tracker.observe(b"engine", EngineBootsTime { boots: 3, time: 20 });
+    assert_eq!(status, BootsTimeStatus::Forward);
+    // without limit, any increase is a reboot
+    tracker.set_max_boots_jump(None);
+    let status = tracker.observe(b"engine", spoofed);
+    assert!(matches!(status, BootsTimeStatus::Reboot { .. }));
+    assert_eq!(tracker.get(b"engine"), Some(spoofed));
+}
+
+#[test]
+fn test_snmp_v3_lengths() {
+    assert_eq!(check_snmp_v3_lengths(SNMPV3_REQ), Ok(vec![]));
+    // trailing data in the message
+    let mut bytes = SNMPV3_REQ.to_vec();
+    bytes[1] += 2;
+    bytes.extend_from_slice(&[0, 0]);
+    assert_eq!(
+        check_snmp_v3_lengths(&bytes),
+        Ok(vec![LengthAnomaly::TrailingData {
+            field: "message",
+            offset: SNMPV3_REQ.len(),
+            len: 2
+        }])
+    );
+    parse_snmp_v3(&bytes).expect("parsing failed");
+    // trailing data in the header data
+    let mut bytes = SNMPV3_REQ.to_vec();
+    bytes[1] += 1;
+    bytes[6] += 1;
+    bytes.insert(0x18, 0);
+    assert_eq!(
+        check_snmp_v3_lengths(&bytes),
+        Ok(vec![LengthAnomaly::TrailingData {
+            field: "header_data",
+            offset: 0x18,
+            len: 1
+        }])
+    );
+    // trailing data in the security parameters
+    let mut bytes = SNMPV3_REQ.to_vec();
+    bytes[1] += 1;
+    bytes[0x19] += 1;
+    bytes.insert(0x2a, 0);
+    assert_eq!(
+        check_snmp_v3_lengths(&bytes),
+        Ok(vec![LengthAnomaly::TrailingData {
+            field: "security_parameters",
+            offset: 0x2a,
+            len: 1
+        }])
+    );
+    // scoped PDU overshooting the message
+    let mut bytes = SNMPV3_REQ.to_vec();
+    bytes[0x2b] += 1;
+    assert_eq!(
+        check_snmp_v3_lengths(&bytes),
+        Ok(vec![LengthAnomaly::Overshoot {
+            field: "message",
+            offset: 0x2a,
+            declared: 0x24,
+            available: 0x23
+        }])
+    );
+    assert!(parse_snmp_v3(&bytes).is_err());
+    assert_eq!(
+        check_snmp_v3_lengths(&[0x04, 0x00]),
+        Err(SnmpError::InvalidMessage)
+    );
+}
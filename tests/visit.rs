@@ -0,0 +1,88 @@
+#![cfg(all(feature = "v2c", feature = "v3"))]
+
+extern crate snmp_parser;
+
+use core::ops::ControlFlow;
+use snmp_parser::inspect::RawVarBind;
+use snmp_parser::visit::*;
+use snmp_parser::*;
+
+static SNMPV2_GET_RESPONSE: &[u8] = include_bytes!("../assets/snmpv2c-get-response.bin");
+static SNMPV3_REQ_ENCRYPTED: &[u8] = include_bytes!("../assets/snmpv3_req_encrypted.bin");
+
+#[derive(Default)]
+struct Recorder {
+    calls: Vec<String>,
+    stop_after_pdu: bool,
+}
+
+impl<'a> SnmpVisitor<'a> for Recorder {
+    fn visit_version(&mut self, version: u32) -> ControlFlow<()> {
+        self.calls.push(format!("version {}", version));
+        ControlFlow::Continue(())
+    }
+
+    fn visit_community(&mut self, community: &'a [u8]) -> ControlFlow<()> {
+        self.calls
+            .push(format!("community {}", String::from_utf8_lossy(community)));
+        ControlFlow::Continue(())
+    }
+
+    fn visit_header_data(&mut self, header_data: &HeaderData) -> ControlFlow<()> {
+        self.calls.push(format!("msg_id {}", header_data.msg_id));
+        ControlFlow::Continue(())
+    }
+
+    fn visit_pdu(&mut self, pdu_type: PduType, request_id: Option<u32>) -> ControlFlow<()> {
+        self.calls
+            .push(format!("pdu {:?} {:?}", pdu_type, request_id));
+        if self.stop_after_pdu {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+
+    fn visit_varbind(&mut self, var: &RawVarBind<'a>) -> ControlFlow<()> {
+        self.calls.push(format!("var {}", var.oid.to_id_string()));
+        ControlFlow::Continue(())
+    }
+}
+
+#[test]
+fn test_visit_matches_parser() {
+    let mut recorder = Recorder::default();
+    let (rem, ()) = parse_with_visitor(SNMPV2_GET_RESPONSE, &mut recorder).expect("parsing failed");
+    assert!(rem.is_empty());
+    let (_, msg) = parse_snmp_v2c(SNMPV2_GET_RESPONSE).expect("parsing failed");
+    let mut expected = vec![
+        "version 1".to_string(),
+        "community public".to_string(),
+        format!("pdu {:?} {:?}", PduType::Response, msg.pdu.request_id()),
+    ];
+    expected.extend(
+        msg.vars_iter()
+            .map(|v| format!("var {}", v.oid.to_id_string())),
+    );
+    assert_eq!(recorder.calls, expected);
+}
+
+#[test]
+fn test_visit_stop() {
+    let mut recorder = Recorder {
+        stop_after_pdu: true,
+        ..Default::default()
+    };
+    parse_with_visitor(SNMPV2_GET_RESPONSE, &mut recorder).expect("parsing failed");
+    assert_eq!(recorder.calls.len(), 3);
+    // a truncated message is still rejected
+    assert!(parse_with_visitor(&SNMPV2_GET_RESPONSE[..20], &mut Recorder::default()).is_err());
+}
+
+#[test]
+fn test_visit_encrypted() {
+    let mut recorder = Recorder::default();
+    parse_with_visitor(SNMPV3_REQ_ENCRYPTED, &mut recorder).expect("parsing failed");
+    assert_eq!(recorder.calls.len(), 2);
+    assert_eq!(recorder.calls[0], "version 3");
+}
@@ -0,0 +1,191 @@
+extern crate snmp_parser;
+
+mod common;
+
+use common::*;
+use snmp_parser::walk::*;
+use snmp_parser::*;
+use std::cmp::Ordering;
+
+fn counter(s: &str) -> SnmpVariable<'static> {
+    varbind(s, VarBindValue::Value(ObjectSyntax::Counter32(1)))
+}
+
+fn generic(pdu_type: PduType, var: Vec<SnmpVariable<'static>>) -> SnmpPdu<'static> {
+    SnmpPdu::Generic(SnmpGenericPdu {
+        pdu_type,
+        req_id: 1,
+        err: ErrorStatus::NoError,
+        err_index: 0,
+        var,
+    })
+}
+
+#[test]
+fn test_cmp_oid() {
+    assert_eq!(cmp_oid(&oid("1.3.6.1"), &oid("1.3.6.1")), Ordering::Equal);
+    assert_eq!(cmp_oid(&oid("1.3.6.1"), &oid("1.3.6.1.2")), Ordering::Less);
+    assert_eq!(
+        cmp_oid(&oid("1.3.6.2"), &oid("1.3.6.1.2")),
+        Ordering::Greater
+    );
+    assert_eq!(
+        cmp_oid(&oid("1.3.6.127"), &oid("1.3.6.128")),
+        Ordering::Less
+    );
+    // 16383 is encoded as ff 7f, 16384 as 81 80 00
+    assert_eq!(
+        cmp_oid(&oid("1.3.16383"), &oid("1.3.16384")),
+        Ordering::Less
+    );
+    assert_eq!(cmp_oid(&oid("2.39.3"), &oid("1.3.6")), Ordering::Greater);
+}
+
+#[test]
+fn test_check_response_order_getnext() {
+    let request = generic(
+        PduType::GetNextRequest,
+        vec![null("1.3.6.1.2.1.2.2.1.2"), null("1.3.6.1.2.1.2.2.1.10")],
+    );
+    let response = generic(
+        PduType::Response,
+        vec![
+            counter("1.3.6.1.2.1.2.2.1.2.1"),
+            counter("1.3.6.1.2.1.2.2.1.9.1"),
+        ],
+    );
+    let violations = check_response_order(&request, &response);
+    assert_eq!(
+        violations,
+        vec![OrderViolation {
+            index: 1,
+            oid: oid("1.3.6.1.2.1.2.2.1.9.1"),
+            previous: oid("1.3.6.1.2.1.2.2.1.10"),
+        }]
+    );
+    // other requests are not checked
+    let request = generic(PduType::GetRequest, vec![null("1.3.6.1.2.1.1.1.0")]);
+    let response = generic(PduType::Response, vec![counter("1.3.6.1.2.1.1.1.0")]);
+    assert!(check_response_order(&request, &response).is_empty());
+}
+
+#[test]
+fn test_check_response_order_bulk() {
+    let request = SnmpPdu::Bulk(SnmpBulkPdu {
+        req_id: 1,
+        non_repeaters: 1,
+        max_repetitions: 3,
+        var: vec![
+            null("1.3.6.1.2.1.1.3"),
+            null("1.3.6.1.2.1.2.2.1.2"),
+            null("1.3.6.1.2.1.2.2.1.10"),
+        ],
+    });
+    let response = generic(
+        PduType::Response,
+        vec![
+            counter("1.3.6.1.2.1.1.3.0"),
+            counter("1.3.6.1.2.1.2.2.1.2.1"),
+            counter("1.3.6.1.2.1.2.2.1.10.1"),
+            counter("1.3.6.1.2.1.2.2.1.2.2"),
+            // same as previous repetition
+            counter("1.3.6.1.2.1.2.2.1.10.1"),
+            varbind("1.3.6.1.2.1.2.2.1.2.2", VarBindValue::EndOfMibView),
+            counter("1.3.6.1.2.1.2.2.1.10.3"),
+        ],
+    );
+    let violations = check_response_order(&request, &response);
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].index, 4);
+    assert_eq!(violations[0].previous, oid("1.3.6.1.2.1.2.2.1.10.1"));
+}
+
+#[test]
+fn test_partition_bulk_response() {
+    let request = SnmpBulkPdu {
+        req_id: 1,
+        non_repeaters: 1,
+        max_repetitions: 3,
+        var: vec![
+            null("1.3.6.1.2.1.1.3"),
+            null("1.3.6.1.2.1.2.2.1.2"),
+            null("1.3.6.1.2.1.2.2.1.10"),
+        ],
+    };
+    // truncated response: 2 rows and a half
+    let response = SnmpGenericPdu {
+        pdu_type: PduType::Response,
+        req_id: 1,
+        err: ErrorStatus::NoError,
+        err_index: 0,
+        var: vec![
+            counter("1.3.6.1.2.1.1.3.0"),
+            counter("1.3.6.1.2.1.2.2.1.2.1"),
+            counter("1.3.6.1.2.1.2.2.1.10.1"),
+            counter("1.3.6.1.2.1.2.2.1.2.2"),
+            counter("1.3.6.1.2.1.2.2.1.10.2"),
+            counter("1.3.6.1.2.1.2.2.1.2.3"),
+        ],
+    };
+    let parts = partition_bulk_response(&request, &response);
+    assert_eq!(parts.non_repeaters, &response.var[..1]);
+    assert_eq!(parts.rows.len(), 3);
+    assert_eq!(parts.rows[1], &response.var[3..5]);
+    assert_eq!(parts.rows[2].len(), 1);
+    // non-repeaters greater than the number of variables
+    let request = SnmpBulkPdu {
+        non_repeaters: 10,
+        ..request
+    };
+    let parts = partition_bulk_response(&request, &response);
+    assert_eq!(parts.non_repeaters.len(), 3);
+    assert!(parts.rows.is_empty());
+}
+
+#[test]
+fn test_table_walk() {
+    // ifEntry, walked with GetBulk on ifDescr and ifType
+    let responses = [
+        SnmpGenericPdu {
+            pdu_type: PduType::Response,
+            req_id: 1,
+            err: ErrorStatus::NoError,
+            err_index: 0,
+            var: vec![
+                counter("1.3.6.1.2.1.2.2.1.2.1"),
+                counter("1.3.6.1.2.1.2.2.1.3.1"),
+                counter("1.3.6.1.2.1.2.2.1.2.2"),
+            ],
+        },
+        SnmpGenericPdu {
+            pdu_type: PduType::Response,
+            req_id: 2,
+            err: ErrorStatus::NoError,
+            err_index: 0,
+            var: vec![
+                counter("1.3.6.1.2.1.2.2.1.3.2"),
+                counter("1.3.6.1.2.1.2.2.1.2.10"),
+                varbind("1.3.6.1.2.1.2.2.1.3.10", VarBindValue::NoSuchInstance),
+                counter("1.3.6.1.2.1.2.2.1.2.10"),
+                counter("1.3.6.1.2.1.3.1.1.1.1"),
+            ],
+        },
+    ];
+    let entry = oid("1.3.6.1.2.1.2.2.1");
+    let mut table = TableWalk::new(&entry, 2);
+    let rows = table.push_response(&responses[0]);
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].index, vec![1]);
+    assert_eq!(
+        rows[0].columns,
+        vec![(2, &responses[0].var[0]), (3, &responses[0].var[1])]
+    );
+    let rows = table.push_response(&responses[1]);
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].index, vec![2]);
+    assert_eq!(rows[0].columns[0], (2, &responses[0].var[2]));
+    let rows = table.finish();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].index, vec![10]);
+    assert_eq!(rows[0].columns, vec![(2, &responses[1].var[1])]);
+}